@@ -0,0 +1,154 @@
+use serde_json::Value;
+
+use crate::{BuildType, Project, Subcommand};
+
+// how many of the largest functions or crates cargo-bloat lists
+const BLOAT_ENTRIES: &str = "100";
+
+impl<'a> Project<'a> {
+    /// Measure what takes up the space in the release binary with cargo-bloat, which must be installed.
+    /// It prints a json report on stdout, see [`bloat_report`]. Crates are listed instead of functions if `crates` is set
+    pub fn bloat(&mut self, crates: bool) -> &mut Self {
+        self.build_type(BuildType::Release)
+            .subcommand(Subcommand::Bloat)
+            .subcommand_flags(&["--message-format", "json", "-n", BLOAT_ENTRIES]);
+
+        if crates {
+            self.subcommand_flag("--crates");
+        }
+
+        self
+    }
+}
+
+/// A function or crate, and how many bytes of the text section it takes up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloatEntry {
+    pub name: String,
+    /// The crate a function belongs to, None for crates
+    pub krate: Option<String>,
+    pub size: u64,
+}
+
+/// What cargo-bloat reported. Functions and crates are filled in by separate runs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BloatReport {
+    pub file_size: u64,
+    pub text_size: u64,
+    pub functions: Vec<BloatEntry>,
+    pub crates: Vec<BloatEntry>,
+}
+
+/// The json reports cargo-bloat printed to stdout, merged. None if there weren't any
+pub fn bloat_report(stdout: &str) -> Option<BloatReport> {
+    let mut report = None::<BloatReport>;
+
+    for line in stdout.lines() {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            continue;
+        }
+
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+
+        let Some(file_size) = value.get("file-size").and_then(Value::as_u64) else {
+            continue;
+        };
+
+        let report = report.get_or_insert_with(Default::default);
+        report.file_size = file_size;
+        report.text_size = value
+            .get("text-section-size")
+            .and_then(Value::as_u64)
+            .unwrap_or_default();
+
+        if let Some(functions) = value.get("functions") {
+            report.functions = entries(functions);
+        }
+
+        if let Some(crates) = value.get("crates") {
+            report.crates = entries(crates);
+        }
+    }
+
+    report
+}
+
+fn entries(value: &Value) -> Vec<BloatEntry> {
+    let Some(entries) = value.as_array() else {
+        return vec![];
+    };
+
+    entries
+        .iter()
+        .map(|entry| {
+            let string = |key| {
+                entry
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .map(ToString::to_string)
+            };
+
+            BloatEntry {
+                name: string("name").unwrap_or_default(),
+                krate: string("crate"),
+                size: entry
+                    .get("size")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bloat_flags() {
+        let mut project = Project::new("bloat");
+        project.bloat(true);
+
+        let builder = &project.cargo_command_builder;
+        assert_eq!(builder.subcommand, Some(Subcommand::Bloat));
+        assert_eq!(builder.build_type, Some(BuildType::Release));
+        assert_eq!(
+            builder.subcommand_flags.as_deref(),
+            Some(&["--message-format", "json", "-n", "100", "--crates"][..])
+        );
+    }
+
+    #[test]
+    fn parse_reports() {
+        let stdout = r#"    Finished release [optimized] target(s) in 0.5s
+{"file-size":300000,"text-section-size":200000,"functions":[{"crate":"std","name":"std::rt::lang_start","size":1200},{"crate":"p","name":"p::main","size":80}]}
+{"file-size":300000,"text-section-size":200000,"crates":[{"name":"std","size":150000},{"name":"p","size":80}]}
+"#;
+
+        let report = bloat_report(stdout).unwrap();
+
+        assert_eq!(report.file_size, 300000);
+        assert_eq!(report.text_size, 200000);
+        assert_eq!(
+            report.functions[1],
+            BloatEntry {
+                name: "p::main".to_string(),
+                krate: Some("p".to_string()),
+                size: 80,
+            }
+        );
+        assert_eq!(
+            report.crates[0],
+            BloatEntry {
+                name: "std".to_string(),
+                krate: None,
+                size: 150000,
+            }
+        );
+
+        assert_eq!(bloat_report("Hello\n{\"reason\":\"build-finished\"}"), None);
+    }
+}
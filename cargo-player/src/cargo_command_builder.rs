@@ -8,6 +8,8 @@ pub struct CargoCommandBuilder<'a> {
     pub subcommand: Option<Subcommand>,
     // debug or release
     pub build_type: Option<BuildType>,
+    // target triple, e.g. wasm32-unknown-unknown
+    pub target: Option<&'a str>,
     pub cargo_flags: Option<Vec<&'a str>>,
     pub subcommand_flags: Option<Vec<&'a str>>,
     pub dash_args: Option<Vec<&'a str>>,
@@ -34,6 +36,11 @@ impl<'a> CargoCommandBuilder<'a> {
         self
     }
 
+    pub fn target(&mut self, target: &'a str) -> &mut Self {
+        self.target = Some(target);
+        self
+    }
+
     pub fn subcommand_flag(&mut self, flag: &'a str) -> &mut Self {
         if self.subcommand_flags.is_none() {
             self.subcommand_flags = Some(vec![]);
@@ -96,7 +103,7 @@ impl<'a> CargoCommandBuilder<'a> {
 
         if let Some(channel) = self.channel {
             let channel: &str = channel.into();
-            command.arg(&format!("+{channel}"));
+            command.arg(format!("+{channel}"));
         }
 
         if let Some(flags) = &self.cargo_flags {
@@ -117,6 +124,10 @@ impl<'a> CargoCommandBuilder<'a> {
             }
         }
 
+        if let Some(target) = self.target {
+            command.args(["--target", target]);
+        }
+
         if let Some(flags) = &self.dash_args {
             command.arg("--");
             command.args(flags);
@@ -176,4 +187,31 @@ mod tests {
 
         assert_eq!("cargo +stable run", commandline);
     }
+
+    #[test]
+    fn cargo_command_target() {
+        let mut builder = CargoCommandBuilder::new();
+        builder
+            .channel(Channel::Stable)
+            .subcommand(Subcommand::Build)
+            .subcommand_flag("--subflag")
+            .build_type(BuildType::Release)
+            .target("wasm32-unknown-unknown")
+            .dash_arg("--dash");
+
+        let command = builder.build();
+
+        let mut commandline = command.get_program().to_str().unwrap().to_string();
+        commandline.push_str(
+            &command
+                .get_args()
+                .map(|i| format!(" {}", i.to_str().unwrap()))
+                .collect::<String>(),
+        );
+
+        assert_eq!(
+            "cargo +stable build --subflag --release --target wasm32-unknown-unknown -- --dash",
+            commandline
+        );
+    }
 }
@@ -0,0 +1,154 @@
+use crate::{Diagnostic, Level, Message, Project, Span, Subcommand};
+
+// clippy's help ends with a link to the lint's documentation
+const FURTHER_INFORMATION: &str = "for further information visit ";
+const CLIPPY_LINTS: &str = "https://rust-lang.github.io/rust-clippy/master/index.html";
+const ERROR_CODES: &str = "https://doc.rust-lang.org/error_codes";
+
+impl<'a> Project<'a> {
+    /// Lint the scratch with clippy. It reports as json messages on stdout, see [`lints`].
+    /// `lint_levels` are handed to clippy, e.g. `["-W", "clippy::pedantic", "-A", "clippy::needless_return"]`
+    pub fn clippy(&mut self, lint_levels: &[&'a str]) -> &mut Self {
+        self.subcommand(Subcommand::Clippy)
+            .message_format_json()
+            .dash_args(lint_levels)
+    }
+}
+
+/// A lint, or error, clippy reported at a place in the code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub level: Level,
+    // e.g. clippy::needless_return, unused_variables or E0308. Empty for errors without a code
+    pub name: String,
+    pub message: String,
+    // where the lint was reported
+    pub span: Span,
+    // the help and notes attached to the lint, e.g. what to write instead
+    pub help: Vec<String>,
+    // the documentation of the lint, if it has any
+    pub url: Option<String>,
+}
+
+impl Lint {
+    fn from_diagnostic(diagnostic: Diagnostic) -> Option<Self> {
+        let span = diagnostic
+            .spans
+            .iter()
+            .find(|span| span.is_primary)
+            .or_else(|| diagnostic.spans.first())?
+            .clone();
+
+        let name = diagnostic.code.unwrap_or_default();
+
+        let mut url = None;
+        let mut help = vec![];
+
+        for child in diagnostic.children {
+            match child.message.strip_prefix(FURTHER_INFORMATION) {
+                Some(link) => url = Some(link.trim().to_string()),
+                None => help.push(child.message),
+            }
+        }
+
+        let url = url.or_else(|| documentation(&name));
+
+        Some(Self {
+            level: diagnostic.level,
+            name,
+            message: diagnostic.message,
+            span,
+            help,
+            url,
+        })
+    }
+}
+
+// rustc's own lints are documented on pages per default level, which the message doesn't tell
+fn documentation(name: &str) -> Option<String> {
+    if let Some(lint) = name.strip_prefix("clippy::") {
+        return Some(format!("{CLIPPY_LINTS}#{lint}"));
+    }
+
+    let is_error_code =
+        name.len() == 5 && name.starts_with('E') && name[1..].chars().all(|c| c.is_ascii_digit());
+
+    is_error_code.then(|| format!("{ERROR_CODES}/{name}.html"))
+}
+
+/// The lints in the json messages clippy printed to stdout, in the order they were reported.
+/// Summaries without a place in the code, e.g. the count of warnings, are left out
+pub fn lints(stdout: &str) -> Vec<Lint> {
+    stdout
+        .lines()
+        .filter_map(|line| match Message::parse(line) {
+            Message::CompilerMessage(diagnostic) => Lint::from_diagnostic(diagnostic),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STDOUT: &str = r#"{"reason":"compiler-message","package_id":"p 0.1.0","target":{},"message":{"message":"unneeded `return` statement","code":{"code":"clippy::needless_return","explanation":null},"level":"warning","spans":[{"file_name":"src/main.rs","byte_start":20,"byte_end":29,"line_start":2,"line_end":2,"column_start":5,"column_end":14,"is_primary":true,"text":[],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[{"message":"`#[warn(clippy::needless_return)]` on by default","code":null,"level":"note","spans":[],"children":[],"rendered":null},{"message":"for further information visit https://rust-lang.github.io/rust-clippy/master/index.html#needless_return","code":null,"level":"help","spans":[],"children":[],"rendered":null},{"message":"remove `return`","code":null,"level":"help","spans":[],"children":[],"rendered":null}],"rendered":"warning: unneeded `return` statement\n"}}
+{"reason":"compiler-message","package_id":"p 0.1.0","target":{},"message":{"message":"mismatched types","code":{"code":"E0308","explanation":"..."},"level":"error","spans":[{"file_name":"src/main.rs","byte_start":40,"byte_end":42,"line_start":5,"line_end":5,"column_start":18,"column_end":20,"is_primary":true,"text":[],"label":"expected `u8`, found `&str`","suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[],"rendered":"error[E0308]: mismatched types\n"}}
+{"reason":"compiler-message","package_id":"p 0.1.0","target":{},"message":{"message":"1 warning emitted","code":null,"level":"warning","spans":[],"children":[],"rendered":"warning: 1 warning emitted\n"}}
+{"reason":"build-finished","success":false}
+"#;
+
+    #[test]
+    fn clippy_lint_levels() {
+        let mut project = Project::new("clippy");
+        project.clippy(&["-W", "clippy::pedantic"]);
+
+        let command = project.cargo_command_builder.build();
+        let args = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+
+        assert!(args.contains(&"clippy".to_string()));
+        assert!(args.ends_with(&["-W".to_string(), "clippy::pedantic".to_string()]));
+    }
+
+    #[test]
+    fn clippy_lints() {
+        let lints = lints(STDOUT);
+        assert_eq!(lints.len(), 2);
+
+        let lint = &lints[0];
+        assert_eq!(lint.level, Level::Warning);
+        assert_eq!(lint.name, "clippy::needless_return");
+        assert_eq!(lint.span.line_start, 2);
+        assert_eq!(
+            lint.help,
+            vec![
+                "`#[warn(clippy::needless_return)]` on by default".to_string(),
+                "remove `return`".to_string()
+            ]
+        );
+        assert_eq!(
+            lint.url.as_deref(),
+            Some("https://rust-lang.github.io/rust-clippy/master/index.html#needless_return")
+        );
+
+        let error = &lints[1];
+        assert_eq!(error.level, Level::Error);
+        assert_eq!(
+            error.span.label.as_deref(),
+            Some("expected `u8`, found `&str`")
+        );
+        assert_eq!(
+            error.url.as_deref(),
+            Some("https://doc.rust-lang.org/error_codes/E0308.html")
+        );
+    }
+
+    #[test]
+    fn rustc_lints_without_docs() {
+        assert_eq!(documentation("unused_variables"), None);
+        assert_eq!(documentation(""), None);
+    }
+}
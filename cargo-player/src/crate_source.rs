@@ -0,0 +1,313 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::{dependency_toml, Project, Subcommand};
+
+// files listed for a crate, its rust source besides the manifest and readme
+const SOURCE_EXTENSIONS: &[&str] = &["rs", "toml", "md"];
+
+impl<'a> Project<'a> {
+    /// List the packages of the project with cargo metadata, downloading any dependency cargo doesn't have yet.
+    /// It prints json on stdout, see [`crate_sources`]
+    pub fn metadata(&mut self) -> &mut Self {
+        self.subcommand(Subcommand::Metadata)
+            .subcommand_flags(&["--format-version", "1"])
+    }
+
+    /// Print the dependency graph of the project with cargo tree, see [`tree_sections`]
+    pub fn tree(&mut self) -> &mut Self {
+        self.subcommand(Subcommand::Tree).subcommand_flags(&[
+            "--charset",
+            "utf8",
+            "--color",
+            "never",
+        ])
+    }
+}
+
+/// A dependency of the project, and the directory cargo extracted it to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateSource {
+    pub name: String,
+    // the version cargo resolved
+    pub version: String,
+    pub dir: PathBuf,
+    // whether the default features are enabled
+    pub default_features: bool,
+    // the features enabled besides the default ones, sorted
+    pub features: Vec<String>,
+}
+
+impl CrateSource {
+    /// The line of a Cargo.toml `[dependencies]` table which resolves to the same version and features
+    pub fn cargo_toml(&self) -> String {
+        dependency_toml(
+            &self.name,
+            &self.version,
+            self.default_features,
+            &self.features,
+        )
+    }
+
+    /// The source files of the crate, relative to its directory and sorted
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = vec![];
+        collect_files(&self.dir, &self.dir, &mut files);
+
+        files.sort();
+        files
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            // build output, in case the crate was ever built in place
+            if !path.ends_with("target") {
+                collect_files(root, &path, files);
+            }
+
+            continue;
+        }
+
+        let is_source = matches!(
+            path.extension().and_then(|extension| extension.to_str()),
+            Some(extension) if SOURCE_EXTENSIONS.contains(&extension)
+        );
+
+        if let (true, Ok(relative)) = (is_source, path.strip_prefix(root)) {
+            files.push(relative.to_path_buf());
+        }
+    }
+}
+
+/// The direct dependencies listed in the json cargo metadata printed to stdout, sorted by name
+pub fn crate_sources(stdout: &str) -> Vec<CrateSource> {
+    let Ok(metadata) = serde_json::from_str::<Value>(stdout.trim()) else {
+        return vec![];
+    };
+
+    let Some(packages) = metadata["packages"].as_array() else {
+        return vec![];
+    };
+
+    let resolve = &metadata["resolve"];
+    let nodes = resolve["nodes"].as_array();
+    let node = |id: &Value| nodes.and_then(|nodes| nodes.iter().find(|node| node["id"] == *id));
+
+    let direct = node(&resolve["root"])
+        .and_then(|root| root["dependencies"].as_array())
+        .map(|dependencies| {
+            dependencies
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+        });
+
+    let mut sources = packages
+        .iter()
+        .filter(|package| match &direct {
+            Some(direct) => direct.contains(&package["id"].as_str().unwrap_or_default()),
+            // without the resolved graph, everything which came from a registry
+            None => !package["source"].is_null(),
+        })
+        .filter_map(|package| {
+            let enabled = node(&package["id"])
+                .and_then(|node| node["features"].as_array())
+                .map(|features| features.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+
+            let (default_features, features) = beyond_defaults(package, &enabled);
+
+            Some(CrateSource {
+                name: package["name"].as_str()?.to_string(),
+                version: package["version"].as_str()?.to_string(),
+                dir: Path::new(package["manifest_path"].as_str()?)
+                    .parent()?
+                    .to_path_buf(),
+                default_features,
+                features,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    sources.sort_by(|a, b| a.name.cmp(&b.name));
+    sources
+}
+
+// Whether the default features are among the enabled ones, and the enabled features the default
+// ones don't already turn on
+fn beyond_defaults(package: &Value, enabled: &BTreeSet<&str>) -> (bool, Vec<String>) {
+    let default_features = enabled.contains("default");
+
+    // the features default turns on, and the ones those turn on in turn
+    let mut implied = BTreeSet::new();
+    let mut pending = default_features
+        .then_some("default")
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    while let Some(feature) = pending.pop() {
+        if !implied.insert(feature) {
+            continue;
+        }
+
+        let Some(turns_on) = package["features"][feature].as_array() else {
+            continue;
+        };
+
+        // "dep:name" and "dep/feature" are about other crates
+        pending.extend(
+            turns_on
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|feature| !feature.contains([':', '/'])),
+        );
+    }
+
+    let features = enabled
+        .difference(&implied)
+        .map(ToString::to_string)
+        .collect();
+
+    (default_features, features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_flags() {
+        let mut project = Project::new("metadata");
+        project.metadata();
+
+        let builder = &project.cargo_command_builder;
+        assert_eq!(builder.subcommand, Some(Subcommand::Metadata));
+        assert_eq!(
+            builder.subcommand_flags.as_deref(),
+            Some(&["--format-version", "1"][..])
+        );
+    }
+
+    #[test]
+    fn tree_flags() {
+        let mut project = Project::new("tree");
+        project.tree();
+
+        let builder = &project.cargo_command_builder;
+        assert_eq!(builder.subcommand, Some(Subcommand::Tree));
+        assert_eq!(
+            builder.subcommand_flags.as_deref(),
+            Some(&["--charset", "utf8", "--color", "never"][..])
+        );
+    }
+
+    #[test]
+    fn parse_direct_dependencies() {
+        let stdout = r#"{
+            "packages": [
+                {"id": "p 0.1.0 (path+file:///tmp/p)", "name": "p", "version": "0.1.0", "source": null, "manifest_path": "/tmp/p/Cargo.toml"},
+                {"id": "rand 0.8.5 (registry+https://github.com/rust-lang/crates.io-index)", "name": "rand", "version": "0.8.5", "source": "registry+https://github.com/rust-lang/crates.io-index", "manifest_path": "/cargo/registry/src/rand-0.8.5/Cargo.toml"},
+                {"id": "libc 0.2.139 (registry+https://github.com/rust-lang/crates.io-index)", "name": "libc", "version": "0.2.139", "source": "registry+https://github.com/rust-lang/crates.io-index", "manifest_path": "/cargo/registry/src/libc-0.2.139/Cargo.toml"}
+            ],
+            "resolve": {
+                "root": "p 0.1.0 (path+file:///tmp/p)",
+                "nodes": [
+                    {"id": "p 0.1.0 (path+file:///tmp/p)", "dependencies": ["rand 0.8.5 (registry+https://github.com/rust-lang/crates.io-index)"]},
+                    {"id": "rand 0.8.5 (registry+https://github.com/rust-lang/crates.io-index)", "dependencies": ["libc 0.2.139 (registry+https://github.com/rust-lang/crates.io-index)"]}
+                ]
+            }
+        }"#;
+
+        assert_eq!(
+            crate_sources(stdout),
+            vec![CrateSource {
+                name: "rand".to_string(),
+                version: "0.8.5".to_string(),
+                dir: PathBuf::from("/cargo/registry/src/rand-0.8.5"),
+                default_features: false,
+                features: vec![],
+            }]
+        );
+
+        assert!(crate_sources("error: failed to download").is_empty());
+    }
+
+    #[test]
+    fn list_source_files() {
+        let dir = std::env::temp_dir().join("cargo-player-crate-source");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+
+        for file in [
+            "Cargo.toml",
+            "README.md",
+            "LICENSE",
+            "src/lib.rs",
+            "src/nested/mod.rs",
+            "target/out.rs",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        let source = CrateSource {
+            name: "nested".to_string(),
+            version: "1.0.0".to_string(),
+            dir: dir.clone(),
+            default_features: true,
+            features: vec![],
+        };
+
+        assert_eq!(
+            source.files(),
+            ["Cargo.toml", "README.md", "src/lib.rs", "src/nested/mod.rs"].map(PathBuf::from)
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn features_beyond_defaults() {
+        let stdout = r#"{
+            "packages": [
+                {"id": "p 0.1.0", "name": "p", "version": "0.1.0", "source": null, "manifest_path": "/tmp/p/Cargo.toml", "features": {}},
+                {"id": "rand 0.8.5", "name": "rand", "version": "0.8.5", "source": "registry", "manifest_path": "/r/rand/Cargo.toml",
+                 "features": {"default": ["std", "std_rng"], "std": ["alloc", "rand_core/std"], "std_rng": ["rand_chacha"], "alloc": [], "small_rng": [], "serde1": ["serde"]}},
+                {"id": "serde 1.0.152", "name": "serde", "version": "1.0.152", "source": "registry", "manifest_path": "/r/serde/Cargo.toml",
+                 "features": {"default": ["std"], "std": [], "derive": ["serde_derive"]}}
+            ],
+            "resolve": {
+                "root": "p 0.1.0",
+                "nodes": [
+                    {"id": "p 0.1.0", "dependencies": ["rand 0.8.5", "serde 1.0.152"], "features": []},
+                    {"id": "rand 0.8.5", "dependencies": [], "features": ["alloc", "default", "small_rng", "std", "std_rng"]},
+                    {"id": "serde 1.0.152", "dependencies": [], "features": ["derive"]}
+                ]
+            }
+        }"#;
+
+        let sources = crate_sources(stdout);
+
+        assert!(sources[0].default_features);
+        assert_eq!(sources[0].features, ["small_rng"]);
+        assert_eq!(
+            sources[0].cargo_toml(),
+            r#"rand = { version = "0.8.5", features = ["small_rng"] }"#
+        );
+
+        assert!(!sources[1].default_features);
+        assert_eq!(
+            sources[1].cargo_toml(),
+            r#"serde = { version = "1.0.152", default-features = false, features = ["derive"] }"#
+        );
+    }
+}
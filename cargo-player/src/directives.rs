@@ -0,0 +1,546 @@
+use crate::script::{embedded_manifest, shebang};
+use crate::LineEnding;
+
+// dependencies, e.g. `//# serde = "1"`, or `//#dev proptest = "1"` for another table
+const DEPENDENCY: &str = "//#";
+// extra cargo toml, e.g. `//> [profile.dev]`
+const CARGO_TOML: &str = "//>";
+
+/// The table of the Cargo.toml a `//#` directive puts its dependency in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Table {
+    Dependencies,
+    DevDependencies,
+    BuildDependencies,
+}
+
+impl Table {
+    pub(crate) const ALL: [Self; 3] = [
+        Self::Dependencies,
+        Self::DevDependencies,
+        Self::BuildDependencies,
+    ];
+
+    /// What follows `//#` to pick the table, e.g. `dev` in `//#dev proptest = "1"`
+    pub(crate) fn marker(self) -> &'static str {
+        match self {
+            Self::Dependencies => "",
+            Self::DevDependencies => "dev",
+            Self::BuildDependencies => "build",
+        }
+    }
+
+    /// The table and the dependency of what follows `//#`, e.g. `dev proptest = "1"`.
+    /// A crate which happens to be called dev or build is still read as one, e.g. `dev = "1"`
+    pub(crate) fn split(directive: &str) -> (Self, &str) {
+        for table in [Self::DevDependencies, Self::BuildDependencies] {
+            let Some(dependency) = directive.strip_prefix(table.marker()) else {
+                continue;
+            };
+
+            if dependency.starts_with(char::is_whitespace) && !dependency.trim().starts_with('=') {
+                return (table, dependency.trim());
+            }
+        }
+
+        (Self::Dependencies, directive)
+    }
+}
+
+/// A `//#` or `//>` line of the code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Directive<'a> {
+    // counting from 0
+    pub line: usize,
+    pub text: &'a str,
+    // false if it's below the first line of code, where directives are ignored
+    pub read: bool,
+}
+
+// what a directive line says
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DirectiveLine<'a> {
+    Dependency(Table, &'a str),
+    CargoToml(&'a str),
+}
+
+impl<'a> DirectiveLine<'a> {
+    /// The directive on a line, None if it has none
+    pub(crate) fn parse(line: &'a str) -> Option<Self> {
+        let trimmed = line.trim();
+
+        if let Some(dependency) = trimmed.strip_prefix(DEPENDENCY) {
+            // e.g. `//#![feature(never_type)]` is an attribute which was commented out
+            let marked = Table::ALL
+                .into_iter()
+                .filter_map(|table| dependency.strip_prefix(table.marker()))
+                .any(ends_marker);
+
+            return marked.then(|| {
+                let (table, dependency) = Table::split(dependency.trim());
+                Self::Dependency(table, dependency)
+            });
+        }
+
+        let toml = trimmed
+            .strip_prefix(CARGO_TOML)
+            .filter(|toml| ends_marker(toml))?;
+        Some(Self::CargoToml(toml.strip_prefix(' ').unwrap_or(toml)))
+    }
+}
+
+// whether what follows the marker of a directive, e.g. `//#dev`, leaves it on its own
+fn ends_marker(rest: &str) -> bool {
+    rest.is_empty() || rest.starts_with(char::is_whitespace)
+}
+
+/// Whether a line may come before the directives, and between them: blank lines, comments, doc comments,
+/// inner attributes and a shebang. Directives below the first line of anything else are ignored
+pub(crate) fn is_preamble(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with("//") || line.starts_with("#!")
+}
+
+/// The directives which are read, those above the first line of code
+pub(crate) fn read_directives(code: &str) -> impl Iterator<Item = DirectiveLine<'_>> {
+    code.lines()
+        .take_while(|line| is_preamble(line))
+        .filter_map(DirectiveLine::parse)
+}
+
+/// Every `//#` and `//>` line of the code, and whether it's read. Only those above the first line of code
+/// are, comments, doc comments and inner attributes may come before and between them
+pub fn directives(code: &str) -> Vec<Directive<'_>> {
+    let mut read = true;
+
+    code.lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            read &= is_preamble(text);
+
+            DirectiveLine::parse(text).map(|_| Directive {
+                line,
+                text: text.trim(),
+                read,
+            })
+        })
+        .collect()
+}
+
+/// Tidy the `//#` and `//>` directives at the top of the code. Spacing is normalized, dependencies are
+/// sorted by table and name, and the `=` signs of each block are aligned. They're gathered where the first
+/// of them is, the dependencies first. Comments among them go below, the rest of the code is left as is
+pub fn format_directives(code: &str) -> String {
+    let mut dependencies = vec![];
+    let mut cargo_toml = vec![];
+
+    // the comments, and a shebang, above the directives and among them
+    let mut before = String::new();
+    let mut among = String::new();
+
+    let mut lines = code.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next_if(|line| is_preamble(line)) {
+        match DirectiveLine::parse(line) {
+            Some(DirectiveLine::Dependency(table, dependency)) => {
+                dependencies.push((table, dependency))
+            }
+            Some(DirectiveLine::CargoToml(toml)) => cargo_toml.push(toml.trim()),
+            None if dependencies.is_empty() && cargo_toml.is_empty() => before.push_str(line),
+            None => among.push_str(line),
+        }
+    }
+
+    if dependencies.is_empty() && cargo_toml.is_empty() {
+        return code.to_string();
+    }
+
+    let line_ending = match LineEnding::detect(code) {
+        Some(LineEnding::Crlf) => "\r\n",
+        _ => "\n",
+    };
+
+    let name = |dependency: &str| key_value(dependency).map(|(key, _)| key.to_string());
+    dependencies.sort_by_key(|(table, dependency)| (*table, name(dependency)));
+
+    let mut formatted = before;
+
+    // aligned per table as well
+    for table in Table::ALL {
+        let lines = dependencies
+            .iter()
+            .filter(|(other, _)| *other == table)
+            .map(|(_, dependency)| *dependency)
+            .collect::<Vec<_>>();
+
+        for line in aligned(&lines) {
+            let marker = table.marker();
+            formatted.push_str(&format!("{DEPENDENCY}{marker} {line}{line_ending}"));
+        }
+    }
+
+    // aligned per table, keys of different tables have nothing to do with each other
+    for table in cargo_toml.split_inclusive(|line| line.starts_with('[')) {
+        for line in aligned(table) {
+            let line = format!("{CARGO_TOML} {line}");
+            formatted.push_str(line.trim_end());
+            formatted.push_str(line_ending);
+        }
+    }
+
+    // the directives were the whole code, without a line break at the end
+    if among.is_empty() && lines.peek().is_none() && !code.ends_with('\n') {
+        formatted.truncate(formatted.len() - line_ending.len());
+    }
+
+    formatted.push_str(&among);
+    formatted.extend(lines);
+    formatted
+}
+
+/// A line of a Cargo.toml `[dependencies]` table, e.g. `rand = { version = "0.8.5", features = ["small_rng"] }`
+pub fn dependency_toml(
+    name: &str,
+    version: &str,
+    default_features: bool,
+    features: &[String],
+) -> String {
+    if default_features && features.is_empty() {
+        return format!(r#"{name} = "{version}""#);
+    }
+
+    let mut line = format!(r#"{name} = {{ version = "{version}""#);
+
+    if !default_features {
+        line.push_str(", default-features = false");
+    }
+
+    if !features.is_empty() {
+        let features = features
+            .iter()
+            .map(|feature| format!(r#""{feature}""#))
+            .collect::<Vec<_>>();
+
+        line.push_str(&format!(", features = [{}]", features.join(", ")));
+    }
+
+    line.push_str(" }");
+    line
+}
+
+/// Add a `//#` directive for a dependency, e.g. a line made by [`dependency_toml`]. It replaces the one of
+/// a crate of the same name in the directives at the top, and otherwise goes after the last of them,
+/// or at the top, after a shebang
+pub fn add_dependency_directive(code: &str, dependency: &str) -> String {
+    let line_ending = match LineEnding::detect(code) {
+        Some(LineEnding::Crlf) => "\r\n",
+        _ => "\n",
+    };
+
+    // crate names may be written with - or _ either way
+    let normalized = |line: &str| key_value(line).map(|(name, _)| name.replace('-', "_"));
+    let name = normalized(dependency);
+    let directive = format!("{DEPENDENCY} {dependency}");
+
+    let mut lines = code.lines().map(str::to_string).collect::<Vec<_>>();
+    let top = lines.iter().take_while(|line| is_preamble(line)).count();
+
+    let existing = lines[..top]
+        .iter()
+        .position(|line| match DirectiveLine::parse(line) {
+            Some(DirectiveLine::Dependency(Table::Dependencies, dependency)) => {
+                name.is_some() && normalized(dependency) == name
+            }
+            _ => false,
+        });
+
+    match existing {
+        Some(i) => lines[i] = directive,
+        None => {
+            let after = lines[..top]
+                .iter()
+                .rposition(|line| {
+                    matches!(
+                        DirectiveLine::parse(line),
+                        Some(DirectiveLine::Dependency(..))
+                    )
+                })
+                .map_or(usize::from(shebang(code).is_some()), |i| i + 1);
+
+            lines.insert(after, directive);
+        }
+    }
+
+    let mut added = lines.join(line_ending);
+    if code.ends_with('\n') {
+        added.push_str(line_ending);
+    }
+
+    added
+}
+
+/// The names of the dependencies the code lists itself in `//#` directives or a script's embedded
+/// manifest, as opposed to those inferred from its `use` statements
+pub fn dependency_directives(code: &str) -> Vec<String> {
+    let embedded = embedded_manifest(code).unwrap_or_default().dependencies;
+
+    read_directives(code)
+        .filter_map(|directive| match directive {
+            DirectiveLine::Dependency(_, dependency) => Some(dependency),
+            DirectiveLine::CargoToml(_) => None,
+        })
+        .chain(embedded.iter().map(|(_, dependency)| dependency.as_str()))
+        .filter_map(key_value)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+// split a `key = value` line, None if it has no `=` or is a table header or comment
+pub(crate) fn key_value(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with(['[', '#']) {
+        return None;
+    }
+
+    let (key, value) = line.split_once('=')?;
+
+    Some((key.trim(), value.trim()))
+}
+
+// pad the keys so the `=` signs line up, lines which aren't a key and value are left as is
+fn aligned(lines: &[&str]) -> Vec<String> {
+    let width = lines
+        .iter()
+        .filter_map(|line| key_value(line))
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    lines
+        .iter()
+        .map(|line| match key_value(line) {
+            Some((key, value)) => format!("{key:width$} = {value}"),
+            None => line.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_and_align_dependencies() {
+        let code = r#"//#   serde={ version = "1", features = ["derive"] }
+//# anyhow = "1"
+//# rand= "0.8"
+//> [profile.dev]
+//>   opt-level=1
+//> debug = true
+//>
+//> [features]
+//> default = []
+
+fn main() {}
+"#;
+
+        assert_eq!(
+            format_directives(code),
+            r#"//# anyhow = "1"
+//# rand   = "0.8"
+//# serde  = { version = "1", features = ["derive"] }
+//> [profile.dev]
+//> opt-level = 1
+//> debug     = true
+//>
+//> [features]
+//> default = []
+
+fn main() {}
+"#
+        );
+    }
+
+    #[test]
+    fn dev_and_build_dependencies() {
+        let code = "//#build cc = \"1\"\n//#dev  proptest=\"1\"\n//# rand = \"0.8\"\n//#dev = \"1\"\nfn main() {}\n";
+
+        assert_eq!(
+            format_directives(code),
+            "//# dev  = \"1\"\n//# rand = \"0.8\"\n//#dev proptest = \"1\"\n//#build cc = \"1\"\nfn main() {}\n"
+        );
+        assert_eq!(
+            dependency_directives(code),
+            ["cc", "proptest", "rand", "dev"]
+        );
+    }
+
+    #[test]
+    fn dependencies_move_first() {
+        let code = "//> [profile.dev]\r\n//# b = \"*\"\r\n//# a = \"*\"";
+
+        assert_eq!(
+            format_directives(code),
+            "//# a = \"*\"\r\n//# b = \"*\"\r\n//> [profile.dev]"
+        );
+    }
+
+    #[test]
+    fn after_a_shebang() {
+        let code = "#!/usr/bin/env rust-script\n//# rand= \"0.8\"\nfn main() {}\n";
+
+        assert_eq!(
+            format_directives(code),
+            "#!/usr/bin/env rust-script\n//# rand = \"0.8\"\nfn main() {}\n"
+        );
+        assert_eq!(
+            add_dependency_directive(code, r#"serde = "1""#),
+            "#!/usr/bin/env rust-script\n//# rand= \"0.8\"\n//# serde = \"1\"\nfn main() {}\n"
+        );
+        assert_eq!(
+            add_dependency_directive("#!/bin/x", r#"serde = "1""#),
+            "#!/bin/x\n//# serde = \"1\""
+        );
+
+        // an inner attribute isn't one, and is left where it is
+        assert_eq!(
+            add_dependency_directive("#![allow(unused)]\n", r#"serde = "1""#),
+            "//# serde = \"1\"\n#![allow(unused)]\n"
+        );
+    }
+
+    #[test]
+    fn below_comments() {
+        let code = "//! Docs\n//# rand= \"0.8\"\n\n// a comment\n//# anyhow = \"1\"\nfn main() {}\n//# serde = \"1\"\n";
+
+        assert_eq!(
+            directives(code),
+            [
+                Directive {
+                    line: 1,
+                    text: "//# rand= \"0.8\"",
+                    read: true
+                },
+                Directive {
+                    line: 4,
+                    text: "//# anyhow = \"1\"",
+                    read: true
+                },
+                Directive {
+                    line: 6,
+                    text: "//# serde = \"1\"",
+                    read: false
+                },
+            ]
+        );
+        assert_eq!(dependency_directives(code), ["rand", "anyhow"]);
+
+        // gathered where the first one is, the comments among them go below
+        assert_eq!(
+            format_directives(code),
+            "//! Docs\n//# anyhow = \"1\"\n//# rand   = \"0.8\"\n\n// a comment\nfn main() {}\n//# serde = \"1\"\n"
+        );
+        assert_eq!(
+            add_dependency_directive(code, r#"serde = "1""#),
+            "//! Docs\n//# rand= \"0.8\"\n\n// a comment\n//# anyhow = \"1\"\n//# serde = \"1\"\nfn main() {}\n//# serde = \"1\"\n"
+        );
+    }
+
+    #[test]
+    fn dependency_names() {
+        let code = "//# serde = { version = \"1\", features = [\"derive\"] }\n//# rand-core=\"*\"\n//> [features]\nfn main() {}";
+
+        assert_eq!(dependency_directives(code), ["serde", "rand-core"]);
+
+        let script = "//! ```cargo\n//! [dependencies]\n//! time = \"0.1\"\n//! ```\n//# rand = \"0.8\"\nfn main() {}";
+        assert_eq!(dependency_directives(script), ["rand", "time"]);
+    }
+
+    #[test]
+    fn dependency_lines() {
+        assert_eq!(
+            dependency_toml("rand", "0.8.5", true, &[]),
+            r#"rand = "0.8.5""#
+        );
+        assert_eq!(
+            dependency_toml(
+                "tokio",
+                "1.25.0",
+                false,
+                &["macros".to_string(), "rt".to_string()]
+            ),
+            r#"tokio = { version = "1.25.0", default-features = false, features = ["macros", "rt"] }"#
+        );
+    }
+
+    #[test]
+    fn add_dependencies() {
+        let code = "//# rand = \"0.8\"\n//> [profile.dev]\nfn main() {}\n";
+
+        assert_eq!(
+            add_dependency_directive(code, r#"serde = "1""#),
+            "//# rand = \"0.8\"\n//# serde = \"1\"\n//> [profile.dev]\nfn main() {}\n"
+        );
+
+        // the same crate is replaced, whichever way its name is written
+        assert_eq!(
+            add_dependency_directive(
+                "//# rand_core = \"*\"\r\nfn main() {}",
+                r#"rand-core = "0.6""#
+            ),
+            "//# rand-core = \"0.6\"\r\nfn main() {}"
+        );
+
+        assert_eq!(
+            add_dependency_directive("fn main() {}", r#"rand = "0.8""#),
+            "//# rand = \"0.8\"\nfn main() {}"
+        );
+        assert_eq!(
+            add_dependency_directive("//# rand = \"0.8\"", r#"serde = "1""#),
+            "//# rand = \"0.8\"\n//# serde = \"1\""
+        );
+        assert_eq!(
+            add_dependency_directive("", r#"rand = "0.8""#),
+            "//# rand = \"0.8\""
+        );
+    }
+
+    #[test]
+    fn markers_stand_alone() {
+        for line in [
+            "//#![feature(never_type)]",
+            "//#derive(Debug)",
+            "//#device = \"1\"",
+            "//>= 2",
+        ] {
+            assert_eq!(DirectiveLine::parse(line), None, "{line}");
+        }
+
+        assert_eq!(
+            DirectiveLine::parse("//#build\tcc = \"1\""),
+            Some(DirectiveLine::Dependency(
+                Table::BuildDependencies,
+                "cc = \"1\""
+            ))
+        );
+        assert_eq!(
+            DirectiveLine::parse("//>"),
+            Some(DirectiveLine::CargoToml(""))
+        );
+
+        let code = "//#![feature(never_type)]\n//# rand = \"0.8\"\nfn main() {}\n";
+        assert_eq!(
+            read_directives(code).collect::<Vec<_>>(),
+            [DirectiveLine::Dependency(
+                Table::Dependencies,
+                "rand = \"0.8\""
+            )]
+        );
+    }
+
+    #[test]
+    fn code_without_directives() {
+        let code = "// a comment\n//# serde = \"1\"\nfn main() {}";
+
+        assert_eq!(format_directives(code), code);
+        assert_eq!(format_directives(""), "");
+    }
+}
@@ -0,0 +1,297 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use thiserror::Error;
+
+// written and removed again to tell whether a directory can be generated in
+const PROBE_FILE: &str = ".cargo-player-probe";
+
+/// The directory every project is generated in, whatever its prefix, unless it has a [`Project::base_dir`]
+///
+/// [`Project::base_dir`]: crate::Project::base_dir
+pub fn projects_dir() -> PathBuf {
+    std::env::temp_dir().join("rust")
+}
+
+#[derive(Debug, Error)]
+pub enum BaseDirError {
+    #[error("The path has to be absolute")]
+    Relative,
+    #[error("Couldn't create the directory: {0}")]
+    Create(io::Error),
+    #[error("Can't write to the directory: {0}")]
+    ReadOnly(io::Error),
+}
+
+/// Check a directory can hold the generated projects, e.g. one on a ramdisk given to
+/// [`Project::base_dir`]. It's created if it doesn't exist yet
+///
+/// [`Project::base_dir`]: crate::Project::base_dir
+pub fn prepare_base_dir(dir: &Path) -> Result<(), BaseDirError> {
+    if !dir.is_absolute() {
+        return Err(BaseDirError::Relative);
+    }
+
+    fs::create_dir_all(dir).map_err(BaseDirError::Create)?;
+
+    let probe = dir.join(PROBE_FILE);
+    fs::write(&probe, []).map_err(BaseDirError::ReadOnly)?;
+    let _ = fs::remove_file(probe);
+
+    Ok(())
+}
+
+/// A generated project and the disk it uses
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectUsage {
+    pub dir: PathBuf,
+    // the whole project, including its build output
+    pub bytes: u64,
+    // the build output in target/, which cleaning removes
+    pub target_bytes: u64,
+    // when it was last built, None if it never was
+    pub built: Option<SystemTime>,
+}
+
+/// The disk each project generated in `dir` uses, e.g. [`projects_dir`], the least recently built first
+pub fn project_usage(dir: &Path) -> Vec<ProjectUsage> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut usage = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|dir| {
+            let target = dir.join("target");
+
+            ProjectUsage {
+                bytes: dir_size(&dir),
+                target_bytes: dir_size(&target),
+                built: last_built(&target),
+                dir,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    usage.sort_by_key(|project| project.built);
+
+    usage
+}
+
+/// Remove the build output of the least recently built projects until all of them use at most `quota`
+/// bytes. The sources stay, so a project builds again from scratch the next time. Returns the bytes freed
+pub fn clean_projects(usage: &[ProjectUsage], quota: u64) -> u64 {
+    let mut total = usage.iter().map(|project| project.bytes).sum::<u64>();
+    let mut freed = 0;
+
+    for project in usage.iter().filter(|project| project.target_bytes > 0) {
+        if total <= quota {
+            break;
+        }
+
+        if fs::remove_dir_all(project.dir.join("target")).is_ok() {
+            total = total.saturating_sub(project.target_bytes);
+            freed += project.target_bytes;
+        }
+    }
+
+    freed
+}
+
+// when the project was last built, by the newest of cargo's fingerprints and dep-info files. target/ itself
+// only changes when something is added right in it, not on every build
+fn last_built(target: &Path) -> Option<SystemTime> {
+    // the profiles, e.g. target/debug, and those built for another target, e.g.
+    // target/wasm32-unknown-unknown/debug
+    let profiles = children(target, true)
+        .into_iter()
+        .flat_map(|dir| {
+            let nested = children(&dir, true);
+            std::iter::once(dir).chain(nested)
+        })
+        .collect::<Vec<_>>();
+
+    profiles
+        .iter()
+        .flat_map(|profile| {
+            let fingerprints = children(&profile.join(".fingerprint"), true)
+                .into_iter()
+                .flat_map(|unit| children(&unit, false));
+
+            let dep_info = children(profile, false)
+                .into_iter()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "d"));
+
+            fingerprints.chain(dep_info).collect::<Vec<_>>()
+        })
+        .filter_map(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .max()
+}
+
+// the directories, or else the files, right in the directory. Links aren't followed
+fn children(dir: &Path, dirs: bool) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry.file_type().is_ok_and(|file_type| {
+                if dirs {
+                    file_type.is_dir()
+                } else {
+                    file_type.is_file()
+                }
+            })
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+// the size of every file below the directory, 0 if it doesn't exist
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            // links aren't followed, they may lead out of the project
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(file_type) if file_type.is_file() => {
+                entry.metadata().map_or(0, |metadata| metadata.len())
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+    use std::time::Duration;
+
+    fn project(root: &Path, name: &str, source: usize, target: usize) -> PathBuf {
+        let dir = root.join(name);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src").join("main.rs"), vec![b' '; source]).unwrap();
+
+        if target > 0 {
+            fs::create_dir_all(dir.join("target").join("debug")).unwrap();
+            fs::write(dir.join("target").join("debug").join("p"), vec![0; target]).unwrap();
+            build(&dir);
+        }
+
+        dir
+    }
+
+    // what cargo leaves behind on every build of the project
+    fn build(dir: &Path) {
+        let unit = dir
+            .join("target")
+            .join("debug")
+            .join(".fingerprint")
+            .join("p-1234");
+        fs::create_dir_all(&unit).unwrap();
+        fs::write(unit.join("bin-p"), "").unwrap();
+    }
+
+    #[test]
+    fn measure_and_clean() {
+        let root = std::env::temp_dir().join("cargo-player-disk-usage");
+        let _ = fs::remove_dir_all(&root);
+
+        let old = project(&root, "old", 10, 1000);
+        // built after the old one
+        thread::sleep(Duration::from_millis(20));
+        let new = project(&root, "new", 20, 500);
+        let never_built = project(&root, "never-built", 5, 0);
+
+        let usage = project_usage(&root);
+        let dirs = usage
+            .iter()
+            .map(|project| project.dir.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(dirs, [never_built, old.clone(), new.clone()]);
+
+        let old_usage = &usage[1];
+        assert_eq!(old_usage.bytes, 1010);
+        assert_eq!(old_usage.target_bytes, 1000);
+
+        // removing the oldest build is enough
+        assert_eq!(clean_projects(&usage, 600), 1000);
+        assert!(!old.join("target").exists());
+        assert!(old.join("src").join("main.rs").exists());
+        assert!(new.join("target").exists());
+
+        // under the quota nothing goes
+        let usage = project_usage(&root);
+        assert_eq!(clean_projects(&usage, 600), 0);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn rebuilt_last() {
+        let root = std::env::temp_dir().join("cargo-player-disk-usage-rebuilt");
+        let _ = fs::remove_dir_all(&root);
+
+        let old = project(&root, "old", 10, 0);
+        fs::create_dir_all(old.join("target").join("debug")).unwrap();
+        build(&old);
+
+        thread::sleep(Duration::from_millis(20));
+        let new = project(&root, "new", 10, 100);
+
+        // a rebuild writes inside target/, it doesn't touch target/ itself
+        thread::sleep(Duration::from_millis(20));
+        build(&old);
+
+        let usage = project_usage(&root);
+        let dirs = usage
+            .iter()
+            .map(|project| project.dir.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(dirs, [new, old]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn base_dirs() {
+        let root = std::env::temp_dir().join("cargo-player-base-dir");
+        let _ = fs::remove_dir_all(&root);
+
+        let dir = root.join("nested").join("projects");
+        prepare_base_dir(&dir).unwrap();
+        assert!(dir.is_dir());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        assert!(matches!(
+            prepare_base_dir(Path::new("projects")),
+            Err(BaseDirError::Relative)
+        ));
+
+        // a file is in the way
+        let file = root.join("file");
+        fs::write(&file, "").unwrap();
+        assert!(matches!(
+            prepare_base_dir(&file.join("projects")),
+            Err(BaseDirError::Create(_))
+        ));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
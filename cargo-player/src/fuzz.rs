@@ -0,0 +1,320 @@
+use std::path::PathBuf;
+
+use quote::ToTokens;
+use syn::{parse_file, FnArg, Item, Signature, Type};
+
+use crate::{Channel, Project, Subcommand};
+
+/// Name of the cargo-fuzz target generated around the fuzzed function
+pub const FUZZ_TARGET: &str = "scratch";
+
+/// What a fuzzed function takes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FuzzInput {
+    /// A single `&[u8]`, the raw fuzzer input is passed as is
+    Bytes,
+    /// Any number of arguments built from the fuzzer input with `arbitrary`. Holds their types
+    Arbitrary(Vec<String>),
+}
+
+/// A function of the scratch which can be fuzzed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzFn {
+    pub name: String,
+    pub input: FuzzInput,
+}
+
+/// Top level functions taking arguments, which can be fuzzed. Generic, async and unsafe functions are skipped
+pub fn fuzzable_functions(code: &str) -> Vec<FuzzFn> {
+    plain_functions(code)
+        .into_iter()
+        .map(|sig| {
+            let types = argument_types(&sig);
+
+            let input = match types.as_slice() {
+                [ty] if is_bytes(ty) => FuzzInput::Bytes,
+                types => FuzzInput::Arbitrary(
+                    types
+                        .iter()
+                        .map(|ty| ty.to_token_stream().to_string())
+                        .collect(),
+                ),
+            };
+
+            FuzzFn {
+                name: sig.ident.to_string(),
+                input,
+            }
+        })
+        .collect()
+}
+
+// Signatures of the top level functions which can be called with generated arguments.
+// They take arguments but not self, and aren't generic, async, unsafe or main
+pub(crate) fn plain_functions(code: &str) -> Vec<Signature> {
+    let Ok(file) = parse_file(code) else {
+        return vec![];
+    };
+
+    file.items
+        .into_iter()
+        .filter_map(|item| {
+            let Item::Fn(function) = item else {
+                return None;
+            };
+
+            let sig = function.sig;
+            let is_plain = sig.generics.params.is_empty()
+                && sig.asyncness.is_none()
+                && sig.unsafety.is_none()
+                && sig.receiver().is_none();
+
+            (is_plain && !sig.inputs.is_empty() && sig.ident != "main").then_some(sig)
+        })
+        .collect()
+}
+
+pub(crate) fn argument_types(sig: &Signature) -> Vec<&Type> {
+    sig.inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(arg) => Some(&*arg.ty),
+            FnArg::Receiver(_) => None,
+        })
+        .collect()
+}
+
+// &[u8]
+fn is_bytes(ty: &Type) -> bool {
+    let Type::Reference(reference) = ty else {
+        return false;
+    };
+
+    let Type::Slice(slice) = &*reference.elem else {
+        return false;
+    };
+
+    reference.mutability.is_none() && slice.elem.to_token_stream().to_string() == "u8"
+}
+
+impl<'a> Project<'a> {
+    /// Fuzz a function of the main file with cargo-fuzz, which must be installed.
+    /// A fuzz target calling it is generated next to the project
+    pub fn fuzz(&mut self, function: FuzzFn) -> &mut Self {
+        self.fuzz = Some(function);
+
+        self.channel(Channel::Nightly)
+            .subcommand(Subcommand::Fuzz)
+            .subcommand_flags(&["run", FUZZ_TARGET])
+    }
+
+    /// Run the fuzz target once with a saved input, e.g. a crash from [`Project::fuzz_artifacts_dir`]
+    pub fn fuzz_replay(&mut self, function: FuzzFn, input: &'a str) -> &mut Self {
+        self.fuzz(function).subcommand_flag(input)
+    }
+
+    /// Where cargo-fuzz saves the inputs which crashed the target
+    pub fn fuzz_artifacts_dir(&self) -> PathBuf {
+        self.project_dir()
+            .join("fuzz")
+            .join("artifacts")
+            .join(FUZZ_TARGET)
+    }
+}
+
+// the fuzz crate is separate from the scratch, with the same dependencies
+pub(crate) fn fuzz_cargo_toml(id: u64, edition: &str, dependencies: &str) -> String {
+    format!(
+        r#"[package]
+name = "p{id}-fuzz"
+version = "0.1.0"
+edition = "{edition}"
+publish = false
+
+[package.metadata]
+cargo-fuzz = true
+
+[dependencies]
+libfuzzer-sys = "0.4"
+arbitrary = {{ version = "1", features = ["derive"] }}
+{dependencies}
+
+[[bin]]
+name = "{FUZZ_TARGET}"
+path = "fuzz_targets/{FUZZ_TARGET}.rs"
+test = false
+doc = false
+
+# keep it out of any workspace the scratch may be in
+[workspace]
+members = ["."]
+"#
+    )
+}
+
+// the scratch itself with the fuzzer's entry point appended. Its main is never called
+pub(crate) fn fuzz_target_code(code: &str, function: &FuzzFn) -> String {
+    let name = &function.name;
+
+    let entry = match &function.input {
+        FuzzInput::Bytes => format!(
+            "libfuzzer_sys::fuzz_target!(|data: &[u8]| {{\n    let _ = {name}(data);\n}});\n"
+        ),
+
+        FuzzInput::Arbitrary(types) => {
+            let args = (0..types.len())
+                .map(|i| format!("arg{i}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!(
+                "libfuzzer_sys::fuzz_target!(|input: ({},)| {{\n    let ({args},) = input;\n    let _ = {name}({args});\n}});\n",
+                types.join(", ")
+            )
+        }
+    };
+
+    format!("#![no_main]\n#![allow(dead_code)]\n\n{code}\n{entry}")
+}
+
+/// The latest progress libFuzzer printed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzStats {
+    /// Inputs tried so far
+    pub runs: u64,
+    /// Code edges covered
+    pub coverage: Option<u64>,
+    /// e.g. 12/345b, the number of inputs and their total size
+    pub corpus: Option<String>,
+    pub execs_per_sec: Option<u64>,
+    /// e.g. 35Mb
+    pub rss: Option<String>,
+}
+
+impl FuzzStats {
+    /// Parse a libFuzzer status line, e.g. `#1024 pulse  cov: 12 ft: 14 corp: 3/9b exec/s: 512 rss: 30Mb`
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim().strip_prefix('#')?;
+
+        let mut words = line.split_whitespace();
+        let runs = words.next()?.parse().ok()?;
+
+        let mut stats = Self {
+            runs,
+            coverage: None,
+            corpus: None,
+            execs_per_sec: None,
+            rss: None,
+        };
+
+        while let Some(word) = words.next() {
+            match word {
+                "cov:" => stats.coverage = words.next().and_then(|n| n.parse().ok()),
+                "corp:" => stats.corpus = words.next().map(ToString::to_string),
+                "exec/s:" => stats.execs_per_sec = words.next().and_then(|n| n.parse().ok()),
+                "rss:" => stats.rss = words.next().map(ToString::to_string),
+                _ => (),
+            }
+        }
+
+        Some(stats)
+    }
+}
+
+/// The last status line libFuzzer printed to stderr, if any
+pub fn fuzz_stats(stderr: &str) -> Option<FuzzStats> {
+    stderr.lines().rev().find_map(FuzzStats::parse)
+}
+
+/// The saved input which crashed the fuzz target, if it did
+pub fn fuzz_crash(stderr: &str) -> Option<PathBuf> {
+    stderr.lines().find_map(|line| {
+        let path = line.split("Test unit written to ").nth(1)?;
+
+        Some(PathBuf::from(path.trim()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_fuzzable_functions() {
+        let code = r#"
+fn parse(data: &[u8]) -> Option<u32> { None }
+fn check(name: &str, count: u32) {}
+fn generic<T>(value: T) {}
+fn no_args() {}
+fn main() {}
+"#;
+
+        assert_eq!(
+            fuzzable_functions(code),
+            vec![
+                FuzzFn {
+                    name: "parse".to_string(),
+                    input: FuzzInput::Bytes,
+                },
+                FuzzFn {
+                    name: "check".to_string(),
+                    input: FuzzInput::Arbitrary(vec!["& str".to_string(), "u32".to_string()]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn generate_fuzz_target() {
+        let bytes = FuzzFn {
+            name: "parse".to_string(),
+            input: FuzzInput::Bytes,
+        };
+        let code = fuzz_target_code("fn parse(data: &[u8]) {}", &bytes);
+
+        assert!(code.starts_with("#![no_main]"));
+        assert!(code.contains("fuzz_target!(|data: &[u8]| {\n    let _ = parse(data);"));
+
+        let arbitrary = FuzzFn {
+            name: "check".to_string(),
+            input: FuzzInput::Arbitrary(vec!["& str".to_string(), "u32".to_string()]),
+        };
+        let code = fuzz_target_code("fn check(name: &str, count: u32) {}", &arbitrary);
+
+        assert!(code.contains("fuzz_target!(|input: (& str, u32,)| {"));
+        assert!(code.contains("let (arg0, arg1,) = input;\n    let _ = check(arg0, arg1);"));
+
+        let toml = fuzz_cargo_toml(1, "2021", "rand = \"*\"");
+        assert!(toml.contains("cargo-fuzz = true"));
+        assert!(toml.contains("rand = \"*\""));
+        assert!(toml.contains("path = \"fuzz_targets/scratch.rs\""));
+    }
+
+    #[test]
+    fn parse_fuzzer_output() {
+        let stderr = "\
+INFO: Seed: 1234
+#2	INITED cov: 10 ft: 11 corp: 1/1b exec/s: 0 rss: 30Mb
+#4096	pulse  cov: 25 ft: 30 corp: 6/42b lim: 43 exec/s: 2048 rss: 35Mb
+==42== ERROR: libFuzzer: deadly signal
+artifact_prefix='/tmp/p/fuzz/artifacts/scratch/'; Test unit written to /tmp/p/fuzz/artifacts/scratch/crash-abc
+";
+
+        assert_eq!(
+            fuzz_stats(stderr),
+            Some(FuzzStats {
+                runs: 4096,
+                coverage: Some(25),
+                corpus: Some("6/42b".to_string()),
+                execs_per_sec: Some(2048),
+                rss: Some("35Mb".to_string()),
+            })
+        );
+        assert_eq!(
+            fuzz_crash(stderr),
+            Some(PathBuf::from("/tmp/p/fuzz/artifacts/scratch/crash-abc"))
+        );
+        assert_eq!(fuzz_stats("Hello"), None);
+        assert_eq!(fuzz_crash("Hello"), None);
+    }
+}
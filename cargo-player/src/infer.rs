@@ -1,5 +1,7 @@
 use std::sync::{Arc, Mutex};
 
+use crate::directives::{read_directives, DirectiveLine, Table};
+use crate::script::embedded_manifest;
 use crate::File;
 
 use crates_index::Index;
@@ -8,10 +10,20 @@ use syn::{
     parse_file, Block, Error, Expr, ImplItem, Item, ItemFn, ItemImpl, ItemMod, Stmt, UseTree,
 };
 
-const USE_KEYWORDS: &[&str] = &["std", "core", "crate", "self", "alloc", "super"];
+pub(crate) const USE_KEYWORDS: &[&str] = &["std", "core", "crate", "self", "alloc", "super"];
 
-pub fn infer_deps(files: &[File]) -> Result<String, syn::Error> {
+/// The lines of each dependency table, inferred from the use statements and listed in `//#` directives
+#[derive(Debug, Default, PartialEq)]
+pub struct InferredDeps {
+    pub dependencies: String,
+    pub dev_dependencies: String,
+    pub build_dependencies: String,
+}
+
+pub fn infer_deps(files: &[File]) -> Result<InferredDeps, syn::Error> {
     let mut deps = vec![];
+    // used only in tests, e.g. in a #[cfg(test)] module
+    let mut dev_deps = vec![];
 
     files
         .iter()
@@ -23,55 +35,80 @@ pub fn infer_deps(files: &[File]) -> Result<String, syn::Error> {
                 let mut mod_stmts = vec![];
 
                 tokens.into_iter().for_each(|i| {
-                    extract_use(TokenType::Item(i), &mut deps, &mut mod_stmts);
+                    let deps = if is_test_only(&i) {
+                        &mut dev_deps
+                    } else {
+                        &mut deps
+                    };
+
+                    extract_use(TokenType::Item(i), deps, &mut mod_stmts);
                 });
 
                 // remove any deps from deps list if they match a mod stmt
                 // this is subject to a limited amount of false positives, but is not too likely to happen in real practice
                 deps.retain(|i| !mod_stmts.contains(i));
+                dev_deps.retain(|i| !mod_stmts.contains(i));
             }
         });
 
-    // Process `//# ` as a direct statement to put inside depenencies
-    // Can only appear at beginning of file
-    // stops processing when non ``//# ` is found
-    let mut added = 0;
+    // tests can use the normal dependencies as well
+    dev_deps.retain(|i| !deps.contains(i));
+
+    // Process `//# `, `//#dev ` and `//#build ` as direct statements to put inside their table
+    // Can only appear above the code, comments and inner attributes may come before them
+    let mut listed: [Vec<String>; 3] = Default::default();
     for file in files {
-        for line in file.code.lines() {
-            if let Some(line) = line.strip_prefix(r#"//# "#) {
-                // find the name of the dependency
-                let name = line.find('=').map(|i| line[0..i].trim());
-
-                // remove dependency with same name to avoid conflicts - user provided deps are overrides
-                if let Some(name) = name {
-                    let index = deps.iter().position(|p| {
-                        let convert_case = |b| -> u8 {
-                            // only convert - to _ . Else, it's either _, or something we shouldn't filter
-                            if b == b'-' {
-                                b'_'
-                            } else {
-                                b
-                            }
-                        };
-
-                        // Compare crate names with - or _ being equal
-                        p.bytes()
-                            .map(convert_case)
-                            .eq(name.bytes().map(convert_case))
-                    });
-
-                    if let Some(i) = index {
-                        deps.remove(i);
-                    }
-                }
+        let directives = read_directives(file.code).filter_map(|directive| match directive {
+            DirectiveLine::Dependency(table, line) => Some((table, line)),
+            DirectiveLine::CargoToml(_) => None,
+        });
 
-                deps.insert(0, line.to_string());
-                added += 1;
+        // the dependencies of a script's embedded manifest count as directives, like its other tables
+        // only the main file's are read
+        let manifest = match file.name {
+            "main" => embedded_manifest(file.code).unwrap_or_default(),
+            _ => Default::default(),
+        };
 
-                continue;
+        let embedded = manifest
+            .dependencies
+            .iter()
+            .map(|(table, line)| (*table, line.as_str()));
+
+        for (table, line) in directives.chain(embedded) {
+            // find the name of the dependency
+            let name = line.find('=').map(|i| line[0..i].trim());
+
+            // remove dependency with same name to avoid conflicts - user provided deps are overrides.
+            // Only of the table they're listed in, tests can use the normal dependencies as well
+            if let Some(name) = name {
+                let convert_case = |b| -> u8 {
+                    // only convert - to _ . Else, it's either _, or something we shouldn't filter
+                    if b == b'-' {
+                        b'_'
+                    } else {
+                        b
+                    }
+                };
+
+                // Compare crate names with - or _ being equal
+                let other = |p: &String| {
+                    !p.bytes()
+                        .map(convert_case)
+                        .eq(name.bytes().map(convert_case))
+                };
+
+                match table {
+                    Table::Dependencies => {
+                        deps.retain(other);
+                        dev_deps.retain(other);
+                    }
+                    Table::DevDependencies => dev_deps.retain(other),
+                    Table::BuildDependencies => (),
+                }
             }
 
-            break;
+            listed[table as usize].insert(0, line.to_string());
         }
     }
 
@@ -80,7 +117,7 @@ pub fn infer_deps(files: &[File]) -> Result<String, syn::Error> {
     // this is lazy initialized AND initialized only once to save performance
     static INDEX: OnceCell<Option<Arc<Mutex<Index>>>> = OnceCell::new();
 
-    for dep in deps.iter_mut().skip(added) {
+    for dep in deps.iter_mut().chain(dev_deps.iter_mut()) {
         if dep.contains('_') {
             // lazy initialize to save performance
             let index = INDEX.get_or_init(|| {
@@ -110,7 +147,36 @@ pub fn infer_deps(files: &[File]) -> Result<String, syn::Error> {
         dep.push_str(r#" = "*""#)
     }
 
-    Ok(deps.join("\n"))
+    let [dependencies, dev_dependencies, build_dependencies] = listed;
+    let table = |listed: Vec<String>, inferred: Vec<String>| {
+        listed
+            .into_iter()
+            .chain(inferred)
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    Ok(InferredDeps {
+        dependencies: table(dependencies, deps),
+        dev_dependencies: table(dev_dependencies, dev_deps),
+        build_dependencies: build_dependencies.join("\n"),
+    })
+}
+
+// items which are only compiled for tests, e.g. `#[cfg(test)] mod tests` or a `#[test]` function
+fn is_test_only(item: &Item) -> bool {
+    let attrs = match item {
+        Item::Mod(m) => &m.attrs,
+        Item::Fn(f) => &f.attrs,
+        Item::Use(u) => &u.attrs,
+        Item::Impl(i) => &i.attrs,
+        _ => return false,
+    };
+
+    attrs.iter().any(|attr| {
+        let tokens = attr.tokens.to_string();
+        attr.path.is_ident("test") || (attr.path.is_ident("cfg") && tokens == "(test)")
+    })
 }
 
 #[derive(Debug)]
@@ -292,7 +358,7 @@ mod tests {
             ];
 
             let result = infer_deps(files);
-            assert_eq!($result, result.unwrap());
+            assert_eq!($result, result.unwrap().dependencies);
         };
     }
 
@@ -307,16 +373,30 @@ baz = "*""#,
 
     #[test]
     fn infer_deps_custom_not_first_line() {
+        // comments may come first
         try_infer_deps!(
-            r#"baz_bar = "*""#,
+            r#"baz-bar = "1""#,
             (
                 "main",
                 r#"
-//# baz-bar = "*"
+//! Does baz things
+#![allow(unused)]
+//# baz-bar = "1"
 use baz_bar;
             "#
             )
         );
+
+        // but not code
+        try_infer_deps!(
+            r#"baz_bar = "*""#,
+            (
+                "main",
+                r#"use baz_bar;
+//# baz-bar = "1"
+            "#
+            )
+        );
     }
 
     #[test]
@@ -332,6 +412,39 @@ use baz_bar;
         );
     }
 
+    #[test]
+    fn infer_deps_script() {
+        // directives after a shebang, and the dependencies of the embedded manifest, override inferred ones
+        try_infer_deps!(
+            r#"serde = "1"
+rand = "*""#,
+            (
+                "main",
+                r#"#!/usr/bin/env rust-script
+//# serde = "1"
+use serde::Serialize;
+use rand::Rng;
+"#
+            )
+        );
+
+        try_infer_deps!(
+            r#"time = "0.1"
+rand = "*""#,
+            (
+                "main",
+                r#"#!/usr/bin/env rust-script
+//! ```cargo
+//! [dependencies]
+//! time = "0.1"
+//! ```
+use time::Duration;
+use rand::Rng;
+"#
+            )
+        );
+    }
+
     #[test]
     fn infer_deps_custom() {
         try_infer_deps!(
@@ -361,6 +474,59 @@ mod baz_bar {}
         );
     }
 
+    #[test]
+    fn infer_deps_dev_and_build() {
+        let code = r#"//#dev proptest = "1"
+//#build cc = "1"
+//# rand = "0.8"
+use serde;
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use quickcheck;
+    use serde;
+}
+
+#[test]
+fn check() {
+    use insta;
+}
+"#;
+
+        let inferred = infer_deps(&[File::new("main", code)]).unwrap();
+
+        assert_eq!(
+            inferred,
+            InferredDeps {
+                dependencies: "rand = \"0.8\"\nserde = \"*\"".to_string(),
+                dev_dependencies: "proptest = \"1\"\nquickcheck = \"*\"\ninsta = \"*\"".to_string(),
+                build_dependencies: "cc = \"1\"".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn infer_deps_override_own_table() {
+        let code = r#"//#dev serde = { version = "1", features = ["derive"] }
+//#build cc = "1"
+use serde;
+use cc;
+"#;
+
+        let inferred = infer_deps(&[File::new("main", code)]).unwrap();
+
+        assert_eq!(
+            inferred,
+            InferredDeps {
+                dependencies: "serde = \"*\"\ncc = \"*\"".to_string(),
+                dev_dependencies: "serde = { version = \"1\", features = [\"derive\"] }"
+                    .to_string(),
+                build_dependencies: "cc = \"1\"".to_string(),
+            }
+        );
+    }
+
     #[test]
     fn infer_deps_fix_package_by_index_lookup() {
         try_infer_deps!(
@@ -1,6 +1,48 @@
+mod bloat;
 mod cargo_command_builder;
+mod clippy;
+mod crate_source;
+mod directives;
+mod disk_usage;
+mod fuzz;
 mod infer;
+mod line_endings;
+mod lockfile;
+mod loom;
+mod messages;
+mod miri;
+mod naming;
+mod process;
 mod project;
 mod project_builder;
+mod proptest;
+mod runtime;
+mod sanitizer;
+mod script;
+mod sections;
+mod targets;
+mod wasm;
+mod workspace;
+mod wrap_main;
 
+pub use bloat::*;
+pub use clippy::*;
+pub use crate_source::*;
+pub use directives::*;
+pub use disk_usage::*;
+pub use fuzz::*;
+pub use line_endings::*;
+pub use lockfile::*;
+pub use loom::*;
+pub use messages::*;
+pub use miri::*;
+pub use naming::*;
+pub use process::*;
 pub use project::*;
+pub use proptest::*;
+pub use runtime::*;
+pub use sanitizer::*;
+pub use script::*;
+pub use sections::*;
+pub use wasm::*;
+pub use workspace::*;
@@ -0,0 +1,69 @@
+use std::borrow::Cow;
+
+/// The line endings a text uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    // both kinds appear
+    Mixed,
+}
+
+impl LineEnding {
+    /// None if the text has no line breaks at all
+    pub fn detect(text: &str) -> Option<Self> {
+        let lf = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+
+        match (lf, crlf) {
+            (0, _) => None,
+            (lf, crlf) if lf == crlf => Some(Self::Crlf),
+            (_, 0) => Some(Self::Lf),
+            _ => Some(Self::Mixed),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+            Self::Mixed => "Mixed",
+        }
+    }
+
+    /// Convert every line ending of the text to this one. Mixed leaves it as is
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Self::Lf if text.contains("\r\n") => Cow::Owned(text.replace("\r\n", "\n")),
+            Self::Crlf if Self::detect(text) != Some(Self::Crlf) => {
+                Cow::Owned(Self::Lf.apply(text).replace('\n', "\r\n"))
+            }
+            _ => Cow::Borrowed(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_line_endings() {
+        assert_eq!(LineEnding::detect("fn main() {}"), None);
+        assert_eq!(LineEnding::detect("a\nb\n"), Some(LineEnding::Lf));
+        assert_eq!(LineEnding::detect("a\r\nb\r\n"), Some(LineEnding::Crlf));
+        assert_eq!(LineEnding::detect("a\r\nb\n"), Some(LineEnding::Mixed));
+    }
+
+    #[test]
+    fn convert_line_endings() {
+        let mixed = "a\r\nb\nc";
+
+        assert_eq!(LineEnding::Lf.apply(mixed), "a\nb\nc");
+        assert_eq!(LineEnding::Crlf.apply(mixed), "a\r\nb\r\nc");
+        assert_eq!(LineEnding::Mixed.apply(mixed), mixed);
+
+        assert!(matches!(LineEnding::Lf.apply("a\nb"), Cow::Borrowed(_)));
+        assert!(matches!(LineEnding::Crlf.apply("a\r\nb"), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::directives::key_value;
+
+// the tables of a manifest whose dependencies are pinned
+const DEPENDENCY_TABLES: &[&str] = &[
+    "[dependencies]",
+    "[dev-dependencies]",
+    "[build-dependencies]",
+];
+
+// a package of a Cargo.lock
+#[derive(Debug, Default)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    // `name` or `name version` when the lock has more than one version of it
+    dependencies: Vec<String>,
+}
+
+/// The manifest with every dependency pinned to the version the lock resolved it to, e.g. `rand = "*"`
+/// becomes `rand = "=0.8.5"`, so the project builds with the versions it was tried with. Dependencies the
+/// lock doesn't have, or without a version like `path` and `git` ones, are left as they are
+pub fn pin_versions(manifest: &str, lock: &str) -> String {
+    let packages = locked_packages(lock);
+
+    let package_name = manifest
+        .lines()
+        .skip_while(|line| line.trim() != "[package]")
+        .find_map(|line| match key_value(line.trim())? {
+            ("name", value) => unquote(value),
+            _ => None,
+        });
+
+    let root = packages
+        .iter()
+        .find(|package| Some(package.name.as_str()) == package_name);
+
+    let mut pinned = String::with_capacity(manifest.len());
+    let mut in_dependencies = false;
+
+    for line in manifest.split_inclusive('\n') {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') {
+            in_dependencies = DEPENDENCY_TABLES.contains(&trimmed);
+        }
+
+        let pin = in_dependencies
+            .then(|| pin_line(line, &packages, root))
+            .flatten();
+
+        pinned.push_str(pin.as_deref().unwrap_or(line));
+    }
+
+    pinned
+}
+
+// the dependency line with its version pinned, None if it can't be
+fn pin_line(
+    line: &str,
+    packages: &[LockedPackage],
+    root: Option<&LockedPackage>,
+) -> Option<String> {
+    let (key, value) = key_value(line.trim())?;
+
+    // everything up to the value stays as it is, the keys may be padded to line up
+    let equals = line.find('=')? + 1;
+    let value_start = equals + line[equals..].len() - line[equals..].trim_start().len();
+
+    let (name, version_range) = if let Some(quoted) = value.strip_prefix('"') {
+        (key, 1..quoted.find('"')? + 1)
+    } else if value.starts_with('{') {
+        if ["path", "git"]
+            .iter()
+            .any(|source| inline_value(value, source).is_some())
+        {
+            return None;
+        }
+
+        let name = inline_value(value, "package")
+            .and_then(|range| value.get(range))
+            .unwrap_or(key);
+
+        (name, inline_value(value, "version")?)
+    } else {
+        return None;
+    };
+
+    let version = locked_version(packages, root, name)?;
+
+    let mut pinned = line[..value_start].to_string();
+    pinned.push_str(&value[..version_range.start]);
+    pinned.push('=');
+    pinned.push_str(version);
+    pinned.push_str(&value[version_range.end..]);
+
+    // the line ending after the value
+    pinned.push_str(&line[value_start + value.len()..]);
+
+    Some(pinned)
+}
+
+// the version of the dependency the project's own package depends on, or the only one in the lock
+fn locked_version<'a>(
+    packages: &'a [LockedPackage],
+    root: Option<&'a LockedPackage>,
+    name: &str,
+) -> Option<&'a str> {
+    // crate names may be written with - or _ either way
+    let same_name = |other: &str| other.replace('-', "_") == name.replace('-', "_");
+
+    let dependency = root.and_then(|root| {
+        root.dependencies
+            .iter()
+            .find(|dependency| dependency.split(' ').next().is_some_and(&same_name))
+    });
+
+    if let Some(version) = dependency.and_then(|dependency| dependency.split(' ').nth(1)) {
+        return Some(version);
+    }
+
+    let mut versions = packages
+        .iter()
+        .filter(|package| same_name(&package.name))
+        .map(|package| package.version.as_str());
+
+    let version = versions.next()?;
+
+    // which of several versions it is can't be told
+    versions.next().is_none().then_some(version)
+}
+
+fn locked_packages(lock: &str) -> Vec<LockedPackage> {
+    let mut packages = vec![];
+    let mut in_dependencies = false;
+
+    for line in lock.lines() {
+        let trimmed = line.trim();
+
+        if trimmed == "[[package]]" {
+            packages.push(LockedPackage::default());
+            in_dependencies = false;
+            continue;
+        }
+
+        let Some(package) = packages.last_mut() else {
+            continue;
+        };
+
+        if in_dependencies {
+            in_dependencies = !trimmed.starts_with(']');
+
+            if let Some(dependency) = unquote(trimmed.trim_end_matches(',')) {
+                package.dependencies.push(dependency.to_string());
+            }
+
+            continue;
+        }
+
+        match key_value(line.trim()) {
+            Some(("name", value)) => package.name = unquote(value).unwrap_or_default().to_string(),
+            Some(("version", value)) => {
+                package.version = unquote(value).unwrap_or_default().to_string()
+            }
+            Some(("dependencies", value)) => {
+                let list = value.trim_start_matches('[');
+
+                package.dependencies.extend(
+                    list.trim_end_matches(']')
+                        .split(',')
+                        .filter_map(|dependency| unquote(dependency.trim()))
+                        .map(str::to_string),
+                );
+
+                in_dependencies = !value.ends_with(']');
+            }
+            _ => (),
+        }
+    }
+
+    packages
+}
+
+// the byte range of the string an inline table gives the key, without its quotes
+fn inline_value(table: &str, key: &str) -> Option<std::ops::Range<usize>> {
+    let inner = table.trim_start_matches('{').trim_end_matches('}');
+    let offset = table.len() - table.trim_start_matches('{').len();
+
+    let mut start = 0;
+
+    for pair in inner.split(',') {
+        let pair_start = start;
+        start += pair.len() + 1;
+
+        let Some((pair_key, value)) = pair.split_once('=') else {
+            continue;
+        };
+
+        if pair_key.trim() != key {
+            continue;
+        }
+
+        let value = value.trim();
+        let unquoted = unquote(value)?;
+        let value_start = pair_start + pair.find(value)? + 1;
+
+        return Some(offset + value_start..offset + value_start + unquoted.len());
+    }
+
+    None
+}
+
+fn unquote(value: &str) -> Option<&str> {
+    value.strip_prefix('"')?.strip_suffix('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCK: &str = r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "my-project"
+version = "0.1.0"
+dependencies = [
+ "rand 0.8.5",
+ "serde_json",
+ "tokio",
+]
+
+[[package]]
+name = "rand"
+version = "0.7.3"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "serde_json"
+version = "1.0.91"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["ryu"]
+
+[[package]]
+name = "tokio"
+version = "1.25.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    #[test]
+    fn pinned() {
+        let manifest = "[package]\nname = \"my-project\"\nversion = \"0.1.0\"\n\n[dependencies]\n\
+                        rand       = \"*\"\n\
+                        serde-json = \"1\"\n\
+                        tokio      = { version = \"1\", features = [\"full\"] }\n\
+                        local      = { path = \"../local\" }\n\
+                        missing    = \"0.1\"\n\n\
+                        [profile.dev]\nopt-level = 1\n";
+
+        assert_eq!(
+            pin_versions(manifest, LOCK),
+            "[package]\nname = \"my-project\"\nversion = \"0.1.0\"\n\n[dependencies]\n\
+             rand       = \"=0.8.5\"\n\
+             serde-json = \"=1.0.91\"\n\
+             tokio      = { version = \"=1.25.0\", features = [\"full\"] }\n\
+             local      = { path = \"../local\" }\n\
+             missing    = \"0.1\"\n\n\
+             [profile.dev]\nopt-level = 1\n"
+        );
+    }
+
+    #[test]
+    fn renamed() {
+        let manifest = "[package]\nname = \"my-project\"\n\n[dev-dependencies]\n\
+                        json = { package = \"serde_json\", version = \"1\" }\n";
+
+        assert_eq!(
+            pin_versions(manifest, LOCK),
+            "[package]\nname = \"my-project\"\n\n[dev-dependencies]\n\
+             json = { package = \"serde_json\", version = \"=1.0.91\" }\n"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{BuildType, Project, Subcommand};
+
+/// The loom version added as a dev-dependency by [`Project::loom`]
+pub const LOOM_DEPENDENCY: &str = r#"loom = "0.5""#;
+// lets the scratch switch between loom and std types with #[cfg(loom)]
+pub(crate) const LOOM_CFG: &str = "--cfg loom";
+// the test the harness adds
+const HARNESS_TEST: &str = "loom_harness::model";
+
+// appended to the main file, runs main for every interleaving loom explores
+pub(crate) const LOOM_HARNESS: &str = r#"
+#[cfg(test)]
+mod loom_harness {
+    #[test]
+    fn model() {
+        loom::model(|| {
+            super::main();
+        });
+    }
+}
+"#;
+
+impl<'a> Project<'a> {
+    /// Model check the scratch with loom. Main is wrapped in a test which runs it for every
+    /// interleaving of its threads, so it must use loom's sync types.
+    /// `max_preemptions` bounds the search, which is otherwise exhaustive and can take very long
+    pub fn loom(&mut self, max_preemptions: Option<&'a str>) -> &mut Self {
+        self.loom = true;
+
+        // loom is slow without optimizations. Its output is only seen with --nocapture
+        self.subcommand(Subcommand::Test)
+            .build_type(BuildType::Release)
+            .dev_dependency(LOOM_DEPENDENCY)
+            .dash_arg("--nocapture");
+
+        if let Some(max_preemptions) = max_preemptions {
+            self.env_var("LOOM_MAX_PREEMPTIONS", max_preemptions);
+        }
+
+        self
+    }
+}
+
+/// Outcome of a loom run, read from the output of `cargo test`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoomSummary {
+    /// How many interleavings were explored. Unknown if the model failed
+    pub iterations: Option<usize>,
+    /// None while the test is still running
+    pub passed: Option<bool>,
+}
+
+/// Summarize the stdout of a loom run. None if it wasn't one
+pub fn loom_summary(stdout: &str) -> Option<LoomSummary> {
+    if !stdout.contains(HARNESS_TEST) {
+        return None;
+    }
+
+    let mut summary = LoomSummary {
+        iterations: None,
+        passed: None,
+    };
+
+    for line in stdout.lines() {
+        // loom prints "Completed in N iterations" once every interleaving passed
+        if let Some(rest) = line.split("Completed in ").nth(1) {
+            summary.iterations = rest
+                .trim_end_matches(" iterations")
+                .trim()
+                .parse()
+                .ok()
+                .or(summary.iterations);
+        }
+
+        if let Some(result) = line.strip_prefix("test result: ") {
+            summary.passed = Some(result.starts_with("ok"));
+        }
+    }
+
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loom_project() {
+        let mut project = Project::new("loom");
+        project.loom(Some("3"));
+
+        assert_eq!(project.dev_dependencies, vec![LOOM_DEPENDENCY]);
+        assert_eq!(project.combined_rust_flags().as_deref(), Some(LOOM_CFG));
+        assert!(project.env.contains(&("LOOM_MAX_PREEMPTIONS", "3")));
+
+        // the user's own flags are kept
+        project.rust_flags("-Copt-level=1");
+        assert_eq!(
+            project.combined_rust_flags().as_deref(),
+            Some("-Copt-level=1 --cfg loom")
+        );
+    }
+
+    #[test]
+    fn summarize_output() {
+        let passed = "\
+running 1 test
+test loom_harness::model ... Completed in 42 iterations
+ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out
+";
+
+        assert_eq!(
+            loom_summary(passed),
+            Some(LoomSummary {
+                iterations: Some(42),
+                passed: Some(true)
+            })
+        );
+
+        let failed = "\
+running 1 test
+test loom_harness::model ... FAILED
+
+test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+";
+
+        assert_eq!(
+            loom_summary(failed),
+            Some(LoomSummary {
+                iterations: None,
+                passed: Some(false)
+            })
+        );
+
+        assert_eq!(loom_summary("Hello, world!\n"), None);
+    }
+}
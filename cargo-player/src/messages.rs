@@ -0,0 +1,373 @@
+use std::io::BufRead;
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use crate::Project;
+
+/// Makes cargo print its messages as json lines. Diagnostics keep their colored rendering
+pub const MESSAGE_FORMAT_JSON: &str = "--message-format=json-diagnostic-rendered-ansi";
+
+impl<'a> Project<'a> {
+    /// Have cargo report its progress as json messages on stdout, see [`Message`]
+    pub fn message_format_json(&mut self) -> &mut Self {
+        self.subcommand_flag(MESSAGE_FORMAT_JSON)
+    }
+}
+
+/// A line of cargo's stdout in json message format
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    CompilerArtifact(Artifact),
+    CompilerMessage(Diagnostic),
+    BuildFinished {
+        success: bool,
+    },
+    /// Anything else cargo reports, e.g. build-script-executed. Holds the reason
+    Other(String),
+    /// Not a cargo message, e.g. output of the program itself
+    Text(String),
+}
+
+/// A crate which finished compiling
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+    pub package_id: String,
+    pub target_name: String,
+    // e.g. bin, lib or proc-macro
+    pub target_kind: Vec<String>,
+    pub filenames: Vec<PathBuf>,
+    pub executable: Option<PathBuf>,
+    // whether it was up to date already
+    pub fresh: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+    Help,
+    FailureNote,
+    InternalCompilerError,
+    Other,
+}
+
+impl From<&str> for Level {
+    fn from(level: &str) -> Self {
+        match level {
+            "error" => Self::Error,
+            "warning" => Self::Warning,
+            "note" => Self::Note,
+            "help" => Self::Help,
+            "failure-note" => Self::FailureNote,
+            "error: internal compiler error" => Self::InternalCompilerError,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// A warning or error emitted by the compiler
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub package_id: String,
+    pub level: Level,
+    pub message: String,
+    // e.g. E0308
+    pub code: Option<String>,
+    pub spans: Vec<Span>,
+    // how rustc prints it, including colors
+    pub rendered: Option<String>,
+    // notes and help attached to the diagnostic
+    pub children: Vec<Diagnostic>,
+}
+
+/// Location a diagnostic points at. Lines and columns start at 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub file_name: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+    pub is_primary: bool,
+    pub label: Option<String>,
+}
+
+impl Message {
+    /// Parse one line of stdout. Lines which aren't cargo messages become [`Message::Text`]
+    pub fn parse(line: &str) -> Self {
+        let text = || Self::Text(line.to_string());
+
+        if !line.trim_start().starts_with('{') {
+            return text();
+        }
+
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            return text();
+        };
+
+        let Some(reason) = value.get("reason").and_then(Value::as_str) else {
+            return text();
+        };
+
+        match reason {
+            "compiler-artifact" => Self::CompilerArtifact(Artifact::from_value(&value)),
+
+            "compiler-message" => {
+                let package_id = string(&value, "package_id").unwrap_or_default();
+
+                match value.get("message") {
+                    Some(message) => {
+                        Self::CompilerMessage(Diagnostic::from_value(message, &package_id))
+                    }
+                    None => text(),
+                }
+            }
+
+            "build-finished" => Self::BuildFinished {
+                success: value
+                    .get("success")
+                    .and_then(Value::as_bool)
+                    .unwrap_or_default(),
+            },
+
+            reason => Self::Other(reason.to_string()),
+        }
+    }
+}
+
+impl Artifact {
+    fn from_value(value: &Value) -> Self {
+        let target = value.get("target");
+
+        Self {
+            package_id: string(value, "package_id").unwrap_or_default(),
+            target_name: target
+                .and_then(|target| string(target, "name"))
+                .unwrap_or_default(),
+            target_kind: target
+                .and_then(|target| target.get("kind"))
+                .map(strings)
+                .unwrap_or_default(),
+            filenames: value
+                .get("filenames")
+                .map(strings)
+                .unwrap_or_default()
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+            executable: string(value, "executable").map(PathBuf::from),
+            fresh: value
+                .get("fresh")
+                .and_then(Value::as_bool)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Diagnostic {
+    fn from_value(value: &Value, package_id: &str) -> Self {
+        Self {
+            package_id: package_id.to_string(),
+            level: string(value, "level").as_deref().unwrap_or_default().into(),
+            message: string(value, "message").unwrap_or_default(),
+            code: value.get("code").and_then(|code| string(code, "code")),
+            spans: value
+                .get("spans")
+                .and_then(Value::as_array)
+                .map(|spans| spans.iter().map(Span::from_value).collect())
+                .unwrap_or_default(),
+            rendered: string(value, "rendered"),
+            children: value
+                .get("children")
+                .and_then(Value::as_array)
+                .map(|children| {
+                    children
+                        .iter()
+                        .map(|child| Self::from_value(child, package_id))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Span {
+    fn from_value(value: &Value) -> Self {
+        let number = |key| value.get(key).and_then(Value::as_u64).unwrap_or_default() as usize;
+
+        Self {
+            file_name: string(value, "file_name").unwrap_or_default(),
+            line_start: number("line_start"),
+            line_end: number("line_end"),
+            column_start: number("column_start"),
+            column_end: number("column_end"),
+            is_primary: value
+                .get("is_primary")
+                .and_then(Value::as_bool)
+                .unwrap_or_default(),
+            label: string(value, "label"),
+        }
+    }
+
+    /// Whether the span covers a line and column. The end column is exclusive, a span
+    /// without width still covers the column it points at
+    pub fn contains(&self, line: usize, column: usize) -> bool {
+        if line < self.line_start || line > self.line_end {
+            return false;
+        }
+
+        let after_start = line > self.line_start || column >= self.column_start;
+        let before_end = line < self.line_end
+            || column < self.column_end
+            || (self.line_start == self.line_end && column == self.column_start);
+
+        after_start && before_end
+    }
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key)?.as_str().map(ToString::to_string)
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse cargo's stdout line by line as it comes in, e.g. from a child's piped stdout
+pub fn messages(reader: impl BufRead) -> impl Iterator<Item = Message> {
+    reader
+        .lines()
+        .map_while(Result::ok)
+        .map(|line| Message::parse(&line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_artifact() {
+        let line = r#"{"reason":"compiler-artifact","package_id":"p 0.1.0 (path+file:///tmp/p)","target":{"kind":["bin"],"crate_types":["bin"],"name":"p","src_path":"/tmp/p/src/main.rs"},"profile":{},"features":[],"filenames":["/tmp/p/target/debug/p"],"executable":"/tmp/p/target/debug/p","fresh":false}"#;
+
+        assert_eq!(
+            Message::parse(line),
+            Message::CompilerArtifact(Artifact {
+                package_id: "p 0.1.0 (path+file:///tmp/p)".to_string(),
+                target_name: "p".to_string(),
+                target_kind: vec!["bin".to_string()],
+                filenames: vec![PathBuf::from("/tmp/p/target/debug/p")],
+                executable: Some(PathBuf::from("/tmp/p/target/debug/p")),
+                fresh: false,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_compiler_message() {
+        let line = r#"{"reason":"compiler-message","package_id":"p 0.1.0","target":{},"message":{"message":"unused variable: `x`","code":{"code":"unused_variables","explanation":null},"level":"warning","spans":[{"file_name":"src/main.rs","byte_start":16,"byte_end":17,"line_start":2,"line_end":2,"column_start":9,"column_end":10,"is_primary":true,"text":[],"label":null,"suggested_replacement":null,"suggestion_applicability":null,"expansion":null}],"children":[{"message":"`#[warn(unused_variables)]` on by default","code":null,"level":"note","spans":[],"children":[],"rendered":null}],"rendered":"warning: unused variable: `x`\n"}}"#;
+
+        let Message::CompilerMessage(diagnostic) = Message::parse(line) else {
+            panic!("expected a compiler message");
+        };
+
+        assert_eq!(diagnostic.level, Level::Warning);
+        assert_eq!(diagnostic.message, "unused variable: `x`");
+        assert_eq!(diagnostic.code.as_deref(), Some("unused_variables"));
+        assert_eq!(
+            diagnostic.spans,
+            vec![Span {
+                file_name: "src/main.rs".to_string(),
+                line_start: 2,
+                line_end: 2,
+                column_start: 9,
+                column_end: 10,
+                is_primary: true,
+                label: None,
+            }]
+        );
+        assert_eq!(diagnostic.children.len(), 1);
+        assert_eq!(diagnostic.children[0].level, Level::Note);
+        assert_eq!(
+            diagnostic.rendered.as_deref(),
+            Some("warning: unused variable: `x`\n")
+        );
+    }
+
+    #[test]
+    fn parse_other_lines() {
+        assert_eq!(
+            Message::parse(r#"{"reason":"build-finished","success":true}"#),
+            Message::BuildFinished { success: true }
+        );
+        assert_eq!(
+            Message::parse(r#"{"reason":"build-script-executed","package_id":"p"}"#),
+            Message::Other("build-script-executed".to_string())
+        );
+
+        // program output is passed through as is
+        assert_eq!(
+            Message::parse("Hello, world!"),
+            Message::Text("Hello, world!".to_string())
+        );
+        assert_eq!(
+            Message::parse("{ not json"),
+            Message::Text("{ not json".to_string())
+        );
+    }
+
+    #[test]
+    fn span_contains() {
+        let span = Span {
+            file_name: "src/main.rs".to_string(),
+            line_start: 2,
+            line_end: 3,
+            column_start: 9,
+            column_end: 4,
+            is_primary: true,
+            label: None,
+        };
+
+        assert!(span.contains(2, 9));
+        assert!(span.contains(2, 80));
+        assert!(span.contains(3, 3));
+        assert!(!span.contains(2, 8));
+        assert!(!span.contains(3, 4));
+        assert!(!span.contains(1, 9));
+
+        // e.g. a missing semicolon
+        let empty = Span {
+            line_end: 2,
+            column_end: 9,
+            ..span
+        };
+
+        assert!(empty.contains(2, 9));
+        assert!(!empty.contains(2, 10));
+    }
+
+    #[test]
+    fn stream_messages() {
+        let stdout = "{\"reason\":\"build-finished\",\"success\":false}\nsome output\n";
+
+        let messages = messages(stdout.as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                Message::BuildFinished { success: false },
+                Message::Text("some output".to_string())
+            ]
+        );
+    }
+}
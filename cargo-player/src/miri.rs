@@ -0,0 +1,206 @@
+use crate::{Channel, Diagnostic, Level, Project, Span, Subcommand};
+
+impl<'a> Project<'a> {
+    /// Run the scratch in Miri on nightly, which reports undefined behavior as it interprets it.
+    /// `flags` are passed in MIRIFLAGS, e.g. `-Zmiri-disable-isolation`
+    pub fn miri(&mut self, flags: Option<&'a str>) -> &mut Self {
+        self.channel(Channel::Nightly)
+            .subcommand(Subcommand::Miri)
+            .subcommand_flag("run");
+
+        if let Some(flags) = flags {
+            self.env_var("MIRIFLAGS", flags);
+        }
+
+        self
+    }
+}
+
+/// The errors and warnings in the stderr of a Miri run, e.g. the undefined behavior it found. Compile errors
+/// are among them, those without a location aren't, like the count of errors at the end.
+/// The output must be without colors
+pub fn miri_diagnostics(stderr: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut current: Option<Diagnostic> = None;
+
+    // a diagnostic ends at an empty line, or where the next one starts
+    let mut finish = |current: &mut Option<Diagnostic>| {
+        diagnostics.extend(
+            current
+                .take()
+                .filter(|diagnostic| !diagnostic.spans.is_empty()),
+        );
+    };
+
+    for line in stderr.lines() {
+        if let Some(diagnostic) = header(line) {
+            finish(&mut current);
+            current = Some(diagnostic);
+            continue;
+        }
+
+        let Some(diagnostic) = &mut current else {
+            continue;
+        };
+
+        if line.trim().is_empty() {
+            finish(&mut current);
+            continue;
+        }
+
+        if let Some(rendered) = &mut diagnostic.rendered {
+            rendered.push_str(line);
+            rendered.push('\n');
+        }
+
+        let trimmed = line.trim_start();
+
+        if let Some(location) = trimmed.strip_prefix("--> ") {
+            let is_primary = diagnostic.spans.is_empty();
+            diagnostic.spans.extend(span(location.trim(), is_primary));
+        } else if let Some(child) = trimmed.strip_prefix("= ").and_then(header) {
+            diagnostic.children.push(child);
+        } else if let Some(span) = diagnostic.spans.last_mut() {
+            mark(line, span);
+        }
+    }
+
+    finish(&mut current);
+
+    diagnostics
+}
+
+// e.g. "error: Undefined Behavior: ..." or "warning[E0133]: ..."
+fn header(line: &str) -> Option<Diagnostic> {
+    let (level, message) = line.split_once(": ")?;
+
+    let (level, code) = match level.split_once('[') {
+        Some((level, code)) => (level, Some(code.trim_end_matches(']').to_string())),
+        None => (level, None),
+    };
+
+    let level = Level::from(level);
+    if level == Level::Other {
+        return None;
+    }
+
+    Some(Diagnostic {
+        package_id: String::new(),
+        level,
+        message: message.trim().to_string(),
+        code,
+        spans: vec![],
+        rendered: Some(format!("{line}\n")),
+        children: vec![],
+    })
+}
+
+// e.g. "src/main.rs:5:14"
+fn span(location: &str, is_primary: bool) -> Option<Span> {
+    let mut parts = location.rsplitn(3, ':');
+
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let file_name = parts.next()?.to_string();
+
+    Some(Span {
+        file_name,
+        line_start: line,
+        line_end: line,
+        column_start: column,
+        column_end: column,
+        is_primary,
+        label: None,
+    })
+}
+
+// The carets under the code the span points at, and the label after them, e.g. "  |     ^^ here"
+fn mark(line: &str, span: &mut Span) {
+    let Some((gutter, marked)) = line.split_once('|') else {
+        return;
+    };
+
+    // the line of code itself has its number in the gutter
+    if !gutter.trim().is_empty() || span.label.is_some() {
+        return;
+    }
+
+    let marked = marked.trim_start();
+    let carets = marked.chars().take_while(|&c| c == '^').count();
+    if carets == 0 {
+        return;
+    }
+
+    span.column_end = span.column_start + carets;
+    span.label = Some(marked[carets..].trim().to_string()).filter(|label| !label.is_empty());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = r#"   Compiling scratch v0.1.0 (/tmp/scratch)
+    Finished dev [unoptimized + debuginfo] target(s) in 0.30s
+     Running `/home/me/.rustup/toolchains/nightly/bin/cargo-miri runner target/miri/debug/scratch`
+error: Undefined Behavior: dereferencing pointer failed: alloc1 has been freed, so this pointer is dangling
+ --> src/main.rs:5:14
+  |
+5 |     unsafe { *p };
+  |              ^^ dereferencing pointer failed: alloc1 has been freed
+  |
+  = help: this indicates a bug in the program: it performed an invalid operation, and caused Undefined Behavior
+  = note: BACKTRACE:
+  = note: inside `main` at src/main.rs:5:14: 5:16
+
+note: some details are omitted, run with `MIRIFLAGS=-Zmiri-backtrace=full` for a verbose backtrace
+
+error: aborting due to previous error
+"#;
+
+    #[test]
+    fn miri_flags() {
+        let mut project = Project::new("miri");
+        project.miri(Some("-Zmiri-disable-isolation"));
+
+        assert!(project
+            .env
+            .contains(&("MIRIFLAGS", "-Zmiri-disable-isolation")));
+    }
+
+    #[test]
+    fn undefined_behavior() {
+        let diagnostics = miri_diagnostics(OUTPUT);
+        assert_eq!(diagnostics.len(), 1);
+
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.level, Level::Error);
+        assert!(diagnostic
+            .message
+            .starts_with("Undefined Behavior: dereferencing"));
+        assert_eq!(diagnostic.children.len(), 3);
+        assert_eq!(diagnostic.children[0].level, Level::Help);
+
+        assert_eq!(
+            diagnostic.spans,
+            vec![Span {
+                file_name: "src/main.rs".to_string(),
+                line_start: 5,
+                line_end: 5,
+                column_start: 14,
+                column_end: 16,
+                is_primary: true,
+                label: Some("dereferencing pointer failed: alloc1 has been freed".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn compile_errors_with_codes() {
+        let output = "warning[E0133]: use of mutable static is unsafe\n --> src/main.rs:3:5\n";
+        let diagnostics = miri_diagnostics(output);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].level, Level::Warning);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("E0133"));
+    }
+}
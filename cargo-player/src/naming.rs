@@ -0,0 +1,151 @@
+use syn::{parse_file, Attribute, Item, Lit, Meta, UseTree};
+
+use crate::directives::{read_directives, DirectiveLine};
+use crate::infer::USE_KEYWORDS;
+
+// longer names are cut off at a word
+const MAX_NAME_LEN: usize = 40;
+
+/// A name for a scratch from what its code does: the first crate it uses, and the doc comment of `main`
+/// or else the first thing it defines besides `main`. None if the code doesn't tell much, e.g. only a
+/// `main` using std
+pub fn suggest_name(code: &str) -> Option<String> {
+    let file = parse_file(code).ok()?;
+
+    let krate = directive_crate(code).or_else(|| {
+        file.items.iter().find_map(|item| match item {
+            Item::Use(item) => use_crate(&item.tree),
+            Item::ExternCrate(item) => Some(item.ident.to_string()),
+            _ => None,
+        })
+    });
+
+    let main = file.items.iter().find_map(|item| match item {
+        Item::Fn(item) if item.sig.ident == "main" => Some(item),
+        _ => None,
+    });
+
+    let summary = main
+        .and_then(|main| doc_summary(&main.attrs))
+        .or_else(|| doc_summary(&file.attrs))
+        .or_else(|| file.items.iter().find_map(defined_name));
+
+    let name = match (krate, summary) {
+        (Some(krate), Some(summary)) => format!("{krate}: {summary}"),
+        (Some(name), None) | (None, Some(name)) => name,
+        (None, None) => return None,
+    };
+
+    Some(shortened(&name))
+}
+
+// the first `//#` dependency at the top of the code
+fn directive_crate(code: &str) -> Option<String> {
+    let line = read_directives(code).find_map(|directive| match directive {
+        DirectiveLine::Dependency(_, dependency) => Some(dependency),
+        DirectiveLine::CargoToml(_) => None,
+    })?;
+    let (name, _) = line.split_once('=')?;
+
+    Some(name.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+fn use_crate(tree: &UseTree) -> Option<String> {
+    let ident = match tree {
+        UseTree::Path(path) => &path.ident,
+        UseTree::Name(name) => &name.ident,
+        UseTree::Rename(rename) => &rename.ident,
+        UseTree::Group(group) => return group.items.iter().find_map(use_crate),
+        UseTree::Glob(_) => return None,
+    };
+
+    let ident = ident.to_string();
+
+    (!USE_KEYWORDS.contains(&ident.as_str())).then_some(ident)
+}
+
+// the first sentence of a doc comment
+fn doc_summary(attrs: &[Attribute]) -> Option<String> {
+    let line = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(doc) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|doc| doc.trim().to_string())
+        .find(|doc| !doc.is_empty())?;
+
+    let sentence = line.split(". ").next().unwrap_or(&line);
+
+    Some(sentence.trim_end_matches('.').to_string())
+}
+
+// functions read as words, types as they are
+fn defined_name(item: &Item) -> Option<String> {
+    match item {
+        Item::Fn(item) if item.sig.ident != "main" => {
+            Some(item.sig.ident.to_string().replace('_', " "))
+        }
+        Item::Struct(item) => Some(item.ident.to_string()),
+        Item::Enum(item) => Some(item.ident.to_string()),
+        Item::Trait(item) => Some(item.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn shortened(name: &str) -> String {
+    if name.chars().count() <= MAX_NAME_LEN {
+        return name.to_string();
+    }
+
+    let cut = name.chars().take(MAX_NAME_LEN).collect::<String>();
+    let cut = cut
+        .rsplit_once(' ')
+        .map_or(cut.as_str(), |(words, _)| words);
+
+    format!("{}…", cut.trim_end_matches([' ', ',', ':']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crate_and_main_doc() {
+        let code = r#"use std::io;
+use rand::Rng;
+
+/// Roll a die. Then print it
+fn main() {}
+"#;
+
+        assert_eq!(suggest_name(code).unwrap(), "rand: Roll a die");
+    }
+
+    #[test]
+    fn directive_and_defined_function() {
+        let code = "//# serde_json = \"1\"\nfn parse_args() {}\nfn main() {}";
+
+        assert_eq!(suggest_name(code).unwrap(), "serde_json: parse args");
+    }
+
+    #[test]
+    fn nothing_to_go_by() {
+        assert_eq!(suggest_name("use std::fmt;\nfn main() {}"), None);
+        assert_eq!(suggest_name("fn main() {"), None);
+    }
+
+    #[test]
+    fn long_names_are_shortened() {
+        let code = "//! Count the words of every line read from stdin, then print the totals\nfn main() {}";
+
+        assert_eq!(
+            suggest_name(code).unwrap(),
+            "Count the words of every line read from…"
+        );
+    }
+}
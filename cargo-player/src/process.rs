@@ -0,0 +1,307 @@
+use std::borrow::Cow;
+use std::io::{self, BufReader, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{Project, ProjectError};
+
+// how often the exit status is checked once the output is closed
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl<'a> Project<'a> {
+    /// Create the project and spawn its command, streaming the output line by line
+    pub fn run(&mut self) -> Result<Process, ProjectError> {
+        let command = self.create()?;
+
+        Process::spawn(command).map_err(ProjectError::Spawn)
+    }
+}
+
+/// A line of output including its line ending. Lines are also split on `\r`, which
+/// programs print to redraw the current line, e.g. for progress bars
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line(Vec<u8>);
+
+impl Line {
+    /// The raw bytes, which aren't necessarily valid utf-8
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+
+    /// Whether the line ends in `\r`, i.e. it's going to be overwritten by the next one
+    pub fn is_dynamic(&self) -> bool {
+        self.0.ends_with(b"\r")
+    }
+}
+
+/// Kills the process from anywhere, e.g. another thread. Whatever it started goes with it,
+/// e.g. the rustc and linker processes of a cargo build
+#[derive(Debug, Clone)]
+pub struct Killer {
+    child: Arc<Mutex<Child>>,
+    tree: Arc<ProcessTree>,
+}
+
+impl Killer {
+    pub fn kill(&self) -> io::Result<()> {
+        self.tree.kill(&mut self.child.lock().unwrap())
+    }
+}
+
+/// A running process with its output streamed line by line.
+/// The fields can be taken apart to hand them to different threads
+#[derive(Debug)]
+pub struct Process {
+    /// Closed once the process exited
+    pub stdout: Receiver<Line>,
+    /// Closed once the process exited
+    pub stderr: Receiver<Line>,
+    /// Receives the exit status once the process exited and all of its output was read
+    pub status: Receiver<io::Result<ExitStatus>>,
+    pub killer: Killer,
+}
+
+impl Process {
+    /// Spawn the command with its stdout and stderr piped
+    pub fn spawn(mut command: Command) -> io::Result<Self> {
+        ProcessTree::prepare(&mut command);
+
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let (stdout_tx, stdout) = channel();
+        let (stderr_tx, stderr) = channel();
+        let (status_tx, status) = channel();
+
+        let stdout_reader = read_lines(child.stdout.take().unwrap(), stdout_tx);
+        let stderr_reader = read_lines(child.stderr.take().unwrap(), stderr_tx);
+
+        let killer = Killer {
+            tree: Arc::new(ProcessTree::new(&child)),
+            child: Arc::new(Mutex::new(child)),
+        };
+
+        let child = killer.child.clone();
+        thread::spawn(move || {
+            let _ = stdout_reader.join();
+            let _ = stderr_reader.join();
+
+            // the lock mustn't be held while blocking, or the process couldn't be killed anymore
+            let status = loop {
+                match child.lock().unwrap().try_wait() {
+                    Ok(Some(status)) => break Ok(status),
+                    Ok(None) => (),
+                    Err(e) => break Err(e),
+                }
+
+                thread::sleep(POLL_INTERVAL);
+            };
+
+            let _ = status_tx.send(status);
+        });
+
+        Ok(Self {
+            stdout,
+            stderr,
+            status,
+            killer,
+        })
+    }
+
+    pub fn kill(&self) -> io::Result<()> {
+        self.killer.kill()
+    }
+
+    pub fn killer(&self) -> Killer {
+        self.killer.clone()
+    }
+
+    /// Block until the process exited and all of its output was read
+    pub fn wait(&self) -> io::Result<ExitStatus> {
+        self.status
+            .recv()
+            .unwrap_or_else(|_| Err(io::Error::other("process lost")))
+    }
+
+    /// The exit status, if the process exited and all of its output was read
+    pub fn try_wait(&self) -> Option<io::Result<ExitStatus>> {
+        match self.status.try_recv() {
+            Ok(status) => Some(status),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(io::Error::other("process lost"))),
+        }
+    }
+}
+
+/// The process and all of its descendants, in its own process group
+#[cfg(unix)]
+#[derive(Debug)]
+struct ProcessTree {
+    // the id of the group is the one of the process
+    group: libc::pid_t,
+}
+
+#[cfg(unix)]
+impl ProcessTree {
+    fn prepare(command: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+    }
+
+    fn new(child: &Child) -> Self {
+        Self {
+            group: child.id() as libc::pid_t,
+        }
+    }
+
+    fn kill(&self, _child: &mut Child) -> io::Result<()> {
+        // SAFETY: only sends a signal
+        if unsafe { libc::killpg(self.group, libc::SIGKILL) } == 0 {
+            return Ok(());
+        }
+
+        match io::Error::last_os_error() {
+            // everyone in it already exited
+            e if e.raw_os_error() == Some(libc::ESRCH) => Ok(()),
+            e => Err(e),
+        }
+    }
+}
+
+/// The process and all of its descendants, in a job object
+#[cfg(windows)]
+#[derive(Debug)]
+struct ProcessTree {
+    // None if the process couldn't be assigned to one, then only the process itself is killed
+    job: Option<windows::Win32::Foundation::HANDLE>,
+}
+
+#[cfg(windows)]
+impl ProcessTree {
+    fn prepare(_command: &mut Command) {}
+
+    // The process could start others before it's assigned, those escape the job. Cargo reads
+    // the manifest and the index first, so its rustc processes are started well after
+    fn new(child: &Child) -> Self {
+        use std::os::windows::io::AsRawHandle;
+        use windows::core::PCWSTR;
+        use windows::Win32::Foundation::{CloseHandle, HANDLE};
+        use windows::Win32::System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW};
+
+        // SAFETY: the handle of the child stays valid while it's borrowed
+        let job = unsafe {
+            CreateJobObjectW(None, PCWSTR::null()).ok().filter(|&job| {
+                let process = HANDLE(child.as_raw_handle() as isize);
+                let assigned = AssignProcessToJobObject(job, process).as_bool();
+
+                if !assigned {
+                    CloseHandle(job);
+                }
+
+                assigned
+            })
+        };
+
+        Self { job }
+    }
+
+    fn kill(&self, child: &mut Child) -> io::Result<()> {
+        use windows::Win32::System::JobObjects::TerminateJobObject;
+
+        let Some(job) = self.job else {
+            return child.kill();
+        };
+
+        // SAFETY: the job is only closed once this is dropped
+        unsafe { TerminateJobObject(job, 1) }.ok()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        if let Some(job) = self.job {
+            // SAFETY: nothing uses the job anymore. Processes still running are left alone
+            unsafe { windows::Win32::Foundation::CloseHandle(job) };
+        }
+    }
+}
+
+fn read_lines(reader: impl Read + Send + 'static, tx: Sender<Line>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut line = vec![];
+
+        for byte in BufReader::new(reader).bytes() {
+            let Ok(byte) = byte else {
+                break;
+            };
+
+            line.push(byte);
+
+            if byte == b'\n' || byte == b'\r' {
+                let _ = tx.send(Line(std::mem::take(&mut line)));
+            }
+        }
+
+        // the last line may not have had a line ending
+        if !line.is_empty() {
+            let _ = tx.send(Line(line));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn stream_lines() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "printf 'out\\nprogress\\rdone'; printf 'err\\n' >&2"]);
+
+        let process = Process::spawn(command).unwrap();
+        let stdout = process.stdout.iter().collect::<Vec<_>>();
+        let stderr = process.stderr.iter().collect::<Vec<_>>();
+
+        assert!(process.wait().unwrap().success());
+        assert_eq!(
+            stdout,
+            vec![
+                Line(b"out\n".to_vec()),
+                Line(b"progress\r".to_vec()),
+                Line(b"done".to_vec())
+            ]
+        );
+        assert!(stdout[1].is_dynamic());
+        assert_eq!(stderr, vec![Line(b"err\n".to_vec())]);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn kill_descendants() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30 & sleep 30"]);
+
+        let process = Process::spawn(command).unwrap();
+        let started = std::time::Instant::now();
+
+        // the background sleep holds on to the output, which is only closed once it's killed as well
+        process.kill().unwrap();
+
+        assert!(!process.wait().unwrap().success());
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
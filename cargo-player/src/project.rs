@@ -1,5 +1,6 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::Once;
@@ -7,7 +8,12 @@ use strum_macros::{Display, IntoStaticStr};
 use thiserror::Error;
 
 use crate::cargo_command_builder::CargoCommandBuilder;
-use crate::project_builder::{ProjectBuildError, ProjectBuilder};
+use crate::project_builder::ProjectBuilder;
+use crate::targets::select_target;
+use crate::{projects_dir, AsyncRuntime, Crate, FuzzFn, Sanitizer, LOOM_CFG};
+
+// the folders cargo puts next to the binaries in target/debug, cargo refuses binaries named like them
+const RESERVED_NAMES: [&str; 4] = ["deps", "build", "examples", "incremental"];
 
 #[derive(Debug, Clone, Copy, Default, IntoStaticStr, PartialEq, Display)]
 pub enum Edition {
@@ -50,9 +56,25 @@ pub enum Subcommand {
     // Run code formatter
     #[strum(to_string = "fmt")]
     Rustfmt,
+    // Fuzz a function - requires cargo-fuzz command be installed
+    #[strum(to_string = "fuzz")]
+    Fuzz,
+    // Measure the binary size - requires cargo-bloat command be installed
+    #[strum(to_string = "bloat")]
+    Bloat,
+    // List the packages of the project as json
+    #[strum(to_string = "metadata")]
+    Metadata,
+    // Run a benchmark target
+    #[strum(to_string = "bench")]
+    Bench,
+    // Print the dependency graph of the project
+    #[strum(to_string = "tree")]
+    Tree,
 }
 
 #[derive(Debug, Clone, Copy, Default, IntoStaticStr, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Channel {
     #[default]
     #[strum(to_string = "stable")]
@@ -64,6 +86,7 @@ pub enum Channel {
 }
 
 #[derive(Debug, Clone, Copy, Default, IntoStaticStr, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Backtrace {
     #[default]
     #[strum(to_string = "")]
@@ -83,22 +106,105 @@ pub enum BuildType {
     Release,
 }
 
+/// What a file is built as. Every file is written to `src/<name>.rs`, the targets are declared in the
+/// Cargo.toml with their path
+#[derive(Debug, Clone, Copy, Default, IntoStaticStr, PartialEq)]
+pub enum TargetKind {
+    // A module of the other files, or the binary if it's named `main`
+    #[default]
+    #[strum(to_string = "")]
+    Module,
+    // The library of the package, there can only be one
+    #[strum(to_string = "lib")]
+    Lib,
+    #[strum(to_string = "bin")]
+    Bin,
+    #[strum(to_string = "example")]
+    Example,
+    // Built without the libtest harness, e.g. for criterion
+    #[strum(to_string = "bench")]
+    Bench,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct File<'a> {
     pub(crate) name: &'a str,
     pub(crate) code: &'a str,
+    pub(crate) kind: TargetKind,
 }
 
 impl<'a> File<'a> {
     pub fn new(name: &'a str, code: &'a str) -> Self {
-        Self { name, code }
+        Self {
+            name,
+            code,
+            kind: TargetKind::Module,
+        }
+    }
+
+    /// Build the file as its own target instead of a module
+    pub fn target(mut self, kind: TargetKind) -> Self {
+        self.kind = kind;
+        self
     }
 }
 
+/// Why a project couldn't be created or started
 #[derive(Debug, Error)]
 pub enum ProjectError {
-    #[error("Failed to build project")]
-    ProjectBuildError(#[from] ProjectBuildError),
+    #[error("The project has no main file, nor a file built as a target")]
+    MissingMainFile,
+    #[error("The project has no subcommand to run")]
+    NoSubcommand,
+    #[error("Failed to write the project: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to generate Cargo.toml: {0}")]
+    TomlGeneration(String),
+    #[error("Failed to run the project: {0}")]
+    Spawn(io::Error),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum NameError {
+    #[error("The name is empty")]
+    Empty,
+    #[error("The name can't start with a digit")]
+    StartsWithDigit,
+    #[error("`{0}` isn't allowed, only letters, digits, - and _ are")]
+    InvalidChar(char),
+    #[error("`{0}` is a Rust keyword")]
+    Keyword(String),
+    #[error("`{0}` is the name of a folder cargo builds in")]
+    Reserved(String),
+}
+
+/// Check a name given to [`Project::package_name`] or [`Project::target_prefix`]. It has to be a valid
+/// cargo package name, which makes it a fine folder name as well
+pub fn validate_name(name: &str) -> Result<(), NameError> {
+    let first = name.chars().next().ok_or(NameError::Empty)?;
+
+    if first.is_ascii_digit() {
+        return Err(NameError::StartsWithDigit);
+    }
+
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_')
+    {
+        return Err(NameError::InvalidChar(c));
+    }
+
+    // the library or binary is named after the package, with - as _
+    if syn::parse_str::<syn::Ident>(&name.replace('-', "_")).is_err() {
+        return Err(NameError::Keyword(name.to_string()));
+    }
+
+    // a binary can't be named like the folders next to it in target/debug
+    if RESERVED_NAMES.contains(&name) {
+        return Err(NameError::Reserved(name.to_string()));
+    }
+
+    Ok(())
 }
 
 #[derive(Debug, Default, Clone)]
@@ -106,10 +212,22 @@ pub struct Project<'a> {
     pub(crate) files: Vec<File<'a>>,
     pub(crate) hash: u64,
     pub(crate) edition: Edition,
-    env: Vec<(&'a str, &'a str)>,
-    cargo_command_builder: CargoCommandBuilder<'a>,
-    pub(crate) location: Option<String>,
+    pub(crate) env: Vec<(&'a str, &'a str)>,
+    pub(crate) cargo_command_builder: CargoCommandBuilder<'a>,
+    pub(crate) location: Option<PathBuf>,
     pub(crate) target_prefix: Option<&'a str>,
+    pub(crate) package_name: Option<&'a str>,
+    pub(crate) base_dir: Option<&'a Path>,
+    pub(crate) sanitizer: Option<Sanitizer>,
+    pub(crate) loom: bool,
+    // lines of the [dev-dependencies] table, e.g. `loom = "0.5"`
+    pub(crate) dev_dependencies: Vec<&'a str>,
+    pub(crate) fuzz: Option<FuzzFn>,
+    // the other crates of its workspace, if it's generated as one
+    pub(crate) members: Vec<Crate<'a>>,
+    // statements of a main file without a main are put in one
+    pub(crate) wrap_main: bool,
+    pub(crate) async_runtime: AsyncRuntime,
 }
 
 impl<'a> Project<'a> {
@@ -180,6 +298,12 @@ impl<'a> Project<'a> {
         self
     }
 
+    /// Set the target triple to build for, e.g. [`WASM_TARGET`](crate::WASM_TARGET)
+    pub fn target(&mut self, target: &'a str) -> &mut Self {
+        self.cargo_command_builder.target(target);
+        self
+    }
+
     /// Append dash arg to cargo command
     pub fn dash_arg(&mut self, arg: &'a str) -> &mut Self {
         self.cargo_command_builder.dash_arg(arg);
@@ -249,6 +373,63 @@ impl<'a> Project<'a> {
         self
     }
 
+    /// Name of the package, and so of the binary it builds. E.g. instead of `p<id>`, use `<name>`.
+    /// Check it with [`validate_name`] first, cargo refuses to build invalid ones
+    pub fn package_name(&mut self, name: &'a str) -> &mut Self {
+        self.package_name = Some(name);
+        self
+    }
+
+    /// The name of the package, `p<id>` unless one was set
+    pub fn package(&self) -> String {
+        match self.package_name {
+            Some(name) => name.to_string(),
+            None => format!("p{}", self.hash),
+        }
+    }
+
+    /// Add a line to the [dev-dependencies] table, e.g. `loom = "0.5"` (append)
+    pub fn dev_dependency(&mut self, dependency: &'a str) -> &mut Self {
+        self.dev_dependencies.push(dependency);
+        self
+    }
+
+    /// Directory to create the project in instead of [`projects_dir`], e.g. on a faster disk.
+    /// It's created along with the project, check it with [`prepare_base_dir`](crate::prepare_base_dir) first
+    pub fn base_dir(&mut self, dir: &'a Path) -> &mut Self {
+        self.base_dir = Some(dir);
+        self
+    }
+
+    /// The directory the project is created in
+    pub fn project_dir(&self) -> PathBuf {
+        let name = self.target_prefix.unwrap_or("cargo-play");
+        let base_dir = self.base_dir.map_or_else(projects_dir, Path::to_path_buf);
+
+        base_dir.join(format!("{name}.{}", self.hash))
+    }
+
+    /// The Cargo.toml [`create`](Self::create) writes, generated without writing or running anything,
+    /// e.g. to preview it
+    pub fn manifest(&self) -> Result<String, ProjectError> {
+        ProjectBuilder::manifest(self)
+    }
+
+    /// The Cargo.toml and the files of `src` [`create`](Self::create) writes, by their path relative to the
+    /// project, e.g. to export them as a project of its own. The workspace members and the fuzz target
+    /// aren't included
+    pub fn sources(&self) -> Result<Vec<(String, String)>, ProjectError> {
+        ProjectBuilder::sources(self)
+    }
+
+    /// The directory of the project if it's on disk, created by this project or an earlier one with the same
+    /// id and prefix
+    pub fn path(&self) -> Option<PathBuf> {
+        let dir = self.project_dir();
+
+        dir.join("Cargo.toml").is_file().then_some(dir)
+    }
+
     /// Cargo clean the project. If project wasn't created yet, returns None
     /// TODO: Make lib that can pipe stdout and stderr together
     pub fn clean_project(&mut self) -> Option<Child> {
@@ -263,20 +444,57 @@ impl<'a> Project<'a> {
         Some(child)
     }
 
+    // RUSTFLAGS with the flags the sanitizer and loom need appended, if there are any
+    pub(crate) fn combined_rust_flags(&self) -> Option<String> {
+        let extra = [
+            self.sanitizer.map(Sanitizer::rust_flag),
+            self.loom.then_some(LOOM_CFG),
+        ];
+
+        if extra.iter().all(Option::is_none) {
+            return None;
+        }
+
+        let rust_flags = self
+            .env
+            .iter()
+            .find(|(var, _)| *var == "RUSTFLAGS")
+            .map(|(_, val)| val.trim());
+
+        let combined = rust_flags
+            .into_iter()
+            .chain(extra.into_iter().flatten())
+            .filter(|flags| !flags.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Some(combined)
+    }
+
     /// Create the project and return the command
     pub fn create(&mut self) -> Result<Command, ProjectError> {
         // Make sure you actually put a subcommand in before creating it
-        assert!(self.cargo_command_builder.subcommand.is_some());
+        if self.cargo_command_builder.subcommand.is_none() {
+            return Err(ProjectError::NoSubcommand);
+        }
 
         fix_paths();
 
-        let mut command = self.cargo_command_builder.build();
+        // the command runs the file picked as the entry, the project keeps building all of them
+        let mut cargo_command_builder = self.cargo_command_builder.clone();
+        select_target(&self.files, &mut cargo_command_builder);
+
+        let mut command = cargo_command_builder.build();
         command.envs(self.env.clone());
 
+        if let Some(rust_flags) = self.combined_rust_flags() {
+            command.env("RUSTFLAGS", rust_flags);
+        }
+
         // Copy and create project in the filesystem
         ProjectBuilder::copy(self)?;
 
-        command.current_dir(self.location.as_ref().unwrap());
+        command.current_dir(self.project_dir());
 
         Ok(command)
     }
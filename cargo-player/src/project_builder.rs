@@ -1,84 +1,276 @@
-use crate::infer::infer_deps;
-use crate::Project;
+use crate::directives::{read_directives, DirectiveLine};
+use crate::fuzz::{fuzz_cargo_toml, fuzz_target_code, FuzzFn, FUZZ_TARGET};
+use crate::infer::{infer_deps, InferredDeps};
+use crate::loom::LOOM_HARNESS;
+use crate::runtime::{add_runtime_attribute, add_runtime_dependency, has_async_main};
+use crate::script::{embedded_manifest, strip_shebang};
+use crate::targets::{default_run, entry_file, target_tables};
+use crate::workspace::{link_main, member_cargo_toml, workspace_table, Crate};
+use crate::wrap_main::wrap_in_main;
+use crate::{validate_name, File, LineEnding, Project, ProjectError};
 
 use std::fs;
-
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-pub enum ProjectBuildError {
-    #[error("Io error occurred")]
-    Io(#[from] std::io::Error),
-}
+use std::io;
+use std::path::Path;
 
 pub struct ProjectBuilder<'a, 'b> {
-    project: &'a mut Project<'b>,
+    project: &'a Project<'b>,
+    // the main file with its statements put in a main or a runtime's attribute on it, if it was asked for
+    // and it needed it
+    wrapped_main: Option<String>,
 }
 
 impl<'a, 'b> ProjectBuilder<'a, 'b> {
-    fn new(project: &'a mut Project<'b>) -> Self {
-        Self { project }
+    fn new(project: &'a Project<'b>) -> Self {
+        let wrapped_main = project
+            .files
+            .iter()
+            .find(|file| file.name == "main")
+            .and_then(|file| {
+                let code = strip_shebang(file.code);
+
+                project
+                    .wrap_main
+                    .then(|| wrap_in_main(code))
+                    .flatten()
+                    .or_else(|| add_runtime_attribute(code, project.async_runtime))
+            });
+
+        Self {
+            project,
+            wrapped_main,
+        }
     }
 
-    fn create_cargo_toml(&self) -> String {
+    // the files as they're written. Dependencies are inferred from them too, statements outside of a
+    // function don't parse as a file
+    fn files(&self) -> Vec<File<'_>> {
+        self.project
+            .files
+            .iter()
+            .map(|file| match &self.wrapped_main {
+                Some(code) if file.name == "main" => File { code, ..*file },
+                _ => *file,
+            })
+            .collect()
+    }
+
+    fn create_cargo_toml(&self, dependencies: &InferredDeps) -> Result<String, ProjectError> {
         let edition = self.project.edition;
-        let id = self.project.hash;
-        // if the user has malformed code, or wrong deps that's not our fault. Running cargo will reveal it
-        let dependencies = infer_deps(&self.project.files).unwrap_or_default();
+        let package = self.project.package();
+        check_name("package", &package)?;
 
-        // we can add extra cargo toml, but only in the main file
+        // we can add extra cargo toml, but only in the main file, or the target run in its place
         let mut extra_cargo = String::new();
-        let main_file = self
-            .project
-            .files
-            .iter()
-            .find(|f| f.name == "main")
-            // this is a hard error. No project can exist without a main file
-            .expect("Main file not found");
+        // no project can exist without a main file or a target
+        let main_file = entry_file(&self.project.files).ok_or(ProjectError::MissingMainFile)?;
 
-        for l in main_file.code.lines() {
-            if l.starts_with("//> ") {
-                extra_cargo.push_str(l.strip_prefix("//> ").unwrap());
+        for directive in read_directives(main_file.code) {
+            if let DirectiveLine::CargoToml(toml) = directive {
+                extra_cargo.push_str(toml);
                 extra_cargo.push('\n');
-                continue;
-            } else if l.starts_with("//# ") {
-                // just ignore these lines
-                continue;
             }
-
-            break;
         }
 
+        // the main file's binary runs rather than a [[bin]] next to it
+        let default_run = if default_run(&self.project.files) {
+            format!("default-run = \"{package}\"\n")
+        } else {
+            String::new()
+        };
+
         let mut formatted = format!(
             r#"[package]
-name = "p{id}"
+name = "{package}"
 version = "0.1.0"
 edition = "{edition}"
-
+{default_run}
 [dependencies]
-{dependencies}
-"#
+{}
+"#,
+            dependencies.dependencies
         );
 
+        // the ones the project needs, e.g. loom, and the ones of the code
+        let dev_dependencies = self
+            .project
+            .dev_dependencies
+            .iter()
+            .copied()
+            .chain(dependencies.dev_dependencies.lines())
+            .collect::<Vec<_>>();
+
+        for (table, lines) in [
+            ("dev-dependencies", dev_dependencies),
+            (
+                "build-dependencies",
+                dependencies.build_dependencies.lines().collect(),
+            ),
+        ] {
+            if lines.is_empty() {
+                continue;
+            }
+
+            formatted.push_str(&format!("\n[{table}]\n"));
+
+            for dependency in lines {
+                formatted.push_str(dependency);
+                formatted.push('\n');
+            }
+        }
+
+        // files built as targets of their own, the others are modules
+        formatted.push_str(&target_tables(&self.project.files));
+
+        // and the rest of a script's embedded manifest
+        if let Some(manifest) = embedded_manifest(main_file.code) {
+            extra_cargo.push_str(&manifest.cargo_toml);
+        }
+
         if !extra_cargo.is_empty() {
             formatted.push('\n');
             formatted.push_str(&extra_cargo);
         }
 
-        formatted
+        if !self.project.members.is_empty() {
+            formatted.push_str(&workspace_table(&self.project.members));
+        }
+
+        Ok(formatted)
+    }
+
+    // a member of the workspace in crates/, its dependencies are inferred from its own files
+    fn create_member(&self, member: &Crate) -> Result<(), ProjectError> {
+        check_name("member", member.name)?;
+
+        let member_dir = self.project.member_dir(member.name);
+        let member_dir_src = member_dir.join("src");
+        if !member_dir_src.exists() {
+            fs::create_dir_all(&member_dir_src)?;
+        }
+
+        let dependencies = infer_deps(&member.files).unwrap_or_default();
+
+        fs::write(
+            member_dir.join("Cargo.toml"),
+            member_cargo_toml(
+                member,
+                &dependencies,
+                self.project.edition,
+                &self.project.members,
+            ),
+        )?;
+
+        for file in &member.files {
+            write_lf(
+                member_dir_src.join(format!("{}.rs", file.name)),
+                strip_shebang(file.code),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // the fuzz crate in fuzz/, where cargo-fuzz looks for it. Its target is the main file, the other
+    // files sit next to it so its modules are found
+    fn create_fuzz_target(
+        &self,
+        function: &FuzzFn,
+        dependencies: &InferredDeps,
+    ) -> Result<(), ProjectError> {
+        let fuzz_dir = self.project.project_dir().join("fuzz");
+        let targets_dir = fuzz_dir.join("fuzz_targets");
+        if !targets_dir.exists() {
+            fs::create_dir_all(&targets_dir)?;
+        }
+
+        let edition: &str = self.project.edition.into();
+        fs::write(
+            fuzz_dir.join("Cargo.toml"),
+            fuzz_cargo_toml(self.project.hash, edition, &dependencies.dependencies),
+        )?;
+
+        for file in &self.project.files {
+            if file.name == "main" {
+                write_lf(
+                    targets_dir.join(format!("{FUZZ_TARGET}.rs")),
+                    &fuzz_target_code(strip_shebang(file.code), function),
+                )?;
+            } else {
+                write_lf(
+                    targets_dir.join(format!("{}.rs", file.name)),
+                    strip_shebang(file.code),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // the Cargo.toml of the main crate, and the dependencies inferred from its files
+    fn manifest_and_dependencies(&self) -> Result<(String, InferredDeps), ProjectError> {
+        // if the user has malformed code, or wrong deps that's not our fault. Running cargo will reveal it
+        let files = self.files();
+        let mut dependencies = infer_deps(&files).unwrap_or_default();
+
+        let async_main = files
+            .iter()
+            .find(|file| file.name == "main")
+            .is_some_and(|file| has_async_main(file.code));
+
+        if async_main {
+            add_runtime_dependency(&mut dependencies.dependencies, self.project.async_runtime);
+        }
+
+        // the members are used like any other crate, but depended on by path
+        let cargo_config = if self.project.members.is_empty() {
+            self.create_cargo_toml(&dependencies)?
+        } else {
+            self.create_cargo_toml(&link_main(&dependencies, &self.project.members))?
+        };
+
+        Ok((cargo_config, dependencies))
+    }
+
+    pub fn manifest(project: &'a Project<'b>) -> Result<String, ProjectError> {
+        let (manifest, _) = ProjectBuilder::new(project).manifest_and_dependencies()?;
+
+        Ok(manifest)
     }
 
-    pub fn copy(project: &'a mut Project<'b>) -> Result<(), ProjectBuildError> {
+    // the contents of a file as it's written to src
+    fn source(&self, file: &File) -> String {
+        // scripts are pasted with one, but it has to be the first line, and the fuzz target puts its
+        // attributes before the code
+        let code = strip_shebang(file.code);
+
+        if self.project.loom && file.name == "main" {
+            format!("{code}{LOOM_HARNESS}")
+        } else {
+            code.to_string()
+        }
+    }
+
+    pub fn sources(project: &'a Project<'b>) -> Result<Vec<(String, String)>, ProjectError> {
         let builder = ProjectBuilder::new(project);
+        let (manifest, _) = builder.manifest_and_dependencies()?;
 
-        let cargo_config = builder.create_cargo_toml();
+        let mut sources = vec![("Cargo.toml".to_string(), manifest)];
 
-        let hash = builder.project.hash;
-        let name = builder.project.target_prefix.unwrap_or("cargo-play");
+        for file in &builder.files() {
+            let source = LineEnding::Lf.apply(&builder.source(file)).into_owned();
+            sources.push((format!("src/{}.rs", file.name), source));
+        }
 
-        let folder_name = format!("{name}.{hash}");
+        Ok(sources)
+    }
 
-        let target_dir = std::env::temp_dir().join("rust").join(folder_name);
+    pub fn copy(project: &'a mut Project<'b>) -> Result<(), ProjectError> {
+        let builder = ProjectBuilder::new(project);
+
+        let (cargo_config, dependencies) = builder.manifest_and_dependencies()?;
+
+        let target_dir = builder.project.project_dir();
 
         // create all directories straight to src
         let target_dir_src = target_dir.join("src");
@@ -88,12 +280,33 @@ edition = "{edition}"
 
         fs::write(target_dir.join("Cargo.toml"), cargo_config)?;
 
-        for file in &builder.project.files {
-            fs::write(target_dir_src.join(format!("{}.rs", file.name)), file.code)?;
+        for file in &builder.files() {
+            let path = target_dir_src.join(format!("{}.rs", file.name));
+            write_lf(path, &builder.source(file))?;
+        }
+
+        for member in &builder.project.members {
+            builder.create_member(member)?;
+        }
+
+        if let Some(function) = &builder.project.fuzz {
+            builder.create_fuzz_target(function, &dependencies)?;
         }
 
-        builder.project.location = Some(target_dir.to_str().unwrap().to_string());
+        project.location = Some(target_dir);
 
         Ok(())
     }
 }
+
+// a name cargo would reject makes the whole Cargo.toml invalid, it's better told apart from the code's errors
+fn check_name(what: &str, name: &str) -> Result<(), ProjectError> {
+    validate_name(name).map_err(|e| {
+        ProjectError::TomlGeneration(format!("`{name}` can't be the {what} name. {e}"))
+    })
+}
+
+// generated files always use LF, whatever the scratch was typed with, so they don't show up as changed
+fn write_lf(path: impl AsRef<Path>, contents: &str) -> io::Result<()> {
+    fs::write(path, LineEnding::Lf.apply(contents).as_bytes())
+}
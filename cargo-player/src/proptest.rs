@@ -0,0 +1,244 @@
+use std::ops::Range;
+
+use quote::ToTokens;
+use syn::{GenericArgument, PathArguments, Type};
+
+use crate::fuzz::{argument_types, plain_functions};
+
+// inclusive upper bound of generated collection lengths
+const MAX_LEN: usize = 100;
+
+/// Top level functions a property test can be generated for
+pub fn proptest_functions(code: &str) -> Vec<String> {
+    plain_functions(code)
+        .into_iter()
+        .map(|sig| sig.ident.to_string())
+        .collect()
+}
+
+/// A `#[cfg(test)]` module with a proptest calling the function with generated arguments.
+/// Strategies are inferred from the parameter types, falling back to `any`.
+/// None if there's no such function, see [`proptest_functions`]
+pub fn proptest_scaffold(code: &str, function: &str) -> Option<String> {
+    let sig = plain_functions(code)
+        .into_iter()
+        .find(|sig| sig.ident == function)?;
+
+    let mut params = vec![];
+    let mut args = vec![];
+
+    for (i, ty) in argument_types(&sig).into_iter().enumerate() {
+        let name = format!("arg{i}");
+
+        let (strategy, arg) = match ty {
+            Type::Reference(reference) => {
+                let arg = if reference.mutability.is_some() {
+                    format!("&mut {name}.clone()")
+                } else {
+                    format!("&{name}")
+                };
+
+                (owned_strategy(&reference.elem), arg)
+            }
+
+            ty => (strategy(ty), name.clone()),
+        };
+
+        params.push(format!("{name} in {strategy}"));
+        args.push(arg);
+    }
+
+    Some(format!(
+        r#"
+#[cfg(test)]
+mod proptest_{function} {{
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {{
+        #[test]
+        fn {function}_holds({params}) {{
+            let _result = {function}({args});
+
+            // TODO: check a property of the result, e.g. with prop_assert!
+        }}
+    }}
+}}
+"#,
+        params = params.join(", "),
+        args = args.join(", "),
+    ))
+}
+
+/// The code with a proptest for the function added, see [`proptest_scaffold`]. A module generated for the
+/// function before is replaced instead of adding a second one of the same name
+pub fn insert_proptest(code: &str, function: &str) -> Option<String> {
+    let scaffold = proptest_scaffold(code, function)?;
+
+    if let Some(range) = existing_module(code, function) {
+        let mut code = code.to_string();
+        code.replace_range(range, scaffold.trim());
+
+        return Some(code);
+    }
+
+    let mut code = code.to_string();
+    if !code.ends_with('\n') {
+        code.push('\n');
+    }
+    code.push_str(&scaffold);
+
+    Some(code)
+}
+
+// byte range of a module generated for the function, from its attribute to the closing brace
+fn existing_module(code: &str, function: &str) -> Option<Range<usize>> {
+    let header = format!("mod proptest_{function} {{");
+    let header_start = code.find(&header)?;
+
+    let start = code[..header_start]
+        .trim_end()
+        .strip_suffix("#[cfg(test)]")
+        .map_or(header_start, str::len);
+
+    // the matching brace, braces in string literals don't count
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in code[header_start..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(start..header_start + i + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+// strategy for the owned value behind a reference, e.g. String for str or Vec<T> for [T]
+fn owned_strategy(ty: &Type) -> String {
+    match ty {
+        Type::Path(path) if path.path.is_ident("str") => string_strategy(),
+        Type::Slice(slice) => vec_strategy(&slice.elem),
+        ty => strategy(ty),
+    }
+}
+
+fn strategy(ty: &Type) -> String {
+    match ty {
+        Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+            let elems = tuple.elems.iter().map(strategy).collect::<Vec<_>>();
+
+            format!("({},)", elems.join(", "))
+        }
+
+        Type::Path(path) => {
+            let Some(segment) = path.path.segments.last() else {
+                return any(ty);
+            };
+
+            let inner = match &segment.arguments {
+                PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+                    Some(GenericArgument::Type(inner)) => Some(inner),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match (segment.ident.to_string().as_str(), inner) {
+                ("String", None) => string_strategy(),
+                ("Vec", Some(inner)) => vec_strategy(inner),
+                ("Option", Some(inner)) => format!("proptest::option::of({})", strategy(inner)),
+                ("Box", Some(inner)) => format!("{}.prop_map(Box::new)", strategy(inner)),
+                _ => any(ty),
+            }
+        }
+
+        ty => any(ty),
+    }
+}
+
+fn string_strategy() -> String {
+    // any printable characters
+    r#""\\PC*""#.to_string()
+}
+
+fn vec_strategy(elem: &Type) -> String {
+    format!(
+        "proptest::collection::vec({}, 0..={MAX_LEN})",
+        strategy(elem)
+    )
+}
+
+fn any(ty: &Type) -> String {
+    format!("any::<{}>()", ty.to_token_stream())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_functions() {
+        let code = r#"
+fn parse(data: &[u8]) {}
+fn add(a: i32, b: i32) -> i32 { a + b }
+fn generic<T>(value: T) {}
+fn main() {}
+"#;
+
+        assert_eq!(proptest_functions(code), vec!["parse", "add"]);
+    }
+
+    #[test]
+    fn infer_strategies() {
+        let code = r#"
+fn check(a: u32, name: &str, owned: String, items: &[u8], list: Vec<Option<bool>>, pair: (i8, char), custom: Point) {}
+"#;
+
+        let scaffold = proptest_scaffold(code, "check").unwrap();
+
+        assert!(scaffold.contains("#[cfg(test)]\nmod proptest_check {"));
+        assert!(scaffold.contains("use proptest::prelude::*;"));
+        assert!(scaffold.contains(
+            r#"fn check_holds(arg0 in any::<u32>(), arg1 in "\\PC*", arg2 in "\\PC*", arg3 in proptest::collection::vec(any::<u8>(), 0..=100), arg4 in proptest::collection::vec(proptest::option::of(any::<bool>()), 0..=100), arg5 in (any::<i8>(), any::<char>(),), arg6 in any::<Point>())"#
+        ));
+        assert!(
+            scaffold.contains("let _result = check(arg0, &arg1, arg2, &arg3, arg4, arg5, arg6);")
+        );
+
+        assert_eq!(proptest_scaffold(code, "missing"), None);
+    }
+
+    #[test]
+    fn replace_existing_module() {
+        let code = "fn add(a: i32, b: i32) -> i32 { a + b }\n";
+
+        let once = insert_proptest(code, "add").unwrap();
+        assert_eq!(once.matches("mod proptest_add {").count(), 1);
+
+        // a generated module which was edited since, with a brace in a string
+        let edited = once.replace(
+            "// TODO: check a property of the result, e.g. with prop_assert!",
+            "prop_assert!(format!(\"{}\", 1) != \"}\");",
+        );
+        let edited = format!("{edited}\nfn after() {{}}\n");
+
+        let twice = insert_proptest(&edited, "add").unwrap();
+        assert_eq!(twice.matches("mod proptest_add {").count(), 1);
+        assert_eq!(twice.matches("#[cfg(test)]").count(), 1);
+        assert!(!twice.contains("prop_assert!(format!"));
+        assert!(twice.ends_with("}\n\nfn after() {}\n"));
+    }
+}
@@ -0,0 +1,186 @@
+use strum_macros::{Display, IntoStaticStr};
+use syn::{parse_file, Item};
+
+use crate::Project;
+
+/// The runtime an `async fn main` is run on, see [`Project::async_runtime`]
+#[derive(Debug, Clone, Copy, Default, IntoStaticStr, PartialEq, Eq, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AsyncRuntime {
+    #[default]
+    #[strum(to_string = "None")]
+    None,
+    #[strum(to_string = "Tokio")]
+    Tokio,
+    #[strum(to_string = "async-std")]
+    AsyncStd,
+}
+
+impl AsyncRuntime {
+    pub const ALL: [Self; 3] = [Self::None, Self::Tokio, Self::AsyncStd];
+
+    // the line of the [dependencies] table, with the features the attribute needs
+    fn dependency(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Tokio => Some(r#"tokio = { version = "1", features = ["full"] }"#),
+            Self::AsyncStd => Some(r#"async-std = { version = "1", features = ["attributes"] }"#),
+        }
+    }
+
+    fn attribute(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Tokio => Some("#[tokio::main]"),
+            Self::AsyncStd => Some("#[async_std::main]"),
+        }
+    }
+}
+
+impl<'a> Project<'a> {
+    /// Run an `async fn main` on a runtime. Its dependency is added, and its attribute unless main has
+    /// one already. A main which isn't async is left alone
+    pub fn async_runtime(&mut self, runtime: AsyncRuntime) -> &mut Self {
+        self.async_runtime = runtime;
+        self
+    }
+}
+
+pub(crate) fn has_async_main(code: &str) -> bool {
+    async_main(code).is_some()
+}
+
+// Some if main is async, with whether it has an attribute like #[tokio::main] already
+fn async_main(code: &str) -> Option<bool> {
+    parse_file(code).ok()?.items.into_iter().find_map(|item| {
+        let Item::Fn(function) = item else {
+            return None;
+        };
+
+        let is_async_main = function.sig.ident == "main" && function.sig.asyncness.is_some();
+
+        is_async_main.then(|| {
+            function.attrs.iter().any(|attr| {
+                attr.path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "main")
+            })
+        })
+    })
+}
+
+// the code with the runtime's attribute put on its async main. It's put on the same line, so the lines of
+// errors still match the scratch. None if it needs none
+pub(crate) fn add_runtime_attribute(code: &str, runtime: AsyncRuntime) -> Option<String> {
+    let attribute = runtime.attribute()?;
+
+    if async_main(code)? {
+        return None;
+    }
+
+    let mut start = 0;
+
+    for line in code.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if trimmed
+            .strip_prefix("pub ")
+            .unwrap_or(trimmed)
+            .starts_with("async fn main(")
+        {
+            let at = start + indent;
+            return Some(format!("{}{attribute} {}", &code[..at], &code[at..]));
+        }
+
+        start += line.len();
+    }
+
+    None
+}
+
+// the runtime's dependency in place of the one inferred from its use, which has none of the features the
+// attribute needs. One listed in a `//#` directive is kept, it's the user's
+pub(crate) fn add_runtime_dependency(dependencies: &mut String, runtime: AsyncRuntime) {
+    let Some(dependency) = runtime.dependency() else {
+        return;
+    };
+
+    let name = dependency.split('=').next().unwrap_or_default().trim();
+    let is_runtime = |line: &str| {
+        line.split('=')
+            .next()
+            .is_some_and(|other| other.trim().replace('_', "-") == name)
+    };
+
+    let mut lines = dependencies
+        .lines()
+        .filter(|line| !(is_runtime(line) && line.ends_with(r#"= "*""#)))
+        .collect::<Vec<_>>();
+
+    if !lines.iter().any(|line| is_runtime(line)) {
+        lines.push(dependency);
+    }
+
+    *dependencies = lines.join("\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute() {
+        assert_eq!(
+            add_runtime_attribute(
+                "use tokio::time;\n\nasync fn main() {\n    time::sleep(time::Duration::ZERO).await;\n}",
+                AsyncRuntime::Tokio
+            )
+            .as_deref(),
+            Some("use tokio::time;\n\n#[tokio::main] async fn main() {\n    time::sleep(time::Duration::ZERO).await;\n}")
+        );
+
+        // it has one, or main isn't async
+        assert_eq!(
+            add_runtime_attribute(
+                "#[async_std::main]\nasync fn main() {}",
+                AsyncRuntime::Tokio
+            ),
+            None
+        );
+        assert_eq!(
+            add_runtime_attribute("fn main() {}", AsyncRuntime::Tokio),
+            None
+        );
+        assert_eq!(
+            add_runtime_attribute("async fn main() {}", AsyncRuntime::None),
+            None
+        );
+    }
+
+    #[test]
+    fn dependency() {
+        let mut dependencies = "rand = \"*\"\ntokio = \"*\"".to_string();
+        add_runtime_dependency(&mut dependencies, AsyncRuntime::Tokio);
+        assert_eq!(
+            dependencies,
+            "rand = \"*\"\ntokio = { version = \"1\", features = [\"full\"] }"
+        );
+
+        // the user's own, and one inferred with the crate's name as it's used in paths
+        let mut dependencies = "tokio = { version = \"1.25\", features = [\"rt\"] }".to_string();
+        add_runtime_dependency(&mut dependencies, AsyncRuntime::Tokio);
+        assert_eq!(
+            dependencies,
+            "tokio = { version = \"1.25\", features = [\"rt\"] }"
+        );
+
+        let mut dependencies = "async_std = \"*\"".to_string();
+        add_runtime_dependency(&mut dependencies, AsyncRuntime::AsyncStd);
+        assert_eq!(
+            dependencies,
+            "async-std = { version = \"1\", features = [\"attributes\"] }"
+        );
+    }
+}
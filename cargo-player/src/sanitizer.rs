@@ -0,0 +1,260 @@
+use std::ops::Range;
+use std::process::Command;
+
+use strum_macros::IntoStaticStr;
+
+use crate::{Channel, Project};
+
+// targets each sanitizer is available on, see the unstable book
+const ADDRESS_TARGETS: &[&str] = &[
+    "aarch64-apple-darwin",
+    "aarch64-unknown-fuchsia",
+    "aarch64-unknown-linux-gnu",
+    "x86_64-apple-darwin",
+    "x86_64-pc-windows-msvc",
+    "x86_64-unknown-freebsd",
+    "x86_64-unknown-fuchsia",
+    "x86_64-unknown-linux-gnu",
+];
+const THREAD_TARGETS: &[&str] = &[
+    "aarch64-apple-darwin",
+    "aarch64-unknown-linux-gnu",
+    "x86_64-apple-darwin",
+    "x86_64-unknown-freebsd",
+    "x86_64-unknown-linux-gnu",
+];
+const LEAK_TARGETS: &[&str] = &[
+    "aarch64-apple-darwin",
+    "aarch64-unknown-linux-gnu",
+    "x86_64-apple-darwin",
+    "x86_64-unknown-linux-gnu",
+];
+
+/// Runtime checks compiled in with `-Zsanitizer`, nightly only
+#[derive(Debug, Clone, Copy, IntoStaticStr, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sanitizer {
+    #[strum(to_string = "address")]
+    Address,
+    #[strum(to_string = "thread")]
+    Thread,
+    #[strum(to_string = "leak")]
+    Leak,
+}
+
+impl Sanitizer {
+    pub const ALL: [Self; 3] = [Self::Address, Self::Thread, Self::Leak];
+
+    pub fn rust_flag(self) -> &'static str {
+        match self {
+            Self::Address => "-Zsanitizer=address",
+            Self::Thread => "-Zsanitizer=thread",
+            Self::Leak => "-Zsanitizer=leak",
+        }
+    }
+
+    /// Whether rustc supports the sanitizer on the target triple
+    pub fn supports(self, target: &str) -> bool {
+        let targets = match self {
+            Self::Address => ADDRESS_TARGETS,
+            Self::Thread => THREAD_TARGETS,
+            Self::Leak => LEAK_TARGETS,
+        };
+
+        targets.contains(&target)
+    }
+}
+
+/// The target triple rustc builds for by default, e.g. x86_64-unknown-linux-gnu
+pub fn host_target() -> Option<String> {
+    let output = Command::new("rustc").arg("-vV").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|host| host.trim().to_string())
+}
+
+impl<'a> Project<'a> {
+    /// Build with a sanitizer on nightly. Its flag is appended to RUSTFLAGS when the project is created.
+    /// The target should be the host, see [`host_target`]. Passing it explicitly keeps build scripts
+    /// and proc macros from being instrumented, which they can't be
+    pub fn sanitizer(&mut self, sanitizer: Sanitizer, target: Option<&'a str>) -> &mut Self {
+        self.sanitizer = Some(sanitizer);
+        self.channel(Channel::Nightly);
+
+        if let Some(target) = target {
+            self.target(target);
+        }
+
+        self
+    }
+}
+
+/// An error or warning printed by a sanitizer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizerReport {
+    /// e.g. AddressSanitizer
+    pub sanitizer: String,
+    /// e.g. heap-use-after-free or data race
+    pub kind: String,
+    /// Line indices of the report, from its header up to and including the summary
+    pub lines: Range<usize>,
+    /// First source location outside the toolchain, e.g. /tmp/p/src/main.rs:5:20
+    pub location: Option<String>,
+}
+
+/// Find the sanitizer reports in a program's stderr
+pub fn sanitizer_reports(output: &str) -> Vec<SanitizerReport> {
+    let mut reports = vec![];
+    let mut current: Option<SanitizerReport> = None;
+
+    for (i, line) in output.lines().enumerate() {
+        if let Some((sanitizer, kind)) = report_header(line) {
+            // a report without a summary ends where the next one begins
+            reports.extend(current.take());
+
+            current = Some(SanitizerReport {
+                sanitizer: sanitizer.to_string(),
+                kind: kind.to_string(),
+                lines: i..i + 1,
+                location: None,
+            });
+
+            continue;
+        }
+
+        let Some(report) = &mut current else {
+            continue;
+        };
+
+        report.lines.end = i + 1;
+
+        if report.location.is_none() {
+            report.location = frame_location(line);
+        }
+
+        if line.trim_start().starts_with("SUMMARY: ") {
+            reports.extend(current.take());
+        }
+    }
+
+    reports.extend(current);
+
+    reports
+}
+
+// e.g. "==1234==ERROR: AddressSanitizer: heap-use-after-free on address 0x..."
+// or "WARNING: ThreadSanitizer: data race (pid=1234)"
+fn report_header(line: &str) -> Option<(&str, &str)> {
+    let start = line.find("ERROR: ").or_else(|| line.find("WARNING: "))?;
+    let rest = line[start..].split_once(": ")?.1;
+
+    let (sanitizer, kind) = rest.split_once(": ")?;
+    if !sanitizer.ends_with("Sanitizer") {
+        return None;
+    }
+
+    // the details after the kind differ between sanitizers
+    let kind = kind
+        .split(" on ")
+        .next()
+        .unwrap_or_default()
+        .split(" (")
+        .next()
+        .unwrap_or_default()
+        .trim();
+
+    Some((sanitizer, kind))
+}
+
+// e.g. "    #0 0x55d5 in p::main::h1f2e /tmp/p/src/main.rs:5:20"
+fn frame_location(line: &str) -> Option<String> {
+    let line = line.trim_start();
+    if !line.starts_with('#') {
+        return None;
+    }
+
+    // thread sanitizer adds the module offset after the location
+    let location = line.split_whitespace().find(|word| word.contains(".rs:"))?;
+
+    let is_toolchain = location.contains("/rustc/") || location.contains("/.cargo/registry/");
+
+    (!is_toolchain).then(|| location.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizer_flags() {
+        let mut project = Project::new("sanitizer");
+        assert_eq!(project.combined_rust_flags(), None);
+
+        project.sanitizer(Sanitizer::Address, Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(
+            project.combined_rust_flags().as_deref(),
+            Some("-Zsanitizer=address")
+        );
+        assert_eq!(
+            project.cargo_command_builder.channel,
+            Some(Channel::Nightly)
+        );
+        assert_eq!(
+            project.cargo_command_builder.target,
+            Some("x86_64-unknown-linux-gnu")
+        );
+
+        // added to the flags which are already set
+        project.rust_flags("-Cdebuginfo=2");
+        assert_eq!(
+            project.combined_rust_flags().as_deref(),
+            Some("-Cdebuginfo=2 -Zsanitizer=address")
+        );
+
+        assert!(Sanitizer::Thread.supports("x86_64-unknown-linux-gnu"));
+        assert!(!Sanitizer::Leak.supports("x86_64-pc-windows-msvc"));
+    }
+
+    #[test]
+    fn parse_reports() {
+        let output = "\
+    Finished dev [unoptimized + debuginfo] target(s) in 0.5s
+=================================================================
+==4242==ERROR: AddressSanitizer: heap-use-after-free on address 0x602000000010 at pc 0x55d5
+READ of size 4 at 0x602000000010 thread T0
+    #0 0x55d5 in core::ptr::read /rustc/abc/library/core/src/ptr/mod.rs:1200:5
+    #1 0x55d6 in p::main::h1f2e /tmp/p/src/main.rs:5:20
+SUMMARY: AddressSanitizer: heap-use-after-free /tmp/p/src/main.rs:5:20 in p::main
+==4242==ABORTING
+==================
+WARNING: ThreadSanitizer: data race (pid=4243)
+  Write of size 4 at 0x7b04 by thread T1:
+    #0 p::main::{{closure}} /tmp/p/src/main.rs:9:9 (p+0x1)
+";
+
+        let reports = sanitizer_reports(output);
+
+        assert_eq!(
+            reports,
+            vec![
+                SanitizerReport {
+                    sanitizer: "AddressSanitizer".to_string(),
+                    kind: "heap-use-after-free".to_string(),
+                    lines: 2..7,
+                    location: Some("/tmp/p/src/main.rs:5:20".to_string()),
+                },
+                SanitizerReport {
+                    sanitizer: "ThreadSanitizer".to_string(),
+                    kind: "data race".to_string(),
+                    lines: 9..12,
+                    location: Some("/tmp/p/src/main.rs:9:9".to_string()),
+                },
+            ]
+        );
+
+        assert!(sanitizer_reports("thread 'main' panicked at 'ERROR: oops'").is_empty());
+    }
+}
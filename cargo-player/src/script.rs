@@ -0,0 +1,163 @@
+use crate::directives::{is_preamble, Table};
+
+/// The shebang a script starts with, e.g. `#!/usr/bin/env rust-script`. An inner attribute such as
+/// `#![allow(unused)]` starts with `#!` as well, but isn't one
+pub fn shebang(code: &str) -> Option<&str> {
+    let line = code.lines().next()?;
+    let rest = line.strip_prefix("#!")?;
+
+    (!rest.trim_start().starts_with('[')).then_some(line)
+}
+
+/// The code without its shebang. The line it was on stays, empty, so line numbers still match the scratch
+pub fn strip_shebang(code: &str) -> &str {
+    match shebang(code) {
+        Some(shebang) => &code[shebang.len()..],
+        None => code,
+    }
+}
+
+/// A Cargo.toml embedded in the doc comment at the top of a script, the way rust-script and
+/// cargo-script read it
+///
+/// ```text
+/// //! ```cargo
+/// //! [dependencies]
+/// //! time = "0.1.25"
+/// //! ```
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct EmbeddedManifest {
+    // the lines of its dependency tables, which count as `//#` directives
+    pub(crate) dependencies: Vec<(Table, String)>,
+    // its other tables, added to the Cargo.toml like `//>` lines
+    pub(crate) cargo_toml: String,
+}
+
+// where the lines of the manifest go
+enum Section {
+    Dependencies(Table),
+    // the project has its own [package], the script's is left out
+    Package,
+    Other,
+}
+
+pub(crate) fn embedded_manifest(code: &str) -> Option<EmbeddedManifest> {
+    let mut lines = code
+        .lines()
+        .take_while(|line| is_preamble(line))
+        .filter_map(|line| line.trim_start().strip_prefix("//!"))
+        .map(|line| line.strip_prefix(' ').unwrap_or(line));
+
+    lines.by_ref().find(|line| line.trim() == "```cargo")?;
+
+    let mut manifest = EmbeddedManifest::default();
+    let mut section = Section::Other;
+
+    for line in lines.take_while(|line| line.trim() != "```") {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') {
+            section = match trimmed {
+                "[dependencies]" => Section::Dependencies(Table::Dependencies),
+                "[dev-dependencies]" => Section::Dependencies(Table::DevDependencies),
+                "[build-dependencies]" => Section::Dependencies(Table::BuildDependencies),
+                "[package]" => Section::Package,
+                _ => Section::Other,
+            };
+
+            if !matches!(section, Section::Other) {
+                continue;
+            }
+        }
+
+        match section {
+            Section::Dependencies(table) => {
+                if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                    manifest.dependencies.push((table, trimmed.to_string()));
+                }
+            }
+
+            Section::Package => (),
+
+            Section::Other => {
+                manifest.cargo_toml.push_str(line);
+                manifest.cargo_toml.push('\n');
+            }
+        }
+    }
+
+    Some(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shebangs() {
+        assert_eq!(
+            shebang("#!/usr/bin/env rust-script\nfn main() {}"),
+            Some("#!/usr/bin/env rust-script")
+        );
+        assert_eq!(shebang("#![allow(unused)]\nfn main() {}"), None);
+        assert_eq!(shebang("#! [allow(unused)]"), None);
+        assert_eq!(shebang("fn main() {}\n#!/bin/sh"), None);
+
+        // the line stays
+        assert_eq!(
+            strip_shebang("#!/usr/bin/env run-cargo-script\r\nfn main() {}"),
+            "\r\nfn main() {}"
+        );
+        assert_eq!(strip_shebang("#![no_std]"), "#![no_std]");
+    }
+
+    #[test]
+    fn manifests() {
+        let code = r#"#!/usr/bin/env rust-script
+//! Prints the time
+//!
+//! ```cargo
+//! [package]
+//! edition = "2021"
+//!
+//! [dependencies]
+//! time = "0.1.25"
+//! # a comment
+//! serde = { version = "1", features = ["derive"] }
+//!
+//! [dev-dependencies]
+//! proptest = "1"
+//!
+//! [profile.dev]
+//! opt-level = 1
+//! ```
+//!
+//! [dependencies]
+//! ignored = "1"
+fn main() {}
+"#;
+
+        assert_eq!(
+            embedded_manifest(code),
+            Some(EmbeddedManifest {
+                dependencies: vec![
+                    (Table::Dependencies, r#"time = "0.1.25""#.to_string()),
+                    (
+                        Table::Dependencies,
+                        r#"serde = { version = "1", features = ["derive"] }"#.to_string()
+                    ),
+                    (Table::DevDependencies, r#"proptest = "1""#.to_string()),
+                ],
+                cargo_toml: "[profile.dev]\nopt-level = 1\n".to_string(),
+            })
+        );
+
+        // only the doc comment at the top is read
+        assert_eq!(
+            embedded_manifest("fn main() {}\n//! ```cargo\n//! [dependencies]\n"),
+            None
+        );
+        assert_eq!(embedded_manifest("//! ```rust\n//! ```\n"), None);
+    }
+}
@@ -0,0 +1,305 @@
+/// A part of a long output which can be folded away, e.g. a dependency `cargo tree` printed with everything
+/// it pulls in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputSection {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+impl OutputSection {
+    fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            lines: vec![],
+        }
+    }
+}
+
+// what cargo tree draws in front of a direct dependency
+const BRANCHES: [&str; 2] = ["├── ", "└── "];
+// how far every level of cargo tree is indented
+const INDENT: usize = 4;
+
+/// The direct dependencies printed by [`Project::tree`](crate::Project::tree), each with the ones it brings
+/// in. Those of the dev- and build-dependency tables say so in their title
+pub fn tree_sections(output: &str) -> Vec<OutputSection> {
+    let mut sections = vec![];
+    // the table the dependencies are listed under, and whether the lines belong to the last section
+    let mut table = None;
+    let mut in_section = false;
+
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(name) = BRANCHES.iter().find_map(|branch| line.strip_prefix(branch)) {
+            let title = match table {
+                Some(table) => format!("{name} ({table})"),
+                None => name.to_string(),
+            };
+
+            sections.push(OutputSection::new(title));
+            in_section = true;
+            continue;
+        }
+
+        // a package at the root, e.g. a member of a workspace, or the table of the dependencies below it
+        if !line.starts_with(['│', ' ']) {
+            table = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'));
+            in_section = false;
+            continue;
+        }
+
+        if let Some(section) = sections.last_mut().filter(|_| in_section) {
+            section.lines.push(line.chars().skip(INDENT).collect());
+        }
+    }
+
+    sections
+}
+
+/// The text `rustc --explain` prints, cut at its code examples. Every example is a section titled by what
+/// it shows, the text before one is titled by its first line
+pub fn explain_sections(output: &str) -> Vec<OutputSection> {
+    let mut sections = vec![];
+    let mut section: Option<OutputSection> = None;
+    let mut in_code = false;
+
+    let mut finish = |section: Option<OutputSection>| {
+        if let Some(mut section) = section {
+            while section
+                .lines
+                .last()
+                .is_some_and(|line| line.trim().is_empty())
+            {
+                section.lines.pop();
+            }
+
+            sections.push(section);
+        }
+    };
+
+    for line in output.lines() {
+        if let Some(info) = line.trim_start().strip_prefix("```") {
+            finish(section.take());
+
+            if !in_code {
+                section = Some(OutputSection::new(example_title(info)));
+            }
+
+            in_code = !in_code;
+            continue;
+        }
+
+        match &mut section {
+            Some(section) => section.lines.push(line.to_string()),
+            None if line.trim().is_empty() => (),
+            None => section = Some(OutputSection::new(line.trim())),
+        }
+    }
+
+    finish(section);
+
+    sections
+}
+
+// the fence of an example says what it shows, e.g. ```compile_fail,E0382
+fn example_title(info: &str) -> &'static str {
+    let info = info.split(',').map(str::trim).collect::<Vec<_>>();
+
+    if info.contains(&"compile_fail") {
+        "Example that doesn't compile"
+    } else if info.contains(&"should_panic") {
+        "Example that panics"
+    } else {
+        "Example"
+    }
+}
+
+/// A manifest such as [`Project::manifest`](crate::Project::manifest) generates, a section for every
+/// table. The keys before the first table, e.g. `cargo-features`, are a section of their own
+pub fn manifest_sections(manifest: &str) -> Vec<OutputSection> {
+    let mut sections: Vec<OutputSection> = vec![];
+
+    for line in manifest.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            sections.push(OutputSection::new(trimmed));
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        match sections.last_mut() {
+            Some(section) => section.lines.push(line.to_string()),
+            None => sections.push(OutputSection {
+                title: "(top level)".to_string(),
+                lines: vec![line.to_string()],
+            }),
+        }
+    }
+
+    sections
+}
+
+/// The error codes in compiler output, e.g. E0382 of `error[E0382]: borrow of moved value`, each once in
+/// the order they're first seen
+pub fn error_codes(output: &str) -> Vec<&str> {
+    let mut codes = vec![];
+
+    for (start, _) in output.match_indices("error[E") {
+        let rest = &output[start + "error[".len()..];
+
+        let Some(code) = rest.split(']').next() else {
+            continue;
+        };
+
+        let digits = &code[1..];
+        let valid = digits.len() == 4 && digits.bytes().all(|b| b.is_ascii_digit());
+
+        if valid && !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(title: &str, lines: &[&str]) -> OutputSection {
+        OutputSection {
+            title: title.to_string(),
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn tree_by_direct_dependency() {
+        let output = "\
+p123 v0.1.0 (/tmp/rust-play/p123)
+├── rand v0.8.5
+│   ├── libc v0.2.139
+│   └── rand_core v0.6.4
+│       └── getrandom v0.2.8 (*)
+└── shapes v0.1.0 (/tmp/rust-play/p123/crates/shapes)
+
+[dev-dependencies]
+└── proptest v1.0.0
+    └── bitflags v1.3.2
+";
+
+        assert_eq!(
+            tree_sections(output),
+            [
+                section(
+                    "rand v0.8.5",
+                    &[
+                        "├── libc v0.2.139",
+                        "└── rand_core v0.6.4",
+                        "    └── getrandom v0.2.8 (*)"
+                    ]
+                ),
+                section("shapes v0.1.0 (/tmp/rust-play/p123/crates/shapes)", &[]),
+                section(
+                    "proptest v1.0.0 (dev-dependencies)",
+                    &["└── bitflags v1.3.2"]
+                ),
+            ]
+        );
+
+        // a root of its own isn't part of the section above it
+        let output = "a v0.1.0\n└── rand v0.8.5\n\nb v0.1.0\n└── serde v1.0.152\n";
+        assert_eq!(
+            tree_sections(output),
+            [section("rand v0.8.5", &[]), section("serde v1.0.152", &[])]
+        );
+    }
+
+    #[test]
+    fn explain_by_example() {
+        let output = "\
+A variable was used after its contents have been moved elsewhere.
+
+Erroneous code example:
+
+```compile_fail,E0382
+let a = vec![1];
+let b = a;
+```
+
+In this example, `a` was moved.
+It can be cloned instead:
+
+```
+let b = a.clone();
+```
+";
+
+        assert_eq!(
+            explain_sections(output),
+            [
+                section(
+                    "A variable was used after its contents have been moved elsewhere.",
+                    &["", "Erroneous code example:"]
+                ),
+                section(
+                    "Example that doesn't compile",
+                    &["let a = vec![1];", "let b = a;"]
+                ),
+                section(
+                    "In this example, `a` was moved.",
+                    &["It can be cloned instead:"]
+                ),
+                section("Example", &["let b = a.clone();"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn manifest_by_table() {
+        let manifest = "\
+cargo-features = [\"edition2024\"]
+
+[package]
+name = \"p123\"
+edition = \"2021\"
+
+[dependencies]
+rand = \"0.8\"
+
+[profile.release]
+";
+
+        assert_eq!(
+            manifest_sections(manifest),
+            [
+                section("(top level)", &["cargo-features = [\"edition2024\"]"]),
+                section("[package]", &["name = \"p123\"", "edition = \"2021\""]),
+                section("[dependencies]", &["rand = \"0.8\""]),
+                section("[profile.release]", &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn codes_of_errors() {
+        let output = "\
+error[E0382]: borrow of moved value: `a`
+error[E0499]: cannot borrow `v` as mutable more than once at a time
+error[E0382]: borrow of moved value: `b`
+error[internal]: not a code
+error: aborting due to 3 previous errors
+";
+
+        assert_eq!(error_codes(output), ["E0382", "E0499"]);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::cargo_command_builder::CargoCommandBuilder;
+use crate::{File, Subcommand, TargetKind};
+
+// the file which is run, and whose `//>` directives go into the Cargo.toml. The one named `main`, or
+// else the first runnable target, or else the library
+pub(crate) fn entry_file<'f, 'a>(files: &'f [File<'a>]) -> Option<&'f File<'a>> {
+    let runnable = |file: &&File| {
+        matches!(
+            file.kind,
+            TargetKind::Bin | TargetKind::Example | TargetKind::Bench
+        )
+    };
+
+    files
+        .iter()
+        .find(|file| file.name == "main")
+        .or_else(|| files.iter().find(runnable))
+        .or_else(|| files.iter().find(|file| file.kind == TargetKind::Lib))
+}
+
+// the [lib], [[bin]], [[example]] and [[bench]] tables of the files which are targets. Only the first
+// library is declared, a package can't have more
+pub(crate) fn target_tables(files: &[File]) -> String {
+    let lib = files.iter().find(|file| file.kind == TargetKind::Lib);
+
+    let targets = files.iter().filter(|file| match file.kind {
+        TargetKind::Module => false,
+        TargetKind::Lib => lib.is_some_and(|lib| lib.name == file.name),
+        _ => true,
+    });
+
+    let mut tables = String::new();
+
+    for file in targets {
+        let kind: &str = file.kind.into();
+
+        let header = match file.kind {
+            TargetKind::Lib => format!("[{kind}]"),
+            _ => format!("[[{kind}]]"),
+        };
+
+        tables.push_str(&format!(
+            "\n{header}\nname = \"{}\"\npath = \"src/{}.rs\"\n",
+            file.name, file.name
+        ));
+
+        // criterion and the like bring their own main
+        if file.kind == TargetKind::Bench {
+            tables.push_str("harness = false\n");
+        }
+    }
+
+    tables
+}
+
+// the binary of the main file is named after the package. Once [[bin]] targets sit next to it cargo can't
+// tell which binary to run, the main file's is made the default
+pub(crate) fn default_run(files: &[File]) -> bool {
+    let main_entry = entry_file(files)
+        .is_some_and(|entry| entry.name == "main" && entry.kind == TargetKind::Module);
+
+    main_entry && files.iter().any(|file| file.kind == TargetKind::Bin)
+}
+
+// run the entry file if it's a target, cargo doesn't know which one otherwise. A bench is run with
+// `cargo bench` instead
+pub(crate) fn select_target<'a>(files: &[File<'a>], builder: &mut CargoCommandBuilder<'a>) {
+    let Some(entry) = entry_file(files) else {
+        return;
+    };
+
+    if builder.subcommand != Some(Subcommand::Run) {
+        return;
+    }
+
+    match entry.kind {
+        TargetKind::Bin => {
+            builder.subcommand_flags(&["--bin", entry.name]);
+        }
+        TargetKind::Example => {
+            builder.subcommand_flags(&["--example", entry.name]);
+        }
+        TargetKind::Bench => {
+            builder
+                .subcommand(Subcommand::Bench)
+                .subcommand_flags(&["--bench", entry.name]);
+        }
+        TargetKind::Module | TargetKind::Lib => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commandline(builder: &CargoCommandBuilder) -> String {
+        let command = builder.build();
+
+        command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn entry_is_main_or_the_first_target() {
+        let files = [
+            File::new("util", ""),
+            File::new("shapes", "").target(TargetKind::Lib),
+            File::new("demo", "").target(TargetKind::Example),
+        ];
+        assert_eq!(entry_file(&files).unwrap().name, "demo");
+
+        let mut with_main = files.to_vec();
+        with_main.push(File::new("main", ""));
+        assert_eq!(entry_file(&with_main).unwrap().name, "main");
+
+        assert_eq!(entry_file(&files[..2]).unwrap().name, "shapes");
+        assert!(entry_file(&files[..1]).is_none());
+    }
+
+    #[test]
+    fn tables_of_targets() {
+        let files = [
+            File::new("main", ""),
+            File::new("util", ""),
+            File::new("shapes", "").target(TargetKind::Lib),
+            File::new("other", "").target(TargetKind::Lib),
+            File::new("server", "").target(TargetKind::Bin),
+            File::new("demo", "").target(TargetKind::Example),
+            File::new("fib", "").target(TargetKind::Bench),
+        ];
+
+        assert_eq!(
+            target_tables(&files),
+            r#"
+[lib]
+name = "shapes"
+path = "src/shapes.rs"
+
+[[bin]]
+name = "server"
+path = "src/server.rs"
+
+[[example]]
+name = "demo"
+path = "src/demo.rs"
+
+[[bench]]
+name = "fib"
+path = "src/fib.rs"
+harness = false
+"#
+        );
+
+        assert_eq!(target_tables(&files[..2]), "");
+    }
+
+    #[test]
+    fn main_runs_by_default() {
+        let server = File::new("server", "").target(TargetKind::Bin);
+
+        assert!(default_run(&[File::new("main", ""), server]));
+        assert!(!default_run(&[File::new("main", "")]));
+        // the bin is the entry, it's picked with --bin
+        assert!(!default_run(&[server]));
+        assert!(!default_run(&[
+            File::new("main", "").target(TargetKind::Bin),
+            server
+        ]));
+    }
+
+    #[test]
+    fn runs_the_entry() {
+        let run = |files: &[File]| {
+            let mut builder = CargoCommandBuilder::new();
+            builder.subcommand(Subcommand::Run);
+            select_target(files, &mut builder);
+
+            commandline(&builder)
+        };
+
+        assert_eq!(run(&[File::new("main", "")]), "run");
+        assert_eq!(
+            run(&[File::new("server", "").target(TargetKind::Bin)]),
+            "run --bin server"
+        );
+        assert_eq!(
+            run(&[File::new("demo", "").target(TargetKind::Example)]),
+            "run --example demo"
+        );
+        assert_eq!(
+            run(&[File::new("fib", "").target(TargetKind::Bench)]),
+            "bench --bench fib"
+        );
+
+        // only what cargo runs needs picking
+        let mut builder = CargoCommandBuilder::new();
+        builder.subcommand(Subcommand::Test);
+        select_target(
+            &[File::new("demo", "").target(TargetKind::Example)],
+            &mut builder,
+        );
+        assert_eq!(commandline(&builder), "test");
+    }
+}
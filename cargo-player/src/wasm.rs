@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::{BuildType, Project, ProjectError};
+
+/// Target triple for running in the browser
+pub const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+impl<'a> Project<'a> {
+    /// Where the web bundle (index.html, js bindings and wasm) is generated
+    pub fn wasm_out_dir(&self) -> PathBuf {
+        self.project_dir().join("pkg")
+    }
+
+    /// The wasm file cargo produces when building for [`WASM_TARGET`]
+    pub fn wasm_artifact(&self) -> PathBuf {
+        let profile = match self.cargo_command_builder.build_type {
+            Some(BuildType::Release) => "release",
+            _ => "debug",
+        };
+
+        self.project_dir()
+            .join("target")
+            .join(WASM_TARGET)
+            .join(profile)
+            .join(format!("{}.wasm", self.package()))
+    }
+
+    /// Command generating the js bindings of the wasm build with wasm-bindgen, which must be installed.
+    /// Also writes an index.html loading it into the output dir. Run it after building the project
+    pub fn wasm_bindgen(&self) -> Result<Command, ProjectError> {
+        let out_dir = self.wasm_out_dir();
+        fs::create_dir_all(&out_dir)?;
+        fs::write(out_dir.join("index.html"), self.index_html())?;
+
+        let mut command = Command::new("wasm-bindgen");
+        command
+            .args(["--target", "web", "--no-typescript", "--out-dir"])
+            .arg(&out_dir)
+            .arg(self.wasm_artifact())
+            .envs(self.env.clone());
+
+        Ok(command)
+    }
+
+    fn index_html(&self) -> String {
+        let name = self.package();
+
+        // eframe apps look for a canvas with this id by convention
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>{name}</title>
+    <style>
+        html, body {{ margin: 0; width: 100%; height: 100%; }}
+        #the_canvas_id {{ width: 100%; height: 100%; }}
+    </style>
+</head>
+<body>
+    <canvas id="the_canvas_id"></canvas>
+    <script type="module">
+        import init from "./{name}.js";
+        init();
+    </script>
+</body>
+</html>
+"#
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wasm_paths() {
+        let mut project = Project::new("wasm");
+        project.target_prefix("test").build_type(BuildType::Release);
+
+        let dir = project.project_dir();
+        let name = format!("p{}", project.hash);
+
+        assert_eq!(dir.join("pkg"), project.wasm_out_dir());
+        assert_eq!(
+            dir.join("target")
+                .join(WASM_TARGET)
+                .join("release")
+                .join(format!("{name}.wasm")),
+            project.wasm_artifact()
+        );
+        assert!(project
+            .index_html()
+            .contains(&format!("import init from \"./{name}.js\"")));
+
+        // the wasm file is named after the package
+        project.package_name("my-app");
+        assert!(project.wasm_artifact().ends_with("release/my-app.wasm"));
+    }
+}
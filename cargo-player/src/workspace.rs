@@ -0,0 +1,308 @@
+use std::path::PathBuf;
+
+use crate::directives::{read_directives, DirectiveLine};
+use crate::infer::InferredDeps;
+use crate::{Edition, File, Project};
+
+// where the members go, relative to the main crate
+const MEMBERS_DIR: &str = "crates";
+
+/// What a member of a workspace project is built as
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CrateKind {
+    /// A library, its root file is `lib`
+    #[default]
+    Lib,
+    /// A proc-macro library, its root file is `lib`. `proc_macro` is in scope
+    ProcMacro,
+    /// A binary of its own, its root file is `main`
+    Bin,
+}
+
+impl CrateKind {
+    // only libraries can be depended on
+    fn is_lib(self) -> bool {
+        self != Self::Bin
+    }
+}
+
+/// A crate of a workspace project besides the main one, e.g. a proc-macro crate the main file uses.
+/// It's a dependency of the main crate, and of the other members using it
+#[derive(Debug, Clone)]
+pub struct Crate<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) kind: CrateKind,
+    pub(crate) files: Vec<File<'a>>,
+}
+
+impl<'a> Crate<'a> {
+    pub fn new(name: &'a str, kind: CrateKind, files: &[File<'a>]) -> Self {
+        Self {
+            name,
+            kind,
+            files: files.to_vec(),
+        }
+    }
+
+    // the file its //> directives are read from, like the main file of the main crate
+    fn root_file(&self) -> Option<&File<'a>> {
+        let root = match self.kind {
+            CrateKind::Lib | CrateKind::ProcMacro => "lib",
+            CrateKind::Bin => "main",
+        };
+
+        self.files.iter().find(|file| file.name == root)
+    }
+}
+
+impl<'a> Project<'a> {
+    /// Add a crate to the project (append). The project is generated as a cargo workspace of the main
+    /// crate and its members then, which allows playing with proc macros and crate boundaries
+    pub fn member(&mut self, member: Crate<'a>) -> &mut Self {
+        self.members.push(member);
+        self
+    }
+
+    /// Add crates to the project (appends slice)
+    pub fn members(&mut self, members: &[Crate<'a>]) -> &mut Self {
+        self.members.extend_from_slice(members);
+        self
+    }
+
+    /// The directory a member of the project is created in
+    pub fn member_dir(&self, name: &str) -> PathBuf {
+        self.project_dir().join(MEMBERS_DIR).join(name)
+    }
+}
+
+// - and _ name the same crate
+fn same_crate(a: &str, b: &str) -> bool {
+    let normalize = |b: u8| if b == b'-' { b'_' } else { b };
+
+    a.bytes().map(normalize).eq(b.bytes().map(normalize))
+}
+
+// the name of a dependency line, e.g. `rand` of `rand = "0.8"`
+fn dependency_name(line: &str) -> &str {
+    line.split('=').next().unwrap_or_default().trim()
+}
+
+// the members a table of dependencies uses. Their lines are inferred like any other crate's
+fn used_members<'m, 'a>(
+    dependencies: &'m str,
+    members: &'m [Crate<'a>],
+) -> impl Iterator<Item = &'m Crate<'a>> {
+    members.iter().filter(|member| {
+        member.kind.is_lib()
+            && dependencies
+                .lines()
+                .any(|line| same_crate(dependency_name(line), member.name))
+    })
+}
+
+// the lines of a table with the ones naming a member taken out, they'd be looked for on crates.io
+fn without_members<'d>(dependencies: &'d str, members: &[Crate]) -> Vec<&'d str> {
+    dependencies
+        .lines()
+        .filter(|line| {
+            let name = dependency_name(line);
+            !members.iter().any(|member| same_crate(name, member.name))
+        })
+        .collect()
+}
+
+fn path_dependency(member: &Crate, dir: &str) -> String {
+    format!(r#"{} = {{ path = "{dir}/{}" }}"#, member.name, member.name)
+}
+
+// the lines of the main crate's dependency tables, with a path dependency on every library member
+pub(crate) fn link_main(dependencies: &InferredDeps, members: &[Crate]) -> InferredDeps {
+    let mut lines = without_members(&dependencies.dependencies, members)
+        .into_iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    lines.extend(
+        members
+            .iter()
+            .filter(|member| member.kind.is_lib())
+            .map(|member| path_dependency(member, MEMBERS_DIR)),
+    );
+
+    InferredDeps {
+        dependencies: lines.join("\n"),
+        dev_dependencies: without_members(&dependencies.dev_dependencies, members).join("\n"),
+        build_dependencies: dependencies.build_dependencies.clone(),
+    }
+}
+
+// the [workspace] table added to the main crate's Cargo.toml, which is a member as well
+pub(crate) fn workspace_table(members: &[Crate]) -> String {
+    let members = members
+        .iter()
+        .map(|member| format!(r#""{MEMBERS_DIR}/{}""#, member.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("\n[workspace]\nmembers = [{members}]\n")
+}
+
+// the Cargo.toml of a member. It only depends on the members it uses, depending on every one of them
+// would make members depend on each other
+pub(crate) fn member_cargo_toml(
+    member: &Crate,
+    dependencies: &InferredDeps,
+    edition: Edition,
+    members: &[Crate],
+) -> String {
+    let others = members
+        .iter()
+        .filter(|other| other.name != member.name)
+        .collect::<Vec<_>>();
+
+    let mut lines = without_members(&dependencies.dependencies, members)
+        .into_iter()
+        .map(str::to_string)
+        // the compiler provides it to proc-macro crates, it isn't on crates.io
+        .filter(|line| {
+            member.kind != CrateKind::ProcMacro || !same_crate(dependency_name(line), "proc_macro")
+        })
+        .collect::<Vec<_>>();
+
+    lines.extend(
+        used_members(&dependencies.dependencies, members)
+            .filter(|used| others.iter().any(|other| other.name == used.name))
+            .map(|used| path_dependency(used, "..")),
+    );
+
+    let mut formatted = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"{edition}\"\n",
+        member.name
+    );
+
+    if member.kind == CrateKind::ProcMacro {
+        formatted.push_str("\n[lib]\nproc-macro = true\n");
+    }
+
+    formatted.push_str(&format!("\n[dependencies]\n{}\n", lines.join("\n")));
+
+    for (table, lines) in [
+        (
+            "dev-dependencies",
+            without_members(&dependencies.dev_dependencies, members),
+        ),
+        (
+            "build-dependencies",
+            dependencies.build_dependencies.lines().collect(),
+        ),
+    ] {
+        if lines.is_empty() {
+            continue;
+        }
+
+        formatted.push_str(&format!("\n[{table}]\n{}\n", lines.join("\n")));
+    }
+
+    let extra_cargo = member
+        .root_file()
+        .into_iter()
+        .flat_map(|file| read_directives(file.code))
+        .filter_map(|directive| match directive {
+            DirectiveLine::CargoToml(toml) => Some(toml),
+            DirectiveLine::Dependency(..) => None,
+        })
+        .collect::<Vec<_>>();
+
+    if !extra_cargo.is_empty() {
+        formatted.push('\n');
+        formatted.push_str(&extra_cargo.join("\n"));
+        formatted.push('\n');
+    }
+
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crate_names() {
+        assert!(same_crate("my-macros", "my_macros"));
+        assert!(same_crate("serde", "serde"));
+        assert!(!same_crate("serde", "serde_json"));
+    }
+
+    #[test]
+    fn main_depends_on_library_members() {
+        let members = [
+            Crate::new("my-macros", CrateKind::ProcMacro, &[]),
+            Crate::new("shapes", CrateKind::Lib, &[]),
+            Crate::new("server", CrateKind::Bin, &[]),
+        ];
+
+        let dependencies = InferredDeps {
+            dependencies: "rand = \"0.8\"\nmy_macros = \"*\"".to_string(),
+            dev_dependencies: "shapes = \"*\"\nproptest = \"*\"".to_string(),
+            build_dependencies: String::new(),
+        };
+
+        assert_eq!(
+            link_main(&dependencies, &members),
+            InferredDeps {
+                dependencies: "rand = \"0.8\"\nmy-macros = { path = \"crates/my-macros\" }\nshapes = { path = \"crates/shapes\" }".to_string(),
+                dev_dependencies: "proptest = \"*\"".to_string(),
+                build_dependencies: String::new(),
+            }
+        );
+
+        assert_eq!(
+            workspace_table(&members),
+            "\n[workspace]\nmembers = [\"crates/my-macros\", \"crates/shapes\", \"crates/server\"]\n"
+        );
+    }
+
+    #[test]
+    fn members_depend_on_the_members_they_use() {
+        let lib = "//> [lints.rust]\n//> unsafe_code = \"forbid\"\nuse proc_macro::TokenStream;\n";
+        let members = [
+            Crate::new("my-macros", CrateKind::ProcMacro, &[File::new("lib", lib)]),
+            Crate::new("shapes", CrateKind::Lib, &[]),
+        ];
+
+        let dependencies = InferredDeps {
+            dependencies: "proc_macro = \"*\"\nquote = \"1\"\nshapes = \"*\"".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            member_cargo_toml(&members[0], &dependencies, Edition::E2021, &members),
+            r#"[package]
+name = "my-macros"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+proc-macro = true
+
+[dependencies]
+quote = "1"
+shapes = { path = "../shapes" }
+
+[lints.rust]
+unsafe_code = "forbid"
+"#
+        );
+
+        // it doesn't depend on itself
+        let dependencies = InferredDeps {
+            dependencies: "shapes = \"*\"".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            member_cargo_toml(&members[1], &dependencies, Edition::E2018, &members),
+            "[package]\nname = \"shapes\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[dependencies]\n\n"
+        );
+    }
+}
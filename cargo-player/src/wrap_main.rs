@@ -0,0 +1,106 @@
+use syn::{Block, Item, Stmt};
+
+use crate::Project;
+
+impl<'a> Project<'a> {
+    /// Put the statements of a main file without a `fn main` into one, the way the playground does, so
+    /// an expression or two runs as it is. Code with a main, with only items, or which doesn't parse is
+    /// left alone
+    pub fn wrap_main(&mut self) -> &mut Self {
+        self.wrap_main = true;
+        self
+    }
+}
+
+// the code with its statements in a generated main, None if it doesn't need one. The lines aren't
+// indented so the columns of errors match the scratch, and the lines are only off by the one added
+pub(crate) fn wrap_in_main(code: &str) -> Option<String> {
+    // items can be declared in a block too, so it parses whichever way the code is written
+    let block = syn::parse_str::<Block>(&format!("{{\n{code}\n}}")).ok()?;
+
+    let has_main = block
+        .stmts
+        .iter()
+        .any(|stmt| matches!(stmt, Stmt::Item(Item::Fn(function)) if function.sig.ident == "main"));
+
+    let has_statements = block.stmts.iter().any(|stmt| match stmt {
+        // macro_rules! has a name, a macro call like println! doesn't
+        Stmt::Item(Item::Macro(item)) => item.ident.is_none(),
+        Stmt::Item(_) => false,
+        Stmt::Local(_) | Stmt::Expr(_) | Stmt::Semi(..) => true,
+    });
+
+    if has_main || !has_statements {
+        return None;
+    }
+
+    // the directives and one line imports stay above it, a `mod a;` can't be declared in a function
+    let hoisted = code
+        .lines()
+        .take_while(|line| {
+            let line = line.trim();
+
+            line.is_empty()
+                || line.starts_with("//")
+                || ["use ", "pub use ", "extern crate ", "mod "]
+                    .iter()
+                    .any(|start| line.starts_with(start) && line.ends_with(';'))
+        })
+        .count();
+
+    let lines = code.lines().collect::<Vec<_>>();
+
+    let mut wrapped = String::new();
+
+    for line in &lines[..hoisted] {
+        wrapped.push_str(line);
+        wrapped.push('\n');
+    }
+
+    wrapped.push_str("fn main() {\n");
+
+    for line in &lines[hoisted..] {
+        wrapped.push_str(line);
+        wrapped.push('\n');
+    }
+
+    wrapped.push_str("}\n");
+
+    Some(wrapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_are_wrapped() {
+        assert_eq!(
+            wrap_in_main("//# rand = \"0.8\"\nuse rand::random;\n\nlet x: u8 = random();\nprintln!(\"{x}\");")
+                .as_deref(),
+            Some("//# rand = \"0.8\"\nuse rand::random;\n\nfn main() {\nlet x: u8 = random();\nprintln!(\"{x}\");\n}\n")
+        );
+
+        // items and statements mixed
+        assert_eq!(
+            wrap_in_main("struct A;\nlet a = A;").as_deref(),
+            Some("fn main() {\nstruct A;\nlet a = A;\n}\n")
+        );
+    }
+
+    #[test]
+    fn programs_are_not_wrapped() {
+        assert_eq!(wrap_in_main("fn main() {\n    println!(\"hi\");\n}"), None);
+        assert_eq!(
+            wrap_in_main("#[tokio::main]\nasync fn main() {}\n\nfn f() {}"),
+            None
+        );
+
+        // a library, or code which doesn't parse, is cargo's to complain about
+        assert_eq!(
+            wrap_in_main("macro_rules! a { () => {} }\n\npub fn f() {}"),
+            None
+        );
+        assert_eq!(wrap_in_main("let x = ;"), None);
+    }
+}
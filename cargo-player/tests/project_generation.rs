@@ -0,0 +1,679 @@
+//! Generates projects the way the playground does and checks what ends up on disk. The tests which
+//! compile a scratch need a toolchain and are ignored by default, run them with `cargo test -- --ignored`
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use cargo_player::{
+    validate_name, Crate, CrateKind, Edition, File, Message, NameError, Project, ProjectError,
+    Subcommand, TargetKind, LOOM_DEPENDENCY,
+};
+
+// keeps the projects of the tests apart from the ones of the playground
+const PREFIX: &str = "cargo-player-test";
+
+/// A generated project, removed again when dropped
+struct Generated {
+    dir: PathBuf,
+    command: Command,
+}
+
+impl Generated {
+    fn new(name: &str, files: &[File], configure: impl FnOnce(&mut Project)) -> Self {
+        let mut project = Project::new(name);
+        project
+            .files(files)
+            .target_prefix(PREFIX)
+            .subcommand(Subcommand::Build);
+
+        configure(&mut project);
+
+        let command = project.create().expect("failed to create the project");
+        let dir = command
+            .get_current_dir()
+            .expect("the command runs in the project")
+            .to_path_buf();
+
+        Self { dir, command }
+    }
+
+    fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.dir.join(path)).unwrap()
+    }
+
+    // the name cargo knows the package by, `p` and the hash the directory ends in
+    fn package(&self) -> String {
+        let hash = self.dir.extension().unwrap().to_str().unwrap();
+        format!("p{hash}")
+    }
+
+    fn cargo_toml(&self, edition: &str, dependencies: &str) -> String {
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"{edition}\"\n\n[dependencies]\n{dependencies}\n",
+            self.package()
+        )
+    }
+
+    // every file of the project, relative to it and sorted
+    fn layout(&self) -> Vec<String> {
+        let mut files = vec![];
+        walk(&self.dir, &self.dir, &mut files);
+        files.sort();
+
+        files
+    }
+}
+
+impl Drop for Generated {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            walk(root, &path, files);
+        } else {
+            let relative = path.strip_prefix(root).unwrap();
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy());
+            files.push(relative.collect::<Vec<_>>().join("/"));
+        }
+    }
+}
+
+#[test]
+fn without_directives() {
+    let code = "fn main() {\n    println!(\"hi\");\n}\n";
+    let project = Generated::new("without_directives", &[File::new("main", code)], |_| {});
+
+    assert_eq!(project.read("Cargo.toml"), project.cargo_toml("2021", ""));
+    assert_eq!(project.layout(), ["Cargo.toml", "src/main.rs"]);
+    assert_eq!(project.read("src/main.rs"), code);
+}
+
+#[test]
+fn inferred_and_listed_dependencies() {
+    let code = r#"//# serde = { version = "1", features = ["derive"] }
+use std::fs;
+use rand::Rng;
+
+fn main() {}
+"#;
+    let project = Generated::new("inferred_and_listed", &[File::new("main", code)], |_| {});
+
+    // listed ones come first, inferred ones take any version
+    assert_eq!(
+        project.read("Cargo.toml"),
+        project.cargo_toml(
+            "2021",
+            "serde = { version = \"1\", features = [\"derive\"] }\nrand = \"*\""
+        )
+    );
+}
+
+#[test]
+fn listed_dependencies_override_inferred_ones() {
+    let code = r#"//# rand = "0.8"
+//# regex-lite = "0.1"
+use rand::Rng;
+use regex_lite::Regex;
+
+fn main() {}
+"#;
+    let project = Generated::new("overrides", &[File::new("main", code)], |_| {});
+
+    // - and _ name the same crate
+    assert_eq!(
+        project.read("Cargo.toml"),
+        project.cargo_toml("2021", "regex-lite = \"0.1\"\nrand = \"0.8\"")
+    );
+}
+
+#[test]
+fn directives_only_at_the_top() {
+    let code = r#"//! Comments may come first
+//# rand = "0.8"
+
+// and between them
+//# itoa = "1"
+fn main() {}
+//# serde = "1"
+"#;
+    let project = Generated::new("directives_at_top", &[File::new("main", code)], |_| {});
+
+    assert_eq!(
+        project.read("Cargo.toml"),
+        project.cargo_toml("2021", "itoa = \"1\"\nrand = \"0.8\"")
+    );
+}
+
+#[test]
+fn dev_and_build_dependencies() {
+    let code = r#"//#dev proptest = "1"
+//#build cc = "1"
+//> [profile.dev]
+//> opt-level = 1
+use rand::Rng;
+
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use quickcheck::quickcheck;
+}
+"#;
+    let project = Generated::new("dev_and_build", &[File::new("main", code)], |_| {});
+
+    let expected = format!(
+        "{}\n[dev-dependencies]\nproptest = \"1\"\nquickcheck = \"*\"\n\n[build-dependencies]\ncc = \"1\"\n\n[profile.dev]\nopt-level = 1\n",
+        project.cargo_toml("2021", "rand = \"*\"")
+    );
+    assert_eq!(project.read("Cargo.toml"), expected);
+}
+
+#[test]
+fn extra_cargo_toml() {
+    let code = r#"//# rand = "0.8"
+//> [profile.dev]
+//> opt-level = 1
+fn main() {}
+"#;
+    let project = Generated::new("extra_cargo_toml", &[File::new("main", code)], |_| {});
+
+    let expected = format!(
+        "{}\n[profile.dev]\nopt-level = 1\n",
+        project.cargo_toml("2021", "rand = \"0.8\"")
+    );
+    assert_eq!(project.read("Cargo.toml"), expected);
+}
+
+#[test]
+fn multiple_files() {
+    let main = r#"//> [profile.release]
+//> debug = true
+mod util;
+use util::double;
+
+fn main() {
+    println!("{}", double(2));
+}
+"#;
+    let util = r#"//# itoa = "1"
+//> [profile.dev]
+//> opt-level = 3
+use rand::random;
+
+pub fn double(x: u8) -> u8 {
+    x * 2 + random::<u8>() % 1
+}
+"#;
+    let project = Generated::new(
+        "multiple_files",
+        &[File::new("main", main), File::new("util", util)],
+        |_| {},
+    );
+
+    // modules aren't dependencies, and only the main file adds to the Cargo.toml
+    let expected = format!(
+        "{}\n[profile.release]\ndebug = true\n",
+        project.cargo_toml("2021", "itoa = \"1\"\nrand = \"*\"")
+    );
+    assert_eq!(project.read("Cargo.toml"), expected);
+
+    assert_eq!(
+        project.layout(),
+        ["Cargo.toml", "src/main.rs", "src/util.rs"]
+    );
+    assert_eq!(project.read("src/util.rs"), util);
+}
+
+#[test]
+fn editions() {
+    for (edition, name) in [
+        (Edition::E2015, "2015"),
+        (Edition::E2018, "2018"),
+        (Edition::E2021, "2021"),
+    ] {
+        let project = Generated::new(
+            &format!("edition_{name}"),
+            &[File::new("main", "fn main() {}")],
+            |project| {
+                project.edition(edition);
+            },
+        );
+
+        assert_eq!(project.read("Cargo.toml"), project.cargo_toml(name, ""));
+    }
+}
+
+#[test]
+fn prefixes() {
+    let mut project = Project::new("prefixes");
+    assert!(project
+        .project_dir()
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("cargo-play."));
+
+    project.target_prefix("scratch");
+    let dir = project.project_dir();
+    let name = dir.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("scratch."));
+
+    let generated = Generated::new("prefixes", &[File::new("main", "fn main() {}")], |_| {});
+    let name = generated.dir.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with(&format!("{PREFIX}.")));
+
+    // the hash is what tells projects apart, the prefix only where they go
+    assert_eq!(
+        name.strip_prefix(PREFIX),
+        dir.file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .strip_prefix("scratch")
+    );
+}
+
+#[test]
+fn package_names() {
+    let project = Generated::new(
+        "package_names",
+        &[File::new("main", "fn main() {}")],
+        |project| {
+            project.package_name("my-scratch");
+        },
+    );
+
+    assert!(project.read("Cargo.toml").contains("name = \"my-scratch\""));
+
+    assert_eq!(validate_name("my-scratch_2"), Ok(()));
+    assert_eq!(validate_name(""), Err(NameError::Empty));
+    assert_eq!(validate_name("2d"), Err(NameError::StartsWithDigit));
+    assert_eq!(
+        validate_name("my scratch"),
+        Err(NameError::InvalidChar(' '))
+    );
+    assert_eq!(validate_name("ünicode"), Err(NameError::InvalidChar('ü')));
+    assert_eq!(
+        validate_name("fn"),
+        Err(NameError::Keyword("fn".to_string()))
+    );
+
+    for name in ["deps", "build", "examples", "incremental"] {
+        assert_eq!(
+            validate_name(name),
+            Err(NameError::Reserved(name.to_string()))
+        );
+    }
+    assert_eq!(validate_name("build-tool"), Ok(()));
+}
+
+#[test]
+fn base_dirs() {
+    let base_dir = std::env::temp_dir().join("cargo-player-test-base-dir");
+    let _ = fs::remove_dir_all(&base_dir);
+
+    let mut project = Project::new("base_dir");
+    project
+        .file(File::new("main", "fn main() {}"))
+        .target_prefix(PREFIX)
+        .subcommand(Subcommand::Build)
+        .base_dir(&base_dir);
+
+    // the directory is created along with the project
+    let command = project.create().expect("failed to create the project");
+    let dir = command.get_current_dir().unwrap();
+    assert_eq!(dir.parent(), Some(base_dir.as_path()));
+    assert!(dir.join("src").join("main.rs").exists());
+
+    let _ = fs::remove_dir_all(&base_dir);
+}
+
+#[test]
+fn manifests_without_writing() {
+    let mut project = Project::new("manifests_without_writing");
+    project
+        .file(File::new(
+            "main",
+            "//> [profile.dev]\n//> opt-level = 1\nfn main() {}",
+        ))
+        .target_prefix(PREFIX)
+        .subcommand(Subcommand::Build);
+
+    let _ = fs::remove_dir_all(project.project_dir());
+
+    let manifest = project.manifest().expect("failed to generate the manifest");
+    assert!(manifest.ends_with("\n[profile.dev]\nopt-level = 1\n"));
+    assert_eq!(project.path(), None);
+
+    project.create().expect("failed to create the project");
+    let dir = project.path().expect("the project was created");
+
+    assert_eq!(dir, project.project_dir());
+    assert_eq!(
+        fs::read_to_string(dir.join("Cargo.toml")).unwrap(),
+        manifest
+    );
+
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn creation_errors() {
+    let create = |files: &[File], configure: fn(&mut Project)| {
+        let mut project = Project::new("creation_errors");
+        project.files(files).target_prefix(PREFIX);
+
+        configure(&mut project);
+
+        project.create()
+    };
+
+    let main = [File::new("main", "fn main() {}")];
+
+    assert!(matches!(
+        create(&main, |_| ()),
+        Err(ProjectError::NoSubcommand)
+    ));
+    assert!(matches!(
+        create(&[File::new("util", "")], |project| {
+            project.subcommand(Subcommand::Build);
+        }),
+        Err(ProjectError::MissingMainFile)
+    ));
+    assert!(matches!(
+        create(&main, |project| {
+            project.subcommand(Subcommand::Build).package_name("2d");
+        }),
+        Err(ProjectError::TomlGeneration(_))
+    ));
+    assert!(matches!(
+        create(&main, |project| {
+            project.subcommand(Subcommand::Build).member(Crate::new(
+                "my macros",
+                CrateKind::ProcMacro,
+                &[],
+            ));
+        }),
+        Err(ProjectError::TomlGeneration(_))
+    ));
+}
+
+#[test]
+fn files_are_written_with_lf() {
+    let code = "//> [profile.dev]\r\n//> opt-level = 1\r\nfn main() {}\r\n";
+    let project = Generated::new("crlf", &[File::new("main", code)], |_| {});
+
+    assert_eq!(
+        project.read("src/main.rs"),
+        "//> [profile.dev]\n//> opt-level = 1\nfn main() {}\n"
+    );
+    assert!(project
+        .read("Cargo.toml")
+        .ends_with("\n[profile.dev]\nopt-level = 1\n"));
+}
+
+#[test]
+fn scripts() {
+    let code = r#"#!/usr/bin/env rust-script
+//! Prints a random number
+//!
+//! ```cargo
+//! [package]
+//! edition = "2018"
+//!
+//! [dependencies]
+//! rand = "0.8"
+//!
+//! [profile.dev]
+//! opt-level = 1
+//! ```
+#![allow(unused)]
+use rand::random;
+
+fn main() {}
+"#;
+    let project = Generated::new("scripts", &[File::new("main", code)], |_| {});
+
+    // the manifest's package is left out, the project has its own
+    let expected = format!(
+        "{}\n[profile.dev]\nopt-level = 1\n",
+        project.cargo_toml("2021", "rand = \"0.8\"")
+    );
+    assert_eq!(project.read("Cargo.toml"), expected);
+
+    // the shebang's line stays, so the line numbers of diagnostics still match
+    let main = project.read("src/main.rs");
+    assert_eq!(main, code.replacen("#!/usr/bin/env rust-script", "", 1));
+    assert!(main.starts_with("\n//! Prints"));
+}
+
+#[test]
+fn loom_harness() {
+    let code = "fn main() {}\n";
+    let project = Generated::new("loom", &[File::new("main", code)], |project| {
+        project.loom(None);
+    });
+
+    let expected = format!(
+        "{}\n[dev-dependencies]\n{LOOM_DEPENDENCY}\n",
+        project.cargo_toml("2021", "")
+    );
+    assert_eq!(project.read("Cargo.toml"), expected);
+
+    let main = project.read("src/main.rs");
+    assert!(main.starts_with(code));
+    assert!(main.contains("loom::model"));
+}
+
+#[test]
+fn targets() {
+    let shapes = "pub fn area(w: u32, h: u32) -> u32 {\n    w * h\n}\n";
+    let demo = "fn main() {\n    println!(\"{}\", shapes::area(2, 3));\n}\n";
+    let project = Generated::new(
+        "targets",
+        &[
+            File::new("shapes", shapes).target(TargetKind::Lib),
+            File::new("demo", demo).target(TargetKind::Example),
+        ],
+        |project| {
+            project.subcommand(Subcommand::Run);
+        },
+    );
+
+    // without a main file, the example is the one run
+    let expected = format!(
+        "{}\n[lib]\nname = \"shapes\"\npath = \"src/shapes.rs\"\n\n[[example]]\nname = \"demo\"\npath = \"src/demo.rs\"\n",
+        project.cargo_toml("2021", "")
+    );
+    assert_eq!(project.read("Cargo.toml"), expected);
+    assert_eq!(
+        project.layout(),
+        ["Cargo.toml", "src/demo.rs", "src/shapes.rs"]
+    );
+
+    let args = project
+        .command
+        .get_args()
+        .map(|arg| arg.to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(args, ["run", "--example", "demo"]);
+}
+
+#[test]
+fn main_next_to_bins() {
+    let project = Generated::new(
+        "main_next_to_bins",
+        &[
+            File::new("main", "fn main() {}"),
+            File::new("server", "fn main() {}").target(TargetKind::Bin),
+        ],
+        |project| {
+            project.subcommand(Subcommand::Run);
+        },
+    );
+
+    // cargo would ask which binary to run otherwise
+    let expected = format!(
+        "[package]\nname = \"{0}\"\nversion = \"0.1.0\"\nedition = \"2021\"\ndefault-run = \"{0}\"\n\n\
+         [dependencies]\n\n\n[[bin]]\nname = \"server\"\npath = \"src/server.rs\"\n",
+        project.package()
+    );
+    assert_eq!(project.read("Cargo.toml"), expected);
+
+    let args = project
+        .command
+        .get_args()
+        .map(|arg| arg.to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(args, ["run"]);
+}
+
+const MACROS: &str = r#"use proc_macro::TokenStream;
+
+#[proc_macro]
+pub fn answer(_: TokenStream) -> TokenStream {
+    "42".parse().unwrap()
+}
+"#;
+
+#[test]
+fn workspace_members() {
+    let main = "use my_macros::answer;\n\nfn main() {\n    println!(\"{}\", answer!());\n}\n";
+    let project = Generated::new("workspace", &[File::new("main", main)], |project| {
+        project.member(Crate::new(
+            "my-macros",
+            CrateKind::ProcMacro,
+            &[File::new("lib", MACROS)],
+        ));
+    });
+
+    // the member is used like any other crate, but found by path instead of on crates.io
+    let expected = format!(
+        "{}\n[workspace]\nmembers = [\"crates/my-macros\"]\n",
+        project.cargo_toml("2021", "my-macros = { path = \"crates/my-macros\" }")
+    );
+    assert_eq!(project.read("Cargo.toml"), expected);
+
+    assert_eq!(
+        project.layout(),
+        [
+            "Cargo.toml",
+            "crates/my-macros/Cargo.toml",
+            "crates/my-macros/src/lib.rs",
+            "src/main.rs"
+        ]
+    );
+    assert_eq!(
+        project.read("crates/my-macros/Cargo.toml"),
+        "[package]\nname = \"my-macros\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[lib]\nproc-macro = true\n\n[dependencies]\n\n"
+    );
+    assert_eq!(project.read("crates/my-macros/src/lib.rs"), MACROS);
+}
+
+#[test]
+#[ignore = "compiles with the installed toolchain"]
+fn runs_a_scratch() {
+    let code = "fn main() {\n    println!(\"hello from a scratch\");\n}\n";
+    let mut project = Generated::new("runs_a_scratch", &[File::new("main", code)], |project| {
+        project.subcommand(Subcommand::Run);
+    });
+
+    let output = project.command.output().expect("failed to run cargo");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "hello from a scratch\n"
+    );
+}
+
+#[test]
+#[ignore = "compiles with the installed toolchain"]
+fn builds_with_json_messages() {
+    let code = "fn main() {\n    let unused = 1;\n}\n";
+    let mut project = Generated::new("json_messages", &[File::new("main", code)], |project| {
+        project.message_format_json();
+    });
+
+    let output = project.command.output().expect("failed to run cargo");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let messages = stdout.lines().map(Message::parse).collect::<Vec<_>>();
+
+    // the layout of cargo's messages is what the playground reads its diagnostics from
+    assert!(messages.iter().any(|message| matches!(
+        message,
+        Message::CompilerMessage(diagnostic) if diagnostic.message.contains("unused")
+    )));
+    assert!(messages
+        .iter()
+        .any(|message| matches!(message, Message::CompilerArtifact(artifact) if artifact.executable.is_some())));
+    assert_eq!(
+        messages.last(),
+        Some(&Message::BuildFinished { success: true })
+    );
+}
+
+#[test]
+#[ignore = "compiles with the installed toolchain"]
+fn runs_a_workspace() {
+    let main = "fn main() {\n    println!(\"{}\", my_macros::answer!());\n}\n";
+    let mut project = Generated::new("runs_a_workspace", &[File::new("main", main)], |project| {
+        project
+            .member(Crate::new(
+                "my-macros",
+                CrateKind::ProcMacro,
+                &[File::new("lib", MACROS)],
+            ))
+            .subcommand(Subcommand::Run);
+    });
+
+    let output = project.command.output().expect("failed to run cargo");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+}
+
+#[test]
+#[ignore = "compiles with the installed toolchain"]
+fn runs_an_example() {
+    let shapes = "pub fn area(w: u32, h: u32) -> u32 {\n    w * h\n}\n";
+    let demo = "fn main() {\n    println!(\"{}\", shapes::area(2, 3));\n}\n";
+    let mut project = Generated::new(
+        "runs_an_example",
+        &[
+            File::new("shapes", shapes).target(TargetKind::Lib),
+            File::new("demo", demo).target(TargetKind::Example),
+        ],
+        |project| {
+            project.subcommand(Subcommand::Run);
+        },
+    );
+
+    let output = project.command.output().expect("failed to run cargo");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "6\n");
+}
@@ -1,4 +1,12 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
 fn main() {
+    embed_git_hash();
+    embed_third_party_licenses();
+
     if cfg!(target_os = "windows") {
         let mut res = winres::WindowsResource::new();
         res.set_icon_with_id("corro.ico", "corro");
@@ -24,3 +32,66 @@ fn main() {
         let _ = res.compile();
     }
 }
+
+// short commit hash of the build, shown in the about dialog
+fn embed_git_hash() {
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=RUST_PLAY_GIT_HASH={hash}");
+}
+
+// a "name version - license" line for every dependency, shown in the about dialog
+fn embed_third_party_licenses() {
+    println!("cargo:rerun-if-changed=../Cargo.lock");
+
+    let out_file = PathBuf::from(env::var("OUT_DIR").unwrap()).join("third_party_licenses.txt");
+    let manifest = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.toml");
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+
+    let metadata = Command::new(cargo)
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--offline",
+            "--manifest-path",
+        ])
+        .arg(manifest)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok());
+
+    let mut lines = metadata
+        .as_ref()
+        .and_then(|metadata| metadata["packages"].as_array())
+        .map(|packages| {
+            packages
+                .iter()
+                // workspace members have no source
+                .filter(|package| !package["source"].is_null())
+                .map(|package| {
+                    format!(
+                        "{} {} - {}",
+                        package["name"].as_str().unwrap_or_default(),
+                        package["version"].as_str().unwrap_or_default(),
+                        package["license"].as_str().unwrap_or("unknown")
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    lines.sort();
+    lines.dedup();
+
+    fs::write(out_file, lines.join("\n")).unwrap();
+}
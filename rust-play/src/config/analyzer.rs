@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+
+use egui::Id;
+
+use crate::utils::lsp::{LspClient, Reply};
+
+/// A suggestion for the word at the cursor
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub label: String,
+    // e.g. the type of a field or the signature of a function
+    pub detail: Option<String>,
+    // what replaces the word
+    pub insert: String,
+}
+
+// rust-analyzer working on one tab, in a project of its own so runs don't rewrite it underneath
+#[derive(Default)]
+pub struct AnalyzerSession {
+    // the server and the src/main.rs of its project, once it's initialized
+    pub starting: Option<Receiver<Result<(LspClient, PathBuf), String>>>,
+    pub client: Option<LspClient>,
+    pub main_rs: PathBuf,
+    // it failed to start, it's not tried again until it's turned off and on
+    pub failed: bool,
+    pub version: i32,
+    // hash of the code it last got
+    pub synced: u64,
+    // hash of the lines the dependencies come from, the project is rewritten when they change
+    pub manifest: u64,
+    pub writing: Option<Receiver<()>>,
+    pub completion: Option<Receiver<Reply>>,
+    // shown in a popup below the cursor until one is picked or the word is left
+    pub completions: Vec<Completion>,
+    pub selected: usize,
+    // the char index of the start of the word hovered, and since when, the type shown for it
+    pub hovered: Option<(usize, f64)>,
+    pub hover: Option<Receiver<Reply>>,
+    pub hover_text: Option<(usize, String)>,
+    pub definition: Option<Receiver<Reply>>,
+}
+
+#[derive(Default)]
+pub struct Analyzer {
+    pub sessions: HashMap<Id, AnalyzerSession>,
+}
@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+
+use cargo_player::Diagnostic;
+use egui::Id;
+
+// cargo check run on one tab when typing stops, the errors of the last check are shown on hover
+#[derive(Default)]
+pub struct CheckSession {
+    // hash of the code the diagnostics are for, they're only shown while it's unchanged
+    pub checked: u64,
+    // hash of the code waiting to be checked and since when it's unchanged
+    pub pending: Option<(u64, f64)>,
+    // the hash being checked and its diagnostics once it's done
+    pub checking: Option<(u64, Receiver<Vec<Diagnostic>>)>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Default)]
+pub struct CheckHints {
+    pub sessions: HashMap<Id, CheckSession>,
+}
@@ -1,12 +1,42 @@
+use std::fs;
+use std::io;
+
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+use super::analyzer::Analyzer;
 use super::dock::DockConfig;
+use super::editor::EditorConfig;
+use super::general::GeneralConfig;
+use super::paths::{settings_backup_file, settings_file};
+use super::performance::Performance;
 use super::theme::ThemeConfig;
+use super::CheckHints;
+use super::Defender;
+use super::DependencySearch;
+use super::DiskUsage;
+use super::GistExport;
+use super::Gists;
 use super::GitHub;
+use super::Idle;
+use super::Presets;
+use super::QuickRun;
+use super::Setup;
 use super::Terminal;
+use super::TerminalSettings;
+use super::Toolchains;
+use super::Updates;
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    pub general: GeneralConfig,
+    #[serde(default)]
+    pub editor: EditorConfig,
+    #[serde(default)]
+    pub terminal_settings: TerminalSettings,
+    #[serde(default)]
+    pub presets: Presets,
     pub github: GitHub,
     pub theme: ThemeConfig,
 
@@ -15,4 +45,89 @@ pub struct Config {
     pub dock: DockConfig,
     #[serde(skip_serializing, skip_deserializing)]
     pub terminal: Terminal,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub updates: Updates,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub toolchains: Toolchains,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub gists: Gists,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub gist_export: GistExport,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub dependency_search: DependencySearch,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub disk_usage: DiskUsage,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub performance: Performance,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub idle: Idle,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub quick_run: QuickRun,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub defender: Defender,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub setup: Setup,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub analyzer: Analyzer,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub check_hints: CheckHints,
+    // settings.toml couldn't be loaded nor backed up, it isn't written over with the defaults
+    #[serde(skip_serializing, skip_deserializing)]
+    keep_file: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Couldn't read settings.toml, it may be locked by another program: {0}")]
+    Unreadable(io::Error),
+    #[error("settings.toml is invalid: {0}")]
+    Invalid(toml::de::Error),
+}
+
+impl Config {
+    /// Read settings.toml, or use the defaults if there is none. If it can't be read or parsed the
+    /// defaults are used for now, and the file is kept as settings.toml.bak so saving them later doesn't
+    /// lose it. Without a backup the file isn't saved over at all.
+    /// Returns the error for the user to see, and whether the backup was made
+    pub fn load() -> (Self, Option<(SettingsError, bool)>) {
+        let file = settings_file();
+        if !file.exists() {
+            return (Self::default(), None);
+        }
+
+        let error = match fs::read_to_string(&file) {
+            Ok(content) => match toml::from_str::<Self>(&content) {
+                Ok(config) => return (config, None),
+                Err(e) => SettingsError::Invalid(e),
+            },
+
+            Err(e) => SettingsError::Unreadable(e),
+        };
+
+        let backed_up = fs::copy(&file, settings_backup_file()).is_ok();
+
+        let config = Self {
+            keep_file: !backed_up,
+            ..Self::default()
+        };
+
+        (config, Some((error, backed_up)))
+    }
+
+    /// Write the persisted sections to settings.toml. Fails if the file couldn't be loaded, and it has no
+    /// backup to fall back on
+    pub fn save(&self) -> io::Result<()> {
+        if self.keep_file {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "settings.toml couldn't be loaded, it's left as it is instead of being replaced with \
+                 the defaults",
+            ));
+        }
+
+        let content =
+            toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        fs::write(settings_file(), content)
+    }
 }
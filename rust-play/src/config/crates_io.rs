@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::mpsc::{channel, Receiver};
+
+use egui::Id;
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::Deserialize;
+use thiserror::Error;
+
+const API_URL: &str = "https://crates.io/api/v1";
+// crates.io turns away requests without a user agent saying who's asking
+const USER_AGENT: &str = "RustPlay (https://github.com/MolotovCherry/RustPlay)";
+pub const SEARCH_RESULTS: usize = 20;
+
+#[derive(Debug, Error)]
+pub enum CratesIoError {
+    #[error("Crate not found")]
+    NotFound,
+    #[error("Too many requests, try again in a bit")]
+    RateLimited,
+    #[error("Failed to reach crates.io")]
+    Unreachable,
+    #[error("Unexpected reply from crates.io")]
+    InvalidReply,
+}
+
+impl From<reqwest::Error> for CratesIoError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status().map(|status| status.as_u16()) {
+            Some(404) => Self::NotFound,
+            Some(429) => Self::RateLimited,
+            _ => Self::Unreachable,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CrateSummary {
+    pub name: String,
+    pub description: Option<String>,
+    pub max_version: String,
+    // None if the crate only has pre-releases
+    #[serde(default)]
+    pub max_stable_version: Option<String>,
+    pub downloads: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CrateVersion {
+    pub num: String,
+    pub yanked: bool,
+    // each feature and the features and dependencies it enables
+    #[serde(default)]
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchReply {
+    crates: Vec<CrateSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionsReply {
+    versions: Vec<CrateVersion>,
+}
+
+/// Searches crates.io by name and keywords, the most relevant first
+/// Does not block, but instead returns a receiver you can use to receive it
+pub fn search_crates(query: &str) -> Receiver<Result<Vec<CrateSummary>, CratesIoError>> {
+    let query = query.trim().to_owned();
+
+    spawn(move |client| {
+        let reply = send(client.get(format!("{API_URL}/crates")).query(&[
+            ("q", query.as_str()),
+            ("per_page", &SEARCH_RESULTS.to_string()),
+        ]))?;
+
+        let reply =
+            serde_json::from_str::<SearchReply>(&reply).map_err(|_| CratesIoError::InvalidReply)?;
+
+        Ok(reply.crates)
+    })
+}
+
+/// Fetches the versions of a crate which weren't yanked, the newest first
+/// Does not block, but instead returns a receiver you can use to receive it
+pub fn crate_versions(name: &str) -> Receiver<Result<Vec<CrateVersion>, CratesIoError>> {
+    let name = name.to_owned();
+
+    spawn(move |client| {
+        let reply = send(client.get(format!("{API_URL}/crates/{name}/versions")))?;
+
+        let reply = serde_json::from_str::<VersionsReply>(&reply)
+            .map_err(|_| CratesIoError::InvalidReply)?;
+
+        Ok(reply
+            .versions
+            .into_iter()
+            .filter(|version| !version.yanked)
+            .collect())
+    })
+}
+
+// runs the request on another thread and sends back the result
+fn spawn<T: Send + 'static>(
+    request: impl FnOnce(&Client) -> Result<T, CratesIoError> + Send + 'static,
+) -> Receiver<Result<T, CratesIoError>> {
+    let (tx, rx) = channel();
+
+    std::thread::spawn(move || {
+        let client = Client::new();
+
+        let _ = tx.send(request(&client));
+    });
+
+    rx
+}
+
+fn send(request: RequestBuilder) -> Result<String, CratesIoError> {
+    let reply = request
+        .header("User-Agent", USER_AGENT)
+        .send()?
+        .error_for_status()?;
+
+    Ok(reply.text()?)
+}
+
+// state of the "Add dependency" dialog
+#[derive(Default)]
+pub struct DependencySearch {
+    // the tab the dependency is added to
+    pub tab: Option<Id>,
+    pub query: String,
+    pub results: Option<Result<Vec<CrateSummary>, CratesIoError>>,
+    pub searching: Option<Receiver<Result<Vec<CrateSummary>, CratesIoError>>>,
+    // the crate picked from the results
+    pub selected: Option<String>,
+    pub versions: Option<Result<Vec<CrateVersion>, CratesIoError>>,
+    pub fetching: Option<Receiver<Result<Vec<CrateVersion>, CratesIoError>>>,
+    // index into the versions
+    pub version: usize,
+    pub default_features: bool,
+    pub features: BTreeSet<String>,
+}
@@ -0,0 +1,11 @@
+use std::sync::mpsc::Receiver;
+
+#[derive(Default)]
+pub struct Defender {
+    // seconds the slow build took, and the crates it compiled
+    pub slow_build: Option<(f32, usize)>,
+    pub open: bool,
+    // result of the elevated helper while it's waiting on the user
+    pub adding: Option<Receiver<Result<(), String>>>,
+    pub added: Option<Result<(), String>>,
+}
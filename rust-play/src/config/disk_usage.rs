@@ -0,0 +1,18 @@
+use std::sync::mpsc::Receiver;
+use std::time::Instant;
+
+#[derive(Default)]
+pub struct DiskUsage {
+    // bytes of all generated projects, None until they were first measured
+    pub bytes: Option<u64>,
+    // result of a running measurement
+    pub measuring: Option<Receiver<Measurement>>,
+    // None to measure again right away
+    pub measured: Option<Instant>,
+}
+
+pub struct Measurement {
+    pub bytes: u64,
+    // build output removed to get under the quota
+    pub freed: u64,
+}
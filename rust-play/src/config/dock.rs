@@ -1,4 +1,13 @@
-use crate::widgets::dock::{Tree, TreeTabs};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use super::drafts::{load_drafts, Draft};
+use super::templates::{load_templates, Template};
+use super::workspaces::load_workspaces;
+use crate::widgets::binary_size::SizeView;
+use crate::widgets::dock::{Tab, Tree, TreeTabs};
+use crate::widgets::hex_viewer::HexView;
+use cargo_player::{Channel, FuzzFn, Sanitizer};
 use egui::Id;
 use egui_dock::NodeIndex;
 
@@ -7,6 +16,16 @@ pub struct DockConfig {
     pub tree: Tree,
     pub commands: Vec<Command>,
     pub counter: u32,
+    pub templates: Vec<Template>,
+    // recently closed or autosaved scratches
+    pub drafts: Vec<Draft>,
+    pub last_autosave: Instant,
+    // most recently closed tabs, for reopening them
+    pub closed: Vec<Tab>,
+    // the workspace the tabs were switched to or saved as, it's saved again when switching away
+    pub workspace: Option<String>,
+    // names of the saved workspaces, most recently saved first
+    pub workspaces: Vec<String>,
 }
 
 impl Default for DockConfig {
@@ -15,6 +34,12 @@ impl Default for DockConfig {
             tree: Tree::init(),
             commands: Default::default(),
             counter: 0,
+            templates: load_templates(),
+            drafts: load_drafts(),
+            last_autosave: Instant::now(),
+            closed: vec![],
+            workspace: None,
+            workspaces: load_workspaces(),
         }
     }
 }
@@ -30,11 +55,117 @@ pub enum MenuCommand {
     Rename(Id),
     Save(Id),
     Share(Id),
+    // copy a playground link with the code in it, no gist needed
+    ShareLink(Id),
+    NewFromTemplate(NodeIndex),
+    About,
+    // install a channel with rustup, or a component of it
+    InstallToolchain(Channel, Option<&'static str>),
+    // show bytes, e.g. binary output or a file, in the hex viewer
+    HexView(HexView),
+    // browse the gists shared from Rust Play
+    Gists,
+    // share every open scratch at once
+    ShareAll,
+    // edit the environment presets
+    Presets,
+    // the largest functions and crates of a release build, and how its size changed
+    BinarySize(SizeView),
+    // save the tabs and their layout under a name
+    SaveWorkspace,
+    // the dependencies of a tab, to open their source
+    CrateSources(Id),
+    // search crates.io for a dependency to add to a tab
+    AddDependency(Id),
+    // run a template in the background and show its output in a popup, without opening a tab
+    QuickRun(NodeIndex, Template),
+    // cargo tree of a tab, folded by direct dependency
+    DependencyTree(Id),
+    // what rustc --explain says about an error code, e.g. E0382
+    Explain(String),
+    // the Cargo.toml a tab's project is generated with
+    Manifest(Id),
+    // save a tab's project as a cargo project of its own, in a folder or a zip
+    ExportProject(Id),
+    // save a tab's last output with its colors, as html or a png
+    ExportOutput(Id),
 }
 
 #[derive(Debug, Clone)]
 pub enum TabCommand {
     Add(NodeIndex),
-    Close(Id),
+    // add a tab with the given code, e.g. from a template
+    AddWithCode(NodeIndex, String),
+    // sent after the tab was removed from the tree
+    Close(Box<Tab>),
+    // ask before closing a tab with unsaved changes
+    ConfirmClose(Id),
+    ReopenClosed,
+    // bring a tab to the front and focus it
+    Focus(Id),
+    // format the code of a tab with rustfmt
+    Format(Id),
+    // open where the std item under the cursor of a tab is defined, in a read-only tab
+    GoToStdSource(Id),
+    // open a file rust-analyzer found a definition in, read-only at its line: the crate, path and line
+    OpenDefinition(String, PathBuf, usize),
+    // the folder of the project a tab was last played in
+    OpenProjectFolder(Id),
+    // the project a tab was last played in, opened with the external editor
+    OpenProjectInEditor(Id),
+    // sort and align the //# and //> directives of a tab, or of every tab if None
+    TidyDirectives(Option<Id>),
     Play(Id),
+    // run the tests of the scratch with cargo test
+    PlayTests(Id),
+    // append a proptest for a function of the scratch, then run the tests
+    Proptest(Id, String),
+    // build for wasm and open the result in the browser
+    PlayWeb(Id),
+    // run on nightly with a sanitizer instrumenting the build
+    PlaySanitized(Id, Sanitizer),
+    // model check with loom
+    PlayLoom(Id),
+    // interpret with Miri on nightly, which reports undefined behavior
+    PlayMiri(Id),
+    // lint with clippy, its lints are listed in place of the output
+    PlayClippy(Id),
+    // build in release and measure what takes up the space with cargo-bloat
+    BinarySize(Id),
+    // fuzz a function of the scratch with cargo-fuzz until stopped
+    Fuzz(Id, FuzzFn),
+    // run the fuzz target once with an input which crashed it
+    FuzzReplay(Id, FuzzFn, PathBuf),
+    // abort the running process of a tab
+    Stop(Id),
+    // scroll the editor of a tab to a line, counted from 0, and focus the tab
+    JumpToLine(Id, usize),
+    // move a tab to a window of its own
+    Detach(Id),
+    // open a file from disk in a new tab
+    Open(PathBuf),
+    // reopen a previously closed or autosaved scratch
+    Restore(Draft),
+    // replace the tabs with those of a saved workspace
+    SwitchWorkspace(String),
+}
+
+impl TabCommand {
+    /// The tab a run is started in, None if it doesn't start one
+    pub fn run_tab(&self) -> Option<Id> {
+        match self {
+            Self::Play(id)
+            | Self::PlayTests(id)
+            | Self::Proptest(id, _)
+            | Self::PlayWeb(id)
+            | Self::PlaySanitized(id, _)
+            | Self::PlayLoom(id)
+            | Self::PlayMiri(id)
+            | Self::PlayClippy(id)
+            | Self::BinarySize(id)
+            | Self::Fuzz(id, _)
+            | Self::FuzzReplay(id, _, _) => Some(*id),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,146 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::widgets::dock::TabLabel;
+
+use super::paths::drafts_dir;
+use super::store::{timestamp, with_store};
+
+// Only the most recent drafts are kept, older ones are removed
+const MAX_DRAFTS: usize = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Draft {
+    // unique per tab
+    #[serde(skip)]
+    pub key: u64,
+    pub name: String,
+    pub code: String,
+    // the legacy toml drafts never had one
+    #[serde(skip)]
+    pub label: Option<TabLabel>,
+    #[serde(skip)]
+    pub modified: Option<SystemTime>,
+}
+
+impl Draft {
+    pub fn save(&self) -> io::Result<()> {
+        let modified = self.modified.unwrap_or_else(SystemTime::now);
+        let label = self
+            .label
+            .as_ref()
+            .and_then(|label| serde_json::to_string(label).ok());
+
+        with_store(|tx| {
+            tx.execute(
+                "INSERT OR REPLACE INTO drafts (key, name, code, modified, label)
+                    VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    self.key as i64,
+                    self.name,
+                    self.code,
+                    timestamp(modified),
+                    label
+                ],
+            )
+        })
+        .map(|_| ())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "failed to save the draft"))
+    }
+}
+
+/// Load a single draft by its key
+pub fn load_draft(key: u64) -> Option<Draft> {
+    with_store(|tx| {
+        tx.query_row(
+            "SELECT name, code, modified, label FROM drafts WHERE key = ?1",
+            [key as i64],
+            |row| {
+                let modified = UNIX_EPOCH + Duration::from_secs(row.get::<_, i64>(2)? as u64);
+
+                Ok(Draft {
+                    key,
+                    name: row.get(0)?,
+                    code: row.get(1)?,
+                    label: parse_label(row.get(3)?),
+                    modified: Some(modified),
+                })
+            },
+        )
+    })
+}
+
+/// Load the drafts, most recently modified first
+pub fn load_drafts() -> Vec<Draft> {
+    import_legacy_drafts();
+
+    with_store(|tx| {
+        let drafts = tx
+            .prepare("SELECT key, name, code, modified, label FROM drafts ORDER BY modified DESC")?
+            .query_map([], |row| {
+                let modified = UNIX_EPOCH + Duration::from_secs(row.get::<_, i64>(3)? as u64);
+
+                Ok(Draft {
+                    key: row.get::<_, i64>(0)? as u64,
+                    name: row.get(1)?,
+                    code: row.get(2)?,
+                    label: parse_label(row.get(4)?),
+                    modified: Some(modified),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        tx.execute(
+            "DELETE FROM drafts WHERE key NOT IN
+                (SELECT key FROM drafts ORDER BY modified DESC LIMIT ?1)",
+            [MAX_DRAFTS],
+        )?;
+
+        Ok(drafts.into_iter().take(MAX_DRAFTS).collect())
+    })
+    .unwrap_or_default()
+}
+
+// a label which doesn't parse anymore is dropped, the draft is still there
+fn parse_label(label: Option<String>) -> Option<TabLabel> {
+    serde_json::from_str(&label?).ok()
+}
+
+// Drafts used to be toml files named after their key. Move them into the store, the files are
+// only removed once they were saved
+fn import_legacy_drafts() {
+    let Ok(entries) = fs::read_dir(drafts_dir()) else {
+        return;
+    };
+
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if path.extension().map_or(true, |ext| ext != "toml") {
+            continue;
+        }
+
+        let draft = read_legacy_draft(&path);
+
+        if draft.map_or(false, |draft| draft.save().is_ok()) {
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    // only removed if it's empty now
+    let _ = fs::remove_dir(drafts_dir());
+}
+
+fn read_legacy_draft(path: &Path) -> Option<Draft> {
+    let key = u64::from_str_radix(&path.file_stem()?.to_string_lossy(), 16).ok()?;
+    let content = fs::read_to_string(path).ok()?;
+
+    let mut draft = toml::from_str::<Draft>(&content).ok()?;
+    draft.key = key;
+    draft.modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    Some(draft)
+}
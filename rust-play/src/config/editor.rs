@@ -0,0 +1,30 @@
+use rustplay_widgets::code_editor::EditorSettings;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorConfig {
+    // font size and wrapping, which the code editor and terminal widgets take
+    #[serde(flatten)]
+    pub settings: EditorSettings,
+    // an installed monospace font, None uses the builtin one
+    pub font_family: Option<String>,
+    // sort and align the //# and //> directives of a scratch when it's saved
+    pub tidy_directives_on_save: bool,
+    // completions, types on hover and go to definition from rust-analyzer
+    pub rust_analyzer: bool,
+    // cargo check the code when typing stops and show its errors on hover, while rust-analyzer is off
+    pub check_while_typing: bool,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            settings: EditorSettings::default(),
+            font_family: None,
+            tidy_directives_on_save: false,
+            rust_analyzer: false,
+            check_while_typing: false,
+        }
+    }
+}
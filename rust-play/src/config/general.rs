@@ -0,0 +1,299 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use cargo_player::prepare_base_dir;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneralConfig {
+    // forward files opened from the command line to an already running instance
+    // instead of opening a second window
+    pub single_instance: bool,
+    // seconds between writing every changed tab to the drafts directory. 0 disables it
+    pub autosave_interval: u64,
+    // compare the installed stable toolchain against the latest release
+    pub check_updates: bool,
+    // in hours
+    pub update_check_interval: u64,
+    // unix timestamp
+    pub last_update_check: u64,
+    // kill the runs still going when the window closes, instead of leaving them running
+    pub stop_runs_on_exit: bool,
+    pub double_click: DoubleClick,
+    // what starting a run does while the tab is still running
+    pub run_again: RunAgain,
+    // how shortcuts are matched to the keys pressed, only on Windows
+    pub shortcut_keys: ShortcutKeys,
+    // MiB the generated projects may take up before the oldest build output is removed. 0 disables it
+    pub disk_quota: u64,
+    // minutes without input after which cached layouts and old output are dropped. 0 disables it
+    pub reclaim_after: u64,
+    // where the projects are generated, e.g. on a ramdisk. Empty uses the system's temp directory
+    pub projects_dir: String,
+    // point out builds slow enough to be antivirus scanning, only on Windows
+    pub defender_hint: bool,
+    // command the generated projects are opened with, e.g. code, given the project's folder.
+    // Empty uses $VISUAL or $EDITOR, or VS Code without either
+    pub external_editor: String,
+    // the projects folder as it was prepared, it's only checked again once the setting changes
+    #[serde(skip)]
+    prepared_dir: Mutex<Option<PreparedDir>>,
+}
+
+#[derive(Debug)]
+struct PreparedDir {
+    setting: String,
+    dir: Result<PathBuf, String>,
+    // whether the error was shown already
+    reported: bool,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        Self {
+            single_instance: true,
+            autosave_interval: 30,
+            check_updates: true,
+            update_check_interval: 24,
+            last_update_check: 0,
+            stop_runs_on_exit: true,
+            double_click: DoubleClick::default(),
+            run_again: RunAgain::default(),
+            shortcut_keys: ShortcutKeys::default(),
+            disk_quota: 0,
+            reclaim_after: 30,
+            projects_dir: String::new(),
+            defender_hint: true,
+            external_editor: String::new(),
+            prepared_dir: Mutex::default(),
+        }
+    }
+}
+
+impl GeneralConfig {
+    /// The directory the projects are generated in, None for the default one. It's created if it's gone
+    /// since it was picked, e.g. with its ramdisk. One which can't be is left for the default as well,
+    /// see [`Self::take_projects_dir_error`]
+    pub fn projects_dir(&self) -> Option<PathBuf> {
+        let setting = self.projects_dir.trim();
+        if setting.is_empty() {
+            return None;
+        }
+
+        let mut prepared = self.prepared_dir.lock().unwrap();
+
+        let prepared = match &mut *prepared {
+            Some(prepared) if prepared.setting == setting => prepared,
+
+            prepared => prepared.insert(PreparedDir {
+                setting: setting.to_string(),
+                dir: prepare_base_dir(Path::new(setting))
+                    .map(|()| PathBuf::from(setting))
+                    .map_err(|e| e.to_string()),
+                reported: false,
+            }),
+        };
+
+        prepared.dir.clone().ok()
+    }
+
+    /// Why the projects folder is left for the default one, only the first time it's asked for
+    pub fn take_projects_dir_error(&self) -> Option<String> {
+        self.projects_dir();
+
+        let mut prepared = self.prepared_dir.lock().unwrap();
+        let prepared = prepared.as_mut()?;
+
+        match &prepared.dir {
+            Err(e) if !prepared.reported => {
+                prepared.reported = true;
+                Some(e.clone())
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Use another projects folder, it's checked again even if it's the same one
+    pub fn set_projects_dir(&mut self, dir: &str) {
+        self.projects_dir = dir.to_string();
+        *self.prepared_dir.get_mut().unwrap() = None;
+    }
+
+    /// The command projects are opened in an editor with, and its arguments before the project's folder.
+    /// Quoted parts stay together, e.g. a path with spaces in it
+    pub fn editor(&self) -> Vec<String> {
+        let setting = Some(self.external_editor.clone());
+        let env = |name| std::env::var(name).ok();
+
+        [setting, env("VISUAL"), env("EDITOR")]
+            .into_iter()
+            .flatten()
+            .map(|editor| split_command(&editor))
+            .find(|editor| !editor.is_empty())
+            .unwrap_or_else(|| vec!["code".to_string()])
+    }
+
+    /// Open a folder with the editor
+    pub fn editor_command(&self, dir: &Path) -> Command {
+        let editor = self.editor();
+
+        let mut command = Command::new(resolve_program(&editor[0]));
+        command.args(&editor[1..]).arg(dir);
+
+        command
+    }
+
+    /// The name of the editor to show on buttons, e.g. VS Code for code
+    pub fn editor_name(&self) -> String {
+        let editor = self.editor();
+        let stem = Path::new(&editor[0])
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        match stem.as_str() {
+            "code" => "VS Code".to_string(),
+            "code-insiders" => "VS Code Insiders".to_string(),
+            _ => stem,
+        }
+    }
+}
+
+// the words of a command line, those in double or single quotes kept as one without their quotes.
+// Backslashes are left alone, they're the path separator on Windows
+fn split_command(line: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    // a quoted empty word is still a word
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    words
+}
+
+// a program without an extension found on the PATH with the extensions Windows runs, as editors are often
+// installed as scripts, e.g. code.cmd. Starting a process only looks for .exe otherwise
+#[cfg(target_os = "windows")]
+fn resolve_program(program: &str) -> PathBuf {
+    let path = Path::new(program);
+
+    if path.extension().is_some() || path.components().count() > 1 {
+        return path.to_path_buf();
+    }
+
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    let dirs = std::env::var_os("PATH").unwrap_or_default();
+
+    std::env::split_paths(&dirs)
+        .flat_map(|dir| {
+            extensions
+                .split(';')
+                .filter(|extension| !extension.is_empty())
+                .map(move |extension| dir.join(format!("{program}{extension}")))
+        })
+        .find(|candidate| candidate.is_file())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn resolve_program(program: &str) -> PathBuf {
+    PathBuf::from(program)
+}
+
+/// What double clicking does, each can be turned off
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DoubleClick {
+    // on empty space in a tab bar, opens a new scratch
+    pub new_tab: bool,
+    // on a tab, renames it in place
+    pub rename: bool,
+    // on the title bar, maximizes or restores the window
+    pub maximize: bool,
+}
+
+impl Default for DoubleClick {
+    fn default() -> Self {
+        Self {
+            new_tab: true,
+            rename: true,
+            maximize: true,
+        }
+    }
+}
+
+/// What starting a run does while the tab is still running. Both runs would build the same project at once,
+/// so the new one never starts before the old one is gone
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum RunAgain {
+    // stop the run and start over once it's gone
+    #[default]
+    Restart,
+    // start once the run finished
+    Queue,
+}
+
+impl RunAgain {
+    pub fn all() -> [Self; 2] {
+        [Self::Restart, Self::Queue]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Restart => "Restart the run",
+            Self::Queue => "Queue after the run",
+        }
+    }
+}
+
+/// How shortcuts are matched to the keys pressed. Some layouts move the keys of shortcuts around,
+/// e.g. Z and Y swap places on a QWERTZ keyboard
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum ShortcutKeys {
+    // by the character the keyboard layout puts on the key
+    #[default]
+    Layout,
+    // by where the key is, as on a US QWERTY keyboard
+    Position,
+}
+
+impl ShortcutKeys {
+    pub fn all() -> [Self; 2] {
+        [Self::Layout, Self::Position]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Layout => "Follow the keyboard layout",
+            Self::Position => "Keep their QWERTY position",
+        }
+    }
+}
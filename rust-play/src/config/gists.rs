@@ -0,0 +1,52 @@
+use std::sync::mpsc::Receiver;
+
+use super::github::{Gist, GistPage, GitHubError};
+
+// state of the "My Gists" window
+#[derive(Default)]
+pub struct Gists {
+    // starts at 1, 0 until the window was first opened
+    pub page: usize,
+    pub listed: Option<Result<GistPage, GitHubError>>,
+    pub listing: Option<Receiver<Result<GistPage, GitHubError>>>,
+    // the gist being previewed, including its code
+    pub selected: Option<Result<Gist, GitHubError>>,
+    pub fetching: Option<Receiver<Result<Gist, GitHubError>>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ExportMode {
+    // a file per scratch, named after its tab
+    #[default]
+    SingleGist,
+    GistPerTab,
+}
+
+impl ExportMode {
+    pub fn all() -> [Self; 2] {
+        [Self::SingleGist, Self::GistPerTab]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::SingleGist => "One gist with a file per scratch",
+            Self::GistPerTab => "A gist per scratch",
+        }
+    }
+}
+
+// a gist being created by "Share all open scratches"
+pub struct GistUpload {
+    // the tabs shared in it
+    pub names: Vec<String>,
+    pub pending: Option<Receiver<Result<String, GitHubError>>>,
+    // the id of the gist once it's created
+    pub result: Option<Result<String, GitHubError>>,
+}
+
+// state of the "Share all open scratches" window, nothing is uploaded until the mode was picked
+#[derive(Default)]
+pub struct GistExport {
+    pub mode: ExportMode,
+    pub uploads: Vec<GistUpload>,
+}
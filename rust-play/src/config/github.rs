@@ -1,8 +1,18 @@
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, Receiver};
+
+use cargo_player::LineEnding;
+use reqwest::blocking::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::sync::mpsc::{channel, Receiver};
 use thiserror::Error;
 
+const API_URL: &str = "https://api.github.com";
+// every gist shared from Rust Play has this description, which is how they're told apart
+const GIST_DESCRIPTION: &str = "Created by Rust Play <https://github.com/MolotovCherry/RustPlay>";
+const GIST_FILE_NAME: &str = "playground.rs";
+pub const GISTS_PER_PAGE: usize = 30;
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct GitHub {
     pub access_token: String,
@@ -22,10 +32,141 @@ pub enum GitHubError {
     Unknown,
 }
 
+impl From<reqwest::Error> for GitHubError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status().map(|status| status.as_u16()) {
+            Some(403) => Self::Forbidden,
+            Some(404) => Self::NotFound,
+            Some(422) => Self::ValidationFailed,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Gist {
+    pub id: String,
+    pub description: Option<String>,
+    pub html_url: String,
+    pub created_at: String,
+    pub files: BTreeMap<String, GistFile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GistFile {
+    pub filename: String,
+    // only included when a single gist is requested
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+impl Gist {
+    /// The code of the scratch, if the gist was fetched with [`GitHub::get_gist`]
+    pub fn code(&self) -> Option<&str> {
+        self.files
+            .get(GIST_FILE_NAME)
+            .or_else(|| {
+                self.files
+                    .values()
+                    .find(|file| file.filename.ends_with(".rs"))
+            })?
+            .content
+            .as_deref()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GistPage {
+    // only the ones created by Rust Play
+    pub gists: Vec<Gist>,
+    pub has_more: bool,
+}
+
 impl GitHub {
     /// Creates a new github gist using a title and content
     /// Does not block, but instead returns a receiver you can use to receive it
     pub fn create_gist(&self, content: &str) -> Receiver<Result<String, GitHubError>> {
+        self.create_gist_with_files(&[(GIST_FILE_NAME.to_string(), content.to_string())])
+    }
+
+    /// Creates a new github gist with a file for each name and content, e.g. one per scratch
+    /// Does not block, but instead returns a receiver you can use to receive it
+    pub fn create_gist_with_files(
+        &self,
+        files: &[(String, String)],
+    ) -> Receiver<Result<String, GitHubError>> {
+        // shared code always uses LF, so it doesn't diff on every line against what others write
+        let files = files
+            .iter()
+            .map(|(name, content)| {
+                let content = LineEnding::Lf.apply(content).into_owned();
+                (name.clone(), json!({ "content": content }))
+            })
+            .collect::<serde_json::Map<_, _>>();
+
+        self.spawn(move |client, access_token| {
+            let body = json!({
+                "description": GIST_DESCRIPTION,
+                "public": true,
+                "files": files
+            })
+            .to_string();
+
+            let reply = send(
+                client.post(format!("{API_URL}/gists")).body(body),
+                &access_token,
+            )?;
+
+            let reply =
+                serde_json::from_str::<GitHubReply>(&reply).map_err(|_| GitHubError::Unknown)?;
+
+            Ok(reply.id)
+        })
+    }
+
+    /// Lists a page of the user's gists which were created by Rust Play. Pages start at 1
+    /// Does not block, but instead returns a receiver you can use to receive it
+    pub fn list_gists(&self, page: usize) -> Receiver<Result<GistPage, GitHubError>> {
+        self.spawn(move |client, access_token| {
+            let reply = send(
+                client.get(format!(
+                    "{API_URL}/gists?per_page={GISTS_PER_PAGE}&page={page}"
+                )),
+                &access_token,
+            )?;
+
+            let gists =
+                serde_json::from_str::<Vec<Gist>>(&reply).map_err(|_| GitHubError::Unknown)?;
+
+            // filtering happens after paging, so a full page means there may be more
+            let has_more = gists.len() == GISTS_PER_PAGE;
+
+            let gists = gists
+                .into_iter()
+                .filter(|gist| gist.description.as_deref() == Some(GIST_DESCRIPTION))
+                .collect();
+
+            Ok(GistPage { gists, has_more })
+        })
+    }
+
+    /// Fetches a single gist including the content of its files
+    /// Does not block, but instead returns a receiver you can use to receive it
+    pub fn get_gist(&self, id: &str) -> Receiver<Result<Gist, GitHubError>> {
+        let id = id.to_owned();
+
+        self.spawn(move |client, access_token| {
+            let reply = send(client.get(format!("{API_URL}/gists/{id}")), &access_token)?;
+
+            serde_json::from_str::<Gist>(&reply).map_err(|_| GitHubError::Unknown)
+        })
+    }
+
+    // runs the request on another thread and sends back the result
+    fn spawn<T: Send + 'static>(
+        &self,
+        request: impl FnOnce(&Client, String) -> Result<T, GitHubError> + Send + 'static,
+    ) -> Receiver<Result<T, GitHubError>> {
         let (tx, rx) = channel();
 
         // Error out immediately if no access token was provided
@@ -35,59 +176,28 @@ impl GitHub {
         }
 
         let access_token = self.access_token.clone();
-        let content = content.to_owned();
 
         std::thread::spawn(move || {
-            let client = reqwest::blocking::Client::new();
-
-            let body = json!({
-                "description": "Created by Rust Play <https://github.com/MolotovCherry/RustPlay>",
-                "public": true,
-                "files": {
-                    "playground.rs": {"content": content}
-                }
-            })
-            .to_string();
+            let client = Client::new();
 
-            let result = client
-                .post("https://api.github.com/gists")
-                .header("User-Agent", "RustPlay")
-                .header("accept", "application/vnd.github+json")
-                .bearer_auth(access_token)
-                .body(body)
-                .send();
-
-            let reply = match result {
-                Ok(v) => v,
-                Err(e) => {
-                    if e.is_status() {
-                        let code = e.status().unwrap().as_u16();
-                        let error = match code {
-                            403 => GitHubError::Forbidden,
-                            404 => GitHubError::NotFound,
-                            422 => GitHubError::ValidationFailed,
-                            _ => GitHubError::Unknown,
-                        };
-
-                        let _ = tx.send(Err(error));
-                        return;
-                    }
-
-                    let _ = tx.send(Err(GitHubError::Unknown));
-                    return;
-                }
-            };
-
-            let reply = serde_json::from_str::<GitHubReply>(&reply.text().unwrap())
-                .expect("Failed to unwrap github reply");
-
-            let _ = tx.send(Ok(reply.id));
+            let _ = tx.send(request(&client, access_token));
         });
 
         rx
     }
 }
 
+fn send(request: RequestBuilder, access_token: &str) -> Result<String, GitHubError> {
+    let reply = request
+        .header("User-Agent", "RustPlay")
+        .header("accept", "application/vnd.github+json")
+        .bearer_auth(access_token)
+        .send()?
+        .error_for_status()?;
+
+    Ok(reply.text()?)
+}
+
 #[derive(Debug, Deserialize)]
 struct GitHubReply {
     id: String,
@@ -0,0 +1,8 @@
+use std::time::Instant;
+
+/// When the window was last used, and whether the memory held on to since was given back yet
+#[derive(Default)]
+pub struct Idle {
+    pub last_input: Option<Instant>,
+    pub reclaimed: bool,
+}
@@ -1,12 +1,53 @@
+mod analyzer;
+mod compiler_hints;
 #[allow(clippy::module_inception)]
 mod config;
+mod crates_io;
+mod defender;
+mod disk_usage;
 mod dock;
+mod drafts;
+mod editor;
+mod general;
+mod gists;
 mod github;
+mod idle;
+mod paths;
+mod performance;
+mod presets;
+mod quick_run;
+mod setup;
+mod store;
+mod syntax_themes;
+mod templates;
 mod terminal;
 mod theme;
+mod toolchains;
+mod updates;
+mod workspaces;
 
+pub use analyzer::*;
+pub use compiler_hints::*;
 pub use config::*;
+pub use crates_io::*;
+pub use defender::*;
+pub use disk_usage::*;
 pub use dock::*;
+pub use drafts::*;
+pub use general::*;
+pub use gists::*;
 pub use github::*;
+pub use idle::*;
+pub use paths::*;
+pub use performance::*;
+pub use presets::*;
+pub use quick_run::*;
+pub use setup::*;
+pub use store::*;
+pub use syntax_themes::*;
+pub use templates::*;
 pub use terminal::*;
 pub use theme::*;
+pub use toolchains::*;
+pub use updates::*;
+pub use workspaces::*;
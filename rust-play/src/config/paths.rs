@@ -0,0 +1,44 @@
+use std::env;
+use std::path::PathBuf;
+
+// All user editable files live next to the executable
+pub fn config_dir() -> PathBuf {
+    env::current_exe().unwrap().parent().unwrap().to_owned()
+}
+
+pub fn settings_file() -> PathBuf {
+    config_dir().join("settings.toml")
+}
+
+// settings.toml as it was when it couldn't be loaded
+pub fn settings_backup_file() -> PathBuf {
+    config_dir().join("settings.toml.bak")
+}
+
+pub fn templates_dir() -> PathBuf {
+    config_dir().join("templates")
+}
+
+pub fn themes_dir() -> PathBuf {
+    config_dir().join("themes")
+}
+
+// drafts were stored here as toml files before they moved into the store
+pub fn drafts_dir() -> PathBuf {
+    config_dir().join("drafts")
+}
+
+// the panics so far, for bug reports
+pub fn panic_log_file() -> PathBuf {
+    config_dir().join("panic.log")
+}
+
+// a report of every crash shown in the crash dialog
+pub fn crash_logs_dir() -> PathBuf {
+    config_dir().join("crashes")
+}
+
+// drafts and run history
+pub fn store_file() -> PathBuf {
+    config_dir().join("rust-play.db")
+}
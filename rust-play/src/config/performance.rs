@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Timings of the last frames for the performance overlay, only kept while it's open
+#[derive(Default)]
+pub struct Performance {
+    pub open: bool,
+    // oldest first
+    pub frames: VecDeque<FrameSample>,
+    // when the last frame started
+    pub last_frame: Option<Instant>,
+    // sampled about once a second, asking the OS every frame is too slow
+    pub threads: Option<usize>,
+    pub threads_sampled: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FrameSample {
+    // seconds since the frame before
+    pub interval: f32,
+    // seconds the frame took to update and paint, as eframe measured it
+    pub cpu: Option<f32>,
+    pub cause: RepaintCause,
+}
+
+/// Why a frame was most likely painted. egui doesn't tell, so it's guessed from what the frame saw
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepaintCause {
+    // the mouse or keyboard was used
+    Input,
+    // a run is going, its output is read every frame
+    Run,
+    // an animation or timer asked for it, e.g. blinking text or a toast
+    Scheduled,
+}
+
+impl RepaintCause {
+    pub fn all() -> [Self; 3] {
+        [Self::Input, Self::Run, Self::Scheduled]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Input => "Input",
+            Self::Run => "Runs",
+            Self::Scheduled => "Animations and timers",
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use cargo_player::Channel;
+use serde::{Deserialize, Serialize};
+
+// a named set of environment variables and build options, which can be applied to any tab
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvPreset {
+    pub name: String,
+    pub vars: Vec<(String, String)>,
+    // added in front of the tab's own RUSTFLAGS
+    pub rust_flags: String,
+    // switched to when the preset is picked, the tab can still change it afterwards
+    pub channel: Option<Channel>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Presets {
+    pub list: Vec<EnvPreset>,
+}
+
+impl Presets {
+    pub fn get(&self, name: &str) -> Option<&EnvPreset> {
+        self.list.iter().find(|preset| preset.name == name)
+    }
+}
+
+impl Default for Presets {
+    fn default() -> Self {
+        let preset = |name: &str, vars: &[(&str, &str)], rust_flags: &str, channel| EnvPreset {
+            name: name.to_string(),
+            vars: vars
+                .iter()
+                .map(|(var, val)| (var.to_string(), val.to_string()))
+                .collect(),
+            rust_flags: rust_flags.to_string(),
+            channel,
+        };
+
+        Self {
+            list: vec![
+                preset("wasm", &[], "--cfg=web_sys_unstable_apis", None),
+                preset(
+                    "nightly + sanitizers",
+                    &[("ASAN_OPTIONS", "detect_leaks=1")],
+                    "-Zsanitizer=address",
+                    Some(Channel::Nightly),
+                ),
+                preset(
+                    "quiet",
+                    &[("RUST_LOG", "off"), ("RUST_BACKTRACE", "0")],
+                    "",
+                    None,
+                ),
+            ],
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use egui_dock::NodeIndex;
+
+use super::Template;
+
+// state of the quick run popup, which previews a template's output without opening a tab for it
+#[derive(Default)]
+pub struct QuickRun {
+    // the template running, and the node "Open in tab" adds it to. None while the popup is closed
+    pub running: Option<(NodeIndex, Template)>,
+}
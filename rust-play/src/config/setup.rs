@@ -0,0 +1,37 @@
+use std::sync::mpsc::Receiver;
+
+/// What the environment check found, the version of every tool that's there
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    pub cargo: Option<String>,
+    pub rustup: Option<String>,
+    // the stable rustc, or without rustup whichever rustc is on the PATH
+    pub stable: Option<String>,
+    // the tools are in cargo's bin folder, it's just not on the PATH this instance started with
+    pub cargo_bin_off_path: bool,
+}
+
+impl Environment {
+    /// Whether scratches can be run
+    pub fn is_ready(&self) -> bool {
+        self.cargo.is_some() && self.stable.is_some()
+    }
+}
+
+#[derive(Default)]
+pub struct Setup {
+    // None until the first check is done
+    pub environment: Option<Environment>,
+    // result of a running check
+    pub checking: Option<Receiver<Environment>>,
+    pub open: bool,
+}
+
+impl Setup {
+    /// Whether the last check didn't find cargo. Unknown until the first one is done
+    pub fn is_cargo_missing(&self) -> bool {
+        self.environment
+            .as_ref()
+            .map_or(false, |environment| environment.cargo.is_none())
+    }
+}
@@ -0,0 +1,237 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde_json::{json, Value};
+
+use super::paths::store_file;
+
+// Each entry upgrades the schema by one version, the version is tracked in `PRAGMA user_version`.
+// Never edit an entry once released, add a new one instead
+const MIGRATIONS: &[&str] = &[
+    // 1
+    "CREATE TABLE drafts (
+        key INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        code TEXT NOT NULL,
+        modified INTEGER NOT NULL
+    );
+    CREATE TABLE runs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        code TEXT NOT NULL,
+        started INTEGER NOT NULL
+    );",
+    // 2
+    "CREATE TABLE binary_sizes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        file_size INTEGER NOT NULL,
+        text_size INTEGER NOT NULL,
+        measured INTEGER NOT NULL
+    );",
+    // 3
+    "CREATE TABLE workspaces (
+        name TEXT PRIMARY KEY,
+        tree TEXT NOT NULL,
+        saved INTEGER NOT NULL
+    );",
+    // 4
+    "ALTER TABLE drafts ADD COLUMN label TEXT;",
+    // 5
+    "ALTER TABLE binary_sizes ADD COLUMN tab INTEGER;",
+];
+
+// runs kept in the history, older ones are dropped as new ones are recorded
+const RUN_HISTORY_LIMIT: i64 = 1000;
+
+// None if the database couldn't be opened, e.g. because the directory is read only
+static STORE: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(open().ok()));
+
+fn open() -> rusqlite::Result<Connection> {
+    let mut conn = Connection::open(store_file())?;
+    migrate(&mut conn)?;
+
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+/// Run queries against the store. Returns None if it's unavailable or the queries failed
+pub fn with_store<T>(f: impl FnOnce(&Transaction) -> rusqlite::Result<T>) -> Option<T> {
+    let mut store = STORE.lock().unwrap();
+    let conn = store.as_mut()?;
+
+    let tx = conn.transaction().ok()?;
+    let result = f(&tx).ok()?;
+    tx.commit().ok()?;
+
+    Some(result)
+}
+
+/// Close the database so everything written to it is flushed to disk, e.g. before exiting.
+/// The store is unavailable afterwards
+pub fn close_store() {
+    if let Some(conn) = STORE.lock().unwrap().take() {
+        let _ = conn.close();
+    }
+}
+
+/// Seconds since the unix epoch
+pub fn timestamp(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Add a run to the history. A run of the same code as the tab's last one, e.g. by watch mode, isn't added again.
+/// Only the newest runs are kept
+pub fn record_run(name: &str, code: &str) {
+    with_store(|tx| {
+        let unchanged = tx
+            .query_row(
+                "SELECT code = ?2 FROM runs WHERE name = ?1 ORDER BY id DESC LIMIT 1",
+                params![name, code],
+                |row| row.get::<_, bool>(0),
+            )
+            .optional()?
+            .unwrap_or_default();
+
+        if unchanged {
+            return Ok(());
+        }
+
+        tx.execute(
+            "INSERT INTO runs (name, code, started) VALUES (?1, ?2, ?3)",
+            params![name, code, timestamp(SystemTime::now())],
+        )?;
+
+        tx.execute(
+            "DELETE FROM runs WHERE id <= (SELECT MAX(id) FROM runs) - ?1",
+            [RUN_HISTORY_LIMIT],
+        )?;
+
+        Ok(())
+    });
+}
+
+/// Size of a release build of a tab, as measured by cargo-bloat
+#[derive(Debug, Clone, Copy)]
+pub struct BinarySize {
+    // seconds since the unix epoch
+    pub measured: i64,
+    pub file_size: u64,
+    pub text_size: u64,
+}
+
+/// Add a measurement to the binary size history of a tab, the tab is known by the key of its draft so renaming
+/// it or another tab taking its name doesn't mix up histories
+pub fn record_binary_size(tab: u64, name: &str, file_size: u64, text_size: u64) {
+    with_store(|tx| {
+        tx.execute(
+            "INSERT INTO binary_sizes (tab, name, file_size, text_size, measured) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                tab as i64,
+                name,
+                file_size as i64,
+                text_size as i64,
+                timestamp(SystemTime::now())
+            ],
+        )
+    });
+}
+
+/// Binary sizes measured for a tab, by the key of its draft, oldest first
+pub fn binary_sizes(tab: u64) -> Vec<BinarySize> {
+    with_store(|tx| {
+        let mut stmt = tx.prepare(
+            "SELECT measured, file_size, text_size FROM binary_sizes WHERE tab = ?1 ORDER BY id",
+        )?;
+
+        let sizes = stmt
+            .query_map([tab as i64], |row| {
+                Ok(BinarySize {
+                    measured: row.get(0)?,
+                    file_size: row.get::<_, i64>(1)? as u64,
+                    text_size: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect();
+
+        sizes
+    })
+    .unwrap_or_default()
+}
+
+/// Dump every table to a json file, so the data isn't locked into the database
+pub fn export_json(path: &Path) -> io::Result<()> {
+    let tables = with_store(|tx| {
+        let drafts = query_json(tx, "SELECT key, name, code, modified FROM drafts")?;
+        let runs = query_json(tx, "SELECT id, name, code, started FROM runs")?;
+        let binary_sizes = query_json(
+            tx,
+            "SELECT id, tab, name, file_size, text_size, measured FROM binary_sizes",
+        )?;
+        let workspaces = query_json(tx, "SELECT name, tree, saved FROM workspaces")?;
+
+        Ok(json!({
+            "drafts": drafts,
+            "runs": runs,
+            "binary_sizes": binary_sizes,
+            "workspaces": workspaces
+        }))
+    })
+    .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "the store is unavailable"))?;
+
+    let content = serde_json::to_string_pretty(&tables)?;
+
+    fs::write(path, content)
+}
+
+// every row as an object of column name to value
+fn query_json(tx: &Transaction, sql: &str) -> rusqlite::Result<Value> {
+    let mut stmt = tx.prepare(sql)?;
+    let columns = stmt
+        .column_names()
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    let rows = stmt
+        .query_map([], |row| {
+            let mut object = serde_json::Map::new();
+
+            for (i, column) in columns.iter().enumerate() {
+                let value = match row.get_ref(i)? {
+                    ValueRef::Null => Value::Null,
+                    ValueRef::Integer(n) => json!(n),
+                    ValueRef::Real(n) => json!(n),
+                    ValueRef::Text(text) => json!(String::from_utf8_lossy(text)),
+                    ValueRef::Blob(blob) => json!(blob),
+                };
+
+                object.insert(column.clone(), value);
+            }
+
+            Ok(Value::Object(object))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Value::Array(rows))
+}
@@ -0,0 +1,12 @@
+use std::fs;
+
+use rustplay_widgets::syntax_themes::load_custom_themes;
+
+use super::paths::themes_dir;
+
+/// Load the themes of the themes directory, again to pick up themes added to or removed from it
+pub fn reload_themes(ctx: &egui::Context) {
+    let _ = fs::create_dir_all(themes_dir());
+
+    load_custom_themes(ctx, &themes_dir());
+}
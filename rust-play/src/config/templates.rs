@@ -0,0 +1,166 @@
+use std::fs;
+
+use super::paths::templates_dir;
+
+// Written to the templates directory the first time it's created.
+// After that the user is free to edit, remove, or add their own
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("Empty main", "fn main() {\n    \n}\n"),
+    (
+        "Async tokio main",
+        r#"//# tokio = { version = "1", features = ["full"] }
+
+#[tokio::main]
+async fn main() {
+    let handle = tokio::spawn(async { 1 + 1 });
+
+    println!("Hello from tokio! {}", handle.await.unwrap());
+}
+"#,
+    ),
+    (
+        "Criterion bench",
+        r#"//> [profile.dev]
+//> opt-level = 3
+
+use criterion::{black_box, Criterion};
+
+fn fibonacci(n: u64) -> u64 {
+    match n {
+        0 | 1 => 1,
+        n => fibonacci(n - 1) + fibonacci(n - 2),
+    }
+}
+
+fn main() {
+    let mut c = Criterion::default();
+
+    c.bench_function("fib 20", |b| b.iter(|| fibonacci(black_box(20))));
+
+    c.final_summary();
+}
+"#,
+    ),
+    (
+        "Error handling with anyhow",
+        r#"use anyhow::{bail, Context, Result};
+
+fn parse_port(s: &str) -> Result<u16> {
+    let port = s
+        .parse::<u16>()
+        .with_context(|| format!("`{s}` is not a valid port"))?;
+
+    if port < 1024 {
+        bail!("port {port} is reserved");
+    }
+
+    Ok(port)
+}
+
+fn main() -> Result<()> {
+    println!("{}", parse_port("8080")?);
+    println!("{}", parse_port("80")?);
+
+    Ok(())
+}
+"#,
+    ),
+    (
+        "Loom model check",
+        r#"// Run it with Check > Loom model check, which runs main for every interleaving of its threads.
+// loom's types only work inside the model, use #[cfg(loom)] to switch between them and std's
+use loom::sync::atomic::{AtomicUsize, Ordering};
+use loom::sync::Arc;
+use loom::thread;
+
+fn main() {
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    let handles = (0..2)
+        .map(|_| {
+            let counter = counter.clone();
+
+            thread::spawn(move || {
+                // not atomic as a whole, loom finds the interleaving losing an increment
+                let value = counter.load(Ordering::Acquire);
+                counter.store(value + 1, Ordering::Release);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+}
+"#,
+    ),
+    (
+        "Proc-macro playground",
+        r#"//# syn = { version = "1", features = ["full", "extra-traits"] }
+
+// Proc macros need their own crate, but the parsing and codegen can be played with right here
+use quote::quote;
+use syn::{parse_quote, ItemFn};
+
+fn main() {
+    let input: ItemFn = parse_quote! {
+        fn hello() {
+            println!("hello");
+        }
+    };
+
+    let name = &input.sig.ident;
+    let output = quote! {
+        #input
+
+        fn traced() {
+            println!("calling {}", stringify!(#name));
+            #name();
+        }
+    };
+
+    println!("{output}");
+}
+"#,
+    ),
+];
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    pub code: String,
+}
+
+/// Load every `.rs` file in the templates directory, sorted by name.
+/// Seeds the directory with the builtin templates if it doesn't exist yet
+pub fn load_templates() -> Vec<Template> {
+    let dir = templates_dir();
+
+    if !dir.exists() && fs::create_dir_all(&dir).is_ok() {
+        for (name, code) in BUILTIN_TEMPLATES {
+            let _ = fs::write(dir.join(format!("{name}.rs")), code);
+        }
+    }
+
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return vec![];
+    };
+
+    let mut templates = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "rs"))
+        .filter_map(|path| {
+            Some(Template {
+                name: path.file_stem()?.to_string_lossy().to_string(),
+                code: fs::read_to_string(&path).ok()?,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    templates
+}
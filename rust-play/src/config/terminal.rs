@@ -1,6 +1,9 @@
-use egui::Vec2;
+use egui::{pos2, vec2, Pos2, Rect, Vec2};
 use ringbuf::{Consumer, HeapRb};
+use rustplay_widgets::terminal::TerminalView;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 use egui::Id;
@@ -18,15 +21,158 @@ pub struct Terminal {
     // just remove the tmp ctx entry to drop it
     // the entry is type Arc<Mutex<Sender<()>>>
     pub abortable: HashMap<Id, Id>,
-    // lets terminal know this is a new run
-    pub started_run: bool,
+    // set once the thread of each tab's last run is done, a new run waits for it
+    pub finished: HashMap<Id, Arc<AtomicBool>>,
+    // what each tab's last run printed so far, taken from content
+    pub output: HashMap<Id, TabOutput>,
     pub open: bool,
     pub scroll_offset: HashMap<Id, Vec2>,
     pub active_tab: Option<Id>,
     pub opened_from_close: bool,
     pub opened_from_close_dragging: bool,
     pub closed_from_open: bool,
-    // keep track of the last valid index before dynamic output was added in stderr
-    // (unstripped, stripped)
-    pub dynamic_index: (usize, usize),
+    // output assertion pattern of each tab's last run, matching lines get highlighted
+    pub assertions: HashMap<Id, String>,
+    // last size the terminal was shown at for each tab, along the axis it opens in
+    pub sizes: HashMap<Id, f32>,
+    // the tab whose run opened the terminal, so it may close it again afterwards
+    pub auto_opened: Option<Id>,
+    // (full size, opening) of a running open/close animation
+    pub animation: Option<(f32, bool)>,
+}
+
+#[derive(Debug, Default)]
+pub struct TabOutput {
+    // overwritten and erased output is applied before it's shown, and only what changed is parsed again
+    pub stdout: TerminalView,
+    pub stderr: TerminalView,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TerminalSettings {
+    // which side of the window the terminal is docked to
+    pub position: TerminalPosition,
+    // open the terminal when a run starts
+    pub auto_open: bool,
+    // height, or width when docked to the side. In points
+    pub open_size: f32,
+    // reopen at the size last used for the tab instead of open_size
+    pub remember_size: bool,
+    // close an automatically opened terminal again if the run succeeded without printing anything
+    pub auto_close: bool,
+    // how far the handle must be dragged away from the window edge to open the terminal. In points
+    pub open_threshold: f32,
+    // dragging the terminal within this distance of the window edge closes it. In points
+    pub close_threshold: f32,
+    // animate opening and closing, except when dragging
+    pub animate: bool,
+    // in seconds
+    pub animation_time: f32,
+    // make text which asks to blink blink, it's shown steadily otherwise
+    pub blink: bool,
+    // of the output's font, separate from the editor's
+    pub zoom: f32,
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        Self {
+            position: TerminalPosition::default(),
+            auto_open: true,
+            open_size: 200.0,
+            remember_size: true,
+            auto_close: false,
+            open_threshold: 17.0,
+            close_threshold: 20.0,
+            animate: true,
+            animation_time: 0.15,
+            blink: true,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl TerminalSettings {
+    /// The size to automatically open the terminal at for a tab
+    pub fn size_for(&self, terminal: &Terminal, id: Id) -> f32 {
+        if self.remember_size {
+            if let Some(&size) = terminal.sizes.get(&id) {
+                return size;
+            }
+        }
+
+        self.open_size
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum TerminalPosition {
+    #[default]
+    Bottom,
+    Left,
+    Right,
+}
+
+impl TerminalPosition {
+    pub fn all() -> [Self; 3] {
+        [Self::Bottom, Self::Left, Self::Right]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Bottom => "Bottom",
+            Self::Left => "Left",
+            Self::Right => "Right",
+        }
+    }
+
+    pub fn is_side(self) -> bool {
+        self != Self::Bottom
+    }
+
+    /// Direction pointing from the docked edge into the window
+    pub fn inward(self) -> Vec2 {
+        match self {
+            Self::Bottom => vec2(0.0, -1.0),
+            Self::Left => vec2(1.0, 0.0),
+            Self::Right => vec2(-1.0, 0.0),
+        }
+    }
+
+    /// How far a point is from the docked edge. Negative when it's outside of the window
+    pub fn distance_to_edge(self, window: Rect, pos: Pos2) -> f32 {
+        match self {
+            Self::Bottom => window.bottom() - pos.y,
+            Self::Left => pos.x - window.left(),
+            Self::Right => window.right() - pos.x,
+        }
+    }
+
+    /// The rect covered by a panel of the given size docked to this edge
+    pub fn panel_rect(self, window: Rect, size: f32) -> Rect {
+        match self {
+            Self::Bottom => Rect::from_two_pos(
+                pos2(window.left(), window.bottom() - size),
+                window.right_bottom(),
+            ),
+            Self::Left => Rect::from_two_pos(
+                window.left_top(),
+                pos2(window.left() + size, window.bottom()),
+            ),
+            Self::Right => Rect::from_two_pos(
+                pos2(window.right() - size, window.top()),
+                window.right_bottom(),
+            ),
+        }
+    }
+
+    /// The size of a docked panel along the axis it opens in
+    pub fn size_of(self, rect: Rect) -> f32 {
+        if self.is_side() {
+            rect.width()
+        } else {
+            rect.height()
+        }
+    }
 }
@@ -1,22 +1,56 @@
-use egui::Color32;
+use eframe::Theme;
+use rustplay_widgets::code_editor::SyntectTheme;
 use serde::{Deserialize, Serialize};
 
+pub use rustplay_widgets::terminal::AnsiColors;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ThemeConfig {
+    #[serde(default)]
+    pub mode: ThemeMode,
+    // None uses the default for the current light/dark mode
+    #[serde(default)]
+    pub background_opacity: Option<f32>,
     ansi_colors: AnsiColors,
     pub force_bright: bool,
+    // None follows the dark/light visuals
+    #[serde(default)]
+    pub syntax_theme: Option<SyntectTheme>,
+    // the zoom of the editor in tabs which weren't zoomed themselves
+    #[serde(default = "default_zoom")]
+    pub editor_zoom: f32,
 }
 
 impl Default for ThemeConfig {
     fn default() -> Self {
         Self {
+            mode: ThemeMode::default(),
+            background_opacity: None,
             ansi_colors: Default::default(),
             force_bright: true,
+            syntax_theme: None,
+            editor_zoom: default_zoom(),
         }
     }
 }
 
 impl ThemeConfig {
+    /// Whether the app should currently be dark, given the theme the OS reports (if any)
+    pub fn is_dark(&self, system_theme: Option<Theme>) -> bool {
+        match self.mode {
+            ThemeMode::Light => false,
+            ThemeMode::Dark => true,
+            ThemeMode::System => system_theme != Some(Theme::Light),
+        }
+    }
+
+    /// Opacity of the tint painted over the window background.
+    /// Dark mode stays see-through over the acrylic by default, while light text needs more backing to be readable
+    pub fn background_opacity(&self, dark_mode: bool) -> f32 {
+        self.background_opacity
+            .unwrap_or(if dark_mode { 0.0 } else { 0.7 })
+    }
+
     pub fn get_ansi_colors(&self) -> AnsiColors {
         if self.force_bright {
             AnsiColors {
@@ -43,54 +77,28 @@ impl ThemeConfig {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, Hash)]
-pub struct AnsiColors {
-    pub black: Rgb,
-    pub red: Rgb,
-    pub green: Rgb,
-    pub yellow: Rgb,
-    pub blue: Rgb,
-    pub magenta: Rgb,
-    pub cyan: Rgb,
-    pub white: Rgb,
-    pub bright_black: Rgb,
-    pub bright_red: Rgb,
-    pub bright_green: Rgb,
-    pub bright_yellow: Rgb,
-    pub bright_blue: Rgb,
-    pub bright_magenta: Rgb,
-    pub bright_cyan: Rgb,
-    pub bright_white: Rgb,
+fn default_zoom() -> f32 {
+    1.0
 }
 
-impl Default for AnsiColors {
-    fn default() -> Self {
-        Self {
-            black: Rgb(12, 12, 12),
-            red: Rgb(197, 15, 31),
-            green: Rgb(19, 161, 14),
-            yellow: Rgb(193, 156, 0),
-            blue: Rgb(0, 55, 218),
-            magenta: Rgb(136, 23, 152),
-            cyan: Rgb(58, 150, 221),
-            white: Rgb(204, 204, 204),
-            bright_black: Rgb(118, 118, 118),
-            bright_red: Rgb(231, 72, 86),
-            bright_green: Rgb(22, 198, 12),
-            bright_yellow: Rgb(249, 241, 165),
-            bright_blue: Rgb(59, 120, 255),
-            bright_magenta: Rgb(180, 0, 158),
-            bright_cyan: Rgb(97, 214, 214),
-            bright_white: Rgb(242, 242, 242),
-        }
-    }
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum ThemeMode {
+    Light,
+    Dark,
+    #[default]
+    System,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, Hash)]
-pub struct Rgb(pub u8, pub u8, pub u8);
+impl ThemeMode {
+    pub fn all() -> [Self; 3] {
+        [Self::Light, Self::Dark, Self::System]
+    }
 
-impl Rgb {
-    pub fn to_color32(self) -> Color32 {
-        Color32::from_rgb(self.0, self.1, self.2)
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+            Self::System => "Follow system",
+        }
     }
 }
@@ -0,0 +1,36 @@
+use std::sync::mpsc::Receiver;
+
+use cargo_player::Channel;
+
+use crate::utils::toolchain::Toolchain;
+
+#[derive(Default)]
+pub struct Toolchains {
+    // None until rustup was first asked
+    pub installed: Option<Vec<Toolchain>>,
+    // result of a running listing or install
+    pub loading: Option<Receiver<Vec<Toolchain>>>,
+}
+
+impl Toolchains {
+    pub fn get(&self, channel: Channel) -> Option<&Toolchain> {
+        self.installed
+            .as_ref()?
+            .iter()
+            .find(|toolchain| toolchain.is_channel(channel))
+    }
+
+    /// Whether the channel is known to be missing. Unknown while rustup wasn't asked yet
+    pub fn is_missing(&self, channel: Channel) -> bool {
+        self.installed.is_some() && self.get(channel).is_none()
+    }
+
+    /// Whether a component of the channel is known to be missing, also when the channel is
+    pub fn is_component_missing(&self, channel: Channel, component: &str) -> bool {
+        let installed = self
+            .get(channel)
+            .map_or(false, |toolchain| toolchain.has_component(component));
+
+        self.installed.is_some() && !installed
+    }
+}
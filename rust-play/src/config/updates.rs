@@ -0,0 +1,10 @@
+use std::sync::mpsc::Receiver;
+
+#[derive(Default)]
+pub struct Updates {
+    // newer stable release than the one installed
+    pub available: Option<String>,
+    // result of a running update check
+    pub checking: Option<Receiver<Option<String>>>,
+    pub dismissed: bool,
+}
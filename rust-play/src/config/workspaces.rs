@@ -0,0 +1,51 @@
+use std::io;
+use std::time::SystemTime;
+
+use rusqlite::params;
+
+use super::store::{timestamp, with_store};
+use crate::widgets::dock::Tree;
+
+/// Save the tabs and their layout under a name, replacing a workspace of the same name
+pub fn save_workspace(name: &str, tree: &Tree) -> io::Result<()> {
+    let content = serde_json::to_string(tree)?;
+
+    with_store(|tx| {
+        tx.execute(
+            "INSERT OR REPLACE INTO workspaces (name, tree, saved) VALUES (?1, ?2, ?3)",
+            params![name, content, timestamp(SystemTime::now())],
+        )
+    })
+    .map(|_| ())
+    .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "failed to save the workspace"))
+}
+
+/// None if there's no workspace of that name, or it can't be read anymore
+pub fn load_workspace(name: &str) -> Option<Tree> {
+    let content = with_store(|tx| {
+        tx.query_row(
+            "SELECT tree FROM workspaces WHERE name = ?1",
+            [name],
+            |row| row.get::<_, String>(0),
+        )
+    })?;
+
+    serde_json::from_str(&content).ok()
+}
+
+/// Names of the saved workspaces, most recently saved first
+pub fn load_workspaces() -> Vec<String> {
+    with_store(|tx| {
+        let names = tx
+            .prepare("SELECT name FROM workspaces ORDER BY saved DESC")?
+            .query_map([], |row| row.get(0))?
+            .collect();
+
+        names
+    })
+    .unwrap_or_default()
+}
+
+pub fn delete_workspace(name: &str) {
+    with_store(|tx| tx.execute("DELETE FROM workspaces WHERE name = ?1", [name]));
+}
@@ -0,0 +1,153 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use eframe::{egui, NativeOptions};
+use egui::{Align, CentralPanel, Layout, ScrollArea, TextEdit, TextStyle, TopBottomPanel, Vec2};
+use reqwest::Url;
+
+use crate::widgets::menubar::REPOSITORY_URL;
+
+const CRASH_REPORT_ARG: &str = "--crash-report";
+// github turns away issue links much longer than this, the rest is in the crash log
+const MAX_ISSUE_REPORT: usize = 6000;
+
+/// The crash log this instance was started to show, if it's a crash dialog
+pub fn crash_report() -> Option<PathBuf> {
+    let mut args = env::args_os().skip(1);
+
+    if args.next()? != CRASH_REPORT_ARG {
+        return None;
+    }
+
+    args.next().map(PathBuf::from)
+}
+
+/// Show a crash log in a new instance, the one which panicked may not be able to draw anymore
+pub fn open_dialog(log: &Path) -> io::Result<()> {
+    Command::new(env::current_exe()?)
+        .arg(CRASH_REPORT_ARG)
+        .arg(log)
+        .spawn()
+        .map(|_| ())
+}
+
+/// The crash dialog, until it's closed
+pub fn run(log: PathBuf) {
+    let report = fs::read_to_string(&log)
+        .unwrap_or_else(|e| format!("The crash log {} couldn't be read: {e}", log.display()));
+
+    let options = NativeOptions {
+        initial_window_size: Some(Vec2::new(640.0, 420.0)),
+        centered: true,
+        ..Default::default()
+    };
+
+    eframe::run_native(
+        "Rust Play crashed",
+        options,
+        Box::new(|_| {
+            Box::new(CrashDialog {
+                log,
+                report,
+                copied: false,
+            })
+        }),
+    );
+}
+
+struct CrashDialog {
+    log: PathBuf,
+    // the panic message and its short backtrace
+    report: String,
+    copied: bool,
+}
+
+impl eframe::App for CrashDialog {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        TopBottomPanel::bottom("crash_buttons").show(ctx, |ui| {
+            ui.add_space(4.0);
+
+            ui.horizontal(|ui| {
+                let copy = if self.copied {
+                    "Copied"
+                } else {
+                    "Copy to clipboard"
+                };
+
+                if ui.button(copy).clicked() {
+                    ctx.output().copied_text = self.report.clone();
+                    self.copied = true;
+                }
+
+                if ui.button("Open issue on GitHub").clicked() {
+                    if let Some(url) = issue_url(&self.report) {
+                        let _ = open::that(url.as_str());
+                    }
+                }
+
+                if ui.button("Open crash log folder").clicked() {
+                    if let Some(dir) = self.log.parent() {
+                        let _ = open::that(dir);
+                    }
+                }
+
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if ui.button("Close").clicked() {
+                        frame.close();
+                    }
+                });
+            });
+
+            ui.add_space(4.0);
+        });
+
+        CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Rust Play crashed");
+            ui.label(format!(
+                "Sorry about that. The report below was saved to {}, \
+                opening an issue with it helps getting it fixed",
+                self.log.display()
+            ));
+
+            ui.separator();
+
+            ScrollArea::both()
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    ui.add(
+                        TextEdit::multiline(&mut self.report.as_str())
+                            .font(TextStyle::Monospace)
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+        });
+    }
+}
+
+// a new issue with the report filled in, titled by the panic message
+fn issue_url(report: &str) -> Option<Url> {
+    let message = report.lines().next().unwrap_or_default();
+    let title = format!("Crash: {message}");
+
+    let mut end = report.len().min(MAX_ISSUE_REPORT);
+    while !report.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let body = format!(
+        "Rust Play {} on {} {}\n\n```\n{}\n```\n\nWhat I was doing when it crashed:\n",
+        env!("CARGO_PKG_VERSION"),
+        env::consts::OS,
+        env::consts::ARCH,
+        &report[..end]
+    );
+
+    Url::parse_with_params(
+        &format!("{REPOSITORY_URL}/issues/new"),
+        [("title", title.as_str()), ("body", body.as_str())],
+    )
+    .ok()
+}
@@ -0,0 +1,144 @@
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+use interprocess::local_socket::{LocalSocketListener, LocalSocketStream, NameTypeSupport};
+
+// command line flag starting a detached window for the draft with the key following it
+const DETACH_ARG: &str = "--detach";
+// prefixes a draft key sent back by a detached window. Forwarded paths are absolute, so never start with it
+const DOCK_PREFIX: &str = "dock:";
+// sent first by a second launch, so the running instance comes to the front even without paths to open
+const FOCUS: &str = "focus:";
+
+/// What another instance sent over
+#[derive(Debug)]
+pub enum Message {
+    // a file opened from the command line
+    Open(PathBuf),
+    // a detached window closed, its scratch goes back into the dock. The key of its draft
+    Dock(u64),
+    // another launch handed over to this instance, the window should come to the front
+    Focus,
+}
+
+// named pipe on Windows, unix socket elsewhere
+fn socket_name() -> &'static str {
+    match NameTypeSupport::query() {
+        NameTypeSupport::OnlyPaths => "/tmp/rust-play.sock",
+        NameTypeSupport::OnlyNamespaced | NameTypeSupport::Both => "@rust-play.sock",
+    }
+}
+
+/// Hand the paths over to an already running instance, one path per line.
+/// Returns true if another instance is running, in which case this process should exit
+pub fn forward_to_existing(paths: &[PathBuf]) -> bool {
+    // windows only lets the foreground process hand the foreground over to another one
+    #[cfg(target_os = "windows")]
+    unsafe {
+        use windows::Win32::UI::WindowsAndMessaging::{AllowSetForegroundWindow, ASFW_ANY};
+
+        AllowSetForegroundWindow(ASFW_ANY);
+    }
+
+    let focus = std::iter::once(FOCUS.to_string());
+    send(focus.chain(paths.iter().map(|path| path.display().to_string())))
+}
+
+/// Start a second instance in a window of its own, showing just the scratch of the draft
+pub fn spawn_detached(draft_key: u64) -> io::Result<()> {
+    Command::new(env::current_exe()?)
+        .args([DETACH_ARG, &draft_key.to_string()])
+        .spawn()
+        .map(|_| ())
+}
+
+/// The draft key this instance was started with, if it's a detached window
+pub fn detached_draft() -> Option<u64> {
+    let mut args = env::args().skip(1);
+
+    if args.next()? != DETACH_ARG {
+        return None;
+    }
+
+    args.next()?.parse().ok()
+}
+
+/// Send the drafts of a detached window's scratches back to the instance it was detached from.
+/// Returns false if that one isn't running anymore
+pub fn dock_back(draft_keys: &[u64]) -> bool {
+    send(draft_keys.iter().map(|key| format!("{DOCK_PREFIX}{key}")))
+}
+
+fn send(lines: impl IntoIterator<Item = String>) -> bool {
+    let Ok(mut stream) = LocalSocketStream::connect(socket_name()) else {
+        return false;
+    };
+
+    for line in lines {
+        let _ = writeln!(stream, "{line}");
+    }
+
+    true
+}
+
+/// Listen for messages from other instances
+/// Does not block, but instead returns a receiver you can use to receive them
+pub fn listen(ctx: egui::Context) -> Receiver<Message> {
+    let (tx, rx) = channel();
+
+    thread::spawn(move || {
+        let name = socket_name();
+
+        let listener = match LocalSocketListener::bind(name) {
+            Ok(listener) => listener,
+            Err(_) => {
+                // a crashed instance may have left a stale socket file behind
+                if name.starts_with('/') {
+                    let _ = std::fs::remove_file(name);
+                }
+
+                match LocalSocketListener::bind(name) {
+                    Ok(listener) => listener,
+                    Err(_) => return,
+                }
+            }
+        };
+
+        for conn in listener.incoming().filter_map(Result::ok) {
+            for line in BufReader::new(conn).lines().filter_map(Result::ok) {
+                if line.is_empty() {
+                    continue;
+                }
+
+                if line == FOCUS {
+                    if tx.send(Message::Focus).is_err() {
+                        return;
+                    }
+
+                    ctx.request_repaint();
+                    continue;
+                }
+
+                let message = match line.strip_prefix(DOCK_PREFIX) {
+                    Some(key) => match key.parse() {
+                        Ok(key) => Message::Dock(key),
+                        Err(_) => continue,
+                    },
+                    None => Message::Open(PathBuf::from(line)),
+                };
+
+                if tx.send(message).is_err() {
+                    return;
+                }
+
+                ctx.request_repaint();
+            }
+        }
+    });
+
+    rx
+}
@@ -8,16 +8,21 @@
 mod os;
 
 mod config;
+mod crash;
+mod ipc;
 mod panic;
 mod popup;
+mod shutdown;
 mod utils;
 mod widgets;
 
 #[cfg(target_os = "windows")]
 use {
+    config::ShortcutKeys,
     os::windows::{
         custom_frame::{self},
         init::load_app_icon,
+        keyboard,
         win_version::is_supported_os,
     },
     std::sync::mpsc::{channel, Sender},
@@ -28,15 +33,27 @@ use std::fs;
 use std::rc::Rc;
 use std::sync::mpsc::Receiver;
 
-use config::Config;
+use config::{config_dir, load_draft, reload_themes, Command, Config, SettingsError, TabCommand};
 use egui::{CentralPanel, Frame, Id, Rect, Ui, Vec2};
+use egui_dock::NodeIndex;
 use panic::set_hook;
 use popup::{display_popup, MessageBoxIcon};
-use widgets::dock::{Dock, TabEvents};
+use shutdown::Shutdown;
+use utils::fonts;
+use widgets::command_palette::CommandPalette;
+use widgets::defender::DefenderHint;
+use widgets::disk_usage::DiskUsageMeter;
+use widgets::dock::{Dock, Tab, TabEvents, Tree};
+use widgets::idle::IdleReclaimer;
+use widgets::menubar::MenuBar;
 
 use eframe::{egui, NativeOptions};
+use widgets::performance::PerformanceHud;
+use widgets::quick_run::QuickRunPopup;
+use widgets::setup::SetupCheck;
 use widgets::terminal::Terminal;
-use widgets::titlebar::custom_window_frame;
+use widgets::titlebar::{custom_window_frame, MENUBAR_HEIGHT};
+use widgets::toasts::{Toast, ToastAction, Toasts};
 
 // Each rectangle is an entire tree; not a single tab
 #[cfg(target_os = "windows")]
@@ -46,6 +63,12 @@ fn main() {
     // set up custom panic hook
     set_hook();
 
+    // started by a panicking instance to show what happened
+    if let Some(log) = crash::crash_report() {
+        crash::run(log);
+        return;
+    }
+
     // check windows version
     #[cfg(target_os = "windows")]
     if !is_supported_os() {
@@ -57,17 +80,43 @@ fn main() {
         return;
     }
 
+    // a window detached from another instance, showing just one scratch
+    let detached = ipc::detached_draft();
+
+    // files passed on the command line are opened as tabs
+    let paths = if detached.is_some() {
+        vec![]
+    } else {
+        env::args_os()
+            .skip(1)
+            .filter_map(|path| fs::canonicalize(path).ok())
+            .collect::<Vec<_>>()
+    };
+
     #[cfg(target_os = "windows")]
-    let app = {
-        let (app, rx) = App::new();
+    let (mut app, rx) = App::new();
 
-        custom_frame::init(rx);
+    #[cfg(not(target_os = "windows"))]
+    let mut app = App::new();
 
-        app
-    };
+    if let Some(key) = detached {
+        app.detach(key);
+    }
 
-    #[cfg(not(target_os = "windows"))]
-    let app = App::new();
+    // another instance is already running, let it open the files instead
+    if !app.detached && app.config.general.single_instance && ipc::forward_to_existing(&paths) {
+        return;
+    }
+
+    #[cfg(target_os = "windows")]
+    custom_frame::init(rx);
+
+    for path in paths {
+        app.config
+            .dock
+            .commands
+            .push(Command::TabCommand(TabCommand::Open(path)));
+    }
 
     tracing_subscriber::fmt::init();
 
@@ -78,12 +127,57 @@ fn main() {
         transparent: true,
         resizable: true,
         centered: true,
+        // keeps the reported system theme up to date
+        follow_system_theme: true,
         #[cfg(not(target_os = "windows"))]
         decorated: false,
         ..Default::default()
     };
 
-    eframe::run_native("Rust Play", options, Box::new(|_cc| Box::new(app)));
+    eframe::run_native(
+        "Rust Play",
+        options,
+        Box::new(move |cc| {
+            // the user's syntax themes, before anything is highlighted with them
+            reload_themes(&cc.egui_ctx);
+
+            if app.config.editor.font_family.is_some() {
+                fonts::apply_monospace_family(
+                    &cc.egui_ctx,
+                    app.config.editor.font_family.as_deref(),
+                );
+            }
+
+            if app.config.general.single_instance && !app.detached {
+                app.ipc = Some(ipc::listen(cc.egui_ctx.clone()));
+            }
+
+            if let Some((error, backed_up)) = app.settings_error.take() {
+                show_settings_error(&cc.egui_ctx, &error, backed_up);
+            }
+
+            app.ctx = Some(cc.egui_ctx.clone());
+
+            Box::new(app)
+        }),
+    );
+}
+
+// the defaults are used instead of the settings, which isn't worth stopping over
+fn show_settings_error(ctx: &egui::Context, error: &SettingsError, backed_up: bool) {
+    let mut message = format!("{error}\nUsing the default settings for now.");
+    if backed_up {
+        message.push_str(" The file was kept as settings.toml.bak");
+    } else {
+        message.push_str(" Changes to the settings won't be saved, so the file isn't lost");
+    }
+
+    Toast::warning(message)
+        .action(ToastAction::Open {
+            label: "Open config folder".to_string(),
+            url: config_dir().display().to_string(),
+        })
+        .push(ctx);
 }
 
 struct App {
@@ -92,6 +186,18 @@ struct App {
     // tab and uncovered titlebar
     #[cfg(target_os = "windows")]
     tx: Rc<Sender<CaptionMaxRect>>,
+    // paths forwarded from other instances, and scratches docked back from detached windows
+    ipc: Option<Receiver<ipc::Message>>,
+    // this window was detached from another instance, its scratches go back there once it's closed
+    detached: bool,
+    // the light/dark mode last applied to the visuals and window frame
+    dark_mode: Option<bool>,
+    // to repaint when closing the window was asked for, which doesn't come with a context
+    ctx: Option<egui::Context>,
+    // the window was asked to close, it does once this is done
+    shutdown: Option<Shutdown>,
+    // settings.toml couldn't be loaded, shown once there's a window. Whether it was backed up
+    settings_error: Option<(SettingsError, bool)>,
 }
 
 impl App {
@@ -99,28 +205,22 @@ impl App {
     fn new() -> (Self, Receiver<CaptionMaxRect>) {
         let (tx, rx) = channel();
 
-        let current_dir = env::current_exe().unwrap().parent().unwrap().to_owned();
-        let file = current_dir.join("settings.toml");
-
-        let mut config = if file.exists() {
-            let content = fs::read_to_string(file).expect("Failed to read config file");
-            toml::from_str::<Config>(&content).unwrap_or_default()
-        } else {
-            Config::default()
-        };
+        let (mut config, settings_error) = Config::load();
 
         // initialize the terminal data
         config.terminal.active_tab = Some(config.dock.tree.find_active().unwrap().1.id);
-        config.terminal.scroll_offset.insert(
-            config.dock.tree.find_active().unwrap().1.id,
-            Vec2::default(),
-        );
 
         config.dock.counter = 2;
 
         let app = Self {
             tx: Rc::new(tx),
             config,
+            ipc: None,
+            detached: false,
+            dark_mode: None,
+            ctx: None,
+            shutdown: None,
+            settings_error,
         };
 
         (app, rx)
@@ -130,14 +230,84 @@ impl App {
     fn new() -> Self {
         Self {
             config: Config::default(),
+            ipc: None,
+            detached: false,
+            dark_mode: None,
+            ctx: None,
+            shutdown: None,
+            settings_error: None,
         }
     }
 
+    // show only the scratch of the draft, it was detached from another instance
+    fn detach(&mut self, draft_key: u64) {
+        self.detached = true;
+
+        let Some(draft) = load_draft(draft_key) else {
+            return;
+        };
+
+        let tab = Tab::from_draft(&draft, self.config.dock.counter);
+        self.config.terminal.active_tab = Some(tab.id);
+
+        let mut tree = Tree::new(vec![tab]);
+        tree.set_focused_node(NodeIndex::root());
+        self.config.dock.tree = tree;
+        self.config.dock.counter += 1;
+    }
+
+    /// Keep egui's visuals and the window frame in sync with the theme mode setting.
+    /// The syntax theme follows the visuals unless one was explicitly picked
+    fn apply_theme_mode(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let dark = self.config.theme.is_dark(frame.info().system_theme);
+
+        // eframe also resets the visuals itself when the system theme changes
+        if self.dark_mode == Some(dark) && ctx.style().visuals.dark_mode == dark {
+            return;
+        }
+
+        ctx.set_visuals(if dark {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        });
+
+        #[cfg(target_os = "windows")]
+        custom_frame::set_dark_mode(dark);
+
+        self.dark_mode = Some(dark);
+    }
+
     fn show_dock(&mut self, ctx: &egui::Context, ui: &mut Ui) {
         Dock::show(ctx, &mut self.config, ui);
     }
 
     fn handle_tabs(&mut self, ctx: &egui::Context) {
+        if let Some(ipc) = &self.ipc {
+            for message in ipc.try_iter() {
+                let command = match message {
+                    ipc::Message::Open(path) => TabCommand::Open(path),
+                    ipc::Message::Dock(key) => match load_draft(key) {
+                        Some(draft) => TabCommand::Restore(draft),
+                        None => continue,
+                    },
+                    ipc::Message::Focus => {
+                        #[cfg(target_os = "windows")]
+                        custom_frame::focus();
+
+                        // eframe can't raise the window elsewhere, this says what happened once it's looked at
+                        #[cfg(not(target_os = "windows"))]
+                        Toast::info("Rust Play was started again, it opened in this window")
+                            .push(ctx);
+
+                        continue;
+                    }
+                };
+
+                self.config.dock.commands.push(Command::TabCommand(command));
+            }
+        }
+
         TabEvents::show(ctx, &mut self.config);
     }
 
@@ -152,25 +322,52 @@ impl App {
 
 impl eframe::App for App {
     fn on_close_event(&mut self) -> bool {
-        // Write config to settings.toml
-
-        let config_string =
-            toml::to_string(&self.config).expect("Failed to convert config to toml");
+        if let Some(shutdown) = &self.shutdown {
+            return shutdown.is_done();
+        }
 
-        let current_dir = env::current_exe().unwrap().parent().unwrap().to_owned();
-        let file = current_dir.join("settings.toml");
+        // the next frames shut down in order, and close the window again once done
+        self.shutdown = Some(Shutdown::default());
 
-        fs::write(file, config_string).expect("Failed to write config file");
+        if let Some(ctx) = &self.ctx {
+            ctx.request_repaint();
+        }
 
-        true
+        false
     }
 
-    // Clear the overlay over the entire background so we have a blank slate to work with
-    fn clear_color(&self, _: &egui::Visuals) -> egui::Rgba {
-        egui::Rgba::TRANSPARENT
+    // Clear the overlay over the entire background, tinted by the opacity setting.
+    // On Windows the acrylic blur shows through whatever is left transparent
+    fn clear_color(&self, visuals: &egui::Visuals) -> egui::Rgba {
+        let opacity = self.config.theme.background_opacity(visuals.dark_mode);
+
+        egui::Rgba::from(visuals.panel_fill).multiply(opacity)
     }
 
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if let Some(shutdown) = &mut self.shutdown {
+            if shutdown.show(ctx, &mut self.config, self.detached) {
+                frame.close();
+            }
+
+            return;
+        }
+
+        PerformanceHud::record(ctx, frame, &mut self.config);
+
+        self.apply_theme_mode(ctx, frame);
+
+        #[cfg(target_os = "windows")]
+        keyboard::set_key_positions(self.config.general.shortcut_keys == ShortcutKeys::Position);
+
+        Terminal::collect_all_output(&mut self.config.terminal);
+        Terminal::auto_close(ctx, &mut self.config);
+
+        DiskUsageMeter::update(ctx, &mut self.config);
+
+        // first, so its keys aren't typed into the editor
+        CommandPalette::show(ctx, &mut self.config);
+
         if self.config.terminal.open {
             self.show_terminal(ctx);
         } else {
@@ -180,19 +377,47 @@ impl eframe::App for App {
         CentralPanel::default()
             .frame(Frame::none())
             .show(ctx, |ui| {
+                // menus go first so they take priority over dragging the window
+                let menu_rect = MenuBar::show(ctx, &mut self.config, ui);
+
+                // double clicks on the tab bars are the dock's, unless it doesn't do anything with them
+                let double_click = self.config.general.double_click;
+                let maximize_on_double_click = double_click.maximize
+                    && Dock::double_click_action(ctx, &self.config.dock.tree, double_click)
+                        .is_none();
+
                 custom_window_frame(
                     ctx,
                     frame,
                     ui,
+                    menu_rect,
+                    maximize_on_double_click,
                     #[cfg(target_os = "windows")]
                     Rc::clone(&self.tx),
                 );
 
-                self.show_dock(ctx, ui);
+                // the dock starts below the menu bar strip
+                let mut dock_rect = ui.max_rect();
+                dock_rect.set_top(dock_rect.top() + MENUBAR_HEIGHT as f32 / 2.0);
+                let mut dock_ui = ui.child_ui(dock_rect, *ui.layout());
+
+                self.show_dock(ctx, &mut dock_ui);
             });
 
         self.handle_tabs(ctx);
 
+        QuickRunPopup::show(ctx, &mut self.config);
+
+        DefenderHint::show(ctx, &mut self.config);
+
+        SetupCheck::show(ctx, &mut self.config);
+
+        Toasts::show(ctx);
+
+        PerformanceHud::show(ctx, &mut self.config);
+
+        IdleReclaimer::update(ctx, &mut self.config);
+
         let counter = ctx
             .memory()
             .data
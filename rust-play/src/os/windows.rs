@@ -1,4 +1,5 @@
 pub mod custom_frame;
 pub mod dwm_win32;
 pub mod init;
+pub mod keyboard;
 pub mod win_version;
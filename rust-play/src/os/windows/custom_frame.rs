@@ -5,9 +5,13 @@ use crate::CaptionMaxRect;
 use egui::{mutex::RwLock, Rect};
 use once_cell::sync::OnceCell;
 
+use windows::Win32::UI::Input::Pointer::GetPointerType;
 use windows::Win32::UI::WindowsAndMessaging::{
-    SetWindowLongPtrW, HTCLOSE, HTMAXBUTTON, HTMINBUTTON, WM_CREATE, WM_NCLBUTTONDOWN,
-    WM_STYLECHANGED, WS_SYSMENU,
+    GetMessageExtraInfo, IsIconic, SetForegroundWindow, SetWindowLongPtrW, ShowWindow, HTCLOSE,
+    HTMAXBUTTON, HTMINBUTTON, PT_PEN, PT_POINTER, PT_TOUCH, SW_RESTORE, WM_CREATE, WM_MOUSEMOVE,
+    WM_NCLBUTTONDBLCLK, WM_NCLBUTTONDOWN, WM_NCMOUSEMOVE, WM_NCPOINTERDOWN, WM_NCPOINTERUP,
+    WM_NCPOINTERUPDATE, WM_POINTERDOWN, WM_POINTERUP, WM_POINTERUPDATE, WM_STYLECHANGED,
+    WS_SYSMENU,
 };
 use windows::Win32::{
     Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
@@ -26,11 +30,32 @@ use windows::Win32::{
     },
 };
 
-use super::dwm_win32::apply_acrylic;
+use super::dwm_win32::{apply_acrylic, force_dark_theme, force_light_theme};
+use super::keyboard::key_message;
 
 const WC_DIALOG: u32 = 0x8002;
 
 static MAX_RECT: OnceCell<RwLock<CaptionMaxRect>> = OnceCell::new();
+// the menu bar lives in the titlebar, and must always be treated as client area
+static MENU_RECT: OnceCell<RwLock<Rect>> = OnceCell::new();
+// the main window, so the frame can be restyled after creation
+static MAIN_WINDOW: OnceCell<HWND> = OnceCell::new();
+// the last touch or pen contact. GetAsyncKeyState and GetCursorPos don't reliably report these,
+// so the caption buttons use this instead until the mouse is used again
+static TOUCH: Mutex<Option<TouchState>> = Mutex::new(None);
+
+// mouse messages synthesized from touch or pen input carry this signature in their extra info
+const MI_WP_SIGNATURE: u32 = 0xFF515700;
+const SIGNATURE_MASK: u32 = 0xFFFFFF00;
+// set in the high word of the wparam of pointer messages while the pointer is touching the screen
+const POINTER_MESSAGE_FLAG_INCONTACT: usize = 0x4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TouchState {
+    pub down: bool,
+    // in screen coords
+    pub pos: POINT,
+}
 
 // macro_rules! RGB {
 //     ($r:expr, $g:expr, $b:expr) => {{
@@ -53,9 +78,45 @@ macro_rules! y_coord {
     };
 }
 
+pub fn set_menu_rect(rect: Rect) {
+    if let Some(menu_rect) = MENU_RECT.get() {
+        *menu_rect.write() = rect;
+    }
+}
+
+/// The last touch or pen contact, if touch or pen was used more recently than the mouse
+pub fn touch_state() -> Option<TouchState> {
+    *TOUCH.lock().unwrap()
+}
+
+/// Bring the main window to the front, restoring it if it's minimized
+pub fn focus() {
+    if let Some(&hwnd) = MAIN_WINDOW.get() {
+        unsafe {
+            if IsIconic(hwnd).as_bool() {
+                ShowWindow(hwnd, SW_RESTORE);
+            }
+
+            SetForegroundWindow(hwnd);
+        }
+    }
+}
+
+/// Switch the DWM frame between its dark and light appearance
+pub fn set_dark_mode(dark: bool) {
+    if let Some(&hwnd) = MAIN_WINDOW.get() {
+        if dark {
+            force_dark_theme(hwnd);
+        } else {
+            force_light_theme(hwnd);
+        }
+    }
+}
+
 pub fn init(receiver: Receiver<CaptionMaxRect>) {
     // continually update the covered titlebar area
     let _ = MAX_RECT.set(RwLock::new(Rect::NOTHING));
+    let _ = MENU_RECT.set(RwLock::new(Rect::NOTHING));
 
     // thread to watch for events down the channel and update them
     std::thread::spawn(move || loop {
@@ -135,6 +196,12 @@ unsafe extern "system" fn subclass_proc(
     let mut f_call_dsp = true;
     let mut l_ret = 0;
 
+    // keys typing a character with AltGr, and shortcuts read by key position, are sorted out before anything
+    // else. The character of a dropped key still comes, it was translated before the key got here
+    let Some(wparam) = key_message(u_msg, wparam, lparam) else {
+        return LRESULT(0);
+    };
+
     if is_dwm_enabled() {
         l_ret = custom_subclass_proc(
             hwnd,
@@ -180,6 +247,8 @@ unsafe fn custom_subclass_proc(
             DwmExtendFrameIntoClientArea(hwnd, &margins).expect("Failed to extend frame");
 
             apply_acrylic(hwnd, None);
+
+            let _ = MAIN_WINDOW.set(hwnd);
         }
 
         WM_STYLECHANGED => {
@@ -209,8 +278,24 @@ unsafe fn custom_subclass_proc(
             }
         }
 
-        // When HTMAXBUTTON is pressed, DO NOT let default handler handle it, just no-op it
-        WM_NCLBUTTONDOWN => match wparam as u32 {
+        // keep track of touch and pen contacts, but still let them get promoted to mouse messages
+        WM_POINTERDOWN | WM_POINTERUPDATE | WM_POINTERUP | WM_NCPOINTERDOWN
+        | WM_NCPOINTERUPDATE | WM_NCPOINTERUP => {
+            update_touch_state(wparam, lparam);
+        }
+
+        // the mouse is being used again
+        WM_MOUSEMOVE | WM_NCMOUSEMOVE => {
+            let extra_info = GetMessageExtraInfo().0 as u32;
+
+            if extra_info & SIGNATURE_MASK != MI_WP_SIGNATURE {
+                *TOUCH.lock().unwrap() = None;
+            }
+        }
+
+        // When HTMAXBUTTON is pressed, DO NOT let default handler handle it, just no-op it.
+        // A quick second press comes as a double click, which would otherwise toggle the window on its own
+        WM_NCLBUTTONDOWN | WM_NCLBUTTONDBLCLK => match wparam as u32 {
             HTMINBUTTON | HTMAXBUTTON | HTCLOSE => {
                 *f_call_dsp = false;
                 l_ret = 0;
@@ -225,6 +310,27 @@ unsafe fn custom_subclass_proc(
     l_ret
 }
 
+unsafe fn update_touch_state(wparam: usize, lparam: isize) {
+    let pointer_id = (wparam & 0xFFFF) as u32;
+
+    let mut pointer_type = PT_POINTER;
+    if !GetPointerType(pointer_id, &mut pointer_type).as_bool() {
+        return;
+    }
+
+    if pointer_type != PT_TOUCH && pointer_type != PT_PEN {
+        return;
+    }
+
+    let down = (wparam >> 16) & POINTER_MESSAGE_FLAG_INCONTACT != 0;
+    let pos = POINT {
+        x: x_coord!(lparam),
+        y: y_coord!(lparam),
+    };
+
+    *TOUCH.lock().unwrap() = Some(TouchState { down, pos });
+}
+
 // Hit test the frame for resizing and moving, and overlayed content in titlebar
 fn hit_test_nca(hwnd: HWND, _: usize, lparam: isize, uidsubclass: usize) -> isize {
     // Get the point coordinates for the hit test.
@@ -273,6 +379,24 @@ fn hit_test_nca(hwnd: HWND, _: usize, lparam: isize, uidsubclass: usize) -> isiz
         {
             return HTMAXBUTTON as isize;
         }
+
+        let rect = MENU_RECT.get().unwrap().read();
+
+        let menu_rect = RECT {
+            left: rc_window.left + (rect.left().floor() as i32 * 2),
+            right: rc_window.left + (rect.right().ceil() as i32 * 2),
+            top: rc_window.top + (rect.top().floor() as i32 * 2),
+            bottom: rc_window.top + (rect.bottom().ceil() as i32 * 2),
+        };
+
+        // don't let the top resize grip steal clicks from the menus
+        if cursor_pos.x >= menu_rect.left
+            && cursor_pos.x <= menu_rect.right
+            && cursor_pos.y >= menu_rect.top
+            && cursor_pos.y <= menu_rect.bottom
+        {
+            return HTNOWHERE as isize;
+        }
     }
 
     // Determine if the point is at the top or bottom of the window.
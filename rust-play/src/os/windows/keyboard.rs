@@ -0,0 +1,207 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, GetKeyboardState, ToUnicode, VIRTUAL_KEY, VK_CONTROL, VK_LCONTROL, VK_LMENU,
+    VK_LWIN, VK_MENU, VK_OEM_1, VK_OEM_2, VK_OEM_3, VK_OEM_4, VK_OEM_5, VK_OEM_6, VK_OEM_7,
+    VK_OEM_COMMA, VK_OEM_MINUS, VK_OEM_PERIOD, VK_OEM_PLUS, VK_RCONTROL, VK_RMENU, VK_RWIN,
+    VK_SHIFT,
+};
+use windows::Win32::UI::WindowsAndMessaging::{WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP};
+
+// shortcuts are read by where their keys are, as on a US QWERTY keyboard, instead of by the layout
+static KEY_POSITIONS: AtomicBool = AtomicBool::new(false);
+
+// the virtual key each key was last pressed as, by scancode and then by whether it's an extended key, so its
+// release goes out as the same key even when Ctrl was let go first. 0 if it went out as it came in
+static PRESSED_AS: Mutex<[u16; 512]> = Mutex::new([0; 512]);
+
+// the scancode of a key message is in bits 16-23 of the lparam, bit 24 is set for extended keys
+const SCANCODE_SHIFT: isize = 16;
+const EXTENDED_KEY: isize = 1 << 24;
+// ToUnicode leaves the keyboard state alone with this flag, so a dead key still combines with the next key
+const KEEP_KEYBOARD_STATE: u32 = 1 << 2;
+
+// the rows of letters of a US QWERTY keyboard and the scancode of their first key
+const LETTER_ROWS: [(u32, &[u8]); 3] = [
+    (0x10, b"QWERTYUIOP"),
+    (0x1E, b"ASDFGHJKL"),
+    (0x2C, b"ZXCVBNM"),
+];
+
+/// Read shortcuts by the position of their keys instead of what the keyboard layout puts there,
+/// e.g. Ctrl+Z stays next to Shift on a QWERTZ layout
+pub fn set_key_positions(enabled: bool) {
+    KEY_POSITIONS.store(enabled, Ordering::Relaxed);
+}
+
+/// Look at a message before the window does. Keys typing a character with AltGr only type their
+/// character, so they can't trigger the Ctrl+Alt shortcut of the key as well. Keys pressed with Ctrl or Alt
+/// get the virtual key of their position if shortcuts are read by position. A release always goes out as
+/// the key was pressed, so no key is left held down.
+/// Returns the wparam to pass on, None if the message is dropped
+pub fn key_message(u_msg: u32, wparam: WPARAM, lparam: LPARAM) -> Option<WPARAM> {
+    let down = matches!(u_msg, WM_KEYDOWN | WM_SYSKEYDOWN);
+    if !down && !matches!(u_msg, WM_KEYUP | WM_SYSKEYUP) {
+        return Some(wparam);
+    }
+
+    let key = VIRTUAL_KEY(wparam.0 as u16);
+    if is_modifier(key) {
+        return Some(wparam);
+    }
+
+    let scancode = (lparam.0 >> SCANCODE_SHIFT) as u32 & 0xFF;
+    let extended = lparam.0 & EXTENDED_KEY != 0;
+    let slot = scancode as usize + if extended { 256 } else { 0 };
+
+    let mut pressed_as = PRESSED_AS.lock().unwrap();
+
+    if !down {
+        let pressed = std::mem::take(&mut pressed_as[slot]);
+        return Some(if pressed == 0 {
+            wparam
+        } else {
+            WPARAM(pressed as usize)
+        });
+    }
+
+    pressed_as[slot] = 0;
+
+    // Windows presses a left Ctrl along with the right Alt when it's AltGr. Keys which don't type
+    // anything with it, e.g. the arrows, are passed on as Ctrl+Alt
+    if is_down(VK_RMENU) && is_down(VK_LCONTROL) {
+        return (!types_character(key, scancode)).then_some(wparam);
+    }
+
+    if !KEY_POSITIONS.load(Ordering::Relaxed) || !(is_down(VK_CONTROL) || is_down(VK_MENU)) {
+        return Some(wparam);
+    }
+
+    let Some(position) = (!extended).then(|| qwerty_key(scancode)).flatten() else {
+        return Some(wparam);
+    };
+
+    pressed_as[slot] = position.0;
+
+    Some(WPARAM(position.0 as usize))
+}
+
+// whether the key types a character, or starts one with a dead key, with the modifiers held down now.
+// If the keyboard state can't be read it's taken to type one
+fn types_character(key: VIRTUAL_KEY, scancode: u32) -> bool {
+    let mut state = [0; 256];
+    let mut buffer = [0; 8];
+
+    unsafe {
+        if !GetKeyboardState(&mut state).as_bool() {
+            return true;
+        }
+
+        ToUnicode(
+            key.0 as u32,
+            scancode,
+            Some(&state),
+            &mut buffer,
+            KEEP_KEYBOARD_STATE,
+        ) != 0
+    }
+}
+
+fn is_down(key: VIRTUAL_KEY) -> bool {
+    unsafe { GetKeyState(key.0 as i32) < 0 }
+}
+
+fn is_modifier(key: VIRTUAL_KEY) -> bool {
+    [
+        VK_SHIFT,
+        VK_CONTROL,
+        VK_LCONTROL,
+        VK_RCONTROL,
+        VK_MENU,
+        VK_LMENU,
+        VK_RMENU,
+        VK_LWIN,
+        VK_RWIN,
+    ]
+    .contains(&key)
+}
+
+// the virtual key a US QWERTY layout has at a scancode, for the keys layouts tend to move around
+fn qwerty_key(scancode: u32) -> Option<VIRTUAL_KEY> {
+    for (first, row) in LETTER_ROWS {
+        if let Some(&letter) = scancode
+            .checked_sub(first)
+            .and_then(|i| row.get(i as usize))
+        {
+            return Some(VIRTUAL_KEY(letter as u16));
+        }
+    }
+
+    let key = match scancode {
+        // 1 to 9, then 0
+        0x02..=0x0A => VIRTUAL_KEY(b'1' as u16 + (scancode - 0x02) as u16),
+        0x0B => VIRTUAL_KEY(b'0' as u16),
+        0x0C => VK_OEM_MINUS,
+        0x0D => VK_OEM_PLUS,
+        0x1A => VK_OEM_4,
+        0x1B => VK_OEM_6,
+        0x27 => VK_OEM_1,
+        0x28 => VK_OEM_7,
+        0x29 => VK_OEM_3,
+        0x2B => VK_OEM_5,
+        0x33 => VK_OEM_COMMA,
+        0x34 => VK_OEM_PERIOD,
+        0x35 => VK_OEM_2,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letter(c: u8) -> Option<VIRTUAL_KEY> {
+        Some(VIRTUAL_KEY(c as u16))
+    }
+
+    #[test]
+    fn letter_rows() {
+        assert_eq!(qwerty_key(0x10), letter(b'Q'));
+        assert_eq!(qwerty_key(0x15), letter(b'Y'));
+        assert_eq!(qwerty_key(0x19), letter(b'P'));
+        assert_eq!(qwerty_key(0x1E), letter(b'A'));
+        assert_eq!(qwerty_key(0x26), letter(b'L'));
+        assert_eq!(qwerty_key(0x2C), letter(b'Z'));
+        assert_eq!(qwerty_key(0x32), letter(b'M'));
+    }
+
+    #[test]
+    fn digits() {
+        assert_eq!(qwerty_key(0x02), letter(b'1'));
+        assert_eq!(qwerty_key(0x0A), letter(b'9'));
+        assert_eq!(qwerty_key(0x0B), letter(b'0'));
+    }
+
+    #[test]
+    fn punctuation() {
+        // right after the ends of the letter rows
+        assert_eq!(qwerty_key(0x1A), Some(VK_OEM_4));
+        assert_eq!(qwerty_key(0x27), Some(VK_OEM_1));
+        assert_eq!(qwerty_key(0x33), Some(VK_OEM_COMMA));
+
+        assert_eq!(qwerty_key(0x0C), Some(VK_OEM_MINUS));
+        assert_eq!(qwerty_key(0x35), Some(VK_OEM_2));
+    }
+
+    #[test]
+    fn other_keys() {
+        // escape, tab, enter and space stay where they are on every layout
+        for scancode in [0x01, 0x0F, 0x1C, 0x39] {
+            assert_eq!(qwerty_key(scancode), None);
+        }
+    }
+}
@@ -1,83 +1,157 @@
+use std::backtrace::Backtrace;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
 use std::panic;
-#[cfg(debug_assertions)]
-use {regex::Regex, std::backtrace::Backtrace};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use regex::Regex;
+
+use crate::config::{crash_logs_dir, panic_log_file};
+use crate::crash;
 use crate::popup::{display_popup, MessageBoxIcon};
 
+// a crash dialog was opened already
+// the size panic.log may grow to before it starts over
+const MAX_PANIC_LOG_LEN: u64 = 256 * 1024;
+
+static REPORTED: AtomicBool = AtomicBool::new(false);
+
 pub fn set_hook() {
     panic::set_hook(Box::new(|v| {
+        let message = v.to_string();
+        log_panic(&message);
+
+        let report = format!(
+            "{message}\n\nstack backtrace:\n{}",
+            short_backtrace(&Backtrace::force_capture().to_string())
+        );
+
         #[cfg(debug_assertions)]
-        {
-            let panic_msg = v.to_string();
-            let backtrace = Backtrace::force_capture();
-
-            let full_backtrace = backtrace.to_string();
-            let raw_frames = full_backtrace.split("\n").collect::<Vec<_>>();
-
-            // Sort frames into a single frame depending on frame content
-            let mut frames = vec![];
-            for chunk_frames in raw_frames.chunks(2) {
-                let main_frame = chunk_frames.get(0);
-                let sub_frame = chunk_frames.get(1);
-
-                if main_frame.is_some() && sub_frame.is_some() {
-                    let main_frame = *main_frame.unwrap();
-                    let sub_frame = *sub_frame.unwrap();
-
-                    if sub_frame.trim().starts_with("at") {
-                        frames.push(format!("{main_frame}\n{sub_frame}"));
-                    } else if main_frame.trim().starts_with("at") {
-                        frames
-                            .last_mut()
-                            .unwrap()
-                            .push_str(&format!("\n{main_frame}"));
-                        frames.push(sub_frame.to_string());
-                    } else {
-                        frames.push(main_frame.to_string());
-                        if !sub_frame.trim().is_empty() {
-                            frames.push(sub_frame.to_string());
-                        }
-                    }
-                } else {
-                    let main_frame = main_frame.unwrap();
-                    if !main_frame.trim().is_empty() {
-                        // end of array
-                        frames.push(main_frame.to_string());
-                    }
+        eprintln!("{report}");
+
+        // one dialog is enough, e.g. for the threads taken down by the first panic. Those are in the panic log.
+        // A panicking crash dialog mustn't open another one either
+        if REPORTED.swap(true, Ordering::SeqCst) || crash::crash_report().is_some() {
+            return;
+        }
+
+        let opened = write_crash_log(&report).and_then(|log| crash::open_dialog(&log));
+
+        if opened.is_err() {
+            display_popup("RustPlay panicked :(", &message, MessageBoxIcon::Error);
+        }
+    }));
+}
+
+// the frames between the panic machinery and the start of the thread, numbered from 0
+fn short_backtrace(backtrace: &str) -> String {
+    let raw_frames = backtrace.split("\n").collect::<Vec<_>>();
+
+    // Sort frames into a single frame depending on frame content
+    let mut frames = vec![];
+    for chunk_frames in raw_frames.chunks(2) {
+        let main_frame = chunk_frames.get(0);
+        let sub_frame = chunk_frames.get(1);
+
+        if main_frame.is_some() && sub_frame.is_some() {
+            let main_frame = *main_frame.unwrap();
+            let sub_frame = *sub_frame.unwrap();
+
+            if sub_frame.trim().starts_with("at") {
+                frames.push(format!("{main_frame}\n{sub_frame}"));
+            } else if main_frame.trim().starts_with("at") {
+                frames
+                    .last_mut()
+                    .unwrap()
+                    .push_str(&format!("\n{main_frame}"));
+                frames.push(sub_frame.to_string());
+            } else {
+                frames.push(main_frame.to_string());
+                if !sub_frame.trim().is_empty() {
+                    frames.push(sub_frame.to_string());
                 }
             }
-
-            // use the frame list generated earlier and sort through them and create a short backtrace from it
-            let re = Regex::new(r"[0-9]+: ").unwrap();
-            let mut capture = false;
-            let frames = frames
-                .into_iter()
-                // filter out all non-short backtraces
-                .filter(|frame| {
-                    if frame.contains("__rust_end_short_backtrace") {
-                        capture = true;
-                        // skip this current frame
-                        return false;
-                    }
-
-                    if frame.contains("__rust_begin_short_backtrace") {
-                        // skip this frame and all following frames
-                        capture = false;
-                    }
-
-                    capture
-                })
-                .enumerate()
-                .map(|(i, frame)| re.replace(&frame, format!("{i}: ")).into_owned())
-                .collect::<Vec<_>>();
-
-            eprintln!("{}\n\nstack backtrace:\n{}", panic_msg, frames.join("\n"));
+        } else {
+            let main_frame = main_frame.unwrap();
+            if !main_frame.trim().is_empty() {
+                // end of array
+                frames.push(main_frame.to_string());
+            }
         }
+    }
 
-        display_popup(
-            "RustPlay panicked :(",
-            &v.to_string(),
-            MessageBoxIcon::Error,
+    // use the frame list generated earlier and sort through them and create a short backtrace from it
+    let re = Regex::new(r"[0-9]+: ").unwrap();
+    let mut capture = false;
+    let frames = frames
+        .into_iter()
+        // filter out all non-short backtraces
+        .filter(|frame| {
+            if frame.contains("__rust_end_short_backtrace") {
+                capture = true;
+                // skip this current frame
+                return false;
+            }
+
+            if frame.contains("__rust_begin_short_backtrace") {
+                // skip this frame and all following frames
+                capture = false;
+            }
+
+            capture
+        })
+        .enumerate()
+        .map(|(i, frame)| re.replace(&frame, format!("{i}: ")).into_owned())
+        .collect::<Vec<_>>();
+
+    // without symbols the markers aren't there to cut at
+    if frames.is_empty() {
+        return backtrace.trim_end().to_string();
+    }
+
+    frames.join("\n")
+}
+
+// a file of its own for the crash dialog, named by when it happened
+fn write_crash_log(report: &str) -> io::Result<PathBuf> {
+    let dir = crash_logs_dir();
+    fs::create_dir_all(&dir)?;
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let path = dir.join(format!("crash-{time}.txt"));
+    fs::write(&path, report)?;
+
+    Ok(path)
+}
+
+// kept for the diagnostic bundle. Once it grows too big it starts over, the previous one is kept next
+// to it. Nothing can be done about it failing while panicking already
+fn log_panic(message: &str) {
+    let path = panic_log_file();
+
+    let full = fs::metadata(&path).is_ok_and(|metadata| metadata.len() >= MAX_PANIC_LOG_LEN);
+    if full {
+        let _ = fs::rename(&path, path.with_extension("log.old"));
+    }
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let file = OpenOptions::new().create(true).append(true).open(path);
+
+    if let Ok(mut file) = file {
+        let _ = writeln!(
+            file,
+            "[{time}] Rust Play {}: {message}\n",
+            env!("CARGO_PKG_VERSION")
         );
-    }));
+    }
 }
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use egui::{Align2, Area, Id};
+use rfd::{MessageDialog, MessageLevel};
+
+use crate::config::{close_store, save_workspace, Config};
+use crate::ipc;
+use crate::widgets::dock::TabEvents;
+
+// how long the runs get to die before the window closes anyway
+const STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Closing the window, in order: nothing new is scheduled, the runs are stopped unless they're left running
+/// by the setting, everything is saved, and only then does the window close. Stopping may take a few frames
+#[derive(Default)]
+pub struct Shutdown {
+    // when the runs were told to stop
+    started: Option<Instant>,
+    done: bool,
+}
+
+impl Shutdown {
+    /// Whether everything was saved, and the window may close
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Shown in place of the app, as it doesn't schedule anything new while it's shutting down.
+    /// Returns true once the window may close
+    pub fn show(&mut self, ctx: &egui::Context, config: &mut Config, detached: bool) -> bool {
+        if self.done {
+            return true;
+        }
+
+        let started = *self.started.get_or_insert_with(|| {
+            if config.general.stop_runs_on_exit {
+                Self::stop_runs(ctx, config);
+            }
+
+            Instant::now()
+        });
+
+        // the runs block once nobody reads their output
+        for (stdout, stderr) in config.terminal.content.values_mut().flatten() {
+            stdout.pop_iter().for_each(drop);
+            stderr.pop_iter().for_each(drop);
+        }
+
+        let running = ctx
+            .memory()
+            .data
+            .get_temp::<u64>(Id::new("continuous_mode"))
+            .unwrap_or_default();
+
+        if config.general.stop_runs_on_exit && running > 0 && started.elapsed() < STOP_TIMEOUT {
+            Area::new("shutdown")
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| ui.label("Stopping runs..."));
+
+            ctx.request_repaint();
+            return false;
+        }
+
+        Self::save(config, detached);
+        self.done = true;
+
+        true
+    }
+
+    fn stop_runs(ctx: &egui::Context, config: &mut Config) {
+        let ids = config
+            .terminal
+            .abortable
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            TabEvents::stop(ctx, &mut config.terminal, id);
+        }
+    }
+
+    fn save(config: &mut Config, detached: bool) {
+        // Don't lose anything typed since the last autosave
+        TabEvents::save_drafts(&mut config.dock);
+
+        // the settings are the other window's to save
+        if detached {
+            ipc::dock_back(&TabEvents::draft_keys(&config.dock.tree));
+        } else {
+            let mut errors = vec![];
+
+            if let Some(workspace) = &config.dock.workspace {
+                if let Err(e) = save_workspace(workspace, &config.dock.tree) {
+                    errors.push(format!("Failed to save the workspace: {e}"));
+                }
+            }
+
+            // Write config to settings.toml
+            if let Err(e) = config.save() {
+                errors.push(format!("Failed to write the settings: {e}"));
+            }
+
+            // the window is about to close, a toast wouldn't be seen
+            if !errors.is_empty() {
+                MessageDialog::new()
+                    .set_level(MessageLevel::Error)
+                    .set_title("Rust Play")
+                    .set_description(&errors.join("\n"))
+                    .show();
+            }
+        }
+
+        close_store();
+    }
+}
@@ -0,0 +1,23 @@
+use std::cell::RefCell;
+
+use arboard::{Clipboard, Error};
+
+thread_local! {
+    // kept open, on linux what's copied is only there while the clipboard which copied it is
+    static CLIPBOARD: RefCell<Option<Clipboard>> = const { RefCell::new(None) };
+}
+
+/// Put html on the clipboard, with the plain text for where html can't be pasted. egui's clipboard only
+/// takes plain text
+pub fn copy_html(html: &str, text: &str) -> Result<(), Error> {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+
+        let clipboard = match &mut *clipboard {
+            Some(clipboard) => clipboard,
+            None => clipboard.insert(Clipboard::new()?),
+        };
+
+        clipboard.set_html(html, Some(text))
+    })
+}
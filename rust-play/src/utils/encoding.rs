@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Encodings besides UTF-8 which files are read in and converted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    // UTF-8 already, the byte order mark in front is kept when saving
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    // anything else which isn't valid UTF-8. Every byte is a character, so it never fails
+    Latin1,
+}
+
+impl Encoding {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Utf8Bom => "UTF-8 with BOM",
+            Self::Utf16Le => "UTF-16 LE",
+            Self::Utf16Be => "UTF-16 BE",
+            Self::Latin1 => "Latin-1",
+        }
+    }
+
+    /// Whether the text was converted to read it, rather than just having its byte order mark taken off
+    pub fn is_converted(self) -> bool {
+        self != Self::Utf8Bom
+    }
+
+    /// The text of a file, and the encoding it was in if that wasn't UTF-8 without a byte order mark.
+    /// UTF-16 is recognized by its byte order mark
+    pub fn decode(bytes: &[u8]) -> (String, Option<Self>) {
+        if let Some(bytes) = bytes.strip_prefix(UTF8_BOM) {
+            return (
+                String::from_utf8_lossy(bytes).into_owned(),
+                Some(Self::Utf8Bom),
+            );
+        }
+
+        if let Some(bytes) = bytes.strip_prefix(UTF16_LE_BOM) {
+            let units = bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]));
+            return (decode_utf16(units), Some(Self::Utf16Le));
+        }
+
+        if let Some(bytes) = bytes.strip_prefix(UTF16_BE_BOM) {
+            let units = bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]));
+            return (decode_utf16(units), Some(Self::Utf16Be));
+        }
+
+        match std::str::from_utf8(bytes) {
+            Ok(text) => (text.to_string(), None),
+            Err(_) => (
+                bytes.iter().map(|&byte| byte as char).collect(),
+                Some(Self::Latin1),
+            ),
+        }
+    }
+
+    /// The text in this encoding, UTF-16 with a byte order mark.
+    /// None if it has characters Latin-1 can't represent
+    pub fn encode(self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Utf8Bom => Some(UTF8_BOM.iter().chain(text.as_bytes()).copied().collect()),
+
+            Self::Utf16Le => Some(
+                UTF16_LE_BOM
+                    .iter()
+                    .copied()
+                    .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                    .collect(),
+            ),
+
+            Self::Utf16Be => Some(
+                UTF16_BE_BOM
+                    .iter()
+                    .copied()
+                    .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                    .collect(),
+            ),
+
+            Self::Latin1 => text.chars().map(|c| u8::try_from(c).ok()).collect(),
+        }
+    }
+}
+
+fn decode_utf16(units: impl Iterator<Item = u16>) -> String {
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
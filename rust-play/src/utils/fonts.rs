@@ -0,0 +1,89 @@
+use egui::{FontData, FontDefinitions, FontFamily};
+use fontdb::{Database, Style, Weight};
+use once_cell::sync::Lazy;
+
+// the family of egui's own monospace font
+const BUILTIN_MONOSPACE: &str = "Hack";
+
+// scanning the system fonts is slow, so only do it once
+static FONT_DB: Lazy<Database> = Lazy::new(|| {
+    let mut db = Database::new();
+    db.load_system_fonts();
+    db
+});
+
+// the installed fonts and the ones built into egui, for text drawn outside of egui
+static EXPORT_FONT_DB: Lazy<Database> = Lazy::new(|| {
+    let mut db = FONT_DB.clone();
+
+    for font in FontDefinitions::default().font_data.into_values() {
+        db.load_font_data(font.font.into_owned());
+    }
+
+    db.set_monospace_family(BUILTIN_MONOSPACE);
+
+    db
+});
+
+static MONOSPACE_FAMILIES: Lazy<Vec<String>> = Lazy::new(|| {
+    let mut families = FONT_DB
+        .faces()
+        .iter()
+        .filter(|face| face.monospaced)
+        .map(|face| face.family.clone())
+        .collect::<Vec<_>>();
+
+    families.sort();
+    families.dedup();
+
+    families
+});
+
+/// Family names of the installed monospace fonts
+pub fn monospace_families() -> &'static [String] {
+    &MONOSPACE_FAMILIES
+}
+
+/// The installed fonts with the builtin ones, to render text like the app does, e.g. into an image.
+/// Its generic `monospace` family is the builtin one
+pub fn export_font_db() -> &'static Database {
+    &EXPORT_FONT_DB
+}
+
+fn load_family(family: &str) -> Option<FontData> {
+    let faces = FONT_DB.faces();
+
+    // prefer the regular face of the family
+    let face = faces
+        .iter()
+        .find(|face| {
+            face.family == family && face.style == Style::Normal && face.weight == Weight::NORMAL
+        })
+        .or_else(|| faces.iter().find(|face| face.family == family))?;
+
+    FONT_DB.with_face_data(face.id, |data, index| {
+        let mut font = FontData::from_owned(data.to_vec());
+        font.index = index;
+        font
+    })
+}
+
+/// Use an installed font for all monospace text, or the builtin one if None
+pub fn apply_monospace_family(ctx: &egui::Context, family: Option<&str>) {
+    let mut fonts = FontDefinitions::default();
+
+    if let Some(font) = family.and_then(load_family) {
+        let name = family.unwrap().to_string();
+
+        fonts.font_data.insert(name.clone(), font);
+
+        // the builtin fonts stay behind it as a fallback for missing glyphs
+        fonts
+            .families
+            .entry(FontFamily::Monospace)
+            .or_default()
+            .insert(0, name);
+    }
+
+    ctx.set_fonts(fonts);
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use reqwest::Url;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// The reply to a request, the error message the server sent if it failed
+pub type Reply = Result<Value, String>;
+
+type Pending = Arc<Mutex<HashMap<u64, Sender<Reply>>>>;
+
+#[derive(Debug, Error)]
+pub enum LspError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid message: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Server(String),
+    #[error("The language server exited")]
+    Exited,
+}
+
+/// A language server spoken to over its stdin and stdout, e.g. rust-analyzer
+pub struct LspClient {
+    child: Child,
+    stdin: Arc<Mutex<ChildStdin>>,
+    next_id: u64,
+    pending: Pending,
+}
+
+impl LspClient {
+    /// Start the server on the project in `root` and wait until it's initialized, which blocks for a moment.
+    /// `options` are its initializationOptions
+    pub fn start(mut command: Command, root: &Path, options: Value) -> Result<Self, LspError> {
+        let mut child = command
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = Arc::new(Mutex::new(child.stdin.take().ok_or(LspError::Exited)?));
+        let stdout = child.stdout.take().ok_or(LspError::Exited)?;
+
+        let pending = Pending::default();
+
+        {
+            let pending = pending.clone();
+            let stdin = stdin.clone();
+
+            thread::spawn(move || read_messages(BufReader::new(stdout), &pending, &stdin));
+        }
+
+        let mut client = Self {
+            child,
+            stdin,
+            next_id: 0,
+            pending,
+        };
+
+        let initialize = client.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": file_uri(root),
+                "initializationOptions": options,
+                "capabilities": {
+                    "textDocument": {
+                        "completion": { "completionItem": { "snippetSupport": false } },
+                        "hover": { "contentFormat": ["plaintext"] },
+                        "definition": { "linkSupport": true },
+                    },
+                },
+            }),
+        )?;
+
+        initialize
+            .recv()
+            .map_err(|_| LspError::Exited)?
+            .map_err(LspError::Server)?;
+
+        client.notify("initialized", json!({}))?;
+
+        Ok(client)
+    }
+
+    /// Send a request, its reply arrives on the receiver. It's dropped without one if the server exits
+    pub fn request(&mut self, method: &str, params: Value) -> Result<Receiver<Reply>, LspError> {
+        self.next_id += 1;
+
+        let (tx, rx) = channel();
+        self.pending.lock().unwrap().insert(self.next_id, tx);
+
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id,
+            "method": method,
+            "params": params,
+        }))?;
+
+        Ok(rx)
+    }
+
+    pub fn notify(&mut self, method: &str, params: Value) -> Result<(), LspError> {
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }))
+    }
+
+    /// Whether the server is still running
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn send(&self, message: &Value) -> Result<(), LspError> {
+        write_message(&mut *self.stdin.lock().unwrap(), message)
+    }
+}
+
+impl Drop for LspClient {
+    // there's nothing for the server to save, it doesn't need to be asked to shut down
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The LSP position of a char index: its line, and the UTF-16 code units before it on the line
+pub fn position(text: &str, index: usize) -> Value {
+    let mut line = 0;
+    let mut character = 0;
+
+    for c in text.chars().take(index) {
+        if c == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += c.len_utf16();
+        }
+    }
+
+    json!({ "line": line, "character": character })
+}
+
+/// The char index of an LSP position, its line's end if the position is past it
+pub fn char_index(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or_default() as usize;
+    let character = position["character"].as_u64().unwrap_or_default() as usize;
+
+    let mut index = 0;
+
+    for (i, text_line) in text.split('\n').enumerate() {
+        if i == line {
+            let mut units = 0;
+
+            for c in text_line.chars() {
+                if units >= character {
+                    break;
+                }
+
+                units += c.len_utf16();
+                index += 1;
+            }
+
+            return index;
+        }
+
+        index += text_line.chars().count() + 1;
+    }
+
+    text.chars().count()
+}
+
+pub fn file_uri(path: &Path) -> String {
+    Url::from_file_path(path)
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+pub fn uri_path(uri: &str) -> Option<PathBuf> {
+    Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+// route the replies to whoever asked. Notifications from the server aren't needed, its requests are
+// answered with nothing so it doesn't wait on them, e.g. to register progress reports
+fn read_messages(mut reader: impl BufRead, pending: &Pending, stdin: &Mutex<ChildStdin>) {
+    while let Some(message) = read_message(&mut reader) {
+        if message.get("method").is_some() {
+            if let Some(id) = message.get("id") {
+                let reply = json!({ "jsonrpc": "2.0", "id": id, "result": null });
+                let _ = write_message(&mut *stdin.lock().unwrap(), &reply);
+            }
+
+            continue;
+        }
+
+        let Some(id) = message.get("id").and_then(Value::as_u64) else {
+            continue;
+        };
+
+        let reply = match message.get("error") {
+            Some(error) => Err(error["message"]
+                .as_str()
+                .unwrap_or("The request failed")
+                .to_string()),
+            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        };
+
+        if let Some(sender) = pending.lock().unwrap().remove(&id) {
+            let _ = sender.send(reply);
+        }
+    }
+
+    // the server exited, nothing is answered anymore
+    pending.lock().unwrap().clear();
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut len = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            len = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; len?];
+    reader.read_exact(&mut body).ok()?;
+
+    // one the client doesn't understand is skipped
+    Some(serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<(), LspError> {
+    let body = serde_json::to_string(message)?;
+
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()?;
+
+    Ok(())
+}
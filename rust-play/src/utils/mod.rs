@@ -1,2 +1,12 @@
-pub mod ansi_parser;
+pub mod clipboard;
 pub mod data;
+pub mod encoding;
+pub mod fonts;
+pub mod lsp;
+pub mod output_assertion;
+pub mod playground;
+pub mod serve;
+pub mod std_source;
+pub mod threads;
+pub mod toolchain;
+pub mod zip;
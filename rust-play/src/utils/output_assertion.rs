@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use notify_rust::Notification;
+use regex::Regex;
+
+/// Watches the output lines of a run and raises an OS notification
+/// the first time one of them matches the pattern
+#[derive(Debug)]
+pub struct OutputAssertion {
+    pattern: Regex,
+    tab_name: String,
+    notified: AtomicBool,
+}
+
+impl OutputAssertion {
+    /// Returns None if the pattern is empty or not a valid regex
+    pub fn new(pattern: &str, tab_name: &str) -> Option<Self> {
+        if pattern.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            pattern: Regex::new(pattern).ok()?,
+            tab_name: tab_name.to_string(),
+            notified: AtomicBool::new(false),
+        })
+    }
+
+    pub fn check(&self, line: &str) {
+        // only notify once per run
+        if self.notified.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let stripped = strip_ansi_escapes::strip(line).unwrap_or_default();
+        let stripped = String::from_utf8_lossy(&stripped);
+
+        if self.pattern.is_match(&stripped) && !self.notified.swap(true, Ordering::Relaxed) {
+            let _ = Notification::new()
+                .summary(&format!("{}: output matched", self.tab_name))
+                .body(stripped.trim())
+                .show();
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use cargo_player::{Channel, Edition};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::json;
+use thiserror::Error;
+
+const EXECUTE_URL: &str = "https://play.rust-lang.org/execute";
+const USER_AGENT: &str = "RustPlay (https://github.com/MolotovCherry/RustPlay)";
+// the playground builds and runs within about a minute, or gives up on the code
+const TIMEOUT: Duration = Duration::from_secs(120);
+// how often a run waiting for the reply checks whether it was stopped
+const ABORT_POLL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Error)]
+pub enum PlaygroundError {
+    #[error("Failed to reach the playground: {0}")]
+    Unreachable(#[from] reqwest::Error),
+    #[error("The playground refused to run it: {0}")]
+    Refused(String),
+    #[error("Unexpected reply from the playground")]
+    InvalidReply,
+    #[error("Stopped before the playground replied")]
+    Aborted,
+}
+
+/// Code run by the official playground instead of the local toolchain. It only has the most
+/// downloaded crates, and no network or files
+#[derive(Debug, Clone)]
+pub struct PlaygroundRun {
+    pub code: String,
+    pub channel: Channel,
+    pub edition: Edition,
+    pub release: bool,
+    // run the tests instead of main
+    pub tests: bool,
+}
+
+/// What the code printed and whether it built and exited successfully
+#[derive(Debug, Deserialize)]
+pub struct Execution {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Reply {
+    Execution(Execution),
+    Error { error: String },
+}
+
+impl PlaygroundRun {
+    /// Like [`execute`](Self::execute), but stops waiting for the reply once `aborted` is set. The
+    /// playground can't be told to stop, the request is left to finish and its reply dropped
+    pub fn execute_abortable(&self, aborted: &AtomicBool) -> Result<Execution, PlaygroundError> {
+        let (tx, rx) = channel();
+        let run = self.clone();
+
+        thread::spawn(move || {
+            let _ = tx.send(run.execute());
+        });
+
+        loop {
+            match rx.recv_timeout(ABORT_POLL) {
+                Ok(reply) => return reply,
+
+                Err(RecvTimeoutError::Timeout) => {
+                    if aborted.load(Ordering::Relaxed) {
+                        return Err(PlaygroundError::Aborted);
+                    }
+                }
+
+                // the request panicked
+                Err(RecvTimeoutError::Disconnected) => return Err(PlaygroundError::InvalidReply),
+            }
+        }
+    }
+
+    /// Blocks until the playground built and ran the code, its output comes all at once
+    pub fn execute(&self) -> Result<Execution, PlaygroundError> {
+        let channel: &str = self.channel.into();
+        let edition: &str = self.edition.into();
+
+        let body = json!({
+            "channel": channel,
+            "mode": if self.release { "release" } else { "debug" },
+            "edition": edition,
+            "crateType": "bin",
+            "tests": self.tests,
+            "backtrace": false,
+            "code": self.code,
+        });
+
+        // errors come with a reply saying why, the status is left to it
+        let reply = Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(TIMEOUT)
+            .build()?
+            .post(EXECUTE_URL)
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()?
+            .text()?;
+
+        let reply =
+            serde_json::from_str::<Reply>(&reply).map_err(|_| PlaygroundError::InvalidReply)?;
+
+        match reply {
+            Reply::Execution(execution) => Ok(execution),
+            Reply::Error { error } => Err(PlaygroundError::Refused(error)),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+
+use egui::mutex::RwLock;
+use once_cell::sync::{Lazy, OnceCell};
+
+// the directory currently being served. A single server is shared by every web run
+static ROOT: Lazy<RwLock<PathBuf>> = Lazy::new(|| RwLock::new(PathBuf::new()));
+static PORT: OnceCell<u16> = OnceCell::new();
+
+/// Serve the files in a directory on localhost, replacing whatever was served before.
+/// Returns the url of its index.html
+pub fn serve(dir: PathBuf) -> io::Result<String> {
+    *ROOT.write() = dir;
+
+    let port = PORT.get_or_try_init(|| {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                thread::spawn(move || handle(stream));
+            }
+        });
+
+        Ok::<_, io::Error>(port)
+    })?;
+
+    Ok(format!("http://127.0.0.1:{port}/index.html"))
+}
+
+fn handle(mut stream: TcpStream) -> io::Result<()> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    // e.g. GET /index.html HTTP/1.1
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or_default();
+
+    let file = resolve(&ROOT.read(), path);
+    let content = file.as_ref().and_then(|file| fs::read(file).ok());
+
+    match (file, content) {
+        (Some(file), Some(content)) => {
+            respond(&mut stream, "200 OK", content_type(&file), &content)
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"Not found"),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    // every run replaces the files, so nothing may be cached
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: {content_type}\r\n\
+         Content-Length: {}\r\n\
+         Cache-Control: no-store\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    )?;
+
+    stream.write_all(body)
+}
+
+// never serve anything outside of the root
+fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path.trim_start_matches('/'));
+
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let file = if path.as_os_str().is_empty() {
+        root.join("index.html")
+    } else {
+        root.join(path)
+    };
+
+    file.is_file().then_some(file)
+}
+
+fn content_type(file: &Path) -> &'static str {
+    match file.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript",
+        // must be set for wasm to be streamed and compiled
+        Some("wasm") => "application/wasm",
+        Some("css") => "text/css",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
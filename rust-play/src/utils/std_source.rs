@@ -0,0 +1,212 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the crates of the library searched, in order, std re-exports most of the other two
+const CRATES: [&str; 3] = ["core", "alloc", "std"];
+// keywords an item is defined with, before its name
+const ITEM_KEYWORDS: [&str; 9] = [
+    "struct ",
+    "enum ",
+    "trait ",
+    "union ",
+    "type ",
+    "fn ",
+    "const ",
+    "static ",
+    "macro_rules! ",
+];
+// qualifiers which may come between the visibility and the keyword
+const QUALIFIERS: [&str; 6] = [
+    "default ",
+    "const ",
+    "async ",
+    "unsafe ",
+    "extern \"C\" ",
+    "extern \"rust-intrinsic\" ",
+];
+
+/// Where an item of the standard library is defined
+#[derive(Debug, Clone)]
+pub struct Definition {
+    // e.g. core
+    pub krate: String,
+    pub path: PathBuf,
+    // 0 based
+    pub line: usize,
+}
+
+/// The path of the item the cursor is on, e.g. `std::collections::HashMap` or `println`.
+/// The cursor is a char index into the code
+pub fn item_at(code: &str, cursor: usize) -> Option<String> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let is_path = |c: char| is_ident(c) || c == ':';
+
+    let chars = code.chars().collect::<Vec<_>>();
+    let cursor = cursor.min(chars.len());
+
+    let start = chars[..cursor]
+        .iter()
+        .rposition(|&c| !is_path(c))
+        .map_or(0, |i| i + 1);
+
+    // the path up to the segment the cursor is on, e.g. the type and not its function
+    let end = chars[cursor..]
+        .iter()
+        .position(|&c| !is_ident(c))
+        .map_or(chars.len(), |i| cursor + i);
+
+    let item = chars[start..end].iter().collect::<String>();
+    let item = item.trim_matches(':');
+
+    let starts_like_ident = item
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_alphabetic() || c == '_');
+
+    starts_like_ident.then(|| item.to_string())
+}
+
+/// Find the definition of an item in the library directory of the rust-src component.
+/// The modules in the path narrow it down when the name is defined in several places
+pub fn find_definition(library: &Path, item: &str) -> Option<Definition> {
+    let segments = item
+        .split("::")
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let (name, modules) = segments.split_last()?;
+
+    let modules = modules
+        .iter()
+        .filter(|segment| !CRATES.contains(segment))
+        .collect::<Vec<_>>();
+
+    let mut best: Option<(usize, Definition)> = None;
+
+    for krate in CRATES {
+        let src = library.join(krate).join("src");
+
+        let mut files = vec![];
+        collect_files(&src, &mut files);
+        files.sort();
+
+        for path in files {
+            let Ok(code) = fs::read_to_string(&path) else {
+                continue;
+            };
+
+            let Some((line, public)) = find_item(&code, name) else {
+                continue;
+            };
+
+            let relative = path.strip_prefix(&src).unwrap_or(&path);
+            // a type in the path counts when the file defines it, e.g. HashMap::new
+            let matched_modules = modules
+                .iter()
+                .filter(|module| {
+                    let in_path = relative.iter().any(|component| {
+                        let component = component.to_string_lossy().to_lowercase();
+                        component.trim_end_matches(".rs") == module.to_lowercase()
+                    });
+
+                    in_path || find_item(&code, module).is_some()
+                })
+                .count();
+
+            // modules matter most, an exported item is preferred over a private one of the same name
+            let score = matched_modules * 2 + usize::from(public);
+
+            if best.as_ref().map_or(true, |(best, _)| score > *best) {
+                let definition = Definition {
+                    krate: krate.to_string(),
+                    path,
+                    line,
+                };
+
+                best = Some((score, definition));
+            }
+        }
+    }
+
+    best.map(|(_, definition)| definition)
+}
+
+// rust files, leaving out the tests and benchmarks
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let file_name = path.file_name().unwrap_or_default();
+        if file_name == "tests" || file_name == "tests.rs" || file_name == "benches" {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "rs")
+        {
+            files.push(path);
+        }
+    }
+}
+
+// the line an item is defined on, and whether it's public
+fn find_item(code: &str, name: &str) -> Option<(usize, bool)> {
+    let mut found = None;
+
+    for (i, line) in code.lines().enumerate() {
+        let line = line.trim_start();
+
+        let (line, public) = match line.strip_prefix("pub") {
+            // pub(crate) and the like are not public
+            Some(rest) if rest.starts_with('(') => match rest.split_once(") ") {
+                Some((_, rest)) => (rest, false),
+                None => continue,
+            },
+            Some(rest) if rest.starts_with(' ') => (&rest[1..], true),
+            _ => (line, false),
+        };
+
+        // `const` is a qualifier of functions, and a keyword of its own
+        let mut line = line;
+        let defines = loop {
+            if defines_item(line, name) {
+                break true;
+            }
+
+            match QUALIFIERS.iter().find_map(|q| line.strip_prefix(q)) {
+                Some(rest) => line = rest,
+                None => break false,
+            }
+        };
+
+        if !defines {
+            continue;
+        }
+
+        if public {
+            return Some((i, true));
+        }
+
+        found.get_or_insert((i, false));
+    }
+
+    found
+}
+
+fn defines_item(line: &str, name: &str) -> bool {
+    ITEM_KEYWORDS.iter().any(|keyword| {
+        let Some(rest) = line.strip_prefix(keyword) else {
+            return false;
+        };
+
+        let Some(rest) = rest.strip_prefix(name) else {
+            return false;
+        };
+
+        !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+    })
+}
@@ -0,0 +1,55 @@
+/// How many threads the process has, the ui's and those of the runs alike. None where the OS isn't asked
+pub fn thread_count() -> Option<usize> {
+    count()
+}
+
+#[cfg(target_os = "linux")]
+fn count() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(target_os = "windows")]
+fn count() -> Option<usize> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+    use windows::Win32::System::Threading::GetCurrentProcessId;
+
+    unsafe {
+        // the snapshot lists the threads of every process
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0).ok()?;
+        let process = GetCurrentProcessId();
+
+        let mut entry = THREADENTRY32 {
+            dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+            ..Default::default()
+        };
+
+        let mut count = 0;
+        let mut found = Thread32First(snapshot, &mut entry).as_bool();
+        while found {
+            if entry.th32OwnerProcessID == process {
+                count += 1;
+            }
+
+            found = Thread32Next(snapshot, &mut entry).as_bool();
+        }
+
+        CloseHandle(snapshot);
+
+        Some(count)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn count() -> Option<usize> {
+    None
+}
@@ -0,0 +1,253 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use cargo_player::Channel;
+
+#[cfg(target_os = "windows")]
+use {std::os::windows::process::CommandExt, windows::Win32::System::Threading::CREATE_NO_WINDOW};
+
+// lists the version of every component of the current stable release
+const STABLE_MANIFEST_URL: &str = "https://static.rust-lang.org/dist/channel-rust-stable.toml";
+
+pub const CHANNELS: [Channel; 3] = [Channel::Stable, Channel::Beta, Channel::Nightly];
+// optional components some of the run modes need
+pub const COMPONENTS: [&str; 4] = ["clippy", "rustfmt", "rust-src", "miri"];
+
+#[derive(Debug, Clone)]
+pub struct Toolchain {
+    // e.g. nightly-x86_64-pc-windows-msvc
+    pub name: String,
+    // e.g. clippy-x86_64-pc-windows-msvc or rust-src
+    pub components: Vec<String>,
+}
+
+impl Toolchain {
+    pub fn is_channel(&self, channel: Channel) -> bool {
+        self.name.split('-').next() == Some(channel.into())
+    }
+
+    pub fn has_component(&self, component: &str) -> bool {
+        self.components.iter().any(|installed| {
+            installed
+                .strip_prefix(component)
+                .map_or(false, |target| target.is_empty() || target.starts_with('-'))
+        })
+    }
+}
+
+fn run(tool: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(tool);
+    command.args(args);
+
+    // hide the console window from command
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW.0);
+
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Output of `<tool> --version`, e.g. `rustc 1.66.0 (69f9c33d7 2022-12-12)`.
+/// Returns None if the tool isn't installed
+pub fn version(tool: &str) -> Option<String> {
+    run(tool, &["--version"])
+}
+
+/// Version number of the installed stable rustc, e.g. `1.66.0`
+pub fn installed_stable() -> Option<String> {
+    let version = run("rustc", &["+stable", "--version"])?;
+
+    version.split_whitespace().nth(1).map(ToString::to_string)
+}
+
+/// Version number of the latest stable release, e.g. `1.66.0`
+pub fn latest_stable() -> Option<String> {
+    let manifest = reqwest::blocking::get(STABLE_MANIFEST_URL)
+        .ok()?
+        .text()
+        .ok()?;
+
+    let manifest = toml::from_str::<toml::Value>(&manifest).ok()?;
+
+    // e.g. "1.66.0 (69f9c33d7 2022-12-12)"
+    let version = manifest
+        .get("pkg")?
+        .get("rustc")?
+        .get("version")?
+        .as_str()?;
+
+    version.split_whitespace().next().map(ToString::to_string)
+}
+
+/// Every toolchain rustup has installed along with its components.
+/// Returns an empty list if rustup isn't installed
+pub fn installed_toolchains() -> Vec<Toolchain> {
+    let Some(list) = run("rustup", &["toolchain", "list"]) else {
+        return vec![];
+    };
+
+    // e.g. "stable-x86_64-pc-windows-msvc (default)"
+    list.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| *name != "no")
+        .map(|name| {
+            let components = run(
+                "rustup",
+                &["component", "list", "--installed", "--toolchain", name],
+            )
+            .map(|list| list.lines().map(ToString::to_string).collect())
+            .unwrap_or_default();
+
+            Toolchain {
+                name: name.to_string(),
+                components,
+            }
+        })
+        .collect()
+}
+
+/// What `rustc --explain` says about an error code, e.g. E0308. Returns None if it has no explanation of it
+pub fn explain(code: &str) -> Option<String> {
+    run("rustc", &["--explain", code])
+}
+
+/// `rustup` command installing a channel, or a component of it if given
+pub fn install_command(channel: Channel, component: Option<&str>) -> Command {
+    let channel: &str = channel.into();
+
+    let mut command = Command::new("rustup");
+
+    match component {
+        Some(component) => command.args(["component", "add", component, "--toolchain", channel]),
+        None => command.args(["toolchain", "install", channel]),
+    };
+
+    command
+}
+
+/// The library directory of the rust-src component of the default toolchain, the source of std, core and alloc.
+/// Returns None if the component isn't installed
+pub fn rust_src_dir() -> Option<PathBuf> {
+    let sysroot = run("rustc", &["--print", "sysroot"])?;
+    let dir = Path::new(&sysroot).join("lib/rustlib/src/rust/library");
+
+    dir.is_dir().then_some(dir)
+}
+
+/// Install the rust-src component for the default toolchain. Returns whether it succeeded
+pub fn install_rust_src() -> bool {
+    run("rustup", &["component", "add", "rust-src"]).is_some()
+}
+
+/// The command starting rust-analyzer, which rustup installs as a component
+pub fn rust_analyzer() -> Command {
+    #[allow(unused_mut)]
+    let mut command = Command::new("rust-analyzer");
+
+    // hide the console window from command
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW.0);
+
+    command
+}
+
+/// Format code with rustfmt. Returns its error output if the code couldn't be formatted
+pub fn rustfmt(code: &str) -> Result<String, String> {
+    let mut command = Command::new("rustfmt");
+    command
+        .args(["--edition", "2021"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // hide the console window from command
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW.0);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run rustfmt: {e}"))?;
+
+    // rustfmt only starts writing once all of the input is read, so this can't deadlock
+    let written = child.stdin.take().unwrap().write_all(code.as_bytes());
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run rustfmt: {e}"))?;
+
+    if let Err(e) = written {
+        return Err(format!("Failed to run rustfmt: {e}"));
+    }
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `cargo install` command for a tool, e.g. cargo-fuzz
+pub fn cargo_install_command(krate: &str) -> Command {
+    let mut command = Command::new("cargo");
+    command.args(["install", krate]);
+
+    command
+}
+
+/// Commands installing whatever fuzzing needs and is missing, nightly and cargo-fuzz
+pub fn fuzz_install_commands() -> Vec<Command> {
+    let mut commands = vec![];
+
+    let has_nightly = installed_toolchains()
+        .iter()
+        .any(|toolchain| toolchain.is_channel(Channel::Nightly));
+
+    if !has_nightly {
+        commands.push(install_command(Channel::Nightly, None));
+    }
+
+    commands.extend(install_cargo_tool_if_missing("fuzz"));
+
+    commands
+}
+
+/// Commands installing a component and its toolchain, as far as they're missing. e.g. miri on nightly
+pub fn component_install_commands(channel: Channel, component: &str) -> Vec<Command> {
+    let toolchain = installed_toolchains()
+        .into_iter()
+        .find(|toolchain| toolchain.is_channel(channel));
+
+    match toolchain {
+        Some(toolchain) if toolchain.has_component(component) => vec![],
+        Some(_) => vec![install_command(channel, Some(component))],
+        None => vec![
+            install_command(channel, None),
+            install_command(channel, Some(component)),
+        ],
+    }
+}
+
+/// `cargo install` command for the tool providing a cargo subcommand, unless it's installed already.
+/// e.g. cargo-bloat for bloat
+pub fn install_cargo_tool_if_missing(subcommand: &str) -> Option<Command> {
+    let installed = run("cargo", &[subcommand, "--version"]).is_some();
+
+    (!installed).then(|| cargo_install_command(&format!("cargo-{subcommand}")))
+}
+
+/// Compare two `major.minor.patch` version numbers
+pub fn is_newer(version: &str, than: &str) -> bool {
+    let parse = |version: &str| {
+        version
+            .split('.')
+            .map(|part| part.parse::<u32>().unwrap_or_default())
+            .collect::<Vec<_>>()
+    };
+
+    parse(version) > parse(than)
+}
@@ -0,0 +1,168 @@
+// the files are stored as they are, what goes into a zip here is small enough not to need compressing
+const METHOD_STORED: u16 = 0;
+const VERSION: u16 = 20;
+// the names are utf-8
+const FLAG_UTF8: u16 = 1 << 11;
+// 1980-01-01, the earliest date a zip can hold
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Writes a zip archive of uncompressed files in memory
+#[derive(Default)]
+pub struct ZipWriter {
+    bytes: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl ZipWriter {
+    pub fn file(&mut self, name: &str, contents: &[u8]) {
+        let entry = Entry {
+            name: name.to_string(),
+            crc: crc32(contents),
+            size: contents.len() as u32,
+            offset: self.bytes.len() as u32,
+        };
+
+        self.u32(0x0403_4b50);
+        self.u16(VERSION);
+        self.u16(FLAG_UTF8);
+        self.u16(METHOD_STORED);
+        self.u16(0);
+        self.u16(DOS_DATE);
+        self.u32(entry.crc);
+        self.u32(entry.size);
+        self.u32(entry.size);
+        self.u16(entry.name.len() as u16);
+        self.u16(0);
+        self.bytes.extend_from_slice(entry.name.as_bytes());
+        self.bytes.extend_from_slice(contents);
+
+        self.entries.push(entry);
+    }
+
+    /// The archive with its central directory appended
+    pub fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.bytes.len() as u32;
+
+        let entries = std::mem::take(&mut self.entries);
+
+        for entry in &entries {
+            self.u32(0x0201_4b50);
+            self.u16(VERSION);
+            self.u16(VERSION);
+            self.u16(FLAG_UTF8);
+            self.u16(METHOD_STORED);
+            self.u16(0);
+            self.u16(DOS_DATE);
+            self.u32(entry.crc);
+            self.u32(entry.size);
+            self.u32(entry.size);
+            self.u16(entry.name.len() as u16);
+            // extra field, comment, disk, internal and external attributes
+            self.u16(0);
+            self.u16(0);
+            self.u16(0);
+            self.u16(0);
+            self.u32(0);
+            self.u32(entry.offset);
+            self.bytes.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let directory_size = self.bytes.len() as u32 - directory_offset;
+        let count = entries.len() as u16;
+
+        self.u32(0x0605_4b50);
+        self.u16(0);
+        self.u16(0);
+        self.u16(count);
+        self.u16(count);
+        self.u32(directory_size);
+        self.u32(directory_offset);
+        self.u16(0);
+
+        self.bytes
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"hello"), 0x3610_A686);
+    }
+
+    #[test]
+    fn archive() {
+        let mut zip = ZipWriter::default();
+        zip.file("a.txt", b"hello");
+        zip.file("b.txt", b"");
+        let bytes = zip.finish();
+
+        // the local header and the contents of the first file, the second one right after
+        assert_eq!(u32_at(&bytes, 0), 0x0403_4b50);
+        assert_eq!(u32_at(&bytes, 14), crc32(b"hello"));
+        assert_eq!(u32_at(&bytes, 18), 5);
+        assert_eq!(u16_at(&bytes, 26), 5);
+        assert_eq!(&bytes[30..35], b"a.txt");
+        assert_eq!(&bytes[35..40], b"hello");
+        assert_eq!(u32_at(&bytes, 40), 0x0403_4b50);
+
+        // the end of the central directory points back at it
+        let end = bytes.len() - 22;
+        assert_eq!(u32_at(&bytes, end), 0x0605_4b50);
+        assert_eq!(u16_at(&bytes, end + 10), 2);
+
+        let directory = u32_at(&bytes, end + 16) as usize;
+        assert_eq!(u32_at(&bytes, end + 12) as usize, end - directory);
+        assert_eq!(u32_at(&bytes, directory), 0x0201_4b50);
+        assert_eq!(&bytes[directory + 46..directory + 51], b"a.txt");
+
+        // the second entry is found at the offset of its local header
+        let second = directory + 46 + 5;
+        assert_eq!(u32_at(&bytes, second), 0x0201_4b50);
+        assert_eq!(u32_at(&bytes, second + 42), 40);
+    }
+}
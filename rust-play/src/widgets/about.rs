@@ -0,0 +1,94 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use egui::{vec2, Align2, CollapsingHeader, ScrollArea, Window};
+use once_cell::sync::OnceCell;
+
+use crate::utils::toolchain;
+
+use super::menubar::REPOSITORY_URL;
+
+const GIT_HASH: &str = env!("RUST_PLAY_GIT_HASH");
+// generated by build.rs
+const THIRD_PARTY_LICENSES: &str =
+    include_str!(concat!(env!("OUT_DIR"), "/third_party_licenses.txt"));
+
+// detected once in the background, running rustc and cargo can take a moment
+static TOOLCHAIN: OnceCell<(Option<String>, Option<String>)> = OnceCell::new();
+static DETECTING: AtomicBool = AtomicBool::new(false);
+
+pub struct About;
+
+impl About {
+    /// Show the about window. Returns false once it's closed
+    pub fn show(ctx: &egui::Context) -> bool {
+        let (rustc, cargo) = Self::toolchain(ctx);
+
+        let mut open = true;
+
+        Window::new("About Rust Play")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.heading(format!("Rust Play {}", env!("CARGO_PKG_VERSION")));
+                ui.label(format!("Commit {GIT_HASH}"));
+                ui.label(format!("License {}", env!("CARGO_PKG_LICENSE")));
+                ui.hyperlink(REPOSITORY_URL);
+
+                ui.separator();
+
+                ui.label(rustc.as_deref().unwrap_or("rustc not found"));
+                ui.label(cargo.as_deref().unwrap_or("cargo not found"));
+
+                ui.separator();
+
+                CollapsingHeader::new("Third-party licenses").show(ui, |ui| {
+                    ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        for line in THIRD_PARTY_LICENSES.lines() {
+                            ui.label(line);
+                        }
+                    });
+                });
+
+                ui.separator();
+
+                if ui.button("Copy diagnostic info").clicked() {
+                    ui.output().copied_text = Self::diagnostics(&rustc, &cargo);
+                }
+            });
+
+        open
+    }
+
+    fn toolchain(ctx: &egui::Context) -> (Option<String>, Option<String>) {
+        if let Some(toolchain) = TOOLCHAIN.get() {
+            return toolchain.clone();
+        }
+
+        if !DETECTING.swap(true, Ordering::Relaxed) {
+            let ctx = ctx.clone();
+
+            thread::spawn(move || {
+                let _ = TOOLCHAIN.set((toolchain::version("rustc"), toolchain::version("cargo")));
+                ctx.request_repaint();
+            });
+        }
+
+        let detecting = Some("detecting...".to_string());
+        (detecting.clone(), detecting)
+    }
+
+    // pasted into bug reports
+    fn diagnostics(rustc: &Option<String>, cargo: &Option<String>) -> String {
+        format!(
+            "Rust Play {} ({GIT_HASH})\nOS: {} {}\nrustc: {}\ncargo: {}",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            rustc.as_deref().unwrap_or("not found"),
+            cargo.as_deref().unwrap_or("not found"),
+        )
+    }
+}
@@ -0,0 +1,278 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use cargo_player::{add_dependency_directive, dependency_toml};
+use egui::{Button, ComboBox, Id, Key, ScrollArea, TextEdit, Ui, Window};
+use egui_dock::Node;
+
+use crate::config::{crate_versions, search_crates, CrateVersion, DependencySearch};
+
+use super::dock::Tree;
+use super::toasts::Toast;
+
+pub struct DependencyDialog;
+
+impl DependencyDialog {
+    /// Search crates.io, pick a crate with a version and its features, and add its `//#` directive to
+    /// the top of a tab. Returns false once it's closed or the dependency was added
+    pub fn show(
+        ctx: &egui::Context,
+        id: Id,
+        search: &mut DependencySearch,
+        tree: &mut Tree,
+    ) -> bool {
+        let tab = tree.iter_mut().find_map(|node| {
+            let Node::Leaf { tabs, .. } = node else {
+                return None;
+            };
+
+            tabs.iter_mut().find(|tab| tab.id == id)
+        });
+
+        // the tab was closed in the meantime
+        let Some(tab) = tab else {
+            *search = DependencySearch::default();
+            return false;
+        };
+
+        // opened for another tab, the search starts over
+        if search.tab != Some(id) {
+            *search = DependencySearch {
+                tab: Some(id),
+                ..Default::default()
+            };
+        }
+
+        if let Some(results) = Self::poll(ctx, &mut search.searching) {
+            search.results = Some(results);
+        }
+
+        if let Some(versions) = Self::poll(ctx, &mut search.fetching) {
+            // the newest release, unless there are only pre-releases
+            if let Ok(versions) = &versions {
+                search.version = versions
+                    .iter()
+                    .position(|version| !version.num.contains('-'))
+                    .unwrap_or_default();
+            }
+
+            search.versions = Some(versions);
+        }
+
+        let read_only = tab.editor.is_read_only();
+        let mut open = true;
+        let mut added = None;
+
+        Window::new(format!("Add dependency to {}", tab.name))
+            .id(Id::new("add_dependency"))
+            .open(&mut open)
+            .default_size([560.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let query = ui
+                        .add(TextEdit::singleline(&mut search.query).hint_text("Search crates.io"));
+
+                    let enter = query.lost_focus() && ui.input().key_pressed(Key::Enter);
+                    let can_search = !search.query.trim().is_empty() && search.searching.is_none();
+
+                    let clicked = ui.add_enabled(can_search, Button::new("Search")).clicked();
+
+                    if can_search && (clicked || enter) {
+                        search.searching = Some(search_crates(&search.query));
+                    }
+                });
+
+                ui.separator();
+
+                ui.columns(2, |columns| {
+                    Self::show_results(&mut columns[0], search);
+                    added = Self::show_crate(&mut columns[1], search, read_only);
+                });
+            });
+
+        if let Some((name, dependency)) = added {
+            tab.editor.code = add_dependency_directive(&tab.editor.code, &dependency);
+
+            Toast::success(format!("Added {name} to {}", tab.name)).push(ctx);
+            open = false;
+        }
+
+        // start over the next time it's opened
+        if !open {
+            *search = DependencySearch::default();
+        }
+
+        open
+    }
+
+    // the result of a request once it's done
+    fn poll<T>(ctx: &egui::Context, rx: &mut Option<Receiver<T>>) -> Option<T> {
+        let result = match rx.as_ref()?.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Disconnected) => None,
+            Err(TryRecvError::Empty) => {
+                // the requests don't wake up the ui on their own
+                ctx.request_repaint();
+                return None;
+            }
+        };
+
+        *rx = None;
+
+        result
+    }
+
+    fn show_results(ui: &mut Ui, search: &mut DependencySearch) {
+        if search.searching.is_some() {
+            ui.spinner();
+            return;
+        }
+
+        let results = match &search.results {
+            Some(Ok(results)) => results,
+            Some(Err(e)) => {
+                ui.label(format!("Failed to search crates.io: {e}"));
+                return;
+            }
+            None => return,
+        };
+
+        if results.is_empty() {
+            ui.label("No crates found");
+            return;
+        }
+
+        let mut fetch = None;
+
+        ScrollArea::vertical()
+            .id_source("crate_results")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for krate in results {
+                    let is_selected = search.selected.as_deref() == Some(krate.name.as_str());
+                    let version = krate
+                        .max_stable_version
+                        .as_deref()
+                        .unwrap_or(&krate.max_version);
+
+                    let mut hover = format!("{} downloads", krate.downloads);
+                    if let Some(description) = &krate.description {
+                        hover = format!("{}\n\n{hover}", description.trim());
+                    }
+
+                    let response = ui
+                        .selectable_label(is_selected, format!("{} {version}", krate.name))
+                        .on_hover_text(hover);
+
+                    if response.clicked() {
+                        fetch = Some(krate.name.clone());
+                    }
+                }
+            });
+
+        if let Some(name) = fetch {
+            search.fetching = Some(crate_versions(&name));
+            search.selected = Some(name);
+            search.versions = None;
+            search.version = 0;
+            search.default_features = true;
+            search.features.clear();
+        }
+    }
+
+    // the versions and features of the picked crate. Returns its name and dependency line once it's added
+    fn show_crate(
+        ui: &mut Ui,
+        search: &mut DependencySearch,
+        read_only: bool,
+    ) -> Option<(String, String)> {
+        if search.fetching.is_some() {
+            ui.spinner();
+            return None;
+        }
+
+        let Some(name) = search.selected.clone() else {
+            ui.label("Select a crate to pick its version and features");
+            return None;
+        };
+
+        let versions = match &search.versions {
+            Some(Ok(versions)) => versions,
+            Some(Err(e)) => {
+                ui.label(format!("Failed to load the versions: {e}"));
+                return None;
+            }
+            None => return None,
+        };
+
+        if versions.is_empty() {
+            ui.label("Every version of this crate was yanked");
+            return None;
+        }
+
+        ui.hyperlink_to(&name, format!("https://crates.io/crates/{name}"));
+
+        let previous = search.version;
+        ComboBox::from_label("Version")
+            .selected_text(&versions[search.version].num)
+            .show_ui(ui, |ui| {
+                for (i, version) in versions.iter().enumerate() {
+                    ui.selectable_value(&mut search.version, i, &version.num);
+                }
+            });
+
+        let version = &versions[search.version];
+
+        // features the picked version doesn't have are dropped
+        if previous != search.version {
+            search
+                .features
+                .retain(|feature| version.features.contains_key(feature));
+        }
+
+        ui.checkbox(&mut search.default_features, "Default features")
+            .on_hover_text(Self::enables(version, "default"));
+
+        let features = search.features.iter().cloned().collect::<Vec<_>>();
+        let dependency = dependency_toml(&name, &version.num, search.default_features, &features);
+
+        ui.code(format!("//# {dependency}"));
+
+        let add = ui
+            .add_enabled(!read_only, Button::new("Add"))
+            .on_disabled_hover_text("The tab is read-only")
+            .clicked();
+
+        ui.separator();
+
+        ScrollArea::vertical()
+            .id_source("crate_features")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for feature in version.features.keys().filter(|f| *f != "default") {
+                    let mut enabled = search.features.contains(feature);
+
+                    if ui
+                        .checkbox(&mut enabled, feature)
+                        .on_hover_text(Self::enables(version, feature))
+                        .changed()
+                    {
+                        if enabled {
+                            search.features.insert(feature.clone());
+                        } else {
+                            search.features.remove(feature);
+                        }
+                    }
+                }
+            });
+
+        add.then_some((name, dependency))
+    }
+
+    // what turning on a feature turns on as well
+    fn enables(version: &CrateVersion, feature: &str) -> String {
+        match version.features.get(feature) {
+            Some(enables) if !enables.is_empty() => format!("Enables {}", enables.join(", ")),
+            _ => "Enables nothing else".to_string(),
+        }
+    }
+}
@@ -0,0 +1,731 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use cargo_player::{Edition, File, Project, Subcommand};
+use egui::{Area, Event, Frame, Id, Key, Modifiers, Order, RichText, ScrollArea, Ui};
+use rustplay_widgets::code_editor::CodeEditor;
+use serde_json::{json, Value};
+
+use crate::config::{AnalyzerSession, Command, Completion, TabCommand};
+use crate::utils::data::Data;
+use crate::utils::lsp::{char_index, file_uri, position, uri_path, LspClient, Reply};
+use crate::utils::toolchain;
+
+use super::dock::{BuildOptions, Tab};
+use super::toasts::Toast;
+
+// how long the pointer rests on a word before its type is asked for
+const HOVER_DELAY: f64 = 0.5;
+const MAX_COMPLETIONS: usize = 50;
+const MAX_HOVER_LINES: usize = 20;
+
+pub struct RustAnalyzer;
+
+impl RustAnalyzer {
+    /// The keys of the completion popup, taken before the editor sees them. Up and down pick, enter and tab
+    /// insert the pick and escape closes it. Ctrl+space asks for completions without typing
+    pub fn keys(
+        ctx: &egui::Context,
+        session: &mut AnalyzerSession,
+        editor_id: Id,
+        code: &mut String,
+    ) {
+        let Some(cursor) = CodeEditor::cursor(ctx, editor_id) else {
+            return;
+        };
+
+        // escape took the focus away before any widget ran, closing the popup is all it does then
+        let visible = Self::visible(session, code, cursor);
+        if !visible.is_empty() && ctx.input().key_pressed(Key::Escape) {
+            session.completions.clear();
+            ctx.memory().request_focus(editor_id);
+            return;
+        }
+
+        if !ctx.memory().has_focus(editor_id) {
+            return;
+        }
+
+        if ctx.input_mut().consume_key(Modifiers::COMMAND, Key::Space) {
+            // the space typed along with it
+            ctx.input_mut()
+                .events
+                .retain(|event| !matches!(event, Event::Text(text) if text == " "));
+
+            session.completion = Self::request(session, "textDocument/completion", code, cursor);
+            return;
+        }
+
+        if visible.is_empty() {
+            return;
+        }
+
+        let mut input = ctx.input_mut();
+
+        if input.consume_key(Modifiers::NONE, Key::ArrowDown) {
+            session.selected = (session.selected + 1) % visible.len();
+        }
+
+        if input.consume_key(Modifiers::NONE, Key::ArrowUp) {
+            session.selected = session.selected.checked_sub(1).unwrap_or(visible.len() - 1);
+        }
+
+        let accept = input.consume_key(Modifiers::NONE, Key::Enter)
+            | input.consume_key(Modifiers::NONE, Key::Tab);
+
+        drop(input);
+
+        if accept {
+            let completion = visible[session.selected.min(visible.len() - 1)].clone();
+            Self::insert(ctx, session, editor_id, code, cursor, &completion);
+        }
+    }
+
+    /// Start rust-analyzer for the tab, keep it in step with the code and show what it says about it:
+    /// completions while typing, the type of what's hovered, and going to a definition with ctrl+click or F12
+    pub fn show(
+        ui: &mut Ui,
+        session: &mut AnalyzerSession,
+        tab: &mut Tab,
+        base_dir: Option<PathBuf>,
+        data: &Data<Command>,
+    ) {
+        let ctx = ui.ctx().clone();
+        let editor_id = tab.id.with("code_editor");
+
+        if !Self::running(&ctx, session, tab, base_dir.clone()) {
+            return;
+        }
+
+        let code = &mut tab.editor.code;
+        let cursor = CodeEditor::cursor(&ctx, editor_id);
+        let focused = ctx.memory().has_focus(editor_id);
+
+        let hash = hash(code);
+        if hash != session.synced {
+            Self::sync(session, code, hash);
+
+            // a word, a field or a path is being typed
+            let typing = cursor.and_then(|cursor| code.chars().nth(cursor.checked_sub(1)?));
+            session.completion = match (typing, cursor) {
+                (Some(c), Some(cursor)) if focused && (is_ident(c) || c == '.' || c == ':') => {
+                    Self::request(session, "textDocument/completion", code, cursor)
+                }
+
+                _ => {
+                    session.completions.clear();
+                    None
+                }
+            };
+        }
+
+        Self::rewrite_project(
+            &ctx,
+            session,
+            tab_project(tab.id),
+            code,
+            &tab.build,
+            base_dir,
+        );
+
+        if let Some(reply) = poll(&mut session.completion) {
+            session.completions = completions(&reply);
+            session.selected = 0;
+        }
+
+        if let (Some(cursor), true) = (cursor, focused) {
+            Self::show_completions(ui, session, editor_id, code, cursor);
+        } else {
+            session.completions.clear();
+        }
+
+        Self::hover(ui, session, editor_id, code);
+
+        let hovered = CodeEditor::hovered(&ctx, editor_id);
+        let clicked = ctx.input().modifiers.command && ctx.input().pointer.primary_clicked();
+
+        let definition_at = match (hovered, cursor) {
+            (Some(index), _) if clicked => Some(index),
+            (_, Some(cursor)) if focused && ctx.input().key_pressed(Key::F12) => Some(cursor),
+            _ => None,
+        };
+
+        if let Some(index) = definition_at {
+            session.definition = Self::request(session, "textDocument/definition", code, index);
+        }
+
+        if let Some(reply) = poll(&mut session.definition) {
+            Self::go_to_definition(&ctx, session, tab, &reply, data);
+        }
+    }
+
+    // whether the server is ready, starting it if it isn't running yet
+    fn running(
+        ctx: &egui::Context,
+        session: &mut AnalyzerSession,
+        tab: &Tab,
+        base_dir: Option<PathBuf>,
+    ) -> bool {
+        if session.failed {
+            return false;
+        }
+
+        if let Some(client) = &mut session.client {
+            if client.is_running() {
+                return true;
+            }
+
+            Toast::error("rust-analyzer stopped, turn it off and on in the settings to restart it")
+                .push(ctx);
+            *session = AnalyzerSession {
+                failed: true,
+                ..Default::default()
+            };
+
+            return false;
+        }
+
+        let Some(starting) = &session.starting else {
+            Self::start(ctx, session, tab, base_dir);
+            return false;
+        };
+
+        match starting.try_recv() {
+            Ok(Ok((mut client, main_rs))) => {
+                let uri = file_uri(&main_rs);
+                let _ = client.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": "rust",
+                            "version": 0,
+                            "text": tab.editor.code,
+                        }
+                    }),
+                );
+
+                session.client = Some(client);
+                session.main_rs = main_rs;
+                session.synced = hash(&tab.editor.code);
+                session.manifest = manifest_hash(&tab.editor.code);
+                session.starting = None;
+
+                true
+            }
+
+            Ok(Err(e)) => {
+                Toast::error(e).push(ctx);
+                session.starting = None;
+                session.failed = true;
+
+                false
+            }
+
+            Err(TryRecvError::Disconnected) => {
+                session.starting = None;
+                session.failed = true;
+
+                false
+            }
+
+            Err(TryRecvError::Empty) => false,
+        }
+    }
+
+    fn start(
+        ctx: &egui::Context,
+        session: &mut AnalyzerSession,
+        tab: &Tab,
+        base_dir: Option<PathBuf>,
+    ) {
+        let (tx, rx) = channel();
+        session.starting = Some(rx);
+
+        let project_id = tab_project(tab.id);
+        let code = tab.editor.code.clone();
+        let build = tab.build.clone();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let result = start_server(project_id, &code, &build, base_dir.as_deref());
+
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+
+    // the whole text is sent, scratches are small
+    fn sync(session: &mut AnalyzerSession, code: &str, hash: u64) {
+        let uri = file_uri(&session.main_rs);
+        let Some(client) = &mut session.client else {
+            return;
+        };
+
+        session.version += 1;
+        session.synced = hash;
+
+        let _ = client.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": session.version },
+                "contentChanges": [{ "text": code }],
+            }),
+        );
+    }
+
+    // the dependencies are inferred from the code, a new `use` or directive may change Cargo.toml
+    fn rewrite_project(
+        ctx: &egui::Context,
+        session: &mut AnalyzerSession,
+        project_id: Id,
+        code: &str,
+        build: &BuildOptions,
+        base_dir: Option<PathBuf>,
+    ) {
+        if let Some(writing) = &session.writing {
+            match writing.try_recv() {
+                Ok(()) => {
+                    // two folders up from src/main.rs
+                    let cargo_toml = session
+                        .main_rs
+                        .ancestors()
+                        .nth(2)
+                        .map(|dir| dir.join("Cargo.toml"));
+
+                    if let (Some(client), Some(cargo_toml)) = (&mut session.client, cargo_toml) {
+                        // 2 is changed
+                        let _ = client.notify(
+                            "workspace/didChangeWatchedFiles",
+                            json!({ "changes": [{ "uri": file_uri(&cargo_toml), "type": 2 }] }),
+                        );
+                    }
+
+                    session.writing = None;
+                }
+
+                Err(TryRecvError::Disconnected) => session.writing = None,
+
+                Err(TryRecvError::Empty) => return,
+            }
+        }
+
+        let manifest = manifest_hash(code);
+        if manifest == session.manifest {
+            return;
+        }
+
+        session.manifest = manifest;
+
+        let (tx, rx) = channel();
+        session.writing = Some(rx);
+
+        let code = code.to_string();
+        let build = build.clone();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            if write_project(project_id, &code, &build, base_dir.as_deref()).is_ok() {
+                let _ = tx.send(());
+            }
+
+            ctx.request_repaint();
+        });
+    }
+
+    fn request(
+        session: &mut AnalyzerSession,
+        method: &str,
+        code: &str,
+        index: usize,
+    ) -> Option<Receiver<Reply>> {
+        let uri = file_uri(&session.main_rs);
+        let client = session.client.as_mut()?;
+
+        client
+            .request(
+                method,
+                json!({
+                    "textDocument": { "uri": uri },
+                    "position": position(code, index),
+                }),
+            )
+            .ok()
+    }
+
+    // the completions which still match the word at the cursor, they're asked for again as it's typed
+    fn visible(session: &AnalyzerSession, code: &str, cursor: usize) -> Vec<Completion> {
+        let chars = code.chars().collect::<Vec<_>>();
+        let cursor = cursor.min(chars.len());
+        let word = chars[word_start(&chars, cursor)..cursor]
+            .iter()
+            .collect::<String>();
+
+        session
+            .completions
+            .iter()
+            .filter(|completion| fuzzy_match(&word, &completion.label))
+            .cloned()
+            .collect()
+    }
+
+    fn show_completions(
+        ui: &mut Ui,
+        session: &mut AnalyzerSession,
+        editor_id: Id,
+        code: &mut String,
+        cursor: usize,
+    ) {
+        let visible = Self::visible(session, code, cursor);
+        let Some(cursor_rect) = CodeEditor::cursor_rect(ui.ctx(), editor_id) else {
+            return;
+        };
+
+        if visible.is_empty() {
+            return;
+        }
+
+        let mut picked = None;
+
+        Area::new(editor_id.with("completions"))
+            .order(Order::Foreground)
+            .fixed_pos(cursor_rect.left_bottom())
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        for (i, completion) in visible.iter().enumerate() {
+                            let selected = i == session.selected.min(visible.len() - 1);
+
+                            ui.horizontal(|ui| {
+                                let label = ui.selectable_label(
+                                    selected,
+                                    RichText::new(&completion.label).monospace(),
+                                );
+
+                                if let Some(detail) = &completion.detail {
+                                    ui.weak(detail);
+                                }
+
+                                if selected {
+                                    label.scroll_to_me(None);
+                                }
+
+                                if label.clicked() {
+                                    picked = Some(completion.clone());
+                                }
+                            });
+                        }
+                    });
+                });
+            });
+
+        if let Some(completion) = picked {
+            Self::insert(ui.ctx(), session, editor_id, code, cursor, &completion);
+            ui.memory().request_focus(editor_id);
+        }
+    }
+
+    // replace the word at the cursor with the completion
+    fn insert(
+        ctx: &egui::Context,
+        session: &mut AnalyzerSession,
+        editor_id: Id,
+        code: &mut String,
+        cursor: usize,
+        completion: &Completion,
+    ) {
+        let mut chars = code.chars().collect::<Vec<_>>();
+        let cursor = cursor.min(chars.len());
+        let start = word_start(&chars, cursor);
+
+        chars.splice(start..cursor, completion.insert.chars());
+        *code = chars.into_iter().collect();
+
+        CodeEditor::set_cursor(ctx, editor_id, start + completion.insert.chars().count());
+
+        // sent right away, the next frame would take the insert for typing and ask again
+        Self::sync(session, code, hash(code));
+
+        session.completions.clear();
+        session.completion = None;
+    }
+
+    // the type of the word the pointer rests on, in a tooltip
+    fn hover(ui: &Ui, session: &mut AnalyzerSession, editor_id: Id, code: &str) {
+        let ctx = ui.ctx();
+        let now = ctx.input().time;
+
+        let chars = code.chars().collect::<Vec<_>>();
+        let hovered = CodeEditor::hovered(ctx, editor_id)
+            .filter(|&index| chars.get(index).map_or(false, |&c| is_ident(c)));
+
+        let Some(index) = hovered else {
+            session.hovered = None;
+            session.hover = None;
+            session.hover_text = None;
+            return;
+        };
+
+        let start = word_start(&chars, index);
+
+        match session.hovered {
+            Some((word, since)) if word == start => {
+                let waited = now - since >= HOVER_DELAY;
+
+                if waited && session.hover.is_none() && session.hover_text.is_none() {
+                    session.hover = Self::request(session, "textDocument/hover", code, index);
+                }
+            }
+
+            _ => {
+                session.hovered = Some((start, now));
+                session.hover = None;
+                session.hover_text = None;
+
+                ctx.request_repaint_after(Duration::from_secs_f64(HOVER_DELAY));
+            }
+        }
+
+        if let Some(reply) = poll(&mut session.hover) {
+            session.hover_text = Some((start, hover_text(&reply)));
+        }
+
+        if let Some((_, text)) = session
+            .hover_text
+            .as_ref()
+            .filter(|(word, text)| *word == start && !text.is_empty())
+        {
+            egui::show_tooltip_at_pointer(ctx, editor_id.with("hover"), |ui| {
+                ui.label(RichText::new(text).monospace());
+            });
+        }
+    }
+
+    // a definition in the scratch moves the cursor to it, one elsewhere is opened in a read-only tab
+    fn go_to_definition(
+        ctx: &egui::Context,
+        session: &AnalyzerSession,
+        tab: &mut Tab,
+        reply: &Value,
+        data: &Data<Command>,
+    ) {
+        let Some((path, start)) = location(reply) else {
+            Toast::warning("rust-analyzer found no definition there").push(ctx);
+            return;
+        };
+
+        let line = start["line"].as_u64().unwrap_or_default() as usize;
+
+        if path == session.main_rs {
+            let index = char_index(&tab.editor.code, &start);
+
+            CodeEditor::set_cursor(ctx, tab.id.with("code_editor"), index);
+            tab.editor.jump_to_line(line);
+
+            return;
+        }
+
+        let krate = source_crate(&path);
+
+        data.borrow_mut()
+            .push(Command::TabCommand(TabCommand::OpenDefinition(
+                krate, path, line,
+            )));
+    }
+}
+
+// a project for each tab, which runs don't rewrite
+fn tab_project(id: Id) -> Id {
+    id.with("rust_analyzer")
+}
+
+fn start_server(
+    project_id: Id,
+    code: &str,
+    build: &BuildOptions,
+    base_dir: Option<&Path>,
+) -> Result<(LspClient, PathBuf), String> {
+    let dir = write_project(project_id, code, build, base_dir)?;
+
+    // checking the code whenever it changes would compete with runs for the build directory
+    let options = json!({ "checkOnSave": false });
+
+    let client = LspClient::start(toolchain::rust_analyzer(), &dir, options).map_err(|e| {
+        format!(
+            "rust-analyzer couldn't be started: {e}. \
+             `rustup component add rust-analyzer` installs it"
+        )
+    })?;
+
+    Ok((client, dir.join("src").join("main.rs")))
+}
+
+// write the project rust-analyzer works on, without running anything. Returns its directory
+fn write_project(
+    project_id: Id,
+    code: &str,
+    build: &BuildOptions,
+    base_dir: Option<&Path>,
+) -> Result<PathBuf, String> {
+    let mut project = Project::new(project_id);
+
+    project
+        .file(File::new("main", code))
+        .edition(Edition::E2021)
+        .subcommand(Subcommand::Check);
+
+    build.apply(&mut project);
+
+    if let Some(base_dir) = base_dir {
+        project.base_dir(base_dir);
+    }
+
+    project.create().map_err(|e| e.to_string())?;
+
+    Ok(project.project_dir())
+}
+
+fn poll(receiver: &mut Option<Receiver<Reply>>) -> Option<Value> {
+    let reply = match receiver.as_ref()?.try_recv() {
+        Ok(reply) => reply,
+        Err(TryRecvError::Empty) => return None,
+        Err(TryRecvError::Disconnected) => {
+            *receiver = None;
+            return None;
+        }
+    };
+
+    *receiver = None;
+
+    reply.ok()
+}
+
+// a list, or the items of one which may be incomplete
+fn completions(reply: &Value) -> Vec<Completion> {
+    let items = match reply {
+        Value::Array(items) => items,
+        reply => match reply["items"].as_array() {
+            Some(items) => items,
+            None => return vec![],
+        },
+    };
+
+    let mut items = items.iter().collect::<Vec<_>>();
+    items.sort_by_key(|item| item["sortText"].as_str().unwrap_or_default().to_string());
+
+    items
+        .into_iter()
+        .take(MAX_COMPLETIONS)
+        .filter_map(|item| {
+            let label = item["label"].as_str()?.to_string();
+            let insert = item["textEdit"]["newText"]
+                .as_str()
+                .or_else(|| item["insertText"].as_str())
+                .unwrap_or(&label)
+                .to_string();
+
+            Some(Completion {
+                detail: item["detail"].as_str().map(ToString::to_string),
+                label,
+                insert,
+            })
+        })
+        .collect()
+}
+
+// markup, a marked string, or a list of them
+fn hover_text(reply: &Value) -> String {
+    let text = match &reply["contents"] {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part.as_str().or_else(|| part["value"].as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        contents => contents["value"].as_str().unwrap_or_default().to_string(),
+    };
+
+    text.trim()
+        .lines()
+        .take(MAX_HOVER_LINES)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// the file and start of the first definition, a location or a location link
+fn location(reply: &Value) -> Option<(PathBuf, Value)> {
+    let location = match reply {
+        Value::Array(locations) => locations.first()?,
+        location => location,
+    };
+
+    let uri = location
+        .get("targetUri")
+        .or_else(|| location.get("uri"))?
+        .as_str()?;
+    let range = location
+        .get("targetSelectionRange")
+        .or_else(|| location.get("range"))?;
+
+    Some((uri_path(uri)?, range["start"].clone()))
+}
+
+// the crate a file is in by the folder of its src folder, e.g. serde-1.0.152 or core
+fn source_crate(path: &Path) -> String {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| dir.file_name().map_or(false, |name| name == "src"))
+        .and_then(Path::parent)
+        .and_then(Path::file_name)
+        .map_or_else(
+            || "definition".to_string(),
+            |name| name.to_string_lossy().to_string(),
+        )
+}
+
+fn word_start(chars: &[char], index: usize) -> usize {
+    chars[..index.min(chars.len())]
+        .iter()
+        .rposition(|&c| !is_ident(c))
+        .map_or(0, |i| i + 1)
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// the chars of the word in order, not necessarily next to each other, e.g. `hm` matches `HashMap`
+fn fuzzy_match(word: &str, label: &str) -> bool {
+    let mut label = label.chars().flat_map(char::to_lowercase);
+
+    word.chars()
+        .flat_map(char::to_lowercase)
+        .all(|c| label.any(|l| l == c))
+}
+
+fn hash(code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    code.hash(&mut hasher);
+    hasher.finish()
+}
+
+// the lines the dependencies are inferred from
+fn manifest_hash(code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for line in code.lines().map(str::trim_start) {
+        if line.starts_with("//#")
+            || line.starts_with("//>")
+            || line.starts_with("extern crate")
+            || line.contains("use ")
+        {
+            line.hash(&mut hasher);
+        }
+    }
+
+    hasher.finish()
+}
@@ -0,0 +1,315 @@
+use std::cmp::{Ordering, Reverse};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use cargo_player::{BloatEntry, BloatReport};
+use egui::{pos2, vec2, Button, Color32, Grid, Id, ScrollArea, Sense, Shape, Stroke, Ui, Window};
+
+use crate::config::{timestamp, BinarySize, Command, MenuCommand};
+
+// temp memory key holding the size view of a tab's last binary size run
+pub const SIZE_VIEW: &str = "binary_size_view";
+// temp memory key holding the tab id of a binary size run which just finished, its view is opened
+pub const SIZE_VIEW_PENDING: &str = "binary_size_view_pending";
+
+const SPARKLINE_HEIGHT: f32 = 40.0;
+
+#[derive(Debug, Clone)]
+pub struct SizeView {
+    pub title: String,
+    pub report: Arc<BloatReport>,
+    // every measurement of the tab so far, oldest first. The last one is the report's
+    pub history: Arc<Vec<BinarySize>>,
+    pub id: Id,
+}
+
+impl SizeView {
+    pub fn new(name: &str, report: BloatReport, history: Vec<BinarySize>) -> Self {
+        let title = format!("Binary size of {name}");
+        let id = Id::new("binary_size").with(&title);
+
+        Self {
+            title,
+            report: Arc::new(report),
+            history: Arc::new(history),
+            id,
+        }
+    }
+
+    /// Whether a window for the same tab is already queued to show
+    pub fn is_open(&self, commands: &[Command]) -> bool {
+        commands.iter().any(|command| {
+            matches!(command, Command::MenuCommand(MenuCommand::BinarySize(view)) if view.id == self.id)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Name,
+    Crate,
+    Size,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TableState {
+    crates: bool,
+    sort: Column,
+    descending: bool,
+}
+
+impl Default for TableState {
+    fn default() -> Self {
+        Self {
+            crates: false,
+            sort: Column::Size,
+            descending: true,
+        }
+    }
+}
+
+pub struct BinarySizeViewer;
+
+impl BinarySizeViewer {
+    /// Show the largest functions or crates of the build in a sortable table, with the size trend of the tab.
+    /// Returns false once it's closed
+    pub fn show(ctx: &egui::Context, view: &SizeView) -> bool {
+        let mut open = true;
+
+        let mut state = ctx
+            .data()
+            .get_temp::<TableState>(view.id)
+            .unwrap_or_default();
+
+        let report = &view.report;
+
+        Window::new(&view.title)
+            .id(view.id)
+            .open(&mut open)
+            .default_size([560.0, 420.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("File {}", format_size(report.file_size)));
+                    ui.separator();
+                    ui.label(format!(".text {}", format_size(report.text_size)));
+
+                    let previous = view.history.iter().rev().nth(1);
+                    if let Some(previous) = previous {
+                        ui.separator();
+                        Self::show_change(ui, previous.file_size, report.file_size);
+                    }
+                });
+
+                if view.history.len() > 1 {
+                    Self::show_trend(ui, &view.history);
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut state.crates, false, "Functions");
+                    ui.selectable_value(&mut state.crates, true, "Crates");
+                });
+
+                let entries = if state.crates {
+                    &report.crates
+                } else {
+                    &report.functions
+                };
+
+                let mut entries = entries.iter().collect::<Vec<_>>();
+                Self::sort(&mut entries, state);
+
+                ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        Grid::new(view.id.with("table"))
+                            .striped(true)
+                            .num_columns(4)
+                            .show(ui, |ui| {
+                                Self::header(ui, &mut state, Column::Size, "Size");
+                                ui.strong("% of .text");
+                                if !state.crates {
+                                    Self::header(ui, &mut state, Column::Crate, "Crate");
+                                }
+                                Self::header(ui, &mut state, Column::Name, "Name");
+                                ui.end_row();
+
+                                for entry in entries {
+                                    ui.monospace(format_size(entry.size));
+
+                                    let share = if report.text_size == 0 {
+                                        0.0
+                                    } else {
+                                        entry.size as f64 / report.text_size as f64 * 100.0
+                                    };
+                                    ui.monospace(format!("{share:.1}%"));
+
+                                    if !state.crates {
+                                        ui.label(entry.krate.as_deref().unwrap_or_default());
+                                    }
+                                    ui.label(&entry.name);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        ctx.data().insert_temp(view.id, state);
+
+        open
+    }
+
+    // a column heading which sorts by the column, clicking it again flips the order
+    fn header(ui: &mut Ui, state: &mut TableState, column: Column, label: &str) {
+        let label = if state.sort != column {
+            label.to_string()
+        } else if state.descending {
+            format!("{label} ⏷")
+        } else {
+            format!("{label} ⏶")
+        };
+
+        if ui.add(Button::new(label).frame(false)).clicked() {
+            if state.sort == column {
+                state.descending = !state.descending;
+            } else {
+                state.sort = column;
+                // the biggest first, names alphabetically
+                state.descending = column == Column::Size;
+            }
+        }
+    }
+
+    fn sort(entries: &mut [&BloatEntry], state: TableState) {
+        match state.sort {
+            Column::Name => entries.sort_by(|a, b| a.name.cmp(&b.name)),
+            Column::Crate => entries.sort_by(|a, b| a.krate.cmp(&b.krate)),
+            Column::Size => entries.sort_by_key(|entry| Reverse(entry.size)),
+        }
+
+        // sizes are sorted biggest first already
+        if state.descending != (state.sort == Column::Size) {
+            entries.reverse();
+        }
+    }
+
+    fn show_change(ui: &mut Ui, previous: u64, current: u64) {
+        let (text, color) = match current.cmp(&previous) {
+            Ordering::Greater => (
+                format!("+{} since the last run", format_size(current - previous)),
+                Color32::RED,
+            ),
+            Ordering::Less => (
+                format!("-{} since the last run", format_size(previous - current)),
+                Color32::GREEN,
+            ),
+            Ordering::Equal => {
+                ui.weak("Unchanged since the last run");
+                return;
+            }
+        };
+
+        ui.colored_label(color, text);
+    }
+
+    // the file size of every run as a line
+    fn show_trend(ui: &mut Ui, history: &[BinarySize]) {
+        let (rect, response) =
+            ui.allocate_exact_size(vec2(ui.available_width(), SPARKLINE_HEIGHT), Sense::hover());
+
+        let min = history
+            .iter()
+            .map(|size| size.file_size)
+            .min()
+            .unwrap_or_default();
+        let max = history
+            .iter()
+            .map(|size| size.file_size)
+            .max()
+            .unwrap_or_default();
+        let range = (max - min).max(1) as f32;
+
+        let rect = rect.shrink(4.0);
+        let step = rect.width() / (history.len() - 1) as f32;
+
+        let points = history
+            .iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let y = (size.file_size - min) as f32 / range;
+                pos2(
+                    rect.left() + i as f32 * step,
+                    rect.bottom() - y * rect.height(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let stroke = Stroke::new(1.5, ui.visuals().selection.bg_fill);
+        ui.painter().add(Shape::line(points.clone(), stroke));
+
+        for point in &points {
+            ui.painter()
+                .circle_filled(*point, 2.5, ui.visuals().selection.bg_fill);
+        }
+
+        response.on_hover_ui(|ui| {
+            let hovered = ui
+                .ctx()
+                .pointer_hover_pos()
+                .map(|pos| ((pos.x - rect.left()) / step).round().max(0.0) as usize)
+                .and_then(|i| Some((i, history.get(i)?)));
+
+            if let Some((i, size)) = hovered {
+                ui.label(format!(
+                    "Run {}, {}: {} (.text {})",
+                    i + 1,
+                    ago(size.measured),
+                    format_size(size.file_size),
+                    format_size(size.text_size)
+                ));
+            }
+
+            ui.weak(format!(
+                "{} runs, {} to {}",
+                history.len(),
+                format_size(min),
+                format_size(max)
+            ));
+        });
+    }
+}
+
+// e.g. 5 minutes ago, for a unix timestamp
+fn ago(then: i64) -> String {
+    let secs = (timestamp(SystemTime::now()) - then).max(0);
+
+    let (amount, unit) = match secs {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (secs / 60, "minute"),
+        3600..=86399 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{amount} {unit}{plural} ago")
+}
+
+/// e.g. 1.5 MiB
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
@@ -1,331 +0,0 @@
-// ----------------------------------------------------------------------------
-
-use egui::text::LayoutJob;
-use egui::{vec2, Color32, FontSelection, Id, Layout, Rect, Rounding, Stroke, Vec2};
-use serde::{Deserialize, Serialize};
-
-/// Memoized Code highlighting
-pub fn highlight(ctx: &egui::Context, theme: &CodeTheme, code: &str, language: &str) -> LayoutJob {
-    impl egui::util::cache::ComputerMut<(&CodeTheme, &str, &str), LayoutJob> for Highlighter {
-        fn compute(&mut self, (theme, code, lang): (&CodeTheme, &str, &str)) -> LayoutJob {
-            self.highlight(theme, code, lang)
-        }
-    }
-
-    type HighlightCache = egui::util::cache::FrameCache<LayoutJob, Highlighter>;
-
-    let mut memory = ctx.memory();
-    let highlight_cache = memory.caches.cache::<HighlightCache>();
-    highlight_cache.get((theme, code, language))
-}
-
-// ----------------------------------------------------------------------------
-
-#[derive(Clone, Copy, Hash, PartialEq, Deserialize, Serialize)]
-enum SyntectTheme {
-    Base16EightiesDark,
-    Base16MochaDark,
-    Base16OceanDark,
-    Base16OceanLight,
-    InspiredGitHub,
-    SolarizedDark,
-    SolarizedLight,
-}
-
-impl SyntectTheme {
-    fn all() -> impl ExactSizeIterator<Item = Self> {
-        [
-            Self::Base16EightiesDark,
-            Self::Base16MochaDark,
-            Self::Base16OceanDark,
-            Self::Base16OceanLight,
-            Self::InspiredGitHub,
-            Self::SolarizedDark,
-            Self::SolarizedLight,
-        ]
-        .iter()
-        .copied()
-    }
-
-    fn name(&self) -> &'static str {
-        match self {
-            Self::Base16EightiesDark => "Base16 Eighties (dark)",
-            Self::Base16MochaDark => "Base16 Mocha (dark)",
-            Self::Base16OceanDark => "Base16 Ocean (dark)",
-            Self::Base16OceanLight => "Base16 Ocean (light)",
-            Self::InspiredGitHub => "InspiredGitHub (light)",
-            Self::SolarizedDark => "Solarized (dark)",
-            Self::SolarizedLight => "Solarized (light)",
-        }
-    }
-
-    fn syntect_key_name(&self) -> &'static str {
-        match self {
-            Self::Base16EightiesDark => "base16-eighties.dark",
-            Self::Base16MochaDark => "base16-mocha.dark",
-            Self::Base16OceanDark => "base16-ocean.dark",
-            Self::Base16OceanLight => "base16-ocean.light",
-            Self::InspiredGitHub => "InspiredGitHub",
-            Self::SolarizedDark => "Solarized (dark)",
-            Self::SolarizedLight => "Solarized (light)",
-        }
-    }
-
-    pub fn is_dark(&self) -> bool {
-        match self {
-            Self::Base16EightiesDark
-            | Self::Base16MochaDark
-            | Self::Base16OceanDark
-            | Self::SolarizedDark => true,
-
-            Self::Base16OceanLight | Self::InspiredGitHub | Self::SolarizedLight => false,
-        }
-    }
-}
-
-#[derive(Clone, Hash, PartialEq, Deserialize, Serialize)]
-#[serde(default)]
-pub struct CodeTheme {
-    dark_mode: bool,
-    syntect_theme: SyntectTheme,
-}
-
-impl Default for CodeTheme {
-    fn default() -> Self {
-        Self::dark()
-    }
-}
-
-impl CodeTheme {
-    pub fn from_style(style: &egui::Style) -> Self {
-        if style.visuals.dark_mode {
-            Self::dark()
-        } else {
-            Self::light()
-        }
-    }
-
-    pub fn from_memory(ctx: &egui::Context) -> Self {
-        if ctx.style().visuals.dark_mode {
-            ctx.data()
-                .get_persisted(egui::Id::new("dark"))
-                .unwrap_or_else(CodeTheme::dark)
-        } else {
-            ctx.data()
-                .get_persisted(egui::Id::new("light"))
-                .unwrap_or_else(CodeTheme::light)
-        }
-    }
-}
-
-impl CodeTheme {
-    pub fn dark() -> Self {
-        Self {
-            dark_mode: true,
-            syntect_theme: SyntectTheme::Base16MochaDark,
-        }
-    }
-
-    pub fn light() -> Self {
-        Self {
-            dark_mode: false,
-            syntect_theme: SyntectTheme::SolarizedLight,
-        }
-    }
-}
-
-// ----------------------------------------------------------------------------
-
-struct Highlighter {
-    ps: syntect::parsing::SyntaxSet,
-    ts: syntect::highlighting::ThemeSet,
-}
-
-impl Default for Highlighter {
-    fn default() -> Self {
-        Self {
-            ps: syntect::parsing::SyntaxSet::load_defaults_newlines(),
-            ts: syntect::highlighting::ThemeSet::load_defaults(),
-        }
-    }
-}
-
-impl Highlighter {
-    #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
-    fn highlight(&self, theme: &CodeTheme, code: &str, lang: &str) -> LayoutJob {
-        self.highlight_impl(theme, code, lang).unwrap_or_else(|| {
-            // Fallback:
-            LayoutJob::simple(
-                code.into(),
-                egui::FontId::monospace(12.0),
-                if theme.dark_mode {
-                    egui::Color32::LIGHT_GRAY
-                } else {
-                    egui::Color32::DARK_GRAY
-                },
-                f32::INFINITY,
-            )
-        })
-    }
-
-    fn highlight_impl(&self, theme: &CodeTheme, text: &str, language: &str) -> Option<LayoutJob> {
-        use syntect::easy::HighlightLines;
-        use syntect::highlighting::FontStyle;
-        use syntect::util::LinesWithEndings;
-
-        let syntax = self
-            .ps
-            .find_syntax_by_name(language)
-            .or_else(|| self.ps.find_syntax_by_extension(language))?;
-
-        let theme = theme.syntect_theme.syntect_key_name();
-        let mut h = HighlightLines::new(syntax, &self.ts.themes[theme]);
-
-        use egui::text::{LayoutSection, TextFormat};
-
-        let mut job = LayoutJob {
-            text: text.into(),
-            ..Default::default()
-        };
-
-        for line in LinesWithEndings::from(text) {
-            for (style, range) in h.highlight_line(line, &self.ps).ok()? {
-                let fg = style.foreground;
-                let text_color = egui::Color32::from_rgb(fg.r, fg.g, fg.b);
-                let italics = style.font_style.contains(FontStyle::ITALIC);
-                let underline = style.font_style.contains(FontStyle::ITALIC);
-                let underline = if underline {
-                    egui::Stroke::new(1.0, text_color)
-                } else {
-                    egui::Stroke::NONE
-                };
-                job.sections.push(LayoutSection {
-                    leading_space: 0.0,
-                    byte_range: as_byte_range(text, range),
-                    format: TextFormat {
-                        font_id: egui::FontId::monospace(12.0),
-                        color: text_color,
-                        italics,
-                        underline,
-                        ..Default::default()
-                    },
-                });
-            }
-        }
-
-        Some(job)
-    }
-}
-
-fn as_byte_range(whole: &str, range: &str) -> std::ops::Range<usize> {
-    let whole_start = whole.as_ptr() as usize;
-    let range_start = range.as_ptr() as usize;
-    assert!(whole_start <= range_start);
-    assert!(range_start + range.len() <= whole_start + whole.len());
-    let offset = range_start - whole_start;
-    offset..(offset + range.len())
-}
-
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct CodeEditor {
-    language: String,
-    pub code: String,
-}
-
-impl Default for CodeEditor {
-    fn default() -> Self {
-        Self {
-            language: "rs".into(),
-            code: r#"// How to write scratches
-//
-// Simply write `use some_crate;` anywhere, and the dependency will get
-// inferred and included automatically at the latest version!
-// This creates a simple depdendency requirement like so:
-//     serde = "*"
-//
-// If you have more complex requirements (such as features, or a specific
-// version), at the top of your file, use //# to specify custom
-// dependencies. All //# must be the very first lines in order to be
-// recognized.
-//# serde = { version = "1.0.152", features = ["derive"] }
-//
-// You can also include any extra custom cargo.toml with //>
-// All //> must be in one block, and either at the top of the file or after
-// any //# . Once the last consecutive //> is found,
-// no more //> blocks will work.
-//> [profile.dev]
-//> opt-level = 1
-//
-
-use rand::Rng;
-
-fn main() {
-    let mut rng = rand::thread_rng();
-
-    let n1: u8 = rng.gen();
-    let n2: u16 = rng.gen();
-    println!("Random u8: {}", n1);
-    println!("Random u16: {}", n2);
-    println!("Random u32: {}", rng.gen::<u32>());
-    println!("Random i32: {}", rng.gen::<i32>());
-    println!("Random float: {}", rng.gen::<f64>());
-}
-"#
-            .into(),
-        }
-    }
-}
-
-impl CodeEditor {
-    pub fn show(&mut self, id: Id, ui: &mut egui::Ui, scroll_offset: Vec2) -> Vec2 {
-        let Self { language, code } = self;
-
-        let frame_rect = ui.max_rect().shrink(6.0);
-        let code_rect = frame_rect.shrink(5.0);
-
-        let theme = CodeTheme::from_memory(ui.ctx());
-        let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
-            let mut layout_job = highlight(ui.ctx(), &theme, string, language);
-            layout_job.wrap.max_width = wrap_width;
-            ui.fonts().layout_job(layout_job)
-        };
-
-        let Rect { max, .. } = ui.max_rect();
-
-        ui.allocate_space(vec2(max.x, max.y));
-
-        ui.painter().rect(
-            frame_rect,
-            Rounding::same(5.0),
-            Color32::BLACK,
-            Stroke::NONE,
-        );
-
-        let mut frame_ui = ui.child_ui(code_rect, Layout::default());
-
-        // get how many rows it takes to fill up our max rect
-        let font_id = FontSelection::default().resolve(ui.style());
-        let row_height = ui.fonts().row_height(&font_id);
-        let rows = ((code_rect.height() - 5.0) / row_height).floor() as usize;
-
-        let text_widget = egui::TextEdit::multiline(code)
-            .font(egui::TextStyle::Monospace) // for cursor height
-            .code_editor()
-            // remove the frame and draw our own
-            .frame(false)
-            .desired_width(f32::INFINITY)
-            .margin(vec2(2.0, 2.0))
-            .layouter(&mut layouter)
-            .cursor_at_end(false)
-            .id(id)
-            .desired_rows(rows);
-
-        let scroll_res = egui::ScrollArea::vertical()
-            .scroll_offset(scroll_offset)
-            .show(&mut frame_ui, |ui| {
-                ui.add(text_widget);
-            });
-
-        scroll_res.state.offset
-    }
-}
@@ -0,0 +1,239 @@
+use std::cmp::Reverse;
+
+use egui::{vec2, Align2, Area, Frame, Id, Key, Modifiers, Order, ScrollArea, TextEdit};
+
+use crate::config::{Command, Config};
+
+use super::terminal;
+
+// temp memory key of the palette's state
+const PALETTE: &str = "command_palette";
+const WIDTH: f32 = 420.0;
+const MAX_HEIGHT: f32 = 300.0;
+
+// temp memory key of the providers the widgets registered, with the names they registered under
+const PROVIDERS: &str = "command_palette_providers";
+
+/// Lists the commands a module offers in the palette, given the current state of the app
+pub type Provider = fn(&mut Config) -> Vec<PaletteCommand>;
+
+pub enum PaletteAction {
+    // queued like a menu click
+    Command(Command),
+    // for anything which isn't a command, e.g. toggling a setting
+    Run(Box<dyn FnOnce(&egui::Context, &mut Config)>),
+}
+
+pub struct PaletteCommand {
+    // e.g. "Run: Play", the category groups related commands when searching
+    pub title: String,
+    pub action: PaletteAction,
+}
+
+impl PaletteCommand {
+    pub fn new(title: impl Into<String>, command: Command) -> Self {
+        Self {
+            title: title.into(),
+            action: PaletteAction::Command(command),
+        }
+    }
+
+    pub fn run(
+        title: impl Into<String>,
+        run: impl FnOnce(&egui::Context, &mut Config) + 'static,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            action: PaletteAction::Run(Box::new(run)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct PaletteState {
+    open: bool,
+    query: String,
+    // index into the matching commands
+    selected: usize,
+}
+
+pub struct CommandPalette;
+
+impl CommandPalette {
+    pub fn open(ctx: &egui::Context) {
+        let state = PaletteState {
+            open: true,
+            ..Default::default()
+        };
+
+        ctx.data().insert_temp(Id::new(PALETTE), state);
+    }
+
+    /// Offer the commands of a module in the palette. Modules register as they're shown, so any widget can add
+    /// commands without the palette knowing of it. Registering under a name again does nothing
+    pub fn register(ctx: &egui::Context, name: &'static str, provider: Provider) {
+        let mut data = ctx.data();
+        let providers =
+            data.get_temp_mut_or_default::<Vec<(&'static str, Provider)>>(Id::new(PROVIDERS));
+
+        if !providers.iter().any(|(registered, _)| *registered == name) {
+            providers.push((name, provider));
+        }
+    }
+
+    /// Ctrl+Shift+P opens an overlay to search every command and run it
+    pub fn show(ctx: &egui::Context, config: &mut Config) {
+        let id = Id::new(PALETTE);
+
+        let mut state = ctx.data().get_temp::<PaletteState>(id).unwrap_or_default();
+
+        let shortcut = Modifiers {
+            command: true,
+            shift: true,
+            ..Default::default()
+        };
+
+        if ctx.input_mut().consume_key(shortcut, Key::P) {
+            state = PaletteState {
+                open: !state.open,
+                ..Default::default()
+            };
+        }
+
+        if state.open && ctx.input_mut().consume_key(Modifiers::NONE, Key::Escape) {
+            state.open = false;
+        }
+
+        if !state.open {
+            ctx.data().insert_temp(id, state);
+            return;
+        }
+
+        let providers = ctx
+            .data()
+            .get_temp::<Vec<(&'static str, Provider)>>(Id::new(PROVIDERS))
+            .unwrap_or_default();
+
+        let mut commands = providers
+            .iter()
+            .flat_map(|(_, provider)| provider(config))
+            .filter_map(|command| Some((fuzzy_score(&state.query, &command.title)?, command)))
+            .collect::<Vec<_>>();
+
+        // the order the providers registered in breaks ties
+        commands.sort_by_key(|(score, _)| Reverse(*score));
+
+        let mut moved = false;
+
+        if !commands.is_empty() {
+            if ctx.input_mut().consume_key(Modifiers::NONE, Key::ArrowDown) {
+                state.selected = (state.selected + 1) % commands.len();
+                moved = true;
+            }
+
+            if ctx.input_mut().consume_key(Modifiers::NONE, Key::ArrowUp) {
+                state.selected = state.selected.checked_sub(1).unwrap_or(commands.len() - 1);
+                moved = true;
+            }
+        }
+
+        state.selected = state.selected.min(commands.len().saturating_sub(1));
+
+        let mut picked = ctx
+            .input_mut()
+            .consume_key(Modifiers::NONE, Key::Enter)
+            .then_some(state.selected);
+
+        let area = Area::new(id)
+            .order(Order::Foreground)
+            .anchor(Align2::CENTER_TOP, vec2(0.0, 60.0))
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(WIDTH);
+
+                    let response = ui.add(
+                        TextEdit::singleline(&mut state.query)
+                            .hint_text("Type a command")
+                            .desired_width(f32::INFINITY),
+                    );
+                    response.request_focus();
+
+                    if response.changed() {
+                        state.selected = 0;
+                    }
+
+                    ui.separator();
+
+                    ScrollArea::vertical()
+                        .max_height(MAX_HEIGHT)
+                        .show(ui, |ui| {
+                            if commands.is_empty() {
+                                ui.weak("No matching command");
+                            }
+
+                            for (i, (_, command)) in commands.iter().enumerate() {
+                                let selected = i == state.selected;
+                                let label = ui.selectable_label(selected, &command.title);
+
+                                if selected && moved {
+                                    label.scroll_to_me(None);
+                                }
+
+                                if label.clicked() {
+                                    picked = Some(i);
+                                }
+                            }
+                        });
+                });
+            });
+
+        if area.response.clicked_elsewhere() {
+            state.open = false;
+        }
+
+        if let Some((_, command)) = picked.and_then(|i| commands.into_iter().nth(i)) {
+            state.open = false;
+
+            match command.action {
+                PaletteAction::Command(command) => {
+                    terminal::queue(&mut config.dock.commands, command)
+                }
+                PaletteAction::Run(run) => run(ctx, config),
+            }
+        }
+
+        ctx.data().insert_temp(id, state);
+    }
+}
+
+// Every character of the query has to appear in order. Consecutive characters and ones starting
+// a word score higher, None if it doesn't match at all
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let index = position + text[position..].iter().position(|t| *t == c)?;
+
+        score += 1;
+
+        if previous.map_or(false, |previous| previous + 1 == index) {
+            score += 5;
+        }
+
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        // the further apart, the less likely it's what was meant
+        score -= (index - position).min(3) as i32;
+
+        previous = Some(index);
+        position = index + 1;
+    }
+
+    Some(score)
+}
@@ -0,0 +1,337 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::mpsc::{channel, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use cargo_player::{messages, Diagnostic, Edition, File, Level, Message, Project, Subcommand};
+use egui::{Color32, Id, RichText, Ui};
+use rustplay_widgets::code_editor::CodeEditor;
+
+use crate::config::CheckSession;
+use crate::utils::toolchain;
+
+use super::dock::{BuildOptions, Tab};
+
+#[cfg(target_os = "windows")]
+use {std::os::windows::process::CommandExt, windows::Win32::System::Threading::CREATE_NO_WINDOW};
+
+// temp memory key of what rustc --explain says about an error code, loaded once for each code
+const EXPLANATION: &str = "compiler_hints_explanation";
+// how long the code has to stay unchanged before it's checked
+const CHECK_DELAY: f64 = 1.0;
+const MAX_EXPLANATION_LINES: usize = 40;
+
+// None while it's loading, empty if rustc doesn't know the code
+type Explanation = Option<Arc<String>>;
+
+pub struct CompilerHints;
+
+impl CompilerHints {
+    /// Check the code with cargo once typing stops, and show the error or warning the pointer is on in a
+    /// tooltip, along with the explanation of its error code
+    pub fn show(ui: &Ui, session: &mut CheckSession, tab: &Tab, base_dir: Option<PathBuf>) {
+        let ctx = ui.ctx();
+        let now = ctx.input().time;
+        let code = &tab.editor.code;
+        let hash = hash(code);
+
+        if let Some((checking, receiver)) = &session.checking {
+            match receiver.try_recv() {
+                Ok(diagnostics) => {
+                    session.checked = *checking;
+                    session.diagnostics = diagnostics;
+                    session.checking = None;
+                }
+
+                // cargo couldn't be run, it's tried again once the code changes
+                Err(TryRecvError::Disconnected) => {
+                    session.checked = *checking;
+                    session.diagnostics.clear();
+                    session.checking = None;
+                }
+
+                Err(TryRecvError::Empty) => (),
+            }
+        }
+
+        let waiting = hash != session.checked
+            && session.checking.as_ref().map(|(checking, _)| *checking) != Some(hash);
+
+        if waiting {
+            match session.pending {
+                Some((pending, since)) if pending == hash => {
+                    // one check at a time, the next starts once it's done
+                    if now - since >= CHECK_DELAY && session.checking.is_none() {
+                        Self::check(ctx, session, tab, hash, base_dir);
+                        session.pending = None;
+                    }
+                }
+
+                _ => {
+                    session.pending = Some((hash, now));
+                    ctx.request_repaint_after(Duration::from_secs_f64(CHECK_DELAY));
+                }
+            }
+        }
+
+        // an edit moves what the diagnostics point at
+        if hash != session.checked {
+            return;
+        }
+
+        let editor_id = tab.id.with("code_editor");
+        let Some(index) = CodeEditor::hovered(ctx, editor_id) else {
+            return;
+        };
+
+        let (line, column) = line_column(code, index);
+
+        let hovered = session.diagnostics.iter().find(|diagnostic| {
+            diagnostic
+                .spans
+                .iter()
+                .any(|span| span.is_primary && span.contains(line, column))
+        });
+
+        if let Some(diagnostic) = hovered {
+            egui::show_tooltip_at_pointer(ctx, editor_id.with("compiler_hint"), |ui| {
+                Self::show_diagnostic(ui, diagnostic);
+            });
+        }
+    }
+
+    /// Show the explanation of the error code at a char index of some text in a tooltip, e.g. the
+    /// E0308 the pointer is on in the terminal
+    pub fn explain_hovered(ctx: &egui::Context, id: Id, text: &str, index: usize) {
+        let Some(code) = error_code_at(text, index) else {
+            return;
+        };
+
+        egui::show_tooltip_at_pointer(ctx, id.with("explanation"), |ui| {
+            ui.strong(&code);
+            Self::show_explanation(ui, &code);
+        });
+    }
+
+    fn show_diagnostic(ui: &mut Ui, diagnostic: &Diagnostic) {
+        let (level, color) = match diagnostic.level {
+            Level::Error | Level::InternalCompilerError => ("error", ui.visuals().error_fg_color),
+            Level::Warning => ("warning", ui.visuals().warn_fg_color),
+            _ => ("note", Color32::LIGHT_BLUE),
+        };
+
+        let title = match &diagnostic.code {
+            Some(code) => format!("{level}[{code}]: {}", diagnostic.message),
+            None => format!("{level}: {}", diagnostic.message),
+        };
+
+        ui.label(RichText::new(title).color(color).strong());
+
+        let label = diagnostic
+            .spans
+            .iter()
+            .filter(|span| span.is_primary)
+            .find_map(|span| span.label.as_deref());
+
+        if let Some(label) = label {
+            ui.label(label);
+        }
+
+        for child in &diagnostic.children {
+            let level = match child.level {
+                Level::Help => "help",
+                _ => "note",
+            };
+
+            ui.weak(format!("{level}: {}", child.message));
+        }
+
+        // lints have a name instead, e.g. unused_variables, rustc only explains error codes
+        if let Some(code) = diagnostic
+            .code
+            .as_deref()
+            .filter(|code| is_error_code(code))
+        {
+            ui.separator();
+            Self::show_explanation(ui, code);
+        }
+    }
+
+    fn show_explanation(ui: &mut Ui, code: &str) {
+        match explanation(ui.ctx(), code) {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Loading the explanation...");
+                });
+            }
+
+            Some(text) if text.is_empty() => {
+                ui.weak("rustc has no explanation of it");
+            }
+
+            Some(text) => {
+                let lines = text.lines().count();
+                let mut shown = text
+                    .lines()
+                    .take(MAX_EXPLANATION_LINES)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                if lines > MAX_EXPLANATION_LINES {
+                    shown.push_str("\n...");
+                }
+
+                ui.label(RichText::new(shown).monospace());
+            }
+        }
+    }
+
+    // cargo check a copy of the code in a project of the tab's own, so runs don't rewrite it underneath
+    fn check(
+        ctx: &egui::Context,
+        session: &mut CheckSession,
+        tab: &Tab,
+        hash: u64,
+        base_dir: Option<PathBuf>,
+    ) {
+        let (tx, rx) = channel();
+        session.checking = Some((hash, rx));
+
+        let project_id = tab.id.with("compiler_hints");
+        let code = tab.editor.code.clone();
+        let build = tab.build.clone();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            if let Some(diagnostics) = check(project_id, &code, &build, base_dir.as_deref()) {
+                let _ = tx.send(diagnostics);
+            }
+
+            ctx.request_repaint();
+        });
+    }
+}
+
+/// The error code, e.g. E0308, at a char index of some text
+pub fn error_code_at(text: &str, index: usize) -> Option<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    if !chars.get(index)?.is_ascii_alphanumeric() {
+        return None;
+    }
+
+    let start = chars[..index]
+        .iter()
+        .rposition(|c| !c.is_ascii_alphanumeric())
+        .map_or(0, |i| i + 1);
+    let end = chars[index..]
+        .iter()
+        .position(|c| !c.is_ascii_alphanumeric())
+        .map_or(chars.len(), |i| index + i);
+
+    let word = chars[start..end].iter().collect::<String>();
+
+    is_error_code(&word).then_some(word)
+}
+
+// E and four digits
+fn is_error_code(word: &str) -> bool {
+    word.len() == 5 && word.starts_with('E') && word[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+// loaded in the background the first time it's asked for, then kept until the app closes
+fn explanation(ctx: &egui::Context, code: &str) -> Explanation {
+    let id = Id::new(EXPLANATION).with(code);
+
+    if let Some(explanation) = ctx.data().get_temp::<Explanation>(id) {
+        return explanation;
+    }
+
+    ctx.data().insert_temp::<Explanation>(id, None);
+
+    let code = code.to_string();
+    let ctx = ctx.clone();
+
+    thread::spawn(move || {
+        let text = toolchain::explain(&code).unwrap_or_default();
+
+        ctx.data()
+            .insert_temp::<Explanation>(id, Some(Arc::new(text)));
+        ctx.request_repaint();
+    });
+
+    None
+}
+
+// the diagnostics pointing into the scratch, or None if cargo couldn't be run
+fn check(
+    project_id: Id,
+    code: &str,
+    build: &BuildOptions,
+    base_dir: Option<&Path>,
+) -> Option<Vec<Diagnostic>> {
+    let mut project = Project::new(project_id);
+
+    project
+        .file(File::new("main", code))
+        .edition(Edition::E2021)
+        .subcommand(Subcommand::Check)
+        .message_format_json();
+
+    build.apply(&mut project);
+
+    if let Some(base_dir) = base_dir {
+        project.base_dir(base_dir);
+    }
+
+    let mut command = project.create().ok()?;
+    command.stdout(Stdio::piped()).stderr(Stdio::null());
+
+    // hide the console window from command
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW.0);
+
+    let mut child = command.spawn().ok()?;
+    let stdout = child.stdout.take()?;
+
+    let diagnostics = messages(BufReader::new(stdout))
+        .filter_map(|message| match message {
+            Message::CompilerMessage(diagnostic) => Some(diagnostic),
+            _ => None,
+        })
+        .filter(|diagnostic| {
+            diagnostic
+                .spans
+                .iter()
+                .any(|span| span.is_primary && Path::new(&span.file_name).ends_with("main.rs"))
+        })
+        .collect();
+
+    let _ = child.wait();
+
+    Some(diagnostics)
+}
+
+// the 1-based line and column of a char index, as rustc counts them
+fn line_column(code: &str, index: usize) -> (usize, usize) {
+    let before = code.chars().take(index).collect::<String>();
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+        + 1;
+
+    (line, column)
+}
+
+fn hash(code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    code.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use cargo_player::{
+    crate_sources, dependency_directives, directives, CrateSource, Edition, File, Project,
+};
+use egui::{CollapsingHeader, Id, ScrollArea, Ui, Window};
+use egui_dock::Node;
+
+use super::dock::{TabEvents, Tree};
+
+// temp memory key of a tab's dependencies, None while cargo metadata is still running
+const CRATE_SOURCES: &str = "crate_sources";
+
+type Sources = Option<Result<Arc<Vec<CrateSource>>, String>>;
+
+pub struct CrateSourceBrowser;
+
+impl CrateSourceBrowser {
+    /// List the dependencies of a tab with the versions and features cargo resolved, whether the code lists
+    /// them itself or they were inferred, and open their files as read-only tabs. The directives of the code are
+    /// listed above them, along with those which are ignored. Returns false once it's closed
+    pub fn show(
+        ctx: &egui::Context,
+        id: Id,
+        tree: &mut Tree,
+        counter: &mut u32,
+        base_dir: Option<PathBuf>,
+    ) -> bool {
+        let tab = tree.iter().find_map(|node| {
+            let Node::Leaf { tabs, .. } = node else {
+                return None;
+            };
+
+            tabs.iter().find(|tab| tab.id == id)
+        });
+
+        // the tab was closed in the meantime
+        let Some(tab) = tab else {
+            return false;
+        };
+
+        let title = format!("Dependencies of {}", tab.name);
+        let sources_id = Id::new(CRATE_SOURCES).with(id);
+
+        let sources = ctx.data().get_temp::<Sources>(sources_id);
+        let Some(sources) = sources else {
+            Self::load(ctx, sources_id, tab.editor.code.clone(), base_dir);
+            return true;
+        };
+
+        // crate names may be written with - or _ either way
+        let normalized = |name: &str| name.replace('-', "_");
+        let explicit = dependency_directives(&tab.editor.code)
+            .iter()
+            .map(|name| normalized(name))
+            .collect::<Vec<_>>();
+
+        // (line number and text, whether it's read)
+        let directives = directives(&tab.editor.code)
+            .iter()
+            .map(|directive| {
+                let label = format!("{}: {}", directive.line + 1, directive.text);
+                (label, directive.read)
+            })
+            .collect::<Vec<_>>();
+
+        let mut open = true;
+        let mut opened = None;
+
+        Window::new(title)
+            .id(sources_id)
+            .open(&mut open)
+            .default_size([360.0, 420.0])
+            .show(ctx, |ui| {
+                Self::show_directives(ui, sources_id, &directives);
+
+                match &sources {
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Fetching the dependencies...");
+                        });
+                    }
+
+                    Some(Err(e)) => {
+                        ui.label(format!("Failed to list the dependencies:\n{e}"));
+                    }
+
+                    Some(Ok(sources)) if sources.is_empty() => {
+                        ui.label("The scratch has no dependencies");
+                    }
+
+                    Some(Ok(sources)) => {
+                        if ui
+                            .button("Copy as Cargo.toml")
+                            .on_hover_text(
+                                "The [dependencies] table with the resolved versions and features",
+                            )
+                            .clicked()
+                        {
+                            ui.output().copied_text = Self::cargo_toml(sources);
+                        }
+
+                        ScrollArea::vertical()
+                            .auto_shrink([false, false])
+                            .show(ui, |ui| {
+                                for source in sources.iter() {
+                                    let origin = if explicit.contains(&normalized(&source.name)) {
+                                        "explicit"
+                                    } else {
+                                        "inferred"
+                                    };
+
+                                    CollapsingHeader::new(format!(
+                                        "{} {} ({origin})",
+                                        source.name, source.version
+                                    ))
+                                    .id_source(sources_id.with(&source.name))
+                                    .show(ui, |ui| {
+                                        ui.label(Self::features_label(source));
+                                        ui.separator();
+
+                                        for file in Self::files(ctx, source).iter() {
+                                            if ui.button(file.display().to_string()).clicked() {
+                                                opened = Some((
+                                                    source.name.clone(),
+                                                    source.dir.join(file),
+                                                ));
+                                            }
+                                        }
+                                    });
+                                }
+                            });
+                    }
+                }
+            });
+
+        if let Some((name, path)) = opened {
+            TabEvents::open_source(ctx, &name, path, None, tree, counter);
+        }
+
+        // fetched again the next time it's opened, the dependencies may have changed by then
+        if !open {
+            ctx.data().remove::<Sources>(sources_id);
+        }
+
+        open
+    }
+
+    /// Fetch the dependencies of a tab again next time they're shown, e.g. after a build which may have
+    /// resolved them differently
+    pub fn refresh(ctx: &egui::Context, id: Id) {
+        let sources_id = Id::new(CRATE_SOURCES).with(id);
+
+        // unless they're being fetched right now
+        if let Some(Some(_)) = ctx.data().get_temp::<Sources>(sources_id) {
+            ctx.data().remove::<Sources>(sources_id);
+        }
+    }
+
+    // the `//#` and `//>` lines, and which of them are ignored for being below the code
+    fn show_directives(ui: &mut Ui, sources_id: Id, directives: &[(String, bool)]) {
+        let ignored = directives.iter().filter(|(_, read)| !read).count();
+
+        CollapsingHeader::new(format!("Directives ({})", directives.len() - ignored))
+            .id_source(sources_id.with("directives"))
+            .default_open(ignored > 0)
+            .show(ui, |ui| {
+                if directives.is_empty() {
+                    ui.label("The scratch has no //# or //> lines");
+                }
+
+                for (label, read) in directives {
+                    if *read {
+                        ui.monospace(label);
+                    } else {
+                        ui.colored_label(ui.visuals().warn_fg_color, format!("{label} (ignored)"));
+                    }
+                }
+            });
+
+        if ignored > 0 {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "Directives below the code are ignored, move them above it",
+            );
+        }
+
+        ui.separator();
+    }
+
+    fn features_label(source: &CrateSource) -> String {
+        let mut features = source.features.clone();
+        if source.default_features {
+            features.insert(0, "default".to_string());
+        }
+
+        if features.is_empty() {
+            "No features".to_string()
+        } else {
+            format!("Features: {}", features.join(", "))
+        }
+    }
+
+    fn cargo_toml(sources: &[CrateSource]) -> String {
+        let mut toml = "[dependencies]\n".to_string();
+
+        for source in sources {
+            toml.push_str(&source.cargo_toml());
+            toml.push('\n');
+        }
+
+        toml
+    }
+
+    fn load(ctx: &egui::Context, sources_id: Id, code: String, base_dir: Option<PathBuf>) {
+        ctx.data().insert_temp::<Sources>(sources_id, None);
+
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = Self::metadata(&code, base_dir.as_deref()).map(Arc::new);
+
+            ctx.data().insert_temp::<Sources>(sources_id, Some(result));
+            ctx.request_repaint();
+        });
+    }
+
+    // a project of its own, cargo metadata mustn't rewrite the manifest of a build in progress
+    fn metadata(code: &str, base_dir: Option<&Path>) -> Result<Vec<CrateSource>, String> {
+        let mut project = Project::new("crate_sources");
+
+        if let Some(base_dir) = base_dir {
+            project.base_dir(base_dir);
+        }
+
+        project
+            .file(File::new("main", code))
+            .edition(Edition::E2021)
+            .target_prefix("rust-play")
+            .metadata();
+
+        let output = project
+            .create()
+            .and_then(|mut command| Ok(command.output()?))
+            .map_err(|e| e.to_string())?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+
+        Ok(crate_sources(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    // listed once per crate, instead of reading the directories every frame
+    fn files(ctx: &egui::Context, source: &CrateSource) -> Arc<Vec<PathBuf>> {
+        let files_id = Id::new(CRATE_SOURCES).with(&source.dir);
+
+        let files = ctx.data().get_temp::<Arc<Vec<PathBuf>>>(files_id);
+        files.unwrap_or_else(|| {
+            let files = Arc::new(source.files());
+            ctx.data().insert_temp(files_id, files.clone());
+
+            files
+        })
+    }
+}
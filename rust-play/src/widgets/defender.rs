@@ -0,0 +1,312 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{channel, TryRecvError};
+use std::thread;
+
+use cargo_player::projects_dir;
+use egui::{vec2, Align2, Button, Id, RichText, Window};
+
+#[cfg(target_os = "windows")]
+use {std::os::windows::process::CommandExt, windows::Win32::System::Threading::CREATE_NO_WINDOW};
+
+use crate::config::{Config, Defender};
+
+/// Temp memory key of the last slow build a run saw, as its seconds and the crates it compiled
+pub const SLOW_BUILD: &str = "defender_slow_build";
+
+// seconds a crate takes on average before a build counts as slow. A scratch compiles in a few, scanning
+// every file rustc writes easily takes several times as long
+const SLOW_CRATE_SECS: f32 = 8.0;
+
+/// Follows the status lines cargo prints, to tell how long a build took for the crates it compiled
+#[derive(Default)]
+pub struct BuildClock {
+    compiled: usize,
+    secs: Option<f32>,
+}
+
+impl BuildClock {
+    pub fn line(&mut self, line: &str) {
+        let line = strip_ansi_escapes::strip(line).unwrap_or_default();
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim();
+
+        if line.starts_with("Compiling ") {
+            self.compiled += 1;
+        } else if let Some(finished) = line.strip_prefix("Finished ") {
+            // e.g. `dev [unoptimized + debuginfo] target(s) in 1m 12s`
+            self.secs = finished
+                .rsplit_once(" in ")
+                .and_then(|(_, duration)| parse_duration(duration));
+        }
+    }
+
+    /// The seconds and crates of the build if it was slow. Nothing compiled is never slow
+    pub fn slow(&self) -> Option<(f32, usize)> {
+        let secs = self.secs?;
+
+        (self.compiled > 0 && secs / self.compiled as f32 >= SLOW_CRATE_SECS)
+            .then_some((secs, self.compiled))
+    }
+}
+
+// cargo's `12.34s` or `1m 05s`
+fn parse_duration(duration: &str) -> Option<f32> {
+    duration.split_whitespace().try_fold(0.0, |total, part| {
+        if let Some(minutes) = part.strip_suffix('m') {
+            Some(total + minutes.parse::<f32>().ok()? * 60.0)
+        } else {
+            Some(total + part.strip_suffix('s')?.parse::<f32>().ok()?)
+        }
+    })
+}
+
+pub struct DefenderHint;
+
+impl DefenderHint {
+    /// After a build slow enough to be antivirus scanning, explain it and offer to exclude the projects
+    /// folder from Windows Defender. Nothing is changed without the user asking for it
+    pub fn show(ctx: &egui::Context, config: &mut Config) {
+        Self::pick_up(ctx, config);
+
+        let defender = &mut config.defender;
+
+        if let Some(adding) = &defender.adding {
+            match adding.try_recv() {
+                Ok(added) => {
+                    // later slow builds aren't the scanning then
+                    if added.is_ok() {
+                        config.general.defender_hint = false;
+                    }
+
+                    defender.added = Some(added);
+                    defender.adding = None;
+                }
+
+                Err(TryRecvError::Disconnected) => defender.adding = None,
+
+                Err(TryRecvError::Empty) => (),
+            }
+        }
+
+        if !defender.open {
+            return;
+        }
+
+        let dir = config.general.projects_dir().unwrap_or_else(projects_dir);
+
+        let mut open = true;
+        let mut close = false;
+
+        Window::new("Slow builds")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(450.0)
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                let defender = &mut config.defender;
+
+                if let Some((secs, crates)) = defender.slow_build {
+                    let crates = match crates {
+                        1 => "1 crate".to_string(),
+                        n => format!("{n} crates"),
+                    };
+
+                    ui.label(format!("The last build took {secs:.0}s for {crates}."));
+                }
+
+                ui.label(
+                    "Windows Defender scanning every file the compiler writes is a common cause of \
+                     slow builds. It can be told to skip the folder the projects are built in:",
+                );
+                ui.label(RichText::new(dir.display().to_string()).monospace());
+
+                ui.add_space(4.0);
+
+                ui.label(
+                    "Nothing in the folder is scanned anymore then, including what the code you run \
+                     writes there. Adding the exclusion runs this as administrator, Windows asks for \
+                     permission first:",
+                );
+                ui.label(RichText::new(exclusion_command(&dir)).monospace());
+
+                match &defender.added {
+                    Some(Ok(())) => {
+                        ui.label("The folder is excluded now.");
+                    }
+
+                    Some(Err(e)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+
+                    None => (),
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let adding = defender.adding.is_some();
+                    let added = matches!(defender.added, Some(Ok(())));
+
+                    if ui
+                        .add_enabled(!adding && !added, Button::new("Add exclusion"))
+                        .clicked()
+                    {
+                        Self::add(ctx, defender, dir.clone());
+                    }
+
+                    if adding {
+                        ui.spinner();
+                    }
+
+                    if ui.button("Not now").clicked() {
+                        close = true;
+                    }
+
+                    if ui
+                        .button("Don't show again")
+                        .on_hover_text("It can still be opened from the disk usage settings")
+                        .clicked()
+                    {
+                        config.general.defender_hint = false;
+                        close = true;
+                    }
+                });
+            });
+
+        if !open || close {
+            config.defender.open = false;
+        }
+    }
+
+    /// Open the hint without a slow build, e.g. from the settings
+    pub fn open(config: &mut Config) {
+        let defender = &mut config.defender;
+
+        defender.open = true;
+        defender.slow_build = None;
+        defender.added = None;
+    }
+
+    // the slow build a run left behind. It's dropped on other platforms, or when the hint is turned off
+    fn pick_up(ctx: &egui::Context, config: &mut Config) {
+        let id = Id::new(SLOW_BUILD);
+
+        let slow = ctx.data().get_temp::<(f32, usize)>(id);
+        let Some(slow) = slow else {
+            return;
+        };
+
+        ctx.data().remove::<(f32, usize)>(id);
+
+        if !cfg!(target_os = "windows") || !config.general.defender_hint || config.defender.open {
+            return;
+        }
+
+        Self::open(config);
+        config.defender.slow_build = Some(slow);
+    }
+
+    fn add(ctx: &egui::Context, defender: &mut Defender, dir: PathBuf) {
+        let (tx, rx) = channel();
+        defender.adding = Some(rx);
+        defender.added = None;
+
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let _ = tx.send(add_exclusion(&dir));
+
+            ctx.request_repaint();
+        });
+    }
+}
+
+fn exclusion_command(dir: &Path) -> String {
+    let dir = dir.display().to_string().replace('\'', "''");
+
+    format!("Add-MpPreference -ExclusionPath '{dir}'")
+}
+
+// Add-MpPreference needs an administrator, so a second powershell is started elevated and waited on.
+// Declining the permission prompt fails Start-Process
+fn add_exclusion(dir: &Path) -> Result<(), String> {
+    let script = format!(
+        "$p = Start-Process powershell -Verb RunAs -Wait -PassThru -WindowStyle Hidden \
+         -ArgumentList '-NoProfile','-NonInteractive','-EncodedCommand','{}'; exit $p.ExitCode",
+        encode_command(&exclusion_command(dir))
+    );
+
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+
+    // hide the console window from command
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW.0);
+
+    let output = command
+        .output()
+        .map_err(|e| format!("Couldn't start powershell: {e}"))?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    match stderr.lines().map(str::trim).find(|line| !line.is_empty()) {
+        Some(line) => Err(line.to_string()),
+        None => Err("The exclusion wasn't added".to_string()),
+    }
+}
+
+// base64 of the script as UTF-16 LE, which -EncodedCommand takes. The path doesn't need quoting through
+// both shells then
+fn encode_command(script: &str) -> String {
+    let bytes = script
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+
+    base64::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("12.34s"), Some(12.34));
+        assert_eq!(parse_duration("1m 05s"), Some(65.0));
+        assert_eq!(parse_duration("2m"), Some(120.0));
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("1h"), None);
+    }
+
+    #[test]
+    fn slow_builds() {
+        let mut clock = BuildClock::default();
+        clock.line("   Compiling serde v1.0.152");
+        clock.line("\x1b[1m\x1b[32m   Compiling\x1b[0m p123 v0.1.0");
+        clock.line("    Finished dev [unoptimized + debuginfo] target(s) in 1m 12s");
+        assert_eq!(clock.slow(), Some((72.0, 2)));
+
+        let mut clock = BuildClock::default();
+        clock.line("   Compiling p123 v0.1.0");
+        clock.line("    Finished dev [unoptimized + debuginfo] target(s) in 2.50s");
+        assert_eq!(clock.slow(), None);
+
+        // nothing compiled, e.g. only the run
+        let mut clock = BuildClock::default();
+        clock.line("    Finished dev [unoptimized + debuginfo] target(s) in 30.00s");
+        assert_eq!(clock.slow(), None);
+    }
+
+    #[test]
+    fn encoded_commands() {
+        // as powershell's [Convert]::ToBase64String([Text.Encoding]::Unicode.GetBytes('dir'))
+        assert_eq!(encode_command("dir"), "ZABpAHIA");
+    }
+}
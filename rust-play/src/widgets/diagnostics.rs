@@ -0,0 +1,191 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use toml::Value;
+
+use crate::config::{panic_log_file, Config};
+use crate::utils::toolchain;
+use crate::utils::zip::ZipWriter;
+
+use super::toasts::Toast;
+
+// files read from the disk are cut down to their end, the latest is what matters
+const MAX_FILE_LEN: usize = 64 * 1024;
+// words in the name of a setting or an environment variable which make its value a secret,
+// e.g. access_token or API_KEY
+const SECRET_WORDS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "key",
+    "auth",
+    "credential",
+    "credentials",
+];
+const REDACTED: &str = "<redacted>";
+const MIN_SECRET_LEN: usize = 4;
+
+pub struct DiagnosticBundle;
+
+impl DiagnosticBundle {
+    /// Zip what a bug report needs into a file the user picks: the settings without their secrets, the
+    /// panics so far, the OS and toolchain, and the last output and Cargo.toml of the focused tab
+    pub fn create(ctx: &egui::Context, config: &mut Config) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Zip", &["zip"])
+            .set_file_name("rust-play-diagnostics.zip")
+            .save_file()
+        else {
+            return;
+        };
+
+        let mut secrets = vec![];
+
+        let settings = match Value::try_from(&*config) {
+            Ok(mut settings) => {
+                scrub(&mut settings, &mut secrets);
+                toml::to_string_pretty(&settings).unwrap_or_default()
+            }
+
+            Err(e) => format!("Failed to write the settings: {e}"),
+        };
+
+        let mut files = vec![("settings.toml", settings)];
+        let mut cargo_toml = None;
+
+        let base_dir = config.general.projects_dir();
+
+        if let Some((_, tab)) = config.dock.tree.find_active() {
+            // whatever their name, e.g. a key the scratch reads and may print
+            for (_, value) in &tab.env.vars {
+                secrets.push(value.clone());
+            }
+
+            let project = tab.build.project(base_dir.as_deref());
+            cargo_toml = Some(project.project_dir().join("Cargo.toml"));
+
+            if let Some(output) = config.terminal.output.get(&tab.id) {
+                let output = format!(
+                    "Standard Error\n\n{}\n\nStandard Output\n\n{}",
+                    tail(output.stderr.text()),
+                    tail(output.stdout.text())
+                );
+
+                files.push(("last_output.txt", output));
+            }
+        }
+
+        let ctx = ctx.clone();
+
+        // running rustc and cargo can take a moment
+        thread::spawn(move || {
+            files.push(("system.txt", system_info()));
+            files.push(("panic.log", read_tail(&panic_log_file())));
+
+            if let Some(cargo_toml) = cargo_toml {
+                files.push(("Cargo.toml", read_tail(&cargo_toml)));
+            }
+
+            let mut zip = ZipWriter::default();
+
+            for (name, contents) in &files {
+                let mut contents = contents.clone();
+
+                // e.g. a token a scratch printed. A value this short would replace much more than itself
+                for secret in secrets
+                    .iter()
+                    .filter(|secret| secret.len() >= MIN_SECRET_LEN)
+                {
+                    contents = contents.replace(secret.as_str(), REDACTED);
+                }
+
+                zip.file(name, contents.as_bytes());
+            }
+
+            let toast = match fs::write(&path, zip.finish()) {
+                Ok(()) => Toast::success(format!(
+                    "Saved the diagnostic bundle to {}, attach it to the issue",
+                    path.display()
+                )),
+                Err(e) => Toast::error(format!("Failed to save the diagnostic bundle: {e}")),
+            };
+
+            toast.push(&ctx);
+        });
+    }
+}
+
+// replace the values of settings with a secret name, and of environment variables stored as
+// [name, value] pairs, keeping what was replaced
+fn scrub(value: &mut Value, secrets: &mut Vec<String>) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                match value {
+                    Value::String(secret) if is_secret(key) => redact(secret, secrets),
+                    value => scrub(value, secrets),
+                }
+            }
+        }
+
+        Value::Array(array) => {
+            if let [Value::String(name), Value::String(secret)] = array.as_mut_slice() {
+                if is_secret(name) {
+                    redact(secret, secrets);
+                }
+
+                return;
+            }
+
+            for value in array {
+                scrub(value, secrets);
+            }
+        }
+
+        _ => (),
+    }
+}
+
+fn redact(value: &mut String, secrets: &mut Vec<String>) {
+    if !value.is_empty() {
+        secrets.push(std::mem::replace(value, REDACTED.to_string()));
+    }
+}
+
+fn is_secret(name: &str) -> bool {
+    name.split(['_', '-', '.'])
+        .any(|word| SECRET_WORDS.contains(&word.to_lowercase().as_str()))
+}
+
+fn system_info() -> String {
+    let tool = |version: Option<String>| version.unwrap_or_else(|| "not found".to_string());
+
+    format!(
+        "Rust Play {}\nOS: {} {}\nrustc: {}\ncargo: {}\nrustup: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        tool(toolchain::version("rustc")),
+        tool(toolchain::version("cargo")),
+        tool(toolchain::version("rustup")),
+    )
+}
+
+fn read_tail(path: &Path) -> String {
+    match fs::read_to_string(path) {
+        Ok(text) => tail(&text).to_string(),
+        Err(e) => format!("{} couldn't be read: {e}", path.display()),
+    }
+}
+
+fn tail(text: &str) -> &str {
+    let mut start = text.len().saturating_sub(MAX_FILE_LEN);
+
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+
+    &text[start..]
+}
@@ -0,0 +1,189 @@
+use std::path::Path;
+use std::sync::mpsc::{channel, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cargo_player::{clean_projects, prepare_base_dir, project_usage, projects_dir};
+use egui::{Button, DragValue, Id, TextEdit, Ui};
+
+use crate::config::{Config, Measurement};
+
+use super::binary_size::format_size;
+use super::defender::DefenderHint;
+use super::toasts::Toast;
+
+// builds add up slowly, there's no need to walk the projects more often
+const MEASURE_INTERVAL: Duration = Duration::from_secs(60);
+const MIB: u64 = 1024 * 1024;
+
+// temp memory keys of the projects folder being typed in the settings, and why it can't be used
+const PROJECTS_DIR_EDIT: &str = "disk_usage_projects_dir_edit";
+const PROJECTS_DIR_ERROR: &str = "disk_usage_projects_dir_error";
+
+pub struct DiskUsageMeter;
+
+impl DiskUsageMeter {
+    /// Measure the disk the generated projects use every so often, on another thread. Once they're over
+    /// the quota, the build output of the least recently built ones is removed, unless something is running
+    pub fn update(ctx: &egui::Context, config: &mut Config) {
+        let usage = &mut config.disk_usage;
+
+        if let Some(measuring) = &usage.measuring {
+            match measuring.try_recv() {
+                Ok(measurement) => {
+                    usage.bytes = Some(measurement.bytes);
+                    usage.measuring = None;
+
+                    if measurement.freed > 0 {
+                        Toast::info(format!(
+                            "Removed {} of old build output, the generated projects were over their quota",
+                            format_size(measurement.freed)
+                        ))
+                        .push(ctx);
+                    }
+                }
+
+                Err(TryRecvError::Disconnected) => usage.measuring = None,
+
+                Err(TryRecvError::Empty) => (),
+            }
+
+            return;
+        }
+
+        if usage
+            .measured
+            .map_or(false, |measured| measured.elapsed() < MEASURE_INTERVAL)
+        {
+            return;
+        }
+
+        // a running build would lose its output halfway through
+        let quota = (config.general.disk_quota > 0 && config.terminal.abortable.is_empty())
+            .then_some(config.general.disk_quota * MIB);
+
+        let dir = config.general.projects_dir().unwrap_or_else(projects_dir);
+
+        let (tx, rx) = channel();
+        usage.measuring = Some(rx);
+        usage.measured = Some(Instant::now());
+
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let projects = project_usage(&dir);
+            let mut bytes = projects.iter().map(|project| project.bytes).sum::<u64>();
+            let mut freed = 0;
+
+            if let Some(quota) = quota.filter(|quota| bytes > *quota) {
+                freed = clean_projects(&projects, quota);
+                bytes = bytes.saturating_sub(freed);
+            }
+
+            let _ = tx.send(Measurement { bytes, freed });
+
+            ctx.request_repaint();
+        });
+    }
+
+    /// The disk usage in a tab's status bar
+    pub fn status(ui: &mut Ui, bytes: u64) {
+        ui.weak(format_size(bytes))
+            .on_hover_text("Disk used by the generated projects and their builds");
+    }
+
+    /// The disk usage and the quota, in the settings menu
+    pub fn settings(ui: &mut Ui, config: &mut Config) {
+        let usage = match config.disk_usage.bytes {
+            Some(bytes) => format_size(bytes),
+            None => "not measured yet".to_string(),
+        };
+
+        ui.label(format!("Generated projects use {usage}"));
+
+        ui.horizontal(|ui| {
+            ui.label("Quota");
+            ui.add(
+                DragValue::new(&mut config.general.disk_quota)
+                    .clamp_range(0..=u32::MAX)
+                    .speed(64.0)
+                    .suffix(" MiB"),
+            )
+            .on_hover_text(
+                "Over this, the build output of the least recently built projects is removed. 0 disables it",
+            );
+        });
+
+        let busy = config.disk_usage.measuring.is_some();
+        if ui
+            .add_enabled(!busy, Button::new("Measure again"))
+            .clicked()
+        {
+            config.disk_usage.measured = None;
+        }
+
+        ui.separator();
+
+        Self::projects_dir_setting(ui, config);
+
+        if cfg!(target_os = "windows")
+            && ui
+                .button("Exclude from Windows Defender...")
+                .on_hover_text("Antivirus scanning slows builds down a lot")
+                .clicked()
+        {
+            DefenderHint::open(config);
+            ui.close_menu();
+        }
+    }
+
+    // the folder the projects are generated in, e.g. on a faster disk. It's only taken once it could be
+    // created and written to
+    fn projects_dir_setting(ui: &mut Ui, config: &mut Config) {
+        let ctx = ui.ctx().clone();
+        let edit_id = Id::new(PROJECTS_DIR_EDIT);
+        let error_id = Id::new(PROJECTS_DIR_ERROR);
+
+        let mut edit = ctx
+            .data()
+            .get_temp::<String>(edit_id)
+            .unwrap_or_else(|| config.general.projects_dir.clone());
+
+        ui.label("Projects folder");
+
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut edit)
+                    .hint_text(projects_dir().display().to_string())
+                    .desired_width(250.0),
+            );
+
+            if ui.button("Apply").clicked() {
+                let dir = edit.trim();
+
+                // empty goes back to the temp directory
+                let result = match dir.is_empty() {
+                    true => Ok(()),
+                    false => prepare_base_dir(Path::new(dir)).map_err(|e| e.to_string()),
+                };
+
+                match result {
+                    Ok(()) => {
+                        config.general.set_projects_dir(dir);
+                        config.disk_usage.measured = None;
+                        ctx.data().remove::<String>(error_id);
+                    }
+
+                    Err(e) => ctx.data().insert_temp(error_id, e),
+                }
+            }
+        });
+
+        let error = ctx.data().get_temp::<String>(error_id);
+        if let Some(error) = error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        ctx.data().insert_temp(edit_id, edit);
+    }
+}
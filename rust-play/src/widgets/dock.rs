@@ -1,496 +1,4144 @@
 use rand::Rng;
-use std::borrow::Cow;
-use std::io::{BufRead, BufReader, Read};
-use std::process::Stdio;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Sender};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Once};
 use std::thread;
+use std::time::{Duration, Instant};
 use windows::Win32::System::Threading::CREATE_NO_WINDOW;
 
-use ringbuf::HeapRb;
+use ringbuf::{HeapProducer, HeapRb};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
-use cargo_player::{BuildType, Channel, Edition, File, Project, Subcommand};
-use egui::{vec2, Align2, Color32, Id, Ui, Vec2, Window};
-use egui_dock::{DockArea, Node, NodeIndex, Style, TabAddAlign};
+use cargo_player::{
+    bloat_report, directives, format_directives, fuzzable_functions, host_target, insert_proptest,
+    proptest_functions, suggest_name, validate_name, AsyncRuntime, Backtrace, BuildType, Channel,
+    Edition, File, FuzzFn, FuzzInput, Killer, Line, Process, Project, ProjectError, Sanitizer,
+    Subcommand, WASM_TARGET,
+};
+use egui::text::LayoutJob;
+use egui::{
+    vec2, Align, Align2, Button, Color32, ComboBox, DragValue, Frame, Grid, Id, Key, Layout,
+    Modifiers, PointerButton, RichText, ScrollArea, TextEdit, TextFormat, TextStyle, Ui, Vec2,
+    Window,
+};
+use egui_dock::{Node, NodeIndex, Style, TabAddAlign, TabIndex};
+use once_cell::sync::OnceCell;
+use reqwest::Url;
+use rustplay_widgets::code_editor::{
+    line_ending_picker, syntax_theme_picker, zoom_input, CodeEditor, CodeTheme, EditorSettings,
+    SyntectTheme,
+};
+use rustplay_widgets::dock::{DockTab, DockView, DoubleClicked};
+use rustplay_widgets::vim::VimCommand;
 use serde::{Deserialize, Serialize};
-use smallvec::SmallVec;
 
-use crate::config::{Command, Config, GitHub, MenuCommand, TabCommand};
+use crate::config::{
+    binary_sizes, load_drafts, load_templates, load_workspace, load_workspaces, record_binary_size,
+    record_run, save_workspace, templates_dir, Analyzer, AnsiColors, CheckHints, Command, Config,
+    DockConfig, DoubleClick, Draft, EnvPreset, GeneralConfig, GitHub, MenuCommand, Presets,
+    RunAgain, TabCommand, Template, Terminal, TerminalSettings, Toolchains,
+};
+use crate::ipc;
+use crate::utils::clipboard;
 use crate::utils::data::Data;
+use crate::utils::encoding::Encoding;
+use crate::utils::output_assertion::OutputAssertion;
+use crate::utils::playground::{PlaygroundError, PlaygroundRun};
+use crate::utils::serve;
+use crate::utils::std_source::{self, Definition};
+use crate::utils::toolchain::{self, CHANNELS};
 
-use super::code_editor::CodeEditor;
-use super::titlebar::TITLEBAR_HEIGHT;
+use super::about::About;
+use super::add_dependency::DependencyDialog;
+use super::analyzer::RustAnalyzer;
+use super::binary_size::{BinarySizeViewer, SizeView, SIZE_VIEW, SIZE_VIEW_PENDING};
+use super::command_palette::{CommandPalette, PaletteCommand};
+use super::compiler_hints::CompilerHints;
+use super::crate_sources::CrateSourceBrowser;
+use super::defender::{BuildClock, SLOW_BUILD};
+use super::disk_usage::DiskUsageMeter;
+use super::gists::{GistBrowser, GistExporter};
+use super::hex_viewer::{HexViewer, BINARY_OUTPUT};
+use super::lints::LINT_RUN;
+use super::output_export::OutputExport;
+use super::pager::OutputPager;
+use super::presets::PresetEditor;
+use super::project_export::ProjectExport;
+use super::quick_run::QuickRunPopup;
+use super::setup::MISSING_TOOL;
+use super::terminal::{self, FUZZ_FUNCTION, MIRI_RUN, QUIET_RUN};
+use super::titlebar::{MENUBAR_HEIGHT, TITLEBAR_HEIGHT};
+use super::toasts::{Toast, ToastAction};
+use super::toolchains::ToolchainManager;
 
 pub type Tree = egui_dock::Tree<Tab>;
 
+// dropping the sender signals the process to abort
+type Aborter = Arc<Mutex<Sender<()>>>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum RunMode {
+    Native,
+    // the tests run with cargo test
+    Test,
+    // built for wasm and opened in the browser
+    Web,
+    Sanitized(Sanitizer),
+    // model checked with loom through cargo test
+    Loom,
+    // interpreted by Miri, which reports undefined behavior
+    Miri,
+    // linted by clippy, which prints its lints as json
+    Clippy,
+    // a function fuzzed with cargo-fuzz, or the fuzz target run once with a saved input
+    Fuzz {
+        function: FuzzFn,
+        replay: Option<PathBuf>,
+    },
+    // built in release and measured with cargo-bloat
+    Bloat,
+}
+
+// temp memory key holding the rustfmt result of a tab, None while it's running
+const FORMAT_RESULT: &str = "dock_format_result";
+// temp memory key holding where the std item under a tab's cursor is defined, None while it's searched for
+const STD_SOURCE: &str = "dock_std_source";
+// temp memory key set once the name suggested for a tab was offered, the tab isn't asked again
+const NAME_SUGGESTED: &str = "dock_name_suggested";
+// temp memory key holding the hash of the code a tab was last warned about ignored directives in
+const IGNORED_DIRECTIVES: &str = "dock_ignored_directives";
+// width either side of a tab's split view keeps
+const MIN_SPLIT_WIDTH: f32 = 150.0;
+// how many closed tabs can be reopened
+const MAX_CLOSED_TABS: usize = 10;
+// stdout kept around for the hex viewer, anything beyond is cut off
+const MAX_BINARY_OUTPUT: usize = 4 * 1024 * 1024;
+// runs taking at least this long raise a toast when they're done, they were likely left in the background
+const LONG_RUN: Duration = Duration::from_secs(5);
+// lines of output waiting for the terminal to take them, per stream
+const OUTPUT_QUEUE_LEN: usize = 1024;
+// how long a run waits for room in a full output queue before checking again
+const OUTPUT_WAIT: Duration = Duration::from_millis(2);
+// offered for a tab's label, any other can be typed
+const LABEL_EMOJIS: [&str; 8] = ["🐛", "✔", "❌", "⚠", "🔥", "⭐", "🚧", "💡"];
+// a label is a mark, not a second name
+const MAX_LABEL_CHARS: usize = 4;
+// the project of the scratches, all tabs currently share the one
+const SCRATCH_PROJECT: &str = "continuous_mode";
+// longer playground links with the code in them get cut off by browsers and chat apps, those are shared
+// as a gist instead
+const MAX_SHARE_URL_LEN: usize = 8000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tab {
     pub name: String,
     pub editor: CodeEditor,
     pub id: Id,
     scroll_offset: Option<Vec2>,
+    #[serde(default)]
+    pub watch: Watch,
+    #[serde(default)]
+    pub env: Env,
+    #[serde(default)]
+    pub build: BuildOptions,
+    // name of the environment preset applied underneath env and build
+    #[serde(default)]
+    pub preset: Option<String>,
+    // overrides the global syntax theme for just this tab
+    #[serde(default)]
+    pub theme: Option<SyntectTheme>,
+    // the editor's zoom, None uses the global default
+    #[serde(default)]
+    pub zoom: Option<f32>,
+    // a color or emoji in front of the title
+    #[serde(default)]
+    pub label: Option<TabLabel>,
+    // show the output to the right of the editor, instead of only in the terminal
+    #[serde(default)]
+    pub split: bool,
+    // the encoding of the file the tab was opened from, if it wasn't UTF-8
+    #[serde(default)]
+    pub encoding: Option<Encoding>,
+    // save back in that encoding instead of converting the file to UTF-8
+    #[serde(default)]
+    pub keep_encoding: bool,
+    // the file a read-only tab shows, e.g. of a dependency's source
+    #[serde(default)]
+    pub source: Option<PathBuf>,
+    // the draft this tab autosaves to, kept when the tab is saved in a workspace
+    #[serde(default)]
+    pub draft_key: u64,
+    // hash of the name and code last written to the draft, so unchanged tabs aren't rewritten
+    #[serde(skip)]
+    draft_hash: u64,
+    // hash of the code when it was last opened or saved to disk
+    #[serde(default)]
+    saved_hash: u64,
+    // a name was offered from the code after a run, it's only offered once
+    #[serde(default)]
+    name_suggested: bool,
 }
 
-pub trait TreeTabs
-where
-    Self: Sized,
-{
-    fn init() -> Self;
+/// A mark in front of a tab's title, to tell scratches apart at a glance
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TabLabel {
+    Color(LabelColor),
+    Emoji(String),
 }
 
-// Initialize the initial tabs / tab data
-impl TreeTabs for Tree {
-    fn init() -> Self {
-        let tab = Tab {
-            name: "Scratch 1".to_string(),
-            editor: CodeEditor::default(),
-            id: Id::new("Scratch 1"),
-            scroll_offset: None,
-        };
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LabelColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
 
-        let mut tree = Tree::new(vec![tab]);
-        tree.set_focused_node(NodeIndex::root());
-        tree
+impl LabelColor {
+    pub const ALL: [Self; 6] = [
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Green,
+        Self::Blue,
+        Self::Purple,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Red => "Red",
+            Self::Orange => "Orange",
+            Self::Yellow => "Yellow",
+            Self::Green => "Green",
+            Self::Blue => "Blue",
+            Self::Purple => "Purple",
+        }
+    }
+
+    // readable on both the light and the dark theme
+    pub fn color(self) -> Color32 {
+        match self {
+            Self::Red => Color32::from_rgb(0xe0, 0x4f, 0x4f),
+            Self::Orange => Color32::from_rgb(0xe8, 0x8a, 0x2e),
+            Self::Yellow => Color32::from_rgb(0xd6, 0xb5, 0x1e),
+            Self::Green => Color32::from_rgb(0x4c, 0xb0, 0x5a),
+            Self::Blue => Color32::from_rgb(0x3f, 0x8c, 0xe0),
+            Self::Purple => Color32::from_rgb(0x9a, 0x62, 0xd8),
+        }
     }
 }
 
-pub struct Dock;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Watch {
+    // re-run the scratch every interval
+    pub enabled: bool,
+    // in seconds
+    pub interval: u64,
+    // regex matched against each line of output, raises a notification on match
+    pub pattern: String,
+    #[serde(skip)]
+    last_run: Option<Instant>,
+}
 
-impl Dock {
-    pub fn show(ctx: &egui::Context, config: &mut Config, ui: &mut Ui) {
-        let tree = &mut config.dock.tree;
+impl Default for Watch {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 60,
+            pattern: String::new(),
+            last_run: None,
+        }
+    }
+}
 
-        let mut style = Style::from_egui(ctx.style().as_ref());
+// environment variables the scratch is run with
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Env {
+    pub vars: Vec<(String, String)>,
+    pub backtrace: Backtrace,
+    // empty leaves RUST_LOG unset
+    pub rust_log: String,
+}
 
-        // important, otherwise it'll draw over the original titlebar
-        style.tab_bar_background_color = Color32::TRANSPARENT;
-        style.tab_bar_height = TITLEBAR_HEIGHT as f32 / 2.0;
-        style.tabs_are_draggable = true;
-        style.tab_include_scrollarea = false;
-        style.show_add_buttons = true;
-        style.add_tab_align = TabAddAlign::Left;
-        style.show_context_menu = true;
+impl Env {
+    /// The preset's variables followed by the tab's own, so the tab's win
+    fn with_preset(&self, preset: &EnvPreset) -> Self {
+        Self {
+            vars: preset.vars.iter().chain(&self.vars).cloned().collect(),
+            ..self.clone()
+        }
+    }
 
-        let tab_data = TabData::new();
+    /// Pass the variables on to the project. Explicit entries take priority over the toggles
+    pub fn apply<'a>(&'a self, project: &mut Project<'a>) {
+        project.backtrace(self.backtrace);
+
+        if !self.rust_log.is_empty() {
+            project.env_var("RUST_LOG", &self.rust_log);
+        }
+
+        for (var, val) in &self.vars {
+            if !var.is_empty() {
+                project.env_var(var, val);
+            }
+        }
+    }
 
-        let mut tab_viewer = TabViewer::new(ctx, &tab_data);
+    fn show(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("RUST_BACKTRACE");
+            ui.radio_value(&mut self.backtrace, Backtrace::None, "None");
+            ui.radio_value(&mut self.backtrace, Backtrace::Short, "Short");
+            ui.radio_value(&mut self.backtrace, Backtrace::Full, "Full");
+        });
 
-        DockArea::new(tree)
-            .style(style)
-            .show_inside(ui, &mut tab_viewer);
+        ui.horizontal(|ui| {
+            ui.label("RUST_LOG");
+            ui.add(
+                TextEdit::singleline(&mut self.rust_log)
+                    .hint_text("e.g. debug")
+                    .desired_width(120.0),
+            );
+        });
 
-        // keep the terminal active display on the selected tab
-        if let Some((_, tab)) = tree.find_active() {
-            config.terminal.active_tab = Some(tab.id);
+        ui.separator();
+
+        let mut remove = None;
+
+        Grid::new("env_vars").num_columns(3).show(ui, |ui| {
+            for (i, (var, val)) in self.vars.iter_mut().enumerate() {
+                ui.add(
+                    TextEdit::singleline(var)
+                        .hint_text("NAME")
+                        .desired_width(120.0),
+                );
+                ui.add(
+                    TextEdit::singleline(val)
+                        .hint_text("value")
+                        .desired_width(180.0),
+                );
+
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = remove {
+            self.vars.remove(i);
         }
 
-        // add data to command vec
-        config
-            .dock
-            .commands
-            .extend_from_slice(tab_data.borrow().as_slice());
+        if ui.button("Add variable").clicked() {
+            self.vars.push(Default::default());
+        }
     }
 }
 
-type TabData = Data<Command>;
+// where a tab's scratch is built and run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    #[default]
+    Local,
+    // the official playground, for machines without a toolchain or comparing against it
+    Playground,
+}
 
-struct TabViewer<'a> {
-    _ctx: &'a egui::Context,
-    data: &'a TabData,
+/// One step of a run in the terminal, a process or the code sent to the playground
+pub enum RunStep {
+    Process(std::process::Command),
+    Playground(PlaygroundRun),
 }
 
-impl<'a> TabViewer<'a> {
-    fn new(ctx: &'a egui::Context, data: &'a TabData) -> Self {
-        Self { _ctx: ctx, data }
+impl From<std::process::Command> for RunStep {
+    fn from(command: std::process::Command) -> Self {
+        Self::Process(command)
     }
 }
 
-impl egui_dock::TabViewer for TabViewer<'_> {
-    type Tab = Tab;
+// advanced cargo options the scratch is run with
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildOptions {
+    pub channel: Channel,
+    // e.g. -Zsanitizer=address
+    pub rust_flags: String,
+    // space or comma separated, dependency features can be enabled with dep/feature
+    pub features: String,
+    pub no_default_features: bool,
+    // bounds loom's search, 0 leaves it unbounded
+    pub loom_max_preemptions: u8,
+    // passed to Miri in MIRIFLAGS, e.g. -Zmiri-disable-isolation
+    pub miri_flags: String,
+    // lint levels clippy runs with, e.g. -W clippy::pedantic -A clippy::needless_return
+    pub lint_levels: String,
+    // the generated project's folder is named <prefix>.<id>, empty uses rust-play
+    pub target_prefix: String,
+    // name of the package and the binary it builds, empty uses p<id>
+    pub package_name: String,
+    // put the statements of a scratch without a main in one, like the playground does
+    pub wrap_main: bool,
+    // what an async fn main runs on, its dependency and attribute are added for it
+    pub async_runtime: AsyncRuntime,
+    // runs and tests can go to the playground, everything else needs the local toolchain
+    pub backend: Backend,
+}
 
-    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
-        // multiple tabs may be open on the screen, so we need to know if one is focused or not so we don't steal focus
-        ui.horizontal(|ui| {
-            if ui.button("Play").clicked() {
-                let mut data = self.data.borrow_mut();
-                data.push(Command::TabCommand(TabCommand::Play(tab.id)));
-            }
-        });
+impl BuildOptions {
+    /// The preset's RUSTFLAGS followed by the tab's own
+    fn with_preset(&self, preset: &EnvPreset) -> Self {
+        let rust_flags = format!("{} {}", preset.rust_flags.trim(), self.rust_flags.trim());
 
-        ui.vertical_centered(|ui| {
-            tab.scroll_offset = Some(tab.editor.show(
-                tab.id.with("code_editor"),
-                ui,
-                tab.scroll_offset.unwrap_or_default(),
-            ));
-        });
+        Self {
+            rust_flags: rust_flags.trim().to_string(),
+            ..self.clone()
+        }
     }
 
-    fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
-        (&*tab.name).into()
+    /// The folder prefix the tab's projects are generated with. Invalid ones aren't used
+    pub fn target_prefix(&self) -> &str {
+        valid_name(&self.target_prefix).unwrap_or("rust-play")
     }
 
-    fn on_add(&mut self, node: NodeIndex) {
-        let mut data = self.data.borrow_mut();
-        data.push(Command::TabCommand(TabCommand::Add(node)));
+    /// The project the tab's scratch is played in, without any code
+    pub fn project<'a>(&'a self, base_dir: Option<&'a Path>) -> Project<'a> {
+        let mut project = Project::new(Id::new(SCRATCH_PROJECT));
+        self.apply(&mut project);
+
+        if let Some(base_dir) = base_dir {
+            project.base_dir(base_dir);
+        }
+
+        project
     }
 
-    fn context_menu(&mut self, ui: &mut Ui, tab: &mut Self::Tab) {
-        let mut data = self.data.borrow_mut();
+    pub fn apply<'a>(&'a self, project: &mut Project<'a>) {
+        project
+            .channel(self.channel)
+            .target_prefix(self.target_prefix());
 
-        let rename_btn = ui.button("Rename".to_string()).clicked();
-        let save_btn = ui.button("Save...".to_string()).clicked();
-        let share_btn = ui.button("Share to Playground".to_string()).clicked();
+        if let Some(name) = valid_name(&self.package_name) {
+            project.package_name(name);
+        }
 
-        let mut command = None;
+        let rust_flags = self.rust_flags.trim();
+        if !rust_flags.is_empty() {
+            project.rust_flags(rust_flags);
+        }
 
-        if rename_btn {
-            command = Some(MenuCommand::Rename(tab.id));
+        let features = self.features.trim();
+        if !features.is_empty() {
+            project.subcommand_flags(&["--features", features]);
         }
 
-        if save_btn || share_btn {
-            command = Some(if save_btn {
-                MenuCommand::Save(tab.id)
-            } else {
-                MenuCommand::Share(tab.id)
-            });
+        if self.no_default_features {
+            project.subcommand_flag("--no-default-features");
         }
 
-        if let Some(command) = command {
-            data.push(Command::MenuCommand(command));
-            ui.close_menu();
+        if self.wrap_main {
+            project.wrap_main();
         }
+
+        project.async_runtime(self.async_runtime);
     }
 
-    fn on_close(&mut self, tab: &mut Self::Tab) -> bool {
-        let mut data = self.data.borrow_mut();
-        data.push(Command::TabCommand(TabCommand::Close(tab.id)));
+    /// Returns true if installing the missing channel was asked for
+    fn show(&mut self, ui: &mut Ui, toolchains: &Toolchains) -> bool {
+        let mut install = false;
 
-        true
-    }
-}
+        Grid::new("build_options").num_columns(2).show(ui, |ui| {
+            ui.label("Channel");
+            ui.horizontal(|ui| {
+                let label = |channel: Channel| {
+                    let name: &str = channel.into();
 
-#[derive(Debug)]
-pub struct TabEvents;
+                    if toolchains.is_missing(channel) {
+                        format!("{name} (not installed)")
+                    } else {
+                        name.to_string()
+                    }
+                };
 
-impl TabEvents {
-    pub fn show(ctx: &egui::Context, config: &mut Config) {
-        // Functions which return false remove their item from the vec.
-        config.dock.commands.retain(|i| match i {
-            Command::MenuCommand(command) => match command {
-                MenuCommand::Rename(v) => Self::show_rename_window(ctx, *v, &mut config.dock.tree),
-                MenuCommand::Save(_) => todo!(),
-                MenuCommand::Share(v) => {
-                    Self::share_scratch(*v, &mut config.dock.tree, &config.github)
+                ComboBox::from_id_source("build_channel")
+                    .selected_text(label(self.channel))
+                    .show_ui(ui, |ui| {
+                        for channel in CHANNELS {
+                            ui.selectable_value(&mut self.channel, channel, label(channel));
+                        }
+                    });
+
+                if toolchains.is_missing(self.channel) && ui.button("Install").clicked() {
+                    install = true;
                 }
-            },
+            });
+            ui.end_row();
 
-            Command::TabCommand(command) => match command {
-                TabCommand::Add(v) => {
-                    let name = format!("Scratch {}", config.dock.counter);
+            ui.label("Run on");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.backend, Backend::Local, "This machine");
+                ui.radio_value(&mut self.backend, Backend::Playground, "play.rust-lang.org")
+                    .on_hover_text(
+                        "Runs and tests are sent to the official playground. It has the most \
+                         downloaded crates only, and ignores the other options. The output comes all \
+                         at once when it's done",
+                    );
+            });
+            ui.end_row();
 
-                    let node_tabs = &config.dock.tree[*v];
+            ui.label("RUSTFLAGS");
+            ui.add(
+                TextEdit::singleline(&mut self.rust_flags)
+                    .hint_text("-Zsanitizer=address")
+                    .desired_width(200.0),
+            );
+            ui.end_row();
 
-                    let tab = Tab {
-                        // unique name based on current nodeindex + tabindex
-                        id: Id::new(format!("{name}-{}-{}", v.0, node_tabs.tabs_count() + 1)),
-                        name,
-                        editor: CodeEditor::default(),
-                        scroll_offset: None,
-                    };
+            ui.label("Features");
+            ui.add(
+                TextEdit::singleline(&mut self.features)
+                    .hint_text("serde/derive")
+                    .desired_width(200.0),
+            );
+            ui.end_row();
 
-                    config.dock.tree.set_focused_node(*v);
-                    config.dock.tree.push_to_focused_leaf(tab);
+            ui.label("Async runtime");
+            ComboBox::from_id_source("build_async_runtime")
+                .selected_text(self.async_runtime.to_string())
+                .show_ui(ui, |ui| {
+                    for runtime in AsyncRuntime::ALL {
+                        ui.selectable_value(&mut self.async_runtime, runtime, runtime.to_string());
+                    }
+                })
+                .response
+                .on_hover_text("Runs an async fn main without writing #[tokio::main] or adding the runtime's dependency");
+            ui.end_row();
 
-                    config.dock.counter += 1;
+            ui.label("Loom preemptions");
+            ui.add(DragValue::new(&mut self.loom_max_preemptions).clamp_range(0..=10))
+                .on_hover_text("Bounds the interleavings loom explores, 0 explores all of them");
+            ui.end_row();
 
-                    false
-                }
+            ui.label("MIRIFLAGS");
+            ui.add(
+                TextEdit::singleline(&mut self.miri_flags)
+                    .hint_text("-Zmiri-disable-isolation")
+                    .desired_width(200.0),
+            );
+            ui.end_row();
 
-                TabCommand::Close(id) => {
-                    // TODO: Remove TextEditState from closed tabs so they aren't reused with the same ID
-                    let editor_id = id.with("code_edit");
+            ui.label("Lint levels");
+            ui.add(
+                TextEdit::singleline(&mut self.lint_levels)
+                    .hint_text("-W clippy::pedantic -A clippy::needless_return")
+                    .desired_width(200.0),
+            )
+            .on_hover_text("Warn about, allow or deny lints when linting with Clippy");
+            ui.end_row();
 
-                    // cleanup old textedit state
+            ui.label("Target prefix");
+            name_edit(ui, &mut self.target_prefix, "rust-play")
+                .on_hover_text("The generated project's folder is named after it");
+            ui.end_row();
 
-                    //let res = ctx.memory().data.remove::<TextEditState>(editor_id);
+            ui.label("Package name");
+            name_edit(ui, &mut self.package_name, "p<id>")
+                .on_hover_text("The name of the built binary, e.g. in process managers");
+            ui.end_row();
+        });
 
-                    //ctx.memory().data.remove::<TextEditState>(editor_id);
+        ui.checkbox(&mut self.no_default_features, "No default features");
+        ui.checkbox(&mut self.wrap_main, "Wrap statements in fn main()")
+            .on_hover_text("A scratch without a main runs its statements in a generated one");
 
-                    if config.dock.tree.num_tabs() == 0 {
-                        let tab = Tab {
-                            name: "Scratch 1".to_string(),
-                            editor: CodeEditor::default(),
-                            id: Id::new("Scratch 1"),
-                            scroll_offset: None,
-                        };
+        install
+    }
+}
 
-                        config.dock.tree.set_focused_node(NodeIndex(0));
-                        config.dock.tree.push_to_focused_leaf(tab);
+// the trimmed name, None if it's empty or cargo wouldn't take it
+fn valid_name(name: &str) -> Option<&str> {
+    let name = name.trim();
+    validate_name(name).is_ok().then_some(name)
+}
 
-                        config.dock.counter = 2;
-                    }
+// an edit for a package or folder name, which tells why the name isn't used while it's invalid
+fn name_edit(ui: &mut Ui, name: &mut String, hint: &str) -> egui::Response {
+    ui.vertical(|ui| {
+        let response = ui.add(
+            TextEdit::singleline(name)
+                .hint_text(hint)
+                .desired_width(200.0),
+        );
 
-                    false
-                }
+        let trimmed = name.trim();
+        if !trimmed.is_empty() {
+            if let Err(e) = validate_name(trimmed) {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{e}, {hint} is used"));
+            }
+        }
 
-                TabCommand::Play(id) => {
-                    let tab = &mut config
-                        .dock
-                        .tree
-                        .iter_mut()
-                        .filter_map(|node| {
-                            let Node::Leaf { tabs, .. } = node else {
-                                return None;
-                            };
+        response
+    })
+    .inner
+}
 
-                            tabs.iter_mut().find(|tab| tab.id == *id)
-                        })
-                        .collect::<SmallVec<[&mut Tab; 1]>>()[0];
+impl Tab {
+    pub fn new(name: String, id: Id, editor: CodeEditor) -> Self {
+        // tabs which are never edited never get a draft
+        let draft_hash = Self::hash_draft(&name, &editor.code, None);
+        let saved_hash = Self::hash_code(&editor.code);
 
-                    let id = *id;
-                    let code = tab.editor.code.clone();
+        Self {
+            name,
+            editor,
+            id,
+            scroll_offset: None,
+            watch: Watch::default(),
+            env: Env::default(),
+            build: BuildOptions::default(),
+            preset: None,
+            theme: None,
+            zoom: None,
+            label: None,
+            split: false,
+            encoding: None,
+            keep_encoding: false,
+            source: None,
+            draft_key: rand::thread_rng().gen(),
+            draft_hash,
+            saved_hash,
+            name_suggested: false,
+        }
+    }
 
-                    // this are used as a thread abort signaler
-                    let (atx, arx) = channel();
+    /// A tab showing a file which can't be edited
+    pub fn read_only(name: String, path: PathBuf, code: String, counter: u32) -> Self {
+        let id = Id::new(format!("{name}-source-{counter}"));
 
-                    let mut rng = rand::thread_rng();
-                    let abort_rid: u64 = rng.gen();
+        let language = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("rs");
 
-                    let abort_id = id.with(format!("_thread_aborter_{abort_rid}"));
+        let mut tab = Self::new(name, id, CodeEditor::read_only(code, language));
+        tab.source = Some(path);
 
-                    let prev = config.terminal.abortable.insert(id, abort_id);
-                    // if there's a previous process running, send the signal abort
-                    type Aborter = Arc<Mutex<Sender<()>>>;
-                    if let Some(atx) = prev {
-                        let mut mem = ctx.memory();
-                        if mem.data.get_temp::<Aborter>(atx).is_some() {
-                            mem.data.remove::<Aborter>(atx);
-                        }
-                    }
+        tab
+    }
 
-                    ctx.memory()
-                        .data
-                        .insert_temp::<Aborter>(abort_id, Arc::new(Mutex::new(atx)));
+    fn hash_code(code: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        code.hash(&mut hasher);
+        hasher.finish()
+    }
 
-                    // these are used to stream the terminal output
-                    let rb_stdout = HeapRb::<String>::new(30);
-                    let rb_stderr = HeapRb::<String>::new(30);
+    /// The name shown in the tab bar
+    pub fn title(&self) -> String {
+        if self.is_dirty() {
+            format!("{}*", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
 
-                    let (mut rb_stdout, rb_stdout_read) = rb_stdout.split();
-                    let (mut rb_stderr, rb_stderr_read) = rb_stderr.split();
+    /// The edition the scratch is played with, every scratch is on the latest one
+    pub fn edition(&self) -> Edition {
+        Edition::E2021
+    }
 
-                    config
-                        .terminal
-                        .content
-                        .insert(id, Some((rb_stdout_read, rb_stderr_read)));
+    /// Whether the tab is still named after its number, as new scratches are
+    pub fn has_default_name(&self) -> bool {
+        self.name
+            .strip_prefix("Scratch ")
+            .map_or(false, |number| number.parse::<u32>().is_ok())
+    }
 
-                    let owned_ctx = ctx.clone();
+    /// Whether the code changed since the tab was opened or last saved
+    pub fn is_dirty(&self) -> bool {
+        Self::hash_code(&self.editor.code) != self.saved_hash
+    }
 
-                    config.terminal.started_run = true;
+    fn hash_draft(name: &str, code: &str, label: Option<&TabLabel>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        code.hash(&mut hasher);
+        label.hash(&mut hasher);
+        hasher.finish()
+    }
 
-                    thread::spawn(move || {
-                        let id = Id::new("continuous_mode");
+    /// A tab writing to the same draft again
+    pub fn from_draft(draft: &Draft, counter: u32) -> Self {
+        let id = Id::new(format!("{}-draft-{}", draft.name, counter));
 
-                        let ctx = owned_ctx;
+        let mut tab = Self::new(draft.name.clone(), id, CodeEditor::new(draft.code.clone()));
+        tab.draft_key = draft.key;
+        tab.label = draft.label.clone();
+        // the draft is up to date, it's not written again just for opening it
+        tab.draft_hash = Self::hash_draft(&tab.name, &tab.editor.code, tab.label.as_ref());
 
-                        // a counter used to indicate when continuous mode is on. It is on as long as any threads are still running
-                        {
-                            let mut mem = ctx.memory();
-                            let counter = mem.data.get_temp_mut_or_default::<u64>(id);
-                            *counter += 1;
-                        }
+        tab
+    }
 
-                        let mut command = Project::new(id)
-                            .build_type(BuildType::Debug)
-                            .channel(Channel::Stable)
-                            .file(File::new("main", &code))
-                            .edition(Edition::E2021)
-                            .subcommand(Subcommand::Run)
-                            .target_prefix("rust-play")
-                            .env_var("CARGO_TERM_COLOR", "always")
-                            .env_var("CARGO_TERM_PROGRESS_WHEN", "always")
-                            .env_var("CARGO_TERM_PROGRESS_WIDTH", "150")
-                            .create()
-                            .expect("Oh no");
-
-                        // hide the console window from command. Very important.
-                        #[cfg(target_os = "windows")]
-                        command.creation_flags(CREATE_NO_WINDOW.0);
-
-                        let mut child = command
-                            .stderr(Stdio::piped())
-                            .stdout(Stdio::piped())
-                            .spawn()
-                            .unwrap();
-
-                        let stdout = child.stdout.take().unwrap();
-                        let stderr = child.stderr.take().unwrap();
-
-                        // special thread which checks for abort code
-                        thread::spawn(move || {
-                            // blocking wait for abort
-                            let _ = arx.recv();
-                            let _ = child.kill();
-                        });
+    pub fn draft(&self) -> Draft {
+        Draft {
+            key: self.draft_key,
+            name: self.name.clone(),
+            code: self.editor.code.clone(),
+            label: self.label.clone(),
+            modified: None,
+        }
+    }
 
-                        let stdout_handle = thread::spawn(move || {
-                            let stdout_reader = BufReader::new(stdout);
-
-                            let mut send = move |line| {
-                                if rb_stdout.is_full() {
-                                    while rb_stdout.is_full() {
-                                        if !rb_stdout.is_full() {
-                                            let _ = rb_stdout.push(line);
-                                            break;
-                                        }
-                                    }
-                                } else {
-                                    let _ = rb_stdout.push(line);
-                                }
-                            };
+    /// Write the tab to its draft file if it changed since the last write.
+    /// Returns whether anything was written
+    pub fn save_draft(&mut self) -> bool {
+        // the file is still there to open again
+        if self.source.is_some() {
+            return false;
+        }
 
-                            // we need to split lines based on newline OR \r, so we can display dynamic output lines
-                            let mut buf = vec![];
-                            for b in stdout_reader.bytes() {
-                                if let Ok(b) = b {
-                                    if b == b'\n' || b == b'\r' {
-                                        buf.push(b);
+        let hash = Self::hash_draft(&self.name, &self.editor.code, self.label.as_ref());
+        if hash == self.draft_hash {
+            return false;
+        }
 
-                                        let line = String::from_utf8_lossy(&buf);
-                                        match line {
-                                            Cow::Borrowed(b) => send(b.to_string()),
-                                            Cow::Owned(o) => send(o),
-                                        }
+        if self.draft().save().is_err() {
+            return false;
+        }
 
-                                        buf.clear();
+        self.draft_hash = hash;
 
-                                        continue;
-                                    }
+        true
+    }
+}
 
-                                    buf.push(b);
-                                } else {
-                                    break;
-                                }
-                            }
+pub trait TreeTabs
+where
+    Self: Sized,
+{
+    fn init() -> Self;
+}
 
-                            // flush remaining output
-                            if !buf.is_empty() {
-                                buf.push(b'\n');
-                                let line = String::from_utf8_lossy(&buf);
-                                match line {
-                                    Cow::Borrowed(b) => send(b.to_string()),
-                                    Cow::Owned(o) => send(o),
-                                }
-                            }
-                        });
+// Initialize the initial tabs / tab data
+impl TreeTabs for Tree {
+    fn init() -> Self {
+        let tab = Tab::new(
+            "Scratch 1".to_string(),
+            Id::new("Scratch 1"),
+            CodeEditor::default(),
+        );
 
-                        let stderr_handle = thread::spawn(move || {
-                            let stderr_reader = BufReader::new(stderr);
-
-                            let mut send = move |line| {
-                                if rb_stderr.is_full() {
-                                    while rb_stderr.is_full() {
-                                        if !rb_stderr.is_full() {
-                                            let _ = rb_stderr.push(line);
-                                            break;
-                                        }
-                                    }
-                                } else {
-                                    let _ = rb_stderr.push(line);
-                                }
-                            };
+        let mut tree = Tree::new(vec![tab]);
+        tree.set_focused_node(NodeIndex::root());
+        tree
+    }
+}
 
-                            // we need to split lines based on newline OR \r, so we can display dynamic output lines
-                            let mut buf = vec![];
-                            for b in stderr_reader.bytes() {
-                                if let Ok(b) = b {
-                                    if b == b'\n' || b == b'\r' {
-                                        buf.push(b);
+pub struct Dock;
 
-                                        let line = String::from_utf8_lossy(&buf);
-                                        match line {
-                                            Cow::Borrowed(b) => send(b.to_string()),
-                                            Cow::Owned(o) => send(o),
-                                        }
+impl Dock {
+    pub fn show(ctx: &egui::Context, config: &mut Config, ui: &mut Ui) {
+        CommandPalette::register(ctx, "dock", Self::palette_commands);
 
-                                        buf.clear();
+        let tree = &mut config.dock.tree;
 
-                                        continue;
-                                    }
+        let mut style = Style::from_egui(ctx.style().as_ref());
 
-                                    buf.push(b);
-                                } else {
-                                    break;
-                                }
-                            }
+        // important, otherwise it'll draw over the original titlebar
+        style.tab_bar_background_color = Color32::TRANSPARENT;
+        style.tab_bar_height = (TITLEBAR_HEIGHT - MENUBAR_HEIGHT) as f32 / 2.0;
+        style.tabs_are_draggable = true;
+        style.tab_include_scrollarea = false;
+        style.show_add_buttons = true;
+        // the add button opens a list of templates to start from
+        style.show_add_popup = true;
+        style.add_tab_align = TabAddAlign::Left;
+        style.show_context_menu = true;
 
-                            // flush remaining output
-                            if !buf.is_empty() {
-                                buf.push(b'\n');
-                                let line = String::from_utf8_lossy(&buf);
-                                match line {
-                                    Cow::Borrowed(b) => send(b.to_string()),
-                                    Cow::Owned(o) => send(o),
-                                }
-                            }
-                        });
+        let tab_data = TabData::new();
 
-                        // kick off the repaints
-                        ctx.request_repaint();
-                        let _ = stdout_handle.join();
-                        let _ = stderr_handle.join();
+        // runs waiting for the one still going in their tab
+        let queued = config
+            .dock
+            .commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::TabCommand(command) => command.run_tab(),
+                _ => None,
+            })
+            .collect();
 
-                        let mut mem = ctx.memory();
-                        let counter = mem.data.get_temp_mut_or_default::<u64>(id);
-                        *counter -= 1;
+        // a server for each tab while it's turned on
+        if config.editor.rust_analyzer {
+            let tabs = tree.tabs().map(|tab| tab.id).collect::<Vec<_>>();
+            config.analyzer.sessions.retain(|id, _| tabs.contains(id));
+        } else {
+            config.analyzer.sessions.clear();
+        }
 
-                        let aborter = mem.data.get_temp::<Aborter>(abort_id);
-                        if aborter.is_some() {
-                            mem.data.remove::<Aborter>(abort_id);
-                        }
-                    });
+        // rust-analyzer shows the errors itself
+        let check_while_typing = config.editor.check_while_typing && !config.editor.rust_analyzer;
+        if check_while_typing {
+            let tabs = tree.tabs().map(|tab| tab.id).collect::<Vec<_>>();
+            config
+                .check_hints
+                .sessions
+                .retain(|id, _| tabs.contains(id));
+        } else {
+            config.check_hints.sessions.clear();
+        }
 
-                    false
-                }
-            },
-        });
-    }
+        let mut tab_viewer = TabViewer::new(
+            ctx,
+            &tab_data,
+            &config.dock.templates,
+            &config.editor.settings,
+            config.editor.settings.zoomed(config.terminal_settings.zoom),
+            config.theme.editor_zoom,
+            &config.toolchains,
+            &config.presets,
+            &mut config.terminal,
+            CodeTheme::from_picked(ctx, config.theme.syntax_theme.as_ref()),
+            config.theme.get_ansi_colors(),
+            config.terminal_settings.blink,
+            queued,
+            config.disk_usage.bytes,
+            &config.general,
+            config.setup.is_cargo_missing(),
+            config.editor.rust_analyzer.then_some(&mut config.analyzer),
+            check_while_typing.then_some(&mut config.check_hints),
+        );
 
-    fn show_rename_window(ctx: &egui::Context, id: Id, tree: &mut Tree) -> bool {
-        let tab = &mut tree
-            .iter_mut()
-            .filter_map(|node| {
-                let Node::Leaf { tabs, .. } = node else {
-                    return None;
-                };
+        let response = DockView::show(ctx, ui, tree, style, &mut tab_viewer);
 
-                tabs.iter_mut().find(|tab| tab.id == id)
-            })
-            .collect::<SmallVec<[&mut Tab; 1]>>()[0];
+        if let Some(id) = response.dropped_outside {
+            tab_data
+                .borrow_mut()
+                .push(Command::TabCommand(TabCommand::Detach(id)));
+        }
 
-        Window::new(&format!("Rename {}", tab.name))
-            .title_bar(false)
-            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
-            .auto_sized()
-            .show(ctx, |ui| {
-                if ui.button("Done").clicked() {
-                    tab.name = "nice".to_string();
-                    return false;
-                }
+        let double_clicked = ctx
+            .input()
+            .pointer
+            .button_double_clicked(PointerButton::Primary);
 
-                true
-            })
-            .unwrap()
-            .inner
-            .unwrap()
-    }
+        if double_clicked {
+            if let Some(command) = Self::double_click_action(ctx, tree, config.general.double_click)
+            {
+                tab_data.borrow_mut().push(command);
+            }
+        }
+
+        if let Some(id) = response.clicked_active {
+            tab_data
+                .borrow_mut()
+                .push(Command::MenuCommand(MenuCommand::Rename(id)));
+        }
 
-    fn share_scratch(id: Id, tree: &mut Tree, github: &GitHub) -> bool {
-        println!("shared scratch token: {}", github.access_token);
+        // keep the terminal active display on the selected tab
+        if let Some((_, tab)) = tree.find_active() {
+            config.terminal.active_tab = Some(tab.id);
+        }
+
+        // add data to command vec
+        for command in tab_data.borrow().iter() {
+            // buttons in a split view may open a view which is already open
+            if matches!(command, Command::MenuCommand(_)) {
+                terminal::queue(&mut config.dock.commands, command.clone());
+                continue;
+            }
+
+            // the confirmation dialog may already be open from an earlier click
+            if let Command::TabCommand(TabCommand::ConfirmClose(id)) = command {
+                let is_open = config.dock.commands.iter().any(|command| match command {
+                    Command::TabCommand(TabCommand::ConfirmClose(other)) => other == id,
+                    _ => false,
+                });
+
+                if is_open {
+                    continue;
+                }
+            }
+
+            config.dock.commands.push(command.clone());
+        }
+    }
+
+    /// What a double click at the pointer does in a tab bar. None if it isn't on one, or the setting for it is off
+    pub fn double_click_action(
+        ctx: &egui::Context,
+        tree: &Tree,
+        double_click: DoubleClick,
+    ) -> Option<Command> {
+        match DockView::under_pointer(ctx, tree)? {
+            DoubleClicked::Tab(id) if double_click.rename => {
+                Some(Command::MenuCommand(MenuCommand::Rename(id)))
+            }
+            DoubleClicked::TabBar(node) if double_click.new_tab => {
+                Some(Command::TabCommand(TabCommand::Add(node)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Commands on the tabs for the command palette. Most act on the focused tab
+    pub fn palette_commands(config: &mut Config) -> Vec<PaletteCommand> {
+        let mut commands = vec![];
+
+        if let Some(node) = config.dock.tree.focused_leaf() {
+            commands.push(PaletteCommand::new(
+                "File: New scratch",
+                Command::TabCommand(TabCommand::Add(node)),
+            ));
+            commands.push(PaletteCommand::new(
+                "File: New from template...",
+                Command::MenuCommand(MenuCommand::NewFromTemplate(node)),
+            ));
+        }
+
+        if !config.dock.closed.is_empty() {
+            commands.push(PaletteCommand::new(
+                "Tab: Reopen closed tab",
+                Command::TabCommand(TabCommand::ReopenClosed),
+            ));
+        }
+
+        if let Some((_, tab)) = config.dock.tree.find_active() {
+            let id = tab.id;
+            let tab_command =
+                |title: &str, command| PaletteCommand::new(title, Command::TabCommand(command));
+            let menu_command =
+                |title: &str, command| PaletteCommand::new(title, Command::MenuCommand(command));
+
+            commands.extend([
+                tab_command("Run: Play", TabCommand::Play(id)),
+                tab_command("Run: Test", TabCommand::PlayTests(id)),
+                tab_command("Run: Run in browser", TabCommand::PlayWeb(id)),
+                tab_command("Run: Stop", TabCommand::Stop(id)),
+                tab_command("Run: Loom model check", TabCommand::PlayLoom(id)),
+                tab_command("Run: Check UB with Miri", TabCommand::PlayMiri(id)),
+                tab_command("Run: Lint with Clippy", TabCommand::PlayClippy(id)),
+                tab_command("Run: Binary size", TabCommand::BinarySize(id)),
+                tab_command("Tab: Move to new window", TabCommand::Detach(id)),
+            ]);
+
+            commands.push(PaletteCommand::run(
+                "View: Toggle output beside the editor",
+                |_, config| {
+                    if let Some((_, tab)) = config.dock.tree.find_active() {
+                        tab.split = !tab.split;
+                    }
+                },
+            ));
+
+            for &sanitizer in host_sanitizers() {
+                commands.push(tab_command(
+                    &format!("Run: {}", sanitizer_label(sanitizer)),
+                    TabCommand::PlaySanitized(id, sanitizer),
+                ));
+            }
+
+            for function in proptest_functions(&tab.editor.code) {
+                commands.push(tab_command(
+                    &format!("Run: Property test {function}"),
+                    TabCommand::Proptest(id, function),
+                ));
+            }
+
+            for function in fuzzable_functions(&tab.editor.code) {
+                commands.push(tab_command(
+                    &format!("Run: Fuzz {}", fuzz_label(&function)),
+                    TabCommand::Fuzz(id, function),
+                ));
+            }
+
+            commands.extend([
+                tab_command("Edit: Format", TabCommand::Format(id)),
+                tab_command("Edit: Go to std source", TabCommand::GoToStdSource(id)),
+                tab_command(
+                    "Edit: Tidy directives",
+                    TabCommand::TidyDirectives(Some(id)),
+                ),
+                tab_command(
+                    "Edit: Tidy directives in all tabs",
+                    TabCommand::TidyDirectives(None),
+                ),
+                menu_command("File: Save...", MenuCommand::Save(id)),
+                menu_command("File: Share to Playground", MenuCommand::Share(id)),
+                menu_command("File: Copy Playground link", MenuCommand::ShareLink(id)),
+                menu_command("Tab: Rename", MenuCommand::Rename(id)),
+                menu_command("Tab: Dependencies...", MenuCommand::CrateSources(id)),
+                menu_command("Tab: Dependency tree...", MenuCommand::DependencyTree(id)),
+                menu_command("Tab: Show Cargo.toml", MenuCommand::Manifest(id)),
+                menu_command("Tab: Export project...", MenuCommand::ExportProject(id)),
+                menu_command("Terminal: Export output...", MenuCommand::ExportOutput(id)),
+                tab_command(
+                    "Tab: Open project folder",
+                    TabCommand::OpenProjectFolder(id),
+                ),
+                tab_command(
+                    "Tab: Open project in editor",
+                    TabCommand::OpenProjectInEditor(id),
+                ),
+                menu_command("Tab: Add dependency...", MenuCommand::AddDependency(id)),
+            ]);
+
+            for channel in CHANNELS {
+                if channel == tab.build.channel {
+                    continue;
+                }
+
+                let name: &str = channel.into();
+
+                commands.push(PaletteCommand::run(
+                    format!("Toolchain: Use {name}"),
+                    move |_, config| {
+                        let tab = config.dock.tree.iter_mut().find_map(|node| {
+                            let Node::Leaf { tabs, .. } = node else {
+                                return None;
+                            };
+
+                            tabs.iter_mut().find(|tab| tab.id == id)
+                        });
+
+                        if let Some(tab) = tab {
+                            tab.build.channel = channel;
+                        }
+                    },
+                ));
+            }
+        }
+
+        for node in config.dock.tree.iter() {
+            let Node::Leaf { tabs, .. } = node else {
+                continue;
+            };
+
+            for tab in tabs {
+                commands.push(PaletteCommand::new(
+                    format!("Tab: Switch to {}", tab.name),
+                    Command::TabCommand(TabCommand::Focus(tab.id)),
+                ));
+            }
+        }
+
+        commands
+    }
+}
+
+impl DockTab for Tab {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn title(&self) -> String {
+        Tab::title(self)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+}
+
+type TabData = Data<Command>;
+
+struct TabViewer<'a> {
+    ctx: &'a egui::Context,
+    data: &'a TabData,
+    templates: &'a [Template],
+    editor_config: &'a EditorSettings,
+    // the terminal's settings, output shown beside the editor is zoomed like the terminal
+    output_config: EditorSettings,
+    // the zoom of tabs which weren't zoomed themselves
+    default_zoom: f32,
+    toolchains: &'a Toolchains,
+    presets: &'a Presets,
+    terminal: &'a mut Terminal,
+    code_theme: CodeTheme,
+    ansi_colors: AnsiColors,
+    blink: bool,
+    // tabs with a run waiting to start
+    queued: Vec<Id>,
+    // bytes of the generated projects, once measured
+    disk_usage: Option<u64>,
+    general: &'a GeneralConfig,
+    // runs can't start until it's installed
+    cargo_missing: bool,
+    // None while rust-analyzer is turned off
+    analyzer: Option<&'a mut Analyzer>,
+    // None while checking as the code is typed is turned off
+    check_hints: Option<&'a mut CheckHints>,
+}
+
+impl<'a> TabViewer<'a> {
+    fn new(
+        ctx: &'a egui::Context,
+        data: &'a TabData,
+        templates: &'a [Template],
+        editor_config: &'a EditorSettings,
+        output_config: EditorSettings,
+        default_zoom: f32,
+        toolchains: &'a Toolchains,
+        presets: &'a Presets,
+        terminal: &'a mut Terminal,
+        code_theme: CodeTheme,
+        ansi_colors: AnsiColors,
+        blink: bool,
+        queued: Vec<Id>,
+        disk_usage: Option<u64>,
+        general: &'a GeneralConfig,
+        cargo_missing: bool,
+        analyzer: Option<&'a mut Analyzer>,
+        check_hints: Option<&'a mut CheckHints>,
+    ) -> Self {
+        Self {
+            ctx,
+            data,
+            templates,
+            editor_config,
+            output_config,
+            default_zoom,
+            toolchains,
+            presets,
+            terminal,
+            code_theme,
+            ansi_colors,
+            blink,
+            queued,
+            disk_usage,
+            general,
+            cargo_missing,
+            analyzer,
+            check_hints,
+        }
+    }
+
+    // a color or an emoji for the front of the title, or any other text that's typed
+    fn label_picker(ui: &mut Ui, label: &mut Option<TabLabel>) {
+        if ui.selectable_label(label.is_none(), "None").clicked() {
+            *label = None;
+            ui.close_menu();
+        }
+
+        ui.separator();
+
+        for color in LabelColor::ALL {
+            let text = RichText::new(format!("● {}", color.label())).color(color.color());
+
+            if ui
+                .selectable_label(*label == Some(TabLabel::Color(color)), text)
+                .clicked()
+            {
+                *label = Some(TabLabel::Color(color));
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+
+        ui.horizontal_wrapped(|ui| {
+            ui.set_max_width(120.0);
+
+            for emoji in LABEL_EMOJIS {
+                let selected = matches!(label, Some(TabLabel::Emoji(picked)) if picked == emoji);
+
+                if ui.selectable_label(selected, emoji).clicked() {
+                    *label = Some(TabLabel::Emoji(emoji.to_string()));
+                    ui.close_menu();
+                }
+            }
+        });
+
+        let mut custom = match label {
+            Some(TabLabel::Emoji(emoji)) => emoji.clone(),
+            _ => String::new(),
+        };
+
+        let edit = TextEdit::singleline(&mut custom)
+            .hint_text("Other")
+            .desired_width(120.0);
+
+        if ui.add(edit).changed() {
+            let custom = custom
+                .trim()
+                .chars()
+                .take(MAX_LABEL_CHARS)
+                .collect::<String>();
+            *label = (!custom.is_empty()).then_some(TabLabel::Emoji(custom));
+        }
+    }
+
+    // where the file of a read-only tab is, in place of the toolbar
+    fn show_source_bar(ui: &mut Ui, path: &Path) {
+        ui.horizontal(|ui| {
+            ui.weak("Read-only");
+            ui.label(path.display().to_string());
+
+            if ui.button("Open folder").clicked() {
+                if let Some(dir) = path.parent() {
+                    let _ = open::that(dir);
+                }
+            }
+        });
+    }
+
+    // the encoding a file which wasn't UTF-8 is saved in, either converted to UTF-8 or kept as it was
+    fn encoding_picker(ui: &mut Ui, encoding: Encoding, keep: &mut bool) {
+        let current = if *keep { encoding.label() } else { "UTF-8" };
+
+        ui.menu_button(current, |ui| {
+            if ui.selectable_value(keep, false, "UTF-8").clicked()
+                || ui.selectable_value(keep, true, encoding.label()).clicked()
+            {
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text(format!(
+            "Opened as {}, click to choose the encoding it's saved in",
+            encoding.label()
+        ));
+    }
+}
+
+impl egui_dock::TabViewer for TabViewer<'_> {
+    type Tab = Tab;
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
+        if let Some(path) = &tab.source {
+            Self::show_source_bar(ui, path);
+        } else {
+            // multiple tabs may be open on the screen, so we need to know if one is focused or not so we don't steal focus
+            ui.horizontal(|ui| {
+                // a run is stopped from where it was started
+                if TabEvents::is_running(self.ctx, self.terminal, tab.id) {
+                    if ui.button("Stop").clicked() {
+                        let mut data = self.data.borrow_mut();
+                        data.push(Command::TabCommand(TabCommand::Stop(tab.id)));
+                    }
+                } else if ui
+                    .add_enabled(!self.cargo_missing, Button::new("Play"))
+                    .on_disabled_hover_text(
+                        "cargo wasn't found, Help > Check Rust installation shows how to install it",
+                    )
+                    .clicked()
+                {
+                    let mut data = self.data.borrow_mut();
+                    data.push(Command::TabCommand(TabCommand::Play(tab.id)));
+                }
+
+                if self.queued.contains(&tab.id) {
+                    ui.weak("Queued")
+                        .on_hover_text("Starts once the last run in this tab is gone");
+                }
+
+                if ui
+                    .button("Web")
+                    .on_hover_text("Build for wasm and open it in the browser")
+                    .clicked()
+                {
+                    let mut data = self.data.borrow_mut();
+                    data.push(Command::TabCommand(TabCommand::PlayWeb(tab.id)));
+                }
+
+                ui.menu_button("Check", |ui| {
+                    let sanitizers = host_sanitizers();
+
+                    if sanitizers.is_empty() {
+                        ui.label("No sanitizer supports this platform");
+                    }
+
+                    for &sanitizer in sanitizers {
+                        if ui.button(sanitizer_label(sanitizer)).clicked() {
+                            let mut data = self.data.borrow_mut();
+                            data.push(Command::TabCommand(TabCommand::PlaySanitized(
+                                tab.id, sanitizer,
+                            )));
+                            ui.close_menu();
+                        }
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Loom model check").clicked() {
+                        let mut data = self.data.borrow_mut();
+                        data.push(Command::TabCommand(TabCommand::PlayLoom(tab.id)));
+                        ui.close_menu();
+                    }
+
+                    let check_ub = ui.button("Check UB with Miri").on_hover_text(miri_hover_text(
+                        self.toolchains
+                            .is_component_missing(Channel::Nightly, "miri"),
+                    ));
+
+                    if check_ub.clicked() {
+                        let mut data = self.data.borrow_mut();
+                        data.push(Command::TabCommand(TabCommand::PlayMiri(tab.id)));
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Lint with Clippy").clicked() {
+                        let mut data = self.data.borrow_mut();
+                        data.push(Command::TabCommand(TabCommand::PlayClippy(tab.id)));
+                        ui.close_menu();
+                    }
+
+                    ui.menu_button("Property test", |ui| {
+                        let functions = proptest_functions(&tab.editor.code);
+
+                        if functions.is_empty() {
+                            ui.label("No function taking arguments to test");
+                        }
+
+                        for function in functions {
+                            if ui.button(&function).clicked() {
+                                let mut data = self.data.borrow_mut();
+                                data.push(Command::TabCommand(TabCommand::Proptest(tab.id, function)));
+                                ui.close_menu();
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Append a proptest for the function and run the tests");
+
+                    ui.menu_button("Fuzz function", |ui| {
+                        let functions = fuzzable_functions(&tab.editor.code);
+
+                        if functions.is_empty() {
+                            ui.label("No function taking arguments to fuzz");
+                        }
+
+                        for function in functions {
+                            if ui.button(fuzz_label(&function)).clicked() {
+                                let mut data = self.data.borrow_mut();
+                                data.push(Command::TabCommand(TabCommand::Fuzz(tab.id, function)));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+
+                    ui.separator();
+
+                    if ui
+                        .button("Binary size")
+                        .on_hover_text("Build in release and list what takes up the space")
+                        .clicked()
+                    {
+                        let mut data = self.data.borrow_mut();
+                        data.push(Command::TabCommand(TabCommand::BinarySize(tab.id)));
+                        ui.close_menu();
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Run with a sanitizer, loom or a fuzzer, to catch undefined behavior, races and crashes",
+                );
+
+                ui.checkbox(&mut tab.watch.enabled, "Watch");
+                if tab.watch.enabled {
+                    ui.add(
+                        DragValue::new(&mut tab.watch.interval)
+                            .clamp_range(1..=86400)
+                            .suffix("s"),
+                    );
+                }
+
+                ui.label("Notify on");
+                ui.add(
+                    TextEdit::singleline(&mut tab.watch.pattern)
+                        .hint_text("regex")
+                        .desired_width(120.0),
+                );
+
+                ComboBox::from_id_source(tab.id.with("preset"))
+                    .selected_text(tab.preset.as_deref().unwrap_or("No preset"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut tab.preset, None, "No preset");
+
+                        for preset in &self.presets.list {
+                            let selected = tab.preset.as_ref() == Some(&preset.name);
+
+                            if ui.selectable_label(selected, &preset.name).clicked() {
+                                tab.preset = Some(preset.name.clone());
+
+                                if let Some(channel) = preset.channel {
+                                    tab.build.channel = channel;
+                                }
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Environment preset, the tab's own settings are applied on top");
+
+                ui.menu_button("Environment", |ui| {
+                    tab.env.show(ui);
+                });
+
+                ui.menu_button("Advanced", |ui| {
+                    if tab.build.show(ui, self.toolchains) {
+                        let mut data = self.data.borrow_mut();
+                        data.push(Command::MenuCommand(MenuCommand::InstallToolchain(
+                            tab.build.channel,
+                            None,
+                        )));
+                        ui.close_menu();
+                    }
+                });
+
+                ui.toggle_value(&mut tab.split, "Split")
+                    .on_hover_text("Show the output beside the editor");
+            });
+        }
+
+        let code_theme = match &tab.theme {
+            Some(theme) => CodeTheme::from_syntect_theme(self.ctx, theme.clone()),
+            None => self.code_theme.clone(),
+        };
+
+        egui::TopBottomPanel::bottom(tab.id.with("status_bar"))
+            .frame(Frame::none())
+            .show_inside(ui, |ui| {
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if tab.editor.is_read_only() {
+                        return;
+                    }
+
+                    line_ending_picker(ui, &mut tab.editor.code);
+
+                    if let Some(encoding) = tab.encoding {
+                        Self::encoding_picker(ui, encoding, &mut tab.keep_encoding);
+                    }
+
+                    if let Some(bytes) = self.disk_usage {
+                        DiskUsageMeter::status(ui, bytes);
+                    }
+
+                    if let Some(zoom) = tab.zoom {
+                        let reset = ui
+                            .small_button(format!("{:.0}%", zoom * 100.0))
+                            .on_hover_text("Zoom of this tab, click to reset it. Ctrl+0 does too");
+
+                        if reset.clicked() {
+                            tab.zoom = None;
+                        }
+                    }
+                });
+            });
+
+        if tab.split {
+            let width = ui.available_width();
+
+            egui::SidePanel::right(tab.id.with("split_output"))
+                .resizable(true)
+                .default_width(width / 2.0)
+                .min_width(MIN_SPLIT_WIDTH)
+                .max_width(width - MIN_SPLIT_WIDTH)
+                .frame(Frame::none())
+                .show_inside(ui, |ui| {
+                    let commands = terminal::Terminal::show_output(
+                        ui,
+                        self.terminal,
+                        &self.output_config,
+                        self.ansi_colors,
+                        self.blink,
+                        tab.id,
+                        tab.id.with("split_output"),
+                    );
+
+                    self.data.borrow_mut().extend(commands);
+                });
+        }
+
+        let editor_id = tab.id.with("code_editor");
+
+        // read-only tabs aren't part of a project
+        let mut session = self
+            .analyzer
+            .as_deref_mut()
+            .filter(|_| !tab.editor.is_read_only())
+            .map(|analyzer| analyzer.sessions.entry(tab.id).or_default());
+
+        if let Some(session) = &mut session {
+            RustAnalyzer::keys(self.ctx, session, editor_id, &mut tab.editor.code);
+        }
+
+        let mut zoom = tab.zoom.unwrap_or(self.default_zoom);
+        let zoom_keys = ui.memory().has_focus(editor_id);
+
+        if zoom_input(ui, ui.max_rect(), zoom_keys, &mut zoom, self.default_zoom) {
+            tab.zoom = (zoom != self.default_zoom).then_some(zoom);
+        }
+
+        ui.vertical_centered(|ui| {
+            tab.scroll_offset = Some(tab.editor.show(
+                editor_id,
+                ui,
+                tab.scroll_offset.unwrap_or_default(),
+                &self.editor_config.zoomed(zoom),
+                &code_theme,
+            ));
+        });
+
+        if let Some(session) = session {
+            RustAnalyzer::show(ui, session, tab, self.general.projects_dir(), self.data);
+        }
+
+        let check_session = self
+            .check_hints
+            .as_deref_mut()
+            .filter(|_| !tab.editor.is_read_only())
+            .map(|hints| hints.sessions.entry(tab.id).or_default());
+
+        if let Some(session) = check_session {
+            CompilerHints::show(ui, session, tab, self.general.projects_dir());
+        }
+
+        if let Some(command) = CodeEditor::vim_command(self.ctx, editor_id) {
+            let mut data = self.data.borrow_mut();
+
+            if matches!(command, VimCommand::Write | VimCommand::WriteQuit) {
+                data.push(Command::MenuCommand(MenuCommand::Save(tab.id)));
+            }
+
+            // after the save, which is run first
+            if matches!(command, VimCommand::Quit | VimCommand::WriteQuit) {
+                data.push(Command::TabCommand(TabCommand::ConfirmClose(tab.id)));
+            }
+        }
+
+        if let Some(copy) = CodeEditor::rich_copy(self.ctx, editor_id) {
+            // it's still worth having without its colors
+            if let Err(e) = clipboard::copy_html(&copy.html, &copy.text) {
+                self.ctx.output().copied_text = copy.text;
+                Toast::warning(format!("Copied the code without its colors: {e}")).push(self.ctx);
+            }
+        }
+    }
+
+    fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
+        match &tab.label {
+            None => tab.title().into(),
+
+            Some(TabLabel::Emoji(emoji)) => format!("{emoji} {}", tab.title()).into(),
+
+            // colors of its own aren't replaced by the tab's, the name gets the text color itself
+            Some(TabLabel::Color(color)) => {
+                let style = self.ctx.style();
+                let font = TextStyle::Button.resolve(&style);
+
+                let mut job = LayoutJob::default();
+                job.append("● ", 0.0, TextFormat::simple(font.clone(), color.color()));
+                job.append(
+                    &tab.title(),
+                    0.0,
+                    TextFormat::simple(font, style.visuals.text_color()),
+                );
+
+                job.into()
+            }
+        }
+    }
+
+    fn add_popup(&mut self, ui: &mut Ui, node: NodeIndex) {
+        let mut data = self.data.borrow_mut();
+
+        ui.set_min_width(150.0);
+
+        if ui.button("Scratch").clicked() {
+            data.push(Command::TabCommand(TabCommand::Add(node)));
+        }
+
+        for template in self.templates {
+            ui.horizontal(|ui| {
+                if ui.button(&template.name).clicked() {
+                    data.push(Command::TabCommand(TabCommand::AddWithCode(
+                        node,
+                        template.code.clone(),
+                    )));
+                }
+
+                if TabEvents::quick_run_button(ui) {
+                    data.push(Command::MenuCommand(MenuCommand::QuickRun(
+                        node,
+                        template.clone(),
+                    )));
+                }
+            });
+        }
+
+        ui.separator();
+
+        if ui.button("New from template...").clicked() {
+            data.push(Command::MenuCommand(MenuCommand::NewFromTemplate(node)));
+        }
+    }
+
+    fn context_menu(&mut self, ui: &mut Ui, tab: &mut Self::Tab) {
+        let mut data = self.data.borrow_mut();
+
+        let rename_btn = ui.button("Rename".to_string()).clicked();
+        let save_btn = ui.button("Save...".to_string()).clicked();
+        let share_btn = ui.button("Share to Playground".to_string()).clicked();
+        let share_link_btn = ui.button("Copy Playground link").clicked();
+        let sources_btn = ui.button("Dependencies...").clicked();
+        let tree_btn = ui.button("Dependency tree...").clicked();
+        let add_dependency_btn = ui.button("Add dependency...").clicked();
+
+        if ui.button("Move to new window").clicked() {
+            data.push(Command::TabCommand(TabCommand::Detach(tab.id)));
+            ui.close_menu();
+        }
+
+        ui.menu_button("Project", |ui| {
+            if ui.button("Show Cargo.toml").clicked() {
+                data.push(Command::MenuCommand(MenuCommand::Manifest(tab.id)));
+                ui.close_menu();
+            }
+
+            if ui.button("Export project...").clicked() {
+                data.push(Command::MenuCommand(MenuCommand::ExportProject(tab.id)));
+                ui.close_menu();
+            }
+
+            // there's nothing on the disk to open before the first run
+            let base_dir = self.general.projects_dir();
+            let generated = tab.build.project(base_dir.as_deref()).path().is_some();
+
+            let commands = [
+                (
+                    "Open folder".to_string(),
+                    TabCommand::OpenProjectFolder(tab.id),
+                ),
+                (
+                    format!("Open in {}", self.general.editor_name()),
+                    TabCommand::OpenProjectInEditor(tab.id),
+                ),
+            ];
+
+            for (label, command) in commands {
+                let button = ui
+                    .add_enabled(generated, Button::new(label))
+                    .on_disabled_hover_text("Run the scratch first to generate its project");
+
+                if button.clicked() {
+                    data.push(Command::TabCommand(command));
+                    ui.close_menu();
+                }
+            }
+        });
+
+        ui.menu_button("Syntax theme", |ui| {
+            syntax_theme_picker(ui, &mut tab.theme, "Use global theme");
+        });
+
+        ui.menu_button("Label", |ui| {
+            Self::label_picker(ui, &mut tab.label);
+        });
+
+        let mut command = None;
+
+        if rename_btn {
+            command = Some(MenuCommand::Rename(tab.id));
+        }
+
+        if sources_btn {
+            command = Some(MenuCommand::CrateSources(tab.id));
+        }
+
+        if tree_btn {
+            command = Some(MenuCommand::DependencyTree(tab.id));
+        }
+
+        if add_dependency_btn {
+            command = Some(MenuCommand::AddDependency(tab.id));
+        }
+
+        if share_link_btn {
+            command = Some(MenuCommand::ShareLink(tab.id));
+        }
+
+        if save_btn || share_btn {
+            command = Some(if save_btn {
+                MenuCommand::Save(tab.id)
+            } else {
+                MenuCommand::Share(tab.id)
+            });
+        }
+
+        if let Some(command) = command {
+            data.push(Command::MenuCommand(command));
+            ui.close_menu();
+        }
+    }
+
+    fn on_close(&mut self, tab: &mut Self::Tab) -> bool {
+        let mut data = self.data.borrow_mut();
+
+        // the confirmation dialog closes the tab itself
+        if tab.is_dirty() {
+            data.push(Command::TabCommand(TabCommand::ConfirmClose(tab.id)));
+            return false;
+        }
+
+        // closing a tab is never destructive, it can be restored from the recent list
+        tab.save_draft();
+
+        data.push(Command::TabCommand(TabCommand::Close(Box::new(
+            tab.clone(),
+        ))));
+
+        true
+    }
+}
+
+// the remaining output of a process doesn't end in a newline, but every terminal line must
+fn to_line(line: &Line) -> String {
+    let mut line = line.to_string_lossy().into_owned();
+
+    if !line.ends_with(&['\n', '\r'][..]) {
+        line.push('\n');
+    }
+
+    line
+}
+
+/// The sanitizers rustc supports on the host, e.g. neither thread nor leak on windows. All of them until rustc
+/// was asked in the background, or if it couldn't be
+pub fn host_sanitizers() -> &'static [Sanitizer] {
+    static SANITIZERS: OnceCell<Vec<Sanitizer>> = OnceCell::new();
+    static ASKED: Once = Once::new();
+
+    ASKED.call_once(|| {
+        thread::spawn(|| {
+            let sanitizers = match host_target() {
+                Some(host) => Sanitizer::ALL
+                    .into_iter()
+                    .filter(|sanitizer| sanitizer.supports(&host))
+                    .collect(),
+                None => Sanitizer::ALL.to_vec(),
+            };
+
+            let _ = SANITIZERS.set(sanitizers);
+        });
+    });
+
+    SANITIZERS.get().map_or(&Sanitizer::ALL, Vec::as_slice)
+}
+
+pub fn sanitizer_label(sanitizer: Sanitizer) -> &'static str {
+    match sanitizer {
+        Sanitizer::Address => "Address sanitizer",
+        Sanitizer::Thread => "Thread sanitizer",
+        Sanitizer::Leak => "Leak sanitizer",
+    }
+}
+
+/// What checking for UB with Miri does first, if it must install anything
+pub fn miri_hover_text(missing: bool) -> &'static str {
+    if missing {
+        "Installs nightly's miri component first, then interprets the scratch to find undefined behavior"
+    } else {
+        "Interpret the scratch to find undefined behavior"
+    }
+}
+
+/// e.g. `parse(&[u8])`
+pub fn fuzz_label(function: &FuzzFn) -> String {
+    let args = match &function.input {
+        FuzzInput::Bytes => "&[u8]".to_string(),
+        FuzzInput::Arbitrary(types) => types.join(", "),
+    };
+
+    format!("{}({args})", function.name)
+}
+
+// The terminal drains the buffers of every tab each frame, so wait for room rather than dropping output.
+// A full buffer holds the process up until the ui catches up. Once the run is aborted nobody reads the
+// buffer anymore, the rest of the output is dropped
+fn push_line(rb: &mut HeapProducer<String>, line: String, aborted: &AtomicBool) {
+    while rb.is_full() {
+        if aborted.load(Ordering::Relaxed) {
+            return;
+        }
+
+        thread::sleep(OUTPUT_WAIT);
+    }
+
+    let _ = rb.push(line);
+}
+
+// marks a run as finished once its thread is done, even when it panicked, so runs waiting on it aren't
+// held up forever
+struct FinishedGuard {
+    finished: Arc<AtomicBool>,
+    ctx: egui::Context,
+}
+
+impl Drop for FinishedGuard {
+    fn drop(&mut self) {
+        self.finished.store(true, Ordering::Relaxed);
+        self.ctx.request_repaint();
+    }
+}
+
+// whether the program comes with rustup, e.g. cargo, rather than being installed on its own
+fn is_rust_tool(program: &str) -> bool {
+    let name = Path::new(program)
+        .file_stem()
+        .map_or_else(|| program.into(), |stem| stem.to_string_lossy());
+
+    ["cargo", "rustup", "rustc"].contains(&&*name)
+}
+
+// what to install when a program a run needs isn't found
+fn install_hint(program: &str) -> String {
+    if is_rust_tool(program) {
+        return format!(
+            "`{program}` isn't on the PATH. Install Rust with rustup from https://rustup.rs and restart \
+             Rust Play, Help > Check Rust installation shows what's missing\n"
+        );
+    }
+
+    let install = match Path::new(program).file_stem().and_then(|stem| stem.to_str()) {
+        // the version has to be the one of the wasm-bindgen crate the scratch uses
+        Some("wasm-bindgen") => {
+            "Install it with `cargo install wasm-bindgen-cli`, the version the scratch's wasm-bindgen \
+             crate is on"
+        }
+        _ => "Install it",
+    };
+
+    format!("`{program}` isn't on the PATH. {install} and restart Rust Play\n")
+}
+
+// status lines cargo prints while building and launching the scratch
+fn is_cargo_noise(line: &str) -> bool {
+    const STATUSES: &[&str] = &[
+        "Blocking",
+        "Building",
+        "Compiling",
+        "Downloaded",
+        "Downloading",
+        "Finished",
+        "Locking",
+        "Running",
+        "Updating",
+    ];
+
+    let line = strip_ansi_escapes::strip(line).unwrap_or_default();
+    let line = String::from_utf8_lossy(&line);
+    let line = line.trim();
+
+    line.is_empty() || STATUSES.iter().any(|status| line.starts_with(status))
+}
+
+#[derive(Debug)]
+pub struct TabEvents;
+
+impl TabEvents {
+    pub fn show(ctx: &egui::Context, config: &mut Config) {
+        if let Some(e) = config.general.take_projects_dir_error() {
+            Toast::error(format!(
+                "Couldn't use the projects folder, the default one is used instead: {e}"
+            ))
+            .push(ctx);
+        }
+
+        Self::schedule_watched(ctx, config);
+        Self::autosave(ctx, config);
+
+        let reopen_shortcut = Modifiers {
+            command: true,
+            shift: true,
+            ..Default::default()
+        };
+
+        if ctx.input_mut().consume_key(reopen_shortcut, Key::T) {
+            config
+                .dock
+                .commands
+                .push(Command::TabCommand(TabCommand::ReopenClosed));
+        }
+
+        if ctx.input_mut().consume_key(Modifiers::NONE, Key::F2) {
+            if let Some((_, tab)) = config.dock.tree.find_active() {
+                let command = MenuCommand::Rename(tab.id);
+                config.dock.commands.push(Command::MenuCommand(command));
+            }
+        }
+
+        if ctx.input_mut().consume_key(Modifiers::NONE, Key::F12) {
+            if let Some((_, tab)) = config.dock.tree.find_active() {
+                let command = TabCommand::GoToStdSource(tab.id);
+                config.dock.commands.push(Command::TabCommand(command));
+            }
+        }
+
+        Self::open_binary_size(ctx, config);
+        Self::mark_suggested_names(ctx, &mut config.dock.tree);
+
+        Self::dedupe_runs(&mut config.dock.commands);
+        Self::drop_runs_of_closed_tabs(&mut config.dock.commands);
+        Self::drop_runs_without_cargo(ctx, config);
+
+        // Functions which return false remove their item from the vec.
+        config.dock.commands.retain(|i| match i {
+            // kept until the tab's last run is gone
+            Command::TabCommand(command)
+                if command.run_tab().map_or(false, |id| {
+                    Self::waits_for_run(ctx, &mut config.terminal, config.general.run_again, id)
+                }) =>
+            {
+                true
+            }
+
+            Command::MenuCommand(command) => match command {
+                MenuCommand::Rename(v) => {
+                    Self::start_rename(ctx, &config.dock.tree, *v);
+                    false
+                }
+                MenuCommand::Save(v) => Self::save_scratch(
+                    ctx,
+                    *v,
+                    &mut config.dock.tree,
+                    config.editor.tidy_directives_on_save,
+                ),
+                MenuCommand::Share(v) => {
+                    Self::share_scratch(ctx, *v, &config.dock.tree, &config.github)
+                }
+                MenuCommand::ShareLink(v) => Self::share_link(ctx, *v, &config.dock.tree),
+                MenuCommand::NewFromTemplate(node) => {
+                    let mut run = None;
+
+                    let open = Self::show_template_window(
+                        ctx,
+                        *node,
+                        &mut config.dock.tree,
+                        &mut config.dock.counter,
+                        &mut config.dock.templates,
+                        &mut run,
+                    );
+
+                    if let Some(template) = run {
+                        QuickRunPopup::start(
+                            ctx,
+                            &mut config.terminal,
+                            &mut config.quick_run,
+                            config.general.projects_dir(),
+                            *node,
+                            template,
+                        );
+                    }
+
+                    open
+                }
+                MenuCommand::QuickRun(node, template) => {
+                    QuickRunPopup::start(
+                        ctx,
+                        &mut config.terminal,
+                        &mut config.quick_run,
+                        config.general.projects_dir(),
+                        *node,
+                        template.clone(),
+                    );
+
+                    false
+                }
+                MenuCommand::About => About::show(ctx),
+                MenuCommand::HexView(view) => HexViewer::show(ctx, view),
+                MenuCommand::BinarySize(view) => BinarySizeViewer::show(ctx, view),
+                MenuCommand::Presets => PresetEditor::show(ctx, &mut config.presets),
+                MenuCommand::CrateSources(id) => CrateSourceBrowser::show(
+                    ctx,
+                    *id,
+                    &mut config.dock.tree,
+                    &mut config.dock.counter,
+                    config.general.projects_dir(),
+                ),
+                MenuCommand::DependencyTree(id) => OutputPager::dependency_tree(
+                    ctx,
+                    *id,
+                    &config.dock.tree,
+                    config.general.projects_dir(),
+                ),
+                MenuCommand::Explain(code) => OutputPager::explain(ctx, code),
+                MenuCommand::Manifest(id) => OutputPager::manifest(
+                    ctx,
+                    *id,
+                    &config.dock.tree,
+                    config.general.projects_dir(),
+                ),
+                MenuCommand::ExportProject(id) => ProjectExport::show(
+                    ctx,
+                    *id,
+                    &config.dock.tree,
+                    config.general.projects_dir(),
+                ),
+                MenuCommand::ExportOutput(id) => OutputExport::show(
+                    ctx,
+                    *id,
+                    &config.terminal,
+                    config.theme.get_ansi_colors(),
+                    config.editor.font_family.as_deref(),
+                ),
+                MenuCommand::AddDependency(id) => DependencyDialog::show(
+                    ctx,
+                    *id,
+                    &mut config.dependency_search,
+                    &mut config.dock.tree,
+                ),
+                MenuCommand::SaveWorkspace => Self::show_workspace_window(
+                    ctx,
+                    &config.dock.tree,
+                    &mut config.dock.workspace,
+                    &mut config.dock.workspaces,
+                ),
+                MenuCommand::Gists => GistBrowser::show(
+                    ctx,
+                    &mut config.gists,
+                    &config.github,
+                    &mut config.dock.tree,
+                    &mut config.dock.counter,
+                ),
+                MenuCommand::ShareAll => GistExporter::show(
+                    ctx,
+                    &mut config.gist_export,
+                    &config.github,
+                    &config.dock.tree,
+                ),
+                MenuCommand::InstallToolchain(channel, component) => {
+                    ToolchainManager::install(
+                        ctx,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &mut config.toolchains,
+                        *channel,
+                        *component,
+                    );
+
+                    false
+                }
+            },
+
+            Command::TabCommand(command) => match command {
+                TabCommand::Add(v) => {
+                    Self::add_tab(
+                        &mut config.dock.tree,
+                        &mut config.dock.counter,
+                        *v,
+                        CodeEditor::default(),
+                    );
+
+                    false
+                }
+
+                TabCommand::AddWithCode(v, code) => {
+                    Self::add_tab(
+                        &mut config.dock.tree,
+                        &mut config.dock.counter,
+                        *v,
+                        CodeEditor::new(code.clone()),
+                    );
+
+                    false
+                }
+
+                TabCommand::Close(tab) => {
+                    // its run goes with it, the runs queued for it were dropped already
+                    Self::stop(ctx, &mut config.terminal, tab.id);
+                    config.terminal.finished.remove(&tab.id);
+
+                    // TODO: Remove TextEditState from closed tabs so they aren't reused with the same ID
+                    let editor_id = tab.id.with("code_edit");
+
+                    // cleanup old textedit state
+
+                    //let res = ctx.memory().data.remove::<TextEditState>(editor_id);
+
+                    //ctx.memory().data.remove::<TextEditState>(editor_id);
+
+                    Self::push_closed(&mut config.dock.closed, (**tab).clone());
+
+                    // the closed tab was just written to the drafts
+                    config.dock.drafts = load_drafts();
+
+                    Self::ensure_tab(&mut config.dock.tree, &mut config.dock.counter);
+
+                    false
+                }
+
+                TabCommand::ConfirmClose(id) => Self::show_close_window(
+                    ctx,
+                    *id,
+                    &mut config.dock.tree,
+                    &mut config.dock.counter,
+                    &mut config.dock.closed,
+                    &mut config.dock.drafts,
+                ),
+
+                TabCommand::ReopenClosed => {
+                    // a closed tab whose draft was restored since is open already
+                    while let Some(mut tab) = config.dock.closed.pop() {
+                        if Self::is_draft_open(&config.dock.tree, tab.draft_key) {
+                            continue;
+                        }
+
+                        // the old id may be in use by now, and state left behind under it shouldn't carry over
+                        tab.id = Id::new(format!("{}-reopened-{}", tab.name, config.dock.counter));
+                        config.dock.counter += 1;
+
+                        config.dock.tree.push_to_focused_leaf(tab);
+                        break;
+                    }
+
+                    false
+                }
+
+                TabCommand::Focus(id) => {
+                    Self::focus_tab(&mut config.dock.tree, *id);
+
+                    false
+                }
+
+                TabCommand::JumpToLine(id, line) => {
+                    if let Some(tab) = Self::focus_tab(&mut config.dock.tree, *id) {
+                        tab.editor.jump_to_line(*line);
+                    }
+
+                    false
+                }
+
+                TabCommand::Format(id) => Self::format_scratch(ctx, *id, &mut config.dock.tree),
+
+                TabCommand::TidyDirectives(id) => {
+                    Self::tidy_directives(&mut config.dock.tree, *id);
+
+                    false
+                }
+
+                TabCommand::OpenProjectFolder(id) => {
+                    Self::open_project(ctx, *id, &config.dock.tree, &config.general, false);
+                    false
+                }
+
+                TabCommand::OpenProjectInEditor(id) => {
+                    Self::open_project(ctx, *id, &config.dock.tree, &config.general, true);
+                    false
+                }
+
+                TabCommand::GoToStdSource(id) => Self::go_to_std_source(
+                    ctx,
+                    *id,
+                    &mut config.dock.tree,
+                    &mut config.dock.counter,
+                ),
+
+                TabCommand::OpenDefinition(krate, path, line) => {
+                    Self::open_source(
+                        ctx,
+                        krate,
+                        path.clone(),
+                        Some(*line),
+                        &mut config.dock.tree,
+                        &mut config.dock.counter,
+                    );
+                    false
+                }
+
+                TabCommand::Open(path) => {
+                    match fs::read(path) {
+                        Ok(bytes) => {
+                            let (code, encoding) = Encoding::decode(&bytes);
+
+                            let name = path
+                                .file_stem()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_string();
+
+                            let id = Id::new(format!("{name}-open-{}", config.dock.counter));
+                            let mut tab = Tab::new(name, id, CodeEditor::new(code));
+                            tab.encoding = encoding;
+                            // only the byte order mark would be lost, which some tools rely on
+                            tab.keep_encoding = encoding == Some(Encoding::Utf8Bom);
+
+                            if let Some(encoding) = encoding.filter(|e| e.is_converted()) {
+                                Toast::warning(format!(
+                                    "{} was converted from {} to UTF-8, it's saved as UTF-8 unless changed in the status bar",
+                                    path.display(),
+                                    encoding.label()
+                                ))
+                                .push(ctx);
+                            }
+
+                            config.dock.tree.push_to_focused_leaf(tab);
+
+                            config.dock.counter += 1;
+                        }
+
+                        Err(e) => Toast::error(format!("Failed to open {}: {e}", path.display()))
+                            .push(ctx),
+                    }
+
+                    false
+                }
+
+                TabCommand::Restore(draft) => {
+                    // two tabs writing the same draft would clobber each other
+                    if !Self::is_draft_open(&config.dock.tree, draft.key) {
+                        let tab = Tab::from_draft(draft, config.dock.counter);
+                        config.dock.tree.push_to_focused_leaf(tab);
+
+                        config.dock.counter += 1;
+                    }
+
+                    false
+                }
+
+                TabCommand::SwitchWorkspace(name) => {
+                    Self::switch_workspace(
+                        ctx,
+                        name,
+                        &mut config.dock.tree,
+                        &mut config.dock.counter,
+                        &mut config.terminal,
+                        &mut config.dock.workspace,
+                        &mut config.dock.workspaces,
+                        &mut config.dock.drafts,
+                    );
+
+                    if let Some((_, tab)) = config.dock.tree.find_active() {
+                        config.terminal.active_tab = Some(tab.id);
+                    }
+
+                    false
+                }
+
+                TabCommand::Detach(id) => {
+                    Self::detach_tab(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.dock.counter,
+                        &mut config.dock.drafts,
+                        *id,
+                    );
+
+                    false
+                }
+
+                TabCommand::Stop(id) => {
+                    Self::stop(ctx, &mut config.terminal, *id);
+
+                    false
+                }
+
+                TabCommand::Play(id) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Native,
+                    );
+
+                    false
+                }
+
+                TabCommand::PlayTests(id) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Test,
+                    );
+
+                    false
+                }
+
+                TabCommand::Proptest(id, function) => {
+                    if Self::add_proptest(ctx, *id, function, &mut config.dock.tree) {
+                        Self::play(
+                            ctx,
+                            &mut config.dock.tree,
+                            &mut config.terminal,
+                            &config.terminal_settings,
+                            &config.presets,
+                            config.general.projects_dir(),
+                            *id,
+                            RunMode::Test,
+                        );
+                    }
+
+                    false
+                }
+
+                TabCommand::PlaySanitized(id, sanitizer) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Sanitized(*sanitizer),
+                    );
+
+                    false
+                }
+
+                TabCommand::PlayLoom(id) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Loom,
+                    );
+
+                    false
+                }
+
+                TabCommand::PlayMiri(id) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Miri,
+                    );
+
+                    false
+                }
+
+                TabCommand::PlayClippy(id) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Clippy,
+                    );
+
+                    false
+                }
+
+                TabCommand::BinarySize(id) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Bloat,
+                    );
+
+                    false
+                }
+
+                TabCommand::Fuzz(id, function) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Fuzz {
+                            function: function.clone(),
+                            replay: None,
+                        },
+                    );
+
+                    false
+                }
+
+                TabCommand::FuzzReplay(id, function, input) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Fuzz {
+                            function: function.clone(),
+                            replay: Some(input.clone()),
+                        },
+                    );
+
+                    false
+                }
+
+                TabCommand::PlayWeb(id) => {
+                    Self::play(
+                        ctx,
+                        &mut config.dock.tree,
+                        &mut config.terminal,
+                        &config.terminal_settings,
+                        &config.presets,
+                        config.general.projects_dir(),
+                        *id,
+                        RunMode::Web,
+                    );
+
+                    false
+                }
+            },
+        });
+    }
+
+    /// Build and run the scratch of a tab in its terminal.
+    /// For the web, the scratch is built for wasm instead and opened in the browser once built.
+    /// Sanitized runs build on nightly for the host target with the sanitizer enabled,
+    /// loom runs wrap main in a model checking test. Fuzzing installs nightly and cargo-fuzz first if they're missing
+    #[allow(clippy::too_many_arguments)]
+    fn play(
+        ctx: &egui::Context,
+        tree: &mut Tree,
+        terminal: &mut Terminal,
+        settings: &TerminalSettings,
+        presets: &Presets,
+        base_dir: Option<PathBuf>,
+        id: Id,
+        mode: RunMode,
+    ) {
+        // a queued run, or one the watch started, may outlive its tab
+        let Some((node_index, tab_index)) = Self::find_tab(tree, id) else {
+            return;
+        };
+
+        let Node::Leaf { tabs, .. } = &mut tree[node_index] else {
+            return;
+        };
+
+        let tab = &mut tabs[tab_index.0];
+
+        let code = tab.editor.code.clone();
+
+        let preset = tab.preset.as_deref().and_then(|name| presets.get(name));
+        let env = preset.map_or_else(|| tab.env.clone(), |preset| tab.env.with_preset(preset));
+        let build =
+            preset.map_or_else(|| tab.build.clone(), |preset| tab.build.with_preset(preset));
+
+        tab.watch.last_run = Some(Instant::now());
+
+        record_run(&tab.name, &code);
+
+        // they look like they're read, nothing tells otherwise
+        let ignored = directives(&code)
+            .iter()
+            .filter(|directive| !directive.read)
+            .map(|directive| (directive.line + 1).to_string())
+            .collect::<Vec<_>>();
+
+        // told once for the code, not on every run of it
+        let warned_id = Id::new(IGNORED_DIRECTIVES).with(id);
+        let code_hash = egui::util::hash(&code);
+        let warned = ctx.data().get_temp::<u64>(warned_id) == Some(code_hash);
+
+        if ignored.is_empty() {
+            ctx.data().remove::<u64>(warned_id);
+        } else if !warned {
+            ctx.data().insert_temp(warned_id, code_hash);
+
+            Toast::warning(format!(
+                "The directives on line {} are below the code and ignored, move them above it",
+                ignored.join(", ")
+            ))
+            .push(ctx);
+        }
+
+        let assertion = OutputAssertion::new(&tab.watch.pattern, &tab.name).map(Arc::new);
+
+        // matching lines are highlighted in the terminal
+        terminal.assertions.insert(id, tab.watch.pattern.clone());
+
+        let project_id = Id::new(SCRATCH_PROJECT);
+        let mut out_project = Project::new(project_id);
+        out_project.target_prefix(build.target_prefix());
+        if let Some(base_dir) = &base_dir {
+            out_project.base_dir(base_dir);
+        }
+        let out_dir = out_project.wasm_out_dir();
+
+        // the terminal offers to replay crashes with the fuzzed function
+        let fuzz_id = Id::new(FUZZ_FUNCTION).with(id);
+        match &mode {
+            RunMode::Fuzz { function, .. } => ctx.data().insert_temp(fuzz_id, function.clone()),
+            _ => ctx.data().remove::<FuzzFn>(fuzz_id),
+        }
+
+        // and lists what Miri reported
+        let miri_id = Id::new(MIRI_RUN).with(id);
+        match &mode {
+            RunMode::Miri => ctx.data().insert_temp(miri_id, true),
+            _ => ctx.data().remove::<bool>(miri_id),
+        }
+
+        // or what clippy did
+        let lint_id = Id::new(LINT_RUN).with(id);
+        match &mode {
+            RunMode::Clippy => ctx.data().insert_temp(lint_id, true),
+            _ => ctx.data().remove::<bool>(lint_id),
+        }
+
+        // offered once the run succeeded, a scratch isn't asked twice. A failed run doesn't use it up
+        let suggested_name = (tab.has_default_name() && !tab.name_suggested)
+            .then(|| suggest_name(&code))
+            .flatten();
+
+        let web = mode == RunMode::Web;
+        let bloat = mode == RunMode::Bloat;
+        let name = tab.name.clone();
+        let draft_key = tab.draft_key;
+        let split = tab.split;
+        let edition = tab.edition();
+
+        // everything else needs cargo and its tools
+        let remote =
+            build.backend == Backend::Playground && matches!(mode, RunMode::Native | RunMode::Test);
+
+        let commands = move || -> Result<Vec<RunStep>, ProjectError> {
+            if remote {
+                return Ok(vec![RunStep::Playground(PlaygroundRun {
+                    code,
+                    channel: build.channel,
+                    edition,
+                    release: false,
+                    tests: mode == RunMode::Test,
+                })]);
+            }
+
+            // rustc is asked here so the ui doesn't wait on it
+            let host = matches!(mode, RunMode::Sanitized(_))
+                .then(host_target)
+                .flatten();
+            let (installs, replay) = match &mode {
+                RunMode::Fuzz { replay, .. } => (
+                    toolchain::fuzz_install_commands(),
+                    replay
+                        .as_ref()
+                        .map(|input| input.to_string_lossy().to_string()),
+                ),
+                RunMode::Bloat => (
+                    toolchain::install_cargo_tool_if_missing("bloat")
+                        .into_iter()
+                        .collect(),
+                    None,
+                ),
+                RunMode::Miri => (
+                    toolchain::component_install_commands(Channel::Nightly, "miri"),
+                    None,
+                ),
+                RunMode::Clippy => (
+                    toolchain::component_install_commands(build.channel, "clippy"),
+                    None,
+                ),
+                _ => (vec![], None),
+            };
+            // 0 leaves the search unbounded
+            let max_preemptions =
+                (build.loom_max_preemptions > 0).then(|| build.loom_max_preemptions.to_string());
+            let miri_flags = Some(build.miri_flags.trim()).filter(|flags| !flags.is_empty());
+            let lint_levels = build.lint_levels.split_whitespace().collect::<Vec<_>>();
+
+            let mut project = Project::new(project_id);
+
+            project
+                .build_type(BuildType::Debug)
+                .file(File::new("main", &code))
+                .edition(edition)
+                .subcommand(Subcommand::Run)
+                .target_prefix("rust-play")
+                .env_var("CARGO_TERM_COLOR", "always")
+                .env_var("CARGO_TERM_PROGRESS_WHEN", "always")
+                .env_var("CARGO_TERM_PROGRESS_WIDTH", "150");
+
+            if let Some(base_dir) = &base_dir {
+                project.base_dir(base_dir);
+            }
+
+            env.apply(&mut project);
+            build.apply(&mut project);
+
+            // a second cargo-bloat run lists the crates, the first one the functions
+            let mut by_crates = None;
+
+            match &mode {
+                RunMode::Sanitized(sanitizer) => {
+                    project.sanitizer(*sanitizer, host.as_deref());
+                }
+                RunMode::Loom => {
+                    project.loom(max_preemptions.as_deref());
+                }
+                RunMode::Miri => {
+                    project.miri(miri_flags);
+                }
+                RunMode::Clippy => {
+                    project.clippy(&lint_levels);
+                }
+                RunMode::Fuzz { function, .. } => match &replay {
+                    Some(input) => {
+                        project.fuzz_replay(function.clone(), input);
+                    }
+                    None => {
+                        project.fuzz(function.clone());
+                    }
+                },
+                RunMode::Test => {
+                    project.subcommand(Subcommand::Test);
+                }
+                RunMode::Bloat => {
+                    let mut crates = project.clone();
+                    crates.bloat(true);
+                    by_crates = Some(crates);
+
+                    project.bloat(false);
+                }
+                RunMode::Native | RunMode::Web => (),
+            }
+
+            if !web {
+                let mut commands = installs;
+                commands.push(project.create()?);
+
+                if let Some(mut crates) = by_crates {
+                    commands.push(crates.create()?);
+                }
+
+                return Ok(commands.into_iter().map(RunStep::from).collect());
+            }
+
+            // wasm can't run here, it's handed over to the browser instead
+            project.subcommand(Subcommand::Build).target(WASM_TARGET);
+
+            Ok(vec![
+                project.create()?.into(),
+                project.wasm_bindgen()?.into(),
+            ])
+        };
+
+        let toast_ctx = ctx.clone();
+        let on_success = move |stdout: &[u8]| {
+            // the build may have resolved the dependencies differently
+            CrateSourceBrowser::refresh(&toast_ctx, id);
+
+            if let Some(suggested) = suggested_name {
+                toast_ctx
+                    .data()
+                    .insert_temp(Id::new(NAME_SUGGESTED).with(id), true);
+
+                Toast::info(format!("Rename {name} to \"{suggested}\"?"))
+                    .action(ToastAction::Rename {
+                        label: "Rename".to_string(),
+                        id,
+                        name: suggested,
+                    })
+                    .push(&toast_ctx);
+            }
+
+            if bloat {
+                Self::show_binary_size(&toast_ctx, id, draft_key, &name, stdout);
+                return;
+            }
+
+            if !web {
+                return;
+            }
+
+            match serve::serve(out_dir) {
+                Ok(url) => {
+                    let _ = open::that(&url);
+
+                    Toast::success("Serving the wasm build")
+                        .action(ToastAction::Open {
+                            label: "Open again".to_string(),
+                            url: url.clone(),
+                        })
+                        .action(ToastAction::Copy {
+                            label: "Copy URL".to_string(),
+                            text: url,
+                        })
+                        .push(&toast_ctx);
+                }
+
+                Err(e) => {
+                    Toast::error(format!("Failed to serve the wasm build: {e}")).push(&toast_ctx)
+                }
+            }
+        };
+
+        Self::run_in_terminal(
+            ctx,
+            terminal,
+            id,
+            tab.name.clone(),
+            assertion,
+            commands,
+            on_success,
+        );
+
+        // the output is already in view beside the editor of a split tab
+        if settings.auto_open && !split && !terminal.open {
+            let size = settings.size_for(terminal, id);
+            terminal::Terminal::open(ctx, terminal, settings, size);
+            terminal.auto_opened = Some(id);
+        }
+    }
+
+    // record the size the cargo-bloat runs measured and queue the view of it for the tab
+    fn show_binary_size(ctx: &egui::Context, id: Id, draft_key: u64, name: &str, stdout: &[u8]) {
+        let Some(report) = bloat_report(&String::from_utf8_lossy(stdout)) else {
+            Toast::warning("cargo-bloat didn't report any sizes").push(ctx);
+            return;
+        };
+
+        record_binary_size(draft_key, name, report.file_size, report.text_size);
+
+        let view = SizeView::new(name, report, binary_sizes(draft_key));
+
+        {
+            let mut data = ctx.data();
+            data.insert_temp(Id::new(SIZE_VIEW).with(id), view);
+            data.insert_temp(Id::new(SIZE_VIEW_PENDING), id);
+        }
+
+        ctx.request_repaint();
+    }
+
+    /// Kill the process started by `run_in_terminal` in the tab, if it's still running
+    pub fn stop(ctx: &egui::Context, terminal: &mut Terminal, id: Id) {
+        if let Some(abort_id) = terminal.abortable.remove(&id) {
+            ctx.memory().data.remove::<Aborter>(abort_id);
+        }
+    }
+
+    // Only the last run started in a tab waits for the one still going, the ones before it would be stopped
+    // or queued just to start over with the same code
+    fn dedupe_runs(commands: &mut Vec<Command>) {
+        let runs = commands
+            .iter()
+            .map(|command| match command {
+                Command::TabCommand(command) => command.run_tab(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut i = 0;
+        commands.retain(|_| {
+            let later = &runs[i + 1..];
+            let keep = runs[i].map_or(true, |id| !later.contains(&Some(id)));
+            i += 1;
+
+            keep
+        });
+    }
+
+    // a run queued for a tab which is being closed would never start, it has no tab to run in
+    fn drop_runs_of_closed_tabs(commands: &mut Vec<Command>) {
+        let closed = commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::TabCommand(TabCommand::Close(tab)) => Some(tab.id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        commands.retain(|command| match command {
+            Command::TabCommand(command) => {
+                command.run_tab().map_or(true, |id| !closed.contains(&id))
+            }
+            _ => true,
+        });
+    }
+
+    // without cargo a run can't start, the setup window explains how to install it instead
+    fn drop_runs_without_cargo(ctx: &egui::Context, config: &mut Config) {
+        if !config.setup.is_cargo_missing() {
+            return;
+        }
+
+        let commands = config.dock.commands.len();
+
+        let tree = &config.dock.tree;
+        let on_playground = |id: Id| {
+            Self::find_tab(tree, id).map_or(false, |(node_index, tab_index)| {
+                let Node::Leaf { tabs, .. } = &tree[node_index] else {
+                    return false;
+                };
+
+                tabs[tab_index.0].build.backend == Backend::Playground
+            })
+        };
+
+        // the playground runs and tests them without cargo
+        config.dock.commands.retain(|command| match command {
+            Command::TabCommand(TabCommand::Play(id) | TabCommand::PlayTests(id)) => {
+                on_playground(*id)
+            }
+            Command::TabCommand(command) => command.run_tab().is_none(),
+            _ => true,
+        });
+
+        if config.dock.commands.len() < commands {
+            ctx.data().insert_temp(Id::new(MISSING_TOOL), true);
+            ctx.request_repaint();
+        }
+    }
+
+    // A tab runs one thing at a time, a second run would build the same project while the first one is.
+    // The run still going is stopped first when restarting, and left to finish when queueing
+    fn waits_for_run(
+        ctx: &egui::Context,
+        terminal: &mut Terminal,
+        run_again: RunAgain,
+        id: Id,
+    ) -> bool {
+        let finished = terminal
+            .finished
+            .get(&id)
+            .map_or(true, |finished| finished.load(Ordering::Relaxed));
+
+        if finished {
+            return false;
+        }
+
+        if run_again == RunAgain::Restart {
+            Self::stop(ctx, terminal, id);
+        }
+
+        true
+    }
+
+    /// Whether a process started by `run_in_terminal` is still running in the tab
+    pub fn is_running(ctx: &egui::Context, terminal: &Terminal, id: Id) -> bool {
+        terminal.abortable.get(&id).map_or(false, |abort_id| {
+            ctx.memory().data.get_temp::<Aborter>(*abort_id).is_some()
+        })
+    }
+
+    /// Spawn processes in the background one after another, streaming their output to the tab's terminal.
+    /// The chain stops at the first failure, `on_success` runs with the stdout of all of them once they succeeded.
+    /// A process still running in the tab is aborted first. `label` names the run in toasts
+    pub fn run_in_terminal<S: Into<RunStep>>(
+        ctx: &egui::Context,
+        terminal: &mut Terminal,
+        id: Id,
+        label: String,
+        assertion: Option<Arc<OutputAssertion>>,
+        commands: impl FnOnce() -> Result<Vec<S>, ProjectError> + Send + 'static,
+        on_success: impl FnOnce(&[u8]) + Send + 'static,
+    ) {
+        // this are used as a thread abort signaler
+        let (atx, arx) = channel();
+
+        let mut rng = rand::thread_rng();
+        let abort_rid: u64 = rng.gen();
+
+        let abort_id = id.with(format!("_thread_aborter_{abort_rid}"));
+
+        let prev = terminal.abortable.insert(id, abort_id);
+        // if there's a previous process running, send the signal abort
+        if let Some(atx) = prev {
+            let mut mem = ctx.memory();
+            if mem.data.get_temp::<Aborter>(atx).is_some() {
+                mem.data.remove::<Aborter>(atx);
+            }
+        }
+
+        ctx.memory()
+            .data
+            .insert_temp::<Aborter>(abort_id, Arc::new(Mutex::new(atx)));
+
+        // these are used to stream the terminal output
+        let rb_stdout = HeapRb::<String>::new(OUTPUT_QUEUE_LEN);
+        let rb_stderr = HeapRb::<String>::new(OUTPUT_QUEUE_LEN);
+
+        let (mut rb_stdout, rb_stdout_read) = rb_stdout.split();
+        let (mut rb_stderr, rb_stderr_read) = rb_stderr.split();
+
+        terminal
+            .content
+            .insert(id, Some((rb_stdout_read, rb_stderr_read)));
+
+        let owned_ctx = ctx.clone();
+
+        // the output of the new run starts out fresh
+        terminal.output.remove(&id);
+
+        // a stale result from an earlier run must not close the terminal
+        ctx.data().remove::<Id>(Id::new(QUIET_RUN));
+        ctx.data()
+            .remove::<Arc<Vec<u8>>>(Id::new(BINARY_OUTPUT).with(id));
+
+        // cleared as soon as the process prints anything besides cargo's status lines
+        let quiet = Arc::new(AtomicBool::new(true));
+
+        let tab_id = id;
+
+        let finished = Arc::new(AtomicBool::new(false));
+        terminal.finished.insert(id, finished.clone());
+
+        thread::spawn(move || {
+            let id = Id::new("continuous_mode");
+
+            let ctx = owned_ctx;
+
+            // a run waiting on this one may start once it's dropped
+            let _finished = FinishedGuard {
+                finished,
+                ctx: ctx.clone(),
+            };
+
+            // a counter used to indicate when continuous mode is on. It is on as long as any threads are still running
+            {
+                let mut mem = ctx.memory();
+                let counter = mem.data.get_temp_mut_or_default::<u64>(id);
+                *counter += 1;
+            }
+
+            // the process currently running, so it can be killed on abort
+            let running = Arc::new(Mutex::new(None::<Killer>));
+            let aborted = Arc::new(AtomicBool::new(false));
+
+            // special thread which checks for abort code
+            // the signal is also dropped once every process is done
+            {
+                let running = running.clone();
+                let aborted = aborted.clone();
+
+                thread::spawn(move || {
+                    // blocking wait for abort
+                    let _ = arx.recv();
+
+                    aborted.store(true, Ordering::Relaxed);
+                    if let Some(killer) = running.lock().unwrap().as_ref() {
+                        let _ = killer.kill();
+                    }
+                });
+            }
+
+            let started = Instant::now();
+            let mut success = true;
+
+            // raw stdout, shown in the hex viewer if it isn't valid utf-8
+            let mut binary = vec![];
+            let mut invalid = false;
+
+            // how long cargo took to compile, slow builds may be antivirus scanning
+            let mut clock = BuildClock::default();
+
+            // a project which couldn't be created is reported, the run just doesn't start
+            let commands = commands().unwrap_or_else(|e| {
+                push_line(&mut rb_stderr, format!("{e}\n"), &aborted);
+                Toast::error(format!("{label} couldn't start: {e}")).push(&ctx);
+
+                success = false;
+                vec![]
+            });
+
+            for step in commands {
+                let mut command = match step.into() {
+                    RunStep::Process(command) => command,
+
+                    // stopping the run stops waiting for the reply, the output comes all at once
+                    RunStep::Playground(run) => {
+                        push_line(
+                            &mut rb_stderr,
+                            "Running on play.rust-lang.org...\n".to_string(),
+                            &aborted,
+                        );
+                        ctx.request_repaint();
+
+                        let execution = match run.execute_abortable(&aborted) {
+                            Ok(execution) => execution,
+
+                            Err(PlaygroundError::Aborted) => {
+                                success = false;
+                                break;
+                            }
+
+                            Err(e) => {
+                                push_line(&mut rb_stderr, format!("{e}\n"), &aborted);
+                                Toast::error(format!("{label} couldn't run: {e}")).push(&ctx);
+
+                                success = false;
+                                break;
+                            }
+                        };
+
+                        for line in execution.stderr.split_inclusive('\n') {
+                            if let Some(assertion) = &assertion {
+                                assertion.check(line);
+                            }
+
+                            push_line(&mut rb_stderr, line.to_string(), &aborted);
+                        }
+
+                        for line in execution.stdout.split_inclusive('\n') {
+                            if binary.len() + line.len() <= MAX_BINARY_OUTPUT {
+                                binary.extend_from_slice(line.as_bytes());
+                            }
+
+                            if let Some(assertion) = &assertion {
+                                assertion.check(line);
+                            }
+
+                            if !line.trim().is_empty() {
+                                quiet.store(false, Ordering::Relaxed);
+                            }
+
+                            push_line(&mut rb_stdout, line.to_string(), &aborted);
+                        }
+
+                        ctx.request_repaint();
+
+                        if !execution.success {
+                            success = false;
+                            break;
+                        }
+
+                        continue;
+                    }
+                };
+
+                // hide the console window from command. Very important.
+                #[cfg(target_os = "windows")]
+                command.creation_flags(CREATE_NO_WINDOW.0);
+
+                let program = command.get_program().to_string_lossy().to_string();
+
+                let process = match Process::spawn(command) {
+                    Ok(process) => process,
+                    Err(e) => {
+                        let line = format!("Failed to run `{program}`: {e}\n");
+                        push_line(&mut rb_stderr, line, &aborted);
+
+                        if e.kind() == io::ErrorKind::NotFound {
+                            let rust_tool = is_rust_tool(&program);
+                            push_line(&mut rb_stderr, install_hint(&program), &aborted);
+
+                            // e.g. cargo isn't installed, the setup window explains what to do
+                            if rust_tool {
+                                ctx.data().insert_temp(Id::new(MISSING_TOOL), true);
+                            }
+                        }
+
+                        Toast::error(format!("{label} couldn't start: {e}")).push(&ctx);
+                        ctx.request_repaint();
+
+                        success = false;
+                        break;
+                    }
+                };
+
+                let Process {
+                    stdout,
+                    stderr,
+                    status,
+                    killer,
+                } = process;
+
+                {
+                    let mut running = running.lock().unwrap();
+
+                    // the abort came in while the process was starting up
+                    if aborted.load(Ordering::Relaxed) {
+                        let _ = killer.kill();
+                    }
+
+                    *running = Some(killer);
+                }
+
+                // kick off the repaints
+                ctx.request_repaint();
+
+                thread::scope(|scope| {
+                    scope.spawn(|| {
+                        for line in stdout {
+                            let bytes = line.as_bytes();
+
+                            // keep the raw bytes around in case it's binary output
+                            if binary.len() + bytes.len() <= MAX_BINARY_OUTPUT {
+                                binary.extend_from_slice(bytes);
+                            }
+
+                            if std::str::from_utf8(bytes).is_err() {
+                                invalid = true;
+                            }
+
+                            let line = to_line(&line);
+
+                            if let Some(assertion) = &assertion {
+                                assertion.check(&line);
+                            }
+
+                            if !line.trim().is_empty() {
+                                quiet.store(false, Ordering::Relaxed);
+                            }
+
+                            push_line(&mut rb_stdout, line, &aborted);
+                        }
+                    });
+
+                    for line in stderr {
+                        let line = to_line(&line);
+
+                        clock.line(&line);
+
+                        if let Some(assertion) = &assertion {
+                            assertion.check(&line);
+                        }
+
+                        if !is_cargo_noise(&line) {
+                            quiet.store(false, Ordering::Relaxed);
+                        }
+
+                        push_line(&mut rb_stderr, line, &aborted);
+                    }
+                });
+
+                let status = status.recv();
+                running.lock().unwrap().take();
+
+                if !matches!(status, Ok(Ok(status)) if status.success()) {
+                    success = false;
+                    break;
+                }
+            }
+
+            {
+                let mut mem = ctx.memory();
+                let counter = mem.data.get_temp_mut_or_default::<u64>(id);
+                *counter -= 1;
+
+                let aborter = mem.data.get_temp::<Aborter>(abort_id);
+                if aborter.is_some() {
+                    mem.data.remove::<Aborter>(abort_id);
+                }
+            }
+
+            let binary = Arc::new(binary);
+
+            if invalid {
+                ctx.data()
+                    .insert_temp(Id::new(BINARY_OUTPUT).with(tab_id), binary.clone());
+            }
+
+            if let Some(slow) = clock.slow() {
+                ctx.data().insert_temp(Id::new(SLOW_BUILD), slow);
+                ctx.request_repaint();
+            }
+
+            if !aborted.load(Ordering::Relaxed) && started.elapsed() >= LONG_RUN {
+                let secs = started.elapsed().as_secs_f32();
+
+                let toast = if success {
+                    Toast::success(format!("{label} finished in {secs:.1}s"))
+                } else {
+                    Toast::error(format!("{label} failed after {secs:.1}s"))
+                };
+
+                toast.push(&ctx);
+            }
+
+            if success {
+                on_success(&binary);
+
+                if quiet.load(Ordering::Relaxed) {
+                    ctx.data().insert_temp(Id::new(QUIET_RUN), tab_id);
+                    ctx.request_repaint();
+                }
+            }
+        });
+    }
+
+    fn push_closed(closed: &mut Vec<Tab>, tab: Tab) {
+        closed.push(tab);
+
+        if closed.len() > MAX_CLOSED_TABS {
+            closed.remove(0);
+        }
+    }
+
+    // whether a tab writing the draft is open
+    fn is_draft_open(tree: &Tree, key: u64) -> bool {
+        tree.iter().any(|node| {
+            let Node::Leaf { tabs, .. } = node else {
+                return false;
+            };
+
+            tabs.iter().any(|tab| tab.draft_key == key)
+        })
+    }
+
+    // there must always be at least one tab open
+    fn ensure_tab(tree: &mut Tree, counter: &mut u32) {
+        if tree.num_tabs() == 0 {
+            let tab = Tab::new(
+                "Scratch 1".to_string(),
+                Id::new("Scratch 1"),
+                CodeEditor::default(),
+            );
+
+            tree.set_focused_node(NodeIndex(0));
+            tree.push_to_focused_leaf(tab);
+
+            *counter = 2;
+        }
+    }
+
+    // move a tab to a window of its own, a second instance showing just its scratch
+    fn detach_tab(
+        ctx: &egui::Context,
+        tree: &mut Tree,
+        counter: &mut u32,
+        drafts: &mut Vec<Draft>,
+        id: Id,
+    ) {
+        let Some((node_index, tab_index)) = Self::find_tab(tree, id) else {
+            return;
+        };
+
+        let Node::Leaf { tabs, .. } = &tree[node_index] else {
+            return;
+        };
+
+        // the new window loads the scratch from its draft
+        let draft = tabs[tab_index.0].draft();
+
+        if let Err(e) = draft.save().and_then(|_| ipc::spawn_detached(draft.key)) {
+            Toast::error(format!("Failed to open a new window: {e}")).push(ctx);
+            return;
+        }
+
+        tree.remove_tab((node_index, tab_index));
+        *drafts = load_drafts();
+
+        Self::ensure_tab(tree, counter);
+    }
+
+    // keep the current tabs in their workspace, or as drafts if they're in none, then show the other workspace's
+    fn switch_workspace(
+        ctx: &egui::Context,
+        name: &str,
+        tree: &mut Tree,
+        counter: &mut u32,
+        terminal: &mut Terminal,
+        workspace: &mut Option<String>,
+        workspaces: &mut Vec<String>,
+        drafts: &mut Vec<Draft>,
+    ) {
+        let Some(mut loaded) = load_workspace(name) else {
+            Toast::error(format!("Failed to load the workspace {name}")).push(ctx);
+            return;
+        };
+
+        if let Some(current) = workspace {
+            if let Err(e) = save_workspace(current, tree) {
+                Toast::error(format!("Failed to save the workspace {current}: {e}")).push(ctx);
+                return;
+            }
+        }
+
+        for node in tree.iter_mut() {
+            if let Node::Leaf { tabs, .. } = node {
+                for tab in tabs {
+                    tab.save_draft();
+
+                    // nothing would show their output anymore
+                    Self::stop(ctx, terminal, tab.id);
+                }
+            }
+        }
+
+        // the ids saved with the workspace may be taken by now, the counter keeps going so they stay unique
+        for node in loaded.iter_mut() {
+            if let Node::Leaf { tabs, .. } = node {
+                for tab in tabs {
+                    tab.id = Id::new(format!("{}-workspace-{counter}", tab.name));
+                    *counter += 1;
+                }
+            }
+        }
+
+        // an empty workspace gets a scratch here, rather than one numbered from 1 again
+        if loaded.num_tabs() == 0 {
+            let name = format!("Scratch {counter}");
+            let id = Id::new(format!("{name}-workspace-{counter}"));
+
+            loaded = Tree::new(vec![Tab::new(name, id, CodeEditor::default())]);
+            *counter += 1;
+        }
+
+        if workspace.is_none() {
+            Toast::info(
+                "The previous tabs weren't in a workspace, they can be reopened from File > Recent",
+            )
+            .push(ctx);
+        }
+
+        *tree = loaded;
+        *workspace = Some(name.to_string());
+        *workspaces = load_workspaces();
+        *drafts = load_drafts();
+    }
+
+    fn show_workspace_window(
+        ctx: &egui::Context,
+        tree: &Tree,
+        workspace: &mut Option<String>,
+        workspaces: &mut Vec<String>,
+    ) -> bool {
+        let name_id = Id::new("workspace_window_name");
+        let mut name = ctx
+            .data()
+            .get_temp::<String>(name_id)
+            .or_else(|| workspace.clone())
+            .unwrap_or_default();
+
+        let mut open = true;
+        let mut save = false;
+
+        Window::new("Save workspace")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                let response = ui.add(TextEdit::singleline(&mut name).hint_text("Name"));
+                let entered = response.lost_focus() && ui.input().key_pressed(Key::Enter);
+
+                if workspaces.iter().any(|saved| saved == name.trim()) {
+                    ui.weak("Replaces the saved workspace of this name");
+                }
+
+                let valid = !name.trim().is_empty();
+                let clicked = ui.add_enabled(valid, Button::new("Save")).clicked();
+
+                save = valid && (clicked || entered);
+            });
+
+        if save || !open {
+            ctx.data().remove::<String>(name_id);
+        } else {
+            ctx.data().insert_temp(name_id, name.clone());
+        }
+
+        if save {
+            let name = name.trim();
+
+            match save_workspace(name, tree) {
+                Ok(()) => {
+                    *workspace = Some(name.to_string());
+                    *workspaces = load_workspaces();
+
+                    Toast::success(format!("Saved the workspace {name}")).push(ctx);
+                }
+
+                Err(e) => Toast::error(format!("Failed to save the workspace: {e}")).push(ctx),
+            }
+
+            return false;
+        }
+
+        open
+    }
+
+    // bring a tab to the front of its leaf and focus the leaf
+    fn focus_tab(tree: &mut Tree, id: Id) -> Option<&mut Tab> {
+        let (node_index, tab_index) = Self::find_tab(tree, id)?;
+
+        tree.set_focused_node(node_index);
+
+        let Node::Leaf { tabs, active, .. } = &mut tree[node_index] else {
+            return None;
+        };
+
+        *active = tab_index;
+
+        tabs.get_mut(tab_index.0)
+    }
+
+    fn find_tab(tree: &Tree, id: Id) -> Option<(NodeIndex, TabIndex)> {
+        tree.iter().enumerate().find_map(|(node_index, node)| {
+            let Node::Leaf { tabs, .. } = node else {
+                return None;
+            };
+
+            let tab_index = tabs.iter().position(|tab| tab.id == id)?;
+
+            Some((NodeIndex(node_index), TabIndex(tab_index)))
+        })
+    }
+
+    fn show_close_window(
+        ctx: &egui::Context,
+        id: Id,
+        tree: &mut Tree,
+        counter: &mut u32,
+        closed: &mut Vec<Tab>,
+        drafts: &mut Vec<Draft>,
+    ) -> bool {
+        let Some((node_index, tab_index)) = Self::find_tab(tree, id) else {
+            return false;
+        };
+
+        let Node::Leaf { tabs, .. } = &mut tree[node_index] else {
+            return false;
+        };
+
+        let tab = &mut tabs[tab_index.0];
+
+        let mut open = true;
+        let mut cancel = false;
+        // Some(true) saves before closing, Some(false) discards the changes. Nothing is lost closing a tab
+        // without any, e.g. with vim's :q
+        let mut choice = (!tab.is_dirty()).then_some(false);
+
+        if choice.is_none() {
+            Window::new("Unsaved changes")
+                .id(id.with("close_window"))
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{} has unsaved changes. Save them before closing?",
+                        tab.name
+                    ));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            choice = Some(true);
+                        }
+
+                        if ui.button("Don't save").clicked() {
+                            choice = Some(false);
+                        }
+
+                        cancel = ui.button("Cancel").clicked();
+                    });
+                });
+        }
+
+        let Some(save) = choice else {
+            return open && !cancel;
+        };
+
+        // keep asking if the save dialog was cancelled
+        if save && !Self::save_to_disk(ctx, tab) {
+            return true;
+        }
+
+        if let Some(mut tab) = tree.remove_tab((node_index, tab_index)) {
+            tab.save_draft();
+            Self::push_closed(closed, tab);
+
+            *drafts = load_drafts();
+        }
+
+        Self::ensure_tab(tree, counter);
+
+        false
+    }
+
+    fn add_tab(tree: &mut Tree, counter: &mut u32, node: NodeIndex, editor: CodeEditor) {
+        let name = format!("Scratch {counter}");
+
+        let node_tabs = &tree[node];
+
+        // unique name based on current nodeindex + tabindex
+        let id = Id::new(format!("{name}-{}-{}", node.0, node_tabs.tabs_count() + 1));
+        let tab = Tab::new(name, id, editor);
+
+        tree.set_focused_node(node);
+        tree.push_to_focused_leaf(tab);
+
+        *counter += 1;
+    }
+
+    fn show_template_window(
+        ctx: &egui::Context,
+        node: NodeIndex,
+        tree: &mut Tree,
+        counter: &mut u32,
+        templates: &mut Vec<Template>,
+        run: &mut Option<Template>,
+    ) -> bool {
+        let selected_id = Id::new("template_window_selected");
+        let mut selected = ctx
+            .data()
+            .get_temp::<usize>(selected_id)
+            .unwrap_or_default();
+
+        let mut open = true;
+        let mut create = false;
+
+        Window::new("New from template")
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Open templates folder").clicked() {
+                        let _ = open::that(templates_dir());
+                    }
+
+                    if ui.button("Reload").clicked() {
+                        *templates = load_templates();
+                    }
+                });
+
+                ui.separator();
+
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        for (i, template) in templates.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.selectable_value(&mut selected, i, &template.name);
+
+                                if Self::quick_run_button(ui) {
+                                    *run = Some(template.clone());
+                                }
+                            });
+                        }
+                    });
+
+                    ui.separator();
+
+                    if let Some(template) = templates.get(selected) {
+                        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                            ui.code(&template.code);
+                        });
+                    }
+                });
+
+                ui.separator();
+
+                create = ui.button("Create").clicked();
+            });
+
+        ctx.data().insert_temp(selected_id, selected);
+
+        if create {
+            if let Some(template) = templates.get(selected) {
+                let editor = CodeEditor::new(template.code.clone());
+                Self::add_tab(tree, counter, node, editor);
+            }
+
+            return false;
+        }
+
+        open
+    }
+
+    // shown while the pointer is over the row of a template, so the list isn't a wall of buttons
+    fn quick_run_button(ui: &mut Ui) -> bool {
+        if !ui.rect_contains_pointer(ui.max_rect()) {
+            return false;
+        }
+
+        ui.small_button("▶")
+            .on_hover_text("Quick run, shows the output without opening a tab")
+            .clicked()
+    }
+
+    // a binary size run finished, its window replaces the one of the tab's previous run
+    fn open_binary_size(ctx: &egui::Context, config: &mut Config) {
+        let pending = ctx.data().get_temp::<Id>(Id::new(SIZE_VIEW_PENDING));
+        let Some(id) = pending else {
+            return;
+        };
+
+        ctx.data().remove::<Id>(Id::new(SIZE_VIEW_PENDING));
+
+        let view = ctx.data().get_temp::<SizeView>(Id::new(SIZE_VIEW).with(id));
+        let Some(view) = view else {
+            return;
+        };
+
+        config.dock.commands.retain(|command| {
+            !matches!(command, Command::MenuCommand(MenuCommand::BinarySize(open)) if open.id == view.id)
+        });
+
+        config
+            .dock
+            .commands
+            .push(Command::MenuCommand(MenuCommand::BinarySize(view)));
+    }
+
+    // the runs which offered a name can't reach their tab, they leave a mark for it
+    fn mark_suggested_names(ctx: &egui::Context, tree: &mut Tree) {
+        for node in tree.iter_mut() {
+            let Node::Leaf { tabs, .. } = node else {
+                continue;
+            };
+
+            for tab in tabs.iter_mut().filter(|tab| !tab.name_suggested) {
+                let id = Id::new(NAME_SUGGESTED).with(tab.id);
+
+                if ctx.data().get_temp::<bool>(id).is_some() {
+                    ctx.data().remove::<bool>(id);
+                    tab.name_suggested = true;
+                }
+            }
+        }
+    }
+
+    // queue a run for every tab in watch mode whose interval has elapsed since its last run finished
+    fn schedule_watched(ctx: &egui::Context, config: &mut Config) {
+        for node in config.dock.tree.iter_mut() {
+            let Node::Leaf { tabs, .. } = node else {
+                continue;
+            };
+
+            for tab in tabs.iter_mut().filter(|tab| tab.watch.enabled) {
+                // a new run would abort the one still going, so a slow program would never get to finish
+                if TabEvents::is_running(ctx, &config.terminal, tab.id) {
+                    tab.watch.last_run = Some(Instant::now());
+                    continue;
+                }
+
+                let interval = Duration::from_secs(tab.watch.interval.max(1));
+
+                match tab.watch.last_run.map(|t| t.elapsed()) {
+                    Some(elapsed) if elapsed < interval => {
+                        ctx.request_repaint_after(interval - elapsed);
+                    }
+
+                    _ => config
+                        .dock
+                        .commands
+                        .push(Command::TabCommand(TabCommand::Play(tab.id))),
+                }
+            }
+        }
+    }
+
+    fn autosave(ctx: &egui::Context, config: &mut Config) {
+        if config.general.autosave_interval == 0 {
+            return;
+        }
+
+        let interval = Duration::from_secs(config.general.autosave_interval);
+        let elapsed = config.dock.last_autosave.elapsed();
+
+        if elapsed < interval {
+            ctx.request_repaint_after(interval - elapsed);
+            return;
+        }
+
+        Self::save_drafts(&mut config.dock);
+    }
+
+    /// The drafts the tabs write to
+    pub fn draft_keys(tree: &Tree) -> Vec<u64> {
+        tree.iter()
+            .filter_map(|node| match node {
+                Node::Leaf { tabs, .. } => Some(tabs),
+                _ => None,
+            })
+            .flatten()
+            .map(|tab| tab.draft_key)
+            .collect()
+    }
+
+    /// Write every changed tab to the drafts directory
+    pub fn save_drafts(dock: &mut DockConfig) {
+        let mut saved = false;
+
+        for node in dock.tree.iter_mut() {
+            let Node::Leaf { tabs, .. } = node else {
+                continue;
+            };
+
+            for tab in tabs {
+                saved |= tab.save_draft();
+            }
+        }
+
+        if saved {
+            dock.drafts = load_drafts();
+        }
+
+        dock.last_autosave = Instant::now();
+    }
+
+    // edit the name in place of the tab's title, it's shown by the dock
+    fn start_rename(ctx: &egui::Context, tree: &Tree, id: Id) {
+        let name = tree.iter().find_map(|node| match node {
+            Node::Leaf { tabs, .. } => tabs.iter().find(|tab| tab.id == id),
+            _ => None,
+        });
+
+        if let Some(tab) = name {
+            DockView::rename(ctx, id, tab.name.clone());
+        }
+    }
+
+    fn save_scratch(ctx: &egui::Context, id: Id, tree: &mut Tree, tidy_directives: bool) -> bool {
+        let Some(tab) = tree.iter_mut().find_map(|node| {
+            let Node::Leaf { tabs, .. } = node else {
+                return None;
+            };
+
+            tabs.iter_mut().find(|tab| tab.id == id)
+        }) else {
+            return false;
+        };
+
+        if tidy_directives && !tab.editor.is_read_only() {
+            tab.editor.code = format_directives(&tab.editor.code);
+        }
+
+        Self::save_to_disk(ctx, tab);
+
+        false
+    }
+
+    // every tab if there's no id, read-only tabs are left alone
+    fn tidy_directives(tree: &mut Tree, id: Option<Id>) {
+        for node in tree.iter_mut() {
+            let Node::Leaf { tabs, .. } = node else {
+                continue;
+            };
+
+            let tabs = tabs
+                .iter_mut()
+                .filter(|tab| id.map_or(true, |id| tab.id == id) && !tab.editor.is_read_only());
+
+            for tab in tabs {
+                let code = format_directives(&tab.editor.code);
+
+                // unchanged code isn't written, so it doesn't count as an edit
+                if code != tab.editor.code {
+                    tab.editor.code = code;
+                }
+            }
+        }
+    }
+
+    // append a proptest scaffold for the function to the code, returns whether it was found
+    fn add_proptest(ctx: &egui::Context, id: Id, function: &str, tree: &mut Tree) -> bool {
+        let Some(tab) = tree.iter_mut().find_map(|node| {
+            let Node::Leaf { tabs, .. } = node else {
+                return None;
+            };
+
+            tabs.iter_mut().find(|tab| tab.id == id)
+        }) else {
+            return false;
+        };
+
+        let Some(code) = insert_proptest(&tab.editor.code, function) else {
+            Toast::error(format!("Couldn't find a function `{function}` to test")).push(ctx);
+
+            return false;
+        };
+
+        tab.editor.code = code;
+
+        true
+    }
+
+    // rustfmt runs in the background, the command stays queued until its result is in
+    fn format_scratch(ctx: &egui::Context, id: Id, tree: &mut Tree) -> bool {
+        let result_id = Id::new(FORMAT_RESULT).with(id);
+
+        let result = ctx
+            .data()
+            .get_temp::<Option<Result<String, String>>>(result_id);
+
+        let Some(result) = result else {
+            let Some((node_index, tab_index)) = Self::find_tab(tree, id) else {
+                return false;
+            };
+
+            let Node::Leaf { tabs, .. } = &tree[node_index] else {
+                return false;
+            };
+
+            let code = tabs[tab_index.0].editor.code.clone();
+
+            // None marks it as running
+            ctx.data()
+                .insert_temp::<Option<Result<String, String>>>(result_id, None);
+
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                let result = toolchain::rustfmt(&code);
+
+                ctx.data().insert_temp(result_id, Some(result));
+                ctx.request_repaint();
+            });
+
+            return true;
+        };
+
+        let Some(result) = result else {
+            return true;
+        };
+
+        ctx.data()
+            .remove::<Option<Result<String, String>>>(result_id);
+
+        match result {
+            Ok(code) => {
+                if let Some(tab) = tree.iter_mut().find_map(|node| {
+                    let Node::Leaf { tabs, .. } = node else {
+                        return None;
+                    };
+
+                    tabs.iter_mut().find(|tab| tab.id == id)
+                }) {
+                    tab.editor.code = code;
+                }
+            }
+
+            Err(e) => Toast::error(format!("Failed to format the code:\n{e}")).push(ctx),
+        }
+
+        false
+    }
+
+    // open the folder of a tab's project, or open it in an editor. It only exists once the tab was run
+    fn open_project(
+        ctx: &egui::Context,
+        id: Id,
+        tree: &Tree,
+        general: &GeneralConfig,
+        in_editor: bool,
+    ) {
+        let Some((node_index, tab_index)) = Self::find_tab(tree, id) else {
+            return;
+        };
+
+        let Node::Leaf { tabs, .. } = &tree[node_index] else {
+            return;
+        };
+
+        let base_dir = general.projects_dir();
+        let project = tabs[tab_index.0].build.project(base_dir.as_deref());
+
+        let Some(dir) = project.path() else {
+            Toast::warning("Run the scratch first, its project isn't generated until then")
+                .push(ctx);
+            return;
+        };
+
+        let result = if in_editor {
+            general
+                .editor_command(&dir)
+                .spawn()
+                .map(|_| ())
+                .map_err(|e| format!("{} couldn't start: {e}", general.editor_name()))
+        } else {
+            open::that(&dir).map_err(|e| e.to_string())
+        };
+
+        if let Err(e) = result {
+            Toast::error(format!("Failed to open {}: {e}", dir.display())).push(ctx);
+        }
+    }
+
+    // the definition is searched for in the background, the command stays queued until it's found
+    fn go_to_std_source(ctx: &egui::Context, id: Id, tree: &mut Tree, counter: &mut u32) -> bool {
+        let result_id = Id::new(STD_SOURCE).with(id);
+
+        let result = ctx
+            .data()
+            .get_temp::<Option<Result<Definition, String>>>(result_id);
+
+        let Some(result) = result else {
+            let Some((node_index, tab_index)) = Self::find_tab(tree, id) else {
+                return false;
+            };
+
+            let Node::Leaf { tabs, .. } = &tree[node_index] else {
+                return false;
+            };
+
+            let tab = &tabs[tab_index.0];
+            let cursor = CodeEditor::cursor(ctx, tab.id.with("code_editor"));
+
+            let Some(item) =
+                cursor.and_then(|cursor| std_source::item_at(&tab.editor.code, cursor))
+            else {
+                Toast::warning("Place the cursor on an item of std to go to its source").push(ctx);
+                return false;
+            };
+
+            // None marks it as running
+            ctx.data()
+                .insert_temp::<Option<Result<Definition, String>>>(result_id, None);
+
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                let result = Self::find_std_definition(&ctx, &item);
+
+                ctx.data().insert_temp(result_id, Some(result));
+                ctx.request_repaint();
+            });
+
+            return true;
+        };
+
+        let Some(result) = result else {
+            return true;
+        };
+
+        ctx.data()
+            .remove::<Option<Result<Definition, String>>>(result_id);
+
+        match result {
+            Ok(definition) => Self::open_source(
+                ctx,
+                &definition.krate,
+                definition.path,
+                Some(definition.line),
+                tree,
+                counter,
+            ),
+
+            Err(e) => Toast::error(e).push(ctx),
+        }
+
+        false
+    }
+
+    // installs rust-src first if it's missing
+    fn find_std_definition(ctx: &egui::Context, item: &str) -> Result<Definition, String> {
+        let library = match toolchain::rust_src_dir() {
+            Some(library) => library,
+            None => {
+                Toast::info("Installing the rust-src component...").push(ctx);
+
+                if !toolchain::install_rust_src() {
+                    return Err("Failed to install the rust-src component".to_string());
+                }
+
+                toolchain::rust_src_dir()
+                    .ok_or_else(|| "The rust-src component wasn't found".to_string())?
+            }
+        };
+
+        std_source::find_definition(&library, item)
+            .ok_or_else(|| format!("Couldn't find `{item}` in the source of std"))
+    }
+
+    /// Show a file in a read-only tab named after the crate it's from, the one it's already open in
+    /// if there is one. The tab scrolls to the line if given
+    pub fn open_source(
+        ctx: &egui::Context,
+        krate: &str,
+        path: PathBuf,
+        line: Option<usize>,
+        tree: &mut Tree,
+        counter: &mut u32,
+    ) {
+        let existing = tree.iter_mut().enumerate().find_map(|(node_index, node)| {
+            let Node::Leaf { tabs, active, .. } = node else {
+                return None;
+            };
+
+            let tab_index = tabs
+                .iter()
+                .position(|tab| tab.source.as_deref() == Some(path.as_path()))?;
+            *active = TabIndex(tab_index);
+
+            if let Some(line) = line {
+                tabs[tab_index].editor.jump_to_line(line);
+            }
+
+            Some(NodeIndex(node_index))
+        });
+
+        if let Some(node_index) = existing {
+            tree.set_focused_node(node_index);
+            return;
+        }
+
+        let code = match fs::read(&path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                Toast::error(format!("Failed to open {}: {e}", path.display())).push(ctx);
+                return;
+            }
+        };
+
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        let mut tab = Tab::read_only(
+            format!("{krate}: {file_name}"),
+            path.clone(),
+            code,
+            *counter,
+        );
+        if let Some(line) = line {
+            tab.editor.jump_to_line(line);
+        }
+
+        tree.push_to_focused_leaf(tab);
+
+        *counter += 1;
+    }
+
+    // returns whether the code was written
+    fn save_to_disk(ctx: &egui::Context, tab: &mut Tab) -> bool {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Rust", &["rs"])
+            .set_file_name(&format!("{}.rs", tab.name))
+            .save_file()
+        else {
+            return false;
+        };
+
+        let mut bytes = None;
+        if let Some(encoding) = tab.encoding.filter(|_| tab.keep_encoding) {
+            bytes = encoding.encode(&tab.editor.code);
+
+            if bytes.is_none() {
+                Toast::warning(format!(
+                    "The code has characters {} can't hold, it was saved as UTF-8",
+                    encoding.label()
+                ))
+                .push(ctx);
+            }
+        }
+
+        let bytes = bytes.unwrap_or_else(|| tab.editor.code.clone().into_bytes());
+
+        if let Err(e) = fs::write(&path, bytes) {
+            Toast::error(format!("Failed to save {}: {e}", path.display())).push(ctx);
+
+            return false;
+        }
+
+        tab.saved_hash = Tab::hash_code(&tab.editor.code);
+
+        Toast::success(format!("Saved {}", path.display())).push(ctx);
+
+        true
+    }
+
+    // upload the scratch as a gist in the background, a toast links to it once it's up
+    fn share_scratch(ctx: &egui::Context, id: Id, tree: &Tree, github: &GitHub) -> bool {
+        let Some(tab) = tree.iter().find_map(|node| {
+            let Node::Leaf { tabs, .. } = node else {
+                return None;
+            };
+
+            tabs.iter().find(|tab| tab.id == id)
+        }) else {
+            return false;
+        };
+
+        let name = tab.name.clone();
+        let rx = github.create_gist(&tab.editor.code);
+
+        let ctx = ctx.clone();
+        thread::spawn(move || match rx.recv() {
+            Ok(Ok(gist_id)) => Toast::success(format!("Shared {name}"))
+                .action(ToastAction::Copy {
+                    label: "Copy playground URL".to_string(),
+                    text: format!("https://play.rust-lang.org/?gist={gist_id}"),
+                })
+                .action(ToastAction::Copy {
+                    label: "Copy gist URL".to_string(),
+                    text: format!("https://gist.github.com/{gist_id}"),
+                })
+                .push(&ctx),
+
+            Ok(Err(e)) => Toast::error(format!("Failed to share {name}: {e}")).push(&ctx),
+
+            Err(_) => (),
+        });
+
+        false
+    }
+
+    // copy a playground link with the code, channel and edition in its query, for sharing without GitHub
+    fn share_link(ctx: &egui::Context, id: Id, tree: &Tree) -> bool {
+        let Some((node_index, tab_index)) = Self::find_tab(tree, id) else {
+            return false;
+        };
+
+        let Node::Leaf { tabs, .. } = &tree[node_index] else {
+            return false;
+        };
+
+        let tab = &tabs[tab_index.0];
+        let channel: &str = tab.build.channel.into();
+        let edition: &str = Edition::E2021.into();
+
+        let url = Url::parse_with_params(
+            "https://play.rust-lang.org/",
+            [
+                ("version", channel),
+                ("mode", "debug"),
+                ("edition", edition),
+                ("code", &tab.editor.code),
+            ],
+        )
+        .map(String::from)
+        .unwrap_or_default();
+
+        if url.len() > MAX_SHARE_URL_LEN {
+            Toast::warning(format!(
+                "{} is too long for a link, share it to the playground as a gist instead",
+                tab.name
+            ))
+            .push(ctx);
+
+            return false;
+        }
+
+        ctx.output().copied_text = url.clone();
+
+        Toast::success(format!("Copied a playground link to {}", tab.name))
+            .action(ToastAction::Open {
+                label: "Open".to_string(),
+                url,
+            })
+            .push(ctx);
 
         false
     }
@@ -0,0 +1,396 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use egui::{Button, Id, ProgressBar, ScrollArea, Ui, Window};
+use egui_dock::Node;
+use rustplay_widgets::code_editor::CodeEditor;
+
+use crate::config::{ExportMode, Gist, GistExport, GistUpload, Gists, GitHub, GitHubError};
+
+use super::dock::{Tab, Tree};
+
+pub struct GistBrowser;
+
+impl GistBrowser {
+    /// Page through the gists shared from Rust Play, preview them and open them as tabs.
+    /// Returns false once it's closed
+    pub fn show(
+        ctx: &egui::Context,
+        gists: &mut Gists,
+        github: &GitHub,
+        tree: &mut Tree,
+        counter: &mut u32,
+    ) -> bool {
+        if gists.page == 0 {
+            Self::load_page(gists, github, 1);
+        }
+
+        if let Some(listed) = Self::poll(ctx, &mut gists.listing) {
+            gists.listed = Some(listed);
+        }
+
+        if let Some(selected) = Self::poll(ctx, &mut gists.fetching) {
+            gists.selected = Some(selected);
+        }
+
+        let mut open = true;
+
+        Window::new("My Gists")
+            .open(&mut open)
+            .default_size([640.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let has_more = matches!(&gists.listed, Some(Ok(page)) if page.has_more);
+                    let idle = gists.listing.is_none();
+
+                    if ui
+                        .add_enabled(idle && gists.page > 1, Button::new("⏴ Previous"))
+                        .clicked()
+                    {
+                        Self::load_page(gists, github, gists.page - 1);
+                    }
+
+                    ui.label(format!("Page {}", gists.page));
+
+                    if ui
+                        .add_enabled(idle && has_more, Button::new("Next ⏵"))
+                        .clicked()
+                    {
+                        Self::load_page(gists, github, gists.page + 1);
+                    }
+
+                    if ui.add_enabled(idle, Button::new("Refresh")).clicked() {
+                        Self::load_page(gists, github, gists.page);
+                    }
+                });
+
+                ui.separator();
+
+                ui.columns(2, |columns| {
+                    Self::show_list(&mut columns[0], gists, github);
+                    Self::show_preview(&mut columns[1], gists, tree, counter);
+                });
+            });
+
+        // start over the next time it's opened, the gists may have changed by then
+        if !open {
+            *gists = Gists::default();
+        }
+
+        open
+    }
+
+    fn load_page(gists: &mut Gists, github: &GitHub, page: usize) {
+        gists.page = page;
+        gists.listing = Some(github.list_gists(page));
+    }
+
+    // the result of a request once it's done
+    fn poll<T>(ctx: &egui::Context, rx: &mut Option<Receiver<T>>) -> Option<T> {
+        let result = match rx.as_ref()?.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Disconnected) => None,
+            Err(TryRecvError::Empty) => {
+                // the requests don't wake up the ui on their own
+                ctx.request_repaint();
+                return None;
+            }
+        };
+
+        *rx = None;
+
+        result
+    }
+
+    fn show_list(ui: &mut Ui, gists: &mut Gists, github: &GitHub) {
+        if gists.listing.is_some() {
+            ui.spinner();
+            return;
+        }
+
+        let page = match &gists.listed {
+            Some(Ok(page)) => page,
+            Some(Err(e)) => {
+                Self::show_error(ui, e);
+                return;
+            }
+            None => return,
+        };
+
+        if page.gists.is_empty() {
+            ui.label("No gists on this page, share a scratch to create one");
+            return;
+        }
+
+        let selected_id = match &gists.selected {
+            Some(Ok(gist)) => Some(gist.id.as_str()),
+            _ => None,
+        };
+
+        let mut fetch = None;
+
+        ScrollArea::vertical()
+            .id_source("gist_list")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for gist in &page.gists {
+                    let is_selected = selected_id == Some(gist.id.as_str());
+
+                    let response = ui
+                        .selectable_label(is_selected, &gist.created_at)
+                        .on_hover_text(&gist.html_url);
+
+                    if response.clicked() {
+                        fetch = Some(gist.id.clone());
+                    }
+                }
+            });
+
+        if let Some(id) = fetch {
+            gists.selected = None;
+            gists.fetching = Some(github.get_gist(&id));
+        }
+    }
+
+    fn show_preview(ui: &mut Ui, gists: &Gists, tree: &mut Tree, counter: &mut u32) {
+        if gists.fetching.is_some() {
+            ui.spinner();
+            return;
+        }
+
+        let gist = match &gists.selected {
+            Some(Ok(gist)) => gist,
+            Some(Err(e)) => {
+                Self::show_error(ui, e);
+                return;
+            }
+            None => {
+                ui.label("Select a gist to preview it");
+                return;
+            }
+        };
+
+        let Some(code) = gist.code() else {
+            ui.label("This gist has no rust code");
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            if ui.button("Open in new tab").clicked() {
+                Self::open(gist, code, tree, counter);
+            }
+
+            ui.hyperlink_to("View on GitHub", &gist.html_url);
+        });
+
+        ScrollArea::vertical()
+            .id_source("gist_preview")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.code(code);
+            });
+    }
+
+    fn open(gist: &Gist, code: &str, tree: &mut Tree, counter: &mut u32) {
+        let name = format!("gist-{}", &gist.id[..gist.id.len().min(7)]);
+
+        let id = Id::new(format!("{name}-gist-{counter}"));
+        let tab = Tab::new(name, id, CodeEditor::new(code.to_string()));
+
+        tree.push_to_focused_leaf(tab);
+
+        *counter += 1;
+    }
+
+    fn show_error(ui: &mut Ui, e: &GitHubError) {
+        match e {
+            GitHubError::NoAuthentication => {
+                ui.label("Add a GitHub access token to the config to see your gists");
+            }
+            e => {
+                ui.label(format!("Failed to load from GitHub: {e}"));
+            }
+        }
+    }
+}
+
+pub struct GistExporter;
+
+impl GistExporter {
+    /// Share every open scratch, in one gist or a gist each, and list the resulting URLs.
+    /// Returns false once it's closed
+    pub fn show(
+        ctx: &egui::Context,
+        export: &mut GistExport,
+        github: &GitHub,
+        tree: &Tree,
+    ) -> bool {
+        for upload in &mut export.uploads {
+            if let Some(result) = GistBrowser::poll(ctx, &mut upload.pending) {
+                upload.result = Some(result);
+            }
+        }
+
+        let mut open = true;
+
+        Window::new("Share all open scratches")
+            .open(&mut open)
+            .default_size([420.0, 300.0])
+            .show(ctx, |ui| {
+                if export.uploads.is_empty() {
+                    Self::show_options(ui, export, github, tree);
+                } else {
+                    Self::show_progress(ui, export);
+                }
+            });
+
+        // start over the next time it's opened
+        if !open {
+            *export = GistExport::default();
+        }
+
+        open
+    }
+
+    fn show_options(ui: &mut Ui, export: &mut GistExport, github: &GitHub, tree: &Tree) {
+        let scratches = Self::scratches(tree);
+
+        for mode in ExportMode::all() {
+            ui.radio_value(&mut export.mode, mode, mode.label());
+        }
+
+        ui.separator();
+
+        let share = ui
+            .add_enabled(
+                !scratches.is_empty(),
+                Button::new(format!("Share {} scratches", scratches.len())),
+            )
+            .on_disabled_hover_text("There are no scratches open")
+            .clicked();
+
+        if !share {
+            return;
+        }
+
+        let upload = |files: Vec<(String, String)>| GistUpload {
+            names: files.iter().map(|(name, _)| name.clone()).collect(),
+            pending: Some(github.create_gist_with_files(&files)),
+            result: None,
+        };
+
+        export.uploads = match export.mode {
+            ExportMode::SingleGist => vec![upload(scratches)],
+            ExportMode::GistPerTab => scratches
+                .into_iter()
+                .map(|scratch| upload(vec![scratch]))
+                .collect(),
+        };
+    }
+
+    fn show_progress(ui: &mut Ui, export: &GistExport) {
+        let done = export
+            .uploads
+            .iter()
+            .filter(|upload| upload.result.is_some())
+            .count();
+        let total = export.uploads.len();
+
+        ui.add(
+            ProgressBar::new(done as f32 / total as f32)
+                .text(format!("{done} of {total} gists created")),
+        );
+
+        if ui
+            .add_enabled(done == total, Button::new("Copy all URLs"))
+            .clicked()
+        {
+            ui.output().copied_text = Self::summary(export);
+        }
+
+        ui.separator();
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for upload in &export.uploads {
+                    let names = upload.names.join(", ");
+
+                    match &upload.result {
+                        None => {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(names);
+                            });
+                        }
+
+                        Some(Ok(gist_id)) => {
+                            ui.label(names);
+                            ui.horizontal(|ui| {
+                                ui.hyperlink_to("Gist", gist_url(gist_id));
+                                ui.hyperlink_to("Playground", playground_url(gist_id));
+                            });
+                        }
+
+                        Some(Err(e)) => {
+                            ui.colored_label(ui.visuals().error_fg_color, format!("{names}: {e}"));
+                        }
+                    }
+
+                    ui.add_space(4.0);
+                }
+            });
+    }
+
+    // the name of every scratch with unique file names, and its code. Read-only tabs aren't scratches
+    fn scratches(tree: &Tree) -> Vec<(String, String)> {
+        let mut scratches: Vec<(String, String)> = vec![];
+
+        for node in tree.iter() {
+            let Node::Leaf { tabs, .. } = node else {
+                continue;
+            };
+
+            for tab in tabs.iter().filter(|tab| !tab.editor.is_read_only()) {
+                // gist file names can't have path separators, and have to differ
+                let stem = tab.name.replace(['/', '\\'], "_");
+                let mut name = format!("{stem}.rs");
+                let mut n = 2;
+
+                while scratches.iter().any(|(other, _)| *other == name) {
+                    name = format!("{stem}-{n}.rs");
+                    n += 1;
+                }
+
+                scratches.push((name, tab.editor.code.clone()));
+            }
+        }
+
+        scratches
+    }
+
+    // one line per gist, to paste somewhere
+    fn summary(export: &GistExport) -> String {
+        export
+            .uploads
+            .iter()
+            .map(|upload| {
+                let names = upload.names.join(", ");
+
+                match &upload.result {
+                    Some(Ok(gist_id)) => format!("{names}: {}", gist_url(gist_id)),
+                    Some(Err(e)) => format!("{names}: failed, {e}"),
+                    None => format!("{names}: not shared yet"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn gist_url(gist_id: &str) -> String {
+    format!("https://gist.github.com/{gist_id}")
+}
+
+fn playground_url(gist_id: &str) -> String {
+    format!("https://play.rust-lang.org/?gist={gist_id}")
+}
@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use egui::text::LayoutJob;
+use egui::{vec2, Button, Color32, Id, ScrollArea, TextEdit, TextFormat, TextStyle, Window};
+
+use crate::config::{Command, MenuCommand};
+
+const BYTES_PER_ROW: usize = 16;
+
+// temp memory key holding the raw stdout of a tab's last run, if it wasn't valid utf-8
+pub const BINARY_OUTPUT: &str = "hex_viewer_binary_output";
+
+#[derive(Debug, Clone)]
+pub struct HexView {
+    pub title: String,
+    pub bytes: Arc<Vec<u8>>,
+    pub id: Id,
+}
+
+impl HexView {
+    pub fn new(title: String, bytes: Arc<Vec<u8>>) -> Self {
+        let id = Id::new("hex_viewer").with(&title);
+
+        Self { title, bytes, id }
+    }
+
+    /// Whether a window for the same title is already queued to show
+    pub fn is_open(&self, commands: &[Command]) -> bool {
+        commands.iter().any(|command| {
+            matches!(command, Command::MenuCommand(MenuCommand::HexView(view)) if view.id == self.id)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Search {
+    query: String,
+    // offsets of every match
+    matches: Vec<usize>,
+    // index into matches
+    current: usize,
+    scroll_to_current: bool,
+}
+
+impl Search {
+    // hex bytes like "de ad be ef" are searched for as bytes, anything else as text
+    fn needle(&self) -> Vec<u8> {
+        let hex = self.query.split_whitespace().collect::<String>();
+
+        let is_hex =
+            !hex.is_empty() && hex.len() % 2 == 0 && hex.chars().all(|c| c.is_ascii_hexdigit());
+
+        if !is_hex {
+            return self.query.as_bytes().to_vec();
+        }
+
+        (0..hex.len())
+            .step_by(2)
+            .filter_map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect()
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        let needle = self.needle();
+
+        self.matches = if needle.is_empty() {
+            vec![]
+        } else {
+            bytes
+                .windows(needle.len())
+                .enumerate()
+                .filter(|(_, window)| *window == needle)
+                .map(|(offset, _)| offset)
+                .collect()
+        };
+
+        self.current = 0;
+        self.scroll_to_current = !self.matches.is_empty();
+    }
+
+    fn is_match(&self, offset: usize, needle_len: usize) -> bool {
+        // matches are sorted, find the last one starting at or before the offset
+        let i = self.matches.partition_point(|start| *start <= offset);
+
+        i > 0 && offset < self.matches[i - 1] + needle_len
+    }
+}
+
+pub struct HexViewer;
+
+impl HexViewer {
+    /// Show a hex dump of the bytes with offset and ascii columns. Returns false once it's closed
+    pub fn show(ctx: &egui::Context, view: &HexView) -> bool {
+        let mut open = true;
+
+        let mut search = ctx.data().get_temp::<Search>(view.id).unwrap_or_default();
+
+        Window::new(&view.title)
+            .id(view.id)
+            .open(&mut open)
+            .default_size([620.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} bytes", view.bytes.len()));
+
+                    ui.separator();
+
+                    let response = ui.add(
+                        TextEdit::singleline(&mut search.query)
+                            .hint_text("Search text or hex bytes")
+                            .desired_width(180.0),
+                    );
+
+                    if response.changed() {
+                        search.update(&view.bytes);
+                    }
+
+                    if !search.query.is_empty() {
+                        ui.label(format!("{} matches", search.matches.len()));
+                    }
+
+                    let has_matches = !search.matches.is_empty();
+
+                    if ui.add_enabled(has_matches, Button::new("⏶")).clicked() {
+                        search.current = search
+                            .current
+                            .checked_sub(1)
+                            .unwrap_or(search.matches.len() - 1);
+                        search.scroll_to_current = true;
+                    }
+
+                    if ui.add_enabled(has_matches, Button::new("⏷")).clicked() {
+                        search.current = (search.current + 1) % search.matches.len();
+                        search.scroll_to_current = true;
+                    }
+                });
+
+                ui.separator();
+
+                let row_height = ui.text_style_height(&TextStyle::Monospace);
+                let rows = (view.bytes.len() + BYTES_PER_ROW - 1) / BYTES_PER_ROW;
+
+                let mut scroll_area = ScrollArea::both().auto_shrink([false, false]);
+
+                if search.scroll_to_current {
+                    if let Some(offset) = search.matches.get(search.current) {
+                        let row = offset / BYTES_PER_ROW;
+                        let spacing = ui.spacing().item_spacing.y;
+                        scroll_area = scroll_area
+                            .scroll_offset(vec2(0.0, row as f32 * (row_height + spacing)));
+                    }
+
+                    search.scroll_to_current = false;
+                }
+
+                let needle_len = search.needle().len();
+
+                scroll_area.show_rows(ui, row_height, rows, |ui, range| {
+                    for row in range {
+                        let start = row * BYTES_PER_ROW;
+                        let end = (start + BYTES_PER_ROW).min(view.bytes.len());
+
+                        let job = Self::layout_row(ui, &view.bytes[start..end], start, |offset| {
+                            search.is_match(offset, needle_len)
+                        });
+
+                        ui.label(job);
+                    }
+                });
+            });
+
+        ctx.data().insert_temp(view.id, search);
+
+        open
+    }
+
+    // e.g. 00000010  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff  |Hello, world!...|
+    fn layout_row(
+        ui: &egui::Ui,
+        bytes: &[u8],
+        start: usize,
+        is_match: impl Fn(usize) -> bool,
+    ) -> LayoutJob {
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let text_color = ui.visuals().text_color();
+        let weak_color = ui.visuals().weak_text_color();
+        let highlight = ui.visuals().selection.bg_fill;
+
+        let format = |color: Color32, background: Color32| TextFormat {
+            font_id: font_id.clone(),
+            color,
+            background,
+            ..Default::default()
+        };
+
+        let background = |offset: usize| {
+            if is_match(offset) {
+                highlight
+            } else {
+                Color32::TRANSPARENT
+            }
+        };
+
+        let mut job = LayoutJob::default();
+
+        job.append(
+            &format!("{start:08x}  "),
+            0.0,
+            format(weak_color, Color32::TRANSPARENT),
+        );
+
+        for i in 0..BYTES_PER_ROW {
+            let text = match bytes.get(i) {
+                Some(byte) => format!("{byte:02x}"),
+                None => "  ".to_string(),
+            };
+
+            job.append(&text, 0.0, format(text_color, background(start + i)));
+
+            // an extra gap halfway through the row
+            let gap = if i == BYTES_PER_ROW / 2 - 1 {
+                "  "
+            } else {
+                " "
+            };
+            job.append(gap, 0.0, format(text_color, Color32::TRANSPARENT));
+        }
+
+        job.append(" |", 0.0, format(weak_color, Color32::TRANSPARENT));
+
+        for (i, byte) in bytes.iter().enumerate() {
+            let (c, color) = if byte.is_ascii_graphic() || *byte == b' ' {
+                (*byte as char, text_color)
+            } else {
+                ('.', weak_color)
+            };
+
+            job.append(&c.to_string(), 0.0, format(color, background(start + i)));
+        }
+
+        job.append("|", 0.0, format(weak_color, Color32::TRANSPARENT));
+
+        job
+    }
+}
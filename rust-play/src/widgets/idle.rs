@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+
+use egui::{DragValue, Ui};
+
+use crate::config::Config;
+
+// lines of each finished run's output kept once the memory is given back
+const KEPT_LINES: usize = 1000;
+
+pub struct IdleReclaimer;
+
+impl IdleReclaimer {
+    /// Once the window wasn't used for a while, drop the cached layouts of the highlighted code and the
+    /// terminal output, and trim the output of finished runs. Called at the end of the frame, so what it
+    /// drops isn't laid out again before the window is used
+    pub fn update(ctx: &egui::Context, config: &mut Config) {
+        let used = {
+            let input = ctx.input();
+            !input.events.is_empty() || input.pointer.is_moving() || input.pointer.any_down()
+        };
+
+        let idle = &mut config.idle;
+
+        let last_input = match idle.last_input {
+            Some(last_input) if !used => last_input,
+            _ => {
+                idle.reclaimed = false;
+                *idle.last_input.insert(Instant::now())
+            }
+        };
+
+        if config.general.reclaim_after == 0 || idle.reclaimed {
+            return;
+        }
+
+        let after = Duration::from_secs(config.general.reclaim_after * 60);
+        let elapsed = last_input.elapsed();
+
+        // nothing may be painted in the meantime, the frame it happens in has to be asked for
+        if elapsed < after {
+            ctx.request_repaint_after(after - elapsed);
+            return;
+        }
+
+        idle.reclaimed = true;
+
+        Self::reclaim(ctx, config);
+    }
+
+    fn reclaim(ctx: &egui::Context, config: &mut Config) {
+        // laid out again the next time they're shown
+        ctx.memory().caches = Default::default();
+
+        let terminal = &mut config.terminal;
+
+        for (id, output) in &mut terminal.output {
+            // a run still going may be redrawing its last lines
+            if terminal.abortable.contains_key(id) {
+                continue;
+            }
+
+            output.stdout.trim(KEPT_LINES);
+            output.stderr.trim(KEPT_LINES);
+        }
+
+        terminal.output.shrink_to_fit();
+        terminal.content.shrink_to_fit();
+        terminal.finished.shrink_to_fit();
+        terminal.scroll_offset.shrink_to_fit();
+        terminal.sizes.shrink_to_fit();
+
+        config.performance.frames.shrink_to_fit();
+    }
+
+    /// How long the window has to be left alone, in the settings menu
+    pub fn settings(ui: &mut Ui, config: &mut Config) {
+        ui.horizontal(|ui| {
+            ui.label("Free memory after");
+            ui.add(
+                DragValue::new(&mut config.general.reclaim_after)
+                    .clamp_range(0..=24 * 60)
+                    .suffix(" min"),
+            )
+            .on_hover_text(format!(
+                "Once the window wasn't used for this long, cached layouts are dropped and the output of finished runs is cut to its last {KEPT_LINES} lines. 0 disables it"
+            ));
+        });
+    }
+}
@@ -0,0 +1,161 @@
+use cargo_player::{Level, Lint};
+use egui::{Button, Grid, Id, Ui};
+
+// temp memory key set for a tab whose last run linted it with clippy
+pub const LINT_RUN: &str = "lints_run";
+// the file of the scratch, the only one lints can jump into
+const SCRATCH_FILE: &str = "src/main.rs";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Level,
+    Name,
+    Line,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TableState {
+    sort: Column,
+    descending: bool,
+}
+
+impl Default for TableState {
+    fn default() -> Self {
+        Self {
+            sort: Column::Line,
+            descending: false,
+        }
+    }
+}
+
+pub struct LintList;
+
+impl LintList {
+    /// Show the lints of a clippy run in a sortable table, each linking to its documentation and
+    /// jumping to its line when its location is clicked. Returns the clicked line, counted from 0
+    pub fn show(ui: &mut Ui, lints: &[Lint], id: Id) -> Option<usize> {
+        let mut jump = None;
+
+        let mut state = ui
+            .ctx()
+            .data()
+            .get_temp::<TableState>(id)
+            .unwrap_or_default();
+
+        if lints.is_empty() {
+            ui.weak("No lints");
+            return None;
+        }
+
+        let mut lints = lints.iter().collect::<Vec<_>>();
+        Self::sort(&mut lints, state);
+
+        Grid::new(id.with("table"))
+            .striped(true)
+            .num_columns(4)
+            .show(ui, |ui| {
+                Self::header(ui, &mut state, Column::Level, "Level");
+                Self::header(ui, &mut state, Column::Name, "Lint");
+                ui.strong("Message");
+                Self::header(ui, &mut state, Column::Line, "Location");
+                ui.end_row();
+
+                for lint in lints {
+                    let color = match lint.level {
+                        Level::Error | Level::InternalCompilerError => ui.visuals().error_fg_color,
+                        Level::Warning => ui.visuals().warn_fg_color,
+                        _ => ui.visuals().text_color(),
+                    };
+                    ui.colored_label(color, format!("{:?}", lint.level));
+
+                    match &lint.url {
+                        Some(url) => {
+                            ui.hyperlink_to(&lint.name, url)
+                                .on_hover_text("Open the documentation of the lint");
+                        }
+                        None => {
+                            ui.label(&lint.name);
+                        }
+                    }
+
+                    // the help says what to do about it, hovering shows it
+                    let message = ui.label(&lint.message);
+                    if !lint.help.is_empty() {
+                        message.on_hover_text(lint.help.join("\n"));
+                    }
+
+                    let span = &lint.span;
+                    let location = format!(
+                        "{}:{}:{}",
+                        span.file_name, span.line_start, span.column_start
+                    );
+
+                    if span.file_name == SCRATCH_FILE {
+                        let button = ui
+                            .small_button(location)
+                            .on_hover_text(span.label.as_deref().unwrap_or("Jump to the line"));
+
+                        if button.clicked() {
+                            jump = Some(span.line_start.saturating_sub(1));
+                        }
+                    } else {
+                        ui.weak(location);
+                    }
+
+                    ui.end_row();
+                }
+            });
+
+        ui.ctx().data().insert_temp(id, state);
+
+        jump
+    }
+
+    // a column heading which sorts by the column, clicking it again flips the order
+    fn header(ui: &mut Ui, state: &mut TableState, column: Column, label: &str) {
+        let label = if state.sort != column {
+            label.to_string()
+        } else if state.descending {
+            format!("{label} ⏷")
+        } else {
+            format!("{label} ⏶")
+        };
+
+        if ui.add(Button::new(label).frame(false)).clicked() {
+            if state.sort == column {
+                state.descending = !state.descending;
+            } else {
+                state.sort = column;
+                state.descending = false;
+            }
+        }
+    }
+
+    // by level the most severe come first, as they do among lints at the same place
+    fn sort(lints: &mut [&Lint], state: TableState) {
+        let by_line = |a: &&Lint, b: &&Lint| {
+            (a.span.line_start, a.span.column_start).cmp(&(b.span.line_start, b.span.column_start))
+        };
+
+        match state.sort {
+            Column::Level => lints.sort_by_key(|lint| severity(lint.level)),
+            Column::Name => lints.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| by_line(a, b))),
+            Column::Line => lints.sort_by(|a, b| {
+                by_line(a, b).then_with(|| severity(a.level).cmp(&severity(b.level)))
+            }),
+        }
+
+        if state.descending {
+            lints.reverse();
+        }
+    }
+}
+
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::InternalCompilerError => 0,
+        Level::Error => 1,
+        Level::Warning => 2,
+        _ => 3,
+    }
+}
@@ -0,0 +1,886 @@
+use std::fs;
+use std::mem;
+use std::sync::Arc;
+
+use cargo_player::{fuzzable_functions, proptest_functions, Channel};
+use egui::{menu, Button, Checkbox, DragValue, Layout, Rect, ScrollArea, Slider, TextEdit, Ui};
+use egui_dock::Node;
+use rustplay_widgets::code_editor::{
+    syntax_theme_picker, MAX_FONT_SIZE, MAX_ZOOM, MIN_FONT_SIZE, MIN_ZOOM,
+};
+
+#[cfg(target_os = "windows")]
+use crate::config::ShortcutKeys;
+use crate::config::{
+    delete_workspace, export_json, load_workspaces, reload_themes, settings_file, themes_dir,
+    Command, Config, MenuCommand, RunAgain, TabCommand, TerminalPosition, ThemeMode,
+};
+use crate::utils::fonts;
+
+use super::command_palette::{CommandPalette, PaletteCommand};
+use super::diagnostics::DiagnosticBundle;
+use super::disk_usage::DiskUsageMeter;
+use super::dock::{fuzz_label, host_sanitizers, miri_hover_text, sanitizer_label};
+use super::hex_viewer::HexView;
+use super::idle::IdleReclaimer;
+use super::setup::SetupCheck;
+use super::terminal::Terminal;
+use super::titlebar::{CAPTION_TOP_PADDING, MENUBAR_HEIGHT};
+use super::toasts::Toast;
+use super::toolchains::ToolchainManager;
+use super::update_nudge::UpdateNudge;
+
+pub const REPOSITORY_URL: &str = "https://github.com/MolotovCherry/RustPlay";
+// lines of code shown when hovering a recent draft
+const PREVIEW_LINES: usize = 15;
+
+pub struct MenuBar;
+
+impl MenuBar {
+    /// Show the menu bar in the top strip of the titlebar.
+    /// Returns the rect covered by the menus, which must not be used to drag the window
+    pub fn show(ctx: &egui::Context, config: &mut Config, ui: &mut Ui) -> Rect {
+        CommandPalette::register(ctx, "menubar", Self::palette_commands);
+
+        let mut rect = ui.max_rect();
+        rect.set_left(rect.left() + 8.0);
+        // leave the resize grip at the top of the window alone
+        rect.set_top(rect.top() + CAPTION_TOP_PADDING as f32 / 2.0);
+        rect.set_bottom(MENUBAR_HEIGHT as f32 / 2.0);
+
+        let mut menu_ui = ui.child_ui(rect, Layout::left_to_right(egui::Align::Center));
+
+        ToolchainManager::check(ctx, &mut config.toolchains);
+
+        let response = menu::bar(&mut menu_ui, |ui| {
+            // commands act on the focused tab
+            let active = config.dock.tree.find_active().map(|(_, tab)| tab.id);
+            let focused_node = config.dock.tree.focused_leaf();
+
+            let mut commands = vec![];
+
+            ui.menu_button("File", |ui| {
+                if let Some(node) = focused_node {
+                    if ui.button("New scratch").clicked() {
+                        commands.push(Command::TabCommand(TabCommand::Add(node)));
+                        ui.close_menu();
+                    }
+
+                    if ui.button("New from template...").clicked() {
+                        commands.push(Command::MenuCommand(MenuCommand::NewFromTemplate(node)));
+                        ui.close_menu();
+                    }
+                }
+
+                if ui.button("Open...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Rust", &["rs"])
+                        .pick_file()
+                    {
+                        commands.push(Command::TabCommand(TabCommand::Open(path)));
+                    }
+
+                    ui.close_menu();
+                }
+
+                if ui.button("Open in hex viewer...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_file() {
+                        match fs::read(&path) {
+                            Ok(bytes) => {
+                                let view =
+                                    HexView::new(path.display().to_string(), Arc::new(bytes));
+
+                                if !view.is_open(&config.dock.commands) {
+                                    commands.push(Command::MenuCommand(MenuCommand::HexView(view)));
+                                }
+                            }
+
+                            Err(e) => {
+                                Toast::error(format!("Failed to open {}: {e}", path.display()))
+                                    .push(ctx)
+                            }
+                        }
+                    }
+
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Recent", |ui| {
+                    let drafts = config.dock.drafts.iter().filter(|draft| {
+                        !config.dock.tree.iter().any(|node| {
+                            let Node::Leaf { tabs, .. } = node else {
+                                return false;
+                            };
+
+                            tabs.iter().any(|tab| tab.draft_key == draft.key)
+                        })
+                    });
+
+                    let mut empty = true;
+                    for draft in drafts {
+                        empty = false;
+
+                        let preview = draft
+                            .code
+                            .lines()
+                            .take(PREVIEW_LINES)
+                            .collect::<Vec<_>>()
+                            .join("\n");
+
+                        let response = ui.button(&draft.name).on_hover_ui(|ui| {
+                            ui.code(preview);
+                        });
+
+                        if response.clicked() {
+                            commands.push(Command::TabCommand(TabCommand::Restore(draft.clone())));
+                            ui.close_menu();
+                        }
+                    }
+
+                    if empty {
+                        ui.label("Nothing closed yet");
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Export history...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("JSON", &["json"])
+                            .set_file_name("rust-play-history.json")
+                            .save_file()
+                        {
+                            match export_json(&path) {
+                                Ok(()) => Toast::success(format!(
+                                    "Exported the history to {}",
+                                    path.display()
+                                ))
+                                .push(ctx),
+
+                                Err(e) => {
+                                    Toast::error(format!("Failed to export the history: {e}"))
+                                        .push(ctx)
+                                }
+                            }
+                        }
+
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Workspaces", |ui| {
+                    let mut deleted = None;
+
+                    for name in &config.dock.workspaces {
+                        let current = config.dock.workspace.as_ref() == Some(name);
+
+                        let response = ui
+                            .selectable_label(current, name)
+                            .on_hover_text("Right click to delete");
+
+                        if response.clicked() && !current {
+                            commands.push(Command::TabCommand(TabCommand::SwitchWorkspace(
+                                name.clone(),
+                            )));
+                            ui.close_menu();
+                        }
+
+                        response.context_menu(|ui| {
+                            if ui.button("Delete").clicked() {
+                                deleted = Some(name.clone());
+                                ui.close_menu();
+                            }
+                        });
+                    }
+
+                    if let Some(name) = deleted {
+                        delete_workspace(&name);
+                        config.dock.workspaces = load_workspaces();
+
+                        if config.dock.workspace.as_ref() == Some(&name) {
+                            config.dock.workspace = None;
+                        }
+                    }
+
+                    if config.dock.workspaces.is_empty() {
+                        ui.label("No workspaces saved yet");
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Save workspace as...").clicked() {
+                        open_window(config, MenuCommand::SaveWorkspace);
+                        ui.close_menu();
+                    }
+                });
+
+                if let Some(id) = active {
+                    if ui.button("Save...").clicked() {
+                        commands.push(Command::MenuCommand(MenuCommand::Save(id)));
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Share to Playground").clicked() {
+                        commands.push(Command::MenuCommand(MenuCommand::Share(id)));
+                        ui.close_menu();
+                    }
+
+                    if ui
+                        .button("Copy Playground link")
+                        .on_hover_text("A link with the code in it, no GitHub account needed")
+                        .clicked()
+                    {
+                        commands.push(Command::MenuCommand(MenuCommand::ShareLink(id)));
+                        ui.close_menu();
+                    }
+                }
+
+                if ui.button("Share all open scratches...").clicked() {
+                    open_window(config, MenuCommand::ShareAll);
+                    ui.close_menu();
+                }
+
+                if ui.button("My Gists").clicked() {
+                    let is_open =
+                        config.dock.commands.iter().any(|command| {
+                            matches!(command, Command::MenuCommand(MenuCommand::Gists))
+                        });
+
+                    if !is_open {
+                        commands.push(Command::MenuCommand(MenuCommand::Gists));
+                    }
+
+                    ui.close_menu();
+                }
+            });
+
+            ui.menu_button("Edit", |ui| {
+                let reopen = ui.add_enabled(
+                    !config.dock.closed.is_empty(),
+                    Button::new("Reopen closed tab (Ctrl+Shift+T)"),
+                );
+
+                if reopen.clicked() {
+                    commands.push(Command::TabCommand(TabCommand::ReopenClosed));
+                    ui.close_menu();
+                }
+
+                let Some(id) = active else {
+                    ui.label("No scratch open");
+                    return;
+                };
+
+                if ui.button("Rename").clicked() {
+                    commands.push(Command::MenuCommand(MenuCommand::Rename(id)));
+                    ui.close_menu();
+                }
+
+                if ui
+                    .button("Tidy directives")
+                    .on_hover_text("Sort and align the //# and //> lines")
+                    .clicked()
+                {
+                    commands.push(Command::TabCommand(TabCommand::TidyDirectives(Some(id))));
+                    ui.close_menu();
+                }
+
+                if ui.button("Tidy directives in all tabs").clicked() {
+                    commands.push(Command::TabCommand(TabCommand::TidyDirectives(None)));
+                    ui.close_menu();
+                }
+
+                if ui.button("Copy code").clicked() {
+                    if let Some((_, tab)) = config.dock.tree.find_active() {
+                        ui.output().copied_text = tab.editor.code.clone();
+                    }
+
+                    ui.close_menu();
+                }
+            });
+
+            ui.menu_button("Run", |ui| {
+                let Some(id) = active else {
+                    ui.label("No scratch open");
+                    return;
+                };
+
+                if ui.button("Play").clicked() {
+                    commands.push(Command::TabCommand(TabCommand::Play(id)));
+                    ui.close_menu();
+                }
+
+                if ui.button("Test").clicked() {
+                    commands.push(Command::TabCommand(TabCommand::PlayTests(id)));
+                    ui.close_menu();
+                }
+
+                if ui.button("Run in browser").clicked() {
+                    commands.push(Command::TabCommand(TabCommand::PlayWeb(id)));
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Sanitize", |ui| {
+                    let sanitizers = host_sanitizers();
+
+                    if sanitizers.is_empty() {
+                        ui.label("No sanitizer supports this platform");
+                    }
+
+                    for &sanitizer in sanitizers {
+                        if ui.button(sanitizer_label(sanitizer)).clicked() {
+                            commands.push(Command::TabCommand(TabCommand::PlaySanitized(
+                                id, sanitizer,
+                            )));
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                if ui.button("Loom model check").clicked() {
+                    commands.push(Command::TabCommand(TabCommand::PlayLoom(id)));
+                    ui.close_menu();
+                }
+
+                let check_ub = ui
+                    .button("Check UB with Miri")
+                    .on_hover_text(miri_hover_text(
+                        config
+                            .toolchains
+                            .is_component_missing(Channel::Nightly, "miri"),
+                    ));
+
+                if check_ub.clicked() {
+                    commands.push(Command::TabCommand(TabCommand::PlayMiri(id)));
+                    ui.close_menu();
+                }
+
+                if ui.button("Lint with Clippy").clicked() {
+                    commands.push(Command::TabCommand(TabCommand::PlayClippy(id)));
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Property test", |ui| {
+                    let functions = config
+                        .dock
+                        .tree
+                        .find_active()
+                        .map(|(_, tab)| proptest_functions(&tab.editor.code))
+                        .unwrap_or_default();
+
+                    if functions.is_empty() {
+                        ui.label("No function taking arguments to test");
+                    }
+
+                    for function in functions {
+                        if ui.button(&function).clicked() {
+                            commands.push(Command::TabCommand(TabCommand::Proptest(id, function)));
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                ui.menu_button("Fuzz function", |ui| {
+                    let functions = config
+                        .dock
+                        .tree
+                        .find_active()
+                        .map(|(_, tab)| fuzzable_functions(&tab.editor.code))
+                        .unwrap_or_default();
+
+                    if functions.is_empty() {
+                        ui.label("No function taking arguments to fuzz");
+                    }
+
+                    for function in functions {
+                        if ui.button(fuzz_label(&function)).clicked() {
+                            commands.push(Command::TabCommand(TabCommand::Fuzz(id, function)));
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                if ui.button("Binary size").clicked() {
+                    commands.push(Command::TabCommand(TabCommand::BinarySize(id)));
+                    ui.close_menu();
+                }
+
+                if ui.button("Stop").clicked() {
+                    commands.push(Command::TabCommand(TabCommand::Stop(id)));
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Run again while running", |ui| {
+                    for run_again in RunAgain::all() {
+                        if ui
+                            .radio_value(
+                                &mut config.general.run_again,
+                                run_again,
+                                run_again.label(),
+                            )
+                            .clicked()
+                        {
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                ui.menu_button("Toolchains", |ui| {
+                    ToolchainManager::show(ctx, config, ui);
+                });
+            });
+
+            ui.menu_button("View", |ui| {
+                let label = if config.terminal.open {
+                    "Close terminal"
+                } else {
+                    "Open terminal"
+                };
+
+                if ui.button(label).clicked() {
+                    Terminal::toggle(ctx, config);
+                    ui.close_menu();
+                }
+
+                if ui.button("Command palette (Ctrl+Shift+P)").clicked() {
+                    CommandPalette::open(ctx);
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Terminal settings", |ui| {
+                    let settings = &mut config.terminal_settings;
+
+                    ui.horizontal(|ui| {
+                        ui.label("Position");
+
+                        for position in TerminalPosition::all() {
+                            if ui
+                                .selectable_value(
+                                    &mut settings.position,
+                                    position,
+                                    position.label(),
+                                )
+                                .changed()
+                            {
+                                // sizes along the old axis don't carry over
+                                config.terminal.sizes.clear();
+                                config.terminal.open = false;
+                                config.terminal.animation = None;
+                            }
+                        }
+                    });
+
+                    ui.separator();
+
+                    ui.checkbox(&mut settings.auto_open, "Open when running");
+
+                    ui.add_enabled_ui(settings.auto_open, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Size");
+                            ui.add(
+                                DragValue::new(&mut settings.open_size)
+                                    .clamp_range(50.0..=2000.0)
+                                    .suffix("px"),
+                            );
+                        });
+
+                        ui.checkbox(&mut settings.remember_size, "Remember size per tab");
+                        ui.checkbox(
+                            &mut settings.auto_close,
+                            "Close again after a successful run without output",
+                        );
+                    });
+
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        ui.label("Drag to open distance");
+                        ui.add(
+                            DragValue::new(&mut settings.open_threshold)
+                                .clamp_range(2.0..=100.0)
+                                .suffix("px"),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Drag to close distance");
+                        ui.add(
+                            DragValue::new(&mut settings.close_threshold)
+                                .clamp_range(2.0..=100.0)
+                                .suffix("px"),
+                        );
+                    });
+
+                    ui.checkbox(&mut settings.animate, "Animate");
+
+                    ui.add_enabled_ui(settings.animate, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Animation time");
+                            ui.add(
+                                DragValue::new(&mut settings.animation_time)
+                                    .clamp_range(0.05..=1.0)
+                                    .speed(0.01)
+                                    .suffix("s"),
+                            );
+                        });
+                    });
+
+                    ui.checkbox(&mut settings.blink, "Blinking text");
+
+                    ui.horizontal(|ui| {
+                        ui.label("Zoom");
+                        ui.add(
+                            DragValue::new(&mut settings.zoom)
+                                .clamp_range(MIN_ZOOM..=MAX_ZOOM)
+                                .speed(0.01),
+                        )
+                        .on_hover_text("Ctrl+scroll over the terminal, or ctrl+= and ctrl+-");
+                    });
+                });
+
+                ui.menu_button("Disk usage", |ui| {
+                    DiskUsageMeter::settings(ui, config);
+                });
+
+                ui.menu_button("Memory", |ui| {
+                    IdleReclaimer::settings(ui, config);
+                });
+
+                if ui.button("Environment presets...").clicked() {
+                    let is_open = config.dock.commands.iter().any(|command| {
+                        matches!(command, Command::MenuCommand(MenuCommand::Presets))
+                    });
+
+                    if !is_open {
+                        commands.push(Command::MenuCommand(MenuCommand::Presets));
+                    }
+
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Theme", |ui| {
+                    for mode in ThemeMode::all() {
+                        if ui
+                            .radio_value(&mut config.theme.mode, mode, mode.label())
+                            .clicked()
+                        {
+                            ui.close_menu();
+                        }
+                    }
+
+                    ui.separator();
+
+                    let dark_mode = ctx.style().visuals.dark_mode;
+                    let mut opacity = config.theme.background_opacity(dark_mode);
+
+                    ui.horizontal(|ui| {
+                        ui.label("Editor zoom");
+                        ui.add(
+                            DragValue::new(&mut config.theme.editor_zoom)
+                                .clamp_range(MIN_ZOOM..=MAX_ZOOM)
+                                .speed(0.01),
+                        )
+                        .on_hover_text(
+                            "The default of every tab. Ctrl+scroll or ctrl+= and ctrl+- zoom a \
+                             tab of its own, ctrl+0 resets it",
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Background opacity");
+
+                        if ui.add(Slider::new(&mut opacity, 0.0..=1.0)).changed() {
+                            config.theme.background_opacity = Some(opacity);
+                        }
+
+                        let reset = ui.add_enabled(
+                            config.theme.background_opacity.is_some(),
+                            Button::new("Reset"),
+                        );
+
+                        if reset.clicked() {
+                            config.theme.background_opacity = None;
+                        }
+                    });
+                });
+
+                ui.menu_button("Double click", |ui| {
+                    let double_click = &mut config.general.double_click;
+
+                    ui.checkbox(&mut double_click.new_tab, "Tab bar opens a new scratch");
+                    ui.checkbox(&mut double_click.rename, "Tab renames it");
+                    ui.checkbox(&mut double_click.maximize, "Title bar maximizes the window");
+                });
+
+                ui.menu_button("External editor", |ui| {
+                    ui.label("Command projects are opened in");
+                    ui.add(
+                        TextEdit::singleline(&mut config.general.external_editor)
+                            .hint_text("$EDITOR, or code")
+                            .desired_width(160.0),
+                    )
+                    .on_hover_text(
+                        "It's run with the project's folder, e.g. code, zed or idea. \
+                        Empty uses $VISUAL or $EDITOR",
+                    );
+                });
+
+                #[cfg(target_os = "windows")]
+                ui.menu_button("Shortcut keys", |ui| {
+                    for shortcut_keys in ShortcutKeys::all() {
+                        if ui
+                            .radio_value(
+                                &mut config.general.shortcut_keys,
+                                shortcut_keys,
+                                shortcut_keys.label(),
+                            )
+                            .clicked()
+                        {
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                let editor = &mut config.editor;
+
+                ui.checkbox(&mut editor.settings.word_wrap, "Word wrap");
+                ui.checkbox(
+                    &mut editor.settings.normalize_paste,
+                    "Match line endings on paste",
+                )
+                .on_hover_text("Pasted text takes on the line endings of the code");
+                ui.checkbox(&mut editor.settings.smart_paste, "Smart paste")
+                    .on_hover_text(
+                        "Strip the prompts and line numbers code was copied with, and offer to \
+                         wrap pasted statements in fn main()",
+                    );
+                ui.checkbox(
+                    &mut editor.tidy_directives_on_save,
+                    "Tidy directives on save",
+                )
+                .on_hover_text("Sort and align the //# and //> lines when saving a scratch");
+                ui.checkbox(
+                    &mut editor.settings.highlight_brackets,
+                    "Highlight brackets",
+                )
+                .on_hover_text(
+                    "The bracket matching the one at the cursor and the block it's in. \
+                     Ctrl+] or ctrl+shift+m jumps to the match",
+                );
+                ui.checkbox(&mut editor.settings.minimap, "Minimap")
+                    .on_hover_text("A shrunken view of the whole code, click or drag it to scroll");
+                ui.checkbox(&mut editor.settings.vim, "Vim mode")
+                    .on_hover_text(
+                        "Normal, insert and visual modes, :w saves and :q closes the tab",
+                    );
+                ui.checkbox(&mut editor.rust_analyzer, "rust-analyzer")
+                    .on_hover_text(
+                        "Completions, types on hover, and go to definition with ctrl+click or \
+                         F12. Installed with `rustup component add rust-analyzer`",
+                    );
+                ui.add_enabled(
+                    !editor.rust_analyzer,
+                    Checkbox::new(&mut editor.check_while_typing, "Check while typing"),
+                )
+                .on_hover_text(
+                    "cargo check the code when typing stops and show its errors on hover, \
+                     with the explanation of their error code. rust-analyzer does this itself",
+                );
+
+                ui.horizontal(|ui| {
+                    ui.label("Font size");
+                    ui.add(
+                        DragValue::new(&mut editor.settings.font_size)
+                            .clamp_range(MIN_FONT_SIZE..=MAX_FONT_SIZE)
+                            .speed(0.5),
+                    );
+                });
+
+                ui.menu_button("Font family", |ui| {
+                    let mut selected = editor.font_family.clone();
+
+                    ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        ui.selectable_value(&mut selected, None, "Default");
+
+                        for family in fonts::monospace_families() {
+                            ui.selectable_value(&mut selected, Some(family.clone()), family);
+                        }
+                    });
+
+                    if selected != editor.font_family {
+                        fonts::apply_monospace_family(ctx, selected.as_deref());
+                        editor.font_family = selected;
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Syntax theme", |ui| {
+                    syntax_theme_picker(
+                        ui,
+                        &mut config.theme.syntax_theme,
+                        "Follow light/dark mode",
+                    );
+
+                    ui.separator();
+
+                    if ui.button("Open themes folder").clicked() {
+                        let dir = themes_dir();
+                        let _ = fs::create_dir_all(&dir);
+                        let _ = open::that(dir);
+                    }
+
+                    if ui.button("Reload themes").clicked() {
+                        reload_themes(ctx);
+                    }
+                });
+            });
+
+            ui.menu_button("Help", |ui| {
+                if ui.button("Repository").clicked() {
+                    let _ = open::that(REPOSITORY_URL);
+                    ui.close_menu();
+                }
+
+                if ui.button("Report an issue").clicked() {
+                    let _ = open::that(format!("{REPOSITORY_URL}/issues/new"));
+                    ui.close_menu();
+                }
+
+                if ui
+                    .button("Create diagnostic bundle...")
+                    .on_hover_text("Settings without secrets, panics, toolchain and the last build, to attach to an issue")
+                    .clicked()
+                {
+                    DiagnosticBundle::create(ctx, config);
+                    ui.close_menu();
+                }
+
+                if ui
+                    .button("Check Rust installation...")
+                    .on_hover_text("Whether cargo, rustup and the stable toolchain are found")
+                    .clicked()
+                {
+                    SetupCheck::open(ctx, config);
+                    ui.close_menu();
+                }
+
+                ui.checkbox(&mut config.performance.open, "Performance overlay")
+                    .on_hover_text("Frame times, repaints and cache use, to attach to an issue");
+
+                ui.separator();
+
+                if ui.button("About").clicked() {
+                    let is_open =
+                        config.dock.commands.iter().any(|command| {
+                            matches!(command, Command::MenuCommand(MenuCommand::About))
+                        });
+
+                    if !is_open {
+                        commands.push(Command::MenuCommand(MenuCommand::About));
+                    }
+
+                    ui.close_menu();
+                }
+            });
+
+            UpdateNudge::show(ctx, config, ui);
+
+            config.dock.commands.extend(commands);
+        });
+
+        response.response.rect
+    }
+
+    /// Commands of the menus which don't act on a tab, for the command palette
+    pub fn palette_commands(config: &mut Config) -> Vec<PaletteCommand> {
+        let mut commands = vec![
+            PaletteCommand::run("File: Open...", |_, config| {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Rust", &["rs"])
+                    .pick_file()
+                {
+                    config
+                        .dock
+                        .commands
+                        .push(Command::TabCommand(TabCommand::Open(path)));
+                }
+            }),
+            PaletteCommand::run("Workspace: Save as...", |_, config| {
+                open_window(config, MenuCommand::SaveWorkspace)
+            }),
+            PaletteCommand::run("File: My Gists", |_, config| {
+                open_window(config, MenuCommand::Gists)
+            }),
+            PaletteCommand::run("File: Share all open scratches...", |_, config| {
+                open_window(config, MenuCommand::ShareAll)
+            }),
+            PaletteCommand::run("View: Environment presets...", |_, config| {
+                open_window(config, MenuCommand::Presets)
+            }),
+            PaletteCommand::run("View: Toggle word wrap", |_, config| {
+                config.editor.settings.word_wrap = !config.editor.settings.word_wrap
+            }),
+            PaletteCommand::run("Settings: Open settings file", |ctx, config| {
+                // the file is only written on exit otherwise
+                match config.save() {
+                    Ok(()) => {
+                        let _ = open::that(settings_file());
+                    }
+
+                    Err(e) => Toast::error(format!("Failed to write the settings: {e}")).push(ctx),
+                }
+            }),
+            PaletteCommand::run("Help: About", |_, config| {
+                open_window(config, MenuCommand::About)
+            }),
+            PaletteCommand::run("Help: Toggle performance overlay", |_, config| {
+                config.performance.open = !config.performance.open
+            }),
+            PaletteCommand::run("Help: Create diagnostic bundle...", |ctx, config| {
+                DiagnosticBundle::create(ctx, config)
+            }),
+            PaletteCommand::run("Help: Check Rust installation...", |ctx, config| {
+                SetupCheck::open(ctx, config)
+            }),
+        ];
+
+        for name in &config.dock.workspaces {
+            if config.dock.workspace.as_ref() == Some(name) {
+                continue;
+            }
+
+            let command = Command::TabCommand(TabCommand::SwitchWorkspace(name.clone()));
+            commands.push(PaletteCommand::run(
+                format!("Workspace: Switch to {name}"),
+                move |_, config| config.dock.commands.push(command),
+            ));
+        }
+
+        for mode in ThemeMode::all() {
+            if mode == config.theme.mode {
+                continue;
+            }
+
+            commands.push(PaletteCommand::run(
+                format!("View: {} theme", mode.label()),
+                move |_, config| config.theme.mode = mode,
+            ));
+        }
+
+        commands
+    }
+}
+
+// queue a window unless it's already open
+fn open_window(config: &mut Config, window: MenuCommand) {
+    let is_open = config.dock.commands.iter().any(|command| match command {
+        Command::MenuCommand(open) => mem::discriminant(open) == mem::discriminant(&window),
+        _ => false,
+    });
+
+    if !is_open {
+        config.dock.commands.push(Command::MenuCommand(window));
+    }
+}
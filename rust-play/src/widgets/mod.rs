@@ -1,4 +1,28 @@
-pub mod code_editor;
+pub mod about;
+pub mod add_dependency;
+pub mod analyzer;
+pub mod binary_size;
+pub mod command_palette;
+pub mod compiler_hints;
+pub mod crate_sources;
+pub mod defender;
+pub mod diagnostics;
+pub mod disk_usage;
 pub mod dock;
+pub mod gists;
+pub mod hex_viewer;
+pub mod idle;
+pub mod lints;
+pub mod menubar;
+pub mod output_export;
+pub mod pager;
+pub mod performance;
+pub mod presets;
+pub mod project_export;
+pub mod quick_run;
+pub mod setup;
 pub mod terminal;
 pub mod titlebar;
+pub mod toasts;
+pub mod toolchains;
+pub mod update_nudge;
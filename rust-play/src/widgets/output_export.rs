@@ -0,0 +1,226 @@
+use std::fs;
+use std::path::PathBuf;
+
+use egui::{vec2, Align2, Button, Id, Window};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{self, FitTo};
+use resvg::usvg_text_layout::TreeTextToPath;
+use rustplay_widgets::ansi::TextProperty;
+use rustplay_widgets::export::{to_html, to_svg};
+use rustplay_widgets::terminal::DefaultColors;
+
+use crate::config::{self, AnsiColors, TabOutput};
+use crate::utils::fonts::export_font_db;
+
+use super::toasts::{Toast, ToastAction};
+
+// temp memory key of whether a tab's output is exported as a png rather than html
+const EXPORT: &str = "output_export";
+// the image is rendered at twice its size, so it stays sharp on high dpi screens
+const PNG_ZOOM: f32 = 2.0;
+
+/// Save the last output of a tab with its colors, as a standalone html snippet or a png, to share colored
+/// errors the way they're shown
+pub struct OutputExport;
+
+impl OutputExport {
+    /// Ask whether to export html or a png, then where to save it. Returns false once it's closed
+    pub fn show(
+        ctx: &egui::Context,
+        id: Id,
+        terminal: &config::Terminal,
+        colors: AnsiColors,
+        font_family: Option<&str>,
+    ) -> bool {
+        let output = terminal
+            .output
+            .get(&id)
+            .filter(|output| !(output.stdout.text().is_empty() && output.stderr.text().is_empty()));
+
+        let Some(output) = output else {
+            Toast::info("There's no output to export").push(ctx);
+            return false;
+        };
+
+        let state_id = Id::new(EXPORT).with(id);
+        let mut png = ctx.data().get_temp::<bool>(state_id).unwrap_or_default();
+
+        let mut open = true;
+        let mut save = false;
+        let mut copy = false;
+
+        Window::new("Export output")
+            .id(state_id)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut png, false, "HTML");
+                    ui.radio_value(&mut png, true, "PNG");
+                });
+
+                ui.horizontal(|ui| {
+                    save = ui.button("Save...").clicked();
+
+                    copy = ui
+                        .add_enabled(!png, Button::new("Copy HTML"))
+                        .on_hover_text("Paste it into anything which takes html")
+                        .clicked();
+                });
+            });
+
+        // the output is only put together once it's exported, not every frame the window is open
+        let exported = (save || copy) && Self::export(ctx, output, colors, font_family, png, copy);
+
+        if exported || !open {
+            ctx.data().remove::<bool>(state_id);
+            return false;
+        }
+
+        ctx.data().insert_temp(state_id, png);
+
+        true
+    }
+
+    // copy the output as html, or save it as html or a png. Returns whether it's done, a cancelled file
+    // dialog leaves the window open
+    fn export(
+        ctx: &egui::Context,
+        output: &TabOutput,
+        colors: AnsiColors,
+        font_family: Option<&str>,
+        png: bool,
+        copy: bool,
+    ) -> bool {
+        // the standard error comes first, like in the terminal
+        let (properties, text) = join(
+            (output.stderr.properties(), output.stderr.text()),
+            (output.stdout.properties(), output.stdout.text()),
+        );
+
+        let defaults = DefaultColors::from_visuals(&ctx.style().visuals);
+
+        if copy {
+            ctx.output().copied_text = to_html(defaults, colors, &properties, &text);
+            Toast::success("Copied the output as HTML").push(ctx);
+            return true;
+        }
+
+        let written = if png {
+            // the family the terminal is shown in, with the builtin one behind it
+            let family = match font_family {
+                Some(family) => format!("'{family}', monospace"),
+                None => "monospace".to_string(),
+            };
+
+            let svg = to_svg(defaults, colors, &properties, &text, &family);
+            save_png(&svg)
+        } else {
+            save_html(&to_html(defaults, colors, &properties, &text))
+        };
+
+        match written {
+            Ok(Some(path)) => {
+                Toast::success(format!("Exported the output to {}", path.display()))
+                    .action(ToastAction::Open {
+                        label: "Open".to_string(),
+                        url: path.to_string_lossy().to_string(),
+                    })
+                    .push(ctx);
+
+                true
+            }
+
+            Ok(None) => false,
+
+            Err(e) => {
+                Toast::error(format!("Failed to export the output: {e}")).push(ctx);
+                false
+            }
+        }
+    }
+}
+
+// the standard error and output as one, a blank line between them if both printed something
+fn join(
+    (stderr_properties, stderr): (&[TextProperty], &str),
+    (stdout_properties, stdout): (&[TextProperty], &str),
+) -> (Vec<TextProperty>, String) {
+    let mut text = stderr.to_string();
+    let mut properties = stderr_properties.to_vec();
+
+    if !stderr.is_empty() && !stdout.is_empty() {
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        text.push('\n');
+    }
+
+    let offset = text.len();
+    text.push_str(stdout);
+
+    properties.extend(stdout_properties.iter().map(|property| TextProperty {
+        start: property.start + offset,
+        end: property.end + offset,
+        ..*property
+    }));
+
+    (properties, text)
+}
+
+// None if no file was picked
+fn save_html(html: &str) -> Result<Option<PathBuf>, String> {
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("HTML", &["html"])
+        .set_file_name("output.html")
+        .save_file()
+    else {
+        return Ok(None);
+    };
+
+    // a page of its own, so it opens in a browser as well as it pastes into one
+    let page = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Output</title></head>\n<body>\n{html}\n</body>\n</html>\n"
+    );
+
+    fs::write(&path, page).map_err(|e| e.to_string())?;
+
+    Ok(Some(path))
+}
+
+// None if no file was picked
+fn save_png(svg: &str) -> Result<Option<PathBuf>, String> {
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("PNG", &["png"])
+        .set_file_name("output.png")
+        .save_file()
+    else {
+        return Ok(None);
+    };
+
+    let mut tree =
+        usvg::Tree::from_str(svg, &usvg::Options::default()).map_err(|e| e.to_string())?;
+    tree.convert_text(export_font_db(), false);
+
+    let size = tree.size.to_screen_size();
+    let width = (size.width() as f32 * PNG_ZOOM).ceil() as u32;
+    let height = (size.height() as f32 * PNG_ZOOM).ceil() as u32;
+
+    let mut pixmap = Pixmap::new(width, height).ok_or("The output is too large for an image")?;
+
+    resvg::render(
+        &tree,
+        FitTo::Zoom(PNG_ZOOM),
+        Transform::default(),
+        pixmap.as_mut(),
+    )
+    .ok_or("Failed to render the output")?;
+
+    let png = pixmap.encode_png().map_err(|e| e.to_string())?;
+    fs::write(&path, png).map_err(|e| e.to_string())?;
+
+    Ok(Some(path))
+}
@@ -0,0 +1,295 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+
+use cargo_player::{
+    explain_sections, manifest_sections, tree_sections, File, OutputSection, Project,
+};
+use egui::{CollapsingHeader, Id, RichText, ScrollArea, TextEdit, Ui, Window};
+use egui_dock::Node;
+
+#[cfg(target_os = "windows")]
+use {std::os::windows::process::CommandExt, windows::Win32::System::Threading::CREATE_NO_WINDOW};
+
+use super::dock::{Tab, Tree};
+
+// temp memory key of a pager's sections, None while its command is still running
+const PAGER: &str = "pager";
+// temp memory key of what's searched for in a pager
+const PAGER_SEARCH: &str = "pager_search";
+
+type Sections = Option<Result<Arc<Vec<OutputSection>>, String>>;
+
+/// Long output which doesn't change once it's printed, shown in a window of its own instead of the terminal.
+/// It's cut into sections which fold away, and can be searched
+pub struct OutputPager;
+
+impl OutputPager {
+    /// `cargo tree` of a tab, a section for every direct dependency. Returns false once it's closed
+    pub fn dependency_tree(
+        ctx: &egui::Context,
+        id: Id,
+        tree: &Tree,
+        base_dir: Option<PathBuf>,
+    ) -> bool {
+        let Some(tab) = Self::find_tab(tree, id) else {
+            return false;
+        };
+
+        let code = tab.editor.code.clone();
+        let edition = tab.edition();
+
+        Self::show(
+            ctx,
+            Id::new(PAGER).with(id),
+            format!("Dependency tree of {}", tab.name),
+            move || {
+                // a project of its own like cargo metadata, the scratch may be building. Every tab has
+                // its own, trees of two tabs may be worked out at once
+                let mut project = Project::new(("dependency_tree", id));
+
+                if let Some(base_dir) = &base_dir {
+                    project.base_dir(base_dir);
+                }
+
+                project
+                    .file(File::new("main", &code))
+                    .edition(edition)
+                    .target_prefix("rust-play")
+                    .tree();
+
+                let mut command = project.create().map_err(|e| e.to_string())?;
+
+                // hide the console window from command
+                #[cfg(target_os = "windows")]
+                command.creation_flags(CREATE_NO_WINDOW.0);
+
+                Self::output(command).map(|output| tree_sections(&output))
+            },
+        )
+    }
+
+    /// The Cargo.toml a tab's scratch is played with, a section for every table. It's generated without
+    /// writing the project, so it's there before the first run. Returns false once it's closed
+    pub fn manifest(ctx: &egui::Context, id: Id, tree: &Tree, base_dir: Option<PathBuf>) -> bool {
+        let Some(tab) = Self::find_tab(tree, id) else {
+            return false;
+        };
+
+        let code = tab.editor.code.clone();
+        let edition = tab.edition();
+        let build = tab.build.clone();
+
+        Self::show(
+            ctx,
+            Id::new(PAGER).with(("manifest", id)),
+            format!("Cargo.toml of {}", tab.name),
+            move || {
+                let mut project = build.project(base_dir.as_deref());
+
+                project
+                    .file(File::new("main", &code))
+                    .edition(edition)
+                    .manifest()
+                    .map(|manifest| manifest_sections(&manifest))
+                    .map_err(|e| e.to_string())
+            },
+        )
+    }
+
+    /// What `rustc --explain` says about an error code, e.g. E0382, its examples as sections of their own.
+    /// Returns false once it's closed
+    pub fn explain(ctx: &egui::Context, code: &str) -> bool {
+        let code = code.to_string();
+
+        Self::show(
+            ctx,
+            Id::new(PAGER).with(&code),
+            format!("Explanation of {code}"),
+            move || {
+                let mut command = Command::new("rustc");
+                command.args(["--explain", &code]);
+
+                // hide the console window from command
+                #[cfg(target_os = "windows")]
+                command.creation_flags(CREATE_NO_WINDOW.0);
+
+                Self::output(command).map(|output| explain_sections(&output))
+            },
+        )
+    }
+
+    fn show(
+        ctx: &egui::Context,
+        pager_id: Id,
+        title: String,
+        load: impl FnOnce() -> Result<Vec<OutputSection>, String> + Send + 'static,
+    ) -> bool {
+        let sections = ctx.data().get_temp::<Sections>(pager_id);
+        let Some(sections) = sections else {
+            Self::load(ctx, pager_id, load);
+            return true;
+        };
+
+        let search_id = Id::new(PAGER_SEARCH).with(pager_id);
+        let mut search = ctx.data().get_temp::<String>(search_id).unwrap_or_default();
+
+        let mut open = true;
+
+        Window::new(title)
+            .id(pager_id)
+            .open(&mut open)
+            .default_size([560.0, 480.0])
+            .show(ctx, |ui| match &sections {
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Running...");
+                    });
+                }
+
+                Some(Err(e)) => {
+                    ui.label(format!("Failed to get the output:\n{e}"));
+                }
+
+                Some(Ok(sections)) if sections.is_empty() => {
+                    ui.label("Nothing was printed");
+                }
+
+                Some(Ok(sections)) => Self::show_sections(ui, pager_id, sections, &mut search),
+            });
+
+        // run again the next time it's opened, e.g. the dependencies may have changed by then
+        if open {
+            ctx.data().insert_temp(search_id, search);
+        } else {
+            ctx.data().remove::<Sections>(pager_id);
+            ctx.data().remove::<String>(search_id);
+        }
+
+        open
+    }
+
+    // the sections with something matching the search, which is highlighted. Searching unfolds them
+    fn show_sections(ui: &mut Ui, pager_id: Id, sections: &[OutputSection], search: &mut String) {
+        let mut fold = None;
+
+        let searched = ui
+            .horizontal(|ui| {
+                let searched = ui
+                    .add(
+                        TextEdit::singleline(search)
+                            .hint_text("Search")
+                            .desired_width(200.0),
+                    )
+                    .changed();
+
+                if ui.button("Expand all").clicked() {
+                    fold = Some(true);
+                }
+
+                if ui.button("Collapse all").clicked() {
+                    fold = Some(false);
+                }
+
+                searched
+            })
+            .inner;
+
+        ui.separator();
+
+        let query = search.to_lowercase();
+        let matches = |text: &str| !query.is_empty() && text.to_lowercase().contains(&query);
+        let highlight = ui.visuals().selection.bg_fill;
+
+        let text = |text: &str| {
+            let rich = RichText::new(text).monospace();
+
+            match matches(text) {
+                true => rich.background_color(highlight),
+                false => rich,
+            }
+        };
+
+        let shown = sections
+            .iter()
+            .enumerate()
+            .filter(|(_, section)| {
+                query.is_empty()
+                    || matches(&section.title)
+                    || section.lines.iter().any(|line| matches(line))
+            })
+            .collect::<Vec<_>>();
+
+        if shown.is_empty() {
+            ui.label("Nothing matches the search");
+            return;
+        }
+
+        let open = fold.or((searched && !query.is_empty()).then_some(true));
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for (i, section) in shown {
+                    // nothing to fold, lined up with the titles which have a fold button
+                    if section.lines.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.add_space(ui.spacing().indent);
+                            ui.label(text(&section.title));
+                        });
+
+                        continue;
+                    }
+
+                    CollapsingHeader::new(text(&section.title))
+                        .id_source(pager_id.with(i))
+                        .open(open)
+                        .show(ui, |ui| {
+                            for line in &section.lines {
+                                ui.label(text(line));
+                            }
+                        });
+                }
+            });
+    }
+
+    fn load(
+        ctx: &egui::Context,
+        pager_id: Id,
+        load: impl FnOnce() -> Result<Vec<OutputSection>, String> + Send + 'static,
+    ) {
+        ctx.data().insert_temp::<Sections>(pager_id, None);
+
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = load().map(Arc::new);
+
+            ctx.data().insert_temp::<Sections>(pager_id, Some(result));
+            ctx.request_repaint();
+        });
+    }
+
+    // None if the tab was closed in the meantime
+    fn find_tab(tree: &Tree, id: Id) -> Option<&Tab> {
+        tree.iter().find_map(|node| {
+            let Node::Leaf { tabs, .. } = node else {
+                return None;
+            };
+
+            tabs.iter().find(|tab| tab.id == id)
+        })
+    }
+
+    // all of stdout once the command is done
+    fn output(mut command: Command) -> Result<String, String> {
+        let output = command.output().map_err(|e| e.to_string())?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
@@ -0,0 +1,285 @@
+use std::time::{Duration, Instant};
+
+use egui::{pos2, vec2, Align2, Color32, Id, Rect, Sense, Stroke, Ui, Window};
+use rustplay_widgets::cache_stats::CacheStats;
+use rustplay_widgets::code_editor::HIGHLIGHT_STATS;
+
+use crate::config::{Config, FrameSample, Performance, RepaintCause};
+use crate::utils::threads::thread_count;
+
+use super::terminal::ANSI_STATS;
+
+// how many frames the timings are taken over
+const MAX_FRAMES: usize = 120;
+const THREAD_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+// a frame at 60 fps
+const FRAME_BUDGET: f32 = 1.0 / 60.0;
+const GRAPH_HEIGHT: f32 = 40.0;
+
+pub struct PerformanceHud;
+
+impl PerformanceHud {
+    /// Take the timings of the frame, at the start of it. Nothing is kept while the overlay is closed
+    pub fn record(ctx: &egui::Context, frame: &eframe::Frame, config: &mut Config) {
+        let now = Instant::now();
+        let last_frame = config.performance.last_frame.replace(now);
+
+        if !config.performance.open {
+            config.performance.frames.clear();
+            return;
+        }
+
+        let Some(last_frame) = last_frame else {
+            return;
+        };
+
+        let running = ctx
+            .data()
+            .get_temp::<u64>(Id::new("continuous_mode"))
+            .unwrap_or_default();
+
+        let cause = {
+            let input = ctx.input();
+
+            if !input.events.is_empty() || input.pointer.is_moving() || input.pointer.any_down() {
+                RepaintCause::Input
+            } else if running > 0 {
+                RepaintCause::Run
+            } else {
+                RepaintCause::Scheduled
+            }
+        };
+
+        let performance = &mut config.performance;
+
+        performance.frames.push_back(FrameSample {
+            interval: (now - last_frame).as_secs_f32(),
+            cpu: frame.info().cpu_usage,
+            cause,
+        });
+
+        while performance.frames.len() > MAX_FRAMES {
+            performance.frames.pop_front();
+        }
+
+        let sample_threads = performance
+            .threads_sampled
+            .map_or(true, |sampled| sampled.elapsed() >= THREAD_SAMPLE_INTERVAL);
+
+        if sample_threads {
+            performance.threads = thread_count();
+            performance.threads_sampled = Some(now);
+        }
+    }
+
+    /// The overlay with the frame timings, why frames were painted, how well the layout caches are
+    /// reused and how many threads there are. Its report can be copied into an issue
+    pub fn show(ctx: &egui::Context, config: &mut Config) {
+        if !config.performance.open {
+            return;
+        }
+
+        let runs = config.terminal.abortable.len();
+        let performance = &mut config.performance;
+        let mut open = true;
+
+        Window::new("Performance")
+            .open(&mut open)
+            .resizable(false)
+            .default_pos(pos2(f32::INFINITY, 0.0))
+            .pivot(Align2::RIGHT_TOP)
+            .show(ctx, |ui| {
+                if performance.frames.is_empty() {
+                    ui.weak("Waiting for frames...");
+                    return;
+                }
+
+                ui.label(Self::frame_summary(performance));
+                Self::show_graph(ui, performance);
+
+                ui.separator();
+
+                ui.strong("Repaints");
+                for (cause, count) in repaints(performance) {
+                    ui.label(format!("{}: {count}", cause.label()));
+                }
+
+                ui.separator();
+
+                ui.strong("Caches");
+                ui.label(cache_summary(
+                    "Highlighting",
+                    &CacheStats::of(ctx, HIGHLIGHT_STATS),
+                ));
+                ui.label(cache_summary(
+                    "Terminal colors",
+                    &CacheStats::of(ctx, ANSI_STATS),
+                ));
+
+                ui.separator();
+
+                ui.label(format!(
+                    "Threads: {}, runs going: {runs}",
+                    threads(performance)
+                ));
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Copy report").clicked() {
+                        ui.output().copied_text = Self::report(ctx, performance, runs);
+                    }
+
+                    if ui
+                        .button("Reset caches")
+                        .on_hover_text("Count the cache hits from now on")
+                        .clicked()
+                    {
+                        CacheStats::of(ctx, HIGHLIGHT_STATS).reset();
+                        CacheStats::of(ctx, ANSI_STATS).reset();
+                    }
+                });
+            });
+
+        performance.open = open;
+    }
+
+    fn frame_summary(performance: &Performance) -> String {
+        let frames = &performance.frames;
+
+        let intervals = frames.iter().map(|frame| frame.interval);
+        let average = intervals.clone().sum::<f32>() / frames.len() as f32;
+        let slowest = intervals.fold(0.0, f32::max);
+
+        let cpu = frames
+            .iter()
+            .filter_map(|frame| frame.cpu)
+            .collect::<Vec<_>>();
+        let cpu = if cpu.is_empty() {
+            String::new()
+        } else {
+            let average = cpu.iter().sum::<f32>() / cpu.len() as f32;
+            let slowest = cpu.iter().copied().fold(0.0, f32::max);
+
+            format!(
+                "\nCPU {:.1} ms average, {:.1} ms slowest",
+                average * 1000.0,
+                slowest * 1000.0
+            )
+        };
+
+        format!(
+            "{:.0} fps, {:.1} ms between frames, {:.1} ms slowest{cpu}",
+            1.0 / average.max(f32::EPSILON),
+            average * 1000.0,
+            slowest * 1000.0,
+        )
+    }
+
+    // the cpu time of every frame as a bar, frames over budget in red
+    fn show_graph(ui: &mut Ui, performance: &Performance) {
+        let (rect, _) = ui.allocate_exact_size(vec2(240.0, GRAPH_HEIGHT), Sense::hover());
+
+        let frames = &performance.frames;
+        let slowest = frames
+            .iter()
+            .map(|frame| frame.cpu.unwrap_or(frame.interval))
+            .fold(FRAME_BUDGET * 2.0, f32::max);
+
+        let width = rect.width() / MAX_FRAMES as f32;
+        let painter = ui.painter();
+
+        for (i, frame) in frames.iter().enumerate() {
+            let time = frame.cpu.unwrap_or(frame.interval);
+            let height = time / slowest * rect.height();
+            let left = rect.left() + i as f32 * width;
+
+            let color = if time > FRAME_BUDGET {
+                ui.visuals().error_fg_color
+            } else {
+                ui.visuals().selection.bg_fill
+            };
+
+            painter.rect_filled(
+                Rect::from_min_max(
+                    pos2(left, rect.bottom() - height),
+                    pos2(left + width, rect.bottom()),
+                ),
+                0.0,
+                color,
+            );
+        }
+
+        // the 60 fps budget
+        let budget = rect.bottom() - FRAME_BUDGET / slowest * rect.height();
+        painter.hline(
+            rect.left()..=rect.right(),
+            budget,
+            Stroke::new(1.0, Color32::GRAY),
+        );
+    }
+
+    // plain text for an issue
+    fn report(ctx: &egui::Context, performance: &Performance, runs: usize) -> String {
+        let mut report = Self::frame_summary(performance);
+
+        let repaints = repaints(performance)
+            .into_iter()
+            .map(|(cause, count)| format!("{} {count}", cause.label().to_lowercase()))
+            .collect::<Vec<_>>();
+        report.push_str(&format!("\nRepaints: {}", repaints.join(", ")));
+
+        report.push('\n');
+        report.push_str(&cache_summary(
+            "Highlighting",
+            &CacheStats::of(ctx, HIGHLIGHT_STATS),
+        ));
+        report.push('\n');
+        report.push_str(&cache_summary(
+            "Terminal colors",
+            &CacheStats::of(ctx, ANSI_STATS),
+        ));
+
+        report.push_str(&format!(
+            "\nThreads {}, runs going {runs}",
+            threads(performance)
+        ));
+        report.push_str(&format!("\nOS {}", std::env::consts::OS));
+
+        report
+    }
+}
+
+// how many of the frames each cause painted
+fn repaints(performance: &Performance) -> Vec<(RepaintCause, usize)> {
+    RepaintCause::all()
+        .into_iter()
+        .map(|cause| {
+            let count = performance
+                .frames
+                .iter()
+                .filter(|frame| frame.cause == cause)
+                .count();
+
+            (cause, count)
+        })
+        .collect()
+}
+
+fn threads(performance: &Performance) -> String {
+    performance
+        .threads
+        .map_or_else(|| "unknown".to_string(), |threads| threads.to_string())
+}
+
+fn cache_summary(name: &str, stats: &CacheStats) -> String {
+    match stats.hit_rate() {
+        Some(rate) => format!(
+            "{name}: {:.1}% hits, {} lookups, {} computed",
+            rate * 100.0,
+            stats.lookups(),
+            stats.misses()
+        ),
+        None => format!("{name}: not used yet"),
+    }
+}
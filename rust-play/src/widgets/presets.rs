@@ -0,0 +1,121 @@
+use egui::{CollapsingHeader, ComboBox, Grid, ScrollArea, TextEdit, Ui, Window};
+
+use crate::config::{EnvPreset, Presets};
+use crate::utils::toolchain::CHANNELS;
+
+pub struct PresetEditor;
+
+impl PresetEditor {
+    /// Edit the environment presets tabs can pick from. Returns false once it's closed
+    pub fn show(ctx: &egui::Context, presets: &mut Presets) -> bool {
+        let mut open = true;
+
+        Window::new("Environment presets")
+            .open(&mut open)
+            .default_size([420.0, 360.0])
+            .show(ctx, |ui| {
+                let mut remove = None;
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    for (i, preset) in presets.list.iter_mut().enumerate() {
+                        // the index keeps the header open while the name is edited
+                        CollapsingHeader::new(&preset.name)
+                            .id_source(("env_preset", i))
+                            .show(ui, |ui| {
+                                Self::show_preset(ui, preset, i);
+
+                                if ui.button("Remove preset").clicked() {
+                                    remove = Some(i);
+                                }
+                            });
+                    }
+                });
+
+                if let Some(i) = remove {
+                    presets.list.remove(i);
+                }
+
+                ui.separator();
+
+                if ui.button("Add preset").clicked() {
+                    presets.list.push(EnvPreset {
+                        name: format!("preset {}", presets.list.len() + 1),
+                        ..Default::default()
+                    });
+                }
+            });
+
+        open
+    }
+
+    fn show_preset(ui: &mut Ui, preset: &mut EnvPreset, i: usize) {
+        Grid::new(("env_preset_options", i))
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut preset.name);
+                ui.end_row();
+
+                ui.label("Channel");
+                let label = |channel: Option<_>| match channel {
+                    Some(channel) => <&str>::from(channel),
+                    None => "Keep the tab's",
+                };
+                ComboBox::from_id_source(("env_preset_channel", i))
+                    .selected_text(label(preset.channel))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut preset.channel, None, label(None));
+
+                        for channel in CHANNELS {
+                            ui.selectable_value(
+                                &mut preset.channel,
+                                Some(channel),
+                                label(Some(channel)),
+                            );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("RUSTFLAGS");
+                ui.add(
+                    TextEdit::singleline(&mut preset.rust_flags)
+                        .hint_text("-Zsanitizer=address")
+                        .desired_width(200.0),
+                );
+                ui.end_row();
+            });
+
+        let mut remove = None;
+
+        Grid::new(("env_preset_vars", i))
+            .num_columns(3)
+            .show(ui, |ui| {
+                for (j, (var, val)) in preset.vars.iter_mut().enumerate() {
+                    ui.add(
+                        TextEdit::singleline(var)
+                            .hint_text("NAME")
+                            .desired_width(120.0),
+                    );
+                    ui.add(
+                        TextEdit::singleline(val)
+                            .hint_text("value")
+                            .desired_width(180.0),
+                    );
+
+                    if ui.button("🗑").clicked() {
+                        remove = Some(j);
+                    }
+
+                    ui.end_row();
+                }
+            });
+
+        if let Some(j) = remove {
+            preset.vars.remove(j);
+        }
+
+        if ui.button("Add variable").clicked() {
+            preset.vars.push(Default::default());
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cargo_player::{pin_versions, validate_name, File, Project};
+use egui::{vec2, Align2, Button, Id, TextEdit, Window};
+use egui_dock::Node;
+
+#[cfg(target_os = "windows")]
+use {std::os::windows::process::CommandExt, windows::Win32::System::Threading::CREATE_NO_WINDOW};
+
+use crate::utils::zip::ZipWriter;
+
+use super::dock::{Tab, Tree};
+use super::toasts::{Toast, ToastAction};
+
+// temp memory key of the package name typed into a tab's export window, and whether it's zipped
+const EXPORT: &str = "project_export";
+// like cargo new, the build output is kept out of git
+const GITIGNORE: &str = "/target\n";
+
+#[derive(Clone)]
+struct ExportState {
+    name: String,
+    zip: bool,
+}
+
+/// Turn a scratch into a project of its own: the Cargo.toml it's generated with, its code and the
+/// versions it last built with pinned, under a package name of the user's
+pub struct ProjectExport;
+
+impl ProjectExport {
+    /// Ask for the package name, then for the folder or zip file to export the tab's project to.
+    /// Returns false once it's closed
+    pub fn show(ctx: &egui::Context, id: Id, tree: &Tree, base_dir: Option<PathBuf>) -> bool {
+        let Some(tab) = find_tab(tree, id) else {
+            return false;
+        };
+
+        let state_id = Id::new(EXPORT).with(id);
+        let mut state = ctx
+            .data()
+            .get_temp::<ExportState>(state_id)
+            .unwrap_or_else(|| ExportState {
+                name: default_name(tab),
+                zip: false,
+            });
+
+        let mut open = true;
+        let mut export = false;
+
+        Window::new(format!("Export {}", tab.name))
+            .id(state_id)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Package name");
+                    ui.add(TextEdit::singleline(&mut state.name).hint_text("my-project"));
+                });
+
+                let error = validate_name(state.name.trim()).err();
+                if let Some(e) = &error {
+                    ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+                }
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut state.zip, false, "Folder");
+                    ui.radio_value(&mut state.zip, true, "Zip");
+                });
+
+                export = ui
+                    .add_enabled(error.is_none(), Button::new("Export..."))
+                    .clicked();
+            });
+
+        // a cancelled file dialog leaves the window open
+        let exported = export && Self::export(ctx, tab, &state, base_dir.as_deref());
+
+        if exported || !open {
+            ctx.data().remove::<ExportState>(state_id);
+            return false;
+        }
+
+        ctx.data().insert_temp(state_id, state);
+
+        true
+    }
+
+    // returns whether it was exported
+    fn export(
+        ctx: &egui::Context,
+        tab: &Tab,
+        state: &ExportState,
+        base_dir: Option<&Path>,
+    ) -> bool {
+        let name = state.name.trim();
+
+        let mut project = tab.build.project(base_dir);
+        project
+            .file(File::new("main", &tab.editor.code))
+            .edition(tab.edition())
+            .package_name(name);
+
+        let mut sources = match project.sources() {
+            Ok(sources) => sources,
+            Err(e) => {
+                Toast::error(format!("Failed to export {}: {e}", tab.name)).push(ctx);
+                return false;
+            }
+        };
+
+        // the versions it's built with are pinned in the Cargo.toml as well, a cargo update doesn't
+        // change them
+        match export_lock(tab, name, base_dir) {
+            Ok(lock) => {
+                for (path, contents) in &mut sources {
+                    if path == "Cargo.toml" {
+                        *contents = pin_versions(contents, &lock);
+                    }
+                }
+
+                sources.push(("Cargo.lock".to_string(), lock));
+            }
+
+            Err(e) => Toast::warning(format!(
+                "The versions of {}'s dependencies aren't pinned, they couldn't be resolved: {e}",
+                tab.name
+            ))
+            .push(ctx),
+        }
+
+        sources.push((".gitignore".to_string(), GITIGNORE.to_string()));
+
+        let written = if state.zip {
+            write_zip(name, &sources)
+        } else {
+            write_folder(name, &sources)
+        };
+
+        match written {
+            Ok(Some(path)) => {
+                Toast::success(format!("Exported {} to {}", tab.name, path.display()))
+                    .action(ToastAction::Open {
+                        label: "Open".to_string(),
+                        url: path.to_string_lossy().to_string(),
+                    })
+                    .push(ctx);
+
+                true
+            }
+
+            Ok(None) => false,
+
+            Err(e) => {
+                Toast::error(format!("Failed to export {}: {e}", tab.name)).push(ctx);
+                false
+            }
+        }
+    }
+}
+
+// the Cargo.lock of the exported project, resolved in a project of its own. It starts from the versions the
+// tab last built with, cargo adds the dependencies missing from it and drops the ones which aren't used
+fn export_lock(tab: &Tab, name: &str, base_dir: Option<&Path>) -> Result<String, String> {
+    let mut project = Project::new(("project_export", tab.id));
+    tab.build.apply(&mut project);
+
+    if let Some(base_dir) = base_dir {
+        project.base_dir(base_dir);
+    }
+
+    project
+        .file(File::new("main", &tab.editor.code))
+        .edition(tab.edition())
+        .package_name(name)
+        .metadata();
+
+    let dir = project.project_dir();
+    let lock = dir.join("Cargo.lock");
+
+    match built_lock(tab, base_dir) {
+        Some(built) => {
+            fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            fs::write(&lock, built).map_err(|e| e.to_string())?;
+        }
+
+        // the one left by an earlier export may have been of other dependencies
+        None => {
+            let _ = fs::remove_file(&lock);
+        }
+    }
+
+    let mut command = project.create().map_err(|e| e.to_string())?;
+
+    // hide the console window from command
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW.0);
+
+    let output = command.output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    fs::read_to_string(lock).map_err(|e| e.to_string())
+}
+
+// the Cargo.lock of the scratch project if it was last built for this tab. Every tab plays in the same
+// project, the lock in it may be of another tab's dependencies
+fn built_lock(tab: &Tab, base_dir: Option<&Path>) -> Option<String> {
+    let mut project = tab.build.project(base_dir);
+    project
+        .file(File::new("main", &tab.editor.code))
+        .edition(tab.edition());
+
+    let dir = project.path()?;
+    let manifest = project.manifest().ok()?;
+
+    if fs::read_to_string(dir.join("Cargo.toml")).ok()? != manifest {
+        return None;
+    }
+
+    fs::read_to_string(dir.join("Cargo.lock")).ok()
+}
+
+// a folder named after the package in the one picked. None if no folder was picked
+fn write_folder(name: &str, sources: &[(String, String)]) -> Result<Option<PathBuf>, String> {
+    let Some(parent) = rfd::FileDialog::new().pick_folder() else {
+        return Ok(None);
+    };
+
+    let dir = parent.join(name);
+
+    // a project already there isn't overwritten
+    if dir.exists() {
+        return Err(format!("{} already exists", dir.display()));
+    }
+
+    for (path, contents) in sources {
+        let path = dir.join(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        fs::write(&path, contents).map_err(|e| e.to_string())?;
+    }
+
+    Ok(Some(dir))
+}
+
+// the files in a folder named after the package, like a zip of the folder. None if no file was picked
+fn write_zip(name: &str, sources: &[(String, String)]) -> Result<Option<PathBuf>, String> {
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("Zip", &["zip"])
+        .set_file_name(&format!("{name}.zip"))
+        .save_file()
+    else {
+        return Ok(None);
+    };
+
+    let mut zip = ZipWriter::default();
+
+    for (file, contents) in sources {
+        zip.file(&format!("{name}/{file}"), contents.as_bytes());
+    }
+
+    fs::write(&path, zip.finish()).map_err(|e| e.to_string())?;
+
+    Ok(Some(path))
+}
+
+// the package name of the tab, or its name as one, e.g. scratch-1
+fn default_name(tab: &Tab) -> String {
+    let package = tab.build.package_name.trim();
+    if validate_name(package).is_ok() {
+        return package.to_string();
+    }
+
+    let name = tab
+        .name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if validate_name(&name).is_ok() {
+        name
+    } else {
+        "scratch".to_string()
+    }
+}
+
+fn find_tab(tree: &Tree, id: Id) -> Option<&Tab> {
+    tree.iter().find_map(|node| {
+        let Node::Leaf { tabs, .. } = node else {
+            return None;
+        };
+
+        tabs.iter().find(|tab| tab.id == id)
+    })
+}
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+
+use cargo_player::{BuildType, Edition, File, Project, Subcommand};
+use egui::{vec2, Align2, Id, Window};
+use egui_dock::NodeIndex;
+
+use crate::config::{Command, Config, QuickRun, TabCommand, Template, Terminal};
+
+use super::dock::TabEvents;
+use super::terminal;
+
+// the output of quick runs goes to the terminal under this id instead of a tab's
+const QUICK_RUN: &str = "quick_run";
+
+pub struct QuickRunPopup;
+
+impl QuickRunPopup {
+    /// Build and run a template in its own project, the scratches' project isn't touched.
+    /// A quick run still going is stopped first, there's only the one popup
+    pub fn start(
+        ctx: &egui::Context,
+        terminal: &mut Terminal,
+        quick_run: &mut QuickRun,
+        base_dir: Option<PathBuf>,
+        node: NodeIndex,
+        template: Template,
+    ) {
+        let id = Id::new(QUICK_RUN);
+        let code = template.code.clone();
+
+        TabEvents::run_in_terminal(
+            ctx,
+            terminal,
+            id,
+            template.name.clone(),
+            None,
+            move || {
+                let mut project = Project::new(id);
+
+                if let Some(base_dir) = &base_dir {
+                    project.base_dir(base_dir);
+                }
+
+                project
+                    .build_type(BuildType::Debug)
+                    .file(File::new("main", &code))
+                    .edition(Edition::E2021)
+                    .subcommand(Subcommand::Run)
+                    .target_prefix("rust-play")
+                    .env_var("CARGO_TERM_COLOR", "always");
+
+                Ok(vec![project.create()?])
+            },
+            |_| (),
+        );
+
+        quick_run.running = Some((node, template));
+    }
+
+    /// The output of the quick run streaming in. Closing the popup stops the run and drops what it printed
+    pub fn show(ctx: &egui::Context, config: &mut Config) {
+        let Some((node, template)) = config.quick_run.running.clone() else {
+            return;
+        };
+
+        let id = Id::new(QUICK_RUN);
+        let running = TabEvents::is_running(ctx, &config.terminal, id);
+
+        let mut open = true;
+        let mut run_again = false;
+        let mut open_in_tab = false;
+
+        Window::new(format!("Quick run: {}", template.name))
+            .id(id.with("popup"))
+            .open(&mut open)
+            .collapsible(false)
+            .default_size(vec2(600.0, 400.0))
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if running {
+                        if ui.button("Stop").clicked() {
+                            TabEvents::stop(ctx, &mut config.terminal, id);
+                        }
+                    } else {
+                        run_again = ui.button("Run again").clicked();
+                    }
+
+                    open_in_tab = ui
+                        .button("Open in tab")
+                        .on_hover_text("Start a scratch from the template")
+                        .clicked();
+                });
+
+                ui.separator();
+
+                // the buttons of the output act on tabs, there's none for a quick run
+                let _ = terminal::Terminal::show_output(
+                    ui,
+                    &mut config.terminal,
+                    &config.editor.settings.zoomed(config.terminal_settings.zoom),
+                    config.theme.get_ansi_colors(),
+                    config.terminal_settings.blink,
+                    id,
+                    id.with("popup_output"),
+                );
+            });
+
+        if run_again {
+            Self::start(
+                ctx,
+                &mut config.terminal,
+                &mut config.quick_run,
+                config.general.projects_dir(),
+                node,
+                template,
+            );
+            return;
+        }
+
+        if open_in_tab {
+            let command = TabCommand::AddWithCode(node, template.code);
+            config.dock.commands.push(Command::TabCommand(command));
+        }
+
+        if !open || open_in_tab {
+            Self::close(ctx, config);
+        }
+    }
+
+    fn close(ctx: &egui::Context, config: &mut Config) {
+        let id = Id::new(QUICK_RUN);
+
+        TabEvents::stop(ctx, &mut config.terminal, id);
+
+        let terminal = &mut config.terminal;
+        terminal.content.remove(&id);
+        terminal.output.remove(&id);
+        terminal.finished.remove(&id);
+        terminal
+            .scroll_offset
+            .remove(&id.with("popup_output").with(id));
+
+        config.quick_run.running = None;
+    }
+}
@@ -0,0 +1,225 @@
+use std::env;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, TryRecvError};
+use std::thread;
+
+use cargo_player::Channel;
+use egui::{vec2, Align2, Button, Id, RichText, Ui, Window};
+
+use crate::config::{Command, Config, Environment, MenuCommand, Setup};
+use crate::utils::toolchain;
+
+/// Temp memory key set by a run which couldn't start its command because the program wasn't found
+pub const MISSING_TOOL: &str = "setup_missing_tool";
+
+const RUSTUP_URL: &str = "https://rustup.rs";
+
+pub struct SetupCheck;
+
+impl SetupCheck {
+    /// Check for cargo, rustup and a stable toolchain once at startup, and again after a run couldn't find
+    /// its program. Whatever is missing is explained in a window, with what fixes it
+    pub fn show(ctx: &egui::Context, config: &mut Config) {
+        Self::pick_up(ctx, config);
+
+        let setup = &mut config.setup;
+
+        if setup.environment.is_none() && setup.checking.is_none() {
+            Self::check(ctx, setup);
+        }
+
+        if let Some(checking) = &setup.checking {
+            match checking.try_recv() {
+                Ok(environment) => {
+                    // a first run without anything missing never sees the window
+                    setup.open |= !environment.is_ready();
+                    setup.environment = Some(environment);
+                    setup.checking = None;
+                }
+
+                Err(TryRecvError::Disconnected) => setup.checking = None,
+
+                Err(TryRecvError::Empty) => (),
+            }
+        }
+
+        if !setup.open {
+            return;
+        }
+
+        let mut open = true;
+        let mut install = false;
+        let mut recheck = false;
+
+        Window::new("Setup")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(450.0)
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                let Some(environment) = &setup.environment else {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Checking the Rust installation...");
+                    });
+
+                    return;
+                };
+
+                if environment.is_ready() {
+                    ui.label("Everything needed to run scratches is installed.");
+                } else {
+                    ui.label(
+                        "Rust Play runs scratches with cargo, but some of what it needs is missing:",
+                    );
+                }
+
+                ui.add_space(4.0);
+
+                Self::tool(ui, "cargo", &environment.cargo);
+                Self::tool(ui, "rustup", &environment.rustup);
+                Self::tool(ui, "stable toolchain", &environment.stable);
+
+                ui.add_space(4.0);
+
+                if environment.cargo_bin_off_path {
+                    ui.label(format!(
+                        "They're installed in {}, which isn't on the PATH Rust Play was started \
+                         with. Restart it, or sign out and back in if that doesn't help.",
+                        cargo_bin_dir().map(|dir| dir.display().to_string()).unwrap_or_default()
+                    ));
+                } else if environment.rustup.is_none() {
+                    ui.label(
+                        "rustup installs cargo and the toolchains, and keeps them up to date. \
+                         Once it's installed, restart Rust Play so it's found on the PATH.",
+                    );
+                } else if environment.stable.is_none() {
+                    ui.label("rustup can install the stable toolchain, its output shows in the terminal.");
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if environment.rustup.is_none() && ui.button("Open rustup.rs").clicked() {
+                        let _ = open::that(RUSTUP_URL);
+                    }
+
+                    let can_install = environment.rustup.is_some() && environment.stable.is_none();
+                    if can_install && ui.button("Install stable").clicked() {
+                        install = true;
+                    }
+
+                    let checking = setup.checking.is_some();
+
+                    recheck = ui.add_enabled(!checking, Button::new("Re-check")).clicked();
+
+                    if checking {
+                        ui.spinner();
+                    }
+                });
+            });
+
+        if recheck {
+            Self::check(ctx, &mut config.setup);
+        }
+
+        if install {
+            config
+                .dock
+                .commands
+                .push(Command::MenuCommand(MenuCommand::InstallToolchain(
+                    Channel::Stable,
+                    None,
+                )));
+        }
+
+        if !open {
+            config.setup.open = false;
+        }
+    }
+
+    /// Check again and show the result, e.g. from the help menu
+    pub fn open(ctx: &egui::Context, config: &mut Config) {
+        config.setup.open = true;
+        Self::check(ctx, &mut config.setup);
+    }
+
+    fn check(ctx: &egui::Context, setup: &mut Setup) {
+        let (tx, rx) = channel();
+        setup.checking = Some(rx);
+
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let _ = tx.send(environment());
+
+            ctx.request_repaint();
+        });
+    }
+
+    // a run couldn't find cargo, or whichever program it started
+    fn pick_up(ctx: &egui::Context, config: &mut Config) {
+        let id = Id::new(MISSING_TOOL);
+
+        let missing = ctx.data().get_temp::<bool>(id);
+        if missing.is_none() {
+            return;
+        }
+
+        ctx.data().remove::<bool>(id);
+
+        Self::open(ctx, config);
+    }
+
+    fn tool(ui: &mut Ui, name: &str, version: &Option<String>) {
+        ui.horizontal(|ui| {
+            match version {
+                Some(_) => ui.label("✔"),
+                None => ui.colored_label(ui.visuals().error_fg_color, "✖"),
+            };
+
+            ui.label(name);
+
+            if let Some(version) = version {
+                ui.weak(RichText::new(version).monospace());
+            }
+        });
+    }
+}
+
+fn environment() -> Environment {
+    let cargo = toolchain::version("cargo");
+    let rustup = toolchain::version("rustup");
+
+    let stable = match rustup {
+        Some(_) => toolchain::installed_stable(),
+        // e.g. a package manager's rust, which has no channels
+        None => toolchain::version("rustc"),
+    };
+
+    let cargo_bin_off_path = cargo.is_none()
+        && cargo_bin_dir().map_or(false, |dir| {
+            dir.join("cargo")
+                .with_extension(env::consts::EXE_EXTENSION)
+                .is_file()
+        });
+
+    Environment {
+        cargo,
+        rustup,
+        stable,
+        cargo_bin_off_path,
+    }
+}
+
+// where rustup puts cargo, which its installer adds to the PATH
+fn cargo_bin_dir() -> Option<PathBuf> {
+    let home = env::var_os("CARGO_HOME").map(PathBuf::from).or_else(|| {
+        let home = env::var_os("USERPROFILE").or_else(|| env::var_os("HOME"))?;
+
+        Some(PathBuf::from(home).join(".cargo"))
+    })?;
+
+    Some(home.join("bin"))
+}
@@ -1,170 +1,269 @@
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
+use std::ops::Range;
 use std::sync::Arc;
+use std::time::Duration;
 
-use egui::mutex::Mutex;
+use cargo_player::{
+    error_codes, fuzz_crash, fuzz_stats, lints, loom_summary, miri_diagnostics, sanitizer_reports,
+    Diagnostic, FuzzFn, FuzzStats, Level, LoomSummary, SanitizerReport,
+};
 use egui::panel::PanelState;
-use egui::text::LayoutJob;
-use egui::{pos2, vec2, Color32, CursorIcon, FontId, Id, Rect, Sense, Stroke, TextBuffer, Vec2};
-use once_cell::sync::OnceCell;
+use egui::text::{CCursor, LayoutJob};
+use egui::util::cache::CacheTrait;
+use egui::{vec2, Align, Color32, CursorIcon, Id, PointerButton, Rect, Sense, Ui};
+use regex::Regex;
+use rustplay_widgets::ansi::TextProperty;
+use rustplay_widgets::cache_stats::CacheStats;
+use rustplay_widgets::code_editor::{zoom_input, EditorSettings};
+use rustplay_widgets::terminal::{
+    align_to_cells, ansi_layout_job, has_blinking, DefaultColors, ReadOnlyString,
+};
+
+use crate::config::{
+    self, AnsiColors, Command, Config, MenuCommand, TabCommand, TerminalPosition, TerminalSettings,
+};
+
+use super::binary_size::{SizeView, SIZE_VIEW};
+use super::command_palette::{CommandPalette, PaletteCommand};
+use super::compiler_hints::CompilerHints;
+use super::dock::TabEvents;
+use super::hex_viewer::{HexView, BINARY_OUTPUT};
+use super::lints::{LintList, LINT_RUN};
+use super::titlebar::TITLEBAR_HEIGHT;
 
-use crate::config::{AnsiColors, Config};
-use crate::utils::ansi_parser::{self, Color};
+// width the editor keeps when the terminal is docked to the side
+const MIN_EDITOR_WIDTH: f32 = 200.0;
+
+// temp memory key holding the tab id of a run which succeeded without printing anything
+pub const QUIET_RUN: &str = "terminal_quiet_run";
+// temp memory key holding the function a tab's last run fuzzed, if it was a fuzzing run
+pub const FUZZ_FUNCTION: &str = "terminal_fuzz_function";
+// temp memory key set for a tab whose last run was interpreted by Miri
+pub const MIRI_RUN: &str = "terminal_miri_run";
+// temp memory key of the error codes in a tab's stderr, with the length of the stderr they were found in
+const ERROR_CODES: &str = "terminal_error_codes";
+// the file of the scratch, the only one diagnostics can jump into
+const SCRATCH_FILE: &str = "src/main.rs";
+
+// how long blinking text is shown, and then hidden, in seconds
+const BLINK_INTERVAL: f64 = 0.5;
+
+/// The name of the [`CacheStats`] of how often [`parse_ansi`] found the layout of the output in its cache
+pub const ANSI_STATS: &str = "terminal_ansi";
+
+type ColorKey<'a> = (
+    DefaultColors,
+    AnsiColors,
+    &'a [TextProperty],
+    &'a str,
+    &'a str,
+    bool,
+);
+
+// Memoized ansi color layout, the output is parsed as it comes in
+pub fn parse_ansi(
+    ctx: &egui::Context,
+    ansi_colors: AnsiColors,
+    properties: &[TextProperty],
+    text: &str,
+    highlight_pattern: &str,
+    blink: bool,
+) -> LayoutJob {
+    impl<'a> egui::util::cache::ComputerMut<ColorKey<'a>, LayoutJob> for AnsiColorParser {
+        fn compute(&mut self, key: ColorKey<'a>) -> LayoutJob {
+            let (defaults, ansi_colors, properties, text, highlight_pattern, blink_visible) = key;
 
-use super::titlebar::TITLEBAR_HEIGHT;
+            let mut job = ansi_layout_job(defaults, ansi_colors, properties, text, blink_visible);
 
-// A read only string for multiline textedit
-struct ReadOnlyString<'a> {
-    content: &'a str,
-}
+            if !highlight_pattern.is_empty() {
+                if let Ok(pattern) = Regex::new(highlight_pattern) {
+                    highlight_lines(&mut job, &pattern);
+                }
+            }
 
-impl<'a> TextBuffer for ReadOnlyString<'a> {
-    fn is_mutable(&self) -> bool {
-        false
-    }
+            highlight_sanitizer_reports(&mut job);
 
-    fn as_str(&self) -> &str {
-        self.content
+            job
+        }
     }
 
-    fn insert_text(&mut self, _: &str, _: usize) -> usize {
-        0
-    }
+    type ColorCache = egui::util::cache::FrameCache<LayoutJob, AnsiColorParser>;
 
-    fn delete_char_range(&mut self, _: std::ops::Range<usize>) {}
+    let defaults = DefaultColors::from_visuals(&ctx.style().visuals);
 
-    fn clear(&mut self) {}
+    // blinking text is visible for the first interval, and hidden for the next
+    let time = ctx.input().time;
+    let blink_visible = !blink || (time / BLINK_INTERVAL) as u64 % 2 == 0;
 
-    fn replace(&mut self, _: &str) {}
-}
+    let stats = CacheStats::of(ctx, ANSI_STATS);
+    stats.lookup();
 
-impl<'a> ReadOnlyString<'a> {
-    fn new(content: &'a str) -> Self {
-        Self { content }
-    }
-}
+    let job = {
+        let mut memory = ctx.memory();
+        let color_cache = memory.caches.cache::<ColorCache>();
 
-// Memoized ansi color parsing
-pub fn parse_ansi(
-    ctx: &egui::Context,
-    ansi_colors: AnsiColors,
-    unparsed_text: &str,
-    text: &str,
-) -> LayoutJob {
-    impl egui::util::cache::ComputerMut<(u64, Color32, AnsiColors, &str, &str), LayoutJob>
-        for AnsiColorParser
-    {
-        fn compute(
-            &mut self,
-            (_, default_color, ansi_colors, unparsed_text, text): (
-                u64,
-                Color32,
-                AnsiColors,
-                &str,
-                &str,
-            ),
-        ) -> LayoutJob {
-            self.parse(default_color, ansi_colors, unparsed_text, text)
-        }
-    }
+        // the cache only drops layouts at the end of a frame, it grows when the layout was computed
+        let cached = color_cache.len();
+        let job = color_cache.get((
+            defaults,
+            ansi_colors,
+            properties,
+            text,
+            highlight_pattern,
+            blink_visible,
+        ));
 
-    type ColorCache = egui::util::cache::FrameCache<LayoutJob, AnsiColorParser>;
+        if color_cache.len() > cached {
+            stats.miss();
+        }
 
-    let mut s = DefaultHasher::new();
-    unparsed_text.hash(&mut s);
-    let hash = s.finish();
+        job
+    };
 
-    let default_color = { ctx.style().visuals.text_color() };
+    if blink && has_blinking(properties) {
+        let remaining = BLINK_INTERVAL - time % BLINK_INTERVAL;
+        ctx.request_repaint_after(Duration::from_secs_f64(remaining));
+    }
 
-    let mut memory = ctx.memory();
-    let color_cache = memory.caches.cache::<ColorCache>();
-    color_cache.get((hash, default_color, ansi_colors, unparsed_text, text))
+    job
 }
 
-struct AnsiColorParser;
+// Give every line matching the pattern a highlighted background
+fn highlight_lines(job: &mut LayoutJob, pattern: &Regex) {
+    let background = Color32::from_rgba_unmultiplied(255, 200, 0, 40);
 
-impl Default for AnsiColorParser {
-    fn default() -> Self {
-        Self
+    let mut ranges = vec![];
+    let mut offset = 0;
+    for line in job.text.split_inclusive('\n') {
+        if pattern.is_match(line) {
+            ranges.push(offset..offset + line.len());
+        }
+
+        offset += line.len();
     }
+
+    highlight_ranges(job, &ranges, background);
 }
 
-impl AnsiColorParser {
-    fn parse(
-        &self,
-        default_color: Color32,
-        colors: AnsiColors,
-        unparsed_text: &str,
-        text: &str,
-    ) -> LayoutJob {
-        let ansi_to_color32 = |color| match color {
-            Color::Black => colors.black.to_color32(),
-            Color::Red => colors.red.to_color32(),
-            Color::Green => colors.green.to_color32(),
-            Color::Yellow => colors.yellow.to_color32(),
-            Color::Blue => colors.blue.to_color32(),
-            Color::Magenta => colors.magenta.to_color32(),
-            Color::Cyan => colors.cyan.to_color32(),
-            Color::White => colors.white.to_color32(),
-            Color::BrightBlack => colors.bright_black.to_color32(),
-            Color::BrightRed => colors.bright_red.to_color32(),
-            Color::BrightGreen => colors.bright_green.to_color32(),
-            Color::BrightYellow => colors.bright_yellow.to_color32(),
-            Color::BrightBlue => colors.bright_blue.to_color32(),
-            Color::BrightMagenta => colors.bright_magenta.to_color32(),
-            Color::BrightCyan => colors.bright_cyan.to_color32(),
-            Color::BrightWhite => colors.bright_white.to_color32(),
-            Color::Rgb(r, g, b) => Color32::from_rgb(r, g, b),
-        };
+// how rustc prints the level of a diagnostic
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note => "note",
+        Level::Help => "help",
+        Level::FailureNote => "failure-note",
+        Level::InternalCompilerError => "error: internal compiler error",
+        Level::Other => "",
+    }
+}
 
-        use egui::text::{LayoutSection, TextFormat};
+// Give every line of a sanitizer report a highlighted background
+fn highlight_sanitizer_reports(job: &mut LayoutJob) {
+    if !job.text.contains("Sanitizer") {
+        return;
+    }
 
-        let parsed = ansi_parser::parse(unparsed_text);
+    let background = Color32::from_rgba_unmultiplied(255, 60, 60, 30);
+
+    let lines = job
+        .text
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some(start..*offset)
+        })
+        .collect::<Vec<_>>();
+
+    let ranges = sanitizer_reports(&job.text)
+        .into_iter()
+        .filter_map(|report| {
+            let start = lines.get(report.lines.start)?.start;
+            let end = lines.get(report.lines.end - 1)?.end;
+            Some(start..end)
+        })
+        .collect::<Vec<_>>();
+
+    highlight_ranges(job, &ranges, background);
+}
 
-        let mut job = LayoutJob {
-            text: text.into(),
-            ..Default::default()
-        };
+// Set the background of the byte ranges, splitting sections where needed. Ranges must be sorted
+fn highlight_ranges(job: &mut LayoutJob, ranges: &[Range<usize>], background: Color32) {
+    if ranges.is_empty() {
+        return;
+    }
 
-        for chunk in parsed.properties {
-            let text_color = chunk.fg.map(ansi_to_color32).unwrap_or(default_color);
-            let background_color = chunk
-                .bg
-                .map(ansi_to_color32)
-                .unwrap_or(Color32::TRANSPARENT);
+    let mut sections = Vec::with_capacity(job.sections.len());
+    for section in job.sections.drain(..) {
+        let mut start = section.byte_range.start;
+        let end = section.byte_range.end;
+
+        for range in ranges.iter().filter(|r| r.start < end) {
+            // before this section, or covered by the last range already
+            if range.end <= start {
+                continue;
+            }
+
+            if range.start > start {
+                let mut before = section.clone();
+                before.byte_range = start..range.start;
+                sections.push(before);
+                start = range.start;
+            }
+
+            let highlight_end = range.end.min(end);
+            let mut highlighted = section.clone();
+            highlighted.byte_range = start..highlight_end;
+            highlighted.format.background = background;
+            sections.push(highlighted);
+
+            start = highlight_end;
+        }
 
-            let italics = chunk.style.italic;
-            let underline = chunk.style.underline;
+        if start < end {
+            let mut after = section;
+            after.byte_range = start..end;
+            sections.push(after);
+        }
+    }
 
-            let underline = if underline {
-                Stroke::new(1.0, text_color)
-            } else {
-                Stroke::NONE
-            };
+    job.sections = sections;
+}
 
-            let strikethrough = if chunk.style.strikethrough {
-                Stroke::new(1.0, text_color)
-            } else {
-                Stroke::NONE
-            };
+struct AnsiColorParser;
 
-            job.sections.push(LayoutSection {
-                leading_space: 0.0,
-                byte_range: chunk.start..chunk.end,
-                format: TextFormat {
-                    font_id: FontId::monospace(12.0),
-                    color: text_color,
-                    italics,
-                    underline,
-                    background: background_color,
-                    strikethrough,
-                    ..Default::default()
-                },
-            });
-        }
+impl Default for AnsiColorParser {
+    fn default() -> Self {
+        Self
+    }
+}
 
-        job
+/// Queue a command from the output or a menu, unless it opens a view which is already open
+pub fn queue(commands: &mut Vec<Command>, command: Command) {
+    let is_open = match &command {
+        Command::MenuCommand(MenuCommand::HexView(view)) => view.is_open(commands),
+        Command::MenuCommand(MenuCommand::BinarySize(view)) => view.is_open(commands),
+        Command::MenuCommand(MenuCommand::CrateSources(id)) => commands.iter().any(|command| {
+            matches!(command, Command::MenuCommand(MenuCommand::CrateSources(open)) if open == id)
+        }),
+        Command::MenuCommand(MenuCommand::DependencyTree(id)) => commands.iter().any(|command| {
+            matches!(command, Command::MenuCommand(MenuCommand::DependencyTree(open)) if open == id)
+        }),
+        Command::MenuCommand(MenuCommand::Manifest(id)) => commands.iter().any(|command| {
+            matches!(command, Command::MenuCommand(MenuCommand::Manifest(open)) if open == id)
+        }),
+        Command::MenuCommand(MenuCommand::Explain(code)) => commands.iter().any(|command| {
+            matches!(command, Command::MenuCommand(MenuCommand::Explain(open)) if open == code)
+        }),
+        // there's one dialog, whichever tab it adds to
+        Command::MenuCommand(MenuCommand::AddDependency(_)) => commands
+            .iter()
+            .any(|command| matches!(command, Command::MenuCommand(MenuCommand::AddDependency(_)))),
+        _ => false,
+    };
+
+    if !is_open {
+        commands.push(command);
     }
 }
 
@@ -172,315 +271,924 @@ pub struct Terminal;
 
 impl Terminal {
     pub fn show(ctx: &egui::Context, config: &mut Config) {
+        CommandPalette::register(ctx, "terminal", Self::palette_commands);
+
         let id = Id::new("terminal");
+        let position = config.terminal_settings.position;
+
+        if let Some((size, opening)) = config.terminal.animation {
+            let t = ctx.animate_bool_with_time(
+                id.with("animation"),
+                opening,
+                config.terminal_settings.animation_time,
+            );
+
+            let rect = position.panel_rect(ctx.available_rect(), size * t);
+            ctx.data().insert_persisted(id, PanelState { rect });
+
+            if opening && t >= 1.0 {
+                config.terminal.animation = None;
+            } else if !opening && t <= 0.0 {
+                config.terminal.animation = None;
+                config.terminal.open = false;
+                return;
+            }
+        }
 
         if config.terminal.opened_from_close {
             // we need to reset the panel state position to be where the mouse pointer is to make it seamless
             // on open, so it doesn't flash when opening by opening big then resetting to where the mouse is
             let coords = ctx.pointer_latest_pos().unwrap_or_default();
             let window_rect = ctx.available_rect();
-            let rect = Rect::from_two_pos(
-                pos2(0.0, coords.y),
-                pos2(window_rect.right(), window_rect.bottom()),
-            );
+            let rect =
+                position.panel_rect(window_rect, position.distance_to_edge(window_rect, coords));
 
             ctx.data().insert_persisted(id, PanelState { rect });
         }
 
-        egui::TopBottomPanel::bottom(id)
-            .resizable(true)
-            .default_height(0.0)
-            .min_height(0.0)
-            .max_height(ctx.available_rect().height() - (TITLEBAR_HEIGHT as f32 / 2.0))
-            .show_separator_line(false)
-            .show(ctx, |ui| {
-                //
-                // Panel handling code
-                //
+        let add_contents = |ui: &mut Ui| {
+            //
+            // Panel handling code
+            //
+
+            let settings = &config.terminal_settings;
+
+            // while still dragging after opening from the handle, stay just below the opening threshold
+            // so the terminal doesn't immediately flip back closed
+            let close_threshold = if config.terminal.opened_from_close_dragging {
+                settings.open_threshold - 1.0
+            } else {
+                settings.close_threshold
+            };
+
+            let pointer_pos = ctx.pointer_latest_pos().unwrap_or_default();
+
+            let resize_id = id.with("__resize");
+
+            // when mouse is outside of window, as long as we were dragging, pointer_pos is still Some()
+            // we can utilize this to allow resizing AS LONG AS mouse isn't beyond the docked edge in screen coords
+            if position.distance_to_edge(ctx.available_rect(), pointer_pos) <= close_threshold
+                && ctx.memory().is_being_dragged(resize_id)
+            {
+                config.terminal.open = false;
+                config.terminal.animation = None;
+                config.terminal.closed_from_open = true;
+            }
+
+            if config.terminal.opened_from_close {
+                let mut memory = ui.memory();
+                memory.set_dragged_id(resize_id);
+
+                config.terminal.opened_from_close = false;
+            }
+
+            if config.terminal.opened_from_close_dragging
+                && !ui.memory().is_being_dragged(resize_id)
+            {
+                config.terminal.opened_from_close_dragging = false;
+            }
+
+            //
+            // Scrollbar and panel contents
+            //
+
+            let mut frame_rect = ui.max_rect();
+            frame_rect.set_left(frame_rect.left() + 2.0);
+            frame_rect.set_right(frame_rect.right() - 2.0);
+            frame_rect.set_bottom(frame_rect.bottom() - 10.0);
+            frame_rect.set_top(frame_rect.top() + 10.0);
+
+            let active_tab = config.terminal.active_tab.unwrap();
+
+            // zoomed apart from the editor, its keys work while the pointer is over the output
+            let zoom = &mut config.terminal_settings.zoom;
+            zoom_input(
+                ui,
+                frame_rect,
+                ui.rect_contains_pointer(frame_rect),
+                zoom,
+                1.0,
+            );
+
+            let commands = Self::show_output(
+                ui,
+                &mut config.terminal,
+                &config.editor.settings.zoomed(config.terminal_settings.zoom),
+                config.theme.get_ansi_colors(),
+                config.terminal_settings.blink,
+                active_tab,
+                id,
+            );
 
-                let mut close_rect = ctx.available_rect();
+            for command in commands {
+                queue(&mut config.dock.commands, command);
+            }
+        };
 
-                let close_threshold = if config.terminal.opened_from_close_dragging {
-                    16.0
+        let available_rect = ctx.available_rect();
+
+        let panel_rect = match position {
+            TerminalPosition::Bottom => {
+                egui::TopBottomPanel::bottom(id)
+                    .resizable(true)
+                    .default_height(0.0)
+                    .min_height(0.0)
+                    .max_height(available_rect.height() - (TITLEBAR_HEIGHT as f32 / 2.0))
+                    .show_separator_line(false)
+                    .show(ctx, add_contents)
+                    .response
+                    .rect
+            }
+
+            TerminalPosition::Left | TerminalPosition::Right => {
+                let panel = if position == TerminalPosition::Left {
+                    egui::SidePanel::left(id)
                 } else {
-                    20.0
+                    egui::SidePanel::right(id)
                 };
 
-                close_rect.set_top(close_rect.bottom() - close_threshold);
+                // always leave some room for the editor
+                panel
+                    .resizable(true)
+                    .default_width(0.0)
+                    .min_width(0.0)
+                    .max_width(available_rect.width() - MIN_EDITOR_WIDTH)
+                    .show_separator_line(false)
+                    .show(ctx, add_contents)
+                    .response
+                    .rect
+            }
+        };
+
+        if !config.terminal.open || config.terminal.animation.is_some() {
+            return;
+        }
 
-                let pointer_pos = ctx.pointer_latest_pos().unwrap_or_default();
+        if let Some(active_tab) = config.terminal.active_tab {
+            config
+                .terminal
+                .sizes
+                .insert(active_tab, position.size_of(panel_rect));
+        }
 
-                let window_close_bottom = ctx.available_rect().bottom() - close_threshold;
+        // double clicking the resize edge closes the terminal
+        let double_clicked = ctx
+            .input()
+            .pointer
+            .button_double_clicked(PointerButton::Primary);
 
-                let resize_id = id.with("__resize");
+        if double_clicked {
+            if let Some(pos) = ctx.pointer_interact_pos() {
+                let edge_distance = (position.distance_to_edge(panel_rect, pos)
+                    - position.size_of(panel_rect))
+                .abs();
 
-                // when mouse is outside of window, as long as we were dragging, pointer_pos is still Some()
-                // we can utilize this to allow resizing AS LONG AS mouse isn't below the window in screen coords
-                if (close_rect.contains(pointer_pos) || pointer_pos.y >= window_close_bottom)
-                    && ctx.memory().is_being_dragged(resize_id)
-                {
-                    config.terminal.open = false;
-                    config.terminal.closed_from_open = true;
+                if edge_distance <= ctx.style().interaction.resize_grab_radius_side {
+                    Self::close(ctx, &mut config.terminal, &config.terminal_settings);
                 }
+            }
+        }
+    }
 
-                if config.terminal.opened_from_close {
-                    let mut memory = ui.memory();
-                    memory.set_dragged_id(resize_id);
+    /// Draw the stderr and stdout of a tab's last run. `id` keeps apart the places showing output,
+    /// e.g. the terminal and a tab's split view. Returns the commands clicked buttons queued
+    pub fn show_output(
+        ui: &mut Ui,
+        terminal: &mut config::Terminal,
+        editor_config: &EditorSettings,
+        ansi_colors: AnsiColors,
+        blink: bool,
+        tab: Id,
+        id: Id,
+    ) -> Vec<Command> {
+        let ctx = ui.ctx().clone();
+
+        let scroll_id = id.with(tab);
+        let offset = terminal
+            .scroll_offset
+            .get(&scroll_id)
+            .copied()
+            .unwrap_or_default();
+
+        Self::collect_output(terminal, tab);
+        let output = &terminal.output[&tab];
+
+        let (stdout_properties, plain_stdout) = (output.stdout.properties(), output.stdout.text());
+        let (stderr_properties, plain_stderr) = (output.stderr.properties(), output.stderr.text());
+
+        let mut read_only_term_stdout = ReadOnlyString::new(plain_stdout);
+        let mut read_only_term_stderr = ReadOnlyString::new(plain_stderr);
+
+        let loom = loom_summary(plain_stdout);
+
+        let reports = if plain_stderr.contains("Sanitizer") {
+            sanitizer_reports(plain_stderr)
+        } else {
+            vec![]
+        };
 
-                    config.terminal.opened_from_close = false;
-                }
+        let miri = if ctx
+            .data()
+            .get_temp::<bool>(Id::new(MIRI_RUN).with(tab))
+            .unwrap_or_default()
+        {
+            miri_diagnostics(plain_stderr)
+        } else {
+            vec![]
+        };
+        let mut jump_to_line = None;
+
+        let has_output = !(plain_stdout.is_empty() && plain_stderr.is_empty());
+        let mut export = false;
+
+        // the errors rustc has a longer explanation of. Output is only ever added to, they're looked for
+        // again once there's more
+        let codes_id = Id::new(ERROR_CODES).with(tab);
+        let cached = ctx
+            .data()
+            .get_temp::<(usize, Arc<Vec<String>>)>(codes_id)
+            .filter(|(len, _)| *len == plain_stderr.len());
+
+        let error_codes = cached.map_or_else(
+            || {
+                let codes = error_codes(plain_stderr)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                let codes = Arc::new(codes);
+
+                ctx.data()
+                    .insert_temp(codes_id, (plain_stderr.len(), codes.clone()));
+
+                codes
+            },
+            |(_, codes)| codes,
+        );
+        let mut explain = None;
+
+        // clippy's stdout is json, it's shown as a list of its lints instead
+        let clippy_lints = ctx
+            .data()
+            .get_temp::<bool>(Id::new(LINT_RUN).with(tab))
+            .unwrap_or_default()
+            .then(|| lints(plain_stdout));
+
+        let fuzz_function = ctx
+            .data()
+            .get_temp::<FuzzFn>(Id::new(FUZZ_FUNCTION).with(tab));
+        let fuzzing = TabEvents::is_running(&ctx, terminal, tab);
+        let mut fuzz_command = None;
+
+        let highlight_pattern = terminal
+            .assertions
+            .get(&tab)
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut layout_job = parse_ansi(
+                ui.ctx(),
+                ansi_colors,
+                stdout_properties,
+                text,
+                highlight_pattern,
+                blink,
+            );
+            editor_config.apply(&mut layout_job, wrap_width);
 
-                if config.terminal.opened_from_close_dragging
-                    && !ui.memory().is_being_dragged(resize_id)
-                {
-                    config.terminal.opened_from_close_dragging = false;
+            let fonts = ui.fonts();
+            align_to_cells(&fonts, &mut layout_job);
+            fonts.layout_job(layout_job)
+        };
+        let mut layouter2 = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut layout_job = parse_ansi(
+                ui.ctx(),
+                ansi_colors,
+                stderr_properties,
+                text,
+                highlight_pattern,
+                blink,
+            );
+            editor_config.apply(&mut layout_job, wrap_width);
+
+            let fonts = ui.fonts();
+            align_to_cells(&fonts, &mut layout_job);
+            fonts.layout_job(layout_job)
+        };
+
+        let text_widget_stdout = egui::TextEdit::multiline(&mut read_only_term_stdout)
+            .font(editor_config.font_id()) // for cursor height
+            // remove the frame and draw our own
+            .frame(false)
+            .desired_width(f32::INFINITY)
+            .layouter(&mut layouter)
+            .id(id.with("term_output_stdout"))
+            .interactive(true);
+
+        let text_widget_stderr = egui::TextEdit::multiline(&mut read_only_term_stderr)
+            .font(editor_config.font_id()) // for cursor height
+            // remove the frame and draw our own
+            .frame(false)
+            .desired_width(f32::INFINITY)
+            .layouter(&mut layouter2)
+            .id(id.with("term_output_stderr"))
+            .interactive(true);
+
+        let binary_output = ctx
+            .data()
+            .get_temp::<Arc<Vec<u8>>>(Id::new(BINARY_OUTPUT).with(tab));
+        let mut view_hex = None;
+
+        let size_view = ctx
+            .data()
+            .get_temp::<SizeView>(Id::new(SIZE_VIEW).with(tab));
+        let mut view_size = false;
+
+        let scrollarea = egui::ScrollArea::new([!editor_config.word_wrap, true])
+            .max_height(f32::INFINITY)
+            .auto_shrink([false, false])
+            .scroll_offset(offset)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                if !miri.is_empty() {
+                    jump_to_line = Self::show_miri_diagnostics(ui, &miri);
                 }
 
-                //
-                // Scrollbar and panel contents
-                //
+                ui.horizontal(|ui| {
+                    ui.vertical(|ui| {
+                        let mut jump = None;
 
-                let mut frame_rect = ui.max_rect();
-                frame_rect.set_left(frame_rect.left() + 2.0);
-                frame_rect.set_right(frame_rect.right() - 2.0);
-                frame_rect.set_bottom(frame_rect.bottom() - 10.0);
-                frame_rect.set_top(frame_rect.top() + 10.0);
+                        ui.horizontal(|ui| {
+                            ui.heading("Standard Error");
+
+                            if has_output {
+                                export = ui
+                                    .small_button("Export...")
+                                    .on_hover_text(
+                                        "Save the output with its colors, as HTML or PNG",
+                                    )
+                                    .clicked();
+                            }
 
-                let active_tab = config.terminal.active_tab.unwrap();
-                let offset = *config
-                    .terminal
-                    .scroll_offset
-                    .get_mut(&active_tab)
-                    .unwrap_or(&mut Vec2::default());
-
-                //
-                // Parsing and caching
-                //
-                // (unstripped, strippedtext)
-                static CACHE_STDOUT: OnceCell<Mutex<HashMap<Id, (String, String)>>> =
-                    OnceCell::new();
-                static CACHE_STDERR: OnceCell<Mutex<HashMap<Id, (String, String)>>> =
-                    OnceCell::new();
-                let mut cache_stdout = CACHE_STDOUT
-                    .get_or_init(|| Mutex::new(HashMap::new()))
-                    .lock();
-                let mut cache_stderr = CACHE_STDERR
-                    .get_or_init(|| Mutex::new(HashMap::new()))
-                    .lock();
-
-                let terminal_output = config.terminal.content.entry(active_tab).or_default();
-                let (
-                    (terminal_output_stdout, terminal_output_stderr),
-                    (plain_stdout, plain_stderr),
-                ) = {
-                    if config.terminal.started_run {
-                        // clear out the cached entries to restart the term output fresh
-                        cache_stdout.remove(&active_tab);
-                        cache_stderr.remove(&active_tab);
-
-                        config.terminal.dynamic_index = (0, 0);
-                        config.terminal.started_run = false;
-                    }
-
-                    let (stdout_unstripped, stdout_stripped) = cache_stdout
-                        .entry(active_tab)
-                        .or_insert((String::new(), String::new()));
-                    let (stderr_unstripped, stderr_stripped) = cache_stderr
-                        .entry(active_tab)
-                        .or_insert((String::new(), String::new()));
-
-                    if let Some((stdout, stderr)) = terminal_output.as_mut() {
-                        for msg in stdout.pop_iter() {
-                            // right now, we don't really truly support overwrite mode, sorry
-                            if msg.ends_with('\r') {
-                                continue;
+                            jump = Self::show_report_nav(
+                                ui,
+                                &reports,
+                                id.with("sanitizer_report").with(tab),
+                            );
+
+                            if !error_codes.is_empty() {
+                                ui.menu_button("Explain", |ui| {
+                                    for code in error_codes.iter() {
+                                        if ui.button(code).clicked() {
+                                            explain = Some(code.clone());
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                            }
+
+                            if let Some(function) = &fuzz_function {
+                                fuzz_command = Self::show_fuzz_status(
+                                    ui,
+                                    plain_stderr,
+                                    function,
+                                    tab,
+                                    fuzzing,
+                                );
                             }
+                        });
 
-                            stdout_unstripped.push_str(&msg);
+                        let output = text_widget_stderr.show(ui);
 
-                            let stripped =
-                                String::from_utf8(strip_ansi_escapes::strip(msg).unwrap()).unwrap();
+                        // the explanation of an error code the pointer is on, e.g. E0308
+                        if let Some(pos) = output.response.hover_pos() {
+                            let galley = &output.galley;
+                            let index = galley
+                                .cursor_from_pos(pos - output.text_draw_pos)
+                                .ccursor
+                                .index;
 
-                            stdout_stripped.push_str(&stripped);
+                            CompilerHints::explain_hovered(ui.ctx(), id, &galley.job.text, index);
                         }
 
-                        for mut msg in stderr.pop_iter() {
-                            // get indexes of last valid non-dynamic output
-                            let previous_newline_unstripped = &mut config.terminal.dynamic_index.0;
-                            let previous_newline_stripped = &mut config.terminal.dynamic_index.1;
+                        if let Some(line) = jump {
+                            let galley = &output.galley;
 
-                            if msg.ends_with('\r') {
-                                //
-                                // First, we need to strip out all previous lines
-                                //
-                                stderr_unstripped.truncate(*previous_newline_unstripped);
-                                stderr_stripped.truncate(*previous_newline_stripped);
+                            let index = galley
+                                .job
+                                .text
+                                .split_inclusive('\n')
+                                .take(line)
+                                .map(|line| line.chars().count())
+                                .sum();
 
-                                //
-                                // Now we can add the the strings to the end
-                                //
+                            let cursor = galley.from_ccursor(CCursor::new(index));
+                            let rect = galley
+                                .pos_from_cursor(&cursor)
+                                .translate(output.response.rect.min.to_vec2());
 
-                                // insert as a new line
-                                // pop off \r
-                                msg.pop();
+                            ui.scroll_to_rect(rect, Some(Align::TOP));
+                        }
+                    });
+                });
 
-                                let trim_len = msg.trim_end().len();
-                                msg.truncate(trim_len);
+                ui.horizontal(|ui| {
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.heading(if clippy_lints.is_some() {
+                                "Lints"
+                            } else {
+                                "Standard Output"
+                            });
 
-                                // ignore empty messages. The next line inserted will be a real one anyways
-                                if msg.is_empty() {
-                                    continue;
-                                }
+                            if let Some(summary) = &loom {
+                                Self::show_loom_summary(ui, summary);
+                            }
 
-                                let mut stripped =
-                                    String::from_utf8(strip_ansi_escapes::strip(&msg).unwrap())
-                                        .unwrap();
+                            if let Some(bytes) = &binary_output {
+                                let view = ui
+                                    .small_button("View as hex")
+                                    .on_hover_text("The output isn't valid UTF-8");
 
-                                msg.push('\n');
-                                stripped.push('\n');
+                                if view.clicked() {
+                                    view_hex = Some(bytes.clone());
+                                }
+                            }
 
-                                stderr_unstripped.push_str(&msg);
-                                stderr_stripped.push_str(&stripped);
+                            if size_view.is_some() {
+                                view_size = ui
+                                    .small_button("Binary size")
+                                    .on_hover_text("Show the last binary size measurement")
+                                    .clicked();
+                            }
+                        });
 
-                                continue;
+                        match &clippy_lints {
+                            Some(lints) => {
+                                let line = LintList::show(ui, lints, id.with("lints").with(tab));
+                                jump_to_line = jump_to_line.or(line);
+                            }
+                            None => {
+                                ui.add(text_widget_stdout);
                             }
+                        }
+                    });
+                });
+            });
 
-                            stderr_unstripped.push_str(&msg);
+        terminal
+            .scroll_offset
+            .insert(scroll_id, scrollarea.state.offset);
 
-                            let stripped =
-                                String::from_utf8(strip_ansi_escapes::strip(&msg).unwrap())
-                                    .unwrap();
+        let mut commands = vec![];
 
-                            stderr_stripped.push_str(&stripped);
+        if let Some(command) = fuzz_command {
+            commands.push(Command::TabCommand(command));
+        }
 
-                            *previous_newline_unstripped += msg.len();
-                            *previous_newline_stripped += stripped.len();
-                        }
-                    }
+        if let Some(line) = jump_to_line {
+            commands.push(Command::TabCommand(TabCommand::JumpToLine(tab, line)));
+        }
 
-                    (
-                        (&**stdout_unstripped, &**stderr_unstripped),
-                        (&**stdout_stripped, &**stderr_stripped),
-                    )
-                };
+        if export {
+            commands.push(Command::MenuCommand(MenuCommand::ExportOutput(tab)));
+        }
 
-                let mut read_only_term_stdout = ReadOnlyString::new(plain_stdout);
-                let mut read_only_term_stderr = ReadOnlyString::new(plain_stderr);
+        if let Some(code) = explain {
+            commands.push(Command::MenuCommand(MenuCommand::Explain(code)));
+        }
 
-                let ansi_colors = config.theme.get_ansi_colors();
+        if let Some(bytes) = view_hex {
+            let view = HexView::new("Standard Output".to_string(), bytes);
+            commands.push(Command::MenuCommand(MenuCommand::HexView(view)));
+        }
 
-                let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
-                    let mut layout_job =
-                        parse_ansi(ui.ctx(), ansi_colors, terminal_output_stdout, text);
-                    layout_job.wrap.max_width = wrap_width;
-                    ui.fonts().layout_job(layout_job)
-                };
-                let mut layouter2 = |ui: &egui::Ui, text: &str, wrap_width: f32| {
-                    let mut layout_job =
-                        parse_ansi(ui.ctx(), ansi_colors, terminal_output_stderr, text);
-                    layout_job.wrap.max_width = wrap_width;
-                    ui.fonts().layout_job(layout_job)
-                };
+        if let Some(view) = size_view.filter(|_| view_size) {
+            commands.push(Command::MenuCommand(MenuCommand::BinarySize(view)));
+        }
 
-                let text_widget_stdout = egui::TextEdit::multiline(&mut read_only_term_stdout)
-                    .font(egui::TextStyle::Monospace) // for cursor height
-                    // remove the frame and draw our own
-                    .frame(false)
-                    .desired_width(f32::INFINITY)
-                    .layouter(&mut layouter)
-                    .id(id.with("term_output_stdout"))
-                    .interactive(true);
-
-                let text_widget_stderr = egui::TextEdit::multiline(&mut read_only_term_stderr)
-                    .font(egui::TextStyle::Monospace) // for cursor height
-                    // remove the frame and draw our own
-                    .frame(false)
-                    .desired_width(f32::INFINITY)
-                    .layouter(&mut layouter2)
-                    .id(id.with("term_output_stderr"))
-                    .interactive(true);
-
-                let scrollarea = egui::ScrollArea::vertical()
-                    .max_height(f32::INFINITY)
-                    .auto_shrink([false, false])
-                    .scroll_offset(offset)
-                    .stick_to_bottom(true)
-                    .show(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.vertical(|ui| {
-                                ui.heading("Standard Error");
-                                ui.add(text_widget_stderr);
-                            });
-                        });
+        commands
+    }
 
-                        ui.horizontal(|ui| {
-                            ui.vertical(|ui| {
-                                ui.heading("Standard Output");
-                                ui.add(text_widget_stdout);
-                            });
-                        });
+    /// Take the output every tab's run printed since the last frame, shown or not, so no run waits
+    /// on its tab to be looked at
+    pub fn collect_all_output(terminal: &mut config::Terminal) {
+        let tabs = terminal.content.keys().copied().collect::<Vec<_>>();
+
+        for tab in tabs {
+            Self::collect_output(terminal, tab);
+        }
+    }
+
+    // move what the tab's process printed since the last frame over from the ring buffers
+    fn collect_output(terminal: &mut config::Terminal, tab: Id) {
+        let output = terminal.output.entry(tab).or_default();
+
+        let Some(Some((stdout, stderr))) = terminal.content.get_mut(&tab) else {
+            return;
+        };
+
+        for msg in stdout.pop_iter() {
+            output.stdout.write(&msg);
+        }
+
+        for msg in stderr.pop_iter() {
+            output.stderr.write(&msg);
+        }
+    }
+
+    /// Commands on the terminal for the command palette
+    pub fn palette_commands(_config: &mut Config) -> Vec<PaletteCommand> {
+        vec![PaletteCommand::run("View: Toggle terminal", Self::toggle)]
+    }
+
+    /// Open or close the terminal without the drag gesture. Opens at a third of the window size
+    pub fn toggle(ctx: &egui::Context, config: &mut Config) {
+        let closing = matches!(config.terminal.animation, Some((_, false)));
+
+        if config.terminal.open && !closing {
+            Self::close(ctx, &mut config.terminal, &config.terminal_settings);
+        } else {
+            let size = config
+                .terminal_settings
+                .position
+                .size_of(ctx.input().screen_rect())
+                / 3.0;
+            Self::open(ctx, &mut config.terminal, &config.terminal_settings, size);
+        }
+
+        config.terminal.auto_opened = None;
+    }
+
+    /// Open the terminal at a specific size
+    pub fn open(
+        ctx: &egui::Context,
+        terminal: &mut config::Terminal,
+        settings: &TerminalSettings,
+        size: f32,
+    ) {
+        let id = Id::new("terminal");
+
+        let start_size = if settings.animate {
+            // the animation must start out closed, whatever state it was last left in
+            ctx.animate_bool_with_time(id.with("animation"), false, settings.animation_time);
+            terminal.animation = Some((size, true));
+            0.0
+        } else {
+            size
+        };
+
+        let rect = settings
+            .position
+            .panel_rect(ctx.input().screen_rect(), start_size);
+        ctx.data().insert_persisted(id, PanelState { rect });
+
+        terminal.open = true;
+    }
+
+    /// Close the terminal, animating it down from its current size
+    pub fn close(
+        ctx: &egui::Context,
+        terminal: &mut config::Terminal,
+        settings: &TerminalSettings,
+    ) {
+        if !settings.animate || !terminal.open {
+            terminal.open = false;
+            return;
+        }
+
+        let id = Id::new("terminal");
+
+        let size = ctx
+            .data()
+            .get_persisted::<PanelState>(id)
+            .map(|state| settings.position.size_of(state.rect))
+            .unwrap_or_default();
+
+        // the animation must start out open, whatever state it was last left in
+        ctx.animate_bool_with_time(id.with("animation"), true, settings.animation_time);
+        terminal.animation = Some((size, false));
+    }
+
+    /// Close the terminal again if it was opened by a run which has since finished without any output
+    pub fn auto_close(ctx: &egui::Context, config: &mut Config) {
+        let quiet_run_id = Id::new(QUIET_RUN);
+
+        let Some(tab) = ctx.data().get_temp::<Id>(quiet_run_id) else {
+            return;
+        };
+
+        ctx.data().remove::<Id>(quiet_run_id);
+
+        if config.terminal_settings.auto_close
+            && config.terminal.open
+            && config.terminal.auto_opened == Some(tab)
+        {
+            Self::close(ctx, &mut config.terminal, &config.terminal_settings);
+        }
+
+        config.terminal.auto_opened = None;
+    }
+
+    fn show_loom_summary(ui: &mut Ui, summary: &LoomSummary) {
+        ui.separator();
+
+        match (summary.passed, summary.iterations) {
+            (None, _) => {
+                ui.label("Loom is exploring interleavings...");
+            }
+            (Some(true), Some(iterations)) => {
+                ui.colored_label(
+                    Color32::GREEN,
+                    format!("✔ Loom explored {iterations} interleavings"),
+                );
+            }
+            (Some(true), None) => {
+                ui.colored_label(Color32::GREEN, "✔ Loom found no failing interleaving");
+            }
+            (Some(false), _) => {
+                ui.colored_label(Color32::RED, "✖ Loom found a failing interleaving");
+            }
+        }
+    }
+
+    // What Miri reported, each location a button jumping to its line if it's in the scratch.
+    // Returns the clicked line, counted from 0
+    fn show_miri_diagnostics(ui: &mut Ui, diagnostics: &[Diagnostic]) -> Option<usize> {
+        let mut jump = None;
+
+        ui.heading("Miri");
+
+        for diagnostic in diagnostics {
+            let color = match diagnostic.level {
+                Level::Error | Level::InternalCompilerError => ui.visuals().error_fg_color,
+                Level::Warning => ui.visuals().warn_fg_color,
+                _ => ui.visuals().text_color(),
+            };
+
+            let level = level_name(diagnostic.level);
+            let message = match &diagnostic.code {
+                Some(code) => format!("{level}[{code}]: {}", diagnostic.message),
+                None => format!("{level}: {}", diagnostic.message),
+            };
+
+            ui.colored_label(color, message);
+
+            ui.indent(ui.id().with(&diagnostic.message), |ui| {
+                for span in &diagnostic.spans {
+                    ui.horizontal(|ui| {
+                        let location = format!(
+                            "{}:{}:{}",
+                            span.file_name, span.line_start, span.column_start
+                        );
+
+                        if span.file_name == SCRATCH_FILE {
+                            let button = ui
+                                .small_button(location)
+                                .on_hover_text("Jump to the line in the editor");
+
+                            if button.clicked() {
+                                jump = Some(span.line_start.saturating_sub(1));
+                            }
+                        } else {
+                            ui.weak(location);
+                        }
+
+                        if let Some(label) = &span.label {
+                            ui.label(label);
+                        }
                     });
+                }
 
-                config
-                    .terminal
-                    .scroll_offset
-                    .insert(active_tab, scrollarea.state.offset);
+                for child in &diagnostic.children {
+                    ui.weak(format!("{}: {}", level_name(child.level), child.message));
+                }
             });
+        }
+
+        ui.separator();
+
+        jump
+    }
+
+    // Fuzzing stats with buttons to stop the fuzzer and replay the input it crashed on.
+    // Returns the command of the clicked button
+    fn show_fuzz_status(
+        ui: &mut Ui,
+        stderr: &str,
+        function: &FuzzFn,
+        tab: Id,
+        running: bool,
+    ) -> Option<TabCommand> {
+        let mut command = None;
+
+        ui.separator();
+
+        match fuzz_stats(stderr) {
+            Some(FuzzStats {
+                runs,
+                coverage,
+                corpus,
+                execs_per_sec,
+                rss,
+            }) => {
+                let mut stats = format!("{runs} runs");
+
+                if let Some(coverage) = coverage {
+                    stats.push_str(&format!(", cov {coverage}"));
+                }
+                if let Some(corpus) = corpus {
+                    stats.push_str(&format!(", corpus {corpus}"));
+                }
+                if let Some(execs_per_sec) = execs_per_sec {
+                    stats.push_str(&format!(", {execs_per_sec} exec/s"));
+                }
+                if let Some(rss) = rss {
+                    stats.push_str(&format!(", rss {rss}"));
+                }
+
+                ui.label(stats);
+            }
+
+            None if running => {
+                ui.label(format!("Preparing to fuzz {}...", function.name));
+            }
+
+            None => (),
+        }
+
+        if running && ui.small_button("Stop").clicked() {
+            command = Some(TabCommand::Stop(tab));
+        }
+
+        if let Some(crash) = fuzz_crash(stderr) {
+            ui.colored_label(Color32::RED, "✖ Crashed");
+
+            let replay = ui
+                .small_button("Replay crash")
+                .on_hover_text(crash.to_string_lossy());
+
+            if replay.clicked() {
+                command = Some(TabCommand::FuzzReplay(tab, function.clone(), crash));
+            }
+        }
+
+        command
+    }
+
+    // Step through the sanitizer reports of the output. Returns the line to scroll to
+    fn show_report_nav(ui: &mut Ui, reports: &[SanitizerReport], id: Id) -> Option<usize> {
+        if reports.is_empty() {
+            return None;
+        }
+
+        let mut current = ui
+            .ctx()
+            .data()
+            .get_temp::<usize>(id)
+            .unwrap_or_default()
+            .min(reports.len() - 1);
+        let mut jump = false;
+
+        ui.separator();
+
+        if ui.small_button("⏶").clicked() {
+            current = current.checked_sub(1).unwrap_or(reports.len() - 1);
+            jump = true;
+        }
+
+        if ui.small_button("⏷").clicked() {
+            current = (current + 1) % reports.len();
+            jump = true;
+        }
+
+        let report = &reports[current];
+
+        ui.label(format!(
+            "{}/{} {}: {}",
+            current + 1,
+            reports.len(),
+            report.sanitizer,
+            report.kind
+        ));
+
+        if let Some(location) = &report.location {
+            ui.weak(location);
+        }
+
+        ui.ctx().data().insert_temp(id, current);
+
+        jump.then_some(report.lines.start)
     }
 
     pub fn show_closed_handle(ctx: &egui::Context, config: &mut Config) {
+        // its commands are there while it's closed too, e.g. to open it again
+        CommandPalette::register(ctx, "terminal", Self::palette_commands);
+
         let id = Id::new("terminal-closed");
+        let position = config.terminal_settings.position;
 
-        egui::TopBottomPanel::bottom(id)
-            .resizable(false)
-            .default_height(13.0)
-            .show_separator_line(false)
-            .show(ctx, |ui| {
-                ui.horizontal_centered(|ui| {
-                    ui.vertical_centered(|ui| {
-                        let center_id = id.with("center_line");
-
-                        let sense = Sense::click_and_drag();
-                        let hover_sense = Sense::hover();
-
-                        let (alloc_id, center_line) = ui.allocate_space(vec2(75.0, 2.0));
-                        let response = ui.interact(center_line, alloc_id, sense);
-                        let h_response =
-                            ui.interact(center_line, center_id.with("hover"), hover_sense);
-
-                        if config.terminal.closed_from_open {
-                            ui.memory().set_dragged_id(alloc_id);
-                            config.terminal.closed_from_open = false;
-                        }
+        let add_contents = |ui: &mut Ui| {
+            let center_id = id.with("center_line");
 
-                        let is_dragging = response.dragged();
+            let sense = Sense::click_and_drag();
+            let hover_sense = Sense::hover();
 
-                        if is_dragging || h_response.hovered() {
-                            ui.output().cursor_icon = CursorIcon::ResizeVertical;
-                        }
+            let handle_size = if position.is_side() {
+                vec2(2.0, 75.0)
+            } else {
+                vec2(75.0, 2.0)
+            };
 
-                        // we need to subtract the closing threshold from the bottom
-                        let window_bottom = ctx.available_rect().bottom() - 17.0;
+            let center_line = Rect::from_center_size(ui.max_rect().center(), handle_size);
 
-                        if response.drag_delta().y <= -0.5
-                            && ctx.pointer_latest_pos().unwrap_or_default().y <= window_bottom
-                        {
-                            config.terminal.open = true;
-                            config.terminal.opened_from_close = true;
-                            config.terminal.opened_from_close_dragging = true;
-                        }
+            // the whole strip around the line can be grabbed, so it's easy to hit with a finger or pen
+            let mut grab_rect = ui.max_rect();
+            if position.is_side() {
+                grab_rect.set_top(center_line.top());
+                grab_rect.set_bottom(center_line.bottom());
+            } else {
+                grab_rect.set_left(center_line.left());
+                grab_rect.set_right(center_line.right());
+            }
 
-                        let stroke = if is_dragging {
-                            ui.style().visuals.widgets.active.bg_stroke
-                        } else if h_response.hovered() {
-                            ui.style().visuals.widgets.hovered.bg_stroke
-                        } else {
-                            ui.style().visuals.widgets.noninteractive.bg_stroke
-                        };
+            let response = ui.interact(grab_rect, center_id, sense);
+            let h_response = ui.interact(grab_rect, center_id.with("hover"), hover_sense);
 
-                        ui.painter().rect_filled(center_line, 2.0, stroke.color);
-                    });
-                });
-            });
+            if config.terminal.closed_from_open {
+                ui.memory().set_dragged_id(center_id);
+                config.terminal.closed_from_open = false;
+            }
+
+            let is_dragging = response.dragged();
+
+            if is_dragging || h_response.hovered() {
+                ui.output().cursor_icon = if position.is_side() {
+                    CursorIcon::ResizeHorizontal
+                } else {
+                    CursorIcon::ResizeVertical
+                };
+            }
+
+            // the pointer must have left the opening threshold at the docked edge
+            let pointer_pos = ctx.pointer_latest_pos().unwrap_or_default();
+            let past_threshold = position.distance_to_edge(ctx.available_rect(), pointer_pos)
+                >= config.terminal_settings.open_threshold;
+
+            // reopen at the last size
+            if response.double_clicked() {
+                let size = config
+                    .terminal
+                    .active_tab
+                    .and_then(|id| config.terminal.sizes.get(&id).copied())
+                    .unwrap_or(config.terminal_settings.open_size);
+
+                Self::open(ctx, &mut config.terminal, &config.terminal_settings, size);
+                config.terminal.auto_opened = None;
+            }
+
+            if response.drag_delta().dot(position.inward()) >= 0.5 && past_threshold {
+                config.terminal.open = true;
+                config.terminal.auto_opened = None;
+                config.terminal.opened_from_close = true;
+                config.terminal.opened_from_close_dragging = true;
+            }
+
+            let stroke = if is_dragging {
+                ui.style().visuals.widgets.active.bg_stroke
+            } else if h_response.hovered() {
+                ui.style().visuals.widgets.hovered.bg_stroke
+            } else {
+                ui.style().visuals.widgets.noninteractive.bg_stroke
+            };
+
+            ui.painter().rect_filled(center_line, 2.0, stroke.color);
+        };
+
+        match position {
+            TerminalPosition::Bottom => {
+                egui::TopBottomPanel::bottom(id)
+                    .resizable(false)
+                    .exact_height(13.0)
+                    .show_separator_line(false)
+                    .show(ctx, add_contents);
+            }
+
+            TerminalPosition::Left => {
+                egui::SidePanel::left(id)
+                    .resizable(false)
+                    .exact_width(13.0)
+                    .show_separator_line(false)
+                    .show(ctx, add_contents);
+            }
+
+            TerminalPosition::Right => {
+                egui::SidePanel::right(id)
+                    .resizable(false)
+                    .exact_width(13.0)
+                    .show_separator_line(false)
+                    .show(ctx, add_contents);
+            }
+        }
     }
 }
@@ -19,9 +19,13 @@ use windows::Win32::UI::WindowsAndMessaging::{
     SW_MINIMIZE, SW_RESTORE, WINDOWPLACEMENT,
 };
 
+#[cfg(target_os = "windows")]
+use crate::os::windows::custom_frame::{set_menu_rect, touch_state};
 use crate::CaptionMaxRect;
 
-pub const TITLEBAR_HEIGHT: i32 = 80;
+// the menu bar sits in a strip above the tab bar
+pub const MENUBAR_HEIGHT: i32 = 40;
+pub const TITLEBAR_HEIGHT: i32 = 80 + MENUBAR_HEIGHT;
 pub const CAPTION_WIDTH_CLOSE: u32 = 94;
 pub const CAPTION_WIDTH_MAXRESTORE: u32 = 87;
 pub const CAPTION_WIDTH_MINIMIZE: u32 = 92;
@@ -41,6 +45,8 @@ pub fn custom_window_frame(
     ctx: &egui::Context,
     frame: &mut eframe::Frame,
     ui: &mut egui::Ui,
+    menu_rect: Rect,
+    maximize_on_double_click: bool,
     #[cfg(target_os = "windows")] sender: Rc<Sender<CaptionMaxRect>>,
 ) {
     let is_maximized = unsafe {
@@ -111,11 +117,29 @@ pub fn custom_window_frame(
         rect.set_bottom(CAPT_TITLEBAR_HEIGHT);
         rect
     };
-    let title_bar_response = ui.interact(title_bar_rect, Id::new("title_bar"), Sense::click());
-    if title_bar_response.is_pointer_button_down_on() {
+    let title_bar_response = ui.interact(
+        title_bar_rect,
+        Id::new("title_bar"),
+        Sense::click_and_drag(),
+    );
+    // clicking the menus must not drag the window around
+    let on_menu = ctx
+        .pointer_interact_pos()
+        .map_or(false, |pos| menu_rect.contains(pos));
+
+    // the window is only dragged once the pointer moves. Dragging right away hands the press to the os,
+    // and the second click of a double click never reaches egui
+    if title_bar_response.dragged() && !on_menu {
         frame.drag_window();
     }
 
+    if title_bar_response.double_clicked() && !on_menu && maximize_on_double_click {
+        toggle_maximized(is_maximized);
+    }
+
+    #[cfg(target_os = "windows")]
+    set_menu_rect(menu_rect);
+
     // Handle caption buttons
     //
     // CLOSE BTN
@@ -144,15 +168,7 @@ pub fn custom_window_frame(
         Color32::from_rgba_unmultiplied(255, 255, 255, 3),
         Color32::from_rgba_unmultiplied(255, 255, 255, 2),
         "titlebar::maximize_btn",
-        || unsafe {
-            let hwnd = GetActiveWindow();
-
-            if is_maximized {
-                ShowWindow(hwnd, SW_RESTORE);
-            } else {
-                ShowWindow(hwnd, SW_MAXIMIZE);
-            }
-        },
+        || toggle_maximized(is_maximized),
     );
 
     //
@@ -172,6 +188,18 @@ pub fn custom_window_frame(
     );
 }
 
+fn toggle_maximized(is_maximized: bool) {
+    unsafe {
+        let hwnd = GetActiveWindow();
+
+        if is_maximized {
+            ShowWindow(hwnd, SW_RESTORE);
+        } else {
+            ShowWindow(hwnd, SW_MAXIMIZE);
+        }
+    }
+}
+
 macro_rules! icon {
     ($ctx:ident, $name:ident) => {{
         paste::paste! {
@@ -262,6 +290,9 @@ fn caption_btn(
         CaptionIcon::Close => 2,
     };
 
+    // touch and pen input is tracked separately from the mouse
+    let touch = touch_state();
+
     // workaround for a problem where checking if hovered, or using hovered pos is imprecise
     // so use the mouse coords and check it's inside the rect to make it exact
     let cursor_pos = if cfg!(target_os = "windows") {
@@ -269,7 +300,13 @@ fn caption_btn(
         // This happens for our max button, which needs special handling for the snaplayout
         let mut point = POINT::default();
         unsafe {
-            GetCursorPos(&mut point);
+            match touch {
+                Some(touch) => point = touch.pos,
+                None => {
+                    GetCursorPos(&mut point);
+                }
+            }
+
             ScreenToClient(GetActiveWindow(), &mut point);
         }
 
@@ -298,7 +335,7 @@ fn caption_btn(
 
         let state = BTN_STATE[btn_index].load(Ordering::Relaxed);
 
-        let click = click_state & 0x8000 != 0;
+        let click = click_state & 0x8000 != 0 || touch.map_or(false, |touch| touch.down);
 
         if click && !state {
             // mouse pressed down
@@ -320,8 +357,9 @@ fn caption_btn(
 
     let pressed = PRESSED[btn_index].load(Ordering::Relaxed);
 
+    // touch and pen can't hover, so don't leave the highlight behind after lifting them
     let target_value = if let Some(pos) = cursor_pos {
-        caption_padding.contains(pos)
+        caption_padding.contains(pos) && touch.map_or(true, |touch| touch.down)
     } else {
         false
     };
@@ -0,0 +1,220 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use egui::{vec2, Align2, Color32, Frame, Id, Order, RichText, Sense, Stroke};
+use rustplay_widgets::dock::DockView;
+
+// temp memory key holding the toasts waiting to be shown or still showing
+const TOASTS: &str = "toasts";
+const TOAST_WIDTH: f32 = 300.0;
+// distance from the bottom right corner of the window
+const MARGIN: f32 = 12.0;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Info => "ℹ",
+            Self::Success => "✔",
+            Self::Warning => "⚠",
+            Self::Error => "✖",
+        }
+    }
+
+    fn color(self) -> Color32 {
+        match self {
+            Self::Info => Color32::from_rgb(90, 160, 255),
+            Self::Success => Color32::from_rgb(90, 200, 110),
+            Self::Warning => Color32::from_rgb(240, 190, 60),
+            Self::Error => Color32::from_rgb(240, 80, 80),
+        }
+    }
+
+    // errors stay up longer, they're more likely to need reading
+    fn duration(self) -> Duration {
+        match self {
+            Self::Info | Self::Success => Duration::from_secs(5),
+            Self::Warning => Duration::from_secs(8),
+            Self::Error => Duration::from_secs(12),
+        }
+    }
+}
+
+/// A button on a toast. Clicking it also dismisses the toast
+#[derive(Debug, Clone)]
+pub enum ToastAction {
+    Copy { label: String, text: String },
+    Open { label: String, url: String },
+    // edit the name of a tab, starting out with the given one
+    Rename { label: String, id: Id, name: String },
+}
+
+impl ToastAction {
+    fn label(&self) -> &str {
+        match self {
+            Self::Copy { label, .. } | Self::Open { label, .. } | Self::Rename { label, .. } => {
+                label
+            }
+        }
+    }
+
+    fn run(&self, ctx: &egui::Context) {
+        match self {
+            Self::Copy { text, .. } => ctx.output().copied_text = text.clone(),
+            Self::Open { url, .. } => {
+                let _ = open::that(url);
+            }
+            Self::Rename { id, name, .. } => DockView::rename(ctx, *id, name.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    id: u64,
+    level: ToastLevel,
+    message: String,
+    actions: Vec<ToastAction>,
+    // when it was first shown, or last hovered
+    shown: Option<Instant>,
+}
+
+impl Toast {
+    pub fn new(level: ToastLevel, message: impl Into<String>) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            level,
+            message: message.into(),
+            actions: vec![],
+            shown: None,
+        }
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(ToastLevel::Info, message)
+    }
+
+    pub fn success(message: impl Into<String>) -> Self {
+        Self::new(ToastLevel::Success, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(ToastLevel::Warning, message)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(ToastLevel::Error, message)
+    }
+
+    pub fn action(mut self, action: ToastAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Queue the toast. Can be called from any thread
+    pub fn push(self, ctx: &egui::Context) {
+        ctx.data()
+            .get_temp_mut_or_default::<Vec<Toast>>(Id::new(TOASTS))
+            .push(self);
+
+        ctx.request_repaint();
+    }
+}
+
+pub struct Toasts;
+
+impl Toasts {
+    /// Show the queued toasts stacked in the bottom right corner, newest at the bottom.
+    /// They dismiss themselves after a while unless hovered
+    pub fn show(ctx: &egui::Context) {
+        let mut toasts = ctx
+            .data()
+            .get_temp::<Vec<Toast>>(Id::new(TOASTS))
+            .unwrap_or_default();
+
+        if toasts.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        let shown_ids = toasts.iter().map(|toast| toast.id).collect::<Vec<_>>();
+        let mut dismissed = vec![];
+
+        egui::Area::new("toasts")
+            .anchor(Align2::RIGHT_BOTTOM, vec2(-MARGIN, -MARGIN))
+            .order(Order::Foreground)
+            .show(ctx, |ui| {
+                for toast in &mut toasts {
+                    let shown = *toast.shown.get_or_insert(now);
+
+                    let response = Frame::popup(ui.style())
+                        .stroke(Stroke::new(1.0, toast.level.color()))
+                        .show(ui, |ui| {
+                            ui.set_width(TOAST_WIDTH);
+
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new(toast.level.icon())
+                                        .color(toast.level.color())
+                                        .strong(),
+                                );
+
+                                ui.label(&toast.message);
+                            });
+
+                            ui.horizontal(|ui| {
+                                for action in &toast.actions {
+                                    if ui.small_button(action.label()).clicked() {
+                                        action.run(ctx);
+                                        dismissed.push(toast.id);
+                                    }
+                                }
+
+                                if ui.small_button("Dismiss").clicked() {
+                                    dismissed.push(toast.id);
+                                }
+                            });
+                        })
+                        .response
+                        .interact(Sense::hover());
+
+                    // keep it up while it's being read
+                    if response.hovered() {
+                        toast.shown = Some(now);
+                    } else if now.duration_since(shown) >= toast.level.duration() {
+                        dismissed.push(toast.id);
+                    }
+                }
+            });
+
+        toasts.retain(|toast| !dismissed.contains(&toast.id));
+
+        // wake up again when the next one is due
+        if let Some(remaining) = toasts
+            .iter()
+            .filter_map(|toast| {
+                let elapsed = now.duration_since(toast.shown?);
+                Some(toast.level.duration().saturating_sub(elapsed))
+            })
+            .min()
+        {
+            ctx.request_repaint_after(remaining);
+        }
+
+        // toasts pushed by other threads in the meantime are kept
+        let mut data = ctx.data();
+        let queued = data.get_temp_mut_or_default::<Vec<Toast>>(Id::new(TOASTS));
+        queued.retain(|toast| !shown_ids.contains(&toast.id));
+        toasts.append(queued);
+        *queued = toasts;
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+use cargo_player::Channel;
+use egui::Ui;
+
+use crate::config::{Command, Config, MenuCommand, Terminal, TerminalSettings, Toolchains};
+use crate::utils::toolchain::{self, CHANNELS, COMPONENTS};
+
+use super::dock::TabEvents;
+use super::terminal;
+
+pub struct ToolchainManager;
+
+impl ToolchainManager {
+    /// List the installed toolchains in the background once, and pick up the result
+    pub fn check(ctx: &egui::Context, toolchains: &mut Toolchains) {
+        if let Some(loading) = &toolchains.loading {
+            match loading.try_recv() {
+                Ok(installed) => {
+                    toolchains.installed = Some(installed);
+                    toolchains.loading = None;
+                }
+
+                Err(TryRecvError::Disconnected) => toolchains.loading = None,
+
+                Err(TryRecvError::Empty) => (),
+            }
+
+            return;
+        }
+
+        if toolchains.installed.is_none() {
+            Self::refresh(ctx, toolchains);
+        }
+    }
+
+    fn refresh(ctx: &egui::Context, toolchains: &mut Toolchains) {
+        let (tx, rx) = mpsc::channel();
+        toolchains.loading = Some(rx);
+
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let _ = tx.send(toolchain::installed_toolchains());
+
+            ctx.request_repaint();
+        });
+    }
+
+    /// Menu listing every channel and its components, with buttons to install missing ones
+    pub fn show(ctx: &egui::Context, config: &mut Config, ui: &mut Ui) {
+        let toolchains = &mut config.toolchains;
+
+        if toolchains.loading.is_some() {
+            ui.label("Checking toolchains...");
+            return;
+        }
+
+        if toolchains.installed.as_ref().map_or(true, Vec::is_empty) {
+            ui.label("No toolchains found, is rustup installed?");
+        }
+
+        let mut commands = vec![];
+
+        for channel in CHANNELS {
+            let name: &str = channel.into();
+
+            let Some(installed) = toolchains.get(channel) else {
+                ui.horizontal(|ui| {
+                    ui.label(name);
+
+                    if ui.small_button("Install").clicked() {
+                        commands.push(MenuCommand::InstallToolchain(channel, None));
+                        ui.close_menu();
+                    }
+                });
+
+                continue;
+            };
+
+            ui.menu_button(format!("{name} ✔"), |ui| {
+                ui.label(&installed.name);
+
+                ui.separator();
+
+                for component in COMPONENTS {
+                    if installed.has_component(component) {
+                        ui.label(format!("{component} ✔"));
+                        continue;
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label(component);
+
+                        if ui.small_button("Install").clicked() {
+                            commands.push(MenuCommand::InstallToolchain(channel, Some(component)));
+                            ui.close_menu();
+                        }
+                    });
+                }
+            });
+        }
+
+        ui.separator();
+
+        if ui.button("Refresh").clicked() {
+            Self::refresh(ctx, toolchains);
+        }
+
+        config
+            .dock
+            .commands
+            .extend(commands.into_iter().map(Command::MenuCommand));
+    }
+
+    /// Stream the rustup install to the terminal of the selected tab.
+    /// The toolchain list is refreshed once it succeeded
+    pub fn install(
+        ctx: &egui::Context,
+        terminal: &mut Terminal,
+        settings: &TerminalSettings,
+        toolchains: &mut Toolchains,
+        channel: Channel,
+        component: Option<&'static str>,
+    ) {
+        let Some(id) = terminal.active_tab else {
+            return;
+        };
+
+        terminal.assertions.remove(&id);
+
+        let (tx, rx) = mpsc::channel();
+        toolchains.loading = Some(rx);
+
+        let repaint_ctx = ctx.clone();
+
+        TabEvents::run_in_terminal(
+            ctx,
+            terminal,
+            id,
+            "Toolchain install".to_string(),
+            None,
+            move || Ok(vec![toolchain::install_command(channel, component)]),
+            move |_| {
+                let _ = tx.send(toolchain::installed_toolchains());
+
+                repaint_ctx.request_repaint();
+            },
+        );
+
+        if !terminal.open {
+            let size = settings.size_for(terminal, id);
+            terminal::Terminal::open(ctx, terminal, settings, size);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::process::Command;
+use std::sync::mpsc::{channel, TryRecvError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use egui::Ui;
+
+use crate::config::Config;
+use crate::utils::toolchain;
+
+use super::dock::TabEvents;
+use super::terminal::Terminal;
+
+pub struct UpdateNudge;
+
+impl UpdateNudge {
+    /// Show a small notice in the menu bar when a newer stable toolchain is out
+    pub fn show(ctx: &egui::Context, config: &mut Config, ui: &mut Ui) {
+        Self::check(ctx, config);
+
+        if config.updates.dismissed {
+            return;
+        }
+
+        let Some(version) = config.updates.available.clone() else {
+            return;
+        };
+
+        ui.separator();
+
+        ui.label(format!("Rust {version} is available"));
+
+        if ui
+            .small_button("Update")
+            .on_hover_text("Run rustup update stable")
+            .clicked()
+        {
+            Self::update(ctx, config);
+        }
+
+        if ui.small_button("Dismiss").clicked() {
+            config.updates.dismissed = true;
+        }
+    }
+
+    // kick off a background check once the interval has passed, and pick up its result
+    fn check(ctx: &egui::Context, config: &mut Config) {
+        let updates = &mut config.updates;
+
+        if let Some(checking) = &updates.checking {
+            match checking.try_recv() {
+                Ok(available) => {
+                    updates.available = available;
+                    updates.checking = None;
+                }
+
+                Err(TryRecvError::Disconnected) => updates.checking = None,
+
+                Err(TryRecvError::Empty) => (),
+            }
+
+            return;
+        }
+
+        let general = &mut config.general;
+
+        if !general.check_updates {
+            return;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let next_check = general.last_update_check + general.update_check_interval.max(1) * 60 * 60;
+
+        if now < next_check {
+            ctx.request_repaint_after(Duration::from_secs(next_check - now));
+            return;
+        }
+
+        general.last_update_check = now;
+
+        let (tx, rx) = channel();
+        updates.checking = Some(rx);
+
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let available = match (toolchain::installed_stable(), toolchain::latest_stable()) {
+                (Some(installed), Some(latest)) if toolchain::is_newer(&latest, &installed) => {
+                    Some(latest)
+                }
+
+                _ => None,
+            };
+
+            let _ = tx.send(available);
+
+            ctx.request_repaint();
+        });
+    }
+
+    // stream `rustup update stable` to the terminal of the selected tab
+    fn update(ctx: &egui::Context, config: &mut Config) {
+        let Some(id) = config.terminal.active_tab else {
+            return;
+        };
+
+        config.terminal.assertions.remove(&id);
+
+        TabEvents::run_in_terminal(
+            ctx,
+            &mut config.terminal,
+            id,
+            "Rust update".to_string(),
+            None,
+            || {
+                let mut command = Command::new("rustup");
+                command.args(["update", "stable"]);
+                Ok(vec![command])
+            },
+            |_| (),
+        );
+
+        if !config.terminal.open {
+            Terminal::toggle(ctx, config);
+        }
+
+        config.updates.available = None;
+    }
+}
@@ -0,0 +1,514 @@
+// a control sequence with more parameters than this is garbage, it's dropped without being applied
+const MAX_PARAMS_LEN: usize = 64;
+
+// parse color mode 5
+fn parse_rgb(color: u8) -> Color {
+    // 0-15 are regular colors, even in color mode 5
+    if color < 16 {
+        return base_color(color);
+    }
+
+    // extended range of colors
+    if let 16..=231 = color {
+        let index_r = (color - 16) / 36;
+        let rgb_r = if index_r > 0 { 55 + index_r * 40 } else { 0 };
+        let index_g = ((color - 16) % 36) / 6;
+        let rgb_g = if index_g > 0 { 55 + index_g * 40 } else { 0 };
+        let index_b = (color - 16) % 6;
+        let rgb_b = if index_b > 0 { 55 + index_b * 40 } else { 0 };
+
+        Color::Rgb(rgb_r, rgb_g, rgb_b)
+    } else {
+        // grayscale colors from 232-256
+        let color = (color - 232) * 10 + 8;
+        Color::Rgb(color, color, color)
+    }
+}
+
+// where the escape sequence being parsed is at
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Text,
+    // after the escape character
+    Escape,
+    // a control sequence, the parameters so far
+    Csi(String),
+    // an operating system command, e.g. a title or hyperlink, dropped entirely
+    Osc,
+    // an escape inside an operating system command, which may be the string terminator
+    OscEscape,
+}
+
+// the style in effect, changed by graphics sequences
+#[derive(Debug, Default, Clone, Copy)]
+struct Pen {
+    style: TextStyle,
+    fg: Option<Color>,
+    bg: Option<Color>,
+}
+
+/// Parse the styles of the text between escape sequences. Byte ranges are those of the text with the
+/// sequences stripped. Sequences which are unknown, cut off or malformed are dropped without a style applied
+pub fn parse(text: &str) -> Parsed {
+    let mut parser = Parser::default();
+    parser.feed(text);
+
+    Parsed {
+        properties: parser.properties,
+    }
+}
+
+/// Parses text as it comes in, so earlier text isn't parsed again. An escape sequence cut off at the end
+/// of one chunk is finished by the next, and the style in effect carries over to it
+#[derive(Debug, Default)]
+pub struct Parser {
+    state: State,
+    pen: Pen,
+    // start of the text written with the current pen, and the end of the text so far
+    start: usize,
+    end: usize,
+    properties: Vec<TextProperty>,
+}
+
+impl Parser {
+    /// The styles of the text so far, see [`parse`]
+    pub fn properties(&self) -> &[TextProperty] {
+        &self.properties
+    }
+
+    pub fn feed(&mut self, text: &str) {
+        for c in text.chars() {
+            self.state = self.next_state(c);
+        }
+
+        self.extend_property();
+    }
+
+    /// Forget the text past the first `len` bytes of the stripped text. The style in effect is forgotten
+    /// with it, text fed afterwards starts out unstyled
+    pub fn truncate(&mut self, len: usize) {
+        self.properties.retain(|property| property.start < len);
+
+        if let Some(last) = self.properties.last_mut() {
+            last.end = last.end.min(len);
+        }
+
+        self.state = State::Text;
+        self.pen = Pen::default();
+        self.end = self.end.min(len);
+        self.start = self.end;
+    }
+
+    /// Give back the memory the styles of text since truncated took up
+    pub fn shrink_to_fit(&mut self) {
+        self.properties.shrink_to_fit();
+    }
+
+    fn next_state(&mut self, c: char) -> State {
+        match std::mem::take(&mut self.state) {
+            State::Text if c == '\x1b' => State::Escape,
+            State::Text => {
+                self.end += c.len_utf8();
+                State::Text
+            }
+
+            State::Escape => match c {
+                '[' => State::Csi(String::new()),
+                ']' => State::Osc,
+                // the character after it ends other sequences
+                _ => State::Text,
+            },
+
+            State::Csi(mut params) => match c {
+                // parameters and intermediate bytes
+                '\x20'..='\x3f' if params.len() < MAX_PARAMS_LEN => {
+                    params.push(c);
+                    State::Csi(params)
+                }
+
+                // too long to be anything real, the rest of it is skipped up to the final byte
+                '\x20'..='\x3f' => State::Csi(params),
+
+                // the final byte
+                '\x40'..='\x7e' => {
+                    if c == 'm' && params.len() < MAX_PARAMS_LEN {
+                        self.extend_property();
+                        set_graphics(&mut self.pen, &params);
+                        self.start = self.end;
+                    }
+
+                    State::Text
+                }
+
+                // anything else cuts the sequence off, it's shown as text instead
+                '\x1b' => State::Escape,
+                c => {
+                    self.end += c.len_utf8();
+                    State::Text
+                }
+            },
+
+            State::Osc => match c {
+                '\x07' => State::Text,
+                '\x1b' => State::OscEscape,
+                _ => State::Osc,
+            },
+
+            State::OscEscape => match c {
+                '\\' => State::Text,
+                '\x1b' => State::OscEscape,
+                _ => State::Osc,
+            },
+        }
+    }
+
+    // cover the text written since the last change of style, the property is extended as more of it comes in
+    fn extend_property(&mut self) {
+        if self.end == self.start {
+            return;
+        }
+
+        match self.properties.last_mut() {
+            Some(last) if last.start == self.start => last.end = self.end,
+            _ => self.properties.push(TextProperty {
+                start: self.start,
+                end: self.end,
+                style: self.pen.style,
+                fg: self.pen.fg,
+                bg: self.pen.bg,
+            }),
+        }
+    }
+}
+
+// apply a select graphic rendition sequence. Parameters which aren't numbers, and colors missing components
+// or out of range, end the sequence without the rest of it applied
+fn set_graphics(pen: &mut Pen, params: &str) {
+    // colon separated sub parameters are treated like the semicolon separated kind
+    let mut params = params.split([';', ':']).map(|param| match param {
+        // an empty parameter is a reset
+        "" => Some(0),
+        param => param.parse::<u8>().ok(),
+    });
+
+    while let Some(Some(param)) = params.next() {
+        let style = &mut pen.style;
+
+        match param {
+            // reset all modes
+            0 => *pen = Pen::default(),
+
+            // set bold -> 22 reset
+            1 => style.bold = true,
+
+            // set dim/faint -> 22 reset
+            2 => style.dim = true,
+
+            // set italic -> 23 reset
+            3 => style.italic = true,
+
+            // set underline -> 24 reset
+            4 => style.underline = true,
+
+            // set blink -> 25 reset
+            5 => style.blink = true,
+
+            // set inverse/reverse -> 27 reset
+            7 => style.reverse = true,
+
+            // set hidden -> 28 reset
+            8 => style.hidden = true,
+
+            // set strikethrough -> 29 reset
+            9 => style.strikethrough = true,
+
+            22 => {
+                style.bold = false;
+                style.dim = false;
+            }
+            23 => style.italic = false,
+            24 => style.underline = false,
+            25 => style.blink = false,
+            27 => style.reverse = false,
+            28 => style.hidden = false,
+            29 => style.strikethrough = false,
+
+            30..=37 => pen.fg = Some(base_color(param - 30)),
+            40..=47 => pen.bg = Some(base_color(param - 40)),
+
+            // Default
+            39 => pen.fg = None,
+            49 => pen.bg = None,
+
+            90..=97 => pen.fg = Some(base_color(param - 90 + 8)),
+            100..=107 => pen.bg = Some(base_color(param - 100 + 8)),
+
+            // extended colors
+            38 | 48 => {
+                let color = match params.next().flatten() {
+                    // color mode 5
+                    Some(5) => params.next().flatten().map(parse_rgb),
+
+                    Some(2) => {
+                        let r = params.next().flatten();
+                        let g = params.next().flatten();
+                        let b = params.next().flatten();
+
+                        r.zip(g).zip(b).map(|((r, g), b)| Color::Rgb(r, g, b))
+                    }
+
+                    _ => None,
+                };
+
+                let Some(color) = color else {
+                    return;
+                };
+
+                if param == 38 {
+                    pen.fg = Some(color);
+                } else {
+                    pen.bg = Some(color);
+                }
+            }
+
+            // e.g. fonts and frames, which can't be shown
+            _ => (),
+        }
+    }
+}
+
+// the 16 regular colors
+fn base_color(color: u8) -> Color {
+    match color {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::White,
+        8 => Color::BrightBlack,
+        9 => Color::BrightRed,
+        10 => Color::BrightGreen,
+        11 => Color::BrightYellow,
+        12 => Color::BrightBlue,
+        13 => Color::BrightMagenta,
+        14 => Color::BrightCyan,
+        _ => Color::BrightWhite,
+    }
+}
+
+#[derive(Debug)]
+pub struct Parsed {
+    pub properties: Vec<TextProperty>,
+}
+
+#[derive(Debug, Hash, Copy, Clone)]
+pub struct TextProperty {
+    pub start: usize,
+    pub end: usize,
+    pub style: TextStyle,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+#[derive(Debug, Copy, Clone, Default, Hash)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub blink: bool,
+    pub reverse: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+}
+
+#[derive(Debug, Copy, Clone, Hash)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// The bright version of the 8 basic colors, which is how terminals show bold text in them
+    pub fn brightened(self) -> Self {
+        match self {
+            Self::Black => Self::BrightBlack,
+            Self::Red => Self::BrightRed,
+            Self::Green => Self::BrightGreen,
+            Self::Yellow => Self::BrightYellow,
+            Self::Blue => Self::BrightBlue,
+            Self::Magenta => Self::BrightMagenta,
+            Self::Cyan => Self::BrightCyan,
+            Self::White => Self::BrightWhite,
+            color => color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the parts of the text the properties cover
+    fn covered<'a>(text: &'a str, parsed: &Parsed) -> Vec<&'a str> {
+        parsed
+            .properties
+            .iter()
+            .map(|property| &text[property.start..property.end])
+            .collect()
+    }
+
+    fn check(input: &str) -> Parsed {
+        let parsed = parse(input);
+
+        let mut last_end = 0;
+        for property in &parsed.properties {
+            assert!(property.start >= last_end, "{input:?}: {parsed:?}");
+            assert!(property.start < property.end, "{input:?}: {parsed:?}");
+            last_end = property.end;
+        }
+
+        parsed
+    }
+
+    #[test]
+    fn styled_text() {
+        let parsed = check("a\x1b[1;31mb\x1b[0mc\x1b[38;2;1;2;3md\x1b[48;5;196me");
+
+        assert_eq!(covered("abcde", &parsed), ["a", "b", "c", "d", "e"]);
+        assert!(parsed.properties[1].style.bold);
+        assert!(matches!(parsed.properties[1].fg, Some(Color::Red)));
+        assert!(!parsed.properties[2].style.bold);
+        assert!(matches!(parsed.properties[3].fg, Some(Color::Rgb(1, 2, 3))));
+        assert!(matches!(
+            parsed.properties[4].bg,
+            Some(Color::Rgb(255, 0, 0))
+        ));
+    }
+
+    #[test]
+    fn malformed_sequences() {
+        let inputs = [
+            "\x1b",
+            "\x1b[",
+            "\x1b[38",
+            "\x1b[38;",
+            "\x1b[38;2;1;2m",
+            "\x1b[38;5m",
+            "\x1b[38;9;1m",
+            "\x1b[999m",
+            "\x1b[-1m",
+            "\x1b[1;;;m",
+            "\x1b[;m",
+            "\x1b[1:2:3m",
+            "\x1b[38;2;256;0;0m",
+            "\x1b[\x1b[\x1b[m",
+            "\x1b]0;title",
+            "\x1b]0;title\x1b",
+            "\x1b]8;;link\x1b\\",
+            "\x1b[1\ntext",
+            "\x1b[1é",
+            "\x1b\x1b\x1b",
+            "text\x1b",
+        ];
+
+        for input in inputs {
+            check(input);
+            check(&format!("a{input}b"));
+            check(&input.repeat(3));
+        }
+
+        assert_eq!(covered("ab", &check("a\x1b]0;title\x07b")), ["ab"]);
+        assert_eq!(covered("ab", &check("a\x1b[38;2;1;2mb")), ["a", "b"]);
+        assert_eq!(covered("a\nb", &check("a\x1b[1\nb")), ["a\nb"]);
+    }
+
+    #[test]
+    fn long_sequence() {
+        let input = format!("a\x1b[{}mb", "1;".repeat(MAX_PARAMS_LEN * 10));
+        let parsed = check(&input);
+
+        assert_eq!(covered("ab", &parsed), ["ab"]);
+        assert!(!parsed.properties[0].style.bold);
+    }
+
+    #[test]
+    fn random_input() {
+        let alphabet = [
+            '\x1b', '[', ']', ';', ':', 'm', 'H', '0', '1', '2', '5', '3', '8', '9', '\x07', '\\',
+            '\n', 'a', 'é', '字',
+        ];
+
+        // a fixed linear congruential generator, so a failure can be reproduced
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..2000 {
+            let len = (seed >> 33) % 64;
+            let input = (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    alphabet[(seed >> 33) as usize % alphabet.len()]
+                })
+                .collect::<String>();
+
+            check(&input);
+
+            // fed in two chunks, cut anywhere, it's parsed the same
+            let chars = input.chars().count();
+            let cut = input
+                .char_indices()
+                .nth((seed >> 40) as usize % (chars + 1))
+                .map_or(input.len(), |(i, _)| i);
+
+            let mut parser = Parser::default();
+            parser.feed(&input[..cut]);
+            parser.feed(&input[cut..]);
+
+            assert_eq!(
+                format!("{:?}", parser.properties()),
+                format!("{:?}", parse(&input).properties),
+                "{input:?} cut at {cut}"
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_and_feed() {
+        let mut parser = Parser::default();
+        parser.feed("a\x1b[31mb");
+        parser.feed("c\x1b[");
+        parser.feed("1md\x1b[0m\n");
+
+        assert_eq!(
+            format!("{:?}", parser.properties()),
+            format!("{:?}", parse("a\x1b[31mbc\x1b[1md\x1b[0m\n").properties)
+        );
+
+        // the last line is written again, the style before it doesn't carry over
+        parser.truncate(2);
+        parser.feed("x\x1b[32my");
+
+        assert_eq!(
+            format!("{:?}", parser.properties()),
+            format!("{:?}", parse("a\x1b[31mb\x1b[0mx\x1b[32my").properties)
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Matching brackets of Rust code. Brackets in strings, chars and comments aren't counted
+
+/// The pairs of matching brackets as char indices of the opening and the closing one, ordered by the
+/// opening one. A bracket without a match, e.g. one being typed, is left out
+pub fn bracket_pairs(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut pairs = vec![];
+    let mut open = Vec::<(char, usize)>::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+
+        match chars[i] {
+            '/' if next == Some('/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+
+            '/' if next == Some('*') => i = block_comment_end(chars, i),
+
+            '"' => i = string_end(chars, i + 1, 0),
+
+            'r' | 'b' if is_raw_string(chars, i) => {
+                let quote = (i..chars.len())
+                    .find(|&i| chars[i] == '"')
+                    .unwrap_or(chars.len());
+                let hashes = chars[i..quote].iter().filter(|&&c| c == '#').count();
+
+                i = string_end(chars, quote + 1, hashes);
+            }
+
+            // a char, unlike a lifetime it's closed right after its char or escape
+            '\'' if next == Some('\\') => {
+                // past the escaped char, it may be a quote
+                i += 3;
+                while i < chars.len() && chars[i] != '\'' {
+                    i += 1;
+                }
+            }
+
+            '\'' if chars.get(i + 2) == Some(&'\'') => i += 2,
+
+            c @ ('(' | '[' | '{') => open.push((c, i)),
+
+            c @ (')' | ']' | '}') if open.last().map(|&(open, _)| closing(open)) == Some(c) => {
+                let (_, start) = open.pop().unwrap();
+                pairs.push((start, i));
+            }
+
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    pairs.sort_unstable();
+    pairs
+}
+
+/// The bracket next to the cursor and its match. The one after the cursor is picked over the one before it
+pub fn matching_bracket(chars: &[char], cursor: usize) -> Option<(usize, usize)> {
+    let pairs = bracket_pairs(chars);
+
+    let find = |index: usize| {
+        pairs.iter().find_map(|&(open, close)| match index {
+            i if i == open => Some((open, close)),
+            i if i == close => Some((close, open)),
+            _ => None,
+        })
+    };
+
+    find(cursor).or_else(|| find(cursor.checked_sub(1)?))
+}
+
+/// The innermost pair of braces the cursor is between
+pub fn enclosing_block(chars: &[char], cursor: usize) -> Option<(usize, usize)> {
+    bracket_pairs(chars)
+        .into_iter()
+        .rev()
+        .find(|&(open, close)| chars[open] == '{' && open < cursor && cursor <= close)
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+// r"..", r#".."#, br".." and so on, but not the end of an identifier such as `bar`
+fn is_raw_string(chars: &[char], i: usize) -> bool {
+    if i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_') {
+        return false;
+    }
+
+    let start = if chars[i] == 'b' { i + 1 } else { i };
+    if chars.get(start) != Some(&'r') {
+        return false;
+    }
+
+    chars[start + 1..].iter().find(|&&c| c != '#') == Some(&'"')
+}
+
+// the index of the quote closing a string, escapes are skipped unless it's raw
+fn string_end(chars: &[char], mut i: usize, hashes: usize) -> usize {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if hashes == 0 => i += 1,
+
+            '"' if chars[i + 1..].iter().take_while(|&&c| c == '#').count() >= hashes => {
+                return i + hashes;
+            }
+
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    chars.len()
+}
+
+// block comments nest
+fn block_comment_end(chars: &[char], mut i: usize) -> usize {
+    let mut depth = 0;
+
+    while i + 1 < chars.len() {
+        match (chars[i], chars[i + 1]) {
+            ('/', '*') => {
+                depth += 1;
+                i += 1;
+            }
+
+            ('*', '/') => {
+                depth -= 1;
+                i += 1;
+
+                if depth == 0 {
+                    return i;
+                }
+            }
+
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    chars.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn pairs() {
+        let code = chars("fn main() { let x = [1, (2)]; }");
+
+        assert_eq!(
+            bracket_pairs(&code),
+            vec![(7, 8), (10, 30), (20, 27), (24, 26)]
+        );
+    }
+
+    #[test]
+    fn skipped() {
+        let code = chars(
+            r##"{ "}" '}' '\'' r#"}"# /* { /* } */ */ // }
+}"##,
+        );
+
+        assert_eq!(bracket_pairs(&code), vec![(0, 43)]);
+
+        // a lifetime isn't a char
+        let code = chars("fn f<'a>(x: &'a str) {}");
+        assert_eq!(bracket_pairs(&code), vec![(8, 19), (21, 22)]);
+
+        // unclosed while typing
+        assert_eq!(bracket_pairs(&chars("{ ( }")), vec![]);
+        assert_eq!(bracket_pairs(&chars("{ ) }")), vec![(0, 4)]);
+    }
+
+    #[test]
+    fn matching() {
+        let code = chars("f(a[0])");
+
+        assert_eq!(matching_bracket(&code, 1), Some((1, 6)));
+        assert_eq!(matching_bracket(&code, 2), Some((1, 6)));
+        assert_eq!(matching_bracket(&code, 7), Some((6, 1)));
+        // the one after the cursor first
+        assert_eq!(matching_bracket(&code, 6), Some((6, 1)));
+        assert_eq!(matching_bracket(&code, 0), None);
+    }
+
+    #[test]
+    fn blocks() {
+        let code = chars("fn f() { if x { y } }");
+
+        assert_eq!(enclosing_block(&code, 9), Some((7, 20)));
+        assert_eq!(enclosing_block(&code, 16), Some((14, 18)));
+        assert_eq!(enclosing_block(&code, 3), None);
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use egui::Id;
+
+// temp memory key of the stats, together with the name of the cache
+const STATS: &str = "cache_stats";
+
+/// Counts how often a memoized layout was asked for, and how often it had to be computed.
+/// Kept in the memory of the context the cache is in, see [`CacheStats::of`]
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    lookups: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheStats {
+    pub const fn new() -> Self {
+        Self {
+            lookups: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The stats of a cache of the context by the cache's name, e.g. [`crate::code_editor::HIGHLIGHT_STATS`].
+    /// Every context counts its own
+    pub fn of(ctx: &egui::Context, cache: &str) -> Arc<Self> {
+        let id = Id::new(STATS).with(cache);
+
+        ctx.data().get_temp_mut_or_default::<Arc<Self>>(id).clone()
+    }
+
+    /// Count a lookup of the cache
+    pub fn lookup(&self) {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a lookup which had to compute the value
+    pub fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn lookups(&self) -> u64 {
+        self.lookups.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// The share of lookups which found their value in the cache, None before the first lookup
+    pub fn hit_rate(&self) -> Option<f32> {
+        let lookups = self.lookups();
+        if lookups == 0 {
+            return None;
+        }
+
+        let hits = lookups.saturating_sub(self.misses());
+
+        Some(hits as f32 / lookups as f32)
+    }
+
+    pub fn reset(&self) {
+        self.lookups.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_rate() {
+        let stats = CacheStats::new();
+        assert_eq!(stats.hit_rate(), None);
+
+        for _ in 0..4 {
+            stats.lookup();
+        }
+        stats.miss();
+
+        assert_eq!(stats.hit_rate(), Some(0.75));
+
+        stats.reset();
+        assert_eq!(stats.hit_rate(), None);
+    }
+
+    #[test]
+    fn per_context() {
+        let ctx = egui::Context::default();
+        let other = egui::Context::default();
+
+        CacheStats::of(&ctx, "test").lookup();
+
+        assert_eq!(CacheStats::of(&ctx, "test").lookups(), 1);
+        assert_eq!(CacheStats::of(&ctx, "other").lookups(), 0);
+        assert_eq!(CacheStats::of(&other, "test").lookups(), 0);
+    }
+}
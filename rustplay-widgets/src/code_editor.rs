@@ -0,0 +1,1747 @@
+// ----------------------------------------------------------------------------
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Weak};
+use std::thread;
+
+use cargo_player::LineEnding;
+use egui::mutex::Mutex;
+use egui::text::{CCursor, LayoutJob, LayoutSection, TextFormat};
+use egui::text_edit::{CCursorRange, TextEditState};
+use egui::{
+    vec2, Align, Align2, Color32, Event, FontId, Frame, Id, Key, Layout, Modifiers, Rect, Rounding,
+    Sense, Shape, Stroke, TextBuffer, Ui, Vec2,
+};
+use serde::{Deserialize, Serialize};
+use syntect::highlighting::{FontStyle, HighlightIterator, HighlightState, Style, ThemeSet};
+use syntect::parsing::ParseState;
+
+use crate::brackets::{enclosing_block, matching_bracket};
+use crate::cache_stats::CacheStats;
+use crate::export;
+use crate::paste;
+use crate::syntax_themes::SyntaxThemes;
+use crate::vim::{Vim, VimCommand, VimKey, VimMode, VimOutcome};
+
+pub const MIN_FONT_SIZE: f32 = 6.0;
+pub const MAX_FONT_SIZE: f32 = 48.0;
+pub const MIN_ZOOM: f32 = 0.5;
+pub const MAX_ZOOM: f32 = 4.0;
+// how much ctrl+= and ctrl+- zoom by
+const ZOOM_STEP: f32 = 1.1;
+
+// added to the black background of the editor, the block around the cursor is barely lighter
+const BLOCK_TINT: Color32 = Color32::from_additive_luminance(12);
+const BRACKET_TINT: Color32 = Color32::from_additive_luminance(60);
+
+const MINIMAP_WIDTH: f32 = 80.0;
+// the size of a char in the minimap. Lines get thinner when the code is too long to fit otherwise
+const MINIMAP_CHAR_WIDTH: f32 = 1.0;
+const MINIMAP_LINE_HEIGHT: f32 = 2.0;
+
+/// The name of the [`CacheStats`] of how often [`highlight`] found the layout of the code in its cache
+pub const HIGHLIGHT_STATS: &str = "code_editor_highlight";
+
+/// Font and wrapping of the code editor, the terminal shows output with them too
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorSettings {
+    pub font_size: f32,
+    pub word_wrap: bool,
+    // convert the line endings of pasted text to those of the code it's pasted into
+    pub normalize_paste: bool,
+    // strip the prompts and line numbers pasted code was copied with, and offer to put statements in a
+    // fn main()
+    pub smart_paste: bool,
+    // vim's normal, insert and visual modes, the app carries out :w and :q
+    pub vim: bool,
+    // the bracket matching the one next to the cursor, and the block the cursor is in
+    pub highlight_brackets: bool,
+    // a shrunken view of the whole code on the right, clicked or dragged to scroll
+    pub minimap: bool,
+}
+
+impl Default for EditorSettings {
+    fn default() -> Self {
+        Self {
+            font_size: 12.0,
+            word_wrap: true,
+            normalize_paste: true,
+            smart_paste: false,
+            vim: false,
+            highlight_brackets: true,
+            minimap: false,
+        }
+    }
+}
+
+impl EditorSettings {
+    pub fn font_id(&self) -> FontId {
+        FontId::monospace(self.font_size)
+    }
+
+    /// Apply the font size and word wrap settings to a highlighted layout job
+    pub fn apply(&self, job: &mut LayoutJob, wrap_width: f32) {
+        for section in &mut job.sections {
+            section.format.font_id.size = self.font_size;
+        }
+
+        job.wrap.max_width = if self.word_wrap {
+            wrap_width
+        } else {
+            f32::INFINITY
+        };
+    }
+
+    /// The settings with the font size scaled by a zoom, see [`zoom_input`]
+    pub fn zoomed(&self, zoom: f32) -> Self {
+        Self {
+            font_size: (self.font_size * zoom).clamp(MIN_FONT_SIZE, MAX_FONT_SIZE),
+            ..self.clone()
+        }
+    }
+}
+
+/// Zoom with ctrl+scroll while the pointer is over `rect`, and with ctrl+= and ctrl+- while `keys` is true.
+/// Ctrl+0 goes back to `default`. Returns whether the zoom changed
+pub fn zoom_input(ui: &Ui, rect: Rect, keys: bool, zoom: &mut f32, default: f32) -> bool {
+    let before = *zoom;
+
+    if ui.rect_contains_pointer(rect) {
+        *zoom *= ui.input().zoom_delta();
+    }
+
+    if keys {
+        let mut input = ui.ctx().input_mut();
+
+        if input.consume_key(Modifiers::COMMAND, Key::PlusEquals) {
+            *zoom *= ZOOM_STEP;
+        }
+
+        if input.consume_key(Modifiers::COMMAND, Key::Minus) {
+            *zoom /= ZOOM_STEP;
+        }
+
+        if input.consume_key(Modifiers::COMMAND, Key::Num0) {
+            *zoom = default;
+        }
+
+        // some platforms type the key along with the shortcut
+        if input.modifiers.command {
+            input
+                .events
+                .retain(|event| !matches!(event, Event::Text(text) if ["=", "+", "-", "0"].contains(&text.as_str())));
+        }
+    }
+
+    *zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+
+    *zoom != before
+}
+
+/// Memoized code highlighting, done on a worker thread so big pastes don't freeze the ui. Until the worker
+/// is done, the lines which are the same as in the last highlighted code keep their colors and the others
+/// are unstyled, the ui is repainted once it's done
+pub fn highlight(ctx: &egui::Context, theme: &CodeTheme, code: &str, language: &str) -> LayoutJob {
+    let stats = CacheStats::of(ctx, HIGHLIGHT_STATS);
+    stats.lookup();
+
+    let key = egui::util::hash((theme, code, language));
+    let style = egui::util::hash((theme, language));
+
+    // before the memory is locked, the themes are in it
+    let theme_set = Arc::clone(SyntaxThemes::get(ctx).theme_set());
+
+    let mut memory = ctx.memory();
+    let cache = memory.caches.cache::<HighlightCache>();
+
+    if let Some(job) = cache.get(key) {
+        return job;
+    }
+
+    // asked for already, it's styled from the last highlighted code until it arrives
+    if !cache.is_pending(key) {
+        stats.miss();
+        cache.request(ctx, &theme_set, theme, code, language, key, style);
+    }
+
+    cache.interim(style, theme, code)
+}
+
+// like highlight, but it waits for the worker rather than returning the code without all of its colors. For
+// one-off uses like copying the code, a frame shouldn't wait on it
+fn highlight_blocking(
+    ctx: &egui::Context,
+    theme: &CodeTheme,
+    code: &str,
+    language: &str,
+) -> LayoutJob {
+    let job = highlight(ctx, theme, code, language);
+
+    let key = egui::util::hash((theme, code, language));
+
+    // the memory isn't locked while waiting, the worker repaints the ui once it's done
+    let pending = ctx
+        .memory()
+        .caches
+        .cache::<HighlightCache>()
+        .take_pending(key);
+
+    // highlighted already
+    let Some(pending) = pending else {
+        return job;
+    };
+
+    match pending.receiver.recv() {
+        Ok(highlighted) => {
+            ctx.memory().caches.cache::<HighlightCache>().insert(
+                key,
+                pending.style,
+                highlighted.clone(),
+            );
+
+            highlighted
+        }
+
+        Err(_) => job,
+    }
+}
+
+struct HighlightRequest {
+    theme_set: Arc<ThemeSet>,
+    theme: CodeTheme,
+    code: String,
+    language: String,
+    key: u64,
+    // gone once the cache stops waiting for it, e.g. more was typed since
+    wanted: Weak<()>,
+    ctx: egui::Context,
+    reply: Sender<LayoutJob>,
+}
+
+// the layouts highlighted by the worker and those it's still working on, by the hash of the code, theme
+// and language. Like egui's frame cache, the ones which weren't asked for last frame are dropped
+struct HighlightCache {
+    generation: u32,
+    done: HashMap<u64, (u32, LayoutJob)>,
+    pending: Mutex<HashMap<u64, Pending>>,
+    // the last layout highlighted in each theme and language, by their hash
+    latest: HashMap<u64, LayoutJob>,
+    worker: Mutex<Sender<HighlightRequest>>,
+}
+
+struct Pending {
+    generation: u32,
+    style: u64,
+    receiver: Receiver<LayoutJob>,
+    // the worker skips the request once this is dropped
+    _wanted: Arc<()>,
+}
+
+impl Default for HighlightCache {
+    fn default() -> Self {
+        let (tx, rx) = channel::<HighlightRequest>();
+
+        thread::Builder::new()
+            .name("highlighter".to_string())
+            .spawn(move || {
+                let highlighter = Highlighter::default();
+
+                while let Ok(request) = rx.recv() {
+                    // everything asked for while the last one was highlighted, the newest first. Only the
+                    // newest request of the same code is highlighted, and none which aren't waited for
+                    let mut requests = vec![request];
+                    requests.extend(rx.try_iter());
+
+                    let mut seen = HashSet::new();
+
+                    for request in requests.into_iter().rev() {
+                        if !seen.insert(request.key) || request.wanted.strong_count() == 0 {
+                            continue;
+                        }
+
+                        let job = highlighter.highlight(
+                            &request.theme_set,
+                            &request.theme,
+                            &request.code,
+                            &request.language,
+                        );
+
+                        // the editor may be long gone, e.g. the tab was closed
+                        if request.reply.send(job).is_ok() {
+                            request.ctx.request_repaint();
+                        }
+                    }
+                }
+            })
+            .expect("failed to start the highlighter thread");
+
+        Self {
+            generation: 0,
+            done: HashMap::new(),
+            pending: Mutex::new(HashMap::new()),
+            latest: HashMap::new(),
+            worker: Mutex::new(tx),
+        }
+    }
+}
+
+impl HighlightCache {
+    fn get(&mut self, key: u64) -> Option<LayoutJob> {
+        if let Some((generation, job)) = self.done.get_mut(&key) {
+            *generation = self.generation;
+            return Some(job.clone());
+        }
+
+        let mut pending = self.pending.lock();
+        let received = pending.get(&key)?.receiver.try_recv();
+
+        let job = match received {
+            Ok(job) => job,
+            Err(TryRecvError::Empty) => return None,
+
+            // skipped for a newer request of the same code, it's asked for again
+            Err(TryRecvError::Disconnected) => {
+                pending.remove(&key);
+                return None;
+            }
+        };
+
+        let style = pending.remove(&key).map(|pending| pending.style);
+        drop(pending);
+
+        if let Some(style) = style {
+            self.insert(key, style, job.clone());
+        }
+
+        Some(job)
+    }
+
+    fn insert(&mut self, key: u64, style: u64, job: LayoutJob) {
+        self.done.insert(key, (self.generation, job.clone()));
+        self.latest.insert(style, job);
+    }
+
+    // the request still being worked on, to wait for it outside of the cache
+    fn take_pending(&self, key: u64) -> Option<Pending> {
+        self.pending.lock().remove(&key)
+    }
+
+    fn is_pending(&self, key: u64) -> bool {
+        let mut pending = self.pending.lock();
+
+        match pending.get_mut(&key) {
+            Some(pending) => {
+                pending.generation = self.generation;
+                true
+            }
+
+            None => false,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn request(
+        &self,
+        ctx: &egui::Context,
+        theme_set: &Arc<ThemeSet>,
+        theme: &CodeTheme,
+        code: &str,
+        language: &str,
+        key: u64,
+        style: u64,
+    ) {
+        let (tx, rx) = channel();
+        let wanted = Arc::new(());
+
+        let _ = self.worker.lock().send(HighlightRequest {
+            theme_set: Arc::clone(theme_set),
+            theme: theme.clone(),
+            code: code.to_string(),
+            language: language.to_string(),
+            key,
+            wanted: Arc::downgrade(&wanted),
+            ctx: ctx.clone(),
+            reply: tx,
+        });
+
+        self.pending.lock().insert(
+            key,
+            Pending {
+                generation: self.generation,
+                style,
+                receiver: rx,
+                _wanted: wanted,
+            },
+        );
+    }
+
+    // the code styled line by line like the same lines of the last highlighted code, edited lines are
+    // unstyled. Shown while the worker highlights it, so typing doesn't flash the whole code unstyled
+    fn interim(&self, style: u64, theme: &CodeTheme, code: &str) -> LayoutJob {
+        let Some(latest) = self.latest.get(&style) else {
+            return plain(theme, code);
+        };
+
+        let mut styled = HashMap::new();
+        let mut start = 0;
+        let mut sections = latest.sections.iter().peekable();
+
+        for line in latest.text.split_inclusive('\n') {
+            let end = start + line.len();
+            let mut line_sections = vec![];
+
+            while let Some(section) = sections.peek() {
+                if section.byte_range.start >= end {
+                    break;
+                }
+
+                let range = section.byte_range.start.max(start)..section.byte_range.end.min(end);
+                if range.start < range.end {
+                    line_sections.push((range.start - start..range.end - start, &section.format));
+                }
+
+                // a section across the line break goes on into the next line
+                if section.byte_range.end > end {
+                    break;
+                }
+
+                sections.next();
+            }
+
+            styled.entry(line).or_insert(line_sections);
+            start = end;
+        }
+
+        let unstyled = plain_format(theme);
+        let mut job = LayoutJob {
+            text: code.into(),
+            ..Default::default()
+        };
+
+        let mut start = 0;
+        for line in code.split_inclusive('\n') {
+            match styled.get(line) {
+                Some(line_sections) => {
+                    for (range, format) in line_sections {
+                        job.sections.push(LayoutSection {
+                            leading_space: 0.0,
+                            byte_range: start + range.start..start + range.end,
+                            format: (*format).clone(),
+                        });
+                    }
+                }
+
+                None => job.sections.push(LayoutSection {
+                    leading_space: 0.0,
+                    byte_range: start..start + line.len(),
+                    format: unstyled.clone(),
+                }),
+            }
+
+            start += line.len();
+        }
+
+        job
+    }
+}
+
+impl egui::util::cache::CacheTrait for HighlightCache {
+    fn update(&mut self) {
+        let current = self.generation;
+
+        self.done
+            .retain(|_, (generation, _)| *generation == current);
+        self.pending
+            .lock()
+            .retain(|_, pending| pending.generation == current);
+
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    fn len(&self) -> usize {
+        self.done.len()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+// the code in one color, until it's highlighted or if it can't be
+fn plain(theme: &CodeTheme, code: &str) -> LayoutJob {
+    let format = plain_format(theme);
+
+    LayoutJob::simple(code.into(), format.font_id, format.color, f32::INFINITY)
+}
+
+fn plain_format(theme: &CodeTheme) -> TextFormat {
+    TextFormat::simple(
+        egui::FontId::monospace(12.0),
+        if theme.dark_mode {
+            egui::Color32::LIGHT_GRAY
+        } else {
+            egui::Color32::DARK_GRAY
+        },
+    )
+}
+
+// Shown when hovering a theme in the picker
+const PREVIEW_CODE: &str = r#"/// A point in space
+#[derive(Debug)]
+struct Point {
+    x: f32,
+    y: f32,
+}
+
+fn main() {
+    let point = Point { x: 1.0, y: 2.5 };
+    // print it
+    println!("{point:?} {}", 42);
+}
+"#;
+
+/// Shows the line endings of the code, clicking it offers to convert them
+pub fn line_ending_picker(ui: &mut Ui, code: &mut String) {
+    // new lines are typed as LF, whatever the platform
+    let current = LineEnding::detect(code).unwrap_or(LineEnding::Lf);
+
+    ui.menu_button(current.label(), |ui| {
+        for ending in [LineEnding::Lf, LineEnding::Crlf] {
+            if ui
+                .selectable_label(current == ending, ending.label())
+                .clicked()
+            {
+                *code = ending.apply(code).into_owned();
+                ui.close_menu();
+            }
+        }
+    })
+    .response
+    .on_hover_text("Line endings, click to convert");
+}
+
+/// A list of every syntax theme, with a live preview of each on hover.
+/// None is listed first as `default_label`
+pub fn syntax_theme_picker(ui: &mut Ui, selected: &mut Option<SyntectTheme>, default_label: &str) {
+    ui.selectable_value(selected, None, default_label);
+
+    for syntect_theme in SyntectTheme::all(ui.ctx()) {
+        let name = syntect_theme.name().to_string();
+        let code_theme = CodeTheme::from_syntect_theme(ui.ctx(), syntect_theme.clone());
+
+        ui.selectable_value(selected, Some(syntect_theme), name)
+            .on_hover_ui(|ui| code_theme.preview(ui));
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Hash, PartialEq, Deserialize, Serialize)]
+pub enum SyntectTheme {
+    Base16EightiesDark,
+    Base16MochaDark,
+    Base16OceanDark,
+    Base16OceanLight,
+    InspiredGitHub,
+    SolarizedDark,
+    SolarizedLight,
+    // a .tmTheme file loaded with load_custom_themes
+    Custom(String),
+}
+
+impl SyntectTheme {
+    /// The builtin themes, and the custom ones loaded into the context
+    pub fn all(ctx: &egui::Context) -> impl ExactSizeIterator<Item = Self> {
+        let mut themes = vec![
+            Self::Base16EightiesDark,
+            Self::Base16MochaDark,
+            Self::Base16OceanDark,
+            Self::Base16OceanLight,
+            Self::InspiredGitHub,
+            Self::SolarizedDark,
+            Self::SolarizedLight,
+        ];
+
+        let custom = SyntaxThemes::get(ctx);
+        themes.extend(custom.custom_names().iter().cloned().map(Self::Custom));
+
+        themes.into_iter()
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Base16EightiesDark => "Base16 Eighties (dark)",
+            Self::Base16MochaDark => "Base16 Mocha (dark)",
+            Self::Base16OceanDark => "Base16 Ocean (dark)",
+            Self::Base16OceanLight => "Base16 Ocean (light)",
+            Self::InspiredGitHub => "InspiredGitHub (light)",
+            Self::SolarizedDark => "Solarized (dark)",
+            Self::SolarizedLight => "Solarized (light)",
+            Self::Custom(name) => name,
+        }
+    }
+
+    fn syntect_key_name(&self) -> &str {
+        match self {
+            Self::Base16EightiesDark => "base16-eighties.dark",
+            Self::Base16MochaDark => "base16-mocha.dark",
+            Self::Base16OceanDark => "base16-ocean.dark",
+            Self::Base16OceanLight => "base16-ocean.light",
+            Self::InspiredGitHub => "InspiredGitHub",
+            Self::SolarizedDark => "Solarized (dark)",
+            Self::SolarizedLight => "Solarized (light)",
+            Self::Custom(name) => name,
+        }
+    }
+
+    pub fn is_dark(&self, ctx: &egui::Context) -> bool {
+        match self {
+            Self::Base16EightiesDark
+            | Self::Base16MochaDark
+            | Self::Base16OceanDark
+            | Self::SolarizedDark => true,
+
+            Self::Base16OceanLight | Self::InspiredGitHub | Self::SolarizedLight => false,
+
+            // judge by the brightness of the background, dark unless it's known to be light
+            Self::Custom(name) => SyntaxThemes::get(ctx)
+                .theme_set()
+                .themes
+                .get(name)
+                .and_then(|theme| theme.settings.background)
+                .is_none_or(|bg| u32::from(bg.r) + u32::from(bg.g) + u32::from(bg.b) < 384),
+        }
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CodeTheme {
+    dark_mode: bool,
+    syntect_theme: SyntectTheme,
+}
+
+impl Default for CodeTheme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl CodeTheme {
+    pub fn from_style(style: &egui::Style) -> Self {
+        if style.visuals.dark_mode {
+            Self::dark()
+        } else {
+            Self::light()
+        }
+    }
+
+    /// The picked theme, or the default one for the current visuals if there's none
+    pub fn from_picked(ctx: &egui::Context, picked: Option<&SyntectTheme>) -> Self {
+        match picked {
+            Some(theme) => Self::from_syntect_theme(ctx, theme.clone()),
+            None => Self::from_style(&ctx.style()),
+        }
+    }
+
+    pub fn from_syntect_theme(ctx: &egui::Context, syntect_theme: SyntectTheme) -> Self {
+        Self {
+            dark_mode: syntect_theme.is_dark(ctx),
+            syntect_theme,
+        }
+    }
+
+    /// The background color of the theme, black if it has none
+    pub fn background(&self, ctx: &egui::Context) -> Color32 {
+        SyntaxThemes::get(ctx)
+            .theme_set()
+            .themes
+            .get(self.syntect_theme.syntect_key_name())
+            .and_then(|theme| theme.settings.background)
+            .map_or(Color32::BLACK, |bg| Color32::from_rgb(bg.r, bg.g, bg.b))
+    }
+
+    /// Render a small code sample in this theme
+    pub fn preview(&self, ui: &mut Ui) {
+        Frame::none()
+            .fill(self.background(ui.ctx()))
+            .inner_margin(6.0)
+            .rounding(Rounding::same(5.0))
+            .show(ui, |ui| {
+                ui.label(highlight(ui.ctx(), self, PREVIEW_CODE, "rs"));
+            });
+    }
+}
+
+impl CodeTheme {
+    pub fn dark() -> Self {
+        Self {
+            dark_mode: true,
+            syntect_theme: SyntectTheme::Base16MochaDark,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            dark_mode: false,
+            syntect_theme: SyntectTheme::SolarizedLight,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+
+// how many highlighted codes are kept to start edits from, e.g. one for each tab being shown
+const RECENT_HIGHLIGHTS: usize = 8;
+
+thread_local! {
+    // the lines of the code highlighted lately, an edit only highlights the lines which differ from the
+    // code sharing the most lines with it. The parse states can't be sent to other threads, so they
+    // can't be kept in egui's memory
+    static RECENT: RefCell<Vec<HighlightedLines>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Highlighter {
+    ps: syntect::parsing::SyntaxSet,
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self {
+            ps: syntect::parsing::SyntaxSet::load_defaults_newlines(),
+        }
+    }
+}
+
+// code highlighted line by line
+struct HighlightedLines {
+    theme: String,
+    language: String,
+    lines: Vec<HighlightedLine>,
+}
+
+struct HighlightedLine {
+    // with its line ending
+    text: String,
+    // the state syntect is in after the line, which the next line starts from
+    parse: ParseState,
+    highlight: HighlightState,
+    // byte ranges from the start of the line
+    sections: Vec<(std::ops::Range<usize>, TextFormat)>,
+}
+
+impl Highlighter {
+    fn highlight(
+        &self,
+        theme_set: &ThemeSet,
+        theme: &CodeTheme,
+        code: &str,
+        lang: &str,
+    ) -> LayoutJob {
+        self.highlight_impl(theme_set, theme, code, lang)
+            .unwrap_or_else(|| plain(theme, code))
+    }
+
+    fn highlight_impl(
+        &self,
+        theme_set: &ThemeSet,
+        theme: &CodeTheme,
+        text: &str,
+        language: &str,
+    ) -> Option<LayoutJob> {
+        use syntect::parsing::ScopeStack;
+        use syntect::util::LinesWithEndings;
+
+        let syntax = self
+            .ps
+            .find_syntax_by_name(language)
+            .or_else(|| self.ps.find_syntax_by_extension(language))?;
+
+        // custom themes may have been removed from the themes directory
+        let theme_name = theme.syntect_theme.syntect_key_name().to_string();
+        let syntect_theme = theme_set.themes.get(&theme_name)?;
+        let highlighter = syntect::highlighting::Highlighter::new(syntect_theme);
+
+        let new_lines = LinesWithEndings::from(text).collect::<Vec<_>>();
+
+        let shared = |lines: &HighlightedLines| {
+            lines
+                .lines
+                .iter()
+                .zip(&new_lines)
+                .take_while(|(old, new)| old.text == **new)
+                .count()
+        };
+
+        let mut old_lines = RECENT.with(|recent| {
+            let mut recent = recent.borrow_mut();
+
+            let base = recent
+                .iter()
+                .enumerate()
+                .filter(|(_, lines)| lines.theme == theme_name && lines.language == language)
+                .max_by_key(|(_, lines)| shared(lines))
+                .map(|(i, _)| i);
+
+            base.map_or_else(Vec::new, |i| recent.remove(i).lines)
+        });
+
+        let initial = (
+            ParseState::new(syntax),
+            HighlightState::new(&highlighter, ScopeStack::new()),
+        );
+
+        // the lines before the first edited one are kept as they are
+        let prefix = old_lines
+            .iter()
+            .zip(&new_lines)
+            .take_while(|(old, new)| old.text == **new)
+            .count();
+
+        // so are those after the last edited one, once the state they start in is the same as before
+        let suffix = old_lines[prefix..]
+            .iter()
+            .rev()
+            .zip(new_lines[prefix..].iter().rev())
+            .take_while(|(old, new)| old.text == **new)
+            .count();
+
+        let (mut parse, mut highlight) = match prefix.checked_sub(1) {
+            Some(last) => (
+                old_lines[last].parse.clone(),
+                old_lines[last].highlight.clone(),
+            ),
+            None => initial.clone(),
+        };
+
+        let mut edited = vec![];
+        let mut resumed = None;
+
+        for (i, line) in new_lines.iter().enumerate().skip(prefix) {
+            if i >= new_lines.len() - suffix {
+                let old = i + old_lines.len() - new_lines.len();
+                let before = match old.checked_sub(1) {
+                    Some(previous) => (&old_lines[previous].parse, &old_lines[previous].highlight),
+                    None => (&initial.0, &initial.1),
+                };
+
+                if before == (&parse, &highlight) {
+                    resumed = Some(old);
+                    break;
+                }
+            }
+
+            let ops = parse.parse_line(line, &self.ps).ok()?;
+            let sections = HighlightIterator::new(&mut highlight, &ops, line, &highlighter)
+                .map(|(style, range)| (as_byte_range(line, range), text_format(style)))
+                .collect();
+
+            edited.push(HighlightedLine {
+                text: line.to_string(),
+                parse: parse.clone(),
+                highlight: highlight.clone(),
+                sections,
+            });
+        }
+
+        let rest = resumed.map_or_else(Vec::new, |old| old_lines.split_off(old));
+        old_lines.truncate(prefix);
+
+        let mut lines = old_lines;
+        lines.extend(edited);
+        lines.extend(rest);
+
+        let mut job = LayoutJob {
+            text: text.into(),
+            ..Default::default()
+        };
+
+        let mut start = 0;
+        for line in &lines {
+            for (range, format) in &line.sections {
+                job.sections.push(LayoutSection {
+                    leading_space: 0.0,
+                    byte_range: start + range.start..start + range.end,
+                    format: format.clone(),
+                });
+            }
+
+            start += line.text.len();
+        }
+
+        RECENT.with(|recent| {
+            let mut recent = recent.borrow_mut();
+
+            recent.insert(
+                0,
+                HighlightedLines {
+                    theme: theme_name,
+                    language: language.to_string(),
+                    lines,
+                },
+            );
+            recent.truncate(RECENT_HIGHLIGHTS);
+        });
+
+        Some(job)
+    }
+}
+
+fn text_format(style: Style) -> TextFormat {
+    let fg = style.foreground;
+    let text_color = egui::Color32::from_rgb(fg.r, fg.g, fg.b);
+    let italics = style.font_style.contains(FontStyle::ITALIC);
+    let underline = style.font_style.contains(FontStyle::ITALIC);
+    let underline = if underline {
+        egui::Stroke::new(1.0, text_color)
+    } else {
+        egui::Stroke::NONE
+    };
+
+    TextFormat {
+        font_id: egui::FontId::monospace(12.0),
+        color: text_color,
+        italics,
+        underline,
+        ..Default::default()
+    }
+}
+
+fn as_byte_range(whole: &str, range: &str) -> std::ops::Range<usize> {
+    let whole_start = whole.as_ptr() as usize;
+    let range_start = range.as_ptr() as usize;
+    assert!(whole_start <= range_start);
+    assert!(range_start + range.len() <= whole_start + whole.len());
+    let offset = range_start - whole_start;
+    offset..(offset + range.len())
+}
+
+/// A syntax highlighted code editor. It's serializable, to keep the code across restarts
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CodeEditor {
+    language: String,
+    pub code: String,
+    #[serde(default)]
+    read_only: bool,
+    // a line to scroll to the next time it's shown
+    #[serde(skip)]
+    jump: Option<usize>,
+}
+
+impl Default for CodeEditor {
+    fn default() -> Self {
+        Self {
+            language: "rs".into(),
+            read_only: false,
+            jump: None,
+            code: r#"// How to write scratches
+//
+// Simply write `use some_crate;` anywhere, and the dependency will get
+// inferred and included automatically at the latest version!
+// This creates a simple depdendency requirement like so:
+//     serde = "*"
+//
+// If you have more complex requirements (such as features, or a specific
+// version), at the top of your file, use //# to specify custom
+// dependencies. All //# must be the very first lines in order to be
+// recognized.
+//# serde = { version = "1.0.152", features = ["derive"] }
+//
+// You can also include any extra custom cargo.toml with //>
+// All //> must be in one block, and either at the top of the file or after
+// any //# . Once the last consecutive //> is found,
+// no more //> blocks will work.
+//> [profile.dev]
+//> opt-level = 1
+//
+
+use rand::Rng;
+
+fn main() {
+    let mut rng = rand::thread_rng();
+
+    let n1: u8 = rng.gen();
+    let n2: u16 = rng.gen();
+    println!("Random u8: {}", n1);
+    println!("Random u16: {}", n2);
+    println!("Random u32: {}", rng.gen::<u32>());
+    println!("Random i32: {}", rng.gen::<i32>());
+    println!("Random float: {}", rng.gen::<f64>());
+}
+"#
+            .into(),
+        }
+    }
+}
+
+impl CodeEditor {
+    pub fn new(code: String) -> Self {
+        Self {
+            code,
+            ..Default::default()
+        }
+    }
+
+    /// Code which can be looked at but not edited, highlighted as the language of the file extension
+    pub fn read_only(code: String, language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            code,
+            read_only: true,
+            jump: None,
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Scroll to a line, counted from 0, the next time the editor is shown
+    pub fn jump_to_line(&mut self, line: usize) {
+        self.jump = Some(line);
+    }
+
+    /// The char index the cursor is at, None if the editor was never focused
+    pub fn cursor(ctx: &egui::Context, id: Id) -> Option<usize> {
+        let state = TextEditState::load(ctx, id)?;
+
+        Some(state.ccursor_range()?.primary.index)
+    }
+
+    /// Move the cursor to a char index, e.g. after inserting a completion
+    pub fn set_cursor(ctx: &egui::Context, id: Id, index: usize) {
+        let mut state = TextEditState::load(ctx, id).unwrap_or_default();
+        state.set_ccursor_range(Some(CCursorRange::one(CCursor::new(index))));
+        state.store(ctx, id);
+    }
+
+    /// Where the cursor was drawn last frame, e.g. to show a popup below it
+    pub fn cursor_rect(ctx: &egui::Context, id: Id) -> Option<Rect> {
+        ctx.data().get_temp(id.with("cursor_rect"))
+    }
+
+    /// The char index the pointer was over last frame, None if it wasn't over the code
+    pub fn hovered(ctx: &egui::Context, id: Id) -> Option<usize> {
+        ctx.data().get_temp::<Option<usize>>(id.with("hovered"))?
+    }
+
+    /// The `:` command typed into the editor since it was last asked, with [`EditorSettings::vim`] on
+    pub fn vim_command(ctx: &egui::Context, id: Id) -> Option<VimCommand> {
+        let command_id = id.with("vim_command");
+
+        let command = ctx.data().get_temp::<VimCommand>(command_id)?;
+        ctx.data().remove::<VimCommand>(command_id);
+
+        Some(command)
+    }
+
+    /// The code picked "Copy as rich text" on in the editor's context menu since it was last asked. It's up
+    /// to the caller to put it on the clipboard, egui's clipboard only takes plain text
+    pub fn rich_copy(ctx: &egui::Context, id: Id) -> Option<RichCopy> {
+        let copy_id = id.with("rich_copy");
+
+        let copy = ctx.data().get_temp::<RichCopy>(copy_id)?;
+        ctx.data().remove::<RichCopy>(copy_id);
+
+        Some(copy)
+    }
+
+    /// Fill the space left in the ui with the editor, scrolled to the offset. Returns the offset it was
+    /// scrolled to, to pass in next frame. `id` is the id of the text edit, e.g. for [`Self::cursor`].
+    /// Zooming is up to the caller, see [`zoom_input`] and [`EditorSettings::zoomed`]
+    pub fn show(
+        &mut self,
+        id: Id,
+        ui: &mut egui::Ui,
+        scroll_offset: Vec2,
+        settings: &EditorSettings,
+        theme: &CodeTheme,
+    ) -> Vec2 {
+        let Self {
+            language,
+            code,
+            read_only,
+            jump,
+        } = self;
+
+        let jump = jump.take();
+
+        let frame_rect = ui.max_rect().shrink(6.0);
+        let mut code_rect = frame_rect.shrink(5.0);
+
+        let minimap_rect = settings.minimap.then(|| {
+            let rect = Rect::from_min_max(
+                code_rect.right_top() - vec2(MINIMAP_WIDTH, 0.0),
+                code_rect.right_bottom(),
+            );
+            code_rect.max.x = rect.min.x - 5.0;
+
+            rect
+        });
+
+        let font_id = settings.font_id();
+
+        let focused = ui.memory().has_focus(id);
+
+        // ctrl+] where the platform passes it on as text, ctrl+shift+m everywhere else
+        if focused && jump_to_bracket_pressed(ui) {
+            let chars = code.chars().collect::<Vec<_>>();
+            let matching =
+                Self::cursor(ui.ctx(), id).and_then(|cursor| matching_bracket(&chars, cursor));
+
+            if let Some((_, to)) = matching {
+                Self::set_cursor(ui.ctx(), id, to);
+            }
+        }
+
+        // the cursor as of last frame, the text edit hasn't moved it yet
+        let bracket_cursor = (settings.highlight_brackets && focused)
+            .then(|| Self::cursor(ui.ctx(), id))
+            .flatten();
+
+        let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
+            let mut layout_job = highlight(ui.ctx(), theme, string, language);
+            if let Some(cursor) = bracket_cursor {
+                highlight_brackets(&mut layout_job, cursor);
+            }
+            settings.apply(&mut layout_job, wrap_width);
+            ui.fonts().layout_job(layout_job)
+        };
+
+        let Rect { max, .. } = ui.max_rect();
+
+        ui.allocate_space(vec2(max.x, max.y));
+
+        ui.painter().rect(
+            frame_rect,
+            Rounding::same(5.0),
+            Color32::BLACK,
+            Stroke::NONE,
+        );
+
+        let mut frame_ui = ui.child_ui(code_rect, Layout::default());
+
+        // get how many rows it takes to fill up our max rect
+        let row_height = ui.fonts().row_height(&font_id);
+        let rows = ((code_rect.height() - 5.0) / row_height).floor() as usize;
+
+        let wrap_offer_id = id.with("wrap_offer");
+
+        if (settings.normalize_paste || settings.smart_paste) && focused {
+            let ending = match LineEnding::detect(code) {
+                Some(LineEnding::Crlf) => LineEnding::Crlf,
+                _ => LineEnding::Lf,
+            };
+
+            let mut offer = false;
+
+            for event in &mut ui.ctx().input_mut().events {
+                if let Event::Paste(text) = event {
+                    if settings.smart_paste {
+                        *text = paste::clean(text);
+                        offer |= paste::needs_main(text);
+                    }
+
+                    if settings.normalize_paste {
+                        *text = ending.apply(text).into_owned();
+                    }
+                }
+            }
+
+            if offer {
+                ui.data().insert_temp(wrap_offer_id, true);
+            }
+        }
+
+        let vim = (settings.vim && !*read_only).then(|| vim_keys(ui, id, code));
+
+        let mut source;
+        let text: &mut dyn TextBuffer = if *read_only {
+            source = code.as_str();
+            &mut source
+        } else {
+            code
+        };
+
+        let text_widget = egui::TextEdit::multiline(text)
+            .font(font_id) // for cursor height
+            .code_editor()
+            // remove the frame and draw our own
+            .frame(false)
+            .desired_width(f32::INFINITY)
+            .margin(vec2(2.0, 2.0))
+            .layouter(&mut layouter)
+            .cursor_at_end(false)
+            .id(id)
+            .desired_rows(rows);
+
+        // without word wrap long lines scroll sideways instead
+        let scroll_res = egui::ScrollArea::new([!settings.word_wrap, true])
+            .scroll_offset(scroll_offset)
+            .show(&mut frame_ui, |ui| {
+                let output = text_widget.show(ui);
+                let galley = &output.galley;
+                let offset = output.text_draw_pos.to_vec2();
+
+                // for popups next to the cursor and tooltips on the code
+                if let Some(range) = output.cursor_range {
+                    let rect = galley.pos_from_cursor(&range.primary).translate(offset);
+                    ui.data().insert_temp(id.with("cursor_rect"), rect);
+
+                    // the brackets were laid out around where the cursor was before it moved
+                    let moved =
+                        bracket_cursor.filter(|&cursor| cursor != range.primary.ccursor.index);
+                    if moved.is_some() {
+                        ui.ctx().request_repaint();
+                    }
+                }
+
+                let hovered = output.response.hover_pos().map(|pos| {
+                    galley
+                        .cursor_from_pos(pos - output.text_draw_pos)
+                        .ccursor
+                        .index
+                });
+                ui.data().insert_temp(id.with("hovered"), hovered);
+
+                if let Some(line) = jump {
+                    let index = galley
+                        .job
+                        .text
+                        .split_inclusive('\n')
+                        .take(line)
+                        .map(|line| line.chars().count())
+                        .sum();
+
+                    let cursor = galley.from_ccursor(CCursor::new(index));
+                    let rect = galley
+                        .pos_from_cursor(&cursor)
+                        .translate(output.response.rect.min.to_vec2());
+
+                    ui.scroll_to_rect(rect, Some(Align::TOP));
+                }
+
+                // the selection, or all of the code, with its colors
+                output.response.context_menu(|ui| {
+                    if ui.button("Copy as rich text").clicked() {
+                        let copy = rich_copy(ui.ctx(), id, theme, &galley.job.text, language);
+                        ui.data().insert_temp(id.with("rich_copy"), copy);
+                        ui.close_menu();
+                    }
+                });
+            });
+
+        let offered = ui
+            .data()
+            .get_temp::<bool>(wrap_offer_id)
+            .unwrap_or_default();
+        if offered && !*read_only {
+            // it's withdrawn once the code can run as it is, e.g. the paste was undone
+            if settings.smart_paste && paste::needs_main(code) {
+                let mut offer_ui =
+                    ui.child_ui(code_rect.shrink(4.0), Layout::right_to_left(Align::Min));
+
+                if offer_ui.small_button("✖").clicked() {
+                    ui.data().remove::<bool>(wrap_offer_id);
+                }
+
+                if offer_ui
+                    .button("Wrap in fn main()")
+                    .on_hover_text("The pasted statements can't run outside of a function")
+                    .clicked()
+                {
+                    *code = paste::wrap_in_main(code);
+                    ui.data().remove::<bool>(wrap_offer_id);
+                }
+            } else {
+                ui.data().remove::<bool>(wrap_offer_id);
+            }
+        }
+
+        let mut offset = scroll_res.state.offset;
+
+        if let Some(rect) = minimap_rect {
+            let job = highlight(ui.ctx(), theme, code, language);
+            let viewport = scroll_res.inner_rect.height();
+
+            if let Some(y) = minimap(
+                ui,
+                id,
+                rect,
+                &job,
+                offset.y,
+                viewport,
+                scroll_res.content_size.y,
+            ) {
+                offset.y = y;
+            }
+        }
+
+        if let Some(vim) = vim {
+            ui.painter().text(
+                frame_rect.right_bottom() - vec2(8.0, 4.0),
+                Align2::RIGHT_BOTTOM,
+                vim.status(),
+                FontId::monospace(settings.font_size),
+                ui.visuals().weak_text_color(),
+            );
+        }
+
+        offset
+    }
+}
+
+/// Code copied with its highlighting, see [`CodeEditor::rich_copy`]. There's no rtf of it: the clipboard
+/// can't take it on every platform, and Word, Outlook, Slack and mail clients paste the html with its colors
+#[derive(Debug, Clone)]
+pub struct RichCopy {
+    /// A `<pre>` in the colors of the theme
+    pub html: String,
+    /// The same code as plain text, for where html can't be pasted
+    pub text: String,
+}
+
+// the selected code, or all of it if there's no selection, highlighted as it's shown
+fn rich_copy(
+    ctx: &egui::Context,
+    id: Id,
+    theme: &CodeTheme,
+    code: &str,
+    language: &str,
+) -> RichCopy {
+    let selection = TextEditState::load(ctx, id)
+        .and_then(|state| state.ccursor_range())
+        .map(|range| {
+            let [start, end] = range.sorted();
+            let byte = |index: usize| {
+                code.char_indices()
+                    .nth(index)
+                    .map_or(code.len(), |(i, _)| i)
+            };
+
+            byte(start.index)..byte(end.index)
+        })
+        .filter(|range| !range.is_empty());
+
+    let range = selection.unwrap_or(0..code.len());
+
+    // the code on screen may still be highlighted while the edited lines are shown unstyled
+    let job = highlight_blocking(ctx, theme, code, language);
+
+    RichCopy {
+        html: export::layout_job_html(&job, range.clone(), theme.background(ctx)),
+        text: code[range].to_string(),
+    }
+}
+
+// draw each run of chars of the code as a line in the color it's highlighted in, with the part scrolled to
+// framed. Returns the offset to scroll to if it was clicked or dragged
+fn minimap(
+    ui: &Ui,
+    id: Id,
+    rect: Rect,
+    job: &LayoutJob,
+    offset: f32,
+    viewport: f32,
+    content_height: f32,
+) -> Option<f32> {
+    let lines = job.text.lines().count().max(1);
+    let line_height = MINIMAP_LINE_HEIGHT.min(rect.height() / lines as f32);
+    let height = line_height * lines as f32;
+
+    let painter = ui.painter_at(rect);
+    let mut shapes = vec![];
+
+    let mut line = 0;
+    let mut column = 0;
+
+    for section in &job.sections {
+        let color = section.format.color.linear_multiply(0.7);
+        let mut run: Option<usize> = None;
+
+        for c in job.text[section.byte_range.clone()].chars().chain([' ']) {
+            match (c.is_whitespace(), run) {
+                (false, None) => run = Some(column),
+
+                (true, Some(start)) => {
+                    let min = rect.min
+                        + vec2(start as f32 * MINIMAP_CHAR_WIDTH, line as f32 * line_height);
+                    let size = vec2(
+                        (column - start) as f32 * MINIMAP_CHAR_WIDTH,
+                        line_height.max(1.0),
+                    );
+
+                    shapes.push(Shape::rect_filled(
+                        Rect::from_min_size(min, size),
+                        0.0,
+                        color,
+                    ));
+                    run = None;
+                }
+
+                _ => (),
+            }
+
+            if c == '\n' {
+                line += 1;
+                column = 0;
+            } else {
+                column += 1;
+            }
+        }
+
+        // the space closing the last run isn't part of the code
+        column -= 1;
+    }
+
+    painter.extend(shapes);
+
+    if content_height <= 0.0 {
+        return None;
+    }
+
+    let scale = height / content_height;
+    let frame = Rect::from_min_size(
+        rect.min + vec2(0.0, offset * scale),
+        vec2(rect.width(), (viewport * scale).min(height)),
+    );
+
+    painter.rect_filled(frame, 0.0, Color32::from_additive_luminance(20));
+
+    let response = ui.interact(rect, id.with("minimap"), Sense::click_and_drag());
+    let pos = response.interact_pointer_pos()?;
+
+    // the frame is centered on the pointer
+    let max = (content_height - viewport).max(0.0);
+    Some(((pos.y - rect.min.y) / scale - viewport / 2.0).clamp(0.0, max))
+}
+
+// tint the block around the cursor, then the bracket next to it and its match over that
+fn highlight_brackets(job: &mut LayoutJob, cursor: usize) {
+    let chars = job.text.chars().collect::<Vec<_>>();
+    let bytes = job
+        .text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([job.text.len()])
+        .collect::<Vec<_>>();
+
+    if let Some((open, close)) = enclosing_block(&chars, cursor) {
+        tint(job, bytes[open]..bytes[close + 1], BLOCK_TINT);
+    }
+
+    if let Some((from, to)) = matching_bracket(&chars, cursor) {
+        tint(job, bytes[from]..bytes[from + 1], BRACKET_TINT);
+        tint(job, bytes[to]..bytes[to + 1], BRACKET_TINT);
+    }
+}
+
+// give a byte range of the job a background, splitting the sections it starts or ends in
+fn tint(job: &mut LayoutJob, range: std::ops::Range<usize>, color: Color32) {
+    let mut sections = Vec::with_capacity(job.sections.len() + 2);
+
+    for section in job.sections.drain(..) {
+        let whole = section.byte_range.clone();
+        let start = range.start.clamp(whole.start, whole.end);
+        let end = range.end.clamp(whole.start, whole.end);
+
+        let parts = [
+            (whole.start..start, false),
+            (start..end, true),
+            (end..whole.end, false),
+        ];
+        let mut first = true;
+
+        for (byte_range, tinted) in parts.into_iter().filter(|(part, _)| !part.is_empty()) {
+            let mut part = section.clone();
+            part.byte_range = byte_range;
+
+            // the space before the section goes before its first part only
+            if !first {
+                part.leading_space = 0.0;
+            }
+
+            if tinted {
+                part.format.background = color;
+            }
+
+            first = false;
+            sections.push(part);
+        }
+    }
+
+    job.sections = sections;
+}
+
+fn jump_to_bracket_pressed(ui: &Ui) -> bool {
+    let mut input = ui.ctx().input_mut();
+
+    let typed = input.events.iter().position(|event| {
+        matches!(event, Event::Text(text) if text == "]") && input.modifiers.command
+    });
+
+    if let Some(index) = typed {
+        input.events.remove(index);
+        return true;
+    }
+
+    input.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::M)
+}
+
+// the modal state of an editor, with its cursor and the selection showing it, a selection alone can't
+// tell which end the cursor is on
+#[derive(Clone, Default)]
+struct VimState {
+    vim: Vim,
+    cursor: usize,
+    shown: Option<CCursorRange>,
+}
+
+// feed the keys to vim before the text edit sees them, it gets what's typed in insert mode. Returns the
+// state for the status line
+fn vim_keys(ui: &Ui, id: Id, code: &mut String) -> Vim {
+    let state_id = id.with("vim");
+    let focus_id = id.with("vim_focus");
+
+    let mut state = ui.data().get_temp::<VimState>(state_id).unwrap_or_default();
+    let had_focus = ui.data().get_temp::<bool>(focus_id).unwrap_or_default();
+
+    // escape took the focus away before any widget ran, unless there's nothing to cancel it's vim's
+    if had_focus && !state.vim.is_idle() && ui.input().key_pressed(Key::Escape) {
+        ui.memory().request_focus(id);
+    }
+
+    let focused = ui.memory().has_focus(id);
+    ui.data().insert_temp(focus_id, focused);
+
+    if !focused {
+        return state.vim;
+    }
+
+    let range = TextEditState::load(ui.ctx(), id).and_then(|state| state.ccursor_range());
+
+    let mut cursor = match range {
+        Some(range) if Some(range) == state.shown => state.cursor,
+        // e.g. clicked somewhere, or typed in insert mode
+        Some(range) => range.primary.index,
+        None => 0,
+    };
+
+    let events = std::mem::take(&mut ui.ctx().input_mut().events);
+    let mut kept = Vec::with_capacity(events.len());
+    let was_insert = state.vim.mode == VimMode::Insert;
+
+    for event in events {
+        let insert = state.vim.mode == VimMode::Insert;
+
+        match &event {
+            Event::Text(text) => {
+                let mut typed = String::new();
+
+                for c in text.chars() {
+                    match vim_key(&mut state, VimKey::Char(c), code, &mut cursor, ui, id) {
+                        VimOutcome::Ignored => typed.push(c),
+                        // the text edit keeps the history
+                        VimOutcome::Undo => kept.push(undo()),
+                        _ => (),
+                    }
+                }
+
+                if !typed.is_empty() {
+                    kept.push(Event::Text(typed));
+                }
+            }
+
+            Event::Key {
+                key: key @ (Key::Escape | Key::Enter | Key::Backspace),
+                pressed: true,
+                modifiers,
+            } if modifiers.is_none() => {
+                let key = match key {
+                    Key::Escape => VimKey::Escape,
+                    Key::Enter => VimKey::Enter,
+                    _ => VimKey::Backspace,
+                };
+
+                if vim_key(&mut state, key, code, &mut cursor, ui, id) == VimOutcome::Ignored {
+                    kept.push(event);
+                }
+            }
+
+            // these edit the text, which only insert mode does
+            Event::Key {
+                key: Key::Enter | Key::Backspace | Key::Delete | Key::Tab,
+                ..
+            } if !insert => (),
+
+            _ => kept.push(event),
+        }
+    }
+
+    ui.ctx().input_mut().events = kept;
+
+    // typing and selecting with the mouse in insert mode is the text edit's
+    if was_insert && state.vim.mode == VimMode::Insert {
+        ui.data().insert_temp(state_id, state.clone());
+        return state.vim;
+    }
+
+    let chars = code.chars().collect::<Vec<_>>();
+    cursor = cursor.min(chars.len());
+
+    let range = match state.vim.selection(cursor, chars.len()) {
+        Some((start, end)) => CCursorRange::two(CCursor::new(start), CCursor::new(end)),
+
+        // a block on the char under the cursor, like vim's
+        None if state.vim.mode == VimMode::Normal
+            && chars.get(cursor).is_some_and(|&c| c != '\n') =>
+        {
+            CCursorRange::two(CCursor::new(cursor), CCursor::new(cursor + 1))
+        }
+
+        None => CCursorRange::one(CCursor::new(cursor)),
+    };
+
+    if let Some(mut edit_state) = TextEditState::load(ui.ctx(), id) {
+        edit_state.set_ccursor_range(Some(range));
+        edit_state.store(ui.ctx(), id);
+    }
+
+    state.cursor = cursor;
+    state.shown = Some(range);
+
+    ui.data().insert_temp(state_id, state.clone());
+
+    state.vim
+}
+
+fn vim_key(
+    state: &mut VimState,
+    key: VimKey,
+    code: &mut String,
+    cursor: &mut usize,
+    ui: &Ui,
+    id: Id,
+) -> VimOutcome {
+    let outcome = state.vim.key(key, code, cursor);
+
+    if let VimOutcome::Command(command) = outcome {
+        ui.data().insert_temp(id.with("vim_command"), command);
+    }
+
+    outcome
+}
+
+fn undo() -> Event {
+    Event::Key {
+        key: Key::Z,
+        pressed: true,
+        modifiers: Modifiers::COMMAND,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // highlighted on its own, without any lines of earlier code to start from
+    fn fresh(
+        highlighter: &Highlighter,
+        theme_set: &ThemeSet,
+        theme: &CodeTheme,
+        code: &str,
+    ) -> LayoutJob {
+        RECENT.with(|recent| recent.borrow_mut().clear());
+        highlighter.highlight(theme_set, theme, code, "rs")
+    }
+
+    #[test]
+    fn blocking_highlight() {
+        let ctx = egui::Context::default();
+        let theme = CodeTheme::dark();
+
+        // waited for rather than laid out in one color
+        let job = highlight_blocking(&ctx, &theme, "fn main() {}\n", "rs");
+        assert!(job.sections.len() > 1);
+
+        // and cached like any other
+        assert_eq!(
+            highlight(&ctx, &theme, "fn main() {}\n", "rs").sections,
+            job.sections
+        );
+    }
+
+    #[test]
+    fn interim_keeps_unchanged_lines() {
+        let highlighter = Highlighter::default();
+        let theme_set = ThemeSet::load_defaults();
+        let theme = CodeTheme::dark();
+
+        let code = "fn main() {\n    let x = 1;\n}\n";
+        let edited = "fn main() {\n    let x = 12;\n}\n";
+
+        let mut cache = HighlightCache::default();
+        cache
+            .latest
+            .insert(0, fresh(&highlighter, &theme_set, &theme, code));
+
+        let job = cache.interim(0, &theme, edited);
+        let highlighted = fresh(&highlighter, &theme_set, &theme, edited);
+
+        let sections = |job: &LayoutJob, lines: std::ops::Range<usize>| {
+            job.sections
+                .iter()
+                .filter(|section| lines.contains(&section.byte_range.start))
+                .map(|section| (section.byte_range.clone(), section.format.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        // the first and last lines are as they're highlighted, the edited one is unstyled until then
+        assert_eq!(job.text, edited);
+        assert_eq!(sections(&job, 0..12), sections(&highlighted, 0..12));
+        assert_eq!(sections(&job, 28..30), sections(&highlighted, 28..30));
+        assert_eq!(sections(&job, 12..28), [(12..28, plain_format(&theme))]);
+
+        // nothing to take the styles from in another theme
+        assert_eq!(cache.interim(1, &theme, edited).sections.len(), 1);
+    }
+
+    #[test]
+    fn incremental_highlighting() {
+        let highlighter = Highlighter::default();
+        let theme_set = ThemeSet::load_defaults();
+        let theme = CodeTheme::dark();
+
+        let code = "fn main() {\n    let x = 1;\n    println!(\"{x}\");\n}\n";
+        let edits = [
+            // a line changed in the middle
+            "fn main() {\n    let x = 2;\n    println!(\"{x}\");\n}\n",
+            // a string left open changes how every line after it is highlighted
+            "fn main() {\n    let x = \"2;\n    println!(\"{x}\");\n}\n",
+            "fn main() {\n    let x = 2;\n    println!(\"{x}\");\n}\n",
+            // lines added and removed
+            "fn main() {\n    let x = 2;\n\n    let y = 3;\n    println!(\"{x}\");\n}\n",
+            "fn main() {\n    println!(\"{x}\");\n}\n",
+            "",
+        ];
+
+        RECENT.with(|recent| recent.borrow_mut().clear());
+        highlighter.highlight(&theme_set, &theme, code, "rs");
+
+        for edit in edits {
+            let incremental = highlighter.highlight(&theme_set, &theme, edit, "rs");
+
+            assert_eq!(
+                incremental,
+                fresh(&highlighter, &theme_set, &theme, edit),
+                "{edit:?}"
+            );
+
+            // start the next edit from this one
+            highlighter.highlight(&theme_set, &theme, edit, "rs");
+        }
+    }
+}
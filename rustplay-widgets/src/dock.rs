@@ -0,0 +1,401 @@
+//! Tabs in an [`egui_dock`] tree, with what egui_dock leaves to the app: renaming a tab by clicking it once
+//! more or double clicking it, double clicking the empty part of a tab bar, and dragging a tab out of the
+//! window. What the tabs show is up to the [`TabViewer`] passed to [`DockView::show`]
+
+use std::time::Duration;
+
+use egui::{
+    pos2, vec2, Area, Color32, Id, Key, Order, PointerButton, Pos2, Rect, TextEdit, TextStyle,
+};
+use egui_dock::{DockArea, Node, NodeIndex, Style, TabViewer, Tree};
+
+// temp memory key of the leaf whose tab bar was pressed, in case a tab is dragged out of the window
+const DRAGGED_LEAF: &str = "dock_dragged_leaf";
+// temp memory key of the tab being renamed in its tab bar, and the name typed so far
+const RENAMING: &str = "dock_renaming";
+// temp memory key of the active tab pressed on, and when the click was let go
+const CLICKED_ACTIVE: &str = "dock_clicked_active";
+// how long a click on the active tab waits for a second one before renaming, egui's double click delay
+const CLICK_RENAME_DELAY: f64 = 0.3;
+// egui_dock's padding either side of a tab's title, and between the title and the close button
+const TAB_PADDING: f32 = 8.0;
+const TAB_CLOSE_GAP: f32 = 5.0;
+
+/// A tab of a [`DockView`]
+pub trait DockTab {
+    /// Unique among the tabs of the tree
+    fn id(&self) -> Id;
+    /// The text in the tab bar, the same the [`TabViewer`] shows
+    fn title(&self) -> String;
+    /// The name a rename starts out with, no two tabs can be renamed to the same one
+    fn name(&self) -> &str;
+    fn set_name(&mut self, name: String);
+}
+
+/// What happened in the tab bars this frame that the app acts on
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DockResponse {
+    /// A tab dragged out of the window and dropped there, e.g. to open it in a window of its own
+    pub dropped_outside: Option<Id>,
+    /// The active tab clicked once more, to be renamed like a file in a file manager. See [`DockView::rename`]
+    pub clicked_active: Option<Id>,
+}
+
+/// Where a double click in a tab bar landed, see [`DockView::double_clicked`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoubleClicked {
+    Tab(Id),
+    /// The empty part of the tab bar of the leaf, after the add button
+    TabBar(NodeIndex),
+}
+
+pub struct DockView;
+
+impl DockView {
+    /// Show the tabs of the tree, the name typed over a tab being renamed included
+    pub fn show<T: DockTab>(
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        tree: &mut Tree<T>,
+        style: Style,
+        viewer: &mut impl TabViewer<Tab = T>,
+    ) -> DockResponse {
+        DockArea::new(tree).style(style).show_inside(ui, viewer);
+
+        let response = DockResponse {
+            dropped_outside: Self::dropped_outside(ctx, tree),
+            clicked_active: Self::clicked_active(ctx, tree),
+        };
+
+        Self::show_rename(ctx, tree);
+
+        response
+    }
+
+    /// Edit the name of a tab in its tab bar, starting out with the given one. Enter or clicking elsewhere
+    /// keeps it, escape leaves the name as it was
+    pub fn rename(ctx: &egui::Context, id: Id, name: String) {
+        ctx.data().insert_temp(Id::new(RENAMING), (id, name));
+    }
+
+    /// What the pointer is on in a tab bar, if it was double clicked this frame. None if it's not on one,
+    /// or it's on the add button
+    pub fn double_clicked<T: DockTab>(
+        ctx: &egui::Context,
+        tree: &Tree<T>,
+    ) -> Option<DoubleClicked> {
+        let double_clicked = ctx
+            .input()
+            .pointer
+            .button_double_clicked(PointerButton::Primary);
+
+        if !double_clicked {
+            return None;
+        }
+
+        Self::under_pointer(ctx, tree)
+    }
+
+    /// What the pointer is on in a tab bar, e.g. to know whether a double click will be taken by the dock.
+    /// None if it's not on one, or it's on the add button
+    pub fn under_pointer<T: DockTab>(ctx: &egui::Context, tree: &Tree<T>) -> Option<DoubleClicked> {
+        let (bar, pos) = Self::tab_bar_at(ctx, tree)?;
+
+        match bar.tabs.iter().find(|(_, rect)| rect.contains(pos)) {
+            Some(&(id, _)) => Some(DoubleClicked::Tab(id)),
+
+            // the add button comes right after the tabs, it's about as wide as the bar is high
+            None if pos.x < bar.tabs_end + bar.rect.height() => None,
+            None => Some(DoubleClicked::TabBar(bar.node)),
+        }
+    }
+
+    // A tab dragged out of the window and dropped there. egui_dock doesn't report drags ending outside
+    // of a leaf, so a press in a tab bar is remembered and the active tab of that leaf is taken
+    fn dropped_outside<T: DockTab>(ctx: &egui::Context, tree: &Tree<T>) -> Option<Id> {
+        let key = Id::new(DRAGGED_LEAF);
+
+        let (pressed, released, pos) = {
+            let pointer = &ctx.input().pointer;
+            (
+                // egui 0.20 has no primary_pressed
+                pointer.any_pressed() && pointer.primary_down(),
+                pointer.primary_released(),
+                pointer.hover_pos(),
+            )
+        };
+
+        if pressed {
+            let leaf = pos.and_then(|pos| {
+                tree.iter().position(|node| match node {
+                    // the tab bar is the part of a leaf above its contents
+                    Node::Leaf { rect, viewport, .. } => {
+                        Rect::from_min_max(rect.min, pos2(rect.max.x, viewport.min.y)).contains(pos)
+                    }
+                    _ => false,
+                })
+            });
+
+            match leaf {
+                Some(leaf) => ctx.data().insert_temp(key, NodeIndex(leaf)),
+                None => ctx.data().remove::<NodeIndex>(key),
+            }
+
+            return None;
+        }
+
+        if !released {
+            return None;
+        }
+
+        let leaf = ctx.data().get_temp::<NodeIndex>(key)?;
+        ctx.data().remove::<NodeIndex>(key);
+
+        // while dragging, the pointer is still tracked outside of the window
+        if ctx.input().screen_rect().contains(pos?) {
+            return None;
+        }
+
+        let Node::Leaf { tabs, active, .. } = tree.iter().nth(leaf.0)? else {
+            return None;
+        };
+
+        tabs.get(active.0).map(DockTab::id)
+    }
+
+    // The active tab clicked once more. egui_dock makes a tab active once the click is let go, so whether it
+    // already was is checked on the press. Returned once no second click made it a double click
+    fn clicked_active<T: DockTab>(ctx: &egui::Context, tree: &Tree<T>) -> Option<Id> {
+        let key = Id::new(CLICKED_ACTIVE);
+
+        let (pressed, clicked, double_clicked, released, time) = {
+            let input = ctx.input();
+            let pointer = &input.pointer;
+            (
+                pointer.any_pressed() && pointer.primary_down(),
+                pointer.button_clicked(PointerButton::Primary),
+                pointer.button_double_clicked(PointerButton::Primary),
+                pointer.primary_released(),
+                input.time,
+            )
+        };
+
+        if pressed {
+            let active = Self::tab_bar_at(ctx, tree).and_then(|(bar, pos)| {
+                // not when it's closed
+                let (id, _) = bar.tabs.iter().find(|(_, rect)| {
+                    rect.contains(pos) && pos.x < rect.right() - bar.close_width
+                })?;
+
+                match tree.iter().nth(bar.node.0)? {
+                    Node::Leaf { tabs, active, .. } => tabs
+                        .get(active.0)
+                        .filter(|tab| tab.id() == *id)
+                        .map(DockTab::id),
+                    _ => None,
+                }
+            });
+
+            match active {
+                Some(id) => ctx.data().insert_temp(key, (id, None::<f64>)),
+                None => ctx.data().remove::<(Id, Option<f64>)>(key),
+            }
+
+            return None;
+        }
+
+        let (id, clicked_at) = ctx.data().get_temp::<(Id, Option<f64>)>(key)?;
+
+        let delay = Duration::from_secs_f64(CLICK_RENAME_DELAY);
+
+        match clicked_at {
+            None if clicked && !double_clicked => {
+                ctx.data().insert_temp(key, (id, Some(time)));
+                ctx.request_repaint_after(delay);
+            }
+
+            // dragged, or the second click of a double click
+            None if released => ctx.data().remove::<(Id, Option<f64>)>(key),
+
+            Some(at) if time - at >= CLICK_RENAME_DELAY => {
+                ctx.data().remove::<(Id, Option<f64>)>(key);
+                return Some(id);
+            }
+
+            Some(_) => ctx.request_repaint_after(delay),
+
+            None => (),
+        }
+
+        None
+    }
+
+    // the tab bar under the pointer and where the pointer is, None if something is on top of it
+    fn tab_bar_at<T: DockTab>(ctx: &egui::Context, tree: &Tree<T>) -> Option<(TabBar, Pos2)> {
+        let pos = ctx.pointer_interact_pos()?;
+
+        // menus and windows on top of the tab bar get the click
+        if ctx.layer_id_at(pos)?.order != Order::Background {
+            return None;
+        }
+
+        Self::tab_bars(ctx, tree)
+            .into_iter()
+            .find(|bar| bar.rect.contains(pos))
+            .map(|bar| (bar, pos))
+    }
+
+    // Where the tab bars and their tabs are. egui_dock doesn't report where it put the tabs, so they're
+    // measured the way it lays them out, one after the other with the close button after the title
+    fn tab_bars<T: DockTab>(ctx: &egui::Context, tree: &Tree<T>) -> Vec<TabBar> {
+        let font_id = TextStyle::Button.resolve(&ctx.style());
+
+        tree.iter()
+            .enumerate()
+            .filter_map(|(i, node)| {
+                let Node::Leaf {
+                    rect,
+                    viewport,
+                    tabs,
+                    ..
+                } = node
+                else {
+                    return None;
+                };
+
+                let rect = Rect::from_min_max(rect.min, pos2(rect.max.x, viewport.min.y));
+
+                let mut left = rect.left();
+                let mut close_width = 0.0;
+
+                let tabs = tabs
+                    .iter()
+                    .map(|tab| {
+                        let title = ctx.fonts().layout_no_wrap(
+                            tab.title(),
+                            font_id.clone(),
+                            Color32::WHITE,
+                        );
+                        let size = title.size();
+
+                        let close_button = size.y / 1.3;
+                        close_width = close_button + TAB_PADDING;
+                        let width = size.x + TAB_PADDING * 2.0 + close_button + TAB_CLOSE_GAP;
+
+                        let tab_rect =
+                            Rect::from_min_size(pos2(left, rect.top()), vec2(width, rect.height()));
+                        left += width;
+
+                        (tab.id(), tab_rect)
+                    })
+                    .collect();
+
+                Some(TabBar {
+                    node: NodeIndex(i),
+                    rect,
+                    tabs,
+                    tabs_end: left,
+                    close_width,
+                })
+            })
+            .collect()
+    }
+
+    // the name typed over the title of the tab being renamed. A name another tab has can't be kept
+    fn show_rename<T: DockTab>(ctx: &egui::Context, tree: &mut Tree<T>) {
+        let key = Id::new(RENAMING);
+
+        let Some((id, mut name)) = ctx.data().get_temp::<(Id, String)>(key) else {
+            return;
+        };
+
+        // the tab may have been closed in the meantime
+        let rect = Self::tab_bars(ctx, tree)
+            .into_iter()
+            .flat_map(|bar| bar.tabs)
+            .find_map(|(tab, rect)| (tab == id).then_some(rect));
+
+        let Some(rect) = rect else {
+            ctx.data().remove::<(Id, String)>(key);
+            return;
+        };
+
+        let taken = |name: &str| {
+            tree.iter().any(|node| match node {
+                Node::Leaf { tabs, .. } => {
+                    tabs.iter().any(|tab| tab.id() != id && tab.name() == name)
+                }
+                _ => false,
+            })
+        };
+
+        let response = Area::new("dock_rename")
+            .order(Order::Foreground)
+            .fixed_pos(rect.min)
+            .show(ctx, |ui| {
+                let duplicate = taken(name.trim());
+                let error_color = ui.visuals().error_fg_color;
+
+                let response = ui.add(
+                    TextEdit::singleline(&mut name)
+                        .desired_width(rect.width().max(120.0))
+                        .text_color_opt(duplicate.then_some(error_color))
+                        .id(key),
+                );
+
+                if duplicate {
+                    ui.colored_label(error_color, "Another tab has this name");
+                }
+
+                response
+            })
+            .inner;
+
+        let duplicate = taken(name.trim());
+
+        let (entered, escaped) = {
+            let input = ctx.input();
+            (
+                input.key_pressed(Key::Enter),
+                input.key_pressed(Key::Escape),
+            )
+        };
+
+        // enter on a name that's taken keeps editing, so it can be changed
+        if !response.lost_focus() || (entered && duplicate) {
+            if !response.has_focus() {
+                response.request_focus();
+            }
+
+            ctx.data().insert_temp(key, (id, name));
+            return;
+        }
+
+        ctx.data().remove::<(Id, String)>(key);
+
+        let name = name.trim();
+        if name.is_empty() || escaped || duplicate {
+            return;
+        }
+
+        let tab = tree.iter_mut().find_map(|node| match node {
+            Node::Leaf { tabs, .. } => tabs.iter_mut().find(|tab| tab.id() == id),
+            _ => None,
+        });
+
+        if let Some(tab) = tab {
+            tab.set_name(name.to_string());
+        }
+    }
+}
+
+// a leaf's tab bar, and the tabs in it
+struct TabBar {
+    node: NodeIndex,
+    rect: Rect,
+    tabs: Vec<(Id, Rect)>,
+    // where the last tab ends
+    tabs_end: f32,
+    // the part at the end of each tab with the close button
+    close_width: f32,
+}
@@ -0,0 +1,353 @@
+//! Terminal output with its colors as a standalone html snippet or svg image, to share it outside of the app
+//! looking the way the [`TerminalView`](crate::terminal::TerminalView) shows it. Highlighted code is turned
+//! into html the same way, see [`layout_job_html`]
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use egui::text::LayoutJob;
+use egui::{Color32, Stroke};
+use unicode_width::UnicodeWidthStr;
+
+use crate::ansi::TextProperty;
+use crate::terminal::{chunk_colors, AnsiColors, DefaultColors};
+
+// the svg is laid out on a grid of cells, the width of a cell fits the usual monospace fonts
+const FONT_SIZE: f32 = 14.0;
+const CELL_WIDTH: f32 = FONT_SIZE * 0.6;
+const LINE_HEIGHT: f32 = FONT_SIZE * 1.25;
+// around the text, in the background color
+const PADDING: f32 = 12.0;
+
+/// A `<pre>` with the output in it, every styled piece in a `<span>` with inline styles, so it can be pasted
+/// anywhere html goes without a stylesheet
+pub fn to_html(
+    defaults: DefaultColors,
+    colors: AnsiColors,
+    properties: &[TextProperty],
+    text: &str,
+) -> String {
+    let mut html = format!(
+        "<pre style=\"background-color: {}; color: {}; padding: 8px; font-family: monospace;\">",
+        css_color(defaults.background),
+        css_color(defaults.text),
+    );
+
+    for (range, chunk) in chunks(properties, text) {
+        let text = escape(&text[range]);
+
+        let Some(chunk) = chunk else {
+            html.push_str(&text);
+            continue;
+        };
+
+        // blinking text is shown steadily
+        let (color, background) = chunk_colors(defaults, colors, chunk, true);
+
+        let decorated = chunk.style.italic || chunk.style.underline || chunk.style.strikethrough;
+
+        // plain text needs no span, it's in the colors of the <pre>
+        if color == defaults.text && background == Color32::TRANSPARENT && !decorated {
+            html.push_str(&text);
+            continue;
+        }
+
+        let mut style = format!("color: {};", css_color(color));
+
+        if background != Color32::TRANSPARENT {
+            let _ = write!(style, " background-color: {};", css_color(background));
+        }
+
+        if chunk.style.italic {
+            style.push_str(" font-style: italic;");
+        }
+
+        match (chunk.style.underline, chunk.style.strikethrough) {
+            (true, true) => style.push_str(" text-decoration: underline line-through;"),
+            (true, false) => style.push_str(" text-decoration: underline;"),
+            (false, true) => style.push_str(" text-decoration: line-through;"),
+            (false, false) => (),
+        }
+
+        let _ = write!(html, "<span style=\"{style}\">{text}</span>");
+    }
+
+    html.push_str("</pre>");
+    html
+}
+
+/// An svg of the output, every line of text placed on a grid of monospace cells in the font family given,
+/// e.g. `"Hack, monospace"`. Render it to get an image of the output
+pub fn to_svg(
+    defaults: DefaultColors,
+    colors: AnsiColors,
+    properties: &[TextProperty],
+    text: &str,
+    font_family: &str,
+) -> String {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+
+    let columns = text.split('\n').map(|line| line.width()).max().unwrap_or(0);
+    let lines = text.split('\n').count();
+
+    let width = (columns as f32 * CELL_WIDTH + PADDING * 2.0).ceil();
+    let height = (lines as f32 * LINE_HEIGHT + PADDING * 2.0).ceil();
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         font-family=\"{}\" font-size=\"{FONT_SIZE}\">\
+         <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        escape(font_family),
+        css_color(defaults.background),
+    );
+
+    let mut line = 0;
+    let mut column = 0;
+
+    for (range, chunk) in chunks(properties, text) {
+        let (color, background) = match chunk {
+            Some(chunk) => chunk_colors(defaults, colors, chunk, true),
+            None => (defaults.text, Color32::TRANSPARENT),
+        };
+
+        // a chunk is split at its line breaks, every piece is placed on its own
+        for (i, piece) in text[range].split('\n').enumerate() {
+            if i > 0 {
+                line += 1;
+                column = 0;
+            }
+
+            if piece.is_empty() {
+                continue;
+            }
+
+            let x = PADDING + column as f32 * CELL_WIDTH;
+            let y = PADDING + line as f32 * LINE_HEIGHT;
+            let cells = piece.width();
+
+            if background != Color32::TRANSPARENT {
+                let _ = write!(
+                    svg,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{}\" height=\"{LINE_HEIGHT}\" fill=\"{}\"/>",
+                    cells as f32 * CELL_WIDTH,
+                    css_color(background),
+                );
+            }
+
+            column += cells;
+
+            if color == Color32::TRANSPARENT || piece.trim().is_empty() {
+                continue;
+            }
+
+            let mut attributes = format!("fill=\"{}\"", css_color(color));
+
+            if let Some(chunk) = chunk {
+                if chunk.style.italic {
+                    attributes.push_str(" font-style=\"italic\"");
+                }
+
+                match (chunk.style.underline, chunk.style.strikethrough) {
+                    (true, true) => {
+                        attributes.push_str(" text-decoration=\"underline line-through\"")
+                    }
+                    (true, false) => attributes.push_str(" text-decoration=\"underline\""),
+                    (false, true) => attributes.push_str(" text-decoration=\"line-through\""),
+                    (false, false) => (),
+                }
+            }
+
+            // the baseline sits about where egui puts it in the row
+            let _ = write!(
+                svg,
+                "<text x=\"{x}\" y=\"{}\" xml:space=\"preserve\" {attributes}>{}</text>",
+                y + FONT_SIZE,
+                escape(piece),
+            );
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// A `<pre>` with the part of the laid out text in the byte range, e.g. highlighted code, in the colors it
+/// was laid out with. Word processors and mail clients keep the colors when it's pasted as html
+pub fn layout_job_html(job: &LayoutJob, range: Range<usize>, background: Color32) -> String {
+    let mut html = format!(
+        "<pre style=\"background-color: {}; padding: 8px; font-family: monospace;\">",
+        css_color(background),
+    );
+
+    for section in &job.sections {
+        let start = section.byte_range.start.max(range.start);
+        let end = section.byte_range.end.min(range.end);
+
+        if start >= end {
+            continue;
+        }
+
+        let format = &section.format;
+
+        let mut style = format!("color: {};", css_color(format.color));
+
+        if format.italics {
+            style.push_str(" font-style: italic;");
+        }
+
+        if format.underline != Stroke::NONE {
+            style.push_str(" text-decoration: underline;");
+        }
+
+        let _ = write!(
+            html,
+            "<span style=\"{style}\">{}</span>",
+            escape(&job.text[start..end])
+        );
+    }
+
+    html.push_str("</pre>");
+    html
+}
+
+// the byte ranges of the text with the style each has, None for text between styled chunks
+fn chunks<'a>(
+    properties: &'a [TextProperty],
+    text: &str,
+) -> Vec<(Range<usize>, Option<&'a TextProperty>)> {
+    let mut chunks = vec![];
+    let mut end = 0;
+
+    for chunk in properties {
+        let start = chunk.start.min(text.len());
+
+        if start > end {
+            chunks.push((end..start, None));
+        }
+
+        let chunk_end = chunk.end.min(text.len());
+        if chunk_end > start {
+            chunks.push((start..chunk_end, Some(chunk)));
+        }
+
+        end = end.max(chunk_end);
+    }
+
+    if end < text.len() {
+        chunks.push((end..text.len(), None));
+    }
+
+    chunks
+}
+
+fn css_color(color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+
+    if a == u8::MAX {
+        format!("#{r:02x}{g:02x}{b:02x}")
+    } else {
+        format!("rgba({r}, {g}, {b}, {:.2})", a as f32 / 255.0)
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::text::TextFormat;
+    use egui::{FontId, Visuals};
+
+    use super::*;
+    use crate::ansi::parse;
+
+    fn defaults() -> DefaultColors {
+        DefaultColors::from_visuals(&Visuals::dark())
+    }
+
+    #[test]
+    fn html() {
+        let text = "\x1b[31merror\x1b[0m: <a> & b";
+        let parsed = parse(text);
+        let stripped = "error: <a> & b";
+
+        let html = to_html(
+            defaults(),
+            AnsiColors::default(),
+            &parsed.properties,
+            stripped,
+        );
+
+        assert!(html.starts_with("<pre style=\"background-color: "));
+        assert!(html.contains("<span style=\"color: #c50f1f;\">error</span>"));
+        assert!(html.contains("&lt;a&gt; &amp; b"));
+        assert!(html.ends_with("</pre>"));
+    }
+
+    #[test]
+    fn highlighted_code() {
+        let mut job = LayoutJob::default();
+        job.append(
+            "fn",
+            0.0,
+            TextFormat::simple(FontId::monospace(12.0), Color32::RED),
+        );
+        job.append(
+            " a<b",
+            0.0,
+            TextFormat::simple(FontId::monospace(12.0), Color32::WHITE),
+        );
+
+        assert_eq!(
+            layout_job_html(&job, 1..5, Color32::BLACK),
+            "<pre style=\"background-color: #000000; padding: 8px; font-family: monospace;\">\
+             <span style=\"color: #ff0000;\">n</span><span style=\"color: #ffffff;\"> a&lt;</span></pre>"
+        );
+    }
+
+    #[test]
+    fn svg_lines() {
+        let text = "\x1b[42mok\x1b[0m\nsecond line\n";
+        let parsed = parse(text);
+        let stripped = "ok\nsecond line\n";
+
+        let svg = to_svg(
+            defaults(),
+            AnsiColors::default(),
+            &parsed.properties,
+            stripped,
+            "monospace",
+        );
+
+        // 11 columns and 2 lines, the trailing line break doesn't add one
+        let width = (11.0 * CELL_WIDTH + PADDING * 2.0).ceil();
+        let height = (2.0 * LINE_HEIGHT + PADDING * 2.0).ceil();
+        assert!(svg.starts_with(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\""
+        )));
+
+        // the background of the green chunk, and the second line below the first
+        assert!(svg.contains(&format!(
+            "<rect x=\"{PADDING}\" y=\"{PADDING}\" width=\"{}\" height=\"{LINE_HEIGHT}\" fill=\"#13a10e\"/>",
+            2.0 * CELL_WIDTH
+        )));
+        assert!(svg.contains(&format!(
+            "y=\"{}\" xml:space=\"preserve\"",
+            PADDING + LINE_HEIGHT + FONT_SIZE
+        )));
+        assert!(svg.contains(">second line</text>"));
+    }
+}
@@ -0,0 +1,27 @@
+//! The widgets of Rust Play, for embedding in other egui apps.
+//!
+//! None of them reach into the settings of the app, everything they need is passed to `show`:
+//!
+//! - [`code_editor::CodeEditor`] is a syntax highlighted editor, see [`code_editor::EditorSettings`]
+//!   for its font and wrapping. Custom syntax themes are loaded with [`syntax_themes::load_custom_themes`]
+//! - [`terminal::TerminalView`] shows process output the way a terminal would, colors included
+//! - [`dock::DockView`] shows tabs in an egui_dock tree, renaming them in their tab bar
+//! - [`vim::Vim`] is the modal editing the code editor has with [`code_editor::EditorSettings::vim`] on
+//!
+//! The parts they are built on are public too, [`ansi`] parses color escape sequences, [`brackets`]
+//! matches the brackets the editor highlights, [`paste`] tidies up code copied from elsewhere,
+//! [`export`] turns colored output into html or svg to share it and [`virtual_terminal`] applies the
+//! cursor movement progress bars are drawn with. How well the memoized layouts are reused is counted in
+//! [`cache_stats::CacheStats`]
+
+pub mod ansi;
+pub mod brackets;
+pub mod cache_stats;
+pub mod code_editor;
+pub mod dock;
+pub mod export;
+pub mod paste;
+pub mod syntax_themes;
+pub mod terminal;
+pub mod vim;
+pub mod virtual_terminal;
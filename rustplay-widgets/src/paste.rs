@@ -0,0 +1,315 @@
+//! Tidying up code pasted from Stack Overflow, the playground, a terminal or a listing with line numbers
+
+use crate::brackets::bracket_pairs;
+
+// what lines start with when they're copied from a prompt or a quote
+const PROMPTS: &[&str] = &["$ ", ">>> ", "> "];
+
+// lines at the top level starting with these are items, or part of one, instead of statements
+const ITEM_STARTS: &[&str] = &[
+    "fn ",
+    "pub ",
+    "pub(",
+    "use ",
+    "mod ",
+    "struct ",
+    "enum ",
+    "union ",
+    "trait ",
+    "impl ",
+    "impl<",
+    "type ",
+    "const ",
+    "static ",
+    "extern ",
+    "unsafe ",
+    "async fn ",
+    "macro_rules!",
+    "where",
+    "#[",
+    "#!",
+    "//",
+    "/*",
+    "}",
+    ")",
+    "]",
+];
+
+/// The pasted text without the prompt or the line number every line starts with. Text with any line
+/// lacking it is left as it is
+pub fn clean(text: &str) -> String {
+    strip_line_numbers(text)
+        .or_else(|| strip_prompt(text))
+        .unwrap_or_else(|| text.to_string())
+}
+
+/// Whether the code is statements which have to be put in a function to run, rather than a program with
+/// a `fn main()` or items alone
+pub fn needs_main(code: &str) -> bool {
+    let chars = code.chars().collect::<Vec<_>>();
+    let pairs = bracket_pairs(&chars);
+
+    let mut statements = false;
+    let mut start = 0;
+
+    for line in code.split('\n') {
+        let len = line.chars().count();
+        let trimmed = line.trim_start();
+        let indent = len - trimmed.chars().count();
+
+        // the line continues something which is open, e.g. the body of a function
+        let nested = pairs
+            .iter()
+            .any(|&(open, close)| open < start + indent && start + indent <= close);
+
+        let item = ITEM_STARTS.iter().any(|item| trimmed.starts_with(item));
+
+        if trimmed.starts_with("fn main(") {
+            return false;
+        }
+
+        statements |= !nested && !item && !trimmed.is_empty();
+
+        start += len + 1;
+    }
+
+    statements
+}
+
+/// The code with its statements moved into a `fn main()`. `use` lines stay above it
+pub fn wrap_in_main(code: &str) -> String {
+    let lines = code.trim_end().lines().collect::<Vec<_>>();
+
+    let mut uses = 0;
+    // inside a `use` spread over lines, e.g. `use a::{`, until its `;`
+    let mut open_use = false;
+
+    for line in &lines {
+        let line = line.trim();
+        let code = line.split("//").next().unwrap_or(line).trim_end();
+
+        if open_use || code.starts_with("use ") {
+            open_use = !code.ends_with(';');
+        } else if !(line.is_empty() || line.starts_with("//")) {
+            break;
+        }
+
+        uses += 1;
+    }
+
+    let mut wrapped = String::new();
+
+    for line in &lines[..uses] {
+        wrapped.push_str(line);
+        wrapped.push('\n');
+    }
+
+    if uses > 0 && !wrapped.ends_with("\n\n") {
+        wrapped.push('\n');
+    }
+
+    wrapped.push_str("fn main() {\n");
+
+    for line in &lines[uses..] {
+        if !line.trim().is_empty() {
+            wrapped.push_str("    ");
+            wrapped.push_str(line);
+        }
+
+        wrapped.push('\n');
+    }
+
+    wrapped.push_str("}\n");
+    wrapped
+}
+
+// numbers counting up by one from the first line, like `1 | `, `1: ` or `1. `, the same separator on every
+// line. Bare numbers only count if the code after them starts at the same column on every line, past a tab
+// or more than one space, `0 => "zero",` isn't numbered. A single line isn't touched, it could as well
+// start with a number
+fn strip_line_numbers(text: &str) -> Option<String> {
+    let lines = text.trim_end().lines().collect::<Vec<_>>();
+    if lines.len() < 2 {
+        return None;
+    }
+
+    let mut first = None;
+    // the line without its number, and the column the number ends at
+    let mut numbered = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+
+        let number = trimmed[..digits].parse::<usize>().ok()?;
+        let first = *first.get_or_insert(number);
+        if number != first + i {
+            return None;
+        }
+
+        let end = line.len() - trimmed.len() + digits;
+        numbered.push((&trimmed[digits..], end));
+    }
+
+    strip_separator(&numbered)
+        .or_else(|| strip_gutter(&numbered))
+        .map(|stripped| join(&stripped, text))
+}
+
+// `1 | `, `1: ` or `1. `, the space after the separator goes with it, the indentation after that stays
+fn strip_separator<'a>(numbered: &[(&'a str, usize)]) -> Option<Vec<&'a str>> {
+    let separator = [" |", "|", ":", "."]
+        .into_iter()
+        .find(|separator| numbered.iter().all(|(rest, _)| rest.starts_with(separator)))?;
+
+    let stripped = numbered
+        .iter()
+        .map(|(rest, _)| {
+            let rest = &rest[separator.len()..];
+            rest.strip_prefix(' ').unwrap_or(rest)
+        })
+        .collect();
+
+    Some(stripped)
+}
+
+// bare numbers ending at the same column, then a tab or spaces up to where the code starts
+fn strip_gutter<'a>(numbered: &[(&'a str, usize)]) -> Option<Vec<&'a str>> {
+    let column = numbered.first()?.1;
+    if numbered.iter().any(|&(_, end)| end != column) {
+        return None;
+    }
+
+    let code = numbered
+        .iter()
+        .map(|(rest, _)| *rest)
+        .filter(|rest| !rest.trim().is_empty())
+        .collect::<Vec<_>>();
+
+    let tabbed = !code.is_empty() && code.iter().all(|rest| rest.starts_with('\t'));
+
+    // the least indented line starts right after the gutter. The number has to end there, `12ab` isn't
+    // one, and a single space after it could as well be code
+    let gutter = if tabbed {
+        1
+    } else {
+        code.iter()
+            .map(|rest| rest.len() - rest.trim_start_matches(' ').len())
+            .min()
+            .filter(|&spaces| spaces > 1)?
+    };
+
+    let stripped = numbered
+        .iter()
+        .map(|(rest, _)| rest.get(gutter..).unwrap_or(""))
+        .collect();
+
+    Some(stripped)
+}
+
+// the same prompt on every line with something on it, a lone `>` counts for a quoted empty line
+fn strip_prompt(text: &str) -> Option<String> {
+    let lines = text.trim_end().lines().collect::<Vec<_>>();
+
+    let prompt = PROMPTS.iter().find(|prompt| {
+        lines.iter().all(|line| {
+            line.trim().is_empty() || line.starts_with(*prompt) || *line == prompt.trim_end()
+        })
+    })?;
+
+    let stripped = lines
+        .iter()
+        .map(|line| {
+            line.strip_prefix(prompt)
+                .unwrap_or_else(|| line.trim_start_matches(prompt.trim_end()))
+        })
+        .collect::<Vec<_>>();
+
+    Some(join(&stripped, text))
+}
+
+// the lines joined back, ending in a newline if the pasted text did
+fn join(lines: &[&str], text: &str) -> String {
+    let mut joined = lines.join("\n");
+
+    if text.ends_with('\n') {
+        joined.push('\n');
+    }
+
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_numbers() {
+        assert_eq!(
+            clean("1 | fn main() {\n2 |     let x = 1;\n3 | }\n"),
+            "fn main() {\n    let x = 1;\n}\n"
+        );
+        assert_eq!(
+            clean(" 9. let a = 1;\n10.\n11. a + 1"),
+            "let a = 1;\n\na + 1"
+        );
+
+        // bare numbers with the code at the same column, the indentation kept
+        assert_eq!(
+            clean(" 9  fn f() {\n10      g();\n11  }"),
+            "fn f() {\n    g();\n}"
+        );
+        assert_eq!(clean("1\tlet a;\n2\t\ta;"), "let a;\n\ta;");
+
+        // not counting up, or a single line
+        assert_eq!(clean("1 + 2\n3 + 4"), "1 + 2\n3 + 4");
+        assert_eq!(clean("1 + 2"), "1 + 2");
+
+        // a single space after a bare number is code
+        let arms = "0 => \"zero\",\n1 => \"one\",\n2 => \"two\",";
+        assert_eq!(clean(arms), arms);
+
+        // a different separator on some lines
+        assert_eq!(clean("1. a\n2: b"), "1. a\n2: b");
+    }
+
+    #[test]
+    fn prompts() {
+        assert_eq!(clean("> let x = 1;\n>\n> x"), "let x = 1;\n\nx");
+        assert_eq!(clean("$ cargo run\n$ ls"), "cargo run\nls");
+
+        // only some of the lines
+        assert_eq!(clean("a > b\n> c"), "a > b\n> c");
+    }
+
+    #[test]
+    fn statements() {
+        assert!(needs_main("let x = 1;\nprintln!(\"{x}\");"));
+        assert!(needs_main("use std::mem;\n\nlet x = mem::size_of::<u8>();"));
+
+        assert!(!needs_main("fn main() {\n    let x = 1;\n}"));
+        assert!(!needs_main(
+            "#[derive(Debug)]\nstruct A {\n    x: u8,\n}\n\nimpl A {}"
+        ));
+        assert!(!needs_main(
+            "fn add(a: u8,\n       b: u8) -> u8 {\n    a + b\n}"
+        ));
+        assert!(!needs_main(""));
+    }
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(
+            wrap_in_main("use std::mem;\nlet x = 1;\n\nx + 1;\n"),
+            "use std::mem;\n\nfn main() {\n    let x = 1;\n\n    x + 1;\n}\n"
+        );
+        assert_eq!(
+            wrap_in_main("use std::{\n    fmt,\n    mem,\n};\nlet x = 1;"),
+            "use std::{\n    fmt,\n    mem,\n};\n\nfn main() {\n    let x = 1;\n}\n"
+        );
+        assert_eq!(
+            wrap_in_main("let x = 1;"),
+            "fn main() {\n    let x = 1;\n}\n"
+        );
+    }
+}
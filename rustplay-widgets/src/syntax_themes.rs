@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use egui::Id;
+use syntect::highlighting::ThemeSet;
+
+// temp memory key of the themes, so every egui context has its own
+const THEMES: &str = "syntax_themes";
+
+/// The builtin syntax themes, plus the custom ones loaded with [`load_custom_themes`]. Kept in egui's memory,
+/// see [`SyntaxThemes::get`]
+#[derive(Clone)]
+pub struct SyntaxThemes {
+    theme_set: Arc<ThemeSet>,
+    // the names of the loaded custom themes, their file names
+    custom: Arc<Vec<String>>,
+}
+
+impl Default for SyntaxThemes {
+    fn default() -> Self {
+        Self {
+            theme_set: Arc::new(ThemeSet::load_defaults()),
+            custom: Arc::default(),
+        }
+    }
+}
+
+impl SyntaxThemes {
+    /// The themes of the context, only the builtin ones until custom themes are loaded into it
+    pub fn get(ctx: &egui::Context) -> Self {
+        let id = Id::new(THEMES);
+
+        if let Some(themes) = ctx.data().get_temp::<Self>(id) {
+            return themes;
+        }
+
+        let themes = Self::default();
+        ctx.data().insert_temp(id, themes.clone());
+
+        themes
+    }
+
+    pub fn theme_set(&self) -> &Arc<ThemeSet> {
+        &self.theme_set
+    }
+
+    /// Names of the loaded custom themes
+    pub fn custom_names(&self) -> &[String] {
+        &self.custom
+    }
+}
+
+fn theme_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut names = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "tmTheme"))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect::<Vec<_>>();
+
+    names.sort();
+
+    names
+}
+
+/// Replace the custom themes of the context with the .tmTheme files of a directory, named after the file.
+/// There are only the builtin themes until this is called
+pub fn load_custom_themes(ctx: &egui::Context, dir: &Path) {
+    let mut theme_set = ThemeSet::load_defaults();
+    let mut names = vec![];
+
+    for name in theme_names(dir) {
+        let path = dir.join(format!("{name}.tmTheme"));
+
+        // a broken theme file shouldn't take the others down with it
+        if let Ok(theme) = ThemeSet::get_theme(path) {
+            theme_set.themes.insert(name.clone(), theme);
+            names.push(name);
+        }
+    }
+
+    let themes = SyntaxThemes {
+        theme_set: Arc::new(theme_set),
+        custom: Arc::new(names),
+    };
+
+    ctx.data().insert_temp(Id::new(THEMES), themes);
+}
@@ -0,0 +1,400 @@
+use egui::text::{Fonts, LayoutJob, LayoutSection, TextFormat};
+use egui::{Color32, FontId, Id, ScrollArea, Stroke, TextBuffer, TextEdit, Ui, Visuals};
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::ansi::{Color, Parser, TextProperty};
+use crate::code_editor::EditorSettings;
+use crate::virtual_terminal::VirtualTerminal;
+
+/// A read only string for a multiline text edit, the text can be selected and copied but not changed
+pub struct ReadOnlyString<'a> {
+    content: &'a str,
+}
+
+impl<'a> TextBuffer for ReadOnlyString<'a> {
+    fn is_mutable(&self) -> bool {
+        false
+    }
+
+    fn as_str(&self) -> &str {
+        self.content
+    }
+
+    fn insert_text(&mut self, _: &str, _: usize) -> usize {
+        0
+    }
+
+    fn delete_char_range(&mut self, _: std::ops::Range<usize>) {}
+
+    fn clear(&mut self) {}
+
+    fn replace(&mut self, _: &str) {}
+}
+
+impl<'a> ReadOnlyString<'a> {
+    pub fn new(content: &'a str) -> Self {
+        Self { content }
+    }
+}
+
+/// The colors of the 16 ansi colors
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Hash)]
+pub struct AnsiColors {
+    pub black: Rgb,
+    pub red: Rgb,
+    pub green: Rgb,
+    pub yellow: Rgb,
+    pub blue: Rgb,
+    pub magenta: Rgb,
+    pub cyan: Rgb,
+    pub white: Rgb,
+    pub bright_black: Rgb,
+    pub bright_red: Rgb,
+    pub bright_green: Rgb,
+    pub bright_yellow: Rgb,
+    pub bright_blue: Rgb,
+    pub bright_magenta: Rgb,
+    pub bright_cyan: Rgb,
+    pub bright_white: Rgb,
+}
+
+impl Default for AnsiColors {
+    fn default() -> Self {
+        Self {
+            black: Rgb(12, 12, 12),
+            red: Rgb(197, 15, 31),
+            green: Rgb(19, 161, 14),
+            yellow: Rgb(193, 156, 0),
+            blue: Rgb(0, 55, 218),
+            magenta: Rgb(136, 23, 152),
+            cyan: Rgb(58, 150, 221),
+            white: Rgb(204, 204, 204),
+            bright_black: Rgb(118, 118, 118),
+            bright_red: Rgb(231, 72, 86),
+            bright_green: Rgb(22, 198, 12),
+            bright_yellow: Rgb(249, 241, 165),
+            bright_blue: Rgb(59, 120, 255),
+            bright_magenta: Rgb(180, 0, 158),
+            bright_cyan: Rgb(97, 214, 214),
+            bright_white: Rgb(242, 242, 242),
+        }
+    }
+}
+
+impl AnsiColors {
+    /// The color an ansi color is shown in
+    pub fn color32(&self, color: Color) -> Color32 {
+        match color {
+            Color::Black => self.black.to_color32(),
+            Color::Red => self.red.to_color32(),
+            Color::Green => self.green.to_color32(),
+            Color::Yellow => self.yellow.to_color32(),
+            Color::Blue => self.blue.to_color32(),
+            Color::Magenta => self.magenta.to_color32(),
+            Color::Cyan => self.cyan.to_color32(),
+            Color::White => self.white.to_color32(),
+            Color::BrightBlack => self.bright_black.to_color32(),
+            Color::BrightRed => self.bright_red.to_color32(),
+            Color::BrightGreen => self.bright_green.to_color32(),
+            Color::BrightYellow => self.bright_yellow.to_color32(),
+            Color::BrightBlue => self.bright_blue.to_color32(),
+            Color::BrightMagenta => self.bright_magenta.to_color32(),
+            Color::BrightCyan => self.bright_cyan.to_color32(),
+            Color::BrightWhite => self.bright_white.to_color32(),
+            Color::Rgb(r, g, b) => Color32::from_rgb(r, g, b),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub fn to_color32(self) -> Color32 {
+        Color32::from_rgb(self.0, self.1, self.2)
+    }
+}
+
+/// The colors of text which doesn't set its own
+#[derive(Debug, Copy, Clone, Hash)]
+pub struct DefaultColors {
+    pub text: Color32,
+    // bold text
+    pub strong_text: Color32,
+    // behind the text, it's the text color of reversed text
+    pub background: Color32,
+}
+
+impl DefaultColors {
+    pub fn from_visuals(visuals: &Visuals) -> Self {
+        Self {
+            text: visuals.text_color(),
+            strong_text: visuals.strong_text_color(),
+            background: visuals.extreme_bg_color,
+        }
+    }
+}
+
+/// Whether any of the text blinks, so the caller knows to keep repainting it
+pub fn has_blinking(properties: &[TextProperty]) -> bool {
+    properties.iter().any(|property| property.style.blink)
+}
+
+// the (text, background) colors of a chunk, a transparent background is the default one. Shared with the
+// export, so what's exported looks the way it's shown
+pub(crate) fn chunk_colors(
+    defaults: DefaultColors,
+    colors: AnsiColors,
+    chunk: &TextProperty,
+    blink_visible: bool,
+) -> (Color32, Color32) {
+    let style = chunk.style;
+
+    // there's no bold monospace font, bold text is shown brighter instead
+    let mut text_color = match chunk.fg {
+        Some(color) if style.bold => colors.color32(color.brightened()),
+        Some(color) => colors.color32(color),
+        None if style.bold => defaults.strong_text,
+        None => defaults.text,
+    };
+    let mut background_color = chunk.bg.map(|color| colors.color32(color));
+
+    if style.dim {
+        text_color = text_color.linear_multiply(0.5);
+    }
+
+    if style.reverse {
+        let background = background_color.unwrap_or(defaults.background);
+        background_color = Some(text_color);
+        text_color = background;
+    }
+
+    if style.hidden || (style.blink && !blink_visible) {
+        text_color = Color32::TRANSPARENT;
+    }
+
+    (text_color, background_color.unwrap_or(Color32::TRANSPARENT))
+}
+
+/// Lay out the text with the styles parsed from its escape sequences, see [`ansi::Parser`](crate::ansi::Parser).
+/// Text without a color of its own is in the default colors. Blinking text is left out when `blink_visible`
+/// is false, switch it every so often to animate it
+pub fn ansi_layout_job(
+    defaults: DefaultColors,
+    colors: AnsiColors,
+    properties: &[TextProperty],
+    text: &str,
+    blink_visible: bool,
+) -> LayoutJob {
+    let mut job = LayoutJob {
+        text: text.into(),
+        ..Default::default()
+    };
+
+    for chunk in properties {
+        let (text_color, background_color) = chunk_colors(defaults, colors, chunk, blink_visible);
+
+        let italics = chunk.style.italic;
+        let underline = chunk.style.underline;
+
+        let underline = if underline {
+            Stroke::new(1.0, text_color)
+        } else {
+            Stroke::NONE
+        };
+
+        let strikethrough = if chunk.style.strikethrough {
+            Stroke::new(1.0, text_color)
+        } else {
+            Stroke::NONE
+        };
+
+        job.sections.push(LayoutSection {
+            leading_space: 0.0,
+            byte_range: chunk.start..chunk.end,
+            format: TextFormat {
+                font_id: FontId::monospace(12.0),
+                color: text_color,
+                italics,
+                underline,
+                background: background_color,
+                strikethrough,
+                ..Default::default()
+            },
+        });
+    }
+
+    job
+}
+
+// Wide characters (CJK, emoji) and combining marks come from fallback fonts whose advances don't match the
+// monospace grid. Fit every such grapheme to the number of cells a terminal gives it, so columns line up
+pub fn align_to_cells(fonts: &Fonts, job: &mut LayoutJob) {
+    if job.text.is_ascii() {
+        return;
+    }
+
+    // space left behind by a narrow grapheme, added in front of whatever follows it
+    let mut pad = 0.0;
+
+    let mut sections = Vec::with_capacity(job.sections.len());
+    for section in job.sections.drain(..) {
+        let font_id = section.format.font_id.clone();
+        let cell = fonts.glyph_width(&font_id, ' ');
+
+        let mut start = section.byte_range.start;
+        let end = section.byte_range.end;
+
+        // only the first piece of a split section keeps its leading space
+        let mut leading_space = section.leading_space;
+        let mut piece = |range: std::ops::Range<usize>, pad: f32| {
+            let mut piece = section.clone();
+            piece.byte_range = range;
+            piece.leading_space = leading_space + pad;
+            leading_space = 0.0;
+            piece
+        };
+
+        for (offset, grapheme) in job.text[start..end].grapheme_indices(true) {
+            if grapheme.is_ascii() {
+                continue;
+            }
+
+            let expected = grapheme.width() as f32 * cell;
+            let actual = grapheme
+                .chars()
+                .map(|c| fonts.glyph_width(&font_id, c))
+                .sum::<f32>();
+
+            if expected == 0.0 || (expected - actual).abs() < 0.5 {
+                continue;
+            }
+
+            let grapheme_start = section.byte_range.start + offset;
+            let grapheme_end = grapheme_start + grapheme.len();
+
+            if grapheme_start > start {
+                sections.push(piece(start..grapheme_start, pad));
+                pad = 0.0;
+            }
+
+            let mut wide = piece(grapheme_start..grapheme_end, pad);
+
+            // too wide glyphs are shrunk, too narrow ones get padded
+            if actual > expected {
+                wide.format.font_id.size *= expected / actual;
+                pad = 0.0;
+            } else {
+                pad = expected - actual;
+            }
+
+            sections.push(wide);
+
+            start = grapheme_end;
+        }
+
+        if start < end {
+            sections.push(piece(start..end, pad));
+            pad = 0.0;
+        }
+    }
+
+    job.sections = sections;
+}
+
+/// Process output shown the way a terminal shows it, in color. Output is written to it as it comes in,
+/// only the lines it changed are rendered and parsed again
+#[derive(Debug, Default)]
+pub struct TerminalView {
+    terminal: VirtualTerminal,
+    // (unstripped, stripped)
+    output: (String, String),
+    // the styles of the stripped output
+    parser: Parser,
+}
+
+impl TerminalView {
+    pub fn write(&mut self, text: &str) {
+        self.terminal.write(text);
+
+        let (unstripped, stripped) = &mut self.output;
+        if let Some((unstripped_from, stripped_from)) = self.terminal.render(unstripped, stripped) {
+            self.parser.truncate(stripped_from);
+            self.parser.feed(&unstripped[unstripped_from..]);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Forget all but the last `lines` lines of the output, and give back the memory the rest took up.
+    /// Meant for output which is done, the cursor and any escape sequence cut off at its end are forgotten
+    pub fn trim(&mut self, lines: usize) {
+        if lines == 0 {
+            self.clear();
+            return;
+        }
+
+        // rendered lines start unstyled, so the text from a line break on renders the same on its own
+        let end = self.output.0.rmatch_indices('\n').nth(lines - 1);
+        let Some((end, _)) = end else {
+            let (unstripped, stripped) = &mut self.output;
+            unstripped.shrink_to_fit();
+            stripped.shrink_to_fit();
+            self.parser.shrink_to_fit();
+            return;
+        };
+
+        let kept = self.output.0.split_off(end + 1);
+
+        *self = Self::default();
+        self.write(&kept);
+    }
+
+    /// The output without its escape sequences
+    pub fn text(&self) -> &str {
+        &self.output.1
+    }
+
+    /// The styles of [`text`](Self::text)
+    pub fn properties(&self) -> &[TextProperty] {
+        self.parser.properties()
+    }
+
+    /// Fill the space left in the ui with the output, sticking to the bottom as more comes in
+    pub fn show(&self, ui: &mut Ui, id: Id, settings: &EditorSettings, colors: AnsiColors) {
+        let (properties, stripped) = (self.properties(), self.text());
+
+        let mut layouter = |ui: &Ui, text: &str, wrap_width: f32| {
+            let defaults = DefaultColors::from_visuals(ui.visuals());
+
+            // blinking text is shown steadily
+            let mut layout_job = ansi_layout_job(defaults, colors, properties, text, true);
+            settings.apply(&mut layout_job, wrap_width);
+
+            let fonts = ui.fonts();
+            align_to_cells(&fonts, &mut layout_job);
+            fonts.layout_job(layout_job)
+        };
+
+        let mut text = ReadOnlyString::new(stripped);
+
+        ScrollArea::new([!settings.word_wrap, true])
+            .id_source(id)
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                ui.add(
+                    TextEdit::multiline(&mut text)
+                        .font(settings.font_id()) // for cursor height
+                        .frame(false)
+                        .desired_width(f32::INFINITY)
+                        .layouter(&mut layouter)
+                        .id(id),
+                );
+            });
+    }
+}
@@ -0,0 +1,563 @@
+//! The basics of vim's modal editing, for the code editor. It works on the text and a char index of the
+//! cursor, [`crate::code_editor::CodeEditor`] feeds it the keys before its text edit sees them
+
+use crate::brackets::matching_bracket;
+
+/// What the keys typed do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    /// Keys move around and edit, e.g. `dd` deletes the line
+    #[default]
+    Normal,
+    /// Keys type text
+    Insert,
+    /// Keys move the end of a selection
+    Visual,
+}
+
+/// A `:` command for the app, the editor can't save or close itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimCommand {
+    /// `:w`
+    Write,
+    /// `:q`
+    Quit,
+    /// `:wq` or `:x`
+    WriteQuit,
+}
+
+/// A key the modal layer reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimKey {
+    Char(char),
+    Escape,
+    Enter,
+    Backspace,
+}
+
+/// What became of a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimOutcome {
+    /// It's left for the text edit, e.g. typing in insert mode
+    Ignored,
+    Handled,
+    /// `u`, which the text edit's own history does
+    Undo,
+    Command(VimCommand),
+}
+
+/// The state of the modal layer of one editor
+#[derive(Debug, Clone, Default)]
+pub struct Vim {
+    pub mode: VimMode,
+    // the first key of a two key command, the d of dd
+    pending: Option<char>,
+    // what's typed after `:`, while it's typed
+    command_line: Option<String>,
+    // the text last yanked or deleted, and whether it's whole lines
+    register: String,
+    linewise: bool,
+    // where the selection of visual mode started
+    anchor: usize,
+}
+
+impl Vim {
+    /// The mode, or the `:` command being typed, for a status line
+    pub fn status(&self) -> String {
+        match (&self.command_line, self.mode) {
+            (Some(line), _) => format!(":{line}"),
+            (None, VimMode::Normal) => "NORMAL".to_string(),
+            (None, VimMode::Insert) => "-- INSERT --".to_string(),
+            (None, VimMode::Visual) => "-- VISUAL --".to_string(),
+        }
+    }
+
+    /// Whether escape has nothing to cancel, no mode to leave or command half typed
+    pub fn is_idle(&self) -> bool {
+        self.mode == VimMode::Normal && self.pending.is_none() && self.command_line.is_none()
+    }
+
+    /// The chars selected in visual mode, from where it started up to and including the one under the
+    /// cursor
+    pub fn selection(&self, cursor: usize, len: usize) -> Option<(usize, usize)> {
+        (self.mode == VimMode::Visual).then(|| {
+            let (start, end) = (self.anchor.min(cursor), self.anchor.max(cursor));
+
+            (start.min(len), (end + 1).min(len))
+        })
+    }
+
+    /// Apply a key to the text and the cursor, a char index
+    pub fn key(&mut self, key: VimKey, text: &mut String, cursor: &mut usize) -> VimOutcome {
+        if self.command_line.is_some() {
+            return self.command_line(key);
+        }
+
+        // the text can change between keys, e.g. undone or replaced by a paste, past where they were
+        let len = text.chars().count();
+        *cursor = (*cursor).min(len);
+        self.anchor = self.anchor.min(len);
+
+        match self.mode {
+            VimMode::Insert => {
+                if key != VimKey::Escape {
+                    return VimOutcome::Ignored;
+                }
+
+                self.mode = VimMode::Normal;
+
+                // like vim, the cursor steps back onto the last char typed
+                let chars = text.chars().collect::<Vec<_>>();
+                if *cursor > line_start(&chars, *cursor) {
+                    *cursor -= 1;
+                }
+
+                VimOutcome::Handled
+            }
+
+            VimMode::Normal => self.normal(key, text, cursor),
+
+            VimMode::Visual => {
+                self.visual(key, text, cursor);
+                VimOutcome::Handled
+            }
+        }
+    }
+
+    fn command_line(&mut self, key: VimKey) -> VimOutcome {
+        let Some(line) = &mut self.command_line else {
+            return VimOutcome::Ignored;
+        };
+
+        match key {
+            VimKey::Char(c) => line.push(c),
+
+            VimKey::Backspace => {
+                if line.pop().is_none() {
+                    self.command_line = None;
+                }
+            }
+
+            VimKey::Escape => self.command_line = None,
+
+            VimKey::Enter => {
+                let line = self.command_line.take().unwrap_or_default();
+
+                let command = match line.trim() {
+                    "w" => VimCommand::Write,
+                    "q" | "q!" => VimCommand::Quit,
+                    "wq" | "x" => VimCommand::WriteQuit,
+                    _ => return VimOutcome::Handled,
+                };
+
+                return VimOutcome::Command(command);
+            }
+        }
+
+        VimOutcome::Handled
+    }
+
+    fn normal(&mut self, key: VimKey, text: &mut String, cursor: &mut usize) -> VimOutcome {
+        // escape drops a command half typed. Enter and backspace don't edit outside of insert mode
+        let VimKey::Char(c) = key else {
+            self.pending = None;
+            return VimOutcome::Handled;
+        };
+
+        let mut chars = text.chars().collect::<Vec<_>>();
+        let start = line_start(&chars, *cursor);
+        let end = line_end(&chars, *cursor);
+
+        if let Some(first) = self.pending.take() {
+            match (first, c) {
+                ('d', 'd') => {
+                    let (from, to) = line_with_newline(&chars, *cursor);
+
+                    self.yank(&chars[start..end], true);
+                    chars.drain(from..to);
+                    *cursor = line_start(&chars, from.min(chars.len()));
+                }
+
+                ('y', 'y') => self.yank(&chars[start..end], true),
+
+                ('g', 'g') => *cursor = 0,
+
+                _ => (),
+            }
+        } else {
+            match c {
+                'd' | 'y' | 'g' => self.pending = Some(c),
+
+                'i' => self.mode = VimMode::Insert,
+
+                'a' => {
+                    *cursor = (*cursor + 1).min(end);
+                    self.mode = VimMode::Insert;
+                }
+
+                'I' => {
+                    *cursor = (start..end)
+                        .find(|&i| !chars[i].is_whitespace())
+                        .unwrap_or(end);
+                    self.mode = VimMode::Insert;
+                }
+
+                'A' => {
+                    *cursor = end;
+                    self.mode = VimMode::Insert;
+                }
+
+                'o' => {
+                    chars.insert(end, '\n');
+                    *cursor = end + 1;
+                    self.mode = VimMode::Insert;
+                }
+
+                'O' => {
+                    chars.insert(start, '\n');
+                    *cursor = start;
+                    self.mode = VimMode::Insert;
+                }
+
+                'x' if *cursor < end => {
+                    self.yank(&chars[*cursor..*cursor + 1], false);
+                    chars.remove(*cursor);
+                }
+
+                'p' => *cursor = self.paste(&mut chars, *cursor, true),
+
+                'P' => *cursor = self.paste(&mut chars, *cursor, false),
+
+                'u' => return VimOutcome::Undo,
+
+                'v' => {
+                    self.mode = VimMode::Visual;
+                    self.anchor = *cursor;
+                }
+
+                ':' => self.command_line = Some(String::new()),
+
+                c => *cursor = motion(&chars, *cursor, c),
+            }
+        }
+
+        if self.mode == VimMode::Normal {
+            *cursor = clamp(&chars, *cursor);
+        }
+
+        *text = chars.into_iter().collect();
+
+        VimOutcome::Handled
+    }
+
+    fn visual(&mut self, key: VimKey, text: &mut String, cursor: &mut usize) {
+        let mut chars = text.chars().collect::<Vec<_>>();
+
+        match key {
+            VimKey::Escape | VimKey::Char('v') => self.mode = VimMode::Normal,
+
+            VimKey::Char(c @ ('d' | 'x' | 'y')) => {
+                let Some((start, end)) = self.selection(*cursor, chars.len()) else {
+                    return;
+                };
+
+                self.yank(&chars[start..end], false);
+
+                if c != 'y' {
+                    chars.drain(start..end);
+                    *text = chars.iter().collect();
+                }
+
+                *cursor = start;
+                self.mode = VimMode::Normal;
+            }
+
+            VimKey::Char(c) => *cursor = motion(&chars, *cursor, c),
+
+            VimKey::Enter | VimKey::Backspace => (),
+        }
+
+        *cursor = clamp(&chars, *cursor);
+    }
+
+    fn yank(&mut self, chars: &[char], linewise: bool) {
+        self.register = chars.iter().collect();
+        self.linewise = linewise;
+    }
+
+    // put the register after or before the cursor, lines go below or above its line. Returns where the
+    // cursor goes, on the start of what was put
+    fn paste(&self, chars: &mut Vec<char>, cursor: usize, after: bool) -> usize {
+        if self.register.is_empty() {
+            return cursor;
+        }
+
+        let register = self.register.chars();
+
+        if !self.linewise {
+            let end = line_end(chars, cursor);
+            let at = if after && cursor < end {
+                cursor + 1
+            } else {
+                cursor
+            };
+
+            let len = self.register.chars().count();
+            chars.splice(at..at, register);
+
+            return at + len - 1;
+        }
+
+        if !after {
+            let start = line_start(chars, cursor);
+            chars.splice(start..start, register.chain(['\n']));
+
+            return start;
+        }
+
+        let end = line_end(chars, cursor);
+        chars.splice(end..end, ['\n'].into_iter().chain(register));
+
+        end + 1
+    }
+}
+
+// the char index the line of the cursor starts at
+fn line_start(chars: &[char], cursor: usize) -> usize {
+    chars[..cursor.min(chars.len())]
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map_or(0, |i| i + 1)
+}
+
+// the char index of the newline ending the line of the cursor, or the length on the last line
+fn line_end(chars: &[char], cursor: usize) -> usize {
+    let cursor = cursor.min(chars.len());
+
+    chars[cursor..]
+        .iter()
+        .position(|&c| c == '\n')
+        .map_or(chars.len(), |i| cursor + i)
+}
+
+// the line of the cursor along with a newline next to it, the one before it on the last line
+fn line_with_newline(chars: &[char], cursor: usize) -> (usize, usize) {
+    let start = line_start(chars, cursor);
+    let end = line_end(chars, cursor);
+
+    if end < chars.len() {
+        (start, end + 1)
+    } else {
+        (start.saturating_sub(1), end)
+    }
+}
+
+// outside of insert mode the cursor is on a char, never on the newline after it
+fn clamp(chars: &[char], cursor: usize) -> usize {
+    let start = line_start(chars, cursor);
+    let end = line_end(chars, cursor);
+
+    cursor.min(end.saturating_sub(1)).max(start)
+}
+
+// where a movement key takes the cursor, keys which aren't one leave it
+fn motion(chars: &[char], cursor: usize, key: char) -> usize {
+    let start = line_start(chars, cursor);
+    let end = line_end(chars, cursor);
+    let column = cursor - start;
+
+    match key {
+        'h' => cursor.saturating_sub(1).max(start),
+
+        'l' => (cursor + 1).min(end.saturating_sub(1)).max(cursor),
+
+        'j' if end < chars.len() => {
+            let next = end + 1;
+            (next + column).min(line_end(chars, next))
+        }
+
+        'k' if start > 0 => {
+            let previous = line_start(chars, start - 1);
+            (previous + column).min(start - 1)
+        }
+
+        '0' => start,
+
+        '$' => end,
+
+        'G' => line_start(chars, chars.len()),
+
+        '%' => matching_bracket(chars, cursor).map_or(cursor, |(_, to)| to),
+
+        'w' => {
+            let class = char_class(chars.get(cursor).copied());
+            let mut i = cursor;
+
+            while i < chars.len() && class != 0 && char_class(Some(chars[i])) == class {
+                i += 1;
+            }
+
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+
+            i
+        }
+
+        'b' => {
+            let mut i = cursor;
+
+            while i > 0 && chars[i - 1].is_whitespace() {
+                i -= 1;
+            }
+
+            let class = char_class(i.checked_sub(1).map(|i| chars[i]));
+
+            while i > 0 && char_class(Some(chars[i - 1])) == class {
+                i -= 1;
+            }
+
+            i
+        }
+
+        _ => cursor,
+    }
+}
+
+// words are made of letters, digits and underscores, runs of other chars are words too. 0 is whitespace
+fn char_class(c: Option<char>) -> u8 {
+    match c {
+        None => 0,
+        Some(c) if c.is_whitespace() => 0,
+        Some(c) if c.is_alphanumeric() || c == '_' => 1,
+        Some(_) => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the text and cursor after typing the keys in normal mode, from the cursor
+    fn typed(text: &str, cursor: usize, keys: &str) -> (String, usize) {
+        let mut vim = Vim::default();
+        let mut text = text.to_string();
+        let mut cursor = cursor;
+
+        for c in keys.chars() {
+            let key = match c {
+                '\x1b' => VimKey::Escape,
+                c => VimKey::Char(c),
+            };
+
+            if vim.key(key, &mut text, &mut cursor) == VimOutcome::Ignored {
+                let at = text
+                    .char_indices()
+                    .nth(cursor)
+                    .map_or(text.len(), |(i, _)| i);
+                text.insert(at, c);
+                cursor += 1;
+            }
+        }
+
+        (text, cursor)
+    }
+
+    #[test]
+    fn movement() {
+        let text = "fn main() {\n    let x = 1;\n}";
+
+        assert_eq!(typed(text, 0, "lll").1, 3);
+        assert_eq!(typed(text, 3, "j").1, 15);
+        assert_eq!(typed(text, 15, "k").1, 3);
+        // the last line is shorter, the cursor stays on it
+        assert_eq!(typed(text, 20, "j").1, 27);
+        assert_eq!(typed(text, 15, "0").1, 12);
+        assert_eq!(typed(text, 15, "$").1, 25);
+        assert_eq!(typed(text, 0, "w").1, 3);
+        assert_eq!(typed(text, 0, "ww").1, 7);
+        assert_eq!(typed(text, 7, "b").1, 3);
+        assert_eq!(typed(text, 0, "G").1, 27);
+        assert_eq!(typed(text, 27, "gg").1, 0);
+        assert_eq!(typed(text, 10, "%").1, 27);
+        assert_eq!(typed(text, 27, "%").1, 10);
+        // h stops at the start of the line, l at its last char
+        assert_eq!(typed(text, 12, "h").1, 12);
+        assert_eq!(typed(text, 8, "llll").1, 10);
+    }
+
+    #[test]
+    fn lines() {
+        let text = "a\nb\nc";
+
+        assert_eq!(typed(text, 2, "dd"), ("a\nc".to_string(), 2));
+        // the last line takes the newline before it
+        assert_eq!(typed(text, 4, "dd"), ("a\nb".to_string(), 2));
+        assert_eq!(typed(text, 0, "yyp"), ("a\na\nb\nc".to_string(), 2));
+        assert_eq!(typed(text, 4, "yyP"), ("a\nb\nc\nc".to_string(), 4));
+        assert_eq!(typed(text, 0, "ddp"), ("b\na\nc".to_string(), 2));
+    }
+
+    #[test]
+    fn chars() {
+        assert_eq!(typed("abc", 0, "x"), ("bc".to_string(), 0));
+        assert_eq!(typed("abc", 0, "xp"), ("bac".to_string(), 1));
+        assert_eq!(typed("abc", 2, "x"), ("ab".to_string(), 1));
+        assert_eq!(typed("abcd", 0, "vly$p"), ("abcdab".to_string(), 5));
+        assert_eq!(typed("abcd", 1, "vld"), ("ad".to_string(), 1));
+    }
+
+    #[test]
+    fn inserting() {
+        assert_eq!(typed("ac", 0, "ab\x1b"), ("abc".to_string(), 1));
+        assert_eq!(typed("  x", 2, "0Iy\x1b"), ("  yx".to_string(), 2));
+        assert_eq!(typed("a\nb", 0, "oc\x1b"), ("a\nc\nb".to_string(), 2));
+        assert_eq!(typed("a\nb", 2, "Oc\x1b"), ("a\nc\nb".to_string(), 2));
+        assert_eq!(typed("a", 0, "Ab\x1b"), ("ab".to_string(), 1));
+        // keys typed in normal mode never end up in the text
+        assert_eq!(typed("a", 0, "zq"), ("a".to_string(), 0));
+    }
+
+    #[test]
+    fn commands() {
+        let mut vim = Vim::default();
+        let mut text = String::new();
+        let mut cursor = 0;
+
+        let mut run = |keys: &str| {
+            let mut outcome = VimOutcome::Handled;
+
+            for c in keys.chars() {
+                let key = match c {
+                    '\n' => VimKey::Enter,
+                    c => VimKey::Char(c),
+                };
+
+                outcome = vim.key(key, &mut text, &mut cursor);
+            }
+
+            outcome
+        };
+
+        assert_eq!(run(":w\n"), VimOutcome::Command(VimCommand::Write));
+        assert_eq!(run(":q\n"), VimOutcome::Command(VimCommand::Quit));
+        assert_eq!(run(":wq\n"), VimOutcome::Command(VimCommand::WriteQuit));
+        assert_eq!(run(":nope\n"), VimOutcome::Handled);
+        assert_eq!(run("u"), VimOutcome::Undo);
+    }
+
+    #[test]
+    fn text_shrunk_in_visual_mode() {
+        let mut vim = Vim::default();
+        let mut text = "abcdef".to_string();
+        let mut cursor = 5;
+
+        vim.key(VimKey::Char('v'), &mut text, &mut cursor);
+
+        text.truncate(2);
+        vim.key(VimKey::Char('d'), &mut text, &mut cursor);
+
+        assert_eq!(text, "ab");
+        assert!(cursor <= text.chars().count());
+    }
+}
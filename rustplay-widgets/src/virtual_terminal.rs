@@ -0,0 +1,407 @@
+// lines the cursor can move back up to. Anything above is final, it's no longer kept as cells
+const SCREEN_ROWS: usize = 100;
+// an escape sequence this long without an end is garbage, not one cut off between writes
+const MAX_ESCAPE_LEN: usize = 4096;
+const TAB_WIDTH: usize = 8;
+// the cursor isn't moved further right than this, a move far out would fill the line with blank cells
+const MAX_COLUMN: usize = 4096;
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    ch: char,
+    // index into the styles
+    style: usize,
+}
+
+const BLANK: Cell = Cell { ch: ' ', style: 0 };
+
+/// Renders process output the way a terminal shows it. Carriage returns overwrite the line,
+/// and the cursor movement and erase sequences progress bars are drawn with are applied.
+/// Only the graphics (color and style) sequences are left in the rendered text
+#[derive(Debug)]
+pub struct VirtualTerminal {
+    lines: Vec<Vec<Cell>>,
+    row: usize,
+    col: usize,
+    saved_cursor: (usize, usize),
+    // graphics sequences in effect since the last reset, the first one is no style at all
+    styles: Vec<String>,
+    style: usize,
+    // an escape sequence cut off at the end of the last write
+    pending: String,
+    // the first line changed since the last render
+    changed: Option<usize>,
+    // where each line starts in the rendered text (unstripped, stripped)
+    line_starts: Vec<(usize, usize)>,
+}
+
+impl Default for VirtualTerminal {
+    fn default() -> Self {
+        Self {
+            lines: vec![vec![]],
+            row: 0,
+            col: 0,
+            saved_cursor: (0, 0),
+            styles: vec![String::new()],
+            style: 0,
+            pending: String::new(),
+            changed: None,
+            line_starts: vec![],
+        }
+    }
+}
+
+impl VirtualTerminal {
+    pub fn write(&mut self, text: &str) {
+        let text = std::mem::take(&mut self.pending) + text;
+        let mut chars = text.chars();
+
+        loop {
+            let rest = chars.as_str();
+            let Some(c) = chars.next() else {
+                break;
+            };
+
+            match c {
+                '\x1b' => {
+                    let Some(len) = escape_len(rest) else {
+                        self.pending = rest.to_string();
+                        break;
+                    };
+
+                    self.escape(&rest[..len]);
+                    chars = rest[len..].chars();
+                }
+
+                '\r' => self.col = 0,
+                '\n' => self.line_feed(),
+                '\x08' => self.col = self.col.saturating_sub(1),
+
+                '\t' => {
+                    for _ in 0..TAB_WIDTH - self.col % TAB_WIDTH {
+                        self.put(' ');
+                    }
+                }
+
+                // bells and the like
+                c if c.is_control() => (),
+
+                c => self.put(c),
+            }
+        }
+    }
+
+    /// Bring the rendered text up to date with the lines changed since the last render. Returns where the
+    /// text was rendered again from (unstripped, stripped), None if nothing changed. Lines start unstyled
+    pub fn render(
+        &mut self,
+        unstripped: &mut String,
+        stripped: &mut String,
+    ) -> Option<(usize, usize)> {
+        let changed = self.changed.take()?;
+
+        // the last rendered line has no line break yet, it's rendered again along with the new ones
+        let changed = changed.min(self.line_starts.len().saturating_sub(1));
+
+        let from = match self.line_starts.get(changed) {
+            Some(&from) => from,
+            None => (unstripped.len(), stripped.len()),
+        };
+
+        unstripped.truncate(from.0);
+        stripped.truncate(from.1);
+
+        self.line_starts.truncate(changed);
+
+        for (i, line) in self.lines.iter().enumerate().skip(changed) {
+            if i > changed {
+                unstripped.push('\n');
+                stripped.push('\n');
+            }
+
+            self.line_starts.push((unstripped.len(), stripped.len()));
+            self.render_line(line, unstripped, stripped);
+        }
+
+        // what scrolled off the screen can't change anymore
+        let scrolled = self.lines.len().saturating_sub(SCREEN_ROWS).min(self.row);
+        if scrolled > 0 {
+            self.lines.drain(..scrolled);
+            self.line_starts.drain(..scrolled);
+
+            self.row -= scrolled;
+            self.saved_cursor.0 = self.saved_cursor.0.saturating_sub(scrolled);
+        }
+
+        Some(from)
+    }
+
+    fn render_line(&self, line: &[Cell], unstripped: &mut String, stripped: &mut String) {
+        let mut style = 0;
+
+        for cell in line {
+            if cell.style != style {
+                if style != 0 {
+                    unstripped.push_str(RESET);
+                }
+
+                unstripped.push_str(&self.styles[cell.style]);
+                style = cell.style;
+            }
+
+            unstripped.push(cell.ch);
+            stripped.push(cell.ch);
+        }
+
+        if style != 0 {
+            unstripped.push_str(RESET);
+        }
+    }
+
+    fn touch(&mut self, row: usize) {
+        self.changed = Some(self.changed.map_or(row, |changed| changed.min(row)));
+    }
+
+    fn line(&mut self) -> &mut Vec<Cell> {
+        if self.row >= self.lines.len() {
+            let start = self.lines.len();
+            self.lines.resize(self.row + 1, vec![]);
+            self.touch(start);
+        }
+
+        self.touch(self.row);
+
+        &mut self.lines[self.row]
+    }
+
+    fn put(&mut self, ch: char) {
+        let (col, style) = (self.col, self.style);
+        let line = self.line();
+
+        if col > line.len() {
+            line.resize(col, BLANK);
+        }
+
+        let cell = Cell { ch, style };
+        match line.get_mut(col) {
+            Some(existing) => *existing = cell,
+            None => line.push(cell),
+        }
+
+        self.col += 1;
+    }
+
+    // output is piped, not a tty, so a line feed also returns the cursor
+    fn line_feed(&mut self) {
+        self.row += 1;
+        self.col = 0;
+        self.line();
+    }
+
+    fn escape(&mut self, sequence: &str) {
+        // only control sequences do anything, e.g. titles and hyperlinks are left out
+        let Some(sequence) = sequence.strip_prefix("\x1b[") else {
+            return;
+        };
+
+        let Some(action) = sequence.chars().last() else {
+            return;
+        };
+
+        let params = &sequence[..sequence.len() - action.len_utf8()];
+
+        let mut numbers = params.split(';').map(|n| n.parse::<usize>().ok());
+        let first = numbers.next().flatten();
+        let second = numbers.next().flatten();
+
+        // cursor movement by nothing still moves by one
+        let n = first.unwrap_or(1).max(1);
+        let last_row = self.lines.len() - 1;
+
+        match action {
+            'm' => self.set_style(params),
+
+            'A' => self.row = self.row.saturating_sub(n),
+            'B' => self.row = self.row.saturating_add(n).min(last_row.max(self.row)),
+            'C' => self.col = self.col.saturating_add(n).min(MAX_COLUMN.max(self.col)),
+            'D' => self.col = self.col.saturating_sub(n),
+
+            'E' => {
+                self.row = self.row.saturating_add(n).min(last_row.max(self.row));
+                self.col = 0;
+            }
+
+            'F' => {
+                self.row = self.row.saturating_sub(n);
+                self.col = 0;
+            }
+
+            'G' => self.col = (n - 1).min(MAX_COLUMN),
+
+            // there's no fixed screen, rows count from the first line kept. Past the output they're
+            // kept to a screen's worth, so a move far down doesn't add lines without end
+            'H' | 'f' => {
+                self.row = (first.unwrap_or(1).max(1) - 1).min(last_row.max(SCREEN_ROWS - 1));
+                self.col = (second.unwrap_or(1).max(1) - 1).min(MAX_COLUMN);
+            }
+
+            'K' => self.erase_line(first.unwrap_or(0)),
+            'J' => self.erase_display(first.unwrap_or(0)),
+
+            's' => self.saved_cursor = (self.row, self.col),
+            'u' => (self.row, self.col) = self.saved_cursor,
+
+            // modes, e.g. hiding the cursor, don't matter to the output
+            _ => (),
+        }
+    }
+
+    fn set_style(&mut self, params: &str) {
+        let reset = params
+            .split(';')
+            .all(|param| param.is_empty() || param == "0");
+
+        let style = if reset {
+            String::new()
+        } else {
+            format!("{}\x1b[{params}m", self.styles[self.style])
+        };
+
+        self.style = match self.styles.iter().position(|existing| *existing == style) {
+            Some(index) => index,
+            None => {
+                self.styles.push(style);
+                self.styles.len() - 1
+            }
+        };
+    }
+
+    fn erase_line(&mut self, mode: usize) {
+        let col = self.col;
+        let line = self.line();
+
+        match mode {
+            // to the end of the line
+            0 => line.truncate(col),
+
+            // to the start of the line, the cursor included
+            1 => {
+                let end = (col + 1).min(line.len());
+                line[..end].fill(BLANK);
+            }
+
+            _ => line.clear(),
+        }
+    }
+
+    fn erase_display(&mut self, mode: usize) {
+        match mode {
+            // to the end of the screen
+            0 => {
+                self.erase_line(0);
+                self.lines.truncate(self.row + 1);
+            }
+
+            // to the start of the screen
+            1 => {
+                self.erase_line(1);
+
+                for line in &mut self.lines[..self.row] {
+                    line.clear();
+                }
+
+                self.touch(0);
+            }
+
+            // the cleared screen starts over at the top, instead of leaving blank lines behind
+            _ => {
+                self.lines = vec![vec![]];
+                self.row = 0;
+                self.col = 0;
+                self.touch(0);
+            }
+        }
+    }
+}
+
+// length of the escape sequence at the start of the text, None if it's cut off
+fn escape_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+
+    let end = match bytes.get(1)? {
+        // control sequence, ended by a byte in @..~
+        b'[' => bytes[2..]
+            .iter()
+            .position(|byte| (0x40..=0x7e).contains(byte))
+            .map(|i| i + 3),
+
+        // operating system command, ended by BEL or ST
+        b']' => bytes
+            .windows(2)
+            .enumerate()
+            .skip(1)
+            .find_map(|(i, window)| match window {
+                [b'\x07', _] => Some(i + 1),
+                [b'\x1b', b'\\'] => Some(i + 2),
+                _ => None,
+            })
+            .or_else(|| (bytes.last() == Some(&b'\x07')).then_some(bytes.len())),
+
+        // a single character follows
+        _ => Some(1 + text[1..].chars().next()?.len_utf8()),
+    };
+
+    match end {
+        Some(end) => Some(end),
+        // drop just the escape character
+        None if text.len() > MAX_ESCAPE_LEN => Some(1),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the text shown for the output, without the styles
+    fn render(output: &str) -> String {
+        let mut terminal = VirtualTerminal::default();
+        terminal.write(output);
+
+        let (mut unstripped, mut stripped) = (String::new(), String::new());
+        terminal.render(&mut unstripped, &mut stripped);
+
+        stripped
+    }
+
+    #[test]
+    fn progress_bar() {
+        assert_eq!(render("10%\r50%\r100%\ndone"), "100%\ndone");
+        assert_eq!(render("working...\x1b[2K\rdone"), "done");
+        assert_eq!(render("a\nb\x1b[1A\rc"), "c\nb");
+    }
+
+    #[test]
+    fn huge_moves() {
+        let max = usize::MAX;
+
+        // right by as far as it goes, then further without overflowing
+        let line = render(&format!("\x1b[{max}C\x1b[5Cx"));
+        assert_eq!(line.len(), MAX_COLUMN + 1);
+        assert!(line.ends_with(" x"));
+
+        assert_eq!(render(&format!("\x1b[{max}Gx")).len(), MAX_COLUMN + 1);
+
+        // down stops at the last line
+        assert_eq!(render(&format!("a\nb\x1b[2A\x1b[{max}Bc")), "a\nbc");
+        assert_eq!(render(&format!("a\x1b[{max}E\x1b[{max}Eb")), "b");
+
+        let screen = render(&format!("\x1b[{max};{max}Hx"));
+        assert_eq!(screen.lines().count(), SCREEN_ROWS);
+        assert_eq!(screen.lines().last().unwrap().len(), MAX_COLUMN + 1);
+
+        // numbers past usize are ignored, they move by one
+        assert_eq!(render("\x1b[99999999999999999999999Cx"), " x");
+    }
+}